@@ -0,0 +1,46 @@
+/// Wraps a type `Extender: Extend<Element>` and passes elements to the `Extender` in chunks of a fixed size.
+///
+/// This is useful if extending the `Extender` has a large overhead per call, but elements arrive one at a time.
+/// Elements are buffered until the chunk is full, and the remaining elements are passed on when
+/// [`flush`](Self::flush) is called or when this is dropped.
+pub struct ExtendChunked<'extender, Element, Extender: Extend<Element>> {
+    extender: &'extender mut Extender,
+    buffer: Vec<Element>,
+    chunk_size: usize,
+}
+
+impl<'extender, Element, Extender: Extend<Element>> ExtendChunked<'extender, Element, Extender> {
+    /// Panics if `chunk_size` is zero.
+    pub fn new(extender: &'extender mut Extender, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        Self {
+            extender,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    /// Pass all buffered elements to the `Extender`, even if they do not fill a whole chunk.
+    pub fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            self.extender.extend(self.buffer.drain(..));
+        }
+    }
+}
+
+impl<Element, Extender: Extend<Element>> Extend<Element> for ExtendChunked<'_, Element, Extender> {
+    fn extend<T: IntoIterator<Item = Element>>(&mut self, iter: T) {
+        for element in iter {
+            self.buffer.push(element);
+            if self.buffer.len() >= self.chunk_size {
+                self.flush();
+            }
+        }
+    }
+}
+
+impl<Element, Extender: Extend<Element>> Drop for ExtendChunked<'_, Element, Extender> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -0,0 +1,31 @@
+/// Wraps a type `Extender: Extend<Element>` and counts the elements passed to the `Extender`.
+///
+/// Unlike comparing the length of the `Extender` before and after extending,
+/// this also works for extenders that have no length or that drop some elements.
+pub struct ExtendCounted<'extender, Extender> {
+    extender: &'extender mut Extender,
+    count: usize,
+}
+
+impl<'extender, Extender> ExtendCounted<'extender, Extender> {
+    pub fn new(extender: &'extender mut Extender) -> Self {
+        Self { extender, count: 0 }
+    }
+
+    /// The number of elements passed to the `Extender` so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn into_inner(self) -> &'extender mut Extender {
+        self.extender
+    }
+}
+
+impl<Element, Extender: Extend<Element>> Extend<Element> for ExtendCounted<'_, Extender> {
+    fn extend<T: IntoIterator<Item = Element>>(&mut self, iter: T) {
+        let count = &mut self.count;
+        self.extender
+            .extend(iter.into_iter().inspect(|_| *count += 1));
+    }
+}
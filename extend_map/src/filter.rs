@@ -0,0 +1,38 @@
+use std::marker::PhantomData;
+
+/// Wraps a type `Extender: Extend<Element>` and passes only the elements for which a predicate returns true to the `Extender`.
+pub struct ExtendFilter<
+    'extender,
+    Element,
+    Predicate: FnMut(&Element) -> bool,
+    Extender: Extend<Element>,
+> {
+    extender: &'extender mut Extender,
+    predicate: Predicate,
+    phantom_data: PhantomData<Element>,
+}
+
+impl<'extender, Element, Predicate: FnMut(&Element) -> bool, Extender: Extend<Element>>
+    ExtendFilter<'extender, Element, Predicate, Extender>
+{
+    pub fn new(extender: &'extender mut Extender, predicate: Predicate) -> Self {
+        Self {
+            extender,
+            predicate,
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> &'extender mut Extender {
+        self.extender
+    }
+}
+
+impl<Element, Predicate: FnMut(&Element) -> bool, Extender: Extend<Element>> Extend<Element>
+    for ExtendFilter<'_, Element, Predicate, Extender>
+{
+    fn extend<T: IntoIterator<Item = Element>>(&mut self, iter: T) {
+        self.extender
+            .extend(iter.into_iter().filter(&mut self.predicate));
+    }
+}
@@ -0,0 +1,34 @@
+use std::marker::PhantomData;
+
+/// Wraps a type `Extender: Extend<Element>` and calls an inspection function on each element before passing it to the `Extender`.
+pub struct ExtendInspect<'extender, Element, Inspector: FnMut(&Element), Extender: Extend<Element>>
+{
+    extender: &'extender mut Extender,
+    inspector: Inspector,
+    phantom_data: PhantomData<Element>,
+}
+
+impl<'extender, Element, Inspector: FnMut(&Element), Extender: Extend<Element>>
+    ExtendInspect<'extender, Element, Inspector, Extender>
+{
+    pub fn new(extender: &'extender mut Extender, inspector: Inspector) -> Self {
+        Self {
+            extender,
+            inspector,
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> &'extender mut Extender {
+        self.extender
+    }
+}
+
+impl<Element, Inspector: FnMut(&Element), Extender: Extend<Element>> Extend<Element>
+    for ExtendInspect<'_, Element, Inspector, Extender>
+{
+    fn extend<T: IntoIterator<Item = Element>>(&mut self, iter: T) {
+        self.extender
+            .extend(iter.into_iter().inspect(&mut self.inspector));
+    }
+}
@@ -1,7 +1,21 @@
-//! Wrap an `impl Extend` to map elements before extending.
+//! Wrap an `impl Extend` to map, filter, inspect, count, batch or duplicate elements before extending.
 
 use std::marker::PhantomData;
 
+pub use chunked::ExtendChunked;
+pub use counted::ExtendCounted;
+pub use filter::ExtendFilter;
+pub use inspect::ExtendInspect;
+pub use tee::ExtendTee;
+
+mod chunked;
+mod counted;
+mod filter;
+mod inspect;
+mod tee;
+#[cfg(test)]
+mod tests;
+
 /// Wraps a type `Extender: Extend<ExtendElement>` and applies a mapping function before passing elements to the `Extender`.
 pub struct ExtendMap<
     'extender,
@@ -0,0 +1,28 @@
+/// Wraps two types `First: Extend<Element>` and `Second: Extend<Element>` and passes each element to both.
+///
+/// The `First` extender receives clones of the elements, and the `Second` extender receives the originals.
+pub struct ExtendTee<'first, 'second, First, Second> {
+    first: &'first mut First,
+    second: &'second mut Second,
+}
+
+impl<'first, 'second, First, Second> ExtendTee<'first, 'second, First, Second> {
+    pub fn new(first: &'first mut First, second: &'second mut Second) -> Self {
+        Self { first, second }
+    }
+
+    pub fn into_inner(self) -> (&'first mut First, &'second mut Second) {
+        (self.first, self.second)
+    }
+}
+
+impl<Element: Clone, First: Extend<Element>, Second: Extend<Element>> Extend<Element>
+    for ExtendTee<'_, '_, First, Second>
+{
+    fn extend<T: IntoIterator<Item = Element>>(&mut self, iter: T) {
+        for element in iter {
+            self.first.extend(Some(element.clone()));
+            self.second.extend(Some(element));
+        }
+    }
+}
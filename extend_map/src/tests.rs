@@ -0,0 +1,64 @@
+use crate::{ExtendChunked, ExtendCounted, ExtendFilter, ExtendInspect, ExtendMap, ExtendTee};
+
+#[test]
+fn map() {
+    let mut output = Vec::new();
+    ExtendMap::new(&mut output, |element: usize| element * 2).extend([1, 2, 3]);
+    assert_eq!(output, [2, 4, 6]);
+}
+
+#[test]
+fn filter() {
+    let mut output = Vec::new();
+    ExtendFilter::new(&mut output, |element: &usize| element % 2 == 1).extend([1, 2, 3]);
+    assert_eq!(output, [1, 3]);
+}
+
+#[test]
+fn inspect() {
+    let mut output = Vec::new();
+    let mut sum = 0;
+    ExtendInspect::new(&mut output, |element: &usize| sum += element).extend([1, 2, 3]);
+    assert_eq!(output, [1, 2, 3]);
+    assert_eq!(sum, 6);
+}
+
+#[test]
+fn counted() {
+    let mut output = std::collections::HashSet::new();
+    let mut counted = ExtendCounted::new(&mut output);
+    counted.extend([1, 2]);
+    counted.extend([2, 3]);
+    // The set drops the duplicate, but it was passed on nevertheless.
+    assert_eq!(counted.count(), 4);
+    assert_eq!(counted.into_inner().len(), 3);
+}
+
+#[test]
+fn chunked() {
+    #[derive(Default)]
+    struct Chunks(Vec<Vec<usize>>);
+
+    impl Extend<usize> for Chunks {
+        fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+            self.0.push(iter.into_iter().collect());
+        }
+    }
+
+    let mut output = Chunks::default();
+    let mut chunked = ExtendChunked::new(&mut output, 2);
+    chunked.extend([1, 2, 3]);
+    chunked.extend([4]);
+    chunked.extend([5]);
+    drop(chunked);
+    assert_eq!(output.0, [vec![1, 2], vec![3, 4], vec![5]]);
+}
+
+#[test]
+fn tee() {
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    ExtendTee::new(&mut first, &mut second).extend(["a", "b"]);
+    assert_eq!(first, ["a", "b"]);
+    assert_eq!(second, ["a", "b"]);
+}
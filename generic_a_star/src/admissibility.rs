@@ -0,0 +1,59 @@
+use num_traits::SaturatingAdd;
+
+use crate::{cost::Cost, AStar, AStarContext, AStarNode};
+
+/// A closed node whose cost plus lower bound exceeds the optimal cost of a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdmissibilityViolation<Identifier> {
+    pub identifier: Identifier,
+    pub cost: Cost,
+    pub a_star_lower_bound: Cost,
+    /// The identifiers of the closed nodes from the root to the violating node.
+    pub path: Vec<Identifier>,
+}
+
+impl<Context: AStarContext> AStar<Context> {
+    /// Returns all closed nodes with `cost + a_star_lower_bound > optimal_total_cost`, ordered by their identifiers.
+    ///
+    /// With an admissible lower bound, A* closes only nodes whose cost plus lower bound is at most the optimal cost,
+    /// since an open node on an optimal path always has a lower sum.
+    /// Hence, any violation means that the lower bound overestimated the cost to the target somewhere,
+    /// and the path of the violating node shows where the search went.
+    ///
+    /// This is meant to be called after the search terminated, for example with the cost of the target found,
+    /// or with the cost found by a search with a lower bound that is known to be admissible.
+    /// It does not apply to weighted searches.
+    pub fn admissibility_violations(
+        &self,
+        optimal_total_cost: Cost,
+    ) -> Vec<AdmissibilityViolation<<Context::Node as AStarNode>::Identifier>> {
+        let mut violations: Vec<_> = self
+            .closed_list
+            .values()
+            .filter(|node| {
+                node.cost().saturating_add(&node.a_star_lower_bound()) > optimal_total_cost
+            })
+            .map(|node| {
+                let mut path = vec![node.identifier().clone()];
+                let mut current = node;
+                while let Some(predecessor) = current
+                    .predecessor()
+                    .and_then(|predecessor| self.closed_list.get(predecessor))
+                {
+                    path.push(predecessor.identifier().clone());
+                    current = predecessor;
+                }
+                path.reverse();
+
+                AdmissibilityViolation {
+                    identifier: node.identifier().clone(),
+                    cost: node.cost(),
+                    a_star_lower_bound: node.a_star_lower_bound(),
+                    path,
+                }
+            })
+            .collect();
+        violations.sort_unstable_by(|a, b| a.identifier.cmp(&b.identifier));
+        violations
+    }
+}
@@ -0,0 +1,153 @@
+//! The closed list of an A* search.
+//!
+//! Resetting a hash map touches all of its buckets, which dominates the runtime of workloads with many short searches,
+//! such as lookaheads or the construction of lower bound matrices.
+//! Hence, entries are stamped with the generation of the search that inserted them,
+//! and resetting only increments the generation, which invalidates all entries at once.
+
+use std::{collections::HashMap, hash::Hash, mem, ops::Index};
+
+use deterministic_default_hasher::DeterministicDefaultHasher;
+
+use crate::reset::Reset;
+
+#[cfg(test)]
+mod tests;
+
+/// A map from identifiers to closed nodes, whose [`reset`](Reset::reset) runs in constant time.
+///
+/// Entries of previous generations are ignored by all methods, and are overwritten or removed lazily.
+/// They are removed before the map would grow, if at least half of its entries are outdated.
+#[derive(Debug)]
+pub struct AStarClosedList<Identifier, Node> {
+    entries: HashMap<Identifier, (u32, Node), DeterministicDefaultHasher>,
+    generation: u32,
+    /// The number of entries of the current generation.
+    len: usize,
+}
+
+impl<Identifier, Node> AStarClosedList<Identifier, Node> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::with_hasher(DeterministicDefaultHasher),
+            generation: 0,
+            len: 0,
+        }
+    }
+
+    /// The number of closed nodes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of nodes the list can hold without reallocating, including the outdated entries that would be removed first.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// The memory allocated by the list in bytes, excluding heap memory owned by the nodes.
+    pub fn memory_usage(&self) -> usize {
+        // The hash map stores the identifier, the generation and the node of each entry, and one control byte.
+        self.entries.capacity()
+            * (std::mem::size_of::<Identifier>() + std::mem::size_of::<(u32, Node)>() + 1)
+    }
+}
+
+impl<Identifier: Hash + Eq, Node> AStarClosedList<Identifier, Node> {
+    pub fn get(&self, identifier: &Identifier) -> Option<&Node> {
+        self.entries
+            .get(identifier)
+            .filter(|(generation, _)| *generation == self.generation)
+            .map(|(_, node)| node)
+    }
+
+    pub fn contains_key(&self, identifier: &Identifier) -> bool {
+        self.get(identifier).is_some()
+    }
+
+    /// Inserts a node, returning the node previously closed with the same identifier.
+    pub fn insert(&mut self, identifier: Identifier, node: Node) -> Option<Node> {
+        if self.entries.len() == self.entries.capacity() && self.entries.len() >= 2 * self.len {
+            self.remove_outdated_entries();
+        }
+
+        match self.entries.insert(identifier, (self.generation, node)) {
+            Some((generation, previous)) if generation == self.generation => Some(previous),
+            _ => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Removes all entries, freeing the memory owned by outdated nodes, but keeping the allocation of the map.
+    ///
+    /// Unlike [`reset`](Reset::reset), this touches all buckets of the map.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &Node)> {
+        self.entries
+            .iter()
+            .filter(|(_, (generation, _))| *generation == self.generation)
+            .map(|(identifier, (_, node))| (identifier, node))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Identifier> {
+        self.iter().map(|(identifier, _)| identifier)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Node> {
+        self.iter().map(|(_, node)| node)
+    }
+
+    /// Moves the entries of the current generation into a new map of the same capacity.
+    ///
+    /// Removing the outdated entries in place would leave tombstones that make the map grow anyways.
+    fn remove_outdated_entries(&mut self) {
+        let generation = self.generation;
+        let capacity = self.entries.capacity();
+        let entries = mem::replace(
+            &mut self.entries,
+            HashMap::with_capacity_and_hasher(capacity, DeterministicDefaultHasher),
+        );
+        self.entries.extend(
+            entries
+                .into_iter()
+                .filter(|(_, (entry_generation, _))| *entry_generation == generation),
+        );
+    }
+}
+
+impl<Identifier: Hash + Eq, Node> Reset for AStarClosedList<Identifier, Node> {
+    fn reset(&mut self) {
+        if let Some(generation) = self.generation.checked_add(1) {
+            self.generation = generation;
+        } else {
+            // Outdated entries could be mistaken for current ones once the generation wraps around.
+            self.entries.clear();
+            self.generation = 0;
+        }
+        self.len = 0;
+    }
+}
+
+impl<Identifier, Node> Default for AStarClosedList<Identifier, Node> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Identifier: Hash + Eq, Node> Index<&Identifier> for AStarClosedList<Identifier, Node> {
+    type Output = Node;
+
+    fn index(&self, identifier: &Identifier) -> &Self::Output {
+        self.get(identifier).expect("node is not closed")
+    }
+}
@@ -0,0 +1,59 @@
+use crate::reset::Reset;
+
+use super::AStarClosedList;
+
+#[test]
+fn reset_invalidates_all_entries() {
+    let mut closed_list = AStarClosedList::new();
+    assert_eq!(closed_list.insert(1, "a"), None);
+    assert_eq!(closed_list.insert(2, "b"), None);
+    assert_eq!(closed_list.insert(1, "c"), Some("a"));
+    assert_eq!(closed_list.len(), 2);
+    assert_eq!(closed_list[&1], "c");
+
+    closed_list.reset();
+    assert!(closed_list.is_empty());
+    assert_eq!(closed_list.get(&1), None);
+    assert!(!closed_list.contains_key(&2));
+    assert_eq!(closed_list.iter().count(), 0);
+
+    // Outdated entries are not returned as previous nodes.
+    assert_eq!(closed_list.insert(2, "d"), None);
+    assert_eq!(closed_list.len(), 1);
+    assert_eq!(closed_list.keys().collect::<Vec<_>>(), [&2]);
+    assert_eq!(closed_list.values().collect::<Vec<_>>(), [&"d"]);
+}
+
+#[test]
+fn outdated_entries_are_removed_before_growing() {
+    let mut closed_list = AStarClosedList::new();
+    for identifier in 0..100 {
+        closed_list.insert(identifier, identifier);
+    }
+    let capacity = closed_list.capacity();
+
+    // Each generation uses different identifiers, which would grow the map if outdated entries were kept.
+    for generation in 1..100 {
+        closed_list.reset();
+        for identifier in 0..100 {
+            closed_list.insert(generation * 100 + identifier, identifier);
+        }
+        assert_eq!(closed_list.len(), 100);
+        assert!(closed_list.capacity() <= capacity);
+    }
+    assert_eq!(closed_list.get(&9_950), Some(&50));
+    assert_eq!(closed_list.get(&50), None);
+}
+
+#[test]
+fn wrapping_generation_clears_entries() {
+    let mut closed_list = AStarClosedList::new();
+    closed_list.insert(1, "a");
+    closed_list.generation = u32::MAX;
+    closed_list.insert(2, "b");
+
+    closed_list.reset();
+    assert_eq!(closed_list.generation, 0);
+    assert_eq!(closed_list.get(&1), None);
+    assert_eq!(closed_list.get(&2), None);
+}
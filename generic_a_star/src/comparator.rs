@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+
+use binary_heap_plus::BinaryHeap;
+use compare::Compare;
+
+use crate::{node_heap_memory, AStarNode};
+
+/// The policy used to order open nodes whose `cost + a_star_lower_bound` is equal.
+///
+/// The policy can be chosen at runtime via [`AStar::set_tie_breaking`](crate::AStar::set_tie_breaking),
+/// and does not affect the optimality of the search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AStarTieBreaking {
+    /// Use the [`Ord`] implementation of the node.
+    ///
+    /// This is where node types implement domain-specific policies like the anti-diagonal ordering of alignment nodes.
+    /// Note that such a secondary ordering may make things actually slower.
+    /// While it does reduce the number of visited nodes a little bit,
+    /// it also makes heap operations more expensive.
+    /// Preliminary testing showed that this would be a slowdown.
+    #[default]
+    NodeOrd,
+    /// Prefer the node that was opened last.
+    Lifo,
+    /// Prefer the node with the higher [`AStarNode::secondary_maximisable_score`].
+    SecondaryScore,
+    /// Prefer the node with the smaller identifier.
+    ///
+    /// This makes the order of closed nodes independent of the order in which successors are generated.
+    Identifier,
+    /// Order nodes pseudo-randomly, where the order is determined by the seed and the insertion order of the nodes.
+    ///
+    /// Searches with different seeds may find different co-optimal targets,
+    /// while searches with the same seed are reproducible.
+    Random { seed: u64 },
+}
+
+/// Orders the open list of the A* algorithm such that the node with the lowest `cost + a_star_lower_bound` is on top.
+///
+/// Ties are broken according to an [`AStarTieBreaking`] policy.
+///
+/// If the lower bound weight `w` is greater than one, then the nodes are ordered by `cost + w * a_star_lower_bound` instead (weighted A*).
+/// The first target found then has a cost of at most `w` times the optimal cost, if the lower bound is consistent.
+///
+/// With [stable ties](Self::with_stable_ties), nodes that are still equal after applying the policy are ordered by insertion order,
+/// such that the order in which they are closed does not depend on the layout of the binary heap.
+#[derive(Debug, Clone, Copy)]
+pub struct AStarNodeComparator {
+    tie_breaking: AStarTieBreaking,
+    lower_bound_weight: f64,
+    stable_ties: bool,
+}
+
+/// A node in the open list, together with its insertion order.
+#[derive(Debug)]
+pub(crate) struct OpenNode<Node> {
+    pub(crate) node: Node,
+    insertion_index: u64,
+    /// The number of edges between the node and its root node.
+    depth: usize,
+}
+
+/// The open list of the A* algorithm.
+#[derive(Debug)]
+pub(crate) struct OpenList<Node> {
+    heap: BinaryHeap<OpenNode<Node>, AStarNodeComparator>,
+    comparator: AStarNodeComparator,
+    next_insertion_index: u64,
+    /// The depth assigned to nodes pushed via [`Extend`], which are the successors of the node that is being expanded.
+    successor_depth: usize,
+    /// The heap memory owned by the nodes in the open list, excluding the memory of the nodes themselves.
+    node_heap_memory: usize,
+}
+
+impl AStarNodeComparator {
+    pub fn new(tie_breaking: AStarTieBreaking, lower_bound_weight: f64) -> Self {
+        assert!(
+            lower_bound_weight >= 1.0,
+            "The lower bound weight must be at least one, but is {lower_bound_weight}"
+        );
+
+        Self {
+            tie_breaking,
+            lower_bound_weight,
+            stable_ties: false,
+        }
+    }
+
+    /// Order nodes that are equal according to the tie-breaking policy by insertion order, the earlier inserted first.
+    pub fn with_stable_ties(mut self, stable_ties: bool) -> Self {
+        self.stable_ties = stable_ties;
+        self
+    }
+
+    pub fn tie_breaking(&self) -> AStarTieBreaking {
+        self.tie_breaking
+    }
+
+    pub fn lower_bound_weight(&self) -> f64 {
+        self.lower_bound_weight
+    }
+
+    pub fn stable_ties(&self) -> bool {
+        self.stable_ties
+    }
+
+    /// Returns true if the lower bound weight is greater than one.
+    pub fn is_weighted(&self) -> bool {
+        self.lower_bound_weight > 1.0
+    }
+
+    /// Compare two nodes such that the node that should be closed first is the greater one.
+    fn compare_nodes<Node: AStarNode>(
+        &self,
+        n1: &Node,
+        n1_insertion_index: u64,
+        n2: &Node,
+        n2_insertion_index: u64,
+    ) -> Ordering {
+        let ordering = if self.is_weighted() {
+            let weighted_cost = |node: &Node| {
+                node.cost().as_u64() as f64
+                    + self.lower_bound_weight * node.a_star_lower_bound().as_u64() as f64
+            };
+            weighted_cost(n2)
+                .total_cmp(&weighted_cost(n1))
+                .then_with(|| self.break_tie(n1, n1_insertion_index, n2, n2_insertion_index))
+        } else if self.tie_breaking == AStarTieBreaking::NodeOrd {
+            n2.cmp(n1)
+        } else {
+            (n2.cost() + n2.a_star_lower_bound())
+                .cmp(&(n1.cost() + n1.a_star_lower_bound()))
+                .then_with(|| self.break_tie(n1, n1_insertion_index, n2, n2_insertion_index))
+        };
+
+        if self.stable_ties {
+            ordering.then_with(|| n2_insertion_index.cmp(&n1_insertion_index))
+        } else {
+            ordering
+        }
+    }
+
+    /// Compare two nodes with equal costs according to the tie-breaking policy.
+    fn break_tie<Node: AStarNode>(
+        &self,
+        n1: &Node,
+        n1_insertion_index: u64,
+        n2: &Node,
+        n2_insertion_index: u64,
+    ) -> Ordering {
+        match self.tie_breaking {
+            AStarTieBreaking::NodeOrd => n2.cmp(n1),
+            AStarTieBreaking::Lifo => n1_insertion_index.cmp(&n2_insertion_index),
+            AStarTieBreaking::SecondaryScore => n1
+                .secondary_maximisable_score()
+                .cmp(&n2.secondary_maximisable_score()),
+            AStarTieBreaking::Identifier => n2.identifier().cmp(n1.identifier()),
+            AStarTieBreaking::Random { seed } => {
+                random_rank(seed, n1_insertion_index).cmp(&random_rank(seed, n2_insertion_index))
+            }
+        }
+    }
+}
+
+/// A pseudo-random rank of the node with the given insertion index, computed with the SplitMix64 finaliser.
+fn random_rank(seed: u64, insertion_index: u64) -> u64 {
+    let mut z = seed.wrapping_add(
+        insertion_index
+            .wrapping_add(1)
+            .wrapping_mul(0x9E3779B97F4A7C15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+impl Default for AStarNodeComparator {
+    fn default() -> Self {
+        Self::new(Default::default(), 1.0)
+    }
+}
+
+impl<Node: AStarNode> Compare<OpenNode<Node>> for AStarNodeComparator {
+    fn compare(&self, n1: &OpenNode<Node>, n2: &OpenNode<Node>) -> Ordering {
+        self.compare_nodes(&n1.node, n1.insertion_index, &n2.node, n2.insertion_index)
+    }
+}
+
+impl<Node: AStarNode> OpenList<Node> {
+    pub(crate) fn new(comparator: AStarNodeComparator) -> Self {
+        Self {
+            heap: BinaryHeap::from_vec_cmp(Vec::new(), comparator),
+            comparator,
+            next_insertion_index: 0,
+            successor_depth: 0,
+            node_heap_memory: 0,
+        }
+    }
+
+    pub(crate) fn comparator(&self) -> &AStarNodeComparator {
+        &self.comparator
+    }
+
+    pub(crate) fn set_comparator(&mut self, comparator: AStarNodeComparator) {
+        self.comparator = comparator;
+        self.heap.replace_cmp(comparator);
+    }
+
+    pub(crate) fn push(&mut self, node: Node) {
+        self.push_with_depth(node, self.successor_depth);
+    }
+
+    pub(crate) fn push_with_depth(&mut self, node: Node, depth: usize) {
+        self.node_heap_memory += node_heap_memory(&node);
+        self.heap.push(OpenNode {
+            node,
+            insertion_index: self.next_insertion_index,
+            depth,
+        });
+        self.next_insertion_index += 1;
+    }
+
+    /// Removes the node that should be closed next, and returns it together with its depth.
+    pub(crate) fn pop(&mut self) -> Option<(Node, usize)> {
+        self.heap.pop().map(|open_node| {
+            self.node_heap_memory -= node_heap_memory(&open_node.node);
+            (open_node.node, open_node.depth)
+        })
+    }
+
+    /// Sets the depth of nodes pushed afterwards via [`push`](Self::push) or [`Extend`].
+    pub(crate) fn set_successor_depth(&mut self, successor_depth: usize) {
+        self.successor_depth = successor_depth;
+    }
+
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.heap.iter().map(|open_node| &open_node.node)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.heap.clear();
+        self.next_insertion_index = 0;
+        self.successor_depth = 0;
+        self.node_heap_memory = 0;
+    }
+
+    /// The memory used by the open list in bytes, including unused capacity and heap memory owned by the nodes.
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.heap.capacity() * std::mem::size_of::<OpenNode<Node>>() + self.node_heap_memory
+    }
+}
+
+impl<Node: AStarNode> Extend<Node> for OpenList<Node> {
+    fn extend<T: IntoIterator<Item = Node>>(&mut self, iter: T) {
+        for node in iter {
+            self.push(node);
+        }
+    }
+}
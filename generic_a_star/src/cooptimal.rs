@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use deterministic_default_hasher::DeterministicDefaultHasher;
+
+use crate::{AStar, AStarContext, AStarNode, AStarResult, AStarState, AStarStateError};
+
+/// The paths from a root node to a target node that have the same cost as the target found by the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CooptimalPaths<EdgeType> {
+    /// The number of distinct paths, saturating at [`usize::MAX`].
+    pub count: usize,
+    /// Up to the requested number of paths.
+    ///
+    /// Like the iterator returned by [`AStar::backtrack`], each path is ordered from the target to the root.
+    pub paths: Vec<Vec<EdgeType>>,
+}
+
+type Predecessors<Node> = HashMap<
+    <Node as AStarNode>::Identifier,
+    Vec<(
+        <Node as AStarNode>::Identifier,
+        <Node as AStarNode>::EdgeType,
+    )>,
+    DeterministicDefaultHasher,
+>;
+
+impl<Context: AStarContext> AStar<Context> {
+    /// Count the paths to target nodes that have the same cost as the target found, and enumerate up to `limit` of them.
+    ///
+    /// This continues the search until all nodes that may lie on such a path are closed,
+    /// so the performance counters include the additional closed nodes.
+    /// If the memory limit is exceeded while doing so, then only paths through the closed nodes are counted.
+    /// Afterwards, [`backtrack`](Self::backtrack) still returns the path to the target found originally.
+    ///
+    /// Fails if no target was found.
+    pub fn cooptimal_paths(
+        &mut self,
+        limit: usize,
+    ) -> Result<CooptimalPaths<<Context::Node as AStarNode>::EdgeType>, AStarStateError>
+    where
+        <Context::Node as AStarNode>::EdgeType: Clone,
+    {
+        let AStarState::Terminated {
+            result: AStarResult::FoundTarget { identifier, cost },
+        } = &self.state
+        else {
+            return Err(AStarStateError::NoTarget);
+        };
+        let (target, cost) = (identifier.clone(), *cost);
+
+        // Open nodes are ordered by `cost + weight * lower_bound`, which is at most `weight * (cost + lower_bound)`.
+        // Hence, once that exceeds `weight` times the cost of the target, no more nodes on co-optimal paths are open.
+        let weight = self.lower_bound_weight();
+        let weighted_cost = weight * cost.as_u64() as f64;
+        self.search_until(|_, node| {
+            node.cost().as_u64() as f64 + weight * node.a_star_lower_bound().as_u64() as f64
+                > weighted_cost
+        })?;
+        self.state = AStarState::Terminated {
+            result: AStarResult::FoundTarget {
+                identifier: target,
+                cost,
+            },
+        };
+
+        let predecessors = self.tight_predecessors();
+        let targets: Vec<_> = self
+            .closed_list
+            .values()
+            .filter(|node| node.cost() == cost && self.context.is_target(node))
+            .map(|node| node.identifier().clone())
+            .collect();
+
+        let count = targets.iter().fold(0usize, |count, target| {
+            count.saturating_add(self.count_paths(target, &predecessors))
+        });
+        let mut paths = Vec::new();
+        for target in &targets {
+            if paths.len() >= limit {
+                break;
+            }
+            self.enumerate_paths(target, &predecessors, limit, &mut paths);
+        }
+
+        Ok(CooptimalPaths { count, paths })
+    }
+
+    /// For each closed node, collect the closed nodes that have a successor with the same identifier and cost.
+    fn tight_predecessors(&mut self) -> Predecessors<Context::Node> {
+        let mut predecessors = Predecessors::<Context::Node>::default();
+        let mut successors = Vec::new();
+
+        for node in self.closed_list.values() {
+            self.context.generate_successors(node, &mut successors);
+            for successor in successors.drain(..) {
+                let is_tight = self
+                    .closed_list
+                    .get(successor.identifier())
+                    .is_some_and(|closed| closed.cost() == successor.cost());
+                if is_tight {
+                    predecessors
+                        .entry(successor.identifier().clone())
+                        .or_default()
+                        .push((
+                            node.identifier().clone(),
+                            successor.predecessor_edge_type().unwrap(),
+                        ));
+                }
+            }
+        }
+
+        predecessors
+    }
+
+    fn is_root(&self, identifier: &<Context::Node as AStarNode>::Identifier) -> bool {
+        self.closed_list[identifier].predecessor().is_none()
+    }
+
+    /// Count the paths from a root to `target` in the graph of tight edges.
+    fn count_paths(
+        &self,
+        target: &<Context::Node as AStarNode>::Identifier,
+        predecessors: &Predecessors<Context::Node>,
+    ) -> usize {
+        let mut counts = HashMap::<_, usize, DeterministicDefaultHasher>::default();
+        // Use an explicit stack, since paths may be much longer than the call stack allows.
+        let mut stack = vec![(target.clone(), false)];
+
+        while let Some((identifier, is_expanded)) = stack.pop() {
+            if counts.contains_key(&identifier) {
+                continue;
+            }
+
+            let node_predecessors = predecessors.get(&identifier).map_or(&[][..], Vec::as_slice);
+            if is_expanded {
+                let count = if self.is_root(&identifier) {
+                    1
+                } else {
+                    node_predecessors
+                        .iter()
+                        .fold(0usize, |count, (predecessor, _)| {
+                            count.saturating_add(counts[predecessor])
+                        })
+                };
+                counts.insert(identifier, count);
+            } else {
+                stack.push((identifier.clone(), true));
+                stack.extend(
+                    node_predecessors
+                        .iter()
+                        .filter(|(predecessor, _)| !counts.contains_key(predecessor))
+                        .map(|(predecessor, _)| (predecessor.clone(), false)),
+                );
+            }
+        }
+
+        counts[target]
+    }
+
+    /// Append paths from `target` to a root in the graph of tight edges to `paths` until it contains `limit` paths.
+    fn enumerate_paths(
+        &self,
+        target: &<Context::Node as AStarNode>::Identifier,
+        predecessors: &Predecessors<Context::Node>,
+        limit: usize,
+        paths: &mut Vec<Vec<<Context::Node as AStarNode>::EdgeType>>,
+    ) where
+        <Context::Node as AStarNode>::EdgeType: Clone,
+    {
+        // Each stack entry is a node and the index of its next predecessor to visit.
+        // The edges are those between consecutive stack entries.
+        let mut stack = vec![(target.clone(), 0)];
+        let mut edges = Vec::new();
+
+        while let Some((identifier, next_predecessor)) = stack.last_mut() {
+            if self.is_root(identifier) {
+                paths.push(edges.clone());
+                if paths.len() >= limit {
+                    return;
+                }
+                stack.pop();
+                edges.pop();
+                continue;
+            }
+
+            if let Some((predecessor, edge)) = predecessors
+                .get(identifier)
+                .and_then(|node_predecessors| node_predecessors.get(*next_predecessor))
+            {
+                *next_predecessor += 1;
+                edges.push(edge.clone());
+                stack.push((predecessor.clone(), 0));
+            } else {
+                stack.pop();
+                edges.pop();
+            }
+        }
+    }
+}
+
+impl<EdgeType> CooptimalPaths<EdgeType> {
+    /// Returns true if there are multiple co-optimal paths.
+    pub fn is_ambiguous(&self) -> bool {
+        self.count > 1
+    }
+}
@@ -1,15 +1,50 @@
 use std::{
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    num::{ParseFloatError, ParseIntError},
     ops::{Add, AddAssign, Sub, SubAssign},
     str::FromStr,
 };
 
-use num_traits::{CheckedSub, SaturatingSub, Zero};
+use num_traits::{CheckedAdd, CheckedSub, SaturatingAdd, SaturatingSub, Zero};
+
+#[cfg(test)]
+mod tests;
 
 type CostType = u64;
 
+/// The operations that an A* search needs from the costs of its nodes.
+///
+/// Costs are never negative.
+/// The checked and saturating operations have the same semantics for all implementations,
+/// while the behaviour of the plain operators on overflow is documented for each implementation.
+pub trait AStarCost:
+    Debug
+    + Display
+    + Copy
+    + Ord
+    + Hash
+    + Zero
+    + Sub<Output = Self>
+    + AddAssign
+    + SubAssign
+    + CheckedAdd
+    + CheckedSub
+    + SaturatingAdd
+    + SaturatingSub
+{
+    /// The greatest cost, used as the limit when no maximum cost is given.
+    const MAX: Self;
+
+    /// Converts the cost to a float, e.g. for statistics.
+    fn as_f64(&self) -> f64;
+}
+
 /// The cost of an A* node.
 ///
 /// This cost type is not allowed to be negative.
+/// Like for plain `u64`, the arithmetic operators panic on overflow in debug builds and wrap around in release builds,
+/// so use the checked or saturating operations where costs may come close to [`Cost::MAX`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cost(CostType);
@@ -52,6 +87,18 @@ impl SaturatingSub for Cost {
     }
 }
 
+impl CheckedAdd for Cost {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Cost)
+    }
+}
+
+impl SaturatingAdd for Cost {
+    fn saturating_add(&self, rhs: &Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
 impl CheckedSub for Cost {
     fn checked_sub(&self, rhs: &Self) -> Option<Self> {
         self.0.checked_sub(rhs.0).map(Cost)
@@ -70,9 +117,9 @@ impl SubAssign for Cost {
     }
 }
 
-impl std::fmt::Display for Cost {
+impl Display for Cost {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        Display::fmt(&self.0, f)
     }
 }
 
@@ -93,3 +140,300 @@ impl Zero for Cost {
         *self == Self::ZERO
     }
 }
+
+impl AStarCost for Cost {
+    const MAX: Self = Self::MAX;
+
+    fn as_f64(&self) -> f64 {
+        self.0 as f64
+    }
+}
+
+/// A cost backed by a non-negative float that is never NaN, e.g. for costs derived from probabilities.
+///
+/// Sums that exceed the finite range of `f64` become infinite, which is [`F64Cost::MAX`].
+/// The checked operations return `None` instead.
+/// Subtracting a greater cost panics.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "f64", into = "f64"))]
+pub struct F64Cost(f64);
+
+/// The error returned when a float is not a valid [`F64Cost`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum F64CostError {
+    Parse(ParseFloatError),
+    /// The float is negative or NaN.
+    Invalid(String),
+}
+
+impl F64Cost {
+    pub const ZERO: Self = Self(0.0);
+    pub const MAX: Self = Self(f64::INFINITY);
+
+    /// Returns `None` if `value` is negative or NaN.
+    pub fn new(value: f64) -> Option<Self> {
+        // Adding zero turns negative zero into positive zero, so that equal costs have equal bits.
+        (value >= 0.0).then_some(Self(value + 0.0))
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for F64Cost {
+    type Error = F64CostError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or_else(|| F64CostError::Invalid(value.to_string()))
+    }
+}
+
+impl From<F64Cost> for f64 {
+    fn from(value: F64Cost) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq for F64Cost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for F64Cost {}
+
+impl PartialOrd for F64Cost {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for F64Cost {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for F64Cost {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Add for F64Cost {
+    type Output = F64Cost;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for F64Cost {
+    type Output = F64Cost;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(&rhs)
+            .unwrap_or_else(|| panic!("Subtracting {rhs} from {self} is negative"))
+    }
+}
+
+impl CheckedAdd for F64Cost {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        // Like for integers, adding zero to the maximum is fine, but adding anything else overflows.
+        let sum = *self + *rhs;
+        (sum.0.is_finite() || self.is_zero() || rhs.is_zero()).then_some(sum)
+    }
+}
+
+impl SaturatingAdd for F64Cost {
+    fn saturating_add(&self, rhs: &Self) -> Self {
+        *self + *rhs
+    }
+}
+
+impl CheckedSub for F64Cost {
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        // Infinity minus infinity is NaN, which is rejected like negative differences.
+        Self::new(self.0 - rhs.0)
+    }
+}
+
+impl SaturatingSub for F64Cost {
+    fn saturating_sub(&self, rhs: &Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(Self::ZERO)
+    }
+}
+
+impl AddAssign for F64Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for F64Cost {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Display for F64Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for F64Cost {
+    type Err = F64CostError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        f64::from_str(s).map_err(F64CostError::Parse)?.try_into()
+    }
+}
+
+impl Zero for F64Cost {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+}
+
+impl AStarCost for F64Cost {
+    const MAX: Self = Self::MAX;
+
+    fn as_f64(&self) -> f64 {
+        self.0
+    }
+}
+
+impl Display for F64CostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(error) => Display::fmt(error, f),
+            Self::Invalid(value) => write!(
+                f,
+                "{value} is not a valid cost, costs must not be negative or NaN"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for F64CostError {}
+
+/// A cost backed by a `u32` whose arithmetic operators saturate instead of overflowing.
+///
+/// Sums that exceed [`SaturatingU32Cost::MAX`] are clamped to it, and differences below zero are clamped to zero,
+/// in both debug and release builds.
+/// This halves the memory needed for costs compared to [`Cost`], without silently wrapping around in long alignments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaturatingU32Cost(u32);
+
+impl SaturatingU32Cost {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(u32::MAX);
+
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for SaturatingU32Cost {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SaturatingU32Cost> for Cost {
+    fn from(value: SaturatingU32Cost) -> Self {
+        Self(value.0.into())
+    }
+}
+
+impl Add for SaturatingU32Cost {
+    type Output = SaturatingU32Cost;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for SaturatingU32Cost {
+    type Output = SaturatingU32Cost;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl CheckedAdd for SaturatingU32Cost {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+}
+
+impl SaturatingAdd for SaturatingU32Cost {
+    fn saturating_add(&self, rhs: &Self) -> Self {
+        *self + *rhs
+    }
+}
+
+impl CheckedSub for SaturatingU32Cost {
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+}
+
+impl SaturatingSub for SaturatingU32Cost {
+    fn saturating_sub(&self, rhs: &Self) -> Self {
+        *self - *rhs
+    }
+}
+
+impl AddAssign for SaturatingU32Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for SaturatingU32Cost {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Display for SaturatingU32Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for SaturatingU32Cost {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u32::from_str(s).map(Self)
+    }
+}
+
+impl Zero for SaturatingU32Cost {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+}
+
+impl AStarCost for SaturatingU32Cost {
+    const MAX: Self = Self::MAX;
+
+    fn as_f64(&self) -> f64 {
+        self.0.into()
+    }
+}
@@ -0,0 +1,69 @@
+use num_traits::{CheckedAdd, CheckedSub};
+
+use super::{AStarCost, Cost, F64Cost, SaturatingU32Cost};
+
+/// Checks the semantics that all cost types share.
+fn check_common_semantics<CostType: AStarCost>(from_u32: impl Fn(u32) -> CostType) {
+    let [zero, one, two] = [0, 1, 2].map(from_u32);
+    assert_eq!(one + one, two);
+    assert_eq!(two - one, one);
+    assert_eq!(CostType::zero(), zero);
+
+    assert_eq!(CostType::MAX.checked_add(&one), None);
+    assert_eq!(CostType::MAX.saturating_add(&one), CostType::MAX);
+    assert_eq!(one.checked_sub(&two), None);
+    assert_eq!(one.saturating_sub(&two), zero);
+    assert!(zero < one && one < CostType::MAX);
+    assert_eq!(two.as_f64(), 2.0);
+}
+
+#[test]
+fn common_semantics() {
+    check_common_semantics(|value| Cost::from(u64::from(value)));
+    check_common_semantics(|value| F64Cost::new(value.into()).unwrap());
+    check_common_semantics(SaturatingU32Cost::from);
+}
+
+#[test]
+fn saturating_u32_cost_operators_saturate() {
+    let max = SaturatingU32Cost::MAX;
+    let one = SaturatingU32Cost::from(1);
+    assert_eq!(max + one, max);
+    assert_eq!(one - max, SaturatingU32Cost::ZERO);
+
+    let mut cost = max;
+    cost += max;
+    assert_eq!(cost, max);
+    assert_eq!(Cost::from(cost), Cost::from(u64::from(u32::MAX)));
+}
+
+#[test]
+fn f64_cost_rejects_invalid_values() {
+    assert_eq!(F64Cost::new(-1.0), None);
+    assert_eq!(F64Cost::new(f64::NAN), None);
+    assert!("-0.5".parse::<F64Cost>().is_err());
+    assert!("abc".parse::<F64Cost>().is_err());
+    assert_eq!("0.25".parse::<F64Cost>().unwrap().as_f64(), 0.25);
+
+    // Negative zero is normalised, so that it equals and hashes like zero.
+    assert_eq!(F64Cost::new(-0.0).unwrap().as_f64().to_bits(), 0);
+}
+
+#[test]
+fn f64_cost_overflows_to_infinity() {
+    let large = F64Cost::new(f64::MAX).unwrap();
+    assert_eq!(large + large, F64Cost::MAX);
+    assert_eq!(large.checked_add(&large), None);
+    assert_eq!(F64Cost::MAX.checked_sub(&F64Cost::MAX), None);
+    assert_eq!(
+        F64Cost::MAX.checked_sub(&large),
+        Some(F64Cost::MAX),
+        "infinity minus a finite cost stays infinite"
+    );
+}
+
+#[test]
+#[should_panic]
+fn f64_cost_negative_difference_panics() {
+    let _ = F64Cost::ZERO - F64Cost::new(1.0).unwrap();
+}
@@ -0,0 +1,113 @@
+//! Searching with a cost limit that is increased until a target is found.
+//!
+//! A low cost limit keeps the search small if the target is cheap,
+//! and increasing it only when needed avoids exploring expensive parts of the graph early.
+//! Between iterations the search is resumed with [`AStar::resume_after_context_change`],
+//! so the work of previous iterations is reused.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    cost::Cost, AStar, AStarContext, AStarNode, AStarResult, AStarStateError, ContextChange,
+};
+
+/// The cost limits used by [`AStar::search_with_cost_limit_ramp`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CostLimitRamp {
+    /// Start with the `initial` cost limit, and multiply it by `factor` after each iteration without target.
+    ///
+    /// The limit is raised by at least one, and the search only stops without target if the graph is exhausted.
+    Exponential { initial: Cost, factor: u64 },
+    /// Use the given cost limits in order.
+    ///
+    /// If no target is found with the last limit, then the search stops without target.
+    Schedule(Vec<Cost>),
+}
+
+/// Statistics about a single iteration of [`AStar::search_with_cost_limit_ramp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostLimitIteration {
+    pub cost_limit: Cost,
+    /// The nodes opened in this iteration.
+    pub opened_nodes: usize,
+    /// The nodes closed in this iteration.
+    pub closed_nodes: usize,
+    pub duration: Duration,
+}
+
+/// The result of [`AStar::search_with_cost_limit_ramp`].
+#[derive(Debug)]
+pub struct CostLimitRampResult<NodeIdentifier> {
+    /// The result of the last iteration.
+    pub result: AStarResult<NodeIdentifier>,
+    pub iterations: Vec<CostLimitIteration>,
+}
+
+impl CostLimitRamp {
+    fn cost_limit(&self, iteration: usize, previous: Option<Cost>) -> Option<Cost> {
+        match self {
+            Self::Exponential { initial, factor } => Some(match previous {
+                None => *initial,
+                Some(previous) if previous == Cost::MAX => return None,
+                Some(previous) => previous
+                    .as_u64()
+                    .saturating_mul(*factor)
+                    .max(previous.as_u64() + 1)
+                    .into(),
+            }),
+            Self::Schedule(cost_limits) => cost_limits.get(iteration).copied(),
+        }
+    }
+}
+
+impl<Context: AStarContext> AStar<Context> {
+    /// Searches with increasing cost limits until a target is found.
+    ///
+    /// The search must be initialised, and `set_max_cost` must set the value returned by [`AStarContext::max_cost`].
+    /// Each iteration continues the previous one, and the limits configured for this search, like the time limit, apply to each iteration separately.
+    /// The ramp stops early if an iteration terminates for another reason than the cost limit, and returns the result of that iteration.
+    /// Fails if the algorithm was not initialised.
+    pub fn search_with_cost_limit_ramp(
+        &mut self,
+        ramp: &CostLimitRamp,
+        mut set_max_cost: impl FnMut(&mut Context, Cost),
+    ) -> Result<CostLimitRampResult<<Context::Node as AStarNode>::Identifier>, AStarStateError>
+    {
+        self.check_initialised()?;
+        let mut iterations = Vec::new();
+        let mut cost_limit = None;
+
+        loop {
+            let Some(next_cost_limit) = ramp.cost_limit(iterations.len(), cost_limit) else {
+                let result = AStarResult::NoTarget {
+                    max_cost: cost_limit,
+                };
+                return Ok(CostLimitRampResult { result, iterations });
+            };
+            if cost_limit.is_some() {
+                self.resume_after_context_change(ContextChange::IncreasedMaxCost, |context| {
+                    set_max_cost(context, next_cost_limit)
+                })?;
+            } else {
+                set_max_cost(&mut self.context, next_cost_limit);
+            }
+            cost_limit = Some(next_cost_limit);
+
+            let opened_nodes = self.performance_counters.opened_nodes;
+            let closed_nodes = self.performance_counters.closed_nodes;
+            let start_time = Instant::now();
+            let result = self.search()?;
+            iterations.push(CostLimitIteration {
+                cost_limit: next_cost_limit,
+                opened_nodes: self.performance_counters.opened_nodes - opened_nodes,
+                closed_nodes: self.performance_counters.closed_nodes - closed_nodes,
+                duration: start_time.elapsed(),
+            });
+
+            // Without open nodes, no higher cost limit can lead to a target.
+            if !matches!(result, AStarResult::NoTarget { .. }) || self.open_list.is_empty() {
+                return Ok(CostLimitRampResult { result, iterations });
+            }
+        }
+    }
+}
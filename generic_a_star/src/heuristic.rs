@@ -0,0 +1,201 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use deterministic_default_hasher::DeterministicDefaultHasher;
+use extend_map::ExtendMap;
+
+use crate::{cost::Cost, reset::Reset, AStarContext, AStarNode};
+
+/// A node whose A* lower bound can be raised after it was generated.
+pub trait AStarNodeWithLowerBound: AStarNode {
+    /// Set the A* lower bound of this node to the maximum of its current lower bound and the given lower bound.
+    fn raise_a_star_lower_bound(&mut self, lower_bound: Cost);
+}
+
+/// A lower bound on the cost from a node to the cheapest target.
+///
+/// Heuristics are queried by a [`HeuristicContext`] whenever a node is generated,
+/// such that they do not need to be baked into node generation.
+/// For the search to stay optimal, the heuristic must be admissible, i.e. never overestimate the remaining cost.
+/// If it is additionally consistent, the search stays label-setting.
+pub trait Heuristic<Node: AStarNode>: Reset {
+    fn lower_bound(&mut self, node: &Node) -> Cost;
+
+    /// The number of lower bounds that were answered from a cache and the number that had to be computed.
+    ///
+    /// Heuristics without a cache return zero for both.
+    fn cache_hits_and_misses(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+/// The maximum of two heuristics.
+///
+/// If both heuristics are admissible (consistent), then so is their maximum.
+#[derive(Debug, Clone, Default)]
+pub struct MaxHeuristic<Heuristic1, Heuristic2> {
+    pub first: Heuristic1,
+    pub second: Heuristic2,
+}
+
+/// Caches the lower bounds of a heuristic by node identifier.
+///
+/// This is useful for heuristics that are expensive to evaluate,
+/// since the same node may be generated many times from different predecessors.
+#[derive(Debug)]
+pub struct CachedHeuristic<Node: AStarNode, InnerHeuristic> {
+    heuristic: InnerHeuristic,
+    cache: HashMap<Node::Identifier, Cost, DeterministicDefaultHasher>,
+    cache_hits: usize,
+    cache_misses: usize,
+    phantom_data: PhantomData<Node>,
+}
+
+/// Wraps an [`AStarContext`] and raises the lower bound of each generated node with a [`Heuristic`].
+#[derive(Debug)]
+pub struct HeuristicContext<Context, ContextHeuristic> {
+    context: Context,
+    heuristic: ContextHeuristic,
+}
+
+impl<Heuristic1, Heuristic2> MaxHeuristic<Heuristic1, Heuristic2> {
+    pub fn new(first: Heuristic1, second: Heuristic2) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<Node: AStarNode, Heuristic1: Heuristic<Node>, Heuristic2: Heuristic<Node>> Heuristic<Node>
+    for MaxHeuristic<Heuristic1, Heuristic2>
+{
+    fn lower_bound(&mut self, node: &Node) -> Cost {
+        self.first
+            .lower_bound(node)
+            .max(self.second.lower_bound(node))
+    }
+
+    fn cache_hits_and_misses(&self) -> (usize, usize) {
+        let (first_hits, first_misses) = self.first.cache_hits_and_misses();
+        let (second_hits, second_misses) = self.second.cache_hits_and_misses();
+        (first_hits + second_hits, first_misses + second_misses)
+    }
+}
+
+impl<Heuristic1: Reset, Heuristic2: Reset> Reset for MaxHeuristic<Heuristic1, Heuristic2> {
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+impl<Node: AStarNode, InnerHeuristic> CachedHeuristic<Node, InnerHeuristic> {
+    pub fn new(heuristic: InnerHeuristic) -> Self {
+        Self {
+            heuristic,
+            cache: Default::default(),
+            cache_hits: 0,
+            cache_misses: 0,
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn cache_size(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn into_inner(self) -> InnerHeuristic {
+        self.heuristic
+    }
+}
+
+impl<Node: AStarNode, InnerHeuristic: Heuristic<Node>> Heuristic<Node>
+    for CachedHeuristic<Node, InnerHeuristic>
+{
+    fn lower_bound(&mut self, node: &Node) -> Cost {
+        if let Some(lower_bound) = self.cache.get(node.identifier()) {
+            self.cache_hits += 1;
+            *lower_bound
+        } else {
+            self.cache_misses += 1;
+            let lower_bound = self.heuristic.lower_bound(node);
+            self.cache.insert(node.identifier().clone(), lower_bound);
+            lower_bound
+        }
+    }
+
+    fn cache_hits_and_misses(&self) -> (usize, usize) {
+        (self.cache_hits, self.cache_misses)
+    }
+}
+
+impl<Node: AStarNode, InnerHeuristic: Reset> Reset for CachedHeuristic<Node, InnerHeuristic> {
+    fn reset(&mut self) {
+        self.heuristic.reset();
+        self.cache.clear();
+        self.cache_hits = 0;
+        self.cache_misses = 0;
+    }
+}
+
+impl<Context, ContextHeuristic> HeuristicContext<Context, ContextHeuristic> {
+    pub fn new(context: Context, heuristic: ContextHeuristic) -> Self {
+        Self { context, heuristic }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn heuristic(&self) -> &ContextHeuristic {
+        &self.heuristic
+    }
+
+    pub fn into_inner(self) -> (Context, ContextHeuristic) {
+        (self.context, self.heuristic)
+    }
+}
+
+impl<Context: AStarContext, ContextHeuristic: Heuristic<Context::Node>> AStarContext
+    for HeuristicContext<Context, ContextHeuristic>
+where
+    Context::Node: AStarNodeWithLowerBound,
+{
+    type Node = Context::Node;
+
+    fn create_root(&self) -> Self::Node {
+        // The heuristic cannot be queried here, since it needs to be borrowed mutably.
+        // This is fine, since the root node is expanded first anyways.
+        self.context.create_root()
+    }
+
+    fn generate_successors(&mut self, node: &Self::Node, output: &mut impl Extend<Self::Node>) {
+        let heuristic = &mut self.heuristic;
+        self.context.generate_successors(
+            node,
+            &mut ExtendMap::new(output, |mut successor: Self::Node| {
+                let lower_bound = heuristic.lower_bound(&successor);
+                successor.raise_a_star_lower_bound(lower_bound);
+                successor
+            }),
+        );
+    }
+
+    fn is_target(&self, node: &Self::Node) -> bool {
+        self.context.is_target(node)
+    }
+
+    fn max_cost(&self) -> Option<Cost> {
+        self.context.max_cost()
+    }
+
+    fn is_label_setting(&self) -> bool {
+        self.context.is_label_setting()
+    }
+}
+
+impl<Context: Reset, ContextHeuristic: Reset> Reset
+    for HeuristicContext<Context, ContextHeuristic>
+{
+    fn reset(&mut self) {
+        self.context.reset();
+        self.heuristic.reset();
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+/// A histogram over non-negative integer values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Histogram {
+    /// Pairs of a value and the number of times it occurs, ordered by value.
+    ///
+    /// Values that do not occur are omitted.
+    pub buckets: Vec<(u64, usize)>,
+}
+
+/// The state of an A* search at some point in time.
+///
+/// If the open list contains many nodes with the smallest f-value, then the order of closing them is decided by the tie-breaking.
+/// If instead the f-values of the open nodes are spread out and the closed nodes are shallow, then the lower bound is weak.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AStarSearchSnapshot {
+    /// The number of nodes that were closed when the snapshot was taken.
+    pub closed_nodes: usize,
+    /// The distribution of `cost + a_star_lower_bound` of the nodes in the open list.
+    pub open_f_values: Histogram,
+    /// The distribution of the depths of all nodes closed so far.
+    ///
+    /// The depth of a node is the number of edges between it and its root node.
+    /// Nodes that were closed multiple times are counted multiple times.
+    pub closed_depths: Histogram,
+}
+
+impl Histogram {
+    pub fn from_values(values: impl IntoIterator<Item = u64>) -> Self {
+        let mut counts = BTreeMap::new();
+        for value in values {
+            *counts.entry(value).or_insert(0) += 1;
+        }
+
+        Self {
+            buckets: counts.into_iter().collect(),
+        }
+    }
+
+    /// Creates a histogram from a vector whose entry at each index is the number of times the index occurs.
+    pub fn from_counts(counts: &[usize]) -> Self {
+        Self {
+            buckets: counts
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(|(value, count)| (value as u64, *count))
+                .collect(),
+        }
+    }
+
+    /// The number of values in the histogram.
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|(_, count)| count).sum()
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{
+    cost::Cost, AStarContext, AStarNode, AStarPerformanceCounters, AStarResult, AStarStateError,
+};
+
+/// An iterative deepening A* (IDA*) search over an [`AStarContext`].
+///
+/// In contrast to [`AStar`](crate::AStar), this does not keep a closed list, but only the current path
+/// together with the unexplored successors of each node on it.
+/// Hence its memory usage is linear in the length of the path times the number of successors per node.
+/// The price is that nodes reachable via multiple paths are explored multiple times,
+/// and that each iteration repeats the work of the previous iterations.
+#[derive(Debug)]
+pub struct IdaStar<Context: AStarContext> {
+    context: Context,
+    stack: Vec<Frame<Context::Node>>,
+    state: IdaStarState,
+    performance_counters: AStarPerformanceCounters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdaStarState {
+    Empty,
+    FoundTarget,
+    NoTarget,
+}
+
+enum IterationResult {
+    /// The target is the top node on the stack.
+    FoundTarget,
+    /// No target was found, but some nodes were skipped because they exceeded the threshold.
+    ExceededThreshold { next_threshold: Cost },
+    /// No target was found, and no nodes were skipped.
+    Exhausted,
+}
+
+#[derive(Debug)]
+struct Frame<Node> {
+    node: Node,
+    /// The successors of the node that have not yet been explored, with the most promising one last.
+    successors: Vec<Node>,
+}
+
+impl<Context: AStarContext> IdaStar<Context> {
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            stack: Default::default(),
+            state: IdaStarState::Empty,
+            performance_counters: Default::default(),
+        }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn into_context(self) -> Context {
+        self.context
+    }
+
+    pub fn performance_counters(&self) -> &AStarPerformanceCounters {
+        &self.performance_counters
+    }
+
+    pub fn reset(&mut self) {
+        self.context.reset();
+        self.stack.clear();
+        self.state = IdaStarState::Empty;
+        self.performance_counters = Default::default();
+    }
+
+    /// Search for the cheapest target node.
+    ///
+    /// Starts with the cost bound being the lower bound of the root node,
+    /// and raises it to the lowest `cost + a_star_lower_bound` that exceeded it in the previous iteration.
+    /// Fails if the algorithm searched already and was not reset since.
+    pub fn search(
+        &mut self,
+    ) -> Result<AStarResult<<Context::Node as AStarNode>::Identifier>, AStarStateError> {
+        if self.state != IdaStarState::Empty {
+            return Err(AStarStateError::NotReset);
+        }
+
+        let max_cost = self.context.max_cost();
+        let root = self.context.create_root();
+        let mut threshold = root.cost() + root.a_star_lower_bound();
+
+        let result = loop {
+            if threshold > max_cost.unwrap_or(Cost::MAX) {
+                break AStarResult::NoTarget { max_cost };
+            }
+
+            match self.search_iteration(threshold) {
+                IterationResult::FoundTarget => {
+                    let node = &self.stack.last().unwrap().node;
+                    break AStarResult::FoundTarget {
+                        identifier: node.identifier().clone(),
+                        cost: node.cost(),
+                    };
+                }
+                IterationResult::ExceededThreshold { next_threshold } => {
+                    debug_assert!(next_threshold > threshold);
+                    threshold = next_threshold;
+                }
+                IterationResult::Exhausted => break AStarResult::NoTarget { max_cost },
+            }
+        };
+
+        self.state = match result {
+            AStarResult::FoundTarget { .. } => IdaStarState::FoundTarget,
+            AStarResult::NoTarget { .. } => IdaStarState::NoTarget,
+            AStarResult::ExceededMemoryLimit { .. }
+            | AStarResult::ExceededTimeLimit { .. }
+            | AStarResult::Cancelled { .. } => {
+                unreachable!("IDA* has no memory or time limit and cannot be cancelled")
+            }
+        };
+        Ok(result)
+    }
+
+    /// Perform a depth-first search through all nodes whose `cost + a_star_lower_bound` is at most `threshold`.
+    fn search_iteration(&mut self, threshold: Cost) -> IterationResult {
+        let max_cost = self.context.max_cost().unwrap_or(Cost::MAX);
+        let mut next_threshold = None;
+
+        self.stack.clear();
+        let root = self.context.create_root();
+        self.performance_counters.opened_nodes += 1;
+        if self.push_frame(root) {
+            return IterationResult::FoundTarget;
+        }
+
+        while let Some(frame) = self.stack.last_mut() {
+            let Some(successor) = frame.successors.pop() else {
+                self.stack.pop();
+                continue;
+            };
+
+            if successor.cost() > max_cost {
+                continue;
+            }
+
+            let lower_bound_cost = successor.cost() + successor.a_star_lower_bound();
+            if lower_bound_cost > threshold {
+                next_threshold = Some(
+                    next_threshold.map_or(lower_bound_cost, |next_threshold: Cost| {
+                        next_threshold.min(lower_bound_cost)
+                    }),
+                );
+                continue;
+            }
+
+            if self.push_frame(successor) {
+                return IterationResult::FoundTarget;
+            }
+        }
+
+        if let Some(next_threshold) = next_threshold {
+            IterationResult::ExceededThreshold { next_threshold }
+        } else {
+            IterationResult::Exhausted
+        }
+    }
+
+    /// Push a node to the stack, generating its successors if it is not a target.
+    ///
+    /// Returns true if the node is a target.
+    fn push_frame(&mut self, node: Context::Node) -> bool {
+        if self.context.is_target(&node) {
+            self.stack.push(Frame {
+                node,
+                successors: Vec::new(),
+            });
+            return true;
+        }
+
+        let mut successors = Vec::new();
+        self.context.generate_successors(&node, &mut successors);
+        self.performance_counters.closed_nodes += 1;
+        self.performance_counters.opened_nodes += successors.len();
+        // Explore the most promising successor first.
+        successors.sort_unstable_by(|a, b| b.cmp(a));
+
+        self.stack.push(Frame { node, successors });
+        false
+    }
+
+    /// Backtrack from the target node to the root node.
+    ///
+    /// Fails if the search found no target.
+    pub fn backtrack(
+        &self,
+    ) -> Result<
+        impl use<'_, Context> + Iterator<Item = <Context::Node as AStarNode>::EdgeType>,
+        AStarStateError,
+    > {
+        if self.state != IdaStarState::FoundTarget {
+            return Err(AStarStateError::NoTarget);
+        }
+
+        Ok(self
+            .stack
+            .iter()
+            .rev()
+            .filter_map(|frame| frame.node.predecessor_edge_type()))
+    }
+}
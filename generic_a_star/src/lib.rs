@@ -4,24 +4,48 @@ use std::{
     collections::HashMap,
     fmt::{Debug, Display},
     hash::Hash,
+    time::{Duration, Instant},
 };
 
-use binary_heap_plus::{BinaryHeap, MinComparator};
+use cancellation::CancellationToken;
+use closed_list::AStarClosedList;
+use comparator::{AStarNodeComparator, AStarTieBreaking, OpenList};
 use cost::Cost;
 use deterministic_default_hasher::DeterministicDefaultHasher;
+use extend_map::{ExtendCounted, ExtendInspect};
+use histogram::{AStarSearchSnapshot, Histogram};
+use observer::AStarObserver;
 use reset::Reset;
 
+pub mod admissibility;
+pub mod cancellation;
+pub mod closed_list;
+pub mod comparator;
+pub mod cooptimal;
 pub mod cost;
+pub mod cost_limit_ramp;
+pub mod heuristic;
+pub mod histogram;
+pub mod ida_star;
+pub mod observer;
+pub mod phases;
+pub mod potential;
 pub mod reset;
+pub mod streaming;
+#[cfg(test)]
+mod tests;
+pub mod trace;
 
 /// A node of the A* graph.
 /// The node must implement [`Ord`](std::cmp::Ord), ordering it by its cost, ascending.
+/// This ordering is used to break ties with [`AStarTieBreaking::NodeOrd`].
 /// The graph defined by the node type must be cycle-free.
 pub trait AStarNode: Sized + Ord + Debug + Display {
     /// A unique identifier of the node.
     ///
     /// For example, in case of traditional edit distance, this would be the tuple (i, j) indicating which alignment matrix cell this node belongs to.
-    type Identifier: Debug + Clone + Eq + Hash;
+    /// The ordering of identifiers is used to break ties with [`AStarTieBreaking::Identifier`].
+    type Identifier: Debug + Clone + Eq + Ord + Hash;
 
     /// The type collecting possible edge types.
     ///
@@ -44,6 +68,26 @@ pub trait AStarNode: Sized + Ord + Debug + Display {
 
     /// Returns the edge type used to reach this node from the predecessor, or `None` if this is a root node.
     fn predecessor_edge_type(&self) -> Option<Self::EdgeType>;
+
+    /// A score that is used to break ties with [`AStarTieBreaking::SecondaryScore`].
+    /// Nodes with a higher score are preferred.
+    fn secondary_maximisable_score(&self) -> usize {
+        0
+    }
+
+    /// The memory required to store this node in bytes.
+    ///
+    /// This is used to track the memory usage of a search while it is running.
+    /// Node types that own heap allocations should add their size.
+    fn required_memory(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+/// The heap memory owned by a node, excluding the memory of the node itself.
+pub(crate) fn node_heap_memory<Node: AStarNode>(node: &Node) -> usize {
+    node.required_memory()
+        .saturating_sub(std::mem::size_of::<Node>())
 }
 
 pub trait AStarContext: Reset {
@@ -62,6 +106,19 @@ pub trait AStarContext: Reset {
     /// Returns the maximum cost that the target node is allowed to have.
     /// If no target is found with this cost or lower, then [`AStarResult::NoTarget`] is returned.
     fn max_cost(&self) -> Option<Cost>;
+
+    /// Returns true if the A* lower bound of this context is consistent.
+    ///
+    /// If the lower bound is consistent, then each node is closed with its optimal cost, and nodes are never closed twice.
+    /// Otherwise, the search is label-correcting:
+    /// if a node is reached again with a lower cost after it was closed, then it is reopened and its successors are generated again.
+    /// This keeps the result optimal as long as the lower bound is admissible.
+    ///
+    /// Costs are unsigned, so graphs with negative edge costs need to be searched with reduced costs, see [`potential`].
+    /// Lower bounds for the reduced costs are usually inconsistent, so such searches are typically label-correcting.
+    fn is_label_setting(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Default)]
@@ -70,6 +127,18 @@ pub struct AStarPerformanceCounters {
     /// Opened nodes that do not have optimal costs.
     pub suboptimal_opened_nodes: usize,
     pub closed_nodes: usize,
+    /// Closed nodes that were closed again with a lower cost.
+    ///
+    /// This is always zero for label-setting searches.
+    pub reopened_nodes: usize,
+    /// The maximum number of nodes in the open list at any time.
+    pub peak_open_nodes: usize,
+    /// The maximum number of nodes in the closed list at any time.
+    pub peak_closed_nodes: usize,
+    /// The maximum of [`AStar::memory_usage`] at any time, in bytes.
+    pub peak_memory_usage: usize,
+    /// Snapshots of the search, taken whenever the number of closed nodes reaches a power of two, and when the search terminates.
+    pub snapshots: Vec<AStarSearchSnapshot>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -88,22 +157,46 @@ pub enum AStarState<NodeIdentifier> {
 pub struct AStar<Context: AStarContext> {
     state: AStarState<<Context::Node as AStarNode>::Identifier>,
     context: Context,
-    closed_list: HashMap<
-        <Context::Node as AStarNode>::Identifier,
-        Context::Node,
-        DeterministicDefaultHasher,
-    >,
-    open_list: BinaryHeap<Context::Node, MinComparator>,
+    closed_list: AStarClosedList<<Context::Node as AStarNode>::Identifier, Context::Node>,
+    open_list: OpenList<Context::Node>,
+    /// The heap memory owned by the nodes in the closed list, excluding the memory of the nodes themselves.
+    closed_list_heap_memory: usize,
+    /// The number of closed nodes of each depth.
+    closed_depth_counts: Vec<usize>,
+    memory_limit: Option<usize>,
+    time_limit: Option<Duration>,
+    cancellation_token: Option<CancellationToken>,
+    /// True if the context was relaxed since the search was initialised, so closed nodes may be reached again with a lower cost.
+    relaxed: bool,
+    observer: Option<Box<dyn AStarObserver<Context::Node>>>,
     performance_counters: AStarPerformanceCounters,
 }
 
 #[derive(Debug)]
 pub struct AStarBuffers<NodeIdentifier, Node> {
-    closed_list: HashMap<NodeIdentifier, Node, DeterministicDefaultHasher>,
-    open_list: BinaryHeap<Node, MinComparator>,
+    closed_list: AStarClosedList<NodeIdentifier, Node>,
+    open_list: OpenList<Node>,
+    closed_depth_counts: Vec<usize>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// How the context was changed in [`AStar::resume_after_context_change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextChange {
+    /// Only [`AStarContext::max_cost`] was increased or removed.
+    ///
+    /// The search continues with the open list, since no node below the previous maximum cost is affected.
+    IncreasedMaxCost,
+    /// Nodes may have additional successors or successors with lower costs, e.g. because a constraint was relaxed.
+    ///
+    /// The successors of all closed nodes are generated again,
+    /// and closed nodes that are reached with a lower cost are reopened until the search is reset.
+    /// The A* lower bounds must stay admissible.
+    Relaxed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum AStarResult<NodeIdentifier> {
     /// The algorithm has found a target node.
     FoundTarget {
@@ -112,6 +205,32 @@ pub enum AStarResult<NodeIdentifier> {
     },
     /// The algorithm terminated, but did not find a target.
     NoTarget { max_cost: Option<Cost> },
+    /// The algorithm exceeded its memory limit before finding a target.
+    ExceededMemoryLimit { memory_usage: usize },
+    /// The algorithm exceeded its time limit before finding a target.
+    ///
+    /// The lower bound is the cost plus A* lower bound of the node that would have been closed next.
+    /// Unless the lower bound is weighted, no target is cheaper than this.
+    ExceededTimeLimit { lower_bound: Cost },
+    /// The search was cancelled through its [`CancellationToken`] before finding a target.
+    ///
+    /// The lower bound is the same as for [`ExceededTimeLimit`](Self::ExceededTimeLimit).
+    Cancelled { lower_bound: Cost },
+}
+
+/// A method of [`AStar`] or [`IdaStar`](ida_star::IdaStar) was called in a phase of the search that does not allow it.
+///
+/// The phases are tracked by [`AStarState`]: a search is [`initialised`](AStar::initialise) once after creation or [`reset`](AStar::reset),
+/// then [`searched`](AStar::search) any number of times, and [`backtracked`](AStar::backtrack) after it found a target.
+/// The wrappers in [`phases`] track the phase in their type instead, so misusing them fails to compile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AStarStateError {
+    /// The search was initialised already, and needs to be reset before initialising it again.
+    NotReset,
+    /// The search was not initialised since it was created or reset.
+    NotInitialised,
+    /// The search has not found a target, so there is no path to backtrack.
+    NoTarget,
 }
 
 struct BacktrackingIterator<'a_star, Context: AStarContext> {
@@ -130,7 +249,14 @@ impl<Context: AStarContext> AStar<Context> {
             state: AStarState::Empty,
             context,
             closed_list: Default::default(),
-            open_list: BinaryHeap::new_min(),
+            open_list: OpenList::new(Default::default()),
+            closed_list_heap_memory: 0,
+            closed_depth_counts: Vec::new(),
+            memory_limit: None,
+            time_limit: None,
+            cancellation_token: None,
+            relaxed: false,
+            observer: None,
             performance_counters: Default::default(),
         }
     }
@@ -139,13 +265,21 @@ impl<Context: AStarContext> AStar<Context> {
         context: Context,
         mut buffers: AStarBuffers<<Context::Node as AStarNode>::Identifier, Context::Node>,
     ) -> Self {
-        buffers.closed_list.clear();
+        buffers.closed_list.reset();
         buffers.open_list.clear();
+        buffers.closed_depth_counts.clear();
         Self {
             state: AStarState::Empty,
             context,
             closed_list: buffers.closed_list,
             open_list: buffers.open_list,
+            closed_list_heap_memory: 0,
+            closed_depth_counts: buffers.closed_depth_counts,
+            memory_limit: None,
+            time_limit: None,
+            cancellation_token: None,
+            relaxed: false,
+            observer: None,
             performance_counters: Default::default(),
         }
     }
@@ -154,6 +288,100 @@ impl<Context: AStarContext> AStar<Context> {
         &self.state
     }
 
+    pub fn tie_breaking(&self) -> AStarTieBreaking {
+        self.open_list.comparator().tie_breaking()
+    }
+
+    /// Set the policy used to order open nodes with equal costs.
+    ///
+    /// The policy is kept when the algorithm is reset.
+    pub fn set_tie_breaking(&mut self, tie_breaking: AStarTieBreaking) {
+        self.open_list.set_comparator(
+            AStarNodeComparator::new(tie_breaking, self.lower_bound_weight())
+                .with_stable_ties(self.stable_ties()),
+        );
+    }
+
+    pub fn stable_ties(&self) -> bool {
+        self.open_list.comparator().stable_ties()
+    }
+
+    /// Close open nodes that are equal according to the tie-breaking policy in insertion order,
+    /// instead of in the order in which they happen to leave the binary heap of the open list.
+    ///
+    /// This makes the search order a function of the order in which successors are generated,
+    /// independent of the implementation of the open list.
+    /// The setting is kept when the algorithm is reset.
+    pub fn set_stable_ties(&mut self, stable_ties: bool) {
+        self.open_list
+            .set_comparator(self.open_list.comparator().with_stable_ties(stable_ties));
+    }
+
+    pub fn lower_bound_weight(&self) -> f64 {
+        self.open_list.comparator().lower_bound_weight()
+    }
+
+    /// Set the weight of the A* lower bound when ordering open nodes.
+    ///
+    /// With a weight `w > 1`, this becomes weighted A*, which usually closes far fewer nodes,
+    /// but only guarantees that the cost of the target found is at most `w` times the optimal cost.
+    /// The weight is kept when the algorithm is reset.
+    pub fn set_lower_bound_weight(&mut self, lower_bound_weight: f64) {
+        self.open_list.set_comparator(
+            AStarNodeComparator::new(self.tie_breaking(), lower_bound_weight)
+                .with_stable_ties(self.stable_ties()),
+        );
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Set the maximum memory in bytes that the open and closed lists may use.
+    ///
+    /// If the limit is exceeded, then the search terminates with [`AStarResult::ExceededMemoryLimit`].
+    /// The limit is kept when the algorithm is reset.
+    pub fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_limit = memory_limit;
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    /// Set the maximum duration of each call to [`search`](Self::search) or [`search_until`](Self::search_until).
+    ///
+    /// If the limit is exceeded, then the search terminates with [`AStarResult::ExceededTimeLimit`].
+    /// The limit is kept when the algorithm is reset.
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
+    }
+
+    /// Set the token through which the search can be cancelled while it is running.
+    ///
+    /// If the token is cancelled, then the search terminates with [`AStarResult::Cancelled`].
+    /// The token is kept when the algorithm is reset.
+    pub fn set_cancellation_token(&mut self, cancellation_token: Option<CancellationToken>) {
+        self.cancellation_token = cancellation_token;
+    }
+
+    /// Set the observer whose callbacks are invoked while searching.
+    ///
+    /// The observer is kept when the algorithm is reset.
+    pub fn set_observer(&mut self, observer: Option<Box<dyn AStarObserver<Context::Node>>>) {
+        self.observer = observer;
+    }
+
+    /// The memory used by the open and closed lists in bytes.
+    ///
+    /// This includes unused capacity of the lists and heap memory owned by the nodes as reported by [`AStarNode::required_memory`].
+    /// Memory owned by the context is not included.
+    pub fn memory_usage(&self) -> usize {
+        self.closed_list.memory_usage()
+            + self.closed_list_heap_memory
+            + self.open_list.memory_usage()
+    }
+
     pub fn context(&self) -> &Context {
         &self.context
     }
@@ -165,10 +393,24 @@ impl<Context: AStarContext> AStar<Context> {
     pub fn into_buffers(
         self,
     ) -> AStarBuffers<<Context::Node as AStarNode>::Identifier, Context::Node> {
-        AStarBuffers {
-            closed_list: self.closed_list,
-            open_list: self.open_list,
-        }
+        self.into_context_and_buffers().1
+    }
+
+    /// Split the algorithm into its context and its buffers, e.g. to reuse both for another search.
+    pub fn into_context_and_buffers(
+        self,
+    ) -> (
+        Context,
+        AStarBuffers<<Context::Node as AStarNode>::Identifier, Context::Node>,
+    ) {
+        (
+            self.context,
+            AStarBuffers {
+                closed_list: self.closed_list,
+                open_list: self.open_list,
+                closed_depth_counts: self.closed_depth_counts,
+            },
+        )
     }
 
     pub fn closed_node(
@@ -185,113 +427,295 @@ impl<Context: AStarContext> AStar<Context> {
     pub fn reset(&mut self) {
         self.state = AStarState::Empty;
         self.context.reset();
-        self.closed_list.clear();
+        self.closed_list.reset();
         self.open_list.clear();
+        self.closed_list_heap_memory = 0;
+        self.closed_depth_counts.clear();
+        self.relaxed = false;
         self.performance_counters = Default::default();
     }
 
-    pub fn initialise(&mut self) {
-        self.initialise_with(|context| context.create_root());
+    /// Opens the root node created by the context.
+    ///
+    /// Returns the algorithm itself, such that the search can be chained as in `a_star.initialise()?.search()?`.
+    /// Fails if the algorithm was initialised already and not reset since.
+    pub fn initialise(&mut self) -> Result<&mut Self, AStarStateError> {
+        self.initialise_with(|context| context.create_root())
     }
 
-    pub fn initialise_with(&mut self, node: impl FnOnce(&Context) -> Context::Node) {
-        assert_eq!(self.state, AStarState::Empty);
+    /// Like [`initialise`](Self::initialise), but opens the root node returned by `node`.
+    pub fn initialise_with(
+        &mut self,
+        node: impl FnOnce(&Context) -> Context::Node,
+    ) -> Result<&mut Self, AStarStateError> {
+        if self.state != AStarState::Empty {
+            return Err(AStarStateError::NotReset);
+        }
 
         self.state = AStarState::Init;
-        self.open_list.push(node(&self.context));
+        let root = node(&self.context);
+        if let Some(observer) = &mut self.observer {
+            observer.on_open(&root);
+        }
+        self.open_list.push(root);
+        Ok(self)
     }
 
-    pub fn search(&mut self) -> AStarResult<<Context::Node as AStarNode>::Identifier> {
+    /// Applies `change_context` to the context and prepares continuing the search with the next call to [`search`](Self::search),
+    /// reusing the open and closed lists instead of restarting.
+    ///
+    /// This is useful after the search terminated without target, e.g. to retry with an increased maximum cost.
+    /// The `change` describes what `change_context` does, see [`ContextChange`].
+    /// Changes that remove successors or increase their costs are not supported, and require a reset.
+    /// Fails if the algorithm was not initialised.
+    pub fn resume_after_context_change(
+        &mut self,
+        change: ContextChange,
+        change_context: impl FnOnce(&mut Context),
+    ) -> Result<(), AStarStateError> {
+        self.check_initialised()?;
+        change_context(&mut self.context);
+
+        if change == ContextChange::Relaxed {
+            self.relaxed = true;
+
+            // Successors inherit the depth of their predecessor plus one, so closed nodes need their depths to be known.
+            let mut depths = HashMap::with_hasher(DeterministicDefaultHasher);
+            for identifier in self.closed_list.keys() {
+                let mut path = Vec::new();
+                let mut current = Some(identifier);
+                let mut depth = loop {
+                    let Some(identifier) = current else {
+                        break None;
+                    };
+                    if let Some(&depth) = depths.get(identifier) {
+                        break Some(depth);
+                    }
+                    path.push(identifier);
+                    current = self.closed_list[identifier]
+                        .predecessor()
+                        .filter(|predecessor| self.closed_list.contains_key(predecessor));
+                };
+                for identifier in path.into_iter().rev() {
+                    let path_depth = depth.map_or(0, |depth| depth + 1);
+                    depths.insert(identifier.clone(), path_depth);
+                    depth = Some(path_depth);
+                }
+            }
+
+            for (identifier, node) in self.closed_list.iter() {
+                self.open_list.set_successor_depth(depths[identifier] + 1);
+                let mut open_list = ExtendCounted::new(&mut self.open_list);
+                let mut observed_open_list =
+                    ExtendInspect::new(&mut open_list, |successor: &Context::Node| {
+                        if let Some(observer) = &mut self.observer {
+                            observer.on_open(successor);
+                        }
+                    });
+                self.context
+                    .generate_successors(node, &mut observed_open_list);
+                self.performance_counters.opened_nodes += open_list.count();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Searches for a target node of the context.
+    ///
+    /// Fails if the algorithm was not initialised.
+    pub fn search(
+        &mut self,
+    ) -> Result<AStarResult<<Context::Node as AStarNode>::Identifier>, AStarStateError> {
         self.search_until(|context, node| context.is_target(node))
     }
 
+    /// Searches for a node for which `is_target` returns true.
+    ///
+    /// Fails if the algorithm was not initialised.
     pub fn search_until(
+        &mut self,
+        is_target: impl FnMut(&Context, &Context::Node) -> bool,
+    ) -> Result<AStarResult<<Context::Node as AStarNode>::Identifier>, AStarStateError> {
+        self.check_initialised()?;
+        let deadline = self
+            .time_limit
+            .map(|time_limit| Instant::now() + time_limit);
+        Ok(self
+            .search_steps(is_target, deadline, None)
+            .expect("The search only pauses with a limit on closed nodes"))
+    }
+
+    fn check_initialised(&self) -> Result<(), AStarStateError> {
+        if self.state == AStarState::Empty {
+            Err(AStarStateError::NotInitialised)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Searches like [`search_until`](Self::search_until), but returns `None` after closing `max_closed_nodes` nodes without terminating.
+    ///
+    /// The search can be continued by calling this again.
+    /// The caller must ensure that the algorithm is initialised.
+    pub(crate) fn search_steps(
         &mut self,
         mut is_target: impl FnMut(&Context, &Context::Node) -> bool,
-    ) -> AStarResult<<Context::Node as AStarNode>::Identifier> {
-        assert!(matches!(
-            self.state,
-            AStarState::Init | AStarState::Searching | AStarState::Terminated { .. }
-        ));
+        deadline: Option<Instant>,
+        max_closed_nodes: Option<usize>,
+    ) -> Option<AStarResult<<Context::Node as AStarNode>::Identifier>> {
+        debug_assert_ne!(self.state, AStarState::Empty);
 
         let max_cost = self.context.max_cost();
+        let is_label_setting = self.context.is_label_setting() && !self.relaxed;
         if self.open_list.is_empty() {
-            return AStarResult::NoTarget { max_cost };
+            return Some(AStarResult::NoTarget { max_cost });
         }
 
         self.state = AStarState::Searching;
 
         let mut last_node = None;
+        let closed_nodes_limit = max_closed_nodes
+            .map(|max_closed_nodes| self.performance_counters.closed_nodes + max_closed_nodes);
 
         let target_identifier = loop {
-            let Some(node) = self.open_list.pop() else {
+            if closed_nodes_limit
+                .is_some_and(|limit| self.performance_counters.closed_nodes >= limit)
+            {
+                return None;
+            }
+
+            let Some((node, depth)) = self.open_list.pop() else {
                 if last_node.is_none() {
                     unreachable!("Open list was empty.");
                 };
+                self.take_final_snapshot();
                 self.state = AStarState::Terminated {
                     result: AStarResult::NoTarget { max_cost },
                 };
-                return AStarResult::NoTarget { max_cost };
+                return Some(AStarResult::NoTarget { max_cost });
             };
 
             if node.cost() > max_cost.unwrap_or(Cost::MAX) {
+                // Keep the node, such that the search can be resumed with a higher maximum cost.
+                self.open_list.push_with_depth(node, depth);
+                self.take_final_snapshot();
                 self.state = AStarState::Terminated {
                     result: AStarResult::NoTarget { max_cost },
                 };
-                return AStarResult::NoTarget { max_cost };
+                return Some(AStarResult::NoTarget { max_cost });
+            }
+
+            if let Some(memory_limit) = self.memory_limit {
+                let memory_usage = self.memory_usage();
+                if memory_usage > memory_limit {
+                    self.open_list.push_with_depth(node, depth);
+                    self.take_final_snapshot();
+                    self.state = AStarState::Terminated {
+                        result: AStarResult::ExceededMemoryLimit { memory_usage },
+                    };
+                    return Some(AStarResult::ExceededMemoryLimit { memory_usage });
+                }
+            }
+
+            let interruption = if self
+                .cancellation_token
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                Some(AStarResult::Cancelled {
+                    lower_bound: node.cost() + node.a_star_lower_bound(),
+                })
+            } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                Some(AStarResult::ExceededTimeLimit {
+                    lower_bound: node.cost() + node.a_star_lower_bound(),
+                })
+            } else {
+                None
+            };
+            if let Some(result) = interruption {
+                self.open_list.push_with_depth(node, depth);
+                self.take_final_snapshot();
+                self.state = AStarState::Terminated {
+                    result: result.clone(),
+                };
+                return Some(result);
             }
 
             last_node = Some(node.identifier().clone());
 
             if let Some(previous_visit) = self.closed_list.get(node.identifier()) {
-                // If we have already visited the node, we now must be visiting it with a higher cost.
-                debug_assert!(
-                    previous_visit.cost() + previous_visit.a_star_lower_bound()
-                        <= node.cost() + node.a_star_lower_bound(),
-                    "{}",
-                    {
-                        use std::fmt::Write;
-                        let mut previous_visit = previous_visit;
-                        let mut node = &node;
-                        let mut out = String::new();
-
-                        writeln!(out, "previous_visit:").unwrap();
-                        while let Some(predecessor) = previous_visit.predecessor() {
-                            writeln!(out, "{previous_visit}").unwrap();
-                            previous_visit = self.closed_list.get(predecessor).unwrap();
-                        }
-
-                        writeln!(out, "\nnode:").unwrap();
-                        while let Some(predecessor) = node.predecessor() {
-                            writeln!(out, "{node}").unwrap();
-                            node = self.closed_list.get(predecessor).unwrap();
+                if is_label_setting {
+                    // If we have already visited the node, we now must be visiting it with a higher cost.
+                    debug_assert!(
+                        self.open_list.comparator().is_weighted()
+                            || previous_visit.cost() + previous_visit.a_star_lower_bound()
+                                <= node.cost() + node.a_star_lower_bound(),
+                        "{}",
+                        {
+                            use std::fmt::Write;
+                            let mut previous_visit = previous_visit;
+                            let mut node = &node;
+                            let mut out = String::new();
+
+                            writeln!(out, "previous_visit:").unwrap();
+                            while let Some(predecessor) = previous_visit.predecessor() {
+                                writeln!(out, "{previous_visit}").unwrap();
+                                previous_visit = self.closed_list.get(predecessor).unwrap();
+                            }
+
+                            writeln!(out, "\nnode:").unwrap();
+                            while let Some(predecessor) = node.predecessor() {
+                                writeln!(out, "{node}").unwrap();
+                                node = self.closed_list.get(predecessor).unwrap();
+                            }
+
+                            out
                         }
-
-                        out
+                    );
+                    if let Some(observer) = &mut self.observer {
+                        observer.on_skip_suboptimal(&node);
+                    }
+                    self.performance_counters.suboptimal_opened_nodes += 1;
+                    continue;
+                } else if previous_visit.cost() <= node.cost() {
+                    if let Some(observer) = &mut self.observer {
+                        observer.on_skip_suboptimal(&node);
                     }
-                );
-                self.performance_counters.suboptimal_opened_nodes += 1;
-                continue;
+                    self.performance_counters.suboptimal_opened_nodes += 1;
+                    continue;
+                } else {
+                    // We have found a cheaper path to a closed node, so it needs to be reopened.
+                    self.performance_counters.reopened_nodes += 1;
+                }
             }
 
-            let open_nodes_without_new_successors = self.open_list.len();
-            self.context.generate_successors(&node, &mut self.open_list);
-            self.performance_counters.opened_nodes +=
-                self.open_list.len() - open_nodes_without_new_successors;
+            self.open_list.set_successor_depth(depth + 1);
+            let mut open_list = ExtendCounted::new(&mut self.open_list);
+            let mut observed_open_list =
+                ExtendInspect::new(&mut open_list, |successor: &Context::Node| {
+                    if let Some(observer) = &mut self.observer {
+                        observer.on_open(successor);
+                    }
+                });
+            self.context
+                .generate_successors(&node, &mut observed_open_list);
+            self.performance_counters.opened_nodes += open_list.count();
 
             if is_target(&self.context, &node) {
+                if let Some(observer) = &mut self.observer {
+                    observer.on_target_found(&node);
+                }
                 let identifier = node.identifier().clone();
-                let previous_visit = self.closed_list.insert(node.identifier().clone(), node);
-                self.performance_counters.closed_nodes += 1;
-                debug_assert!(previous_visit.is_none());
+                self.close_node(node, depth, is_label_setting);
                 break identifier;
             }
 
-            let previous_visit = self.closed_list.insert(node.identifier().clone(), node);
-            self.performance_counters.closed_nodes += 1;
-            debug_assert!(previous_visit.is_none());
+            self.close_node(node, depth, is_label_setting);
         };
 
+        self.take_final_snapshot();
+
         let cost = self.closed_list.get(&target_identifier).unwrap().cost();
         self.state = AStarState::Terminated {
             result: AStarResult::FoundTarget {
@@ -299,41 +723,102 @@ impl<Context: AStarContext> AStar<Context> {
                 cost,
             },
         };
-        AStarResult::FoundTarget {
+        Some(AStarResult::FoundTarget {
             identifier: target_identifier,
             cost,
+        })
+    }
+
+    fn close_node(&mut self, node: Context::Node, depth: usize, is_label_setting: bool) {
+        if let Some(observer) = &mut self.observer {
+            observer.on_close(&node);
+        }
+        self.closed_list_heap_memory += node_heap_memory(&node);
+        let previous_visit = self.closed_list.insert(node.identifier().clone(), node);
+        if let Some(previous_visit) = &previous_visit {
+            self.closed_list_heap_memory -= node_heap_memory(previous_visit);
+        }
+        debug_assert!(!is_label_setting || previous_visit.is_none());
+
+        if self.closed_depth_counts.len() <= depth {
+            self.closed_depth_counts.resize(depth + 1, 0);
+        }
+        self.closed_depth_counts[depth] += 1;
+        self.performance_counters.closed_nodes += 1;
+
+        // The open list is largest after generating the successors of the node that is closed.
+        let memory_usage = self.memory_usage();
+        let counters = &mut self.performance_counters;
+        counters.peak_open_nodes = counters.peak_open_nodes.max(self.open_list.len());
+        counters.peak_closed_nodes = counters.peak_closed_nodes.max(self.closed_list.len());
+        counters.peak_memory_usage = counters.peak_memory_usage.max(memory_usage);
+
+        if self.performance_counters.closed_nodes.is_power_of_two() {
+            self.take_snapshot();
         }
     }
 
-    pub fn backtrack(
-        &self,
-    ) -> impl use<'_, Context> + Iterator<Item = <Context::Node as AStarNode>::EdgeType> {
-        let AStarState::Terminated {
-            result: AStarResult::FoundTarget { identifier, .. },
-        } = &self.state
-        else {
-            panic!("Cannot backtrack since no target was found.")
+    fn take_snapshot(&mut self) {
+        let snapshot = AStarSearchSnapshot {
+            closed_nodes: self.performance_counters.closed_nodes,
+            open_f_values: Histogram::from_values(self.open_list.nodes().map(|node| {
+                node.cost()
+                    .as_u64()
+                    .saturating_add(node.a_star_lower_bound().as_u64())
+            })),
+            closed_depths: Histogram::from_counts(&self.closed_depth_counts),
         };
+        self.performance_counters.snapshots.push(snapshot);
+    }
 
-        self.backtrack_from(identifier).unwrap()
+    /// Takes a snapshot when the search terminates, unless one was taken at the same number of closed nodes already.
+    fn take_final_snapshot(&mut self) {
+        if self
+            .performance_counters
+            .snapshots
+            .last()
+            .is_none_or(|snapshot| snapshot.closed_nodes != self.performance_counters.closed_nodes)
+        {
+            self.take_snapshot();
+        }
+    }
+
+    /// Backtrack from the target node to a root node.
+    ///
+    /// Fails if the last search found no target.
+    pub fn backtrack(
+        &self,
+    ) -> Result<
+        impl use<'_, Context> + Iterator<Item = <Context::Node as AStarNode>::EdgeType>,
+        AStarStateError,
+    > {
+        Ok(self.backtrack_from(self.target()?).unwrap())
     }
 
     /// Backtrack from the target node to a root node.
     ///
     /// The elements of the iterator are a pair of an edge and the cost of the node that is reached by the edge.
     /// The cost of the first node is never returned.
+    /// Fails if the last search found no target.
     pub fn backtrack_with_costs(
         &self,
-    ) -> impl use<'_, Context> + Iterator<Item = (<Context::Node as AStarNode>::EdgeType, Cost)>
-    {
-        let AStarState::Terminated {
+    ) -> Result<
+        impl use<'_, Context> + Iterator<Item = (<Context::Node as AStarNode>::EdgeType, Cost)>,
+        AStarStateError,
+    > {
+        Ok(self.backtrack_with_costs_from(self.target()?).unwrap())
+    }
+
+    /// The identifier of the target node found by the last search.
+    fn target(&self) -> Result<&<Context::Node as AStarNode>::Identifier, AStarStateError> {
+        if let AStarState::Terminated {
             result: AStarResult::FoundTarget { identifier, .. },
         } = &self.state
-        else {
-            panic!("Cannot backtrack since no target was found.")
-        };
-
-        self.backtrack_with_costs_from(identifier).unwrap()
+        {
+            Ok(identifier)
+        } else {
+            Err(AStarStateError::NoTarget)
+        }
     }
 
     pub fn backtrack_from(
@@ -405,11 +890,27 @@ impl<Context: AStarContext> Iterator for BacktrackingIteratorWithCost<'_, Contex
     }
 }
 
-impl<NodeIdentifier, Node: Ord> Default for AStarBuffers<NodeIdentifier, Node> {
+impl Display for AStarStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotReset => write!(
+                f,
+                "the search was initialised already and needs to be reset first"
+            ),
+            Self::NotInitialised => write!(f, "the search was not initialised"),
+            Self::NoTarget => write!(f, "the search found no target"),
+        }
+    }
+}
+
+impl std::error::Error for AStarStateError {}
+
+impl<NodeIdentifier, Node: AStarNode> Default for AStarBuffers<NodeIdentifier, Node> {
     fn default() -> Self {
         Self {
             closed_list: Default::default(),
-            open_list: BinaryHeap::new_min(),
+            open_list: OpenList::new(Default::default()),
+            closed_depth_counts: Vec::new(),
         }
     }
 }
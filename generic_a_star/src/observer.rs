@@ -0,0 +1,27 @@
+use std::fmt::{Debug, Formatter};
+
+use crate::AStarNode;
+
+/// Callbacks invoked by [`AStar`](crate::AStar) while it searches.
+///
+/// This allows external tooling such as progress bars or dumps of the search space to follow the search without changing the search loop.
+/// All callbacks do nothing by default.
+pub trait AStarObserver<Node: AStarNode> {
+    /// Called when a node is inserted into the open list, including the root node.
+    fn on_open(&mut self, _node: &Node) {}
+
+    /// Called when a node is closed, including a target node.
+    fn on_close(&mut self, _node: &Node) {}
+
+    /// Called when a node is popped from the open list, but dropped because it was closed before with at most the same cost.
+    fn on_skip_suboptimal(&mut self, _node: &Node) {}
+
+    /// Called when a target node is popped from the open list, before it is closed.
+    fn on_target_found(&mut self, _node: &Node) {}
+}
+
+impl<Node: AStarNode> Debug for dyn AStarObserver<Node> + '_ {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AStarObserver")
+    }
+}
@@ -0,0 +1,151 @@
+//! Wrappers around [`AStar`] that track the phase of the search in their type, such that calling a method in the wrong phase fails to compile.
+//!
+//! A search is started with [`AStar::into_initialised`], run with [`InitialisedAStar::search`],
+//! and only a search that found a target can be backtracked:
+//!
+//! ```ignore
+//! let SearchOutcome::FoundTarget(a_star) = AStar::new(context).into_initialised().search() else {
+//!     return Err(...);
+//! };
+//! let path: Vec<_> = a_star.backtrack().collect();
+//! ```
+//!
+//! The methods of [`AStar`] itself check the phase at runtime instead, which allows to reuse one instance across phases without moving it.
+
+use crate::{cost::Cost, AStar, AStarContext, AStarNode, AStarResult, AStarState, ContextChange};
+
+/// An [`AStar`] that was initialised and can be searched.
+pub struct InitialisedAStar<Context: AStarContext> {
+    a_star: AStar<Context>,
+}
+
+/// An [`AStar`] whose last search found a target, such that it can be backtracked.
+pub struct TargetAStar<Context: AStarContext> {
+    a_star: AStar<Context>,
+    identifier: <Context::Node as AStarNode>::Identifier,
+    cost: Cost,
+}
+
+/// The outcome of [`InitialisedAStar::search`].
+pub enum SearchOutcome<Context: AStarContext> {
+    /// The search found a target.
+    FoundTarget(TargetAStar<Context>),
+    /// The search terminated without a target with the given result, which is never [`AStarResult::FoundTarget`].
+    ///
+    /// The search stays initialised, so it can be searched again, e.g. after [`InitialisedAStar::resume_after_context_change`].
+    Terminated {
+        a_star: InitialisedAStar<Context>,
+        result: AStarResult<<Context::Node as AStarNode>::Identifier>,
+    },
+}
+
+impl<Context: AStarContext> AStar<Context> {
+    /// Opens the root node created by the context and tracks the phase of the search in the returned type.
+    ///
+    /// If the algorithm was initialised already, then it is reset first.
+    pub fn into_initialised(self) -> InitialisedAStar<Context> {
+        self.into_initialised_with(|context| context.create_root())
+    }
+
+    /// Like [`into_initialised`](Self::into_initialised), but opens the root node returned by `node`.
+    pub fn into_initialised_with(
+        mut self,
+        node: impl FnOnce(&Context) -> Context::Node,
+    ) -> InitialisedAStar<Context> {
+        if self.state != AStarState::Empty {
+            self.reset();
+        }
+        self.initialise_with(node).expect("The algorithm was reset");
+
+        InitialisedAStar { a_star: self }
+    }
+}
+
+impl<Context: AStarContext> InitialisedAStar<Context> {
+    /// See [`AStar::search`].
+    pub fn search(self) -> SearchOutcome<Context> {
+        self.search_until(|context, node| context.is_target(node))
+    }
+
+    /// See [`AStar::search_until`].
+    pub fn search_until(
+        mut self,
+        is_target: impl FnMut(&Context, &Context::Node) -> bool,
+    ) -> SearchOutcome<Context> {
+        match self
+            .a_star
+            .search_until(is_target)
+            .expect("The algorithm is initialised")
+        {
+            AStarResult::FoundTarget { identifier, cost } => {
+                SearchOutcome::FoundTarget(TargetAStar {
+                    a_star: self.a_star,
+                    identifier,
+                    cost,
+                })
+            }
+            result => SearchOutcome::Terminated {
+                a_star: self,
+                result,
+            },
+        }
+    }
+
+    /// See [`AStar::resume_after_context_change`].
+    pub fn resume_after_context_change(
+        &mut self,
+        change: ContextChange,
+        change_context: impl FnOnce(&mut Context),
+    ) {
+        self.a_star
+            .resume_after_context_change(change, change_context)
+            .expect("The algorithm is initialised");
+    }
+
+    pub fn a_star(&self) -> &AStar<Context> {
+        &self.a_star
+    }
+
+    pub fn into_inner(self) -> AStar<Context> {
+        self.a_star
+    }
+}
+
+impl<Context: AStarContext> TargetAStar<Context> {
+    /// The identifier of the target node.
+    pub fn identifier(&self) -> &<Context::Node as AStarNode>::Identifier {
+        &self.identifier
+    }
+
+    /// The cost of the target node.
+    pub fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    /// See [`AStar::backtrack`].
+    pub fn backtrack(
+        &self,
+    ) -> impl use<'_, Context> + Iterator<Item = <Context::Node as AStarNode>::EdgeType> {
+        self.a_star
+            .backtrack_from(&self.identifier)
+            .expect("The target is closed")
+    }
+
+    /// See [`AStar::backtrack_with_costs`].
+    pub fn backtrack_with_costs(
+        &self,
+    ) -> impl use<'_, Context> + Iterator<Item = (<Context::Node as AStarNode>::EdgeType, Cost)>
+    {
+        self.a_star
+            .backtrack_with_costs_from(&self.identifier)
+            .expect("The target is closed")
+    }
+
+    pub fn a_star(&self) -> &AStar<Context> {
+        &self.a_star
+    }
+
+    pub fn into_inner(self) -> AStar<Context> {
+        self.a_star
+    }
+}
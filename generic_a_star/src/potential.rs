@@ -0,0 +1,42 @@
+//! Searching graphs with negative edge costs by shifting the costs with a potential.
+//!
+//! [`Cost`] is unsigned, so negative edge costs cannot be given to the search directly.
+//! Instead, the context assigns a potential `p` to each node and uses the reduced cost `c + p(u) - p(v)`
+//! for an edge from `u` to `v` with signed cost `c`.
+//! Along a path from `s` to `t`, the potentials telescope, so its reduced cost is its signed cost plus `p(s) - p(t)`.
+//! For a fixed root and target, this is the same offset for all paths, hence the optimal paths stay optimal.
+//!
+//! The potential must be feasible, i.e. all reduced costs must be non-negative.
+//! For example, if each edge increases some depth of the nodes by at least one, like the position in an alignment matrix,
+//! then the negated depth times the absolute value of the most negative edge cost is feasible.
+//! A* lower bounds must be admissible for the reduced costs.
+//! Lower bounds that are derived from the signed costs are usually inconsistent for the reduced costs,
+//! in which case the context must not be [label-setting](crate::AStarContext::is_label_setting).
+
+use crate::cost::Cost;
+
+/// Returns the reduced cost of an edge with the given signed cost between nodes with the given potentials.
+///
+/// Returns `None` if the reduced cost is negative or does not fit into a [`Cost`], i.e. if the potential is not feasible for this edge.
+pub fn reduced_cost(signed_cost: i64, from_potential: i64, to_potential: i64) -> Option<Cost> {
+    u64::try_from(i128::from(signed_cost) + i128::from(from_potential) - i128::from(to_potential))
+        .ok()
+        .map(Cost::from)
+}
+
+/// Returns the signed cost of a path with the given reduced cost between nodes with the given potentials.
+///
+/// This inverts [`reduced_cost`] for whole paths, e.g. for the cost of the target found by the search.
+///
+/// # Panics
+///
+/// Panics if the signed cost does not fit into an `i64`.
+pub fn signed_cost(reduced_cost: Cost, root_potential: i64, target_potential: i64) -> i64 {
+    i64::try_from(
+        i128::from(reduced_cost.as_u64()) - i128::from(root_potential)
+            + i128::from(target_potential),
+    )
+    .unwrap_or_else(|_| {
+        panic!("The signed cost of reduced cost {reduced_cost} does not fit into an i64")
+    })
+}
@@ -0,0 +1,130 @@
+//! Backtracking the parts of the result path that are fixed while the search is still running.
+//!
+//! In a label-setting search, closed nodes are never reopened, so their predecessors never change.
+//! Every target that is closed later is reached through a node that is open now,
+//! hence the path from the root to the deepest common ancestor of all open nodes is a prefix of the result path.
+//! Open nodes whose f-value exceeds an upper bound of the cost of the target never lead to it, so they can be ignored.
+//! Then the prefix grows steadily behind the frontier of minimum f-values,
+//! and can be output long before the search terminates.
+
+use std::{collections::HashMap, time::Instant};
+
+use deterministic_default_hasher::DeterministicDefaultHasher;
+
+use num_traits::SaturatingAdd;
+
+use crate::{cost::Cost, AStar, AStarContext, AStarNode, AStarResult, AStarState, AStarStateError};
+
+impl<Context: AStarContext> AStar<Context> {
+    /// Returns the deepest closed node that lies on the path to every open node that may lead to the target.
+    ///
+    /// Whichever target the search closes later, its path from the root passes through this node,
+    /// so [`backtrack_from`](Self::backtrack_from) this node yields a suffix of the reversed result path.
+    /// Open nodes whose cost plus A* lower bound exceeds the `cost_upper_bound` are ignored,
+    /// hence the bound must not be lower than the cost of the target that the search finds,
+    /// which for weighted searches may be higher than the optimal cost.
+    /// Without an upper bound, expensive open nodes close to the root usually keep the prefix from growing.
+    ///
+    /// Returns `None` if no node is known to be fixed, e.g. before the root is closed,
+    /// or if the search is label-correcting, since reopened nodes may change their predecessors.
+    pub fn fixed_path_prefix(
+        &self,
+        cost_upper_bound: Option<Cost>,
+    ) -> Option<<Context::Node as AStarNode>::Identifier> {
+        if !self.context.is_label_setting() || self.relaxed {
+            return None;
+        }
+        if let AStarState::Terminated {
+            result: AStarResult::FoundTarget { identifier, .. },
+        } = &self.state
+        {
+            return Some(identifier.clone());
+        }
+
+        let mut open_predecessors = self
+            .open_list
+            .nodes()
+            // Open nodes that are closed already are outdated duplicates.
+            .filter(|node| !self.closed_list.contains_key(node.identifier()))
+            .filter(|node| {
+                cost_upper_bound.is_none_or(|cost_upper_bound| {
+                    node.cost().saturating_add(&node.a_star_lower_bound()) <= cost_upper_bound
+                })
+            })
+            .map(AStarNode::predecessor);
+
+        // The path of the first open node, indexed from the root.
+        let mut path = Vec::new();
+        let mut current = open_predecessors.next()??;
+        loop {
+            path.push(current);
+            let Some(predecessor) = self.closed_list.get(current)?.predecessor() else {
+                break;
+            };
+            current = predecessor;
+        }
+        path.reverse();
+
+        // Maps each visited node to the index of the deepest node of the path that lies on its own path.
+        let mut meeting_indices: HashMap<_, _, DeterministicDefaultHasher> =
+            HashMap::with_hasher(DeterministicDefaultHasher);
+        meeting_indices.extend(path.iter().enumerate().map(|(index, node)| (*node, index)));
+        let mut fixed_index = path.len() - 1;
+
+        for predecessor in open_predecessors {
+            let mut current = predecessor?;
+            let mut visited = Vec::new();
+            let meeting_index = loop {
+                if let Some(&meeting_index) = meeting_indices.get(current) {
+                    break meeting_index;
+                }
+                visited.push(current);
+                // Paths that do not meet the path of the first open node lead to another root.
+                current = self.closed_list.get(current)?.predecessor()?;
+            };
+
+            fixed_index = fixed_index.min(meeting_index);
+            meeting_indices.extend(visited.into_iter().map(|node| (node, meeting_index)));
+        }
+
+        Some(path[fixed_index].clone())
+    }
+
+    /// Searches like [`search`](Self::search), and calls `on_fixed_path_prefix` whenever the [`fixed_path_prefix`](Self::fixed_path_prefix) grows.
+    ///
+    /// The fixed prefix is computed with the given `cost_upper_bound` after every `interval` closed nodes,
+    /// and once more with the target after the search found it.
+    /// Its computation takes time linear in the number of closed nodes that are ancestors of open nodes,
+    /// so the interval should be large enough for the search to outweigh it.
+    /// Fails if the algorithm was not initialised.
+    pub fn search_streaming(
+        &mut self,
+        interval: usize,
+        cost_upper_bound: Option<Cost>,
+        mut on_fixed_path_prefix: impl FnMut(&Self, &<Context::Node as AStarNode>::Identifier),
+    ) -> Result<AStarResult<<Context::Node as AStarNode>::Identifier>, AStarStateError> {
+        self.check_initialised()?;
+        let deadline = self
+            .time_limit
+            .map(|time_limit| Instant::now() + time_limit);
+        let mut previous_prefix = None;
+
+        loop {
+            let result = self.search_steps(
+                |context, node| context.is_target(node),
+                deadline,
+                Some(interval.max(1)),
+            );
+
+            let prefix = self.fixed_path_prefix(cost_upper_bound);
+            if prefix.is_some() && prefix != previous_prefix {
+                on_fixed_path_prefix(self, prefix.as_ref().unwrap());
+                previous_prefix = prefix;
+            }
+
+            if let Some(result) = result {
+                return Ok(result);
+            }
+        }
+    }
+}
@@ -0,0 +1,1104 @@
+use std::{cell::RefCell, cmp::Ordering, fmt::Display, rc::Rc, time::Duration};
+
+use crate::{
+    cancellation::CancellationToken,
+    comparator::AStarTieBreaking,
+    cost::Cost,
+    cost_limit_ramp::{CostLimitRamp, CostLimitRampResult},
+    heuristic::{
+        AStarNodeWithLowerBound, CachedHeuristic, Heuristic, HeuristicContext, MaxHeuristic,
+    },
+    ida_star::IdaStar,
+    observer::AStarObserver,
+    phases::SearchOutcome,
+    potential,
+    reset::Reset,
+    trace::{AStarTrace, AStarTraceRecorder, AStarTraceViolation},
+    AStar, AStarContext, AStarNode, AStarResult, AStarStateError, ContextChange,
+};
+
+/// A random directed acyclic graph with edges from lower to higher node indices.
+///
+/// The edges have signed costs, and the search uses their reduced costs under the potentials of the nodes.
+struct RandomGraph {
+    /// The signed edge costs, which are negative for some edges of graphs created with [`RandomGraph::with_negative_edges`].
+    signed_edges: Vec<Vec<(usize, i64)>>,
+    potentials: Vec<i64>,
+    /// The reduced edge costs.
+    edges: Vec<Vec<(usize, Cost)>>,
+    distances: Vec<Cost>,
+    lower_bounds: Vec<Cost>,
+    is_label_setting: bool,
+    max_cost: Option<Cost>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Node {
+    identifier: usize,
+    predecessor: Option<usize>,
+    cost: Cost,
+    a_star_lower_bound: Cost,
+}
+
+/// A minimal linear congruential generator, such that the tests are reproducible without extra dependencies.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, limit: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % limit
+    }
+}
+
+impl RandomGraph {
+    fn new(seed: u64, node_count: usize, is_label_setting: bool) -> Self {
+        Self::generate(seed, node_count, is_label_setting, 0)
+    }
+
+    /// Like [`new`](Self::new), but with edge costs between -10 and 9.
+    fn with_negative_edges(seed: u64, node_count: usize, is_label_setting: bool) -> Self {
+        Self::generate(seed, node_count, is_label_setting, 10)
+    }
+
+    /// Generates a graph with edge costs between `-negative_offset` and `19 - negative_offset`.
+    ///
+    /// The potential of a node is its index times `-negative_offset`, which is feasible since each edge increases the index by at least one.
+    fn generate(
+        seed: u64,
+        node_count: usize,
+        is_label_setting: bool,
+        negative_offset: i64,
+    ) -> Self {
+        let mut random = Random(seed);
+        let mut signed_edges = vec![Vec::new(); node_count];
+        for (from, edges) in signed_edges.iter_mut().enumerate().take(node_count - 1) {
+            // Ensure that the target is reachable.
+            edges.push((from + 1, random.next(20) as i64 - negative_offset));
+
+            for _ in 0..random.next(4) {
+                let to = from + 1 + random.next((node_count - from - 1) as u64) as usize;
+                edges.push((to, random.next(20) as i64 - negative_offset));
+            }
+        }
+
+        let potentials: Vec<_> = (0..node_count)
+            .map(|node| -(node as i64) * negative_offset)
+            .collect();
+        let edges: Vec<Vec<_>> = signed_edges
+            .iter()
+            .enumerate()
+            .map(|(from, edges)| {
+                edges
+                    .iter()
+                    .map(|&(to, cost)| {
+                        (
+                            to,
+                            potential::reduced_cost(cost, potentials[from], potentials[to])
+                                .unwrap(),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Compute the exact distances to the target and derive an admissible but usually inconsistent lower bound from them.
+        let mut distances = vec![Cost::MAX; node_count];
+        distances[node_count - 1] = Cost::ZERO;
+        for from in (0..node_count - 1).rev() {
+            distances[from] = edges[from]
+                .iter()
+                .map(|&(to, cost)| distances[to] + cost)
+                .min()
+                .unwrap();
+        }
+        let lower_bounds = distances
+            .iter()
+            .map(|distance| {
+                if is_label_setting {
+                    Cost::ZERO
+                } else {
+                    Cost::from(distance.as_u64() * random.next(101) / 100)
+                }
+            })
+            .collect();
+
+        Self {
+            signed_edges,
+            potentials,
+            edges,
+            distances,
+            lower_bounds,
+            is_label_setting,
+            max_cost: None,
+        }
+    }
+
+    fn bellman_ford(&self) -> Cost {
+        let mut distances = vec![Cost::MAX; self.edges.len()];
+        distances[0] = Cost::ZERO;
+
+        for _ in 0..self.edges.len() {
+            for (from, edges) in self.edges.iter().enumerate() {
+                if distances[from] == Cost::MAX {
+                    continue;
+                }
+
+                for &(to, cost) in edges {
+                    distances[to] = distances[to].min(distances[from] + cost);
+                }
+            }
+        }
+
+        *distances.last().unwrap()
+    }
+
+    /// Computes the signed cost of the shortest path from the root to the target with the Bellman-Ford algorithm on the signed edge costs.
+    fn signed_bellman_ford(&self) -> i64 {
+        let mut distances = vec![None; self.signed_edges.len()];
+        distances[0] = Some(0);
+
+        for _ in 0..self.signed_edges.len() {
+            for (from, edges) in self.signed_edges.iter().enumerate() {
+                let Some(from_distance) = distances[from] else {
+                    continue;
+                };
+
+                for &(to, cost) in edges {
+                    let distance = from_distance + cost;
+                    if distances[to].is_none_or(|to_distance| distance < to_distance) {
+                        distances[to] = Some(distance);
+                    }
+                }
+            }
+        }
+
+        distances.last().unwrap().unwrap()
+    }
+
+    /// Converts the reduced cost of a path from the root to the target into its signed cost.
+    fn signed_target_cost(&self, cost: Cost) -> i64 {
+        potential::signed_cost(cost, self.potentials[0], *self.potentials.last().unwrap())
+    }
+}
+
+impl AStarNode for Node {
+    type Identifier = usize;
+
+    type EdgeType = ();
+
+    fn identifier(&self) -> &Self::Identifier {
+        &self.identifier
+    }
+
+    fn cost(&self) -> Cost {
+        self.cost
+    }
+
+    fn a_star_lower_bound(&self) -> Cost {
+        self.a_star_lower_bound
+    }
+
+    fn predecessor(&self) -> Option<&Self::Identifier> {
+        self.predecessor.as_ref()
+    }
+
+    fn predecessor_edge_type(&self) -> Option<Self::EdgeType> {
+        self.predecessor.map(|_| ())
+    }
+}
+
+impl AStarNodeWithLowerBound for Node {
+    fn raise_a_star_lower_bound(&mut self, lower_bound: Cost) {
+        self.a_star_lower_bound = self.a_star_lower_bound.max(lower_bound);
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.cost + self.a_star_lower_bound).cmp(&(other.cost + other.a_star_lower_bound))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}; cost: {} + {}",
+            self.identifier, self.cost, self.a_star_lower_bound
+        )
+    }
+}
+
+impl AStarContext for RandomGraph {
+    type Node = Node;
+
+    fn create_root(&self) -> Self::Node {
+        Node {
+            identifier: 0,
+            predecessor: None,
+            cost: Cost::ZERO,
+            a_star_lower_bound: self.lower_bounds[0],
+        }
+    }
+
+    fn generate_successors(&mut self, node: &Self::Node, output: &mut impl Extend<Self::Node>) {
+        output.extend(self.edges[node.identifier].iter().map(|&(to, cost)| Node {
+            identifier: to,
+            predecessor: Some(node.identifier),
+            cost: node.cost + cost,
+            a_star_lower_bound: self.lower_bounds[to],
+        }));
+    }
+
+    fn is_target(&self, node: &Self::Node) -> bool {
+        node.identifier == self.edges.len() - 1
+    }
+
+    fn max_cost(&self) -> Option<Cost> {
+        self.max_cost
+    }
+
+    fn is_label_setting(&self) -> bool {
+        self.is_label_setting
+    }
+}
+
+impl Reset for RandomGraph {
+    fn reset(&mut self) {}
+}
+
+/// A consistent heuristic that is a fraction of the exact distance to the target.
+struct FractionHeuristic {
+    distances: Vec<Cost>,
+    percent: u64,
+}
+
+impl Heuristic<Node> for FractionHeuristic {
+    fn lower_bound(&mut self, node: &Node) -> Cost {
+        Cost::from(self.distances[node.identifier].as_u64() * self.percent / 100)
+    }
+}
+
+impl Reset for FractionHeuristic {
+    fn reset(&mut self) {}
+}
+
+fn assert_optimal(is_label_setting: bool) -> usize {
+    let mut reopened_nodes = 0;
+
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, is_label_setting);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+        assert_eq!(
+            a_star
+                .backtrack_with_costs()
+                .unwrap()
+                .next()
+                .map(|(_, cost)| cost),
+            Some(expected_cost),
+            "seed: {seed}"
+        );
+
+        reopened_nodes += a_star.performance_counters().reopened_nodes;
+    }
+
+    reopened_nodes
+}
+
+#[test]
+fn label_setting_matches_bellman_ford() {
+    assert_eq!(assert_optimal(true), 0);
+}
+
+#[test]
+fn label_correcting_matches_bellman_ford() {
+    // The test graphs should be complex enough to actually require reopening nodes.
+    assert!(assert_optimal(false) > 0);
+}
+
+/// Like [`assert_optimal`], but on graphs with negative edges, comparing the signed cost of the target to Bellman-Ford on the signed edge costs.
+fn assert_optimal_with_negative_edges(is_label_setting: bool) -> usize {
+    let mut reopened_nodes = 0;
+    let mut negative_edges = 0;
+
+    for seed in 0..200 {
+        let graph = RandomGraph::with_negative_edges(seed, 30, is_label_setting);
+        let expected_cost = graph.signed_bellman_ford();
+        negative_edges += graph
+            .signed_edges
+            .iter()
+            .flatten()
+            .filter(|(_, cost)| *cost < 0)
+            .count();
+
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(
+            a_star.context().signed_target_cost(cost),
+            expected_cost,
+            "seed: {seed}"
+        );
+
+        reopened_nodes += a_star.performance_counters().reopened_nodes;
+    }
+
+    assert!(negative_edges > 0);
+    reopened_nodes
+}
+
+#[test]
+fn label_setting_with_negative_edges_matches_bellman_ford() {
+    assert_eq!(assert_optimal_with_negative_edges(true), 0);
+}
+
+#[test]
+fn label_correcting_with_negative_edges_matches_bellman_ford() {
+    assert!(assert_optimal_with_negative_edges(false) > 0);
+}
+
+#[test]
+fn infeasible_potential_is_rejected() {
+    assert_eq!(potential::reduced_cost(-3, 0, 0), None);
+    assert_eq!(potential::reduced_cost(-3, 0, -3), Some(Cost::ZERO));
+    assert_eq!(potential::reduced_cost(-3, 5, 0), Some(Cost::from(2)));
+    assert_eq!(potential::signed_cost(Cost::from(2), 5, 0), -3);
+}
+
+#[test]
+fn weighted_a_star_is_bounded() {
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, false);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        a_star.set_lower_bound_weight(1.5);
+        a_star.initialise().unwrap();
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert!(cost >= expected_cost, "seed: {seed}");
+        assert!(
+            cost.as_u64() as f64 <= expected_cost.as_u64() as f64 * 1.5,
+            "seed: {seed}"
+        );
+    }
+}
+
+#[test]
+fn random_tie_breaking_is_optimal_and_reproducible() {
+    for seed in 0..100 {
+        let graph = RandomGraph::new(seed, 30, false);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        let mut paths = Vec::new();
+        for tie_breaking_seed in [1, 2, 1] {
+            a_star.reset();
+            a_star.set_tie_breaking(AStarTieBreaking::Random {
+                seed: tie_breaking_seed,
+            });
+            a_star.initialise().unwrap();
+            let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+                panic!("Target not found for seed {seed}");
+            };
+            assert_eq!(cost, expected_cost, "seed: {seed}");
+            paths.push(a_star.backtrack().unwrap().collect::<Vec<_>>());
+        }
+        assert_eq!(paths[0], paths[2], "seed: {seed}");
+    }
+}
+
+/// Records the identifiers of the closed nodes in closing order.
+struct ClosingOrderObserver(Rc<RefCell<Vec<usize>>>);
+
+impl AStarObserver<Node> for ClosingOrderObserver {
+    fn on_close(&mut self, node: &Node) {
+        self.0.borrow_mut().push(node.identifier);
+    }
+}
+
+#[test]
+fn stable_ties_close_in_insertion_order() {
+    // The root has many successors with the same cost, which all lead to the target.
+    let node_count = 20;
+    let target = node_count - 1;
+    let mut edges = vec![Vec::new(); node_count];
+    edges[0] = (1..target).map(|to| (to, Cost::ZERO)).collect();
+    for edges in &mut edges[1..target] {
+        edges.push((target, Cost::from(1u64)));
+    }
+    let graph = RandomGraph {
+        signed_edges: edges
+            .iter()
+            .map(|edges| {
+                edges
+                    .iter()
+                    .map(|&(to, cost)| (to, cost.as_u64() as i64))
+                    .collect()
+            })
+            .collect(),
+        potentials: vec![0; node_count],
+        distances: (0..node_count)
+            .map(|node| Cost::from(u64::from(node != target)))
+            .collect(),
+        edges,
+        lower_bounds: vec![Cost::ZERO; node_count],
+        is_label_setting: true,
+        max_cost: None,
+    };
+
+    let closing_order = Rc::new(RefCell::new(Vec::new()));
+    let mut a_star = AStar::new(graph);
+    // All nodes have the same secondary score, so only the stable ties order them.
+    a_star.set_tie_breaking(AStarTieBreaking::SecondaryScore);
+    a_star.set_stable_ties(true);
+    a_star.set_observer(Some(Box::new(ClosingOrderObserver(closing_order.clone()))));
+    a_star.initialise().unwrap();
+    assert!(matches!(
+        a_star.search().unwrap(),
+        AStarResult::FoundTarget { .. }
+    ));
+
+    assert_eq!(*closing_order.borrow(), (0..=target).collect::<Vec<_>>());
+}
+
+#[test]
+fn ida_star_matches_bellman_ford() {
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, false);
+        let expected_cost = graph.bellman_ford();
+
+        let mut ida_star = IdaStar::new(graph);
+        let AStarResult::FoundTarget { cost, .. } = ida_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+        assert!(ida_star.backtrack().unwrap().count() > 0, "seed: {seed}");
+    }
+}
+
+#[test]
+fn search_phases_are_checked() {
+    let mut a_star = AStar::new(RandomGraph::new(0, 30, true));
+    assert_eq!(a_star.search(), Err(AStarStateError::NotInitialised));
+    assert_eq!(a_star.backtrack().err(), Some(AStarStateError::NoTarget));
+    assert!(matches!(
+        a_star.initialise().unwrap().search(),
+        Ok(AStarResult::FoundTarget { .. })
+    ));
+    assert!(a_star.backtrack().unwrap().count() > 0);
+    assert_eq!(a_star.initialise().err(), Some(AStarStateError::NotReset));
+    a_star.reset();
+    assert!(a_star.initialise().is_ok());
+
+    let mut ida_star = IdaStar::new(RandomGraph::new(0, 30, false));
+    assert_eq!(ida_star.backtrack().err(), Some(AStarStateError::NoTarget));
+    assert!(ida_star.search().is_ok());
+    assert_eq!(ida_star.search(), Err(AStarStateError::NotReset));
+}
+
+#[test]
+fn typed_search_phases() {
+    for seed in 0..50 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+        let mut a_star = AStar::new(graph);
+        let AStarResult::FoundTarget { cost, .. } = a_star.initialise().unwrap().search().unwrap()
+        else {
+            panic!("Target not found for seed {seed}");
+        };
+        let expected_path: Vec<_> = a_star.backtrack_with_costs().unwrap().collect();
+
+        // Initialising again resets the search first.
+        let SearchOutcome::FoundTarget(target) = a_star.into_initialised().search() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(target.cost(), cost, "seed: {seed}");
+        assert_eq!(target.cost(), expected_cost, "seed: {seed}");
+        assert_eq!(
+            target.backtrack_with_costs().collect::<Vec<_>>(),
+            expected_path,
+            "seed: {seed}"
+        );
+    }
+
+    let mut graph = RandomGraph::new(0, 30, true);
+    graph.max_cost = Some(Cost::ZERO);
+    let SearchOutcome::Terminated { result, .. } = AStar::new(graph).into_initialised().search()
+    else {
+        panic!("Found a target despite the maximum cost");
+    };
+    assert!(matches!(result, AStarResult::NoTarget { .. }));
+}
+
+#[test]
+fn composed_heuristics_are_optimal() {
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+        let heuristic = CachedHeuristic::new(MaxHeuristic::new(
+            FractionHeuristic {
+                distances: graph.distances.clone(),
+                percent: 30,
+            },
+            FractionHeuristic {
+                distances: graph.distances.clone(),
+                percent: 60,
+            },
+        ));
+
+        let mut a_star = AStar::new(HeuristicContext::new(graph, heuristic));
+        a_star.initialise().unwrap();
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+        let heuristic = a_star.context().heuristic();
+        assert!(heuristic.cache_size() > 0);
+        let (hits, misses) = heuristic.cache_hits_and_misses();
+        assert_eq!(misses, heuristic.cache_size());
+        assert!(hits + misses >= a_star.performance_counters().opened_nodes);
+    }
+}
+
+#[test]
+fn memory_limit_is_respected() {
+    let mut exceeded_count = 0;
+
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        assert!(matches!(
+            a_star.search().unwrap(),
+            AStarResult::FoundTarget { .. }
+        ));
+        let memory_limit = a_star.memory_usage() - 1;
+
+        // The memory usage is only checked before closing a node, so the last closed nodes may still exceed the limit.
+        let mut a_star = AStar::new(a_star.into_context());
+        a_star.set_memory_limit(Some(memory_limit));
+        a_star.initialise().unwrap();
+        match a_star.search().unwrap() {
+            AStarResult::FoundTarget { cost, .. } => assert_eq!(cost, expected_cost),
+            AStarResult::ExceededMemoryLimit { memory_usage } => {
+                assert!(memory_usage > memory_limit, "seed: {seed}");
+                exceeded_count += 1;
+            }
+            AStarResult::NoTarget { .. }
+            | AStarResult::ExceededTimeLimit { .. }
+            | AStarResult::Cancelled { .. } => {
+                panic!("Target not found for seed {seed}")
+            }
+        }
+    }
+
+    assert!(exceeded_count > 0);
+}
+
+#[test]
+fn time_limit_stops_and_resumes_search() {
+    for seed in 0..50 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        a_star.set_time_limit(Some(Duration::ZERO));
+        a_star.initialise().unwrap();
+        let AStarResult::ExceededTimeLimit { lower_bound } = a_star.search().unwrap() else {
+            panic!("Time limit not exceeded for seed {seed}");
+        };
+        assert!(lower_bound <= expected_cost, "seed: {seed}");
+        assert_eq!(a_star.performance_counters().closed_nodes, 0);
+
+        // The node that would have been closed is kept in the open list, so the search can be resumed.
+        a_star.set_time_limit(None);
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+    }
+}
+
+#[test]
+fn cancellation_stops_and_resumes_search() {
+    for seed in 0..50 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let mut a_star = AStar::new(graph);
+        a_star.set_cancellation_token(Some(cancellation_token));
+        a_star.initialise().unwrap();
+        let AStarResult::Cancelled { lower_bound } = a_star.search().unwrap() else {
+            panic!("Search not cancelled for seed {seed}");
+        };
+        assert!(lower_bound <= expected_cost, "seed: {seed}");
+        assert_eq!(a_star.performance_counters().closed_nodes, 0);
+
+        a_star.set_cancellation_token(Some(CancellationToken::new()));
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+    }
+}
+
+#[test]
+fn resume_after_increasing_max_cost() {
+    for seed in 0..100 {
+        for is_label_setting in [true, false] {
+            let mut graph = RandomGraph::new(seed, 30, is_label_setting);
+            let expected_cost = graph.bellman_ford();
+            graph.max_cost = Some(Cost::from(expected_cost.as_u64() / 2));
+
+            let mut a_star = AStar::new(graph);
+            a_star.initialise().unwrap();
+            assert!(
+                matches!(a_star.search().unwrap(), AStarResult::NoTarget { .. }),
+                "seed: {seed}"
+            );
+            let closed_nodes = a_star.performance_counters().closed_nodes;
+
+            a_star
+                .resume_after_context_change(ContextChange::IncreasedMaxCost, |graph| {
+                    graph.max_cost = Some(expected_cost)
+                })
+                .unwrap();
+            let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+                panic!("Target not found for seed {seed}");
+            };
+            assert_eq!(cost, expected_cost, "seed: {seed}");
+            assert!(a_star.performance_counters().closed_nodes > closed_nodes);
+        }
+    }
+}
+
+#[test]
+fn exponential_cost_limit_ramp_finds_optimum() {
+    for seed in 0..100 {
+        let graph = RandomGraph::new(seed, 30, true);
+        let expected_cost = graph.bellman_ford();
+
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        let CostLimitRampResult { result, iterations } = a_star
+            .search_with_cost_limit_ramp(
+                &CostLimitRamp::Exponential {
+                    initial: Cost::ZERO,
+                    factor: 2,
+                },
+                |graph, cost_limit| graph.max_cost = Some(cost_limit),
+            )
+            .unwrap();
+        let AStarResult::FoundTarget { cost, .. } = result else {
+            panic!("Target not found for seed {seed}");
+        };
+        assert_eq!(cost, expected_cost, "seed: {seed}");
+
+        let cost_limits: Vec<_> = iterations
+            .iter()
+            .map(|iteration| iteration.cost_limit.as_u64())
+            .collect();
+        assert_eq!(cost_limits[..2], [0, 1], "seed: {seed}");
+        assert!(cost_limits.windows(2).skip(1).all(|w| w[1] == 2 * w[0]));
+        assert!(*cost_limits.last().unwrap() >= expected_cost.as_u64());
+        assert!(cost_limits[cost_limits.len() - 2] < expected_cost.as_u64());
+        assert_eq!(
+            iterations
+                .iter()
+                .map(|iteration| iteration.closed_nodes)
+                .sum::<usize>(),
+            a_star.performance_counters().closed_nodes
+        );
+    }
+}
+
+#[test]
+fn scheduled_cost_limit_ramp_stops_without_target() {
+    let graph = RandomGraph::new(0, 30, true);
+    let expected_cost = graph.bellman_ford().as_u64();
+
+    let mut a_star = AStar::new(graph);
+    a_star.initialise().unwrap();
+    let schedule = vec![
+        Cost::from(expected_cost / 4),
+        Cost::from(expected_cost / 2),
+        Cost::from(expected_cost - 1),
+    ];
+    let CostLimitRampResult { result, iterations } = a_star
+        .search_with_cost_limit_ramp(
+            &CostLimitRamp::Schedule(schedule.clone()),
+            |graph, cost_limit| graph.max_cost = Some(cost_limit),
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        AStarResult::NoTarget {
+            max_cost: Some(Cost::from(expected_cost - 1))
+        }
+    );
+    assert_eq!(
+        iterations
+            .iter()
+            .map(|iteration| iteration.cost_limit)
+            .collect::<Vec<_>>(),
+        schedule
+    );
+}
+
+#[test]
+fn resume_after_relaxing_context() {
+    for seed in 0..100 {
+        for is_label_setting in [true, false] {
+            let expected_cost = RandomGraph::new(seed, 30, is_label_setting).bellman_ford();
+
+            // Remove all but the first edge of each node, which keeps the target reachable.
+            let mut graph = RandomGraph::new(seed, 30, is_label_setting);
+            let removed_edges: Vec<_> = graph
+                .edges
+                .iter_mut()
+                .map(|edges| edges.split_off(edges.len().min(1)))
+                .collect();
+            let restricted_cost = graph.bellman_ford();
+
+            let mut a_star = AStar::new(graph);
+            a_star.initialise().unwrap();
+            let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+                panic!("Target not found for seed {seed}");
+            };
+            assert_eq!(cost, restricted_cost, "seed: {seed}");
+
+            a_star
+                .resume_after_context_change(ContextChange::Relaxed, |graph| {
+                    for (edges, removed_edges) in graph.edges.iter_mut().zip(removed_edges) {
+                        edges.extend(removed_edges);
+                    }
+                })
+                .unwrap();
+            let cost = match a_star.search().unwrap() {
+                AStarResult::FoundTarget { cost, .. } => cost,
+                // The target is not found again if it cannot become cheaper.
+                AStarResult::NoTarget { .. } => restricted_cost,
+                result => panic!("Unexpected result {result:?} for seed {seed}"),
+            };
+            assert_eq!(cost, expected_cost, "seed: {seed}");
+        }
+    }
+}
+
+#[test]
+fn snapshots_count_open_and_closed_nodes() {
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, true);
+
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        assert!(matches!(
+            a_star.search().unwrap(),
+            AStarResult::FoundTarget { .. }
+        ));
+        let counters = a_star.performance_counters();
+
+        let (last, rest) = counters.snapshots.split_last().unwrap();
+        assert!(rest
+            .iter()
+            .all(|snapshot| snapshot.closed_nodes.is_power_of_two()));
+        assert_eq!(last.closed_nodes, counters.closed_nodes, "seed: {seed}");
+        assert_eq!(counters.peak_closed_nodes, counters.closed_nodes);
+        assert!(counters.peak_open_nodes >= last.open_f_values.total());
+        assert!(counters.peak_memory_usage >= a_star.memory_usage());
+        assert_eq!(last.closed_depths.total(), counters.closed_nodes);
+        assert_eq!(last.closed_depths.buckets.first(), Some(&(0, 1)));
+        assert_eq!(
+            last.open_f_values.total(),
+            1 + counters.opened_nodes - counters.closed_nodes - counters.suboptimal_opened_nodes,
+            "seed: {seed}"
+        );
+    }
+}
+
+#[derive(Default)]
+struct ObservedCounts {
+    opened: usize,
+    closed: usize,
+    skipped: usize,
+    targets: Vec<usize>,
+}
+
+struct CountingObserver(Rc<RefCell<ObservedCounts>>);
+
+impl AStarObserver<Node> for CountingObserver {
+    fn on_open(&mut self, _node: &Node) {
+        self.0.borrow_mut().opened += 1;
+    }
+
+    fn on_close(&mut self, _node: &Node) {
+        self.0.borrow_mut().closed += 1;
+    }
+
+    fn on_skip_suboptimal(&mut self, _node: &Node) {
+        self.0.borrow_mut().skipped += 1;
+    }
+
+    fn on_target_found(&mut self, node: &Node) {
+        self.0.borrow_mut().targets.push(node.identifier);
+    }
+}
+
+#[test]
+fn observer_matches_performance_counters() {
+    for seed in 0..200 {
+        let graph = RandomGraph::new(seed, 30, false);
+        let counts = Rc::new(RefCell::new(ObservedCounts::default()));
+
+        let mut a_star = AStar::new(graph);
+        a_star.set_observer(Some(Box::new(CountingObserver(counts.clone()))));
+        a_star.initialise().unwrap();
+        assert!(matches!(
+            a_star.search().unwrap(),
+            AStarResult::FoundTarget { .. }
+        ));
+
+        let counters = a_star.performance_counters();
+        let counts = counts.borrow();
+        // The root node is opened, but not counted as opened node.
+        assert_eq!(counts.opened, counters.opened_nodes + 1, "seed: {seed}");
+        assert_eq!(counts.closed, counters.closed_nodes, "seed: {seed}");
+        assert_eq!(
+            counts.skipped, counters.suboptimal_opened_nodes,
+            "seed: {seed}"
+        );
+        assert_eq!(counts.targets, [29], "seed: {seed}");
+    }
+}
+
+#[test]
+fn admissibility_violations() {
+    for seed in 0..50 {
+        let graph = RandomGraph::new(seed, 30, false);
+        let mut a_star = AStar::new(graph);
+        a_star.initialise().unwrap();
+        let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+            panic!("seed: {seed}");
+        };
+        assert_eq!(a_star.admissibility_violations(cost), [], "seed: {seed}");
+    }
+
+    // Overestimating the lower bound of the root makes the root itself a violation.
+    let mut graph = RandomGraph::new(0, 30, false);
+    let optimal_cost = graph.bellman_ford();
+    graph.lower_bounds[0] = optimal_cost + Cost::from(1);
+    let mut a_star = AStar::new(graph);
+    a_star.initialise().unwrap();
+    a_star.search().unwrap();
+    let violations = a_star.admissibility_violations(optimal_cost);
+    assert_eq!(violations[0].identifier, 0);
+    assert_eq!(violations[0].path, [0]);
+    assert!(violations
+        .iter()
+        .all(|violation| violation.path.first() == Some(&0)
+            && violation.path.last() == Some(&violation.identifier)));
+}
+
+#[test]
+fn traces_replay_and_prove_admissibility() {
+    for is_label_setting in [true, false] {
+        for seed in 0..50 {
+            let search = |graph: RandomGraph| {
+                let recorder = AStarTraceRecorder::new();
+                let mut a_star = AStar::new(graph);
+                a_star.set_observer(Some(Box::new(recorder.clone())));
+                a_star.initialise().unwrap();
+                assert!(matches!(
+                    a_star.search().unwrap(),
+                    AStarResult::FoundTarget { .. }
+                ));
+                recorder.take_trace()
+            };
+
+            let trace = search(RandomGraph::new(seed, 30, is_label_setting));
+            assert_eq!(trace.check(), Ok(()), "seed: {seed}");
+
+            let mut written = Vec::new();
+            trace.write(&mut written).unwrap();
+            let read = AStarTrace::read(written.as_slice()).unwrap();
+            assert_eq!(read, trace, "seed: {seed}");
+
+            // Searching again replays the same trace.
+            let replayed = search(RandomGraph::new(seed, 30, is_label_setting));
+            assert_eq!(replayed.first_divergence(&trace), None, "seed: {seed}");
+        }
+    }
+
+    // A lower bound above the distance to the target is caught on the path to the target.
+    let mut graph = RandomGraph::new(0, 30, false);
+    graph.lower_bounds[0] = graph.distances[0] + Cost::from(1);
+    let recorder = AStarTraceRecorder::new();
+    let mut a_star = AStar::new(graph);
+    a_star.set_observer(Some(Box::new(recorder.clone())));
+    a_star.initialise().unwrap();
+    a_star.search().unwrap();
+    let trace = recorder.take_trace();
+    assert!(
+        matches!(trace.check(), Err(AStarTraceViolation::Inadmissible { .. })),
+        "{:?}",
+        trace.check()
+    );
+
+    let mut shortened = trace.clone();
+    shortened.events.pop();
+    assert_eq!(
+        shortened.first_divergence(&trace),
+        Some(trace.events.len() - 1)
+    );
+}
+
+#[test]
+fn cooptimal_paths_match_dynamic_programming() {
+    let mut ambiguous_count = 0;
+
+    for is_label_setting in [true, false] {
+        for seed in 0..200 {
+            let graph = RandomGraph::new(seed, 30, is_label_setting);
+
+            // The nodes are topologically sorted by their index.
+            let mut distances = vec![Cost::MAX; graph.edges.len()];
+            let mut path_counts = vec![0; graph.edges.len()];
+            distances[0] = Cost::ZERO;
+            path_counts[0] = 1;
+            for (from, edges) in graph.edges.iter().enumerate() {
+                for &(to, cost) in edges {
+                    match (distances[from] + cost).cmp(&distances[to]) {
+                        Ordering::Less => {
+                            distances[to] = distances[from] + cost;
+                            path_counts[to] = path_counts[from];
+                        }
+                        Ordering::Equal => path_counts[to] += path_counts[from],
+                        Ordering::Greater => {}
+                    }
+                }
+            }
+            let expected_count = *path_counts.last().unwrap();
+
+            let mut a_star = AStar::new(graph);
+            a_star.initialise().unwrap();
+            let AStarResult::FoundTarget { cost, .. } = a_star.search().unwrap() else {
+                panic!("Target not found for seed {seed}");
+            };
+            let cooptimal_paths = a_star.cooptimal_paths(3).unwrap();
+
+            assert_eq!(cooptimal_paths.count, expected_count, "seed: {seed}");
+            assert_eq!(
+                cooptimal_paths.paths.len(),
+                expected_count.min(3),
+                "seed: {seed}"
+            );
+            assert_eq!(
+                a_star
+                    .backtrack_with_costs()
+                    .unwrap()
+                    .next()
+                    .map(|(_, cost)| cost),
+                Some(cost),
+                "seed: {seed}"
+            );
+            if cooptimal_paths.is_ambiguous() {
+                ambiguous_count += 1;
+            }
+        }
+    }
+
+    assert!(ambiguous_count > 0);
+}
+
+/// The identifiers of the nodes on the path from the root to the given closed node.
+fn path_to(a_star: &AStar<RandomGraph>, identifier: usize) -> Vec<usize> {
+    let mut path = vec![identifier];
+    while let Some(&predecessor) = a_star
+        .closed_node(path.last().unwrap())
+        .unwrap()
+        .predecessor()
+    {
+        path.push(predecessor);
+    }
+    path.reverse();
+    path
+}
+
+#[test]
+fn streamed_prefixes_are_prefixes_of_the_result_path() {
+    let mut streamed_prefixes = 0;
+
+    for seed in 0..100 {
+        for (interval, bounded) in [(1, false), (3, false), (1, true), (3, true)] {
+            let graph = RandomGraph::new(seed, 30, true);
+            let expected_cost = graph.bellman_ford();
+            let cost_upper_bound = bounded.then_some(expected_cost);
+            let mut a_star = AStar::new(graph);
+            a_star.initialise().unwrap();
+            let mut prefixes = Vec::new();
+            let AStarResult::FoundTarget { identifier, cost } = a_star
+                .search_streaming(interval, cost_upper_bound, |a_star, prefix| {
+                    prefixes.push(path_to(a_star, *prefix))
+                })
+                .unwrap()
+            else {
+                panic!("Target not found for seed {seed}");
+            };
+            assert_eq!(cost, expected_cost, "seed: {seed}");
+
+            let path = path_to(&a_star, identifier);
+            assert_eq!(prefixes.last(), Some(&path), "seed: {seed}");
+            for (previous, prefix) in prefixes.iter().zip(prefixes.iter().skip(1)) {
+                assert!(previous.len() < prefix.len(), "seed: {seed}");
+                assert!(prefix.starts_with(previous), "seed: {seed}");
+            }
+            assert!(prefixes.iter().all(|prefix| path.starts_with(prefix)));
+            streamed_prefixes += prefixes.len() - 1;
+        }
+    }
+
+    // The prefix should become fixed before the search terminates at least sometimes.
+    assert!(streamed_prefixes > 0);
+}
+
+#[test]
+fn label_correcting_searches_have_no_fixed_prefix() {
+    let graph = RandomGraph::new(0, 30, false);
+    let expected_cost = graph.bellman_ford();
+    let mut a_star = AStar::new(graph);
+    a_star.initialise().unwrap();
+    let mut prefixes = 0;
+    let AStarResult::FoundTarget { cost, .. } = a_star
+        .search_streaming(1, Some(expected_cost), |_, _| prefixes += 1)
+        .unwrap()
+    else {
+        panic!("Target not found");
+    };
+    assert_eq!(cost, expected_cost);
+    assert_eq!(prefixes, 0);
+    assert_eq!(a_star.fixed_path_prefix(None), None);
+}
@@ -0,0 +1,251 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use crate::{cost::Cost, observer::AStarObserver, AStarNode};
+
+/// What happened to a node when it was popped from the open list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AStarTraceEventKind {
+    /// The node was closed and its successors were generated.
+    Close,
+    /// The node was dropped because it was closed before with at most the same cost.
+    SkipSuboptimal,
+    /// The node was a target and is closed next.
+    Target,
+}
+
+/// A node popped from the open list, with its identifier and predecessor formatted with [`Debug`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AStarTraceEvent {
+    pub kind: AStarTraceEventKind,
+    pub cost: Cost,
+    pub a_star_lower_bound: Cost,
+    pub identifier: String,
+    pub predecessor: Option<String>,
+}
+
+/// The sequence of nodes popped from the open list during a search.
+///
+/// Since the open list breaks ties deterministically, searching the same input again yields the same trace.
+/// Traces of inputs that triggered bugs in lower bounds can therefore be stored as regression tests:
+/// [`check`](Self::check) proves that the lower bounds were admissible along the path to the target,
+/// and [`first_divergence`](Self::first_divergence) reports where a changed search deviates from the stored trace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AStarTrace {
+    pub events: Vec<AStarTraceEvent>,
+}
+
+/// An inconsistency found by [`AStarTrace::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AStarTraceViolation {
+    /// The trace contains no target event.
+    NoTarget,
+    /// The predecessor of a node on the path to the target was never closed.
+    MissingPredecessor { event_index: usize },
+    /// The lower bound of a node on the path to the target exceeds the remaining cost of the path.
+    Inadmissible {
+        event_index: usize,
+        a_star_lower_bound: Cost,
+        remaining_cost: Cost,
+    },
+}
+
+/// Parsing a trace failed.
+#[derive(Debug)]
+pub enum AStarTraceReadError {
+    Io(std::io::Error),
+    Malformed { line: usize },
+}
+
+/// An [`AStarObserver`] that records an [`AStarTrace`].
+///
+/// The recorder is cheap to clone, and all clones share the same trace,
+/// such that the trace can be taken after the search owns one of the clones.
+#[derive(Debug, Clone, Default)]
+pub struct AStarTraceRecorder {
+    trace: Rc<RefCell<AStarTrace>>,
+}
+
+impl AStarTrace {
+    /// Writes one event per line, with the tab-separated columns kind, cost, lower bound, identifier and predecessor.
+    pub fn write(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for event in &self.events {
+            let kind = match event.kind {
+                AStarTraceEventKind::Close => "close",
+                AStarTraceEventKind::SkipSuboptimal => "skip",
+                AStarTraceEventKind::Target => "target",
+            };
+            writeln!(
+                writer,
+                "{kind}\t{}\t{}\t{}\t{}",
+                event.cost,
+                event.a_star_lower_bound,
+                event.identifier,
+                event.predecessor.as_deref().unwrap_or(""),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a trace in the format written by [`write`](Self::write).
+    pub fn read(reader: impl BufRead) -> Result<Self, AStarTraceReadError> {
+        let mut events = Vec::new();
+
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.map_err(AStarTraceReadError::Io)?;
+            let malformed = || AStarTraceReadError::Malformed {
+                line: line_index + 1,
+            };
+            let [kind, cost, a_star_lower_bound, identifier, predecessor] = line
+                .split('\t')
+                .collect::<Vec<_>>()
+                .try_into()
+                .map_err(|_| malformed())?;
+
+            events.push(AStarTraceEvent {
+                kind: match kind {
+                    "close" => AStarTraceEventKind::Close,
+                    "skip" => AStarTraceEventKind::SkipSuboptimal,
+                    "target" => AStarTraceEventKind::Target,
+                    _ => return Err(malformed()),
+                },
+                cost: cost.parse().map_err(|_| malformed())?,
+                a_star_lower_bound: a_star_lower_bound.parse().map_err(|_| malformed())?,
+                identifier: identifier.to_string(),
+                predecessor: (!predecessor.is_empty()).then(|| predecessor.to_string()),
+            });
+        }
+
+        Ok(Self { events })
+    }
+
+    /// Returns the index of the first event that differs from `other`, or `None` if both traces are equal.
+    ///
+    /// If one trace is a prefix of the other, then the index is the length of the shorter trace.
+    pub fn first_divergence(&self, other: &Self) -> Option<usize> {
+        self.events
+            .iter()
+            .zip(&other.events)
+            .position(|(event, other_event)| event != other_event)
+            .or_else(|| {
+                (self.events.len() != other.events.len())
+                    .then(|| self.events.len().min(other.events.len()))
+            })
+    }
+
+    /// Checks that the lower bound of each node on the path to the first target does not exceed the remaining cost of the path.
+    ///
+    /// If the path is optimal, this proves that the lower bounds were admissible where it mattered for this search.
+    /// Nodes that were closed more than once are taken from their last close before the target.
+    pub fn check(&self) -> Result<(), AStarTraceViolation> {
+        let target_index = self
+            .events
+            .iter()
+            .position(|event| event.kind == AStarTraceEventKind::Target)
+            .ok_or(AStarTraceViolation::NoTarget)?;
+        let target_cost = self.events[target_index].cost;
+
+        let closed: HashMap<_, _> = self.events[..target_index]
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| event.kind == AStarTraceEventKind::Close)
+            .map(|(event_index, event)| (event.identifier.as_str(), event_index))
+            .collect();
+
+        let mut event_index = target_index;
+        loop {
+            let event = &self.events[event_index];
+            let remaining_cost = target_cost - event.cost;
+            if event.a_star_lower_bound > remaining_cost {
+                return Err(AStarTraceViolation::Inadmissible {
+                    event_index,
+                    a_star_lower_bound: event.a_star_lower_bound,
+                    remaining_cost,
+                });
+            }
+
+            let Some(predecessor) = &event.predecessor else {
+                return Ok(());
+            };
+            event_index = *closed
+                .get(predecessor.as_str())
+                .ok_or(AStarTraceViolation::MissingPredecessor { event_index })?;
+        }
+    }
+}
+
+impl AStarTraceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the recorded trace, leaving an empty trace behind.
+    pub fn take_trace(&self) -> AStarTrace {
+        std::mem::take(&mut self.trace.borrow_mut())
+    }
+
+    fn record<Node: AStarNode>(&self, kind: AStarTraceEventKind, node: &Node) {
+        self.trace.borrow_mut().events.push(AStarTraceEvent {
+            kind,
+            cost: node.cost(),
+            a_star_lower_bound: node.a_star_lower_bound(),
+            identifier: format!("{:?}", node.identifier()),
+            predecessor: node
+                .predecessor()
+                .map(|predecessor| format!("{predecessor:?}")),
+        });
+    }
+}
+
+impl<Node: AStarNode> AStarObserver<Node> for AStarTraceRecorder {
+    fn on_close(&mut self, node: &Node) {
+        self.record(AStarTraceEventKind::Close, node);
+    }
+
+    fn on_skip_suboptimal(&mut self, node: &Node) {
+        self.record(AStarTraceEventKind::SkipSuboptimal, node);
+    }
+
+    fn on_target_found(&mut self, node: &Node) {
+        self.record(AStarTraceEventKind::Target, node);
+    }
+}
+
+impl Display for AStarTraceViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoTarget => write!(f, "the trace contains no target"),
+            Self::MissingPredecessor { event_index } => write!(
+                f,
+                "the predecessor of event {event_index} on the path to the target was never closed"
+            ),
+            Self::Inadmissible {
+                event_index,
+                a_star_lower_bound,
+                remaining_cost,
+            } => write!(
+                f,
+                "the lower bound {a_star_lower_bound} of event {event_index} exceeds the remaining cost {remaining_cost} of the path to the target"
+            ),
+        }
+    }
+}
+
+impl Display for AStarTraceReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "IO error: {error}"),
+            Self::Malformed { line } => write!(f, "malformed trace in line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for AStarTraceViolation {}
+
+impl std::error::Error for AStarTraceReadError {}
@@ -1,25 +1,70 @@
-use std::{fmt::Debug, time::Instant};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display},
+    time::{Duration, Instant},
+};
 
-use alignment_result::{AlignmentResult, IAlignmentType};
+use alignment_result::{
+    alignment_cigar, AlignmentResult, AlignmentStatistics, CostSegment, IAlignmentType,
+    NoTargetReason,
+};
 use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
-use generic_a_star::{cost::Cost, AStar, AStarContext, AStarNode, AStarResult};
+use generic_a_star::{
+    cancellation::CancellationToken,
+    cost::Cost,
+    heuristic::{AStarNodeWithLowerBound, Heuristic, HeuristicContext},
+    ida_star::IdaStar,
+    AStar, AStarContext, AStarNode, AStarPerformanceCounters, AStarResult,
+};
+use log::{debug, info};
+use memory_estimate::MemoryEstimate;
+use noisy_float::types::R64;
 use template_switch_distance::{
     context::Memory,
     strategies::{
-        chaining::ChainingStrategy, primary_match::AllowPrimaryMatchStrategy,
-        shortcut::NoShortcutStrategy, template_switch_count::NoTemplateSwitchCountStrategy,
+        chaining::{ChainingStrategy, CHAINING_BLOCK_SIZE},
+        node_ord::NodeOrdStrategy,
+        primary_match::AllowPrimaryMatchStrategy,
+        shortcut::{NoShortcutStrategy, ShortcutStrategy},
+        template_switch_count::{NoTemplateSwitchCountStrategy, TemplateSwitchCountStrategy},
         AlignmentStrategySelector,
     },
 };
 use traitsequence::interface::Sequence;
 
-use crate::config;
+use crate::{
+    config,
+    error::{Error, Result},
+    soft_mask::SoftMasks,
+};
+
+pub use buffer_pool::BufferPool;
+pub use configurable_a_star_align::{Aligner, AlignerBuilder, BuiltStrategies, SearchAlgorithm};
+pub use generic_a_star::comparator::AStarTieBreaking;
 
 pub mod alignment_result;
+pub mod buffer_pool;
+pub mod configurable_a_star_align;
+pub mod fast_path;
 pub mod gap_affine_edit_distance;
+pub mod memory_estimate;
+#[cfg(all(test, feature = "proptest"))]
+mod property_tests;
 pub mod template_switch_distance;
 #[cfg(test)]
 mod tests;
+pub mod trim;
+
+/// The log target of the events that mark the end of a phase of an alignment.
+///
+/// Each event carries the name of the phase under the key `phase` and its duration in seconds under the key `seconds`,
+/// as well as counters that are specific to the phase.
+pub const PHASE_LOG_TARGET: &str = "tsalign::phase";
+
+/// The number of closed nodes between two computations of the fixed prefix of the alignment when streaming alignment segments.
+///
+/// Tests use a small interval, such that short alignments are streamed in multiple segments.
+const STREAMING_INTERVAL: usize = if cfg!(test) { 4 } else { 1 << 16 };
 
 pub trait AlignmentContext: AStarContext {
     type AlphabetType: Alphabet;
@@ -33,78 +78,786 @@ pub trait AlignmentContext: AStarContext {
     fn reference(&self) -> &Self::SubsequenceType;
 
     fn query(&self) -> &Self::SubsequenceType;
+
+    /// Add statistics that are specific to this context to the statistics of an alignment.
+    fn update_statistics(&self, _statistics: &mut AlignmentStatistics) {}
+
+    /// Add statistics that compare an alignment to alignments of the same cost.
+    fn update_cooptimal_statistics(
+        &self,
+        _alignment: &[(usize, Self::AlignmentType)],
+        _cooptimal_alignments: &[Vec<(usize, Self::AlignmentType)>],
+        _statistics: &mut AlignmentStatistics,
+    ) {
+    }
 }
 
+impl<Context: AlignmentContext, ContextHeuristic: Heuristic<Context::Node>> AlignmentContext
+    for HeuristicContext<Context, ContextHeuristic>
+where
+    Context::Node: AStarNodeWithLowerBound,
+{
+    type AlphabetType = Context::AlphabetType;
+
+    type AlignmentType = Context::AlignmentType;
+
+    type SubsequenceType = Context::SubsequenceType;
+
+    fn reference(&self) -> &Self::SubsequenceType {
+        self.context().reference()
+    }
+
+    fn query(&self) -> &Self::SubsequenceType {
+        self.context().query()
+    }
+
+    fn update_statistics(&self, statistics: &mut AlignmentStatistics) {
+        self.context().update_statistics(statistics);
+        let (hits, misses) = self.heuristic().cache_hits_and_misses();
+        statistics.lower_bound_cache_hits = hits as u64;
+        statistics.lower_bound_cache_misses = misses as u64;
+    }
+
+    fn update_cooptimal_statistics(
+        &self,
+        alignment: &[(usize, Self::AlignmentType)],
+        cooptimal_alignments: &[Vec<(usize, Self::AlignmentType)>],
+        statistics: &mut AlignmentStatistics,
+    ) {
+        self.context()
+            .update_cooptimal_statistics(alignment, cooptimal_alignments, statistics);
+    }
+}
+
+/// Repeat an A* search with pseudo-random tie-breaking to sample co-optimal alignments.
+///
+/// Restart `i` uses [`AStarTieBreaking::Random`] with seed `seed + i`, so the sampled alignments are reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomRestarts {
+    pub count: usize,
+    pub seed: u64,
+}
+
+/// The settings of an A* alignment search that do not depend on the alignment graph.
+#[derive(Debug, Clone, Default)]
+pub struct SearchSettings {
+    /// The maximum cost of the alignment, see [`AStarContext::max_cost`].
+    pub max_cost: Option<Cost>,
+    pub tie_breaking: AStarTieBreaking,
+    pub memory_limit: Option<usize>,
+    /// If the time limit is exceeded, then an alignment result without target is returned,
+    /// whose cost is a lower bound of the optimal cost.
+    pub time_limit: Option<Duration>,
+    /// If the search is cancelled, then an alignment result without target is returned, like for the time limit.
+    pub cancellation_token: Option<CancellationToken>,
+    /// Enumerate up to this many paths with the same cost as the result,
+    /// and count the distinct alignments among them and compare them to the result.
+    pub cooptimal_limit: Option<usize>,
+    /// Compare the alignments found by random restarts to the result as well.
+    pub random_restarts: Option<RandomRestarts>,
+    /// Return an error if a node was closed with a cost plus lower bound above the cost of the result,
+    /// see [`AStar::admissibility_violations`].
+    pub check_admissibility: bool,
+    /// Close open nodes that are tied under the [`tie_breaking`](Self::tie_breaking) policy in insertion order,
+    /// see [`AStar::set_stable_ties`].
+    ///
+    /// Together with the fixed hashers and the sorted chaining anchors used by the aligners,
+    /// this makes the alignment a function of the input and the settings alone.
+    pub deterministic: bool,
+}
+
+/// Align with A* according to the `settings`, where the maximum cost is already part of the context.
+///
+/// If a `segment_stream` is given, then its callback is called with the consecutive segments of the alignment
+/// as soon as they are fixed during the search, see [`AStar::search_streaming`].
 fn a_star_align<Context: AStarContext + AlignmentContext>(
-    context: Context,
-) -> AlignmentResult<Context::AlignmentType>
+    a_star: &mut AStar<Context>,
+    settings: &SearchSettings,
+    segment_stream: Option<AlignmentSegmentStream<Context::AlignmentType>>,
+) -> Result<AlignmentResult<Context::AlignmentType>>
 where
-    <Context::Node as AStarNode>::EdgeType: IAlignmentType,
+    <Context::Node as AStarNode>::EdgeType: IAlignmentType + Clone,
+    Context::AlignmentType: Display,
 {
     let start_time = Instant::now();
+    let SearchSettings {
+        max_cost: _,
+        tie_breaking,
+        memory_limit,
+        time_limit,
+        ref cancellation_token,
+        cooptimal_limit,
+        random_restarts,
+        check_admissibility,
+        deterministic,
+    } = *settings;
 
     // Perform forwards search.
-    let mut a_star = AStar::new(context);
-    a_star.initialise();
-    let (cost, has_target) = match a_star.search() {
-        AStarResult::FoundTarget { cost, .. } => (cost, true),
+    a_star.set_tie_breaking(tie_breaking);
+    a_star.set_stable_ties(deterministic);
+    a_star.set_memory_limit(memory_limit);
+    a_star.set_time_limit(time_limit);
+    a_star.set_cancellation_token(cancellation_token.clone());
+    a_star.initialise()?;
+    let search_result = if let Some(AlignmentSegmentStream {
+        cost_upper_bound,
+        on_alignment_segment,
+    }) = segment_stream
+    {
+        let mut streamed_edges = 0;
+        let search_result =
+            a_star.search_streaming(STREAMING_INTERVAL, cost_upper_bound, |a_star, prefix| {
+                stream_alignment_segment(a_star, prefix, &mut streamed_edges, on_alignment_segment)
+            })?;
+        // Label-correcting searches have no fixed prefix, so their alignment is streamed at once.
+        if let AStarResult::FoundTarget { identifier, .. } = &search_result {
+            stream_alignment_segment(
+                a_star,
+                identifier,
+                &mut streamed_edges,
+                on_alignment_segment,
+            );
+        }
+        search_result
+    } else {
+        a_star.search()?
+    };
+    log_search_phase(start_time.elapsed(), a_star.performance_counters(), 1);
+    let (cost, no_target_reason) = match search_result {
+        AStarResult::FoundTarget { cost, .. } => (cost, None),
         AStarResult::NoTarget { max_cost: None } => {
-            unreachable!("The search can always reach a target if cost is unlimited.")
+            return Err(no_alignment_found(a_star.context()))
         }
         AStarResult::NoTarget {
             max_cost: Some(cost),
-        } => (cost, false),
+        } => (cost, Some(NoTargetReason::CostLimit)),
+        AStarResult::ExceededMemoryLimit { memory_usage } => {
+            return Err(Error::MemoryLimitExceeded {
+                memory_limit: memory_limit.unwrap(),
+                memory_usage,
+            })
+        }
+        AStarResult::ExceededTimeLimit { lower_bound } => {
+            info!(
+                "The search exceeded the time limit of {:.3}s, the optimal cost is at least {lower_bound}",
+                time_limit.unwrap().as_secs_f64()
+            );
+            (lower_bound, Some(NoTargetReason::ExceededTimeLimit))
+        }
+        AStarResult::Cancelled { lower_bound } => {
+            info!("The search was cancelled, the optimal cost is at least {lower_bound}");
+            (lower_bound, Some(NoTargetReason::Cancelled))
+        }
     };
+    let has_target = no_target_reason.is_none();
 
-    let mut alignment = Vec::new();
+    if check_admissibility && has_target {
+        let violations = a_star.admissibility_violations(cost);
+        for violation in &violations {
+            debug!(
+                "Closed node with cost {} and lower bound {} above the cost {cost} of the target: {:?}",
+                violation.cost, violation.a_star_lower_bound, violation.identifier
+            );
+        }
+        if let Some(violation) = violations.first() {
+            return Err(Error::InadmissibleLowerBound {
+                count: violations.len(),
+                target_cost: cost,
+                path: violation
+                    .path
+                    .iter()
+                    .map(|identifier| a_star.closed_node(identifier).unwrap().to_string())
+                    .collect(),
+            });
+        }
+    }
 
-    if has_target {
-        // Backtrack.
-        for alignment_type in a_star
-            .backtrack()
-            .map(<Context as AlignmentContext>::AlignmentType::from)
-        {
-            if !alignment_type.is_internal() {
-                if let Some((count, previous_alignment_type)) = alignment.last_mut() {
-                    if alignment_type.is_repeated(previous_alignment_type) {
-                        *count += 1;
-                    } else {
-                        alignment.push((1, alignment_type));
-                    }
+    let backtrack_start_time = Instant::now();
+    let alignment = match no_target_reason {
+        None => Ok(compress_alignment(
+            a_star
+                .backtrack()?
+                .map(<Context as AlignmentContext>::AlignmentType::from),
+        )),
+        Some(reason) => Err(reason),
+    };
+    let cost_segments = has_target
+        .then(|| backtrack_cost_segments(a_star))
+        .transpose()?;
+    let column_costs = has_target
+        .then(|| backtrack_column_costs(a_star))
+        .transpose()?;
+    log_backtrack_phase(backtrack_start_time.elapsed(), alignment.as_ref().ok());
+
+    let end_time = Instant::now();
+    let duration = (end_time - start_time).as_secs_f64();
+
+    let mut result = create_alignment_result(
+        alignment,
+        cost,
+        duration,
+        a_star.performance_counters(),
+        a_star.context(),
+    );
+    result.statistics_mut().cost_segments = cost_segments.unwrap_or_default();
+    result.statistics_mut().column_costs = column_costs.unwrap_or_default();
+
+    let AlignmentResult::WithTarget {
+        alignment,
+        statistics,
+    } = &mut result
+    else {
+        return Ok(result);
+    };
+    if cooptimal_limit.is_none() && random_restarts.is_none() {
+        return Ok(result);
+    }
+
+    // Different paths through the alignment graph may describe the same alignment, e.g. if they differ only in flank nodes.
+    let mut cooptimal_alignments = BTreeMap::new();
+    if let Some(cooptimal_limit) = cooptimal_limit {
+        let cooptimal_paths = a_star.cooptimal_paths(cooptimal_limit)?;
+        let path_count = cooptimal_paths.count;
+        cooptimal_alignments.extend(cooptimal_paths.paths.into_iter().map(|path| {
+            let alignment = compress_alignment(
+                path.into_iter()
+                    .map(<Context as AlignmentContext>::AlignmentType::from),
+            );
+            (alignment_cigar(&alignment), alignment)
+        }));
+        debug!(
+            "Found {path_count} co-optimal paths, enumerated {} distinct alignments",
+            cooptimal_alignments.len()
+        );
+    }
+
+    // Restarting discards the closed list, hence this happens after enumerating co-optimal paths.
+    if let Some(RandomRestarts { count, seed }) = random_restarts {
+        let mut restarts = 0;
+        for restart in 0..count {
+            a_star.reset();
+            // The time limit applies to all searches together.
+            a_star.set_time_limit(
+                time_limit.map(|time_limit| time_limit.saturating_sub(start_time.elapsed())),
+            );
+            let restart_seed = seed.wrapping_add(restart as u64);
+            a_star.set_tie_breaking(AStarTieBreaking::Random { seed: restart_seed });
+            match a_star.initialise()?.search()? {
+                AStarResult::FoundTarget {
+                    cost: restart_cost, ..
+                } => debug_assert_eq!(restart_cost, cost),
+                AStarResult::NoTarget { .. } => {
+                    return Err(Error::RandomRestartWithoutTarget {
+                        seed: restart_seed,
+                        cost,
+                    })
+                }
+                AStarResult::ExceededMemoryLimit { memory_usage } => {
+                    return Err(Error::MemoryLimitExceeded {
+                        memory_limit: memory_limit.unwrap(),
+                        memory_usage,
+                    })
+                }
+                AStarResult::ExceededTimeLimit { .. } | AStarResult::Cancelled { .. } => {
+                    debug!("Stopping random restarts after exceeding the time limit or being cancelled");
+                    break;
+                }
+            }
+
+            let alignment = compress_alignment(
+                a_star
+                    .backtrack()?
+                    .map(<Context as AlignmentContext>::AlignmentType::from),
+            );
+            cooptimal_alignments.insert(alignment_cigar(&alignment), alignment);
+            restarts += 1;
+        }
+        debug!(
+            "Sampled co-optimal alignments with {restarts} random restarts, found {} distinct alignments in total",
+            cooptimal_alignments.len()
+        );
+        statistics.a_star_restarts += restarts;
+    }
+
+    let cooptimal_alignments: Vec<_> = cooptimal_alignments.into_values().collect();
+    statistics.cooptimal_alignments = cooptimal_alignments.len() as u64;
+    a_star
+        .context()
+        .update_cooptimal_statistics(alignment, &cooptimal_alignments, statistics);
+
+    Ok(result)
+}
+
+/// A callback that receives consecutive segments of an alignment.
+pub(crate) type AlignmentSegmentCallback<'callback, AlignmentType> =
+    &'callback mut dyn FnMut(&[(usize, AlignmentType)]);
+
+/// The receiver of the segments of an alignment that are fixed during the search.
+pub(crate) struct AlignmentSegmentStream<'callback, AlignmentType> {
+    /// An upper bound of the optimal cost, without which segments are rarely fixed before the search terminates,
+    /// see [`AStar::fixed_path_prefix`].
+    pub cost_upper_bound: Option<Cost>,
+    pub on_alignment_segment: AlignmentSegmentCallback<'callback, AlignmentType>,
+}
+
+/// Calls `on_alignment_segment` with the part of the alignment to the fixed `prefix` node that was not streamed yet,
+/// where `streamed_edges` counts the edges of the path that were streamed before.
+fn stream_alignment_segment<Context: AStarContext + AlignmentContext>(
+    a_star: &AStar<Context>,
+    prefix: &<Context::Node as AStarNode>::Identifier,
+    streamed_edges: &mut usize,
+    on_alignment_segment: AlignmentSegmentCallback<Context::AlignmentType>,
+) {
+    let edges: Vec<_> = a_star.backtrack_from(prefix).unwrap().collect();
+    let new_edges = edges.len() - *streamed_edges;
+    *streamed_edges = edges.len();
+
+    let segment = compress_alignment(
+        edges
+            .into_iter()
+            .take(new_edges)
+            .map(<Context as AlignmentContext>::AlignmentType::from),
+    );
+    if !segment.is_empty() {
+        on_alignment_segment(&segment);
+    }
+}
+
+/// Run weighted A* repeatedly with decreasing lower bound weights until either the weight reaches one or the time limit is exceeded.
+///
+/// Returns the cheapest alignment found, and reports the weight of the last search as suboptimality bound.
+/// The searches after the first are aborted when the `time_limit` is exceeded, and then the weight of the search before is reported.
+/// If the time limit of the `settings` is exceeded or the search is cancelled through their cancellation token, then the search is aborted,
+/// and if no alignment was found yet, an alignment result without target is returned.
+/// Co-optimal alignments, random restarts and admissibility checks are not supported.
+fn anytime_a_star_align<Context: AStarContext + AlignmentContext>(
+    a_star: &mut AStar<Context>,
+    settings: &SearchSettings,
+    initial_lower_bound_weight: f64,
+    time_limit: Duration,
+) -> Result<AlignmentResult<Context::AlignmentType>>
+where
+    <Context::Node as AStarNode>::EdgeType: IAlignmentType,
+{
+    let start_time = Instant::now();
+    let memory_limit = settings.memory_limit;
+    let hard_time_limit = settings.time_limit;
+
+    a_star.set_tie_breaking(settings.tie_breaking);
+    a_star.set_stable_ties(settings.deterministic);
+    a_star.set_memory_limit(memory_limit);
+    a_star.set_cancellation_token(settings.cancellation_token.clone());
+    let mut lower_bound_weight = initial_lower_bound_weight.max(1.0);
+    let mut best_alignment = None;
+    let mut previous_lower_bound_weight = None;
+    let mut no_target = None;
+    let mut performance_counters = AStarPerformanceCounters::default();
+    let mut searches = 0;
+    let mut search_duration = Duration::ZERO;
+    let mut backtrack_duration = Duration::ZERO;
+
+    loop {
+        searches += 1;
+        let search_start_time = Instant::now();
+        a_star.reset();
+        a_star.set_lower_bound_weight(lower_bound_weight);
+        // Refinements stop at the time limit of the anytime search, while the first search may use all of the hard time limit.
+        let search_time_limit = if best_alignment.is_some() {
+            Some(hard_time_limit.map_or(time_limit, |hard_time_limit| {
+                hard_time_limit.min(time_limit)
+            }))
+        } else {
+            hard_time_limit
+        };
+        a_star.set_time_limit(
+            search_time_limit.map(|time_limit| time_limit.saturating_sub(start_time.elapsed())),
+        );
+        let search_result = a_star.initialise()?.search()?;
+        search_duration += search_start_time.elapsed();
+
+        match search_result {
+            AStarResult::FoundTarget { cost, .. } => {
+                debug!(
+                    "Found alignment of cost {cost} with lower bound weight {lower_bound_weight}"
+                );
+                if best_alignment
+                    .as_ref()
+                    .is_none_or(|(best_cost, _, _, _)| cost < *best_cost)
+                {
+                    let backtrack_start_time = Instant::now();
+                    best_alignment = Some((
+                        cost,
+                        compress_alignment(
+                            a_star
+                                .backtrack()?
+                                .map(<Context as AlignmentContext>::AlignmentType::from),
+                        ),
+                        backtrack_cost_segments(a_star)?,
+                        backtrack_column_costs(a_star)?,
+                    ));
+                    backtrack_duration += backtrack_start_time.elapsed();
+                }
+            }
+            AStarResult::NoTarget { max_cost: None } => {
+                return Err(no_alignment_found(a_star.context()))
+            }
+            AStarResult::NoTarget {
+                max_cost: Some(cost),
+            } => no_target = Some((cost, NoTargetReason::CostLimit)),
+            AStarResult::ExceededMemoryLimit { memory_usage } => {
+                let Some(previous_lower_bound_weight) = previous_lower_bound_weight else {
+                    return Err(Error::MemoryLimitExceeded {
+                        memory_limit: memory_limit.unwrap(),
+                        memory_usage,
+                    });
+                };
+
+                // Searches with lower weights close more nodes, so they would exceed the limit as well.
+                debug!("Stopping refinement after exceeding the memory limit with lower bound weight {lower_bound_weight}");
+                lower_bound_weight = previous_lower_bound_weight;
+                break;
+            }
+            AStarResult::ExceededTimeLimit { lower_bound }
+            | AStarResult::Cancelled { lower_bound } => {
+                let reason = if matches!(search_result, AStarResult::Cancelled { .. }) {
+                    info!("The search was cancelled with lower bound weight {lower_bound_weight}");
+                    NoTargetReason::Cancelled
+                } else {
+                    info!(
+                        "The search exceeded the time limit of {:.3}s with lower bound weight {lower_bound_weight}",
+                        search_time_limit.unwrap().as_secs_f64()
+                    );
+                    NoTargetReason::ExceededTimeLimit
+                };
+
+                if let Some(previous_lower_bound_weight) = previous_lower_bound_weight {
+                    lower_bound_weight = previous_lower_bound_weight;
                 } else {
-                    alignment.push((1, alignment_type));
+                    // With a weighted lower bound, the cost of the next node is no lower bound of the optimal cost.
+                    let cost = if lower_bound_weight <= 1.0 {
+                        lower_bound
+                    } else {
+                        Cost::ZERO
+                    };
+                    no_target = Some((cost, reason));
                 }
+                break;
             }
         }
 
-        alignment.reverse();
+        let counters = a_star.performance_counters();
+        performance_counters.opened_nodes += counters.opened_nodes;
+        performance_counters.suboptimal_opened_nodes += counters.suboptimal_opened_nodes;
+        performance_counters.closed_nodes += counters.closed_nodes;
+        performance_counters.reopened_nodes += counters.reopened_nodes;
+        performance_counters.peak_open_nodes = performance_counters
+            .peak_open_nodes
+            .max(counters.peak_open_nodes);
+        performance_counters.peak_closed_nodes = performance_counters
+            .peak_closed_nodes
+            .max(counters.peak_closed_nodes);
+        performance_counters.peak_memory_usage = performance_counters
+            .peak_memory_usage
+            .max(counters.peak_memory_usage);
+        // Snapshots cannot be summed, so only those of the last search are kept.
+        performance_counters.snapshots = counters.snapshots.clone();
+
+        // Weighted A* explores all nodes within the cost limit before giving up, so there is no target.
+        if no_target.is_some() || lower_bound_weight <= 1.0 || start_time.elapsed() >= time_limit {
+            break;
+        }
+
+        previous_lower_bound_weight = Some(lower_bound_weight);
+        lower_bound_weight = 1.0 + (lower_bound_weight - 1.0) / 2.0;
+        if lower_bound_weight < 1.05 {
+            lower_bound_weight = 1.0;
+        }
     }
 
+    log_search_phase(search_duration, &performance_counters, searches);
+    log_backtrack_phase(
+        backtrack_duration,
+        best_alignment.as_ref().map(|(_, alignment, ..)| alignment),
+    );
+
     let end_time = Instant::now();
     let duration = (end_time - start_time).as_secs_f64();
 
-    if has_target {
-        AlignmentResult::new_with_target(
-            alignment,
+    let mut result = if let Some((cost, alignment, cost_segments, column_costs)) = best_alignment {
+        let mut result = create_alignment_result(
+            Ok(alignment),
             cost,
             duration,
-            a_star.performance_counters().opened_nodes,
-            a_star.performance_counters().closed_nodes,
-            a_star.performance_counters().suboptimal_opened_nodes,
-            a_star.context().reference().len(),
-            a_star.context().query().len(),
-        )
+            &performance_counters,
+            a_star.context(),
+        );
+        result.statistics_mut().cost_segments = cost_segments;
+        result.statistics_mut().column_costs = column_costs;
+        result
     } else {
-        AlignmentResult::new_without_target(
+        let (cost, reason) = no_target.unwrap();
+        create_alignment_result(
+            Err(reason),
             cost,
             duration,
-            a_star.performance_counters().opened_nodes,
-            a_star.performance_counters().closed_nodes,
-            a_star.performance_counters().suboptimal_opened_nodes,
-            a_star.context().reference().len(),
-            a_star.context().query().len(),
+            &performance_counters,
+            a_star.context(),
         )
+    };
+    let statistics = result.statistics_mut();
+    statistics.suboptimality_bound = lower_bound_weight.try_into().unwrap();
+    statistics.a_star_restarts = (searches - 1) as u64;
+    Ok(result)
+}
+
+fn ida_star_align<Context: AStarContext + AlignmentContext>(
+    context: Context,
+) -> Result<AlignmentResult<Context::AlignmentType>>
+where
+    <Context::Node as AStarNode>::EdgeType: IAlignmentType,
+{
+    let start_time = Instant::now();
+
+    let mut ida_star = IdaStar::new(context);
+    let search_result = ida_star.search()?;
+    log_search_phase(start_time.elapsed(), ida_star.performance_counters(), 1);
+    let (cost, has_target) = match search_result {
+        AStarResult::FoundTarget { cost, .. } => (cost, true),
+        AStarResult::NoTarget { max_cost: None } => {
+            return Err(no_alignment_found(ida_star.context()))
+        }
+        AStarResult::NoTarget {
+            max_cost: Some(cost),
+        } => (cost, false),
+        AStarResult::ExceededMemoryLimit { .. }
+        | AStarResult::ExceededTimeLimit { .. }
+        | AStarResult::Cancelled { .. } => {
+            unreachable!("IDA* has no memory or time limit and cannot be cancelled")
+        }
+    };
+
+    let backtrack_start_time = Instant::now();
+    let alignment = if has_target {
+        Ok(compress_alignment(
+            ida_star
+                .backtrack()?
+                .map(<Context as AlignmentContext>::AlignmentType::from),
+        ))
+    } else {
+        Err(NoTargetReason::CostLimit)
+    };
+    log_backtrack_phase(backtrack_start_time.elapsed(), alignment.as_ref().ok());
+
+    let end_time = Instant::now();
+    let duration = (end_time - start_time).as_secs_f64();
+
+    Ok(create_alignment_result(
+        alignment,
+        cost,
+        duration,
+        ida_star.performance_counters(),
+        ida_star.context(),
+    ))
+}
+
+fn log_search_phase(
+    duration: Duration,
+    performance_counters: &AStarPerformanceCounters,
+    searches: usize,
+) {
+    info!(
+        target: PHASE_LOG_TARGET,
+        phase = "search",
+        seconds = duration.as_secs_f64(),
+        searches = searches,
+        opened_nodes = performance_counters.opened_nodes,
+        closed_nodes = performance_counters.closed_nodes,
+        peak_memory_bytes = performance_counters.peak_memory_usage;
+        "Searched for {:.3}s, closing {} nodes",
+        duration.as_secs_f64(),
+        performance_counters.closed_nodes,
+    );
+}
+
+fn log_backtrack_phase<AlignmentType>(
+    duration: Duration,
+    alignment: Option<&Vec<(usize, AlignmentType)>>,
+) {
+    let alignment_length = alignment.map_or(0, |alignment| {
+        alignment.iter().map(|(count, _)| count).sum::<usize>()
+    });
+    info!(
+        target: PHASE_LOG_TARGET,
+        phase = "backtrack",
+        seconds = duration.as_secs_f64(),
+        alignment_length = alignment_length;
+        "Backtracked for {:.3}s, yielding {alignment_length} alignment steps",
+        duration.as_secs_f64(),
+    );
+}
+
+/// Split the path to the target found by `a_star` into cost segments.
+fn backtrack_cost_segments<Context: AStarContext + AlignmentContext>(
+    a_star: &AStar<Context>,
+) -> Result<Vec<CostSegment>> {
+    Ok(CostSegment::from_backtrack(
+        a_star
+            .backtrack_with_costs()?
+            .map(|(alignment_type, cost)| {
+                (
+                    <Context as AlignmentContext>::AlignmentType::from(alignment_type),
+                    cost,
+                )
+            }),
+    ))
+}
+
+/// Compute the run-length encoded cost of each column of the path to the target found by `a_star`.
+fn backtrack_column_costs<Context: AStarContext + AlignmentContext>(
+    a_star: &AStar<Context>,
+) -> Result<Vec<(usize, R64)>> {
+    Ok(compress_column_costs(a_star.backtrack_with_costs()?.map(
+        |(alignment_type, cost)| {
+            (
+                <Context as AlignmentContext>::AlignmentType::from(alignment_type),
+                cost,
+            )
+        },
+    )))
+}
+
+/// Convert a backtracked sequence of alignment types and the costs of their target nodes
+/// into the forward run-length encoded costs of the alignment columns.
+///
+/// Runs count as one column per step, and their cost is attributed to their first column.
+pub(crate) fn compress_column_costs<AlignmentType: IAlignmentType>(
+    backtrack: impl Iterator<Item = (AlignmentType, Cost)>,
+) -> Vec<(usize, R64)> {
+    let mut path: Vec<_> = backtrack.collect();
+    path.reverse();
+
+    let mut column_costs = Vec::new();
+
+    let mut previous_cost = Cost::ZERO;
+    let mut pending_cost = 0;
+    for (alignment_type, cost) in path {
+        pending_cost += (cost - previous_cost).as_u64();
+        previous_cost = cost;
+        if alignment_type.is_internal() {
+            continue;
+        }
+
+        let columns = alignment_type.as_run().map_or(1, |(length, _)| length);
+        if columns > 0 {
+            push_column_cost(&mut column_costs, 1, R64::new(pending_cost as f64));
+            pending_cost = 0;
+            push_column_cost(&mut column_costs, columns - 1, R64::new(0.0));
+        }
     }
+
+    // Internal steps at the end of the alignment are attributed to its last column.
+    if pending_cost > 0 {
+        if let Some((count, cost)) = column_costs.last_mut() {
+            let last_cost = *cost + pending_cost as f64;
+            if *count == 1 {
+                *cost = last_cost;
+            } else {
+                *count -= 1;
+                column_costs.push((1, last_cost));
+            }
+        }
+    }
+
+    column_costs
+}
+
+/// Appends `count` columns of the given cost to run-length encoded column costs.
+pub(crate) fn push_column_cost(column_costs: &mut Vec<(usize, R64)>, count: usize, cost: R64) {
+    if count == 0 {
+        return;
+    }
+    if let Some((previous_count, previous_cost)) = column_costs.last_mut() {
+        if *previous_cost == cost {
+            *previous_count += count;
+            return;
+        }
+    }
+    column_costs.push((count, cost));
+}
+
+/// Convert a backtracked sequence of alignment types into a forward run-length encoded alignment.
+pub(crate) fn compress_alignment<AlignmentType: IAlignmentType>(
+    backtrack: impl Iterator<Item = AlignmentType>,
+) -> Vec<(usize, AlignmentType)> {
+    let mut alignment = Vec::new();
+
+    for alignment_type in backtrack {
+        if !alignment_type.is_internal() {
+            let (run_length, alignment_type) =
+                alignment_type.as_run().unwrap_or((1, alignment_type));
+
+            if let Some((count, previous_alignment_type)) = alignment.last_mut() {
+                if alignment_type.is_repeated(previous_alignment_type) {
+                    *count += run_length;
+                } else {
+                    alignment.push((run_length, alignment_type));
+                }
+            } else {
+                alignment.push((run_length, alignment_type));
+            }
+        }
+    }
+
+    alignment.reverse();
+    alignment
+}
+
+/// The search can always reach a target if cost is unlimited, so this indicates a broken search space.
+fn no_alignment_found<Context: AlignmentContext>(context: &Context) -> Error {
+    Error::NoAlignmentFound {
+        reference_length: context.reference().len(),
+        query_length: context.query().len(),
+    }
+}
+
+/// Creates a result with the given alignment, or without target for the given reason.
+fn create_alignment_result<Context: AlignmentContext>(
+    alignment: std::result::Result<Vec<(usize, Context::AlignmentType)>, NoTargetReason>,
+    cost: Cost,
+    duration: f64,
+    performance_counters: &AStarPerformanceCounters,
+    context: &Context,
+) -> AlignmentResult<Context::AlignmentType> {
+    let mut result = match alignment {
+        Ok(alignment) => AlignmentResult::new_with_target(
+            alignment,
+            cost,
+            duration,
+            performance_counters.opened_nodes,
+            performance_counters.closed_nodes,
+            performance_counters.suboptimal_opened_nodes,
+            context.reference().len(),
+            context.query().len(),
+        ),
+        Err(reason) => AlignmentResult::new_without_target(
+            reason,
+            cost,
+            duration,
+            performance_counters.opened_nodes,
+            performance_counters.closed_nodes,
+            performance_counters.suboptimal_opened_nodes,
+            context.reference().len(),
+            context.query().len(),
+        ),
+    };
+
+    let statistics = result.statistics_mut();
+    statistics.peak_open_nodes = performance_counters.peak_open_nodes as u64;
+    statistics.peak_closed_nodes = performance_counters.peak_closed_nodes as u64;
+    statistics.peak_memory_bytes = performance_counters.peak_memory_usage as u64;
+    statistics.search_snapshots = performance_counters.snapshots.clone();
+    context.update_statistics(result.statistics_mut());
+    result
 }
 
 pub fn gap_affine_edit_distance_a_star_align<
@@ -114,14 +867,24 @@ pub fn gap_affine_edit_distance_a_star_align<
     reference: &SubsequenceType,
     query: &SubsequenceType,
     scoring_table: gap_affine_edit_distance::ScoringTable,
-) -> AlignmentResult<gap_affine_edit_distance::AlignmentType> {
-    a_star_align(gap_affine_edit_distance::Context::new(
-        reference,
-        query,
-        scoring_table,
-    ))
+    settings: &SearchSettings,
+) -> Result<AlignmentResult<gap_affine_edit_distance::AlignmentType>> {
+    a_star_align(
+        &mut AStar::new(gap_affine_edit_distance::Context::new(
+            reference,
+            query,
+            scoring_table,
+        )),
+        settings,
+        None,
+    )
 }
 
+/// Align `reference` and `query` with template switches using A* with the given `settings`.
+///
+/// If a [`cooptimal_limit`](SearchSettings::cooptimal_limit) is given, then up to that many paths with the same cost as the result are enumerated.
+/// The number of distinct alignments among them is reported in [`AlignmentStatistics::cooptimal_alignments`],
+/// and the template switches of the result that do not occur in all of them are reported in [`AlignmentStatistics::ambiguous_template_switches`].
 pub fn template_switch_distance_a_star_align<
     Strategies: AlignmentStrategySelector<
         TemplateSwitchCount = NoTemplateSwitchCountStrategy,
@@ -133,18 +896,204 @@ pub fn template_switch_distance_a_star_align<
     reference: &SubsequenceType,
     query: &SubsequenceType,
     config: config::TemplateSwitchConfig<Strategies::Alphabet>,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    soft_masks: Option<SoftMasks>,
+    settings: &SearchSettings,
+) -> Result<AlignmentResult<template_switch_distance::AlignmentType>> {
+    a_star_align(
+        &mut AStar::new(template_switch_distance_context::<Strategies, _>(
+            reference,
+            query,
+            config,
+            settings.max_cost,
+            chaining_selection,
+            soft_masks,
+        )?),
+        settings,
+        None,
+    )
+}
+
+/// Like [`template_switch_distance_a_star_align`], but uses anytime weighted A*.
+///
+/// The first search uses `initial_lower_bound_weight` as weight `w` for the lower bound,
+/// and its result has a cost of at most `w` times the optimal cost.
+/// While the `time_limit` is not exceeded, the search is repeated with decreasing weights,
+/// until the weight reaches one, which produces an optimal alignment.
+/// A repeated search that is still running when the `time_limit` is exceeded is aborted.
+/// The weight of the last completed search is reported as suboptimality bound in the statistics.
+/// Co-optimal alignments, random restarts and admissibility checks are not supported.
+#[expect(clippy::too_many_arguments)]
+pub fn template_switch_distance_anytime_a_star_align<
+    Strategies: AlignmentStrategySelector<
+        TemplateSwitchCount = NoTemplateSwitchCountStrategy,
+        Shortcut = NoShortcutStrategy,
+        PrimaryMatch = AllowPrimaryMatchStrategy,
+    >,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    config: config::TemplateSwitchConfig<Strategies::Alphabet>,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    soft_masks: Option<SoftMasks>,
+    settings: &SearchSettings,
+    initial_lower_bound_weight: f64,
+    time_limit: Duration,
+) -> Result<AlignmentResult<template_switch_distance::AlignmentType>> {
+    anytime_a_star_align(
+        &mut AStar::new(template_switch_distance_context::<Strategies, _>(
+            reference,
+            query,
+            config,
+            settings.max_cost,
+            chaining_selection,
+            soft_masks,
+        )?),
+        settings,
+        initial_lower_bound_weight,
+        time_limit,
+    )
+}
+
+/// Like [`template_switch_distance_a_star_align`], but uses iterative deepening A* (IDA*).
+///
+/// IDA* uses memory only linear in the length of the alignment, but may take exponentially longer than A*.
+pub fn template_switch_distance_ida_star_align<
+    Strategies: AlignmentStrategySelector<
+        TemplateSwitchCount = NoTemplateSwitchCountStrategy,
+        Shortcut = NoShortcutStrategy,
+        PrimaryMatch = AllowPrimaryMatchStrategy,
+    >,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    config: config::TemplateSwitchConfig<Strategies::Alphabet>,
+    max_cost: Option<Cost>,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    soft_masks: Option<SoftMasks>,
+) -> Result<AlignmentResult<template_switch_distance::AlignmentType>> {
+    ida_star_align(template_switch_distance_context::<Strategies, _>(
+        reference,
+        query,
+        config,
+        max_cost,
+        chaining_selection,
+        soft_masks,
+    )?)
+}
+
+/// Estimate the peak memory usage of [`template_switch_distance_a_star_align`] before running it.
+///
+/// The node count is the number of primary nodes of the full alignment matrix, including all gap types.
+/// The search usually opens much fewer primary nodes, but may also open flank and secondary nodes,
+/// hence this is a rough estimate only.
+pub fn template_switch_distance_memory_estimate<Strategies: AlignmentStrategySelector>(
+    reference_length: usize,
+    query_length: usize,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+) -> MemoryEstimate {
+    type Node<Strategies> = template_switch_distance::Node<Strategies>;
+
+    MemoryEstimate {
+        node_count: (reference_length + 1)
+            .saturating_mul(query_length + 1)
+            .saturating_mul(3),
+        // Closed nodes are stored together with their identifier, and the hash map uses one control byte per entry.
+        bytes_per_node: std::mem::size_of::<Node<Strategies>>()
+            + std::mem::size_of::<<Node<Strategies> as AStarNode>::Identifier>()
+            + 1,
+        lower_bound_bytes: <Strategies::Chaining as ChainingStrategy>::estimate_memory(
+            reference_length,
+            query_length,
+            chaining_selection,
+        ),
+    }
+}
+
+fn template_switch_distance_context<
+    'reference,
+    'query,
+    Strategies: AlignmentStrategySelector<
+        TemplateSwitchCount = NoTemplateSwitchCountStrategy,
+        Shortcut = NoShortcutStrategy,
+        PrimaryMatch = AllowPrimaryMatchStrategy,
+    >,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    reference: &'reference SubsequenceType,
+    query: &'query SubsequenceType,
+    config: config::TemplateSwitchConfig<Strategies::Alphabet>,
     max_cost: Option<Cost>,
-) -> AlignmentResult<template_switch_distance::AlignmentType> {
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    soft_masks: Option<SoftMasks>,
+) -> Result<template_switch_distance::Context<'reference, 'query, SubsequenceType, Strategies>> {
+    template_switch_distance_context_with_memory(
+        reference,
+        query,
+        config,
+        max_cost,
+        chaining_selection,
+        Default::default(),
+        (),
+        (),
+        soft_masks,
+    )
+}
+
+/// Like [`template_switch_distance_context`], but with any node ordering, template switch count and shortcut strategy,
+/// whose memory is given by the caller.
+#[expect(clippy::too_many_arguments)]
+fn template_switch_distance_context_with_memory<
+    'reference,
+    'query,
+    Strategies: AlignmentStrategySelector<PrimaryMatch = AllowPrimaryMatchStrategy>,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    reference: &'reference SubsequenceType,
+    query: &'query SubsequenceType,
+    config: config::TemplateSwitchConfig<Strategies::Alphabet>,
+    max_cost: Option<Cost>,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    node_ord: <Strategies::NodeOrd as NodeOrdStrategy<AllowPrimaryMatchStrategy>>::Memory,
+    template_switch_count: <Strategies::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory,
+    shortcut: <Strategies::Shortcut as ShortcutStrategy>::Memory,
+    soft_masks: Option<SoftMasks>,
+) -> Result<template_switch_distance::Context<'reference, 'query, SubsequenceType, Strategies>> {
+    let start_time = Instant::now();
+    for (event, costs) in [
+        ("duplication", &config.duplication_costs),
+        ("inversion", &config.inversion_costs),
+    ] {
+        if let Some(costs) = costs {
+            if costs.minimum_finite_input().is_some() && costs.maximum_finite_input().is_none() {
+                return Err(Error::UnboundedJumpEventLength { event });
+            }
+        }
+    }
+
     let memory = Memory {
+        node_ord,
         template_switch_min_length: Default::default(),
-        chaining: <<Strategies as AlignmentStrategySelector>::Chaining as ChainingStrategy>::initialise_memory(reference, query, &config, 20),
-        template_switch_count: (),
-        shortcut: (),
+        chaining: <<Strategies as AlignmentStrategySelector>::Chaining as ChainingStrategy>::initialise_memory(reference, query, &config, CHAINING_BLOCK_SIZE, chaining_selection)?,
+        template_switch_count,
+        shortcut,
         primary_match:(),
     };
 
-    a_star_align(template_switch_distance::Context::<
-        SubsequenceType,
-        Strategies,
-    >::new(reference, query, config, memory, max_cost))
+    let mut context =
+        template_switch_distance::Context::new(reference, query, config, memory, max_cost);
+    context.set_skip_primary_match_runs(true);
+    context.set_skip_secondary_match_runs(true);
+    context.set_soft_masks(soft_masks);
+
+    let duration = start_time.elapsed().as_secs_f64();
+    info!(
+        target: PHASE_LOG_TARGET,
+        phase = "precompute",
+        seconds = duration;
+        "Precomputed the alignment context for {duration:.3}s"
+    );
+    Ok(context)
 }
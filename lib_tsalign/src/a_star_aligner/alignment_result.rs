@@ -1,9 +1,13 @@
-use std::fmt::{Display, Formatter, Result, Write};
+use std::{
+    fmt::{Display, Formatter, Result, Write},
+    ops::AddAssign,
+};
 
+use generic_a_star::histogram::AStarSearchSnapshot;
 use noisy_float::types::R64;
-use num_traits::{Float, Zero};
+use num_traits::{Float, One, Zero};
 
-use crate::costs::cost::Cost;
+use crate::{costs::cost::Cost, provenance::ResultProvenance};
 
 pub trait IAlignmentType {
     fn is_repeatable(&self) -> bool;
@@ -11,6 +15,166 @@ pub trait IAlignmentType {
     fn is_repeated(&self, previous: &Self) -> bool;
 
     fn is_internal(&self) -> bool;
+
+    /// If this alignment type stands for multiple consecutive alignment steps,
+    /// returns their count and the alignment type of a single step.
+    fn as_run(&self) -> Option<(usize, Self)>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// The region and the kind of the alignment column that a single step of this alignment type produces.
+    ///
+    /// Returns `None` if this alignment type does not produce alignment columns, e.g. for template switch entrances.
+    fn alignment_column(&self) -> Option<(AlignmentRegion, AlignmentColumn)> {
+        None
+    }
+
+    /// The kind of cost segment that this alignment type belongs to.
+    ///
+    /// Returns `None` if this alignment type belongs to the segment before it, e.g. for internal alignment types.
+    fn cost_segment_kind(&self) -> Option<CostSegmentKind> {
+        Some(CostSegmentKind::Primary)
+    }
+}
+
+/// The part of an alignment that an alignment column belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentRegion {
+    /// The reference is aligned to the query, including template switch flanks and inversions.
+    Primary,
+    /// The primary is aligned to the secondary inside a template switch.
+    TemplateSwitch,
+}
+
+/// The kind of a [`CostSegment`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CostSegmentKind {
+    /// The reference is aligned to the query outside of template switch flanks.
+    Primary,
+    /// The reference is aligned to the query inside a template switch flank.
+    Flank,
+    /// The jump from the primary to the secondary.
+    TemplateSwitchEntrance,
+    /// The primary is aligned to the secondary.
+    TemplateSwitch,
+    /// The jump from the secondary back to the anti-primary.
+    TemplateSwitchExit,
+    /// A tandem duplication.
+    Duplication,
+    /// An inversion, including its entrance and exit.
+    Inversion,
+}
+
+/// A maximal part of an alignment whose steps are of the same [`CostSegmentKind`], and the cost it contributes to the alignment.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostSegment {
+    pub kind: CostSegmentKind,
+    /// The index of the template switch that this segment belongs to, if it is an entrance, exit or the inner part of a template switch.
+    pub template_switch: Option<usize>,
+    /// The number of edges of the alignment graph in this segment, where a run of matches counts once per match.
+    pub steps: usize,
+    pub cost: R64,
+}
+
+/// Statistics about the chain of anchors computed by a chaining strategy.
+///
+/// The chain cost is a lower bound of the alignment cost, so comparing them shows how tight the chain is.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainStatistics {
+    /// The number of anchors in the chain.
+    pub anchors: u64,
+    /// The fraction of the reference covered by anchors of the chain.
+    pub reference_coverage: R64,
+    /// The fraction of the query covered by anchors of the chain.
+    pub query_coverage: R64,
+    /// The cost of the chain.
+    pub cost: R64,
+    /// The number of gaps between the anchors of the chain and the ends of the sequences,
+    /// which are filled by the exact alignment.
+    pub gaps: u64,
+    /// The number of reverse-complement anchors of allowed template switch directions
+    /// that reach into a gap of the chain.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub template_switch_anchors: u64,
+    /// The transitions between consecutive anchors of the chain, including the ends of the sequences.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub transitions: Vec<ChainTransitionStatistics>,
+    /// The anchors of the chain, ordered by their position in the sequences.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chain_anchors: Vec<ChainAnchorStatistics>,
+}
+
+/// An anchor of a chain, which is an exact match between a block of the reference and a block of the query.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainAnchorStatistics {
+    pub reference_start: u64,
+    pub reference_end: u64,
+    pub query_start: u64,
+    pub query_end: u64,
+    /// The chaining cost from the previous anchor or the start of the sequences to this anchor.
+    pub cost: R64,
+    /// The chaining cost from this anchor to the ends of the sequences.
+    pub cost_to_target: R64,
+}
+
+/// A transition between consecutive anchors of a chain, or between an anchor and an end of the sequences.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainTransitionStatistics {
+    /// The reference position where the gap between the anchors starts.
+    pub reference_start: u64,
+    /// The query position where the gap between the anchors starts.
+    pub query_start: u64,
+    /// The number of reference characters between the anchors.
+    pub reference_gap: u64,
+    /// The number of query characters between the anchors.
+    pub query_gap: u64,
+    /// The chaining cost of the transition.
+    pub cost: R64,
+    /// True if the anchors touch in both sequences.
+    pub direct: bool,
+    /// True if the chaining costs demand at least one non-match between the anchors.
+    pub demands_non_match: bool,
+}
+
+impl ChainStatistics {
+    /// The number of transitions in which the anchors touch in both sequences.
+    pub fn direct_transitions(&self) -> usize {
+        self.transitions
+            .iter()
+            .filter(|transition| transition.direct)
+            .count()
+    }
+
+    /// The number of transitions that demand at least one non-match.
+    pub fn non_match_transitions(&self) -> usize {
+        self.transitions
+            .iter()
+            .filter(|transition| transition.demands_non_match)
+            .count()
+    }
+
+    /// Returns true if the chain cost does not exceed the cost of an optimal alignment,
+    /// as required for the chain to be a lower bound.
+    pub fn is_consistent_with(&self, optimal_cost: R64) -> bool {
+        self.cost <= optimal_cost
+    }
+}
+
+/// The kind of an alignment column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentColumn {
+    Match,
+    Mismatch,
+    Insertion,
+    Deletion,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -25,22 +189,202 @@ pub enum AlignmentResult<AlignmentType> {
     },
 
     WithoutTarget {
+        /// Results serialized before the reason was recorded deserialize as [`NoTargetReason::CostLimit`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        reason: NoTargetReason,
+
         #[cfg_attr(feature = "serde", serde(flatten))]
         statistics: AlignmentStatistics,
     },
 }
 
+/// Why an [`AlignmentResult`] has no alignment.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoTargetReason {
+    /// No alignment has a cost within the maximum cost.
+    ///
+    /// The cost of the result is the maximum cost.
+    #[default]
+    CostLimit,
+    /// The search exceeded its time limit.
+    ///
+    /// The cost of the result is a lower bound of the optimal cost, or zero if no lower bound is known.
+    ExceededTimeLimit,
+    /// The search was cancelled.
+    ///
+    /// The cost of the result is a lower bound of the optimal cost, or zero if no lower bound is known.
+    Cancelled,
+}
+
+/// The version of the serialized [`AlignmentStatistics`].
+///
+/// This is incremented whenever statistics are added, removed or change their meaning.
+pub const STATISTICS_FORMAT_VERSION: u32 = 12;
+
+/// Statistics serialized before the format was versioned deserialize with format version zero,
+/// and statistics missing from older formats deserialize as zero.
+/// Counts are integers since format version five, and counts serialized as floats by older formats are rounded.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[must_use]
 pub struct AlignmentStatistics {
+    /// The version of the format of these statistics, see [`STATISTICS_FORMAT_VERSION`].
+    pub format_version: u32,
     pub cost: R64,
     pub cost_per_base: R64,
     pub duration_seconds: R64,
-    pub opened_nodes: R64,
-    pub closed_nodes: R64,
-    pub suboptimal_opened_nodes: R64,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub opened_nodes: u64,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub closed_nodes: u64,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub suboptimal_opened_nodes: u64,
     pub suboptimal_opened_nodes_ratio: R64,
+    /// An upper bound on the ratio between the cost of the alignment and the optimal cost.
+    ///
+    /// This is one if the alignment is optimal.
+    pub suboptimality_bound: R64,
+    /// The number of template switch min length lookaheads that were answered from memory.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub lookahead_cache_hits: u64,
+    /// The number of template switch min length lookaheads that required a search.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub lookahead_cache_misses: u64,
+    /// The maximum number of nodes in the open list at any time.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub peak_open_nodes: u64,
+    /// The maximum number of nodes in the closed list at any time.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub peak_closed_nodes: u64,
+    /// The maximum estimated memory used by the open and closed lists at any time, in bytes.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub peak_memory_bytes: u64,
+    /// The number of times the search was restarted, e.g. with a lower lower bound weight by anytime A*.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub a_star_restarts: u64,
+    /// The number of lower bounds that were answered from a cache.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub lower_bound_cache_hits: u64,
+    /// The number of lower bounds that had to be computed because they were not cached.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub lower_bound_cache_misses: u64,
+    /// The number of anchors seeded for chaining.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub chaining_anchors: u64,
+    /// The number of alignments with a distinct cigar string and the same cost as this alignment.
+    ///
+    /// Only the co-optimal alignments that were enumerated or found by random restarts are counted,
+    /// so if the enumeration was limited, this is a lower bound.
+    /// This is zero if co-optimal alignments were not counted.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub cooptimal_alignments: u64,
+    /// The number of matching columns outside of template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub primary_matches: u64,
+    /// The number of substituted columns outside of template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub primary_mismatches: u64,
+    /// The number of inserted or deleted columns outside of template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub primary_gap_columns: u64,
+    /// The number of maximal runs of insertions or deletions outside of template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub primary_gap_opens: u64,
+    /// The fraction of columns outside of template switches that are matches.
+    ///
+    /// Like in BLAST, each gap column counts as one column.
+    /// This is zero if there are no such columns.
+    pub primary_identity: R64,
+    /// The fraction of columns outside of template switches that are matches, where each run of gap columns counts as one column.
+    ///
+    /// This is zero if there are no such columns.
+    pub primary_gap_compressed_identity: R64,
+    /// Like [`primary_matches`](Self::primary_matches), but for the alignments of the primary to the secondary inside template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub template_switch_matches: u64,
+    /// Like [`primary_mismatches`](Self::primary_mismatches), but inside template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub template_switch_mismatches: u64,
+    /// Like [`primary_gap_columns`](Self::primary_gap_columns), but inside template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub template_switch_gap_columns: u64,
+    /// Like [`primary_gap_opens`](Self::primary_gap_opens), but inside template switches.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_count"))]
+    pub template_switch_gap_opens: u64,
+    /// Like [`primary_identity`](Self::primary_identity), but inside template switches.
+    pub template_switch_identity: R64,
+    /// Like [`primary_gap_compressed_identity`](Self::primary_gap_compressed_identity), but inside template switches.
+    pub template_switch_gap_compressed_identity: R64,
+    /// Histograms of the open and closed nodes over the course of the search.
+    ///
+    /// These are not aggregated by the piecewise operations, which keep the snapshots of `self`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub search_snapshots: Vec<AStarSearchSnapshot>,
+    /// The indices of the template switches of this alignment that do not occur in all enumerated co-optimal alignments.
+    ///
+    /// The template switches are indexed in the order returned by [`TemplateSwitchAnnotation::canonical_from_alignment`](crate::ts_normalisation::TemplateSwitchAnnotation::canonical_from_alignment).
+    /// Like the search snapshots, these are not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub ambiguous_template_switches: Vec<usize>,
+    /// For each template switch of this alignment, the number of distinct co-optimal alignments that contain it.
+    ///
+    /// The template switches are indexed like in [`ambiguous_template_switches`](Self::ambiguous_template_switches),
+    /// and a template switch is ambiguous if its support is less than [`cooptimal_alignments`](Self::cooptimal_alignments).
+    /// Like the search snapshots, these are not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub template_switch_support: Vec<u64>,
+    /// The costs contributed by the consecutive segments of the alignment, in alignment order.
+    ///
+    /// This is computed while backtracking, so it is empty if the aligner does not support it,
+    /// or if the alignment was modified afterwards in a way that does not preserve the segment costs.
+    /// Like the search snapshots, these are not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub cost_segments: Vec<CostSegment>,
+    /// The cost of each alignment column, run-length encoded as pairs of column count and cost per column.
+    ///
+    /// A column is a step of the CIGAR, such that the costs of internal steps like template switch entrances
+    /// are attributed to the column following them.
+    /// Like the cost segments, this is computed while backtracking and not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub column_costs: Vec<(usize, R64)>,
+    /// Statistics about the chain of anchors, if a chaining strategy computed one.
+    ///
+    /// Like the search snapshots, these are not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub chain: Option<ChainStatistics>,
+    /// The inputs this alignment was computed from, if they were recorded.
+    ///
+    /// Like the search snapshots, this is not aggregated by the piecewise operations.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub provenance: Option<ResultProvenance>,
+    /// True if the pair was aligned without searching template switches,
+    /// because its alignment without them is cheaper than any template switch, see [`fast_path`](super::fast_path).
+    ///
+    /// Like the search snapshots, this is not aggregated by the piecewise operations.
+    pub ts_search_skipped: bool,
 }
 
 macro_rules! each_statistic {
@@ -52,6 +396,29 @@ macro_rules! each_statistic {
         $action!(closed_nodes);
         $action!(suboptimal_opened_nodes);
         $action!(suboptimal_opened_nodes_ratio);
+        $action!(suboptimality_bound);
+        $action!(lookahead_cache_hits);
+        $action!(lookahead_cache_misses);
+        $action!(peak_open_nodes);
+        $action!(peak_closed_nodes);
+        $action!(peak_memory_bytes);
+        $action!(a_star_restarts);
+        $action!(lower_bound_cache_hits);
+        $action!(lower_bound_cache_misses);
+        $action!(chaining_anchors);
+        $action!(cooptimal_alignments);
+        $action!(primary_matches);
+        $action!(primary_mismatches);
+        $action!(primary_gap_columns);
+        $action!(primary_gap_opens);
+        $action!(primary_identity);
+        $action!(primary_gap_compressed_identity);
+        $action!(template_switch_matches);
+        $action!(template_switch_mismatches);
+        $action!(template_switch_gap_columns);
+        $action!(template_switch_gap_opens);
+        $action!(template_switch_identity);
+        $action!(template_switch_gap_compressed_identity);
     }};
 }
 
@@ -66,9 +433,12 @@ impl<AlignmentType> AlignmentResult<AlignmentType> {
         suboptimal_opened_nodes: usize,
         reference_length: usize,
         query_length: usize,
-    ) -> Self {
-        Self::new(
-            Some(alignment),
+    ) -> Self
+    where
+        AlignmentType: IAlignmentType,
+    {
+        let mut result = Self::new(
+            Ok(alignment),
             cost,
             duration_seconds,
             opened_nodes,
@@ -76,10 +446,14 @@ impl<AlignmentType> AlignmentResult<AlignmentType> {
             suboptimal_opened_nodes,
             reference_length,
             query_length,
-        )
+        );
+        result.update_identity_statistics();
+        result
     }
 
+    #[expect(clippy::too_many_arguments)]
     pub fn new_without_target(
+        reason: NoTargetReason,
         cost: Cost,
         duration_seconds: f64,
         opened_nodes: usize,
@@ -89,7 +463,7 @@ impl<AlignmentType> AlignmentResult<AlignmentType> {
         query_length: usize,
     ) -> Self {
         Self::new(
-            None,
+            Err(reason),
             cost,
             duration_seconds,
             opened_nodes,
@@ -102,7 +476,7 @@ impl<AlignmentType> AlignmentResult<AlignmentType> {
 
     #[expect(clippy::too_many_arguments)]
     fn new(
-        alignment: Option<Vec<(usize, AlignmentType)>>,
+        alignment: std::result::Result<Vec<(usize, AlignmentType)>, NoTargetReason>,
         cost: Cost,
         duration_seconds: f64,
         opened_nodes: usize,
@@ -112,39 +486,100 @@ impl<AlignmentType> AlignmentResult<AlignmentType> {
         query_length: usize,
     ) -> Self {
         let statistics = AlignmentStatistics {
+            format_version: STATISTICS_FORMAT_VERSION,
             cost: (cost.as_u64() as f64).try_into().unwrap(),
-            cost_per_base: ((cost.as_u64() * 2) as f64 / (reference_length + query_length) as f64)
+            // Aligning empty sequences opens no nodes and has no cost, so avoid dividing by zero.
+            cost_per_base: ((cost.as_u64() * 2) as f64
+                / (reference_length + query_length).max(1) as f64)
                 .try_into()
                 .unwrap(),
             duration_seconds: duration_seconds.try_into().unwrap(),
-            opened_nodes: (opened_nodes as f64).try_into().unwrap(),
-            closed_nodes: (closed_nodes as f64).try_into().unwrap(),
-            suboptimal_opened_nodes: (suboptimal_opened_nodes as f64).try_into().unwrap(),
+            opened_nodes: opened_nodes as u64,
+            closed_nodes: closed_nodes as u64,
+            suboptimal_opened_nodes: suboptimal_opened_nodes as u64,
             suboptimal_opened_nodes_ratio: (suboptimal_opened_nodes as f64
-                / (opened_nodes - suboptimal_opened_nodes) as f64)
+                / (opened_nodes - suboptimal_opened_nodes).max(1) as f64)
                 .try_into()
                 .unwrap(),
+            suboptimality_bound: R64::one(),
+            lookahead_cache_hits: 0,
+            lookahead_cache_misses: 0,
+            peak_open_nodes: 0,
+            peak_closed_nodes: 0,
+            peak_memory_bytes: 0,
+            a_star_restarts: 0,
+            lower_bound_cache_hits: 0,
+            lower_bound_cache_misses: 0,
+            chaining_anchors: 0,
+            cooptimal_alignments: 0,
+            primary_matches: 0,
+            primary_mismatches: 0,
+            primary_gap_columns: 0,
+            primary_gap_opens: 0,
+            primary_identity: R64::zero(),
+            primary_gap_compressed_identity: R64::zero(),
+            template_switch_matches: 0,
+            template_switch_mismatches: 0,
+            template_switch_gap_columns: 0,
+            template_switch_gap_opens: 0,
+            template_switch_identity: R64::zero(),
+            template_switch_gap_compressed_identity: R64::zero(),
+            search_snapshots: Vec::new(),
+            ambiguous_template_switches: Vec::new(),
+            template_switch_support: Vec::new(),
+            cost_segments: Vec::new(),
+            column_costs: Vec::new(),
+            chain: None,
+            provenance: None,
+            ts_search_skipped: false,
         };
 
-        if let Some(alignment) = alignment {
-            Self::WithTarget {
+        match alignment {
+            Ok(alignment) => Self::WithTarget {
                 alignment,
                 statistics,
-            }
-        } else {
-            Self::WithoutTarget { statistics }
+            },
+            Err(reason) => Self::WithoutTarget { reason, statistics },
+        }
+    }
+
+    /// Returns why there is no alignment, or `None` if there is one.
+    pub fn no_target_reason(&self) -> Option<NoTargetReason> {
+        match self {
+            AlignmentResult::WithTarget { .. } => None,
+            AlignmentResult::WithoutTarget { reason, .. } => Some(*reason),
         }
     }
 
     pub fn statistics(&self) -> &AlignmentStatistics {
         match self {
             AlignmentResult::WithTarget { statistics, .. } => statistics,
-            AlignmentResult::WithoutTarget { statistics } => statistics,
+            AlignmentResult::WithoutTarget { statistics, .. } => statistics,
+        }
+    }
+
+    pub fn statistics_mut(&mut self) -> &mut AlignmentStatistics {
+        match self {
+            AlignmentResult::WithTarget { statistics, .. } => statistics,
+            AlignmentResult::WithoutTarget { statistics, .. } => statistics,
         }
     }
 }
 
 impl<AlignmentType: IAlignmentType> AlignmentResult<AlignmentType> {
+    /// Recomputes the identity statistics from the alignment.
+    ///
+    /// This needs to be called after modifying the alignment.
+    pub fn update_identity_statistics(&mut self) {
+        if let Self::WithTarget {
+            alignment,
+            statistics,
+        } = self
+        {
+            statistics.set_identity(alignment);
+        }
+    }
+
     pub fn cigar(&self) -> String
     where
         AlignmentType: Display,
@@ -162,28 +597,100 @@ impl<AlignmentType: IAlignmentType> AlignmentResult<AlignmentType> {
             return Ok(());
         };
 
-        for (amount, alignment_type) in alignment {
-            if alignment_type.is_repeatable() {
-                write!(writer, "{amount}{alignment_type}")?;
-            } else {
-                write!(writer, "{alignment_type}")?;
-            }
-        }
+        write_alignment_cigar(alignment, writer)
+    }
+}
 
-        Ok(())
+/// Returns the cigar string of a run-length encoded alignment.
+pub fn alignment_cigar<AlignmentType: IAlignmentType + Display>(
+    alignment: &[(usize, AlignmentType)],
+) -> String {
+    let mut result = String::new();
+    write_alignment_cigar(alignment, &mut result).unwrap();
+    result
+}
+
+fn write_alignment_cigar<AlignmentType: IAlignmentType + Display>(
+    alignment: &[(usize, AlignmentType)],
+    writer: &mut impl Write,
+) -> Result {
+    for (amount, alignment_type) in alignment {
+        if alignment_type.is_repeatable() {
+            write!(writer, "{amount}{alignment_type}")?;
+        } else {
+            write!(writer, "{alignment_type}")?;
+        }
     }
+
+    Ok(())
 }
 
 impl AlignmentStatistics {
+    /// Sets the identity statistics of both regions from the columns of `alignment`.
+    fn set_identity<AlignmentType: IAlignmentType>(
+        &mut self,
+        alignment: &[(usize, AlignmentType)],
+    ) {
+        let mut primary = ColumnCounts::default();
+        let mut template_switch = ColumnCounts::default();
+        let mut previous_column = None;
+
+        for (count, alignment_type) in alignment {
+            let (count, column) = match alignment_type.as_run() {
+                Some((length, alignment_type)) => {
+                    (count * length, alignment_type.alignment_column())
+                }
+                None => (*count, alignment_type.alignment_column()),
+            };
+            let Some((region, column)) = column else {
+                if !alignment_type.is_internal() {
+                    previous_column = None;
+                }
+                continue;
+            };
+
+            let counts = match region {
+                AlignmentRegion::Primary => &mut primary,
+                AlignmentRegion::TemplateSwitch => &mut template_switch,
+            };
+            match column {
+                AlignmentColumn::Match => counts.matches += count,
+                AlignmentColumn::Mismatch => counts.mismatches += count,
+                AlignmentColumn::Insertion | AlignmentColumn::Deletion => {
+                    counts.gap_columns += count;
+                    // A run of gaps may span multiple entries, e.g. if it continues into a template switch flank.
+                    if previous_column != Some((region, column)) {
+                        counts.gap_opens += 1;
+                    }
+                }
+            }
+            previous_column = Some((region, column));
+        }
+
+        self.primary_matches = primary.matches as u64;
+        self.primary_mismatches = primary.mismatches as u64;
+        self.primary_gap_columns = primary.gap_columns as u64;
+        self.primary_gap_opens = primary.gap_opens as u64;
+        self.primary_identity = primary.identity();
+        self.primary_gap_compressed_identity = primary.gap_compressed_identity();
+        self.template_switch_matches = template_switch.matches as u64;
+        self.template_switch_mismatches = template_switch.mismatches as u64;
+        self.template_switch_gap_columns = template_switch.gap_columns as u64;
+        self.template_switch_gap_opens = template_switch.gap_opens as u64;
+        self.template_switch_identity = template_switch.identity();
+        self.template_switch_gap_compressed_identity = template_switch.gap_compressed_identity();
+    }
+
     pub fn min_value() -> Self {
         let mut result = Self::default();
 
         macro_rules! create_min_value {
             ($field:ident) => {
-                result.$field = R64::min_value();
+                result.$field = Statistic::statistic_min();
             };
         }
         each_statistic!(create_min_value);
+        result.format_version = STATISTICS_FORMAT_VERSION;
 
         result
     }
@@ -193,10 +700,11 @@ impl AlignmentStatistics {
 
         macro_rules! create_max_value {
             ($field:ident) => {
-                result.$field = R64::max_value();
+                result.$field = Statistic::statistic_max();
             };
         }
         each_statistic!(create_max_value);
+        result.format_version = STATISTICS_FORMAT_VERSION;
 
         result
     }
@@ -206,14 +714,30 @@ impl AlignmentStatistics {
 
         macro_rules! create_zero {
             ($field:ident) => {
-                result.$field = R64::zero();
+                result.$field = Statistic::statistic_zero();
             };
         }
         each_statistic!(create_zero);
+        result.format_version = STATISTICS_FORMAT_VERSION;
 
         result
     }
 
+    /// Returns true if the statistics were serialized by this or an older version of the format.
+    ///
+    /// Statistics of newer formats deserialize, but their new statistics are dropped,
+    /// and statistics whose meaning changed are misinterpreted.
+    pub fn is_format_supported(&self) -> bool {
+        self.format_version <= STATISTICS_FORMAT_VERSION
+    }
+
+    /// Set all statistics that depend on wall-clock time to zero.
+    ///
+    /// The remaining statistics are reproducible between runs with the same input.
+    pub fn clear_timing(&mut self) {
+        self.duration_seconds = R64::zero();
+    }
+
     pub fn piecewise_min(&self, other: &Self) -> Self {
         let mut result = self.clone();
 
@@ -253,12 +777,13 @@ impl AlignmentStatistics {
         result
     }
 
+    /// Divides each statistic by `divisor`, rounding counts to the nearest integer.
     pub fn piecewise_div(&self, divisor: R64) -> Self {
         let mut result = self.clone();
 
         macro_rules! div {
             ($field:ident) => {
-                result.$field /= divisor;
+                result.$field = result.$field.statistic_div(divisor);
             };
         }
         each_statistic!(div);
@@ -270,23 +795,10 @@ impl AlignmentStatistics {
         assert!(percentile >= 0.0);
         assert!(percentile <= 1.0);
         let mut result = Self::zero();
-        let mut buffer = Vec::new();
 
         macro_rules! percentile {
             ($field:ident) => {
-                buffer.clear();
-                buffer.extend(statistics.iter().map(|s| s.$field));
-                buffer.sort();
-
-                // Scale percentile.
-                let index = (percentile * buffer.len() as f64).floor().raw() as usize;
-                let index = if index == buffer.len() {
-                    // Edge case if percentile == 1.0
-                    index - 1
-                } else {
-                    index
-                };
-                result.$field = buffer[index];
+                result.$field = percentile_of(statistics.iter().map(|s| s.$field), percentile);
             };
         }
         each_statistic!(percentile);
@@ -295,41 +807,494 @@ impl AlignmentStatistics {
     }
 }
 
-impl<AlignmentType: Display + IAlignmentType> Display for AlignmentResult<AlignmentType> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        if let AlignmentResult::WithTarget { .. } = self {
-            write!(f, "CIGAR: ")?;
-            self.write_cigar(f)?;
-            writeln!(f)?;
-        } else {
-            writeln!(f, "No alignment found with given maximum costs")?;
+impl<AlignmentType: Display + IAlignmentType> AlignmentResult<AlignmentType> {
+    /// Returns an object that displays the cigar string and the statistics in the given format.
+    pub fn display(&self, format: StatisticsFormat) -> AlignmentResultDisplay<'_, AlignmentType> {
+        AlignmentResultDisplay {
+            result: self,
+            format,
         }
+    }
+}
 
-        let (AlignmentResult::WithTarget { statistics, .. }
-        | AlignmentResult::WithoutTarget { statistics }) = self;
+impl AlignmentStatistics {
+    /// Returns an object that displays the statistics in the given format.
+    pub fn display(&self, format: StatisticsFormat) -> StatisticsDisplay<'_> {
+        StatisticsDisplay {
+            statistics: self,
+            format,
+        }
+    }
+}
 
-        statistics.fmt(f)
+impl<AlignmentType: Display + IAlignmentType> Display for AlignmentResult<AlignmentType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.display(StatisticsFormat::default()).fmt(f)
     }
 }
 
 impl Display for AlignmentStatistics {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        writeln!(f, "Cost: {}", self.cost)?;
-        writeln!(f, "Cost per base: {:.2}", self.cost_per_base)?;
-        writeln!(f, "Opened nodes: {}", self.opened_nodes)?;
-        writeln!(f, "Closed nodes: {}", self.closed_nodes)?;
+        self.display(StatisticsFormat::default()).fmt(f)
+    }
+}
+
+impl<AlignmentType: Display + IAlignmentType> Display
+    for AlignmentResultDisplay<'_, AlignmentType>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match (self.result, self.format.mode) {
+            (AlignmentResult::WithTarget { .. }, StatisticsMode::HumanReadable) => {
+                write!(f, "CIGAR: ")?;
+                self.result.write_cigar(f)?;
+                writeln!(f)?;
+            }
+            (AlignmentResult::WithTarget { .. }, StatisticsMode::Machine) => {
+                write!(f, "cigar = \"")?;
+                self.result.write_cigar(f)?;
+                writeln!(f, "\"")?;
+            }
+            (AlignmentResult::WithoutTarget { reason, .. }, StatisticsMode::HumanReadable) => {
+                match reason {
+                    NoTargetReason::CostLimit => {
+                        writeln!(f, "No alignment found with given maximum costs")?
+                    }
+                    NoTargetReason::ExceededTimeLimit => {
+                        writeln!(f, "No alignment found within the time limit")?
+                    }
+                    NoTargetReason::Cancelled => {
+                        writeln!(f, "No alignment found before the search was cancelled")?
+                    }
+                }
+            }
+            (AlignmentResult::WithoutTarget { reason, .. }, StatisticsMode::Machine) => {
+                if *reason != NoTargetReason::CostLimit {
+                    writeln!(f, "no_target_reason = \"{reason:?}\"")?;
+                }
+            }
+        }
+
+        self.result.statistics().display(self.format).fmt(f)
+    }
+}
+
+impl Display for StatisticsDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.format.mode {
+            StatisticsMode::HumanReadable => self.fmt_human_readable(f),
+            StatisticsMode::Machine => self.fmt_machine(f),
+        }
+    }
+}
+
+impl StatisticsDisplay<'_> {
+    fn fmt_human_readable(&self, f: &mut Formatter<'_>) -> Result {
+        let statistics = self.statistics;
+        let precision = self.format.precision;
+
+        writeln!(f, "Cost: {}", statistics.cost)?;
+        writeln!(f, "Cost per base: {:.precision$}", statistics.cost_per_base)?;
+        writeln!(f, "Opened nodes: {}", statistics.opened_nodes)?;
+        writeln!(f, "Closed nodes: {}", statistics.closed_nodes)?;
         writeln!(
             f,
             "Suboptimal openend nodes: {}",
-            self.suboptimal_opened_nodes
+            statistics.suboptimal_opened_nodes
         )?;
         writeln!(
             f,
-            "Suboptimal openend nodes per optimal opened node: {:.2}",
-            self.suboptimal_opened_nodes_ratio
+            "Suboptimal openend nodes per optimal opened node: {:.precision$}",
+            statistics.suboptimal_opened_nodes_ratio
         )?;
-        write!(f, "Duration: {:.2}s", self.duration_seconds)?;
+        if statistics.suboptimality_bound > 1.0 {
+            writeln!(
+                f,
+                "Suboptimality bound: {:.precision$}",
+                statistics.suboptimality_bound
+            )?;
+        }
+        let lookaheads = statistics.lookahead_cache_hits + statistics.lookahead_cache_misses;
+        if lookaheads > 0 {
+            writeln!(
+                f,
+                "Lookahead cache hit rate: {:.precision$} ({} of {})",
+                statistics.lookahead_cache_hits as f64 / lookaheads as f64,
+                statistics.lookahead_cache_hits,
+                lookaheads
+            )?;
+        }
+        writeln!(
+            f,
+            "Peak open nodes: {}, peak closed nodes: {}",
+            statistics.peak_open_nodes, statistics.peak_closed_nodes
+        )?;
+        writeln!(
+            f,
+            "Peak memory: {:.precision$}MiB",
+            statistics.peak_memory_bytes as f64 / (1024.0 * 1024.0)
+        )?;
+        if statistics.a_star_restarts > 0 {
+            writeln!(f, "A* restarts: {}", statistics.a_star_restarts)?;
+        }
+        let lower_bounds = statistics.lower_bound_cache_hits + statistics.lower_bound_cache_misses;
+        if lower_bounds > 0 {
+            writeln!(
+                f,
+                "Lower bound cache hit rate: {:.precision$} ({} of {})",
+                statistics.lower_bound_cache_hits as f64 / lower_bounds as f64,
+                statistics.lower_bound_cache_hits,
+                lower_bounds
+            )?;
+        }
+        if statistics.chaining_anchors > 0 {
+            writeln!(f, "Chaining anchors: {}", statistics.chaining_anchors)?;
+        }
+        if let Some(chain) = &statistics.chain {
+            writeln!(
+                f,
+                "Chain: {} anchors covering {:.precision$}% of the reference and {:.precision$}% of the query, {} gaps, {} template switch anchors",
+                chain.anchors,
+                chain.reference_coverage * 100.0,
+                chain.query_coverage * 100.0,
+                chain.gaps,
+                chain.template_switch_anchors,
+            )?;
+            writeln!(
+                f,
+                "Chain transitions: {} direct, {} demanding non-matches, {} total",
+                chain.direct_transitions(),
+                chain.non_match_transitions(),
+                chain.transitions.len(),
+            )?;
+            if statistics.cost > 0.0 {
+                writeln!(
+                    f,
+                    "Chain cost: {} ({:.precision$}% of the alignment cost)",
+                    chain.cost,
+                    chain.cost / statistics.cost * 100.0,
+                )?;
+            } else {
+                writeln!(f, "Chain cost: {}", chain.cost)?;
+            }
+        }
+        if statistics.ts_search_skipped {
+            writeln!(
+                f,
+                "Template switch search skipped, since the alignment is cheaper than any template switch"
+            )?;
+        }
+        if statistics.cooptimal_alignments > 0 {
+            writeln!(
+                f,
+                "Co-optimal alignments: {}",
+                statistics.cooptimal_alignments
+            )?;
+            writeln!(
+                f,
+                "Ambiguous template switches: {}",
+                statistics.ambiguous_template_switches.len()
+            )?;
+        }
+        writeln!(
+            f,
+            "Identity: {:.precision$}% (gap-compressed {:.precision$}%), {} mismatches, {} gap opens",
+            statistics.primary_identity * 100.0,
+            statistics.primary_gap_compressed_identity * 100.0,
+            statistics.primary_mismatches,
+            statistics.primary_gap_opens,
+        )?;
+        if statistics.template_switch_matches
+            + statistics.template_switch_mismatches
+            + statistics.template_switch_gap_columns
+            > 0
+        {
+            writeln!(
+                f,
+                "Template switch identity: {:.precision$}% (gap-compressed {:.precision$}%), {} mismatches, {} gap opens",
+                statistics.template_switch_identity * 100.0,
+                statistics.template_switch_gap_compressed_identity * 100.0,
+                statistics.template_switch_mismatches,
+                statistics.template_switch_gap_opens,
+            )?;
+        }
+        if statistics.cost_segments.len() > 1 {
+            writeln!(f, "Cost breakdown:")?;
+            for segment in &statistics.cost_segments {
+                writeln!(f, "  {segment}")?;
+            }
+        }
+        write!(f, "Duration: {:.precision$}s", statistics.duration_seconds)?;
+
+        Ok(())
+    }
+
+    fn fmt_machine(&self, f: &mut Formatter<'_>) -> Result {
+        let statistics = self.statistics;
+        let precision = self.format.precision;
+
+        write!(f, "format_version = {}", statistics.format_version)?;
+        macro_rules! write_statistic {
+            ($field:ident) => {
+                write!(f, "\n{} = ", stringify!($field))?;
+                statistics.$field.write_with_precision(f, precision)?;
+            };
+        }
+        each_statistic!(write_statistic);
 
         Ok(())
     }
 }
+
+/// How [`AlignmentStatistics`] are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatisticsFormat {
+    /// The number of decimal places of statistics that are not integers.
+    pub precision: usize,
+    pub mode: StatisticsMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatisticsMode {
+    /// Descriptive labels and derived statistics like cache hit rates, with memory in MiB and identities in percent.
+    #[default]
+    HumanReadable,
+    /// One `name = value` line per statistic, named like the serialized fields and in the same units.
+    Machine,
+}
+
+/// Displays [`AlignmentStatistics`] in a [`StatisticsFormat`].
+pub struct StatisticsDisplay<'statistics> {
+    statistics: &'statistics AlignmentStatistics,
+    format: StatisticsFormat,
+}
+
+/// Displays an [`AlignmentResult`] with its statistics in a [`StatisticsFormat`].
+pub struct AlignmentResultDisplay<'result, AlignmentType> {
+    result: &'result AlignmentResult<AlignmentType>,
+    format: StatisticsFormat,
+}
+
+impl Default for StatisticsFormat {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            mode: StatisticsMode::default(),
+        }
+    }
+}
+
+/// The operations needed to aggregate and display a statistic.
+trait Statistic: Copy + Ord + AddAssign {
+    fn statistic_min() -> Self;
+
+    fn statistic_max() -> Self;
+
+    fn statistic_zero() -> Self;
+
+    /// Divides the statistic, rounding to the nearest integer for counts.
+    fn statistic_div(self, divisor: R64) -> Self;
+
+    fn write_with_precision(&self, f: &mut Formatter<'_>, precision: usize) -> Result;
+}
+
+impl Statistic for R64 {
+    fn statistic_min() -> Self {
+        <Self as Float>::min_value()
+    }
+
+    fn statistic_max() -> Self {
+        <Self as Float>::max_value()
+    }
+
+    fn statistic_zero() -> Self {
+        <Self as Zero>::zero()
+    }
+
+    fn statistic_div(self, divisor: R64) -> Self {
+        self / divisor
+    }
+
+    fn write_with_precision(&self, f: &mut Formatter<'_>, precision: usize) -> Result {
+        write!(f, "{self:.precision$}")
+    }
+}
+
+impl Statistic for u64 {
+    fn statistic_min() -> Self {
+        u64::MIN
+    }
+
+    fn statistic_max() -> Self {
+        u64::MAX
+    }
+
+    fn statistic_zero() -> Self {
+        0
+    }
+
+    fn statistic_div(self, divisor: R64) -> Self {
+        (self as f64 / divisor.raw()).round() as u64
+    }
+
+    fn write_with_precision(&self, f: &mut Formatter<'_>, _precision: usize) -> Result {
+        write!(f, "{self}")
+    }
+}
+
+/// Returns the value at the given percentile, which must be between zero and one.
+fn percentile_of<T: Ord>(values: impl Iterator<Item = T>, percentile: R64) -> T {
+    let mut buffer: Vec<_> = values.collect();
+    buffer.sort();
+
+    // Scale percentile.
+    let index = (percentile * buffer.len() as f64).floor().raw() as usize;
+    let index = if index == buffer.len() {
+        // Edge case if percentile == 1.0
+        index - 1
+    } else {
+        index
+    };
+    buffer.swap_remove(index)
+}
+
+/// Deserializes a count that may have been serialized as a float by a format version before five.
+#[cfg(feature = "serde")]
+fn deserialize_count<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<u64, D::Error> {
+    struct CountVisitor;
+
+    impl serde::de::Visitor<'_> for CountVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut Formatter) -> Result {
+            write!(formatter, "a non-negative number")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> std::result::Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> std::result::Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::custom(format!("negative count {value}")))
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> std::result::Result<u64, E> {
+            if value >= 0.0 && value.is_finite() {
+                Ok(value.round() as u64)
+            } else {
+                Err(E::custom(format!("invalid count {value}")))
+            }
+        }
+    }
+
+    deserializer.deserialize_any(CountVisitor)
+}
+
+/// The counts of alignment columns in one region of an alignment.
+#[derive(Debug, Default)]
+struct ColumnCounts {
+    matches: usize,
+    mismatches: usize,
+    gap_columns: usize,
+    gap_opens: usize,
+}
+
+impl ColumnCounts {
+    fn identity(&self) -> R64 {
+        Self::match_fraction(self.matches, self.mismatches + self.gap_columns)
+    }
+
+    fn gap_compressed_identity(&self) -> R64 {
+        Self::match_fraction(self.matches, self.mismatches + self.gap_opens)
+    }
+
+    fn match_fraction(matches: usize, other_columns: usize) -> R64 {
+        let columns = matches + other_columns;
+        if columns == 0 {
+            R64::zero()
+        } else {
+            R64::new(matches as f64 / columns as f64)
+        }
+    }
+}
+
+impl CostSegment {
+    /// Splits a path through the alignment graph into cost segments.
+    ///
+    /// The path is given in backtracking order as pairs of an alignment type and the cost of the node that it leads to,
+    /// and the cost of the root node is assumed to be zero.
+    pub fn from_backtrack<AlignmentType: IAlignmentType>(
+        backtrack: impl Iterator<Item = (AlignmentType, Cost)>,
+    ) -> Vec<Self> {
+        let mut path: Vec<_> = backtrack.collect();
+        path.reverse();
+
+        let mut segments: Vec<Self> = Vec::new();
+        let mut template_switch_count: usize = 0;
+        let mut previous_cost = Cost::ZERO;
+        for (alignment_type, cost) in path {
+            let edge_cost = R64::new((cost - previous_cost).as_u64() as f64);
+            previous_cost = cost;
+            let steps = alignment_type.as_run().map_or(1, |(length, _)| length);
+
+            match (alignment_type.cost_segment_kind(), segments.last_mut()) {
+                (Some(kind), Some(segment)) if kind == segment.kind => {
+                    segment.steps += steps;
+                    segment.cost += edge_cost;
+                }
+                (None, Some(segment)) => {
+                    segment.steps += steps;
+                    segment.cost += edge_cost;
+                }
+                (kind, _) => {
+                    let kind = kind.unwrap_or(CostSegmentKind::Primary);
+                    let template_switch = match kind {
+                        CostSegmentKind::Primary
+                        | CostSegmentKind::Flank
+                        | CostSegmentKind::Duplication
+                        | CostSegmentKind::Inversion => None,
+                        CostSegmentKind::TemplateSwitchEntrance => {
+                            template_switch_count += 1;
+                            Some(template_switch_count - 1)
+                        }
+                        CostSegmentKind::TemplateSwitch | CostSegmentKind::TemplateSwitchExit => {
+                            template_switch_count.checked_sub(1)
+                        }
+                    };
+                    segments.push(Self {
+                        kind,
+                        template_switch,
+                        steps,
+                        cost: edge_cost,
+                    });
+                }
+            }
+        }
+
+        segments
+    }
+}
+
+impl Display for CostSegmentKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Primary => write!(f, "primary"),
+            Self::Flank => write!(f, "flank"),
+            Self::TemplateSwitchEntrance => write!(f, "entrance"),
+            Self::TemplateSwitch => write!(f, "secondary"),
+            Self::TemplateSwitchExit => write!(f, "exit"),
+            Self::Duplication => write!(f, "duplication"),
+            Self::Inversion => write!(f, "inversion"),
+        }
+    }
+}
+
+impl Display for CostSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(template_switch) = self.template_switch {
+            write!(f, "TS {template_switch} ")?;
+        }
+        write!(f, "{} ({} steps): {}", self.kind, self.steps, self.cost)
+    }
+}
@@ -0,0 +1,89 @@
+//! Buffers of the template switch aligner that are reused across alignments.
+//!
+//! The open and closed lists of the search and the memorised lookahead results grow to hundreds of megabytes for long sequences.
+//! An [`Aligner`](super::Aligner) checks its buffers out of a [`BufferPool`] for each alignment and returns them afterwards,
+//! such that later alignments reuse the allocations of earlier ones instead of growing their own.
+
+use std::sync::Mutex;
+
+use generic_a_star::{reset::Reset, AStarBuffers, AStarNode};
+
+use super::template_switch_distance::{
+    strategies::{
+        template_switch_min_length::TemplateSwitchMinLengthStrategy, AlignmentStrategySelector,
+    },
+    Node,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The buffers used by one alignment.
+pub struct AlignmentBuffers<Strategies: AlignmentStrategySelector> {
+    /// The open and closed lists of the search.
+    pub(crate) a_star: AStarBuffers<<Node<Strategies> as AStarNode>::Identifier, Node<Strategies>>,
+    /// The open and closed lists of the lookahead searches of the minimum length strategy.
+    pub(crate) lookahead:
+        AStarBuffers<<Node<Strategies> as AStarNode>::Identifier, Node<Strategies>>,
+    /// The memorised lookahead results, which are reset before they are reused.
+    pub(crate) template_switch_min_length:
+        <Strategies::TemplateSwitchMinLength as TemplateSwitchMinLengthStrategy>::Memory,
+}
+
+/// A pool of [`AlignmentBuffers`] that can be shared by aligners running in parallel.
+///
+/// Buffers keep the capacity they had at the end of their last alignment,
+/// and this capacity counts towards the memory limit of the next alignment using them.
+/// Use [`clear`](Self::clear) to release the memory of the pooled buffers.
+pub struct BufferPool<Strategies: AlignmentStrategySelector> {
+    buffers: Mutex<Vec<AlignmentBuffers<Strategies>>>,
+}
+
+impl<Strategies: AlignmentStrategySelector> BufferPool<Strategies> {
+    pub fn new() -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Takes buffers out of the pool, or creates empty buffers if the pool is empty.
+    pub fn check_out(&self) -> AlignmentBuffers<Strategies> {
+        self.buffers.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    /// Returns buffers to the pool, such that the next alignment can reuse them.
+    pub fn give_back(&self, mut buffers: AlignmentBuffers<Strategies>) {
+        buffers.template_switch_min_length.reset();
+        self.buffers.lock().unwrap().push(buffers);
+    }
+
+    /// The number of buffers in the pool that are not checked out.
+    pub fn len(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all buffers in the pool, releasing their memory.
+    pub fn clear(&self) {
+        self.buffers.lock().unwrap().clear();
+    }
+}
+
+impl<Strategies: AlignmentStrategySelector> Default for AlignmentBuffers<Strategies> {
+    fn default() -> Self {
+        Self {
+            a_star: Default::default(),
+            lookahead: Default::default(),
+            template_switch_min_length: Default::default(),
+        }
+    }
+}
+
+impl<Strategies: AlignmentStrategySelector> Default for BufferPool<Strategies> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
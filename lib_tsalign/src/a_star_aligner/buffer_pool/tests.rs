@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use crate::a_star_aligner::{alignment_result::AlignmentResult, AlignerBuilder};
+use crate::test_fixtures::{sample_config, QUERY, REFERENCE};
+
+fn without_timing<AlignmentType>(
+    mut alignment: AlignmentResult<AlignmentType>,
+) -> AlignmentResult<AlignmentType> {
+    alignment.statistics_mut().clear_timing();
+    alignment
+}
+
+#[test]
+fn reused_buffers_give_the_same_alignment() {
+    let aligner = AlignerBuilder::new(sample_config()).build().unwrap();
+    assert!(aligner.buffer_pool().is_empty());
+
+    let first = without_timing(aligner.align_str(REFERENCE, QUERY).unwrap());
+    assert_eq!(aligner.buffer_pool().len(), 1);
+    assert!(first.statistics().lookahead_cache_misses > 0);
+
+    // The memorised lookahead results of the first alignment must not be hits of the second one.
+    let second = without_timing(aligner.align_str(REFERENCE, QUERY).unwrap());
+    assert_eq!(aligner.buffer_pool().len(), 1);
+    assert_eq!(first, second);
+
+    let other = without_timing(aligner.align_str(QUERY, REFERENCE).unwrap());
+    aligner.buffer_pool().clear();
+    assert!(aligner.buffer_pool().is_empty());
+    let fresh = without_timing(aligner.align_str(QUERY, REFERENCE).unwrap());
+    assert_eq!(other, fresh);
+}
+
+#[test]
+fn aligners_share_a_buffer_pool() {
+    let aligner = AlignerBuilder::new(sample_config()).build().unwrap();
+    let other = AlignerBuilder::new(sample_config())
+        .build()
+        .unwrap()
+        .with_buffer_pool(Arc::clone(aligner.buffer_pool()));
+    assert!(Arc::ptr_eq(aligner.buffer_pool(), other.buffer_pool()));
+
+    aligner.align_str(REFERENCE, QUERY).unwrap();
+    other.align_str(REFERENCE, QUERY).unwrap();
+    assert_eq!(aligner.buffer_pool().len(), 1);
+
+    let buffers = [
+        aligner.buffer_pool().check_out(),
+        aligner.buffer_pool().check_out(),
+    ];
+    assert!(aligner.buffer_pool().is_empty());
+    for buffers in buffers {
+        other.buffer_pool().give_back(buffers);
+    }
+    assert_eq!(aligner.buffer_pool().len(), 2);
+}
+
+#[test]
+fn aligners_with_buffer_pools_can_be_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let aligner = AlignerBuilder::new(sample_config()).build().unwrap();
+    assert_send_sync(&aligner);
+    std::thread::scope(|scope| {
+        for _ in 0..2 {
+            scope.spawn(|| aligner.align_str(REFERENCE, QUERY).unwrap());
+        }
+    });
+    assert!(!aligner.buffer_pool().is_empty());
+}
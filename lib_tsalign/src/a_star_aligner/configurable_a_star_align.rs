@@ -0,0 +1,742 @@
+//! A typed builder for the template switch aligner.
+//!
+//! Strategies that are consulted in the inner loop of the search are chosen as type parameters of the [`AlignerBuilder`],
+//! such that only valid combinations of them compile, and each combination is instantiated separately.
+//! Limits and other settings are chosen at runtime.
+//! The resulting [`Aligner`] can align many pairs of sequences with the same configuration.
+
+use std::{marker::PhantomData, mem, sync::Arc, time::Duration};
+
+use compact_genome::{
+    implementation::vec_sequence::VectorGenome,
+    interface::{
+        alphabet::Alphabet,
+        sequence::{GenomeSequence, OwnedGenomeSequence},
+    },
+};
+use generic_a_star::{cancellation::CancellationToken, cost::Cost, AStar};
+
+use super::{
+    a_star_align,
+    alignment_result::AlignmentResult,
+    anytime_a_star_align,
+    buffer_pool::{AlignmentBuffers, BufferPool},
+    fast_path::{banded_edit_distance, without_jumps},
+    ida_star_align,
+    memory_estimate::MemoryEstimate,
+    template_switch_distance::{
+        secondary_score::SecondaryScorePolicy,
+        strategies::{
+            chaining::{ChainingStrategy, NoChainingStrategy, CHAINING_BLOCK_SIZE},
+            node_ord::{
+                AntiDiagonalNodeOrdStrategy, LearnedNodeOrdStrategy, NodeOrdModel, NodeOrdStrategy,
+            },
+            primary_match::AllowPrimaryMatchStrategy,
+            secondary_deletion::AllowSecondaryDeletionStrategy,
+            shortcut::{
+                NoShortcutStrategy, ShortcutStrategy, TemplateSwitchLowerBoundShortcutStrategy,
+            },
+            template_switch_count::{
+                MaxTemplateSwitchCountStrategy, NoTemplateSwitchCountStrategy,
+                TemplateSwitchCountStrategy,
+            },
+            template_switch_min_length::{
+                LookaheadTemplateSwitchMinLengthStrategy, TemplateSwitchMinLengthStrategy,
+            },
+            AlignmentStrategySelection, AlignmentStrategySelector,
+        },
+        AlignmentType,
+    },
+    template_switch_distance_context_with_memory, template_switch_distance_memory_estimate,
+    AStarTieBreaking, AlignmentSegmentCallback, AlignmentSegmentStream, RandomRestarts,
+    SearchSettings,
+};
+use crate::{
+    config::TemplateSwitchConfig,
+    error::{Error, Result},
+    guide::AlignmentGuide,
+    soft_mask::SoftMasks,
+};
+
+/// The search algorithm used by an [`Aligner`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchAlgorithm {
+    /// A*, which finds an optimal alignment.
+    #[default]
+    AStar,
+    /// Weighted A* with decreasing weights, see [`template_switch_distance_anytime_a_star_align`](super::template_switch_distance_anytime_a_star_align).
+    Anytime {
+        initial_lower_bound_weight: f64,
+        time_limit: Duration,
+    },
+    /// Iterative deepening A*, which uses memory only linear in the length of the alignment.
+    IterativeDeepening,
+}
+
+/// The settings of an [`Aligner`] that are chosen at runtime.
+#[derive(Debug, Clone, Default)]
+struct Settings {
+    search: SearchSettings,
+    secondary_score_policy: SecondaryScorePolicy,
+    search_algorithm: SearchAlgorithm,
+    self_alignment: bool,
+    fast_path_max_edit_distance: Option<usize>,
+}
+
+/// Builds an [`Aligner`].
+///
+/// Created with [`AlignerBuilder::new`], which uses the anti-diagonal node ordering, the lookahead minimum length strategy,
+/// no chaining, no limit on the number of template switches and no shortcuts, like the command line interface.
+pub struct AlignerBuilder<
+    AlphabetType: Alphabet,
+    NodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>,
+    TemplateSwitchMinLength,
+    Chaining: ChainingStrategy,
+    TemplateSwitchCount: TemplateSwitchCountStrategy,
+    Shortcut,
+> {
+    config: TemplateSwitchConfig<AlphabetType>,
+    node_ord: NodeOrd::Memory,
+    chaining_selection: Chaining::Selection,
+    template_switch_count: TemplateSwitchCount::Memory,
+    settings: Settings,
+    phantom_data: PhantomData<(TemplateSwitchMinLength, Shortcut)>,
+}
+
+/// The strategies of an [`Aligner`] built with the given strategy types.
+pub type BuiltStrategies<
+    AlphabetType,
+    NodeOrd,
+    TemplateSwitchMinLength,
+    Chaining,
+    TemplateSwitchCount,
+    Shortcut,
+> = AlignmentStrategySelection<
+    AlphabetType,
+    NodeOrd,
+    TemplateSwitchMinLength,
+    Chaining,
+    TemplateSwitchCount,
+    AllowSecondaryDeletionStrategy,
+    Shortcut,
+    AllowPrimaryMatchStrategy,
+>;
+
+/// Aligns pairs of sequences with template switches, using the strategies and settings chosen with an [`AlignerBuilder`].
+pub struct Aligner<Strategies: AlignmentStrategySelector> {
+    config: TemplateSwitchConfig<Strategies::Alphabet>,
+    node_ord: <Strategies::NodeOrd as NodeOrdStrategy<Strategies::PrimaryMatch>>::Memory,
+    chaining_selection: <Strategies::Chaining as ChainingStrategy>::Selection,
+    template_switch_count: <Strategies::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory,
+    shortcut: <Strategies::Shortcut as ShortcutStrategy>::Memory,
+    settings: Settings,
+    buffer_pool: Arc<BufferPool<Strategies>>,
+}
+
+impl<AlphabetType: Alphabet>
+    AlignerBuilder<
+        AlphabetType,
+        AntiDiagonalNodeOrdStrategy,
+        LookaheadTemplateSwitchMinLengthStrategy,
+        NoChainingStrategy,
+        NoTemplateSwitchCountStrategy,
+        NoShortcutStrategy,
+    >
+{
+    pub fn new(config: TemplateSwitchConfig<AlphabetType>) -> Self {
+        Self {
+            config,
+            node_ord: (),
+            chaining_selection: (),
+            template_switch_count: (),
+            settings: Settings::default(),
+            phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<
+        AlphabetType: Alphabet,
+        NodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>,
+        TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy,
+        Chaining: ChainingStrategy,
+        TemplateSwitchCount: TemplateSwitchCountStrategy,
+        Shortcut: ShortcutStrategy,
+    >
+    AlignerBuilder<
+        AlphabetType,
+        NodeOrd,
+        TemplateSwitchMinLength,
+        Chaining,
+        TemplateSwitchCount,
+        Shortcut,
+    >
+{
+    /// Order nodes with equal cost with the given strategy.
+    pub fn node_ord<NewNodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>>(
+        self,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        NewNodeOrd,
+        TemplateSwitchMinLength,
+        Chaining,
+        TemplateSwitchCount,
+        Shortcut,
+    > {
+        AlignerBuilder {
+            config: self.config,
+            node_ord: Default::default(),
+            chaining_selection: self.chaining_selection,
+            template_switch_count: self.template_switch_count,
+            settings: self.settings,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Order nodes with equal cost by their score under the given model, see [`LearnedNodeOrdStrategy`].
+    pub fn learned_node_ord(
+        self,
+        model: NodeOrdModel,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        LearnedNodeOrdStrategy,
+        TemplateSwitchMinLength,
+        Chaining,
+        TemplateSwitchCount,
+        Shortcut,
+    > {
+        self.node_ord::<LearnedNodeOrdStrategy>()
+            .node_ord_memory(model)
+    }
+
+    /// Set the data shared by all nodes of the node ordering strategy.
+    ///
+    /// Defaults to the default of the memory type, which is `()` for strategies without memory.
+    pub fn node_ord_memory(mut self, node_ord: NodeOrd::Memory) -> Self {
+        self.node_ord = node_ord;
+        self
+    }
+
+    /// Enforce the minimum length of template switches with the given strategy.
+    pub fn template_switch_min_length<
+        NewTemplateSwitchMinLength: TemplateSwitchMinLengthStrategy,
+    >(
+        self,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        NodeOrd,
+        NewTemplateSwitchMinLength,
+        Chaining,
+        TemplateSwitchCount,
+        Shortcut,
+    > {
+        AlignerBuilder {
+            config: self.config,
+            node_ord: self.node_ord,
+            chaining_selection: self.chaining_selection,
+            template_switch_count: self.template_switch_count,
+            settings: self.settings,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Apply chaining with the given strategy.
+    ///
+    /// The selection is `()` for strategies chosen at compile time,
+    /// and a [`RuntimeChainingSelection`](super::template_switch_distance::strategies::chaining::RuntimeChainingSelection)
+    /// for the [`RuntimeChainingStrategy`](super::template_switch_distance::strategies::chaining::RuntimeChainingStrategy).
+    pub fn chaining<NewChaining: ChainingStrategy>(
+        self,
+        selection: NewChaining::Selection,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        NodeOrd,
+        TemplateSwitchMinLength,
+        NewChaining,
+        TemplateSwitchCount,
+        Shortcut,
+    > {
+        AlignerBuilder {
+            config: self.config,
+            node_ord: self.node_ord,
+            chaining_selection: selection,
+            template_switch_count: self.template_switch_count,
+            settings: self.settings,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Allow at most `max_template_switch_count` template switches per alignment.
+    pub fn max_template_switch_count(
+        self,
+        max_template_switch_count: usize,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        NodeOrd,
+        TemplateSwitchMinLength,
+        Chaining,
+        MaxTemplateSwitchCountStrategy,
+        Shortcut,
+    > {
+        AlignerBuilder {
+            config: self.config,
+            node_ord: self.node_ord,
+            chaining_selection: self.chaining_selection,
+            template_switch_count: max_template_switch_count,
+            settings: self.settings,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Open the ends of template switches directly from their starts, using a lower bound on the cost of the template switch.
+    ///
+    /// This produces suboptimal alignments and is mostly useful to compute lower bounds.
+    pub fn template_switch_lower_bound_shortcut(
+        self,
+    ) -> AlignerBuilder<
+        AlphabetType,
+        NodeOrd,
+        TemplateSwitchMinLength,
+        Chaining,
+        TemplateSwitchCount,
+        TemplateSwitchLowerBoundShortcutStrategy,
+    > {
+        AlignerBuilder {
+            config: self.config,
+            node_ord: self.node_ord,
+            chaining_selection: self.chaining_selection,
+            template_switch_count: self.template_switch_count,
+            settings: self.settings,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Stop the search once all alignments of at most this cost were explored.
+    ///
+    /// Defaults to no limit.
+    pub fn max_cost(mut self, max_cost: Option<Cost>) -> Self {
+        self.settings.search.max_cost = max_cost;
+        self
+    }
+
+    /// Defaults to [`AStarTieBreaking::NodeOrd`].
+    pub fn tie_breaking(mut self, tie_breaking: AStarTieBreaking) -> Self {
+        self.settings.search.tie_breaking = tie_breaking;
+        self
+    }
+
+    /// Choose which co-optimal alignments are preferred with [`AStarTieBreaking::SecondaryScore`].
+    ///
+    /// Defaults to [`SecondaryScorePolicy::MaximiseMatches`].
+    pub fn secondary_score_policy(mut self, secondary_score_policy: SecondaryScorePolicy) -> Self {
+        self.settings.secondary_score_policy = secondary_score_policy;
+        self
+    }
+
+    /// Abort the search if its open and closed lists use more than this many bytes.
+    ///
+    /// Defaults to no limit. Ignored by [`SearchAlgorithm::IterativeDeepening`].
+    pub fn memory_limit(mut self, memory_limit: Option<usize>) -> Self {
+        self.settings.search.memory_limit = memory_limit;
+        self
+    }
+
+    /// Abort the search if it takes longer than this, and return an alignment result without target.
+    ///
+    /// The cost of that result is a lower bound of the optimal cost, unless it is zero because no lower bound is known.
+    /// With [`SearchAlgorithm::Anytime`], the best alignment found so far is returned instead, if any.
+    /// Defaults to no limit. Ignored by [`SearchAlgorithm::IterativeDeepening`].
+    pub fn time_limit(mut self, time_limit: Option<Duration>) -> Self {
+        self.settings.search.time_limit = time_limit;
+        self
+    }
+
+    /// Abort the search when the token is cancelled, and return an alignment result without target.
+    ///
+    /// The token can be cancelled from another thread, and applies to all alignments of the built aligner.
+    /// The result is the same as for an exceeded [`time_limit`](Self::time_limit).
+    /// Defaults to no token. Ignored by [`SearchAlgorithm::IterativeDeepening`].
+    pub fn cancellation_token(mut self, cancellation_token: Option<CancellationToken>) -> Self {
+        self.settings.search.cancellation_token = cancellation_token;
+        self
+    }
+
+    /// Enumerate up to this many co-optimal paths, see [`template_switch_distance_a_star_align`](super::template_switch_distance_a_star_align).
+    ///
+    /// Defaults to none. Only supported by [`SearchAlgorithm::AStar`], and ignored by the others.
+    pub fn cooptimal_limit(mut self, cooptimal_limit: Option<usize>) -> Self {
+        self.settings.search.cooptimal_limit = cooptimal_limit;
+        self
+    }
+
+    /// Repeat the search with pseudo-random tie-breaking to sample co-optimal alignments, see [`RandomRestarts`].
+    ///
+    /// The sampled alignments are compared to the result like the co-optimal alignments enumerated with [`cooptimal_limit`](Self::cooptimal_limit).
+    /// Defaults to none. Only supported by [`SearchAlgorithm::AStar`], and ignored by the others.
+    pub fn random_restarts(mut self, random_restarts: Option<RandomRestarts>) -> Self {
+        self.settings.search.random_restarts = random_restarts;
+        self
+    }
+
+    /// Defaults to [`SearchAlgorithm::AStar`].
+    pub fn search_algorithm(mut self, search_algorithm: SearchAlgorithm) -> Self {
+        self.settings.search_algorithm = search_algorithm;
+        self
+    }
+
+    /// Require at least one template switch, for aligning a sequence to itself, see [`Context::set_self_alignment`](super::template_switch_distance::Context::set_self_alignment).
+    ///
+    /// The template switches of the resulting alignment are candidate hairpins or inverted repeats of the sequence.
+    /// Defaults to false.
+    pub fn self_alignment(mut self, self_alignment: bool) -> Self {
+        self.settings.self_alignment = self_alignment;
+        self
+    }
+
+    /// Check after the search that no node was closed with a cost plus lower bound above the cost of the alignment,
+    /// and return [`Error::InadmissibleLowerBound`](crate::error::Error::InadmissibleLowerBound) with the path to the first such node otherwise.
+    ///
+    /// This is a debugging aid for lower bounds such as chaining or the template switch lower bound matrices.
+    /// Defaults to false. Only supported by [`SearchAlgorithm::AStar`], and ignored by the others.
+    pub fn check_admissibility(mut self, check_admissibility: bool) -> Self {
+        self.settings.search.check_admissibility = check_admissibility;
+        self
+    }
+
+    /// Close tied open nodes in insertion order, such that the alignment depends only on the input and the settings,
+    /// see [`SearchSettings::deterministic`].
+    ///
+    /// Defaults to false.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.settings.search.deterministic = deterministic;
+        self
+    }
+
+    /// Align pairs whose unit-cost edit distance is at most this without template switches,
+    /// if the resulting alignment is cheaper than any template switch, see [`fast_path`](super::fast_path).
+    ///
+    /// The alignment is optimal either way, and [`AlignmentStatistics::ts_search_skipped`](super::alignment_result::AlignmentStatistics::ts_search_skipped) tells if the fast path was taken.
+    /// Otherwise, the pair is aligned with template switches, which wastes the time spent on the fast path.
+    /// Defaults to none, which disables the fast path. Ignored for [`self_alignment`](Self::self_alignment).
+    pub fn fast_path_max_edit_distance(
+        mut self,
+        fast_path_max_edit_distance: Option<usize>,
+    ) -> Self {
+        self.settings.fast_path_max_edit_distance = fast_path_max_edit_distance;
+        self
+    }
+
+    /// Precomputes the parts of the shortcut strategy that do not depend on the sequences.
+    #[expect(clippy::type_complexity)]
+    pub fn build(
+        self,
+    ) -> Result<
+        Aligner<
+            BuiltStrategies<
+                AlphabetType,
+                NodeOrd,
+                TemplateSwitchMinLength,
+                Chaining,
+                TemplateSwitchCount,
+                Shortcut,
+            >,
+        >,
+    > {
+        Ok(Aligner {
+            shortcut: Shortcut::initialise_memory(&self.config)?,
+            config: self.config,
+            node_ord: self.node_ord,
+            chaining_selection: self.chaining_selection,
+            template_switch_count: self.template_switch_count,
+            settings: self.settings,
+            buffer_pool: Default::default(),
+        })
+    }
+}
+
+impl<Strategies: AlignmentStrategySelector<PrimaryMatch = AllowPrimaryMatchStrategy>>
+    Aligner<Strategies>
+where
+    <Strategies::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory: Clone,
+    <Strategies::Shortcut as ShortcutStrategy>::Memory: Clone,
+{
+    pub fn config(&self) -> &TemplateSwitchConfig<Strategies::Alphabet> {
+        &self.config
+    }
+
+    /// The pool the buffers of each alignment are checked out of.
+    pub fn buffer_pool(&self) -> &Arc<BufferPool<Strategies>> {
+        &self.buffer_pool
+    }
+
+    /// Use the given buffer pool, e.g. to share buffers with other aligners of the same strategies.
+    pub fn with_buffer_pool(mut self, buffer_pool: Arc<BufferPool<Strategies>>) -> Self {
+        self.buffer_pool = buffer_pool;
+        self
+    }
+
+    /// Estimate the peak memory usage of aligning sequences of the given lengths,
+    /// see [`template_switch_distance_memory_estimate`].
+    pub fn memory_estimate(&self, reference_length: usize, query_length: usize) -> MemoryEstimate {
+        template_switch_distance_memory_estimate::<Strategies>(
+            reference_length,
+            query_length,
+            self.chaining_selection,
+        )
+    }
+
+    /// Align `reference` and `query`, where template switches are treated according to the soft masks, if given.
+    pub fn align<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        soft_masks: Option<SoftMasks>,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        self.align_guided(reference, query, soft_masks, None)
+    }
+
+    /// Align the whole `reference` and `query` given as text, without soft masks.
+    ///
+    /// This is the simplest way to use the aligner.
+    /// The characters are interpreted in the alphabet of the config, and an [`Error::Alphabet`](crate::error::Error::Alphabet)
+    /// is returned if a character is not part of it.
+    pub fn align_str(
+        &self,
+        reference: &str,
+        query: &str,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        let reference = VectorGenome::<Strategies::Alphabet>::from_slice_u8(reference.as_bytes())?;
+        let query = VectorGenome::<Strategies::Alphabet>::from_slice_u8(query.as_bytes())?;
+        self.align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            None,
+        )
+    }
+
+    /// Like [`align`](Self::align), but the primary alignment is restricted to the corridor of the guide, if given.
+    ///
+    /// This refines the alignment proposed by an external mapper or chainer with template switches.
+    pub fn align_guided<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        soft_masks: Option<SoftMasks>,
+        guide: Option<AlignmentGuide>,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        self.align_with_segments(reference, query, soft_masks, guide, None)
+    }
+
+    /// Like [`align_guided`](Self::align_guided), but calls `on_alignment_segment` with the consecutive segments of the alignment
+    /// as soon as they are fixed, such that very long alignments can be output before the search terminates.
+    ///
+    /// The segments concatenate to the alignment of the result, except that runs of the same alignment type may be split between segments.
+    /// Segments are streamed during the search only by [`SearchAlgorithm::AStar`] with a label-setting lower bound.
+    /// A segment is fixed once all alternatives to it are more expensive than the alignment without jumps,
+    /// which is computed first as an upper bound of the cost, unless the fast path computed it already.
+    /// Hence, segments are streamed early mostly for similar sequences.
+    /// Otherwise, including for pairs aligned on the [`fast_path_max_edit_distance`](AlignerBuilder::fast_path_max_edit_distance),
+    /// the whole alignment is passed as a single segment once it is found.
+    pub fn align_guided_streaming<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        soft_masks: Option<SoftMasks>,
+        guide: Option<AlignmentGuide>,
+        mut on_alignment_segment: impl FnMut(&[(usize, AlignmentType)]),
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        self.align_with_segments(
+            reference,
+            query,
+            soft_masks,
+            guide,
+            Some(&mut on_alignment_segment),
+        )
+    }
+
+    /// Aligns like [`align_with_segments_inner`](Self::align_with_segments_inner),
+    /// but wraps errors into an [`Error::Alignment`] with the context of the failed alignment.
+    fn align_with_segments<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        soft_masks: Option<SoftMasks>,
+        guide: Option<AlignmentGuide>,
+        on_alignment_segment: Option<AlignmentSegmentCallback<AlignmentType>>,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        self.align_with_segments_inner(reference, query, soft_masks, guide, on_alignment_segment)
+            .map_err(|error| Error::Alignment {
+                reference_range: 0..reference.len(),
+                query_range: 0..query.len(),
+                block_size: CHAINING_BLOCK_SIZE,
+                config_sha256: self.config.sha256(),
+                source: Box::new(error),
+            })
+    }
+
+    fn align_with_segments_inner<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        soft_masks: Option<SoftMasks>,
+        guide: Option<AlignmentGuide>,
+        on_alignment_segment: Option<AlignmentSegmentCallback<AlignmentType>>,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        // The cost of the alignment without jumps, which is an upper bound of the optimal cost unless a template switch is required.
+        let mut cost_without_jumps = None;
+
+        if let Some(max_edit_distance) = self.settings.fast_path_max_edit_distance {
+            if !self.settings.self_alignment
+                && banded_edit_distance(
+                    &reference.iter().collect::<Vec<_>>(),
+                    &query.iter().collect::<Vec<_>>(),
+                    max_edit_distance,
+                )
+                .is_some()
+            {
+                let mut result = self.search(
+                    reference,
+                    query,
+                    without_jumps(&self.config),
+                    soft_masks.clone(),
+                    guide.clone(),
+                    None,
+                )?;
+                // Alignments of equal cost may contain jumps, which would be missed by the fast path.
+                if let AlignmentResult::WithTarget {
+                    alignment,
+                    statistics,
+                } = &mut result
+                {
+                    if statistics.cost < self.config.min_jump_cost().as_u64() as f64 {
+                        statistics.ts_search_skipped = true;
+                        if let Some(on_alignment_segment) = on_alignment_segment {
+                            on_alignment_segment(alignment);
+                        }
+                        return Ok(result);
+                    }
+                    cost_without_jumps = Some(statistics.cost);
+                }
+            }
+        }
+
+        let segment_stream = if let Some(on_alignment_segment) = on_alignment_segment {
+            if cost_without_jumps.is_none()
+                && !self.settings.self_alignment
+                && self.settings.search_algorithm == SearchAlgorithm::AStar
+            {
+                if let AlignmentResult::WithTarget { statistics, .. } = self.search(
+                    reference,
+                    query,
+                    without_jumps(&self.config),
+                    soft_masks.clone(),
+                    guide.clone(),
+                    None,
+                )? {
+                    cost_without_jumps = Some(statistics.cost);
+                }
+            }
+
+            Some(AlignmentSegmentStream {
+                // Rounding down would make the bound lower than the cost of the alignment without jumps,
+                // such that open nodes that can still reach the target would be dropped from the fixed prefix.
+                cost_upper_bound: cost_without_jumps
+                    .map(|cost| Cost::from(cost.raw().ceil() as u64)),
+                on_alignment_segment,
+            })
+        } else {
+            None
+        };
+
+        self.search(
+            reference,
+            query,
+            self.config.clone(),
+            soft_masks,
+            guide,
+            segment_stream,
+        )
+    }
+
+    fn search<SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized>(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        config: TemplateSwitchConfig<Strategies::Alphabet>,
+        soft_masks: Option<SoftMasks>,
+        guide: Option<AlignmentGuide>,
+        segment_stream: Option<AlignmentSegmentStream<AlignmentType>>,
+    ) -> Result<AlignmentResult<AlignmentType>> {
+        let mut context = template_switch_distance_context_with_memory::<Strategies, _>(
+            reference,
+            query,
+            config,
+            self.settings.search.max_cost,
+            self.chaining_selection,
+            self.node_ord.clone(),
+            self.template_switch_count.clone(),
+            self.shortcut.clone(),
+            soft_masks,
+        )?;
+        context.set_secondary_score_policy(self.settings.secondary_score_policy);
+        context.set_self_alignment(self.settings.self_alignment);
+        context.set_guide(guide);
+
+        if self.settings.search_algorithm == SearchAlgorithm::IterativeDeepening {
+            return ida_star_align(context)
+                .inspect(|result| stream_whole_alignment(result, segment_stream));
+        }
+
+        let buffers = self.buffer_pool.check_out();
+        context.a_star_buffers = buffers.lookahead;
+        context.memory.template_switch_min_length = buffers.template_switch_min_length;
+        let mut a_star = AStar::new_with_buffers(context, buffers.a_star);
+
+        let result = match self.settings.search_algorithm {
+            SearchAlgorithm::AStar => {
+                a_star_align(&mut a_star, &self.settings.search, segment_stream)
+            }
+            SearchAlgorithm::Anytime {
+                initial_lower_bound_weight,
+                time_limit,
+            } => anytime_a_star_align(
+                &mut a_star,
+                &self.settings.search,
+                initial_lower_bound_weight,
+                time_limit,
+            )
+            .inspect(|result| stream_whole_alignment(result, segment_stream)),
+            SearchAlgorithm::IterativeDeepening => unreachable!("Handled above"),
+        };
+
+        let (mut context, a_star_buffers) = a_star.into_context_and_buffers();
+        self.buffer_pool.give_back(AlignmentBuffers {
+            a_star: a_star_buffers,
+            lookahead: mem::take(&mut context.a_star_buffers),
+            template_switch_min_length: mem::take(&mut context.memory.template_switch_min_length),
+        });
+        result
+    }
+}
+
+/// Passes the whole alignment of the result as a single segment, for searches that cannot stream segments.
+fn stream_whole_alignment(
+    result: &AlignmentResult<AlignmentType>,
+    segment_stream: Option<AlignmentSegmentStream<AlignmentType>>,
+) {
+    if let (AlignmentResult::WithTarget { alignment, .. }, Some(segment_stream)) =
+        (result, segment_stream)
+    {
+        (segment_stream.on_alignment_segment)(alignment);
+    }
+}
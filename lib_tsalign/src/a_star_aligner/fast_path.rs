@@ -0,0 +1,83 @@
+//! A fast path for sequence pairs that are too similar to benefit from template switches.
+//!
+//! Most pairs of a typical input differ by a few edits only.
+//! For these, the gap-affine alignment without template switches is cheaper than the base cost of a single template switch,
+//! so the template switch search cannot find a better alignment, but still pays for its lower bounds and lookaheads.
+//! A banded unit-cost edit distance cheaply recognises such pairs before anything else is computed.
+
+use compact_genome::interface::alphabet::Alphabet;
+
+use crate::config::{TemplateSwitchConfig, TemplateSwitchDirections};
+
+#[cfg(test)]
+mod tests;
+
+/// Computes the unit-cost edit distance between `reference` and `query`, if it is at most `max_distance`.
+///
+/// Only the band of `2 * max_distance + 1` diagonals around the main diagonal is computed,
+/// so this runs in `O(min(|reference|, |query|) * max_distance)` time.
+pub fn banded_edit_distance<Character: PartialEq>(
+    reference: &[Character],
+    query: &[Character],
+    max_distance: usize,
+) -> Option<usize> {
+    if reference.len().abs_diff(query.len()) > max_distance {
+        return None;
+    }
+
+    // Cell `d` of row `i` stores the distance between the first `i` reference characters and the first `i + d - max_distance` query characters.
+    let width = 2 * max_distance + 1;
+    let unreachable = max_distance + 1;
+    let query_index = |row_index: usize, d: usize| {
+        (row_index + d)
+            .checked_sub(max_distance)
+            .filter(|query_index| *query_index <= query.len())
+    };
+    let mut previous_row: Vec<_> = (0..width)
+        .map(|d| query_index(0, d).unwrap_or(unreachable))
+        .collect();
+    let mut row = vec![unreachable; width];
+
+    for (reference_index, reference_character) in reference.iter().enumerate() {
+        let row_index = reference_index + 1;
+
+        for d in 0..width {
+            row[d] = match query_index(row_index, d) {
+                Some(query_index) => {
+                    let deletion = previous_row
+                        .get(d + 1)
+                        .map_or(unreachable, |distance| distance + 1);
+                    let substitution = if query_index > 0 {
+                        previous_row[d]
+                            + usize::from(*reference_character != query[query_index - 1])
+                    } else {
+                        unreachable
+                    };
+                    let insertion = if d > 0 { row[d - 1] + 1 } else { unreachable };
+                    deletion.min(substitution).min(insertion).min(unreachable)
+                }
+                None => unreachable,
+            };
+        }
+
+        if row.iter().all(|distance| *distance > max_distance) {
+            return None;
+        }
+        std::mem::swap(&mut previous_row, &mut row);
+    }
+
+    let distance = previous_row[query.len() + max_distance - reference.len()];
+    (distance <= max_distance).then_some(distance)
+}
+
+/// The config used to align a pair on the fast path, which searches neither template switches nor other jump events.
+pub(crate) fn without_jumps<AlphabetType: Alphabet>(
+    config: &TemplateSwitchConfig<AlphabetType>,
+) -> TemplateSwitchConfig<AlphabetType> {
+    TemplateSwitchConfig {
+        directions: TemplateSwitchDirections::NONE,
+        duplication_costs: None,
+        inversion_costs: None,
+        ..config.clone()
+    }
+}
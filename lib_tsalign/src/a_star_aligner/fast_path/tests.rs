@@ -0,0 +1,121 @@
+use crate::{
+    a_star_aligner::{alignment_result::AlignmentResult, AlignerBuilder},
+    test_fixtures::{sample_config, QUERY, REFERENCE},
+};
+
+use super::banded_edit_distance;
+
+const SIMILAR_QUERY: &str = "ACGTTGCAACTCAGGATCGTAACGTTCAGGTACCATGCA";
+
+fn edit_distance(reference: &[u8], query: &[u8]) -> usize {
+    let mut row: Vec<_> = (0..=query.len()).collect();
+    for (reference_index, reference_character) in reference.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = reference_index + 1;
+        for (query_index, query_character) in query.iter().enumerate() {
+            let substitution = diagonal + usize::from(reference_character != query_character);
+            diagonal = row[query_index + 1];
+            row[query_index + 1] = substitution
+                .min(row[query_index + 1] + 1)
+                .min(row[query_index] + 1);
+        }
+    }
+    row[query.len()]
+}
+
+#[test]
+fn banded_edit_distance_matches_full_edit_distance() {
+    let sequences = [
+        "",
+        "A",
+        "ACGT",
+        "AGGT",
+        "ACGTACGT",
+        "TTTT",
+        REFERENCE,
+        QUERY,
+        SIMILAR_QUERY,
+    ];
+
+    for reference in sequences {
+        for query in sequences {
+            let distance = edit_distance(reference.as_bytes(), query.as_bytes());
+            for max_distance in 0..=distance + 2 {
+                assert_eq!(
+                    banded_edit_distance(reference.as_bytes(), query.as_bytes(), max_distance),
+                    (distance <= max_distance).then_some(distance),
+                    "{reference} {query} {max_distance}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn similar_pairs_skip_the_template_switch_search() {
+    // The sample config makes template switches cheaper than most single edits.
+    let mut config = sample_config();
+    config.base_cost = 10.into();
+    assert_eq!(config.min_jump_cost(), 10.into());
+
+    let aligner = AlignerBuilder::new(config.clone()).build().unwrap();
+    let fast_aligner = AlignerBuilder::new(config.clone())
+        .fast_path_max_edit_distance(Some(5))
+        .build()
+        .unwrap();
+
+    let alignment = aligner.align_str(REFERENCE, SIMILAR_QUERY).unwrap();
+    let fast_alignment = fast_aligner.align_str(REFERENCE, SIMILAR_QUERY).unwrap();
+    assert!(!alignment.statistics().ts_search_skipped);
+    assert!(fast_alignment.statistics().ts_search_skipped);
+    assert_eq!(
+        alignment.statistics().cost,
+        fast_alignment.statistics().cost
+    );
+    assert!(fast_alignment.statistics().cost < config.base_cost.as_u64() as f64);
+    assert!(
+        fast_alignment.statistics().opened_nodes < alignment.statistics().opened_nodes,
+        "{fast_alignment}"
+    );
+}
+
+#[test]
+fn pairs_benefiting_from_template_switches_are_searched() {
+    let aligner = AlignerBuilder::new(sample_config()).build().unwrap();
+    let alignment = aligner.align_str(REFERENCE, QUERY).unwrap();
+
+    for max_edit_distance in [0, 40] {
+        let fast_alignment = AlignerBuilder::new(sample_config())
+            .fast_path_max_edit_distance(Some(max_edit_distance))
+            .build()
+            .unwrap()
+            .align_str(REFERENCE, QUERY)
+            .unwrap();
+        assert!(!fast_alignment.statistics().ts_search_skipped);
+        let (
+            AlignmentResult::WithTarget {
+                alignment: expected,
+                ..
+            },
+            AlignmentResult::WithTarget {
+                alignment: actual, ..
+            },
+        ) = (&alignment, &fast_alignment)
+        else {
+            panic!("No alignment found");
+        };
+        assert_eq!(expected, actual);
+    }
+    assert!(alignment.cigar().contains("[TS"));
+}
+
+#[test]
+fn self_alignments_are_searched() {
+    let fast_aligner = AlignerBuilder::new(sample_config())
+        .self_alignment(true)
+        .fast_path_max_edit_distance(Some(5))
+        .build()
+        .unwrap();
+    let alignment = fast_aligner.align_str(REFERENCE, REFERENCE).unwrap();
+    assert!(!alignment.statistics().ts_search_skipped);
+}
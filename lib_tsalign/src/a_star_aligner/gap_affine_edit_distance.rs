@@ -5,7 +5,10 @@ use generic_a_star::{reset::Reset, AStarContext, AStarNode};
 
 use crate::costs::cost::Cost;
 
-use super::{alignment_result::IAlignmentType, AlignmentContext};
+use super::{
+    alignment_result::{AlignmentColumn, AlignmentRegion, IAlignmentType},
+    AlignmentContext,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Node {
@@ -15,14 +18,14 @@ pub struct Node {
     cost: Cost,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Identifier {
     reference_index: usize,
     query_index: usize,
     gap_type: GapType,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 enum GapType {
     Insertion,
     Deletion,
@@ -236,10 +239,7 @@ impl PartialOrd for Node {
 impl Ord for Node {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.cost.cmp(&other.cost) {
-            // This secondary ordering may make things actually slower.
-            // While it does reduce the number of visited nodes a little bit,
-            // it also makes heap operations more expensive.
-            // Preliminary testing showed that this would be a slowdown.
+            // Break ties by anti-diagonal, see `AStarTieBreaking::NodeOrd` for why this may be slower.
             std::cmp::Ordering::Equal => other
                 .identifier
                 .anti_diagonal()
@@ -348,4 +348,15 @@ impl IAlignmentType for AlignmentType {
     fn is_internal(&self) -> bool {
         self == &Self::Root
     }
+
+    fn alignment_column(&self) -> Option<(AlignmentRegion, AlignmentColumn)> {
+        let column = match self {
+            Self::Insertion => AlignmentColumn::Insertion,
+            Self::Deletion => AlignmentColumn::Deletion,
+            Self::Substitution => AlignmentColumn::Mismatch,
+            Self::Match => AlignmentColumn::Match,
+            Self::Root => return None,
+        };
+        Some((AlignmentRegion::Primary, column))
+    }
 }
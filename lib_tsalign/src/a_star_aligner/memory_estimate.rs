@@ -0,0 +1,63 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// An estimate of the peak memory usage of an alignment, computed before running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryEstimate {
+    /// The estimated number of nodes opened by the search.
+    pub node_count: usize,
+    /// The memory required per node in bytes, including its entry in the closed list.
+    pub bytes_per_node: usize,
+    /// The memory required by precomputed lower bounds in bytes.
+    pub lower_bound_bytes: usize,
+}
+
+impl MemoryEstimate {
+    pub fn node_bytes(&self) -> usize {
+        self.node_count.saturating_mul(self.bytes_per_node)
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.node_bytes().saturating_add(self.lower_bound_bytes)
+    }
+}
+
+impl Display for MemoryEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Estimated nodes: {}", self.node_count)?;
+        writeln!(f, "Estimated bytes per node: {}", self.bytes_per_node)?;
+        writeln!(
+            f,
+            "Estimated node memory: {}",
+            format_bytes(self.node_bytes())
+        )?;
+        writeln!(
+            f,
+            "Estimated lower bound memory: {}",
+            format_bytes(self.lower_bound_bytes)
+        )?;
+        write!(
+            f,
+            "Estimated peak memory: {}",
+            format_bytes(self.total_bytes())
+        )
+    }
+}
+
+/// Format a number of bytes with a binary unit prefix.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes}B")
+    } else {
+        format!("{value:.2}{}", UNITS[unit])
+    }
+}
@@ -0,0 +1,418 @@
+//! Property tests that compare the aligners against each other on random small sequences.
+//!
+//! They are enabled by the `proptest` feature, e.g. `cargo test -p lib_tsalign --features proptest`.
+//! By default, the sample template switch config is used.
+//! A different config can be tested by setting the environment variable `TSALIGN_PROPTEST_CONFIG` to its path,
+//! and the number of cases can be set via `PROPTEST_CASES`.
+
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::{
+        alphabet::Alphabet,
+        sequence::{GenomeSequence, OwnedGenomeSequence},
+    },
+};
+use generic_a_star::{cost::Cost, AStar, AStarContext, AStarNode};
+use proptest::prelude::*;
+use traitsequence::interface::Sequence;
+
+use crate::{
+    a_star_aligner::template_switch_distance::strategies::{
+        chaining::{
+            ChainingStrategy, LowerBoundChainingStrategy, NoChainingStrategy,
+            RuntimeChainingSelection, RuntimeChainingStrategy, CHAINING_BLOCK_SIZE,
+        },
+        node_ord::AntiDiagonalNodeOrdStrategy,
+        primary_match::AllowPrimaryMatchStrategy,
+        secondary_deletion::AllowSecondaryDeletionStrategy,
+        shortcut::NoShortcutStrategy,
+        template_switch_count::NoTemplateSwitchCountStrategy,
+        template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
+        AlignmentStrategySelection,
+    },
+    alignment_matrix::{affine::AffineAlignmentMatrix, hirschberg::HirschbergAligner},
+    config::TemplateSwitchConfig,
+    costs::gap_affine::{GapAffineAlignmentCostTable, IndelType},
+    test_fixtures::SAMPLE_CONFIG,
+};
+
+use super::{
+    a_star_align, gap_affine_edit_distance::ScoringTable, gap_affine_edit_distance_a_star_align,
+    template_switch_distance_a_star_align, template_switch_distance_context, SearchSettings,
+};
+
+/// The sample config contains costs for `N`, so it needs to be parsed with an alphabet that contains it.
+type AlphabetType = DnaAlphabetOrN;
+
+/// The characters used in the random sequences.
+const CHARACTERS: &[u8] = b"ACGT";
+
+type Strategies<Chaining> = AlignmentStrategySelection<
+    AlphabetType,
+    AntiDiagonalNodeOrdStrategy,
+    NoTemplateSwitchMinLengthStrategy,
+    Chaining,
+    NoTemplateSwitchCountStrategy,
+    AllowSecondaryDeletionStrategy,
+    NoShortcutStrategy,
+    AllowPrimaryMatchStrategy,
+>;
+
+fn config() -> TemplateSwitchConfig<AlphabetType> {
+    if let Ok(path) = std::env::var("TSALIGN_PROPTEST_CONFIG") {
+        let file = std::fs::File::open(&path)
+            .unwrap_or_else(|error| panic!("Error opening config file {path:?}: {error}"));
+        TemplateSwitchConfig::read_plain(std::io::BufReader::new(file))
+    } else {
+        TemplateSwitchConfig::read_plain(SAMPLE_CONFIG.as_bytes())
+    }
+    .unwrap_or_else(|error| panic!("Error parsing template switch config:\n{error}"))
+}
+
+/// Computing the lower bound matrices is slow in debug builds, so run fewer cases than usual unless requested otherwise.
+fn proptest_config() -> ProptestConfig {
+    let config = ProptestConfig::default();
+    if std::env::var_os("PROPTEST_CASES").is_some() {
+        config
+    } else {
+        ProptestConfig { cases: 8, ..config }
+    }
+}
+
+/// Returns the scoring table of the gap-affine aligner that is equivalent to the given costs,
+/// or `None` if the costs depend on the characters in [`CHARACTERS`].
+fn uniform_scoring_table(
+    costs: &GapAffineAlignmentCostTable<AlphabetType>,
+) -> Option<ScoringTable> {
+    let characters: Vec<_> = CHARACTERS
+        .iter()
+        .map(|&character| AlphabetType::ascii_to_character(character).unwrap())
+        .collect();
+    let a = characters[0];
+    let c = characters[1];
+    let scoring_table = ScoringTable {
+        match_cost: costs.match_cost(a, a),
+        substitution_cost: costs.substitution_cost(a, c),
+        gap_open_cost: costs.gap_open_cost(a, IndelType::Insertion),
+        gap_extend_cost: costs.gap_extend_cost(a, IndelType::Insertion),
+    };
+
+    characters
+        .iter()
+        .all(|c1| {
+            [IndelType::Insertion, IndelType::Deletion]
+                .into_iter()
+                .all(|indel_type| {
+                    costs.gap_open_cost(*c1, indel_type) == scoring_table.gap_open_cost
+                        && costs.gap_extend_cost(*c1, indel_type) == scoring_table.gap_extend_cost
+                })
+                && characters.iter().all(|c2| {
+                    if c1 == c2 {
+                        costs.match_cost(*c1, *c2) == scoring_table.match_cost
+                    } else {
+                        costs.substitution_cost(*c1, *c2) == scoring_table.substitution_cost
+                    }
+                })
+        })
+        .then_some(scoring_table)
+}
+
+/// Returns the primary edit costs of `config` with uniform gap costs that differ between insertions and deletions.
+///
+/// If `mirrored` is true, then the gap costs of insertions and deletions are swapped.
+fn asymmetric_gap_config(mirrored: bool) -> TemplateSwitchConfig<AlphabetType> {
+    let mut config = config();
+    let alphabet_size = usize::from(AlphabetType::SIZE);
+    let (insertion_costs, deletion_costs) = ((6, 1), (3, 2));
+    let ((open, extend), (deletion_open, deletion_extend)) = if mirrored {
+        (deletion_costs, insertion_costs)
+    } else {
+        (insertion_costs, deletion_costs)
+    };
+
+    config.primary_edit_costs = GapAffineAlignmentCostTable::new_uniform(
+        "Asymmetric",
+        4.into(),
+        open.into(),
+        extend.into(),
+    )
+    .with_deletion_gap_cost_vectors(
+        vec![Cost::from(deletion_open); alphabet_size],
+        vec![Cost::from(deletion_extend); alphabet_size],
+    );
+    config.base_cost = Cost::MAX;
+    config
+}
+
+/// Generates a random reference and a query that differs from it by a few random edits.
+///
+/// Unrelated random sequences make the exact aligner explore almost the whole search space,
+/// which is too slow for property testing.
+fn sequence_pair(
+    min_length: usize,
+    max_length: usize,
+) -> impl Strategy<Value = (VectorGenome<AlphabetType>, VectorGenome<AlphabetType>)> {
+    let character = || proptest::sample::select(CHARACTERS);
+    (
+        proptest::collection::vec(character(), min_length..=max_length),
+        proptest::collection::vec((any::<prop::sample::Index>(), 0..3u8, character()), 0..=3),
+    )
+        .prop_map(|(reference, edits)| {
+            let mut query = reference.clone();
+            for (index, edit, character) in edits {
+                if query.is_empty() {
+                    query.push(character);
+                    continue;
+                }
+
+                let index = index.index(query.len());
+                match edit {
+                    0 => query[index] = character,
+                    1 => query.insert(index, character),
+                    _ => {
+                        query.remove(index);
+                    }
+                }
+            }
+
+            (
+                VectorGenome::from_slice_u8(&reference).unwrap(),
+                VectorGenome::from_slice_u8(&query).unwrap(),
+            )
+        })
+}
+
+/// Generates a random reference and a query in which a part of the reference is replaced by the reverse complement of another part.
+fn template_switch_pair(
+    length: usize,
+    max_template_switch_length: usize,
+) -> impl Strategy<Value = (VectorGenome<AlphabetType>, VectorGenome<AlphabetType>)> {
+    let character = || proptest::sample::select(CHARACTERS);
+    (
+        proptest::collection::vec(character(), length),
+        0..length - max_template_switch_length,
+        0..length - max_template_switch_length,
+        1..=max_template_switch_length,
+    )
+        .prop_map(|(reference, replaced, copied, template_switch_length)| {
+            let complement = |character: u8| match character {
+                b'A' => b'T',
+                b'C' => b'G',
+                b'G' => b'C',
+                _ => b'A',
+            };
+            let mut query = reference.clone();
+            for offset in 0..template_switch_length {
+                query[replaced + offset] =
+                    complement(reference[copied + template_switch_length - 1 - offset]);
+            }
+
+            (
+                VectorGenome::from_slice_u8(&reference).unwrap(),
+                VectorGenome::from_slice_u8(&query).unwrap(),
+            )
+        })
+}
+
+fn exact_cost(
+    reference: &VectorGenome<AlphabetType>,
+    query: &VectorGenome<AlphabetType>,
+    config: TemplateSwitchConfig<AlphabetType>,
+) -> f64 {
+    template_switch_distance_a_star_align::<Strategies<NoChainingStrategy>, _>(
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        config,
+        (),
+        None,
+        &SearchSettings::default(),
+    )
+    .unwrap()
+    .statistics()
+    .cost
+    .raw()
+}
+
+proptest! {
+    #![proptest_config(proptest_config())]
+
+    #[test]
+    fn disabled_template_switches_equal_gap_affine(
+        (reference, query) in sequence_pair(0, 15),
+    ) {
+        let mut config = config();
+        let Some(scoring_table) = uniform_scoring_table(&config.primary_edit_costs) else {
+            return Err(TestCaseError::reject("primary edit costs are not uniform"));
+        };
+        config.base_cost = Cost::MAX;
+
+        let gap_affine_cost = gap_affine_edit_distance_a_star_align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            scoring_table,
+            &Default::default(),
+        )
+        .unwrap()
+        .statistics()
+        .cost
+        .raw();
+
+        prop_assert_eq!(exact_cost(&reference, &query, config), gap_affine_cost);
+    }
+
+    #[test]
+    fn asymmetric_gaps_are_mirrored_by_swapping_sequences(
+        (reference, query) in sequence_pair(0, 15),
+    ) {
+        // An insertion into the query is a deletion from the reference, and vice versa.
+        let cost = exact_cost(&reference, &query, asymmetric_gap_config(false));
+        let mirrored_cost = exact_cost(&query, &reference, asymmetric_gap_config(true));
+
+        prop_assert_eq!(cost, mirrored_cost);
+    }
+
+    #[test]
+    fn gap_affine_equals_alignment_matrix(
+        (reference, query) in sequence_pair(0, 15),
+    ) {
+        let Some(scoring_table) = uniform_scoring_table(&config().primary_edit_costs) else {
+            return Err(TestCaseError::reject("primary edit costs are not uniform"));
+        };
+
+        let matrix_cost = AffineAlignmentMatrix::new(scoring_table, reference.len(), query.len())
+            .align_with_traceback(reference.as_genome_subsequence(), query.as_genome_subsequence())
+            .statistics()
+            .cost;
+        let gap_affine_cost = gap_affine_edit_distance_a_star_align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            scoring_table,
+            &Default::default(),
+        )
+        .unwrap()
+        .statistics()
+        .cost;
+
+        let hirschberg_cost = HirschbergAligner::new(scoring_table)
+            .align(reference.as_genome_subsequence(), query.as_genome_subsequence())
+            .statistics()
+            .cost;
+
+        prop_assert_eq!(matrix_cost, gap_affine_cost);
+        prop_assert_eq!(matrix_cost, hirschberg_cost);
+    }
+
+    #[test]
+    fn chaining_lower_bounds_are_admissible(
+        (reference, query) in sequence_pair(CHAINING_BLOCK_SIZE, 30),
+    ) {
+        let config = config();
+        let exact_cost = exact_cost(&reference, &query, config.clone());
+        let context = template_switch_distance_context::<Strategies<LowerBoundChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config,
+            None,
+            (),
+            None,
+        )
+        .unwrap();
+
+        let root = LowerBoundChainingStrategy::apply_lower_bound(context.create_root(), &context);
+        prop_assert!(root.a_star_lower_bound().as_u64() as f64 <= exact_cost);
+
+        // An inadmissible lower bound anywhere in the search space may make the search miss the optimum.
+        let chaining_cost = a_star_align(&mut AStar::new(context), &SearchSettings::default(), None)
+            .unwrap()
+            .statistics()
+            .cost
+            .raw();
+        prop_assert_eq!(chaining_cost, exact_cost);
+    }
+
+    #[test]
+    fn runtime_chaining_equals_static_chaining(
+        (reference, query) in sequence_pair(CHAINING_BLOCK_SIZE, 30),
+    ) {
+        let static_result = template_switch_distance_a_star_align::<Strategies<LowerBoundChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config(),
+            (),
+            None,
+            &SearchSettings::default(),
+        )
+        .unwrap();
+        let runtime_result = template_switch_distance_a_star_align::<Strategies<RuntimeChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config(),
+            RuntimeChainingSelection::LowerBound,
+            None,
+            &SearchSettings::default(),
+        )
+        .unwrap();
+
+        prop_assert_eq!(runtime_result.statistics().cost, static_result.statistics().cost);
+        prop_assert_eq!(
+            runtime_result.statistics().opened_nodes,
+            static_result.statistics().opened_nodes
+        );
+    }
+
+    #[test]
+    fn secondary_match_run_skipping_is_exact(
+        (reference, query) in template_switch_pair(25, 12),
+    ) {
+        let mut context = template_switch_distance_context::<Strategies<NoChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config(),
+            None,
+            (),
+            None,
+        )
+        .unwrap();
+        context.set_skip_secondary_match_runs(false);
+        let exact_cost = a_star_align(&mut AStar::new(context), &SearchSettings::default(), None).unwrap();
+
+        let skipping_cost = template_switch_distance_a_star_align::<Strategies<NoChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config(),
+            (),
+            None,
+            &SearchSettings::default(),
+        )
+        .unwrap();
+
+        prop_assert_eq!(skipping_cost.statistics().cost, exact_cost.statistics().cost);
+    }
+
+    #[test]
+    fn primary_match_run_skipping_is_exact(
+        (reference, query) in sequence_pair(1, 30),
+    ) {
+        // Primary match runs are only skipped where no template switch can start.
+        let mut config = config();
+        config.base_cost = Cost::MAX;
+
+        let mut context = template_switch_distance_context::<Strategies<NoChainingStrategy>, _>(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            config.clone(),
+            None,
+            (),
+            None,
+        )
+        .unwrap();
+        context.set_skip_primary_match_runs(false);
+        let non_skipping_cost = a_star_align(&mut AStar::new(context), &SearchSettings::default(), None)
+            .unwrap()
+            .statistics()
+            .cost
+            .raw();
+
+        prop_assert_eq!(exact_cost(&reference, &query, config), non_skipping_cost);
+    }
+}
@@ -1,8 +1,8 @@
 use std::fmt::Display;
 
 use compact_genome::interface::sequence::GenomeSequence;
-use generic_a_star::AStarNode;
-use identifier::{GapType, TemplateSwitchPrimary, TemplateSwitchSecondary};
+use generic_a_star::{heuristic::AStarNodeWithLowerBound, AStarNode};
+use identifier::GapType;
 use num_traits::SaturatingSub;
 use strategies::{
     node_ord::NodeOrdStrategy, primary_match::PrimaryMatchStrategy,
@@ -11,17 +11,25 @@ use strategies::{
 };
 
 use crate::costs::cost::Cost;
+use secondary_score::SecondaryScorePolicy;
 
 mod alignment_type;
+pub mod cigar;
 pub mod context;
 pub mod display;
 mod identifier;
 pub mod lower_bounds;
+pub mod polish;
+pub mod primary_match_runs;
+pub mod secondary_match_runs;
+pub mod secondary_score;
 pub mod strategies;
 
 pub use alignment_type::AlignmentType;
 pub use context::Context;
-pub use identifier::Identifier;
+pub use identifier::{
+    Identifier, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node<Strategies: AlignmentStrategySelector> {
@@ -36,6 +44,9 @@ pub struct NodeData<PrimaryExtraData: Copy> {
     predecessor_edge_type: AlignmentType,
     cost: Cost,
     a_star_lower_bound: Cost,
+    /// The score used to break ties with [`AStarTieBreaking::SecondaryScore`](generic_a_star::comparator::AStarTieBreaking::SecondaryScore),
+    /// see [`SecondaryScorePolicy`].
+    secondary_score: usize,
 }
 
 impl<Strategies: AlignmentStrategySelector> AStarNode for Node<Strategies> {
@@ -62,6 +73,16 @@ impl<Strategies: AlignmentStrategySelector> AStarNode for Node<Strategies> {
     fn predecessor_edge_type(&self) -> Option<Self::EdgeType> {
         Some(self.node_data.predecessor_edge_type)
     }
+
+    fn secondary_maximisable_score(&self) -> usize {
+        self.node_data.secondary_score
+    }
+}
+
+impl<Strategies: AlignmentStrategySelector> AStarNodeWithLowerBound for Node<Strategies> {
+    fn raise_a_star_lower_bound(&mut self, lower_bound: Cost) {
+        self.node_data.a_star_lower_bound = self.node_data.a_star_lower_bound.max(lower_bound);
+    }
 }
 
 impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
@@ -77,9 +98,11 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 reference_index,
                 query_index,
                 gap_type: GapType::None,
+                homopolymer_length: 0,
                 flank_index: 0,
+                template_switched: false,
                 data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::create_root_identifier_primary_extra_data( context),
-            }),
+            }, context.secondary_score_policy()),
             strategies: AlignmentStrategiesNodeMemory::create_root(context),
         }
     }
@@ -124,12 +147,40 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         ))
     }
 
+    /// Skip over `run_length` consecutive primary matches outside of flanks.
+    fn generate_primary_match_run_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        run_length: usize,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Self {
+        let predecessor_identifier @ (Identifier::Primary { .. }
+        | Identifier::PrimaryReentry { .. }) = self.node_data.identifier
+        else {
+            unreachable!("This method is only called on primary nodes.")
+        };
+        let alignment_type = AlignmentType::PrimaryMatchRun { length: run_length };
+
+        self.generate_successor(
+            predecessor_identifier.generate_primary_match_run_successor(
+                run_length,
+                alignment_type,
+                context,
+            ),
+            Cost::ZERO,
+            alignment_type,
+            context,
+        )
+    }
+
     fn generate_primary_deletion_successor<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
     >(
         &self,
         successor_flank_index: isize,
-        cost_increment: Cost,
+        long_gap: bool,
+        (cost_increment, homopolymer_length): (Cost, u8),
         context: &Context<SubsequenceType, Strategies>,
     ) -> Option<Self> {
         if cost_increment == Cost::MAX {
@@ -150,6 +201,8 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         Some(self.generate_successor(
             predecessor_identifier.generate_primary_deletion_successor(
                 successor_flank_index,
+                long_gap,
+                homopolymer_length,
                 alignment_type,
                 context,
             ),
@@ -164,7 +217,8 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
     >(
         &self,
         successor_flank_index: isize,
-        cost_increment: Cost,
+        long_gap: bool,
+        (cost_increment, homopolymer_length): (Cost, u8),
         context: &Context<SubsequenceType, Strategies>,
     ) -> Option<Self> {
         if cost_increment == Cost::MAX {
@@ -185,6 +239,8 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         Some(self.generate_successor(
             predecessor_identifier.generate_primary_insertion_successor(
                 successor_flank_index,
+                long_gap,
+                homopolymer_length,
                 alignment_type,
                 context,
             ),
@@ -200,22 +256,56 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
     >(
         &'result self,
         cost_increment: Cost,
+        template_switch_strand: TemplateSwitchStrand,
         context: &'result Context<SubsequenceType, Strategies>,
     ) -> impl 'result + Iterator<Item = Self> {
-        if !matches!(
-            self.node_data.identifier,
-            Identifier::Primary { .. } | Identifier::PrimaryReentry { .. }
-        ) {
-            unreachable!("This method is only called on primary nodes.")
+        let (Identifier::Primary {
+            reference_index,
+            query_index,
+            ..
         }
+        | Identifier::PrimaryReentry {
+            reference_index,
+            query_index,
+            ..
+        }) = self.node_data.identifier
+        else {
+            unreachable!("This method is only called on primary nodes.")
+        };
+        let is_allowed = context.config.allows_template_switch_at(
+            reference_index,
+            context.reference.len(),
+            query_index,
+            context.query.len(),
+        );
 
         self.node_data
             .identifier
-            .generate_initial_template_switch_entrance_successors()
+            .generate_initial_template_switch_entrance_successors(template_switch_strand)
+            .filter(move |identifier| {
+                if !is_allowed {
+                    return false;
+                }
+
+                let Identifier::TemplateSwitchEntrance {
+                    template_switch_primary,
+                    template_switch_secondary,
+                    ..
+                } = identifier
+                else {
+                    unreachable!("This closure is only called on template switch entrances.")
+                };
+
+                context
+                    .config
+                    .directions
+                    .allows(*template_switch_primary, *template_switch_secondary)
+            })
             .map(move |identifier| {
                 let Identifier::TemplateSwitchEntrance {
                     template_switch_primary,
                     template_switch_secondary,
+                    template_switch_strand,
                     template_switch_first_offset,
                     ..
                 } = &identifier
@@ -229,6 +319,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                     AlignmentType::TemplateSwitchEntrance {
                         primary: *template_switch_primary,
                         secondary: *template_switch_secondary,
+                        strand: *template_switch_strand,
                         first_offset: *template_switch_first_offset,
                     },
                     context,
@@ -253,6 +344,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             entrance_query_index,
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             ..
         } = self.node_data.identifier
         else {
@@ -265,12 +357,14 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 template_switch_first_offset: successor_template_switch_first_offset,
             },
             cost_increment,
             AlignmentType::TemplateSwitchEntrance {
                 primary: template_switch_primary,
                 secondary: template_switch_secondary,
+                strand: template_switch_strand,
                 first_offset: successor_template_switch_first_offset,
             },
             context,
@@ -296,6 +390,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             entrance_query_index,
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             template_switch_first_offset,
         } = self.node_data.identifier
         else {
@@ -319,10 +414,12 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length: 0,
                 primary_index,
                 secondary_index,
                 gap_type: GapType::None,
+                homopolymer_length: 0,
             },
             0.into(),
             AlignmentType::SecondaryRoot,
@@ -363,12 +460,34 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         ))
     }
 
+    /// Skip over `run_length` consecutive secondary matches.
+    fn generate_secondary_match_run_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        run_length: usize,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Self {
+        let predecessor_identifier @ Identifier::Secondary { .. } = self.node_data.identifier
+        else {
+            unreachable!("This method is only called on secondary nodes.")
+        };
+
+        self.generate_successor(
+            predecessor_identifier.generate_secondary_match_run_successor(run_length),
+            Cost::ZERO,
+            AlignmentType::SecondaryMatchRun { length: run_length },
+            context,
+        )
+    }
+
     /// The secondary contains a base missing in the primary.
     fn generate_secondary_deletion_successor<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
     >(
         &self,
-        cost_increment: Cost,
+        long_gap: bool,
+        (cost_increment, homopolymer_length): (Cost, u8),
         context: &Context<SubsequenceType, Strategies>,
     ) -> Option<Self> {
         if cost_increment == Cost::MAX {
@@ -380,12 +499,15 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             unreachable!("This method is only called on secondary nodes.")
         };
 
-        Some(self.generate_successor(
-            predecessor_identifier.generate_secondary_deletion_successor(),
-            cost_increment,
-            AlignmentType::SecondaryDeletion,
-            context,
-        ))
+        Some(
+            self.generate_successor(
+                predecessor_identifier
+                    .generate_secondary_deletion_successor(long_gap, homopolymer_length),
+                cost_increment,
+                AlignmentType::SecondaryDeletion,
+                context,
+            ),
+        )
     }
 
     /// The secondary contains a base missing in the primary.
@@ -393,7 +515,8 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
     >(
         &self,
-        cost_increment: Cost,
+        long_gap: bool,
+        (cost_increment, homopolymer_length): (Cost, u8),
         context: &Context<SubsequenceType, Strategies>,
     ) -> Option<Self> {
         if cost_increment == Cost::MAX {
@@ -405,12 +528,15 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             unreachable!("This method is only called on secondary nodes.")
         };
 
-        Some(self.generate_successor(
-            predecessor_identifier.generate_secondary_insertion_successor(),
-            cost_increment,
-            AlignmentType::SecondaryInsertion,
-            context,
-        ))
+        Some(
+            self.generate_successor(
+                predecessor_identifier
+                    .generate_secondary_insertion_successor(long_gap, homopolymer_length),
+                cost_increment,
+                AlignmentType::SecondaryInsertion,
+                context,
+            ),
+        )
     }
 
     fn generate_initial_template_switch_exit_successor<
@@ -429,6 +555,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             entrance_query_index,
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             primary_index,
             ..
         } = self.node_data.identifier
@@ -442,6 +569,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 primary_index,
                 length_difference: 0,
             },
@@ -470,6 +598,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             entrance_query_index,
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             primary_index,
             ..
         } = self.node_data.identifier
@@ -483,6 +612,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 primary_index,
                 length_difference: successor_length_difference,
             },
@@ -518,7 +648,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 let anti_primary_length = primary_length as isize + length_difference;
                 let query_index = entrance_query_index as isize + anti_primary_length;
 
-                if query_index < 0 {
+                if query_index < 0 || query_index as usize > context.query.len() {
                     return None;
                 }
 
@@ -529,7 +659,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 let anti_primary_length = primary_length as isize + length_difference;
                 let reference_index = entrance_reference_index as isize + anti_primary_length;
 
-                if reference_index < 0 {
+                if reference_index < 0 || reference_index as usize > context.reference.len() {
                     return None;
                 }
 
@@ -537,6 +667,15 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
             }
         };
 
+        if !context.config.allows_template_switch_at(
+            reference_index,
+            context.reference.len(),
+            query_index,
+            context.query.len(),
+        ) {
+            return None;
+        }
+
         debug_assert!(reference_index != usize::MAX, "{self:?}");
         debug_assert!(query_index != usize::MAX, "{self:?}");
         debug_assert!(reference_index < isize::MAX as usize, "{self:?}");
@@ -547,7 +686,9 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 reference_index,
                 query_index,
                 gap_type: GapType::None,
+                homopolymer_length: 0,
                 flank_index: -context.config.right_flank_length,
+                template_switched: context.self_alignment(),
                 data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(identifier, AlignmentType::PrimaryReentry, context),
             },
             0.into(),
@@ -600,7 +741,9 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 reference_index,
                 query_index,
                 gap_type: GapType::None,
+                homopolymer_length: 0,
                 flank_index: -context.config.right_flank_length,
+                template_switched: context.self_alignment(),
                 data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(identifier, AlignmentType::PrimaryReentry, context),
             },
             cost_increment,
@@ -612,6 +755,127 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
         ))
     }
 
+    /// Jump back by `length` characters in the reference, such that they are aligned to the query again.
+    fn generate_primary_duplication_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        length: usize,
+        cost_increment: Cost,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Option<Self> {
+        if cost_increment == Cost::MAX {
+            return None;
+        }
+
+        let predecessor_identifier @ (Identifier::Primary {
+            reference_index,
+            query_index,
+            ..
+        }
+        | Identifier::PrimaryReentry {
+            reference_index,
+            query_index,
+            ..
+        }) = self.node_data.identifier
+        else {
+            unreachable!("This method is only called on primary nodes.")
+        };
+        let alignment_type = AlignmentType::PrimaryDuplication { length };
+
+        Some(self.generate_successor(
+            predecessor_identifier.generate_primary_jump_successor(
+                reference_index - length,
+                query_index,
+                alignment_type,
+                context,
+            ),
+            cost_increment,
+            alignment_type,
+            context,
+        ))
+    }
+
+    fn generate_inversion_entrance_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        length: usize,
+        cost_increment: Cost,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Option<Self> {
+        if cost_increment == Cost::MAX {
+            return None;
+        }
+
+        Some(
+            self.generate_successor(
+                self.node_data
+                    .identifier
+                    .generate_inversion_entrance_successor(length),
+                cost_increment,
+                AlignmentType::InversionEntrance { length },
+                context,
+            ),
+        )
+    }
+
+    fn generate_inversion_diagonal_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        cost_increment: Cost,
+        is_match: bool,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Option<Self> {
+        if cost_increment == Cost::MAX {
+            return None;
+        }
+
+        Some(
+            self.generate_successor(
+                self.node_data
+                    .identifier
+                    .generate_inversion_diagonal_successor(),
+                cost_increment,
+                if is_match {
+                    AlignmentType::InversionMatch
+                } else {
+                    AlignmentType::InversionSubstitution
+                },
+                context,
+            ),
+        )
+    }
+
+    fn generate_inversion_exit_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Self {
+        let predecessor_identifier @ Identifier::Inversion {
+            end_reference_index,
+            query_index,
+            ..
+        } = self.node_data.identifier
+        else {
+            unreachable!("This method is only called on inversion nodes.")
+        };
+
+        self.generate_successor(
+            predecessor_identifier.generate_primary_jump_successor(
+                end_reference_index,
+                query_index,
+                AlignmentType::InversionExit,
+                context,
+            ),
+            Cost::ZERO,
+            AlignmentType::InversionExit,
+            context,
+        )
+    }
+
     fn generate_successor<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
     >(
@@ -626,6 +890,7 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
                 identifier,
                 cost_increment,
                 alignment_type,
+                context.secondary_score_policy(),
             ),
             strategies: self
                 .strategies
@@ -635,13 +900,17 @@ impl<Strategies: AlignmentStrategySelector> Node<Strategies> {
 }
 
 impl<PrimaryExtraData: Copy> NodeData<PrimaryExtraData> {
-    fn create_root(identifier: Identifier<PrimaryExtraData>) -> Self {
+    fn create_root(
+        identifier: Identifier<PrimaryExtraData>,
+        secondary_score_policy: SecondaryScorePolicy,
+    ) -> Self {
         Self {
             identifier,
             predecessor: None,
             predecessor_edge_type: AlignmentType::Root,
             cost: Cost::ZERO,
             a_star_lower_bound: Cost::ZERO,
+            secondary_score: secondary_score_policy.root_score(),
         }
     }
 
@@ -650,6 +919,7 @@ impl<PrimaryExtraData: Copy> NodeData<PrimaryExtraData> {
         identifier: Identifier<PrimaryExtraData>,
         cost_increment: Cost,
         alignment_type: AlignmentType,
+        secondary_score_policy: SecondaryScorePolicy,
     ) -> Self {
         let cost = self.cost + cost_increment;
         let a_star_lower_bound = self.a_star_lower_bound.saturating_sub(&cost_increment);
@@ -659,6 +929,8 @@ impl<PrimaryExtraData: Copy> NodeData<PrimaryExtraData> {
             predecessor_edge_type: alignment_type,
             cost,
             a_star_lower_bound,
+            secondary_score: secondary_score_policy
+                .successor_score(self.secondary_score, alignment_type),
         }
     }
 
@@ -692,6 +964,7 @@ where
                     predecessor_edge_type,
                     cost,
                     a_star_lower_bound,
+                    ..
                 },
             strategies,
         } = self;
@@ -1,6 +1,8 @@
-use crate::a_star_aligner::alignment_result::IAlignmentType;
+use crate::a_star_aligner::alignment_result::{
+    AlignmentColumn, AlignmentRegion, CostSegmentKind, IAlignmentType,
+};
 
-use super::identifier::{TemplateSwitchPrimary, TemplateSwitchSecondary};
+use super::identifier::{TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -13,6 +15,10 @@ pub enum AlignmentType {
     PrimarySubstitution,
     /// The query contains the same base as the reference.
     PrimaryMatch,
+    /// The query contains the same bases as the reference for `length` consecutive bases.
+    ///
+    /// This is generated when extending along the diagonal until the first mismatch, and is expanded into [`PrimaryMatch`](Self::PrimaryMatch)es in alignments.
+    PrimaryMatchRun { length: usize },
     /// The query contains a base that is missing from the reference.
     ///
     /// This happens inside a TS flank.
@@ -37,10 +43,19 @@ pub enum AlignmentType {
     SecondarySubstitution,
     /// The TS secondary contains the same base as the TS primary.
     SecondaryMatch,
+    /// The TS secondary contains the same bases as the TS primary for `length` consecutive bases.
+    ///
+    /// This is generated when skipping over runs of exact matches, and is expanded into [`SecondaryMatch`](Self::SecondaryMatch)es in alignments.
+    SecondaryMatchRun { length: usize },
     /// A template switch entrance.
     TemplateSwitchEntrance {
         primary: TemplateSwitchPrimary,
         secondary: TemplateSwitchSecondary,
+        /// The strand of the secondary that the template switch copies.
+        ///
+        /// Results without a strand are read as copying the reverse complement, which used to be the only strand.
+        #[cfg_attr(feature = "serde", serde(default))]
+        strand: TemplateSwitchStrand,
         first_offset: isize,
     },
     /// A template switch exit.
@@ -58,6 +73,18 @@ pub enum AlignmentType {
         delta_reference: isize,
         delta_query: isize,
     },
+    /// The query contains a tandem duplication of the `length` reference bases before the current position.
+    ///
+    /// The duplicated bases are aligned to the reference again after this step.
+    PrimaryDuplication { length: usize },
+    /// The query contains the reverse complement of the next `length` bases of the reference.
+    InversionEntrance { length: usize },
+    /// Inside an inversion, the query contains a different base than the reverse complement of the reference.
+    InversionSubstitution,
+    /// Inside an inversion, the query contains the same base as the reverse complement of the reference.
+    InversionMatch,
+    /// The end of an inversion.
+    InversionExit,
 }
 
 impl IAlignmentType for AlignmentType {
@@ -73,14 +100,21 @@ impl IAlignmentType for AlignmentType {
             | Self::PrimaryFlankSubstitution
             | Self::SecondarySubstitution
             | Self::PrimaryMatch
+            | Self::PrimaryMatchRun { .. }
             | Self::PrimaryFlankMatch
             | Self::SecondaryMatch
+            | Self::SecondaryMatchRun { .. }
             | Self::Root
             | Self::SecondaryRoot
-            | Self::PrimaryReentry => true,
+            | Self::PrimaryReentry
+            | Self::InversionSubstitution
+            | Self::InversionMatch => true,
             Self::TemplateSwitchEntrance { .. }
             | Self::TemplateSwitchExit { .. }
-            | Self::PrimaryShortcut { .. } => false,
+            | Self::PrimaryShortcut { .. }
+            | Self::PrimaryDuplication { .. }
+            | Self::InversionEntrance { .. }
+            | Self::InversionExit => false,
         }
     }
 
@@ -110,16 +144,19 @@ impl IAlignmentType for AlignmentType {
                 Self::TemplateSwitchEntrance {
                     primary: primary_a,
                     secondary: secondary_a,
+                    strand: strand_a,
                     ..
                 },
                 Self::TemplateSwitchEntrance {
                     primary: primary_b,
                     secondary: secondary_b,
+                    strand: strand_b,
                     ..
                 },
-            ) => primary_a == primary_b && secondary_a == secondary_b,
+            ) => primary_a == primary_b && secondary_a == secondary_b && strand_a == strand_b,
             (Self::TemplateSwitchExit { .. }, Self::TemplateSwitchExit { .. }) => true,
             (Self::PrimaryShortcut { .. }, Self::PrimaryShortcut { .. }) => false,
+            (Self::PrimaryDuplication { .. }, Self::PrimaryDuplication { .. }) => false,
             (a, b) => a == b,
         }
     }
@@ -130,4 +167,80 @@ impl IAlignmentType for AlignmentType {
             Self::Root | Self::SecondaryRoot | Self::PrimaryReentry
         )
     }
+
+    fn as_run(&self) -> Option<(usize, Self)> {
+        match self {
+            Self::PrimaryMatchRun { length } => Some((*length, Self::PrimaryMatch)),
+            Self::SecondaryMatchRun { length } => Some((*length, Self::SecondaryMatch)),
+            _ => None,
+        }
+    }
+
+    fn alignment_column(&self) -> Option<(AlignmentRegion, AlignmentColumn)> {
+        Some(match self {
+            Self::PrimaryInsertion | Self::PrimaryFlankInsertion => {
+                (AlignmentRegion::Primary, AlignmentColumn::Insertion)
+            }
+            Self::PrimaryDeletion | Self::PrimaryFlankDeletion => {
+                (AlignmentRegion::Primary, AlignmentColumn::Deletion)
+            }
+            Self::PrimarySubstitution | Self::PrimaryFlankSubstitution => {
+                (AlignmentRegion::Primary, AlignmentColumn::Mismatch)
+            }
+            Self::PrimaryMatch | Self::PrimaryFlankMatch => {
+                (AlignmentRegion::Primary, AlignmentColumn::Match)
+            }
+            Self::SecondaryInsertion => {
+                (AlignmentRegion::TemplateSwitch, AlignmentColumn::Insertion)
+            }
+            Self::SecondaryDeletion => (AlignmentRegion::TemplateSwitch, AlignmentColumn::Deletion),
+            Self::SecondarySubstitution => {
+                (AlignmentRegion::TemplateSwitch, AlignmentColumn::Mismatch)
+            }
+            Self::SecondaryMatch => (AlignmentRegion::TemplateSwitch, AlignmentColumn::Match),
+            Self::InversionSubstitution => (AlignmentRegion::Primary, AlignmentColumn::Mismatch),
+            Self::InversionMatch => (AlignmentRegion::Primary, AlignmentColumn::Match),
+            Self::PrimaryMatchRun { .. }
+            | Self::SecondaryMatchRun { .. }
+            | Self::TemplateSwitchEntrance { .. }
+            | Self::TemplateSwitchExit { .. }
+            | Self::Root
+            | Self::SecondaryRoot
+            | Self::PrimaryReentry
+            | Self::PrimaryShortcut { .. }
+            | Self::PrimaryDuplication { .. }
+            | Self::InversionEntrance { .. }
+            | Self::InversionExit => return None,
+        })
+    }
+
+    fn cost_segment_kind(&self) -> Option<CostSegmentKind> {
+        match self {
+            Self::PrimaryInsertion
+            | Self::PrimaryDeletion
+            | Self::PrimarySubstitution
+            | Self::PrimaryMatch
+            | Self::PrimaryMatchRun { .. } => Some(CostSegmentKind::Primary),
+            Self::PrimaryFlankInsertion
+            | Self::PrimaryFlankDeletion
+            | Self::PrimaryFlankSubstitution
+            | Self::PrimaryFlankMatch => Some(CostSegmentKind::Flank),
+            Self::SecondaryInsertion
+            | Self::SecondaryDeletion
+            | Self::SecondarySubstitution
+            | Self::SecondaryMatch
+            | Self::SecondaryMatchRun { .. } => Some(CostSegmentKind::TemplateSwitch),
+            Self::TemplateSwitchEntrance { .. } => Some(CostSegmentKind::TemplateSwitchEntrance),
+            Self::TemplateSwitchExit { .. } => Some(CostSegmentKind::TemplateSwitchExit),
+            Self::PrimaryDuplication { .. } => Some(CostSegmentKind::Duplication),
+            Self::InversionEntrance { .. }
+            | Self::InversionSubstitution
+            | Self::InversionMatch
+            | Self::InversionExit => Some(CostSegmentKind::Inversion),
+            Self::Root
+            | Self::SecondaryRoot
+            | Self::PrimaryReentry
+            | Self::PrimaryShortcut { .. } => None,
+        }
+    }
 }
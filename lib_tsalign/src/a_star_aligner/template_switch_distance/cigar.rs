@@ -0,0 +1,488 @@
+//! Parsing the CIGAR strings written for template switch alignments.
+//!
+//! Besides the usual operations `M`, `I` and `D`, the CIGAR contains `S` for substitutions,
+//! template switches as `[TS<primary><secondary><first offset>:<columns>:<length difference>]`,
+//! with an `F` after the secondary if the template switch copies the forward strand of the secondary,
+//! shortcuts as `[PS:R<delta reference>Q<delta query>]`, duplications as `[DUP<length>]`
+//! and inversions as `[INV<length>:<columns>]`.
+//! The columns inside a template switch are secondary columns, and the columns inside an inversion are inversion columns.
+//!
+//! The CIGAR does not distinguish flanks from other primary columns, so all primary columns are parsed as non-flank columns.
+//! For hand-written CIGARs, `=` and `X` are accepted as aliases of `M` and `S`, like in SAM,
+//! and a missing count means a single column.
+//! Parsing does not know the aligned sequences, so [`check_cigar_bounds`] checks that a parsed CIGAR fits them.
+
+use super::{AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand};
+use crate::{
+    a_star_aligner::alignment_result::IAlignmentType,
+    error::{Error, Result},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The part of the alignment that the parser is in, which decides the alignment type of plain columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Primary,
+    TemplateSwitch,
+    Inversion,
+}
+
+struct Parser<'cigar> {
+    cigar: &'cigar str,
+    position: usize,
+}
+
+/// Parses a CIGAR as written by [`alignment_cigar`](crate::a_star_aligner::alignment_result::alignment_cigar)
+/// into a run-length encoded alignment.
+pub fn parse_cigar(cigar: &str) -> Result<Vec<(usize, AlignmentType)>> {
+    // Skip surrounding whitespace, but report positions in the untrimmed CIGAR.
+    let cigar = cigar.trim_end();
+    let mut parser = Parser {
+        cigar,
+        position: cigar.len() - cigar.trim_start().len(),
+    };
+    let mut alignment: Vec<(usize, AlignmentType)> = Vec::new();
+    let mut region = Region::Primary;
+
+    while parser.peek().is_some() {
+        let count = parser.parse_count()?;
+        let Some(character) = parser.next() else {
+            return Err(parser.error("expected an operation after the count".to_string()));
+        };
+
+        let alignment_type = match (character, region) {
+            ('M' | '=', Region::Primary) => AlignmentType::PrimaryMatch,
+            ('S' | 'X', Region::Primary) => AlignmentType::PrimarySubstitution,
+            ('I', Region::Primary) => AlignmentType::PrimaryInsertion,
+            ('D', Region::Primary) => AlignmentType::PrimaryDeletion,
+            ('M' | '=', Region::TemplateSwitch) => AlignmentType::SecondaryMatch,
+            ('S' | 'X', Region::TemplateSwitch) => AlignmentType::SecondarySubstitution,
+            ('I', Region::TemplateSwitch) => AlignmentType::SecondaryInsertion,
+            ('D', Region::TemplateSwitch) => AlignmentType::SecondaryDeletion,
+            ('M' | '=', Region::Inversion) => AlignmentType::InversionMatch,
+            ('S' | 'X', Region::Inversion) => AlignmentType::InversionSubstitution,
+            ('[' | ':' | ']', _) if count.is_some() => {
+                return Err(parser
+                    .error_before(character, format!("unexpected count before '{character}'")));
+            }
+            ('[', Region::Primary) => {
+                let alignment_type = parser.parse_event()?;
+                match alignment_type {
+                    AlignmentType::TemplateSwitchEntrance { .. } => region = Region::TemplateSwitch,
+                    AlignmentType::InversionEntrance { .. } => region = Region::Inversion,
+                    _ => {}
+                }
+                alignment_type
+            }
+            (':', Region::TemplateSwitch) => {
+                let length_difference = parser.parse_signed()?;
+                parser.expect(']')?;
+                region = Region::Primary;
+                AlignmentType::TemplateSwitchExit { length_difference }
+            }
+            (']', Region::Inversion) => {
+                region = Region::Primary;
+                AlignmentType::InversionExit
+            }
+            (character, Region::Primary) => {
+                return Err(parser.error_before(character, format!("unexpected '{character}'")));
+            }
+            (character, Region::TemplateSwitch) => {
+                return Err(parser.error_before(
+                    character,
+                    format!("unexpected '{character}' inside a template switch"),
+                ));
+            }
+            (character, Region::Inversion) => {
+                return Err(parser.error_before(
+                    character,
+                    format!("unexpected '{character}' inside an inversion"),
+                ));
+            }
+        };
+
+        let count = count.unwrap_or(1);
+        match alignment.last_mut() {
+            Some((previous_count, previous))
+                if *previous == alignment_type && alignment_type.is_repeatable() =>
+            {
+                *previous_count += count
+            }
+            _ => alignment.push((count, alignment_type)),
+        }
+    }
+
+    match region {
+        Region::Primary => Ok(alignment),
+        Region::TemplateSwitch => Err(parser.error("unterminated template switch".to_string())),
+        Region::Inversion => Err(parser.error("unterminated inversion".to_string())),
+    }
+}
+
+/// Checks that the `alignment` parsed from a CIGAR aligns exactly a reference of `reference_length` and a query of `query_length` characters.
+///
+/// Besides the end of the alignment, this checks that each template switch stays inside its secondary,
+/// exits inside its anti-primary and has at least one secondary column, and that each inversion stays inside the reference
+/// and has at least one column.
+/// Alignments computed by the aligner always fit their sequences, but hand-written CIGARs may not.
+pub fn check_cigar_bounds(
+    alignment: &[(usize, AlignmentType)],
+    reference_length: usize,
+    query_length: usize,
+) -> Result<()> {
+    let error = |message: String| Err(Error::CigarOutOfRange { message });
+    let reference_length = reference_length as isize;
+    let query_length = query_length as isize;
+    let mut reference_index = 0isize;
+    let mut query_index = 0isize;
+    let mut template_switch_count = 0;
+    // The template switch and its secondary index, the length of the primary inside it and its number of secondary columns.
+    let mut template_switch = None;
+    // The length of the inversion and its number of columns.
+    let mut inversion = None;
+
+    for &(count, alignment_type) in alignment {
+        let count = count as isize;
+        match alignment_type {
+            AlignmentType::PrimaryMatch
+            | AlignmentType::PrimarySubstitution
+            | AlignmentType::PrimaryFlankMatch
+            | AlignmentType::PrimaryFlankSubstitution => {
+                reference_index += count;
+                query_index += count;
+            }
+            AlignmentType::PrimaryMatchRun { length } => {
+                reference_index += count * length as isize;
+                query_index += count * length as isize;
+            }
+            AlignmentType::PrimaryInsertion | AlignmentType::PrimaryFlankInsertion => {
+                query_index += count
+            }
+            AlignmentType::PrimaryDeletion | AlignmentType::PrimaryFlankDeletion => {
+                reference_index += count
+            }
+            AlignmentType::PrimaryShortcut {
+                delta_reference,
+                delta_query,
+            } => {
+                reference_index += count * delta_reference;
+                query_index += count * delta_query;
+            }
+            AlignmentType::PrimaryDuplication { length } => {
+                reference_index -= count * length as isize
+            }
+            AlignmentType::TemplateSwitchEntrance {
+                secondary,
+                first_offset,
+                ..
+            } => {
+                template_switch_count += 1;
+                let (secondary_index, secondary_length) = match secondary {
+                    TemplateSwitchSecondary::Reference => (reference_index, reference_length),
+                    TemplateSwitchSecondary::Query => (query_index, query_length),
+                };
+                let secondary_index = secondary_index + first_offset;
+                if !(0..=secondary_length).contains(&secondary_index) {
+                    return error(format!(
+                        "template switch {template_switch_count} enters the secondary at index {secondary_index}, \
+                         but the secondary has length {secondary_length}"
+                    ));
+                }
+                template_switch = Some((alignment_type, secondary_index, 0isize, 0isize));
+            }
+            AlignmentType::SecondaryMatch
+            | AlignmentType::SecondarySubstitution
+            | AlignmentType::SecondaryMatchRun { .. }
+            | AlignmentType::SecondaryInsertion
+            | AlignmentType::SecondaryDeletion => {
+                let Some((
+                    AlignmentType::TemplateSwitchEntrance {
+                        primary,
+                        secondary,
+                        strand,
+                        ..
+                    },
+                    secondary_index,
+                    primary_length,
+                    columns,
+                )) = &mut template_switch
+                else {
+                    return error("secondary columns outside of a template switch".to_string());
+                };
+                let columns_per_count = match alignment_type {
+                    AlignmentType::SecondaryMatchRun { length } => length as isize,
+                    _ => 1,
+                };
+                let (primary_count, secondary_count) = match alignment_type {
+                    AlignmentType::SecondaryInsertion => (count, 0),
+                    AlignmentType::SecondaryDeletion => (0, count),
+                    _ => (count * columns_per_count, count * columns_per_count),
+                };
+                *primary_length += primary_count;
+                *columns += count * columns_per_count;
+                match strand {
+                    TemplateSwitchStrand::ReverseComplement => *secondary_index -= secondary_count,
+                    TemplateSwitchStrand::Forward => *secondary_index += secondary_count,
+                }
+
+                let (primary_index, primary_sequence_length) = match primary {
+                    TemplateSwitchPrimary::Reference => (reference_index, reference_length),
+                    TemplateSwitchPrimary::Query => (query_index, query_length),
+                };
+                let secondary_length = match secondary {
+                    TemplateSwitchSecondary::Reference => reference_length,
+                    TemplateSwitchSecondary::Query => query_length,
+                };
+                if !(0..=secondary_length).contains(secondary_index) {
+                    return error(format!(
+                        "template switch {template_switch_count} reaches index {secondary_index} of the secondary, \
+                         but the secondary has length {secondary_length}"
+                    ));
+                }
+                if primary_index + *primary_length > primary_sequence_length {
+                    return error(format!(
+                        "template switch {template_switch_count} reaches index {} of the primary, \
+                         but the primary has length {primary_sequence_length}",
+                        primary_index + *primary_length
+                    ));
+                }
+            }
+            AlignmentType::TemplateSwitchExit { length_difference } => {
+                let Some((
+                    AlignmentType::TemplateSwitchEntrance { primary, .. },
+                    _,
+                    primary_length,
+                    columns,
+                )) = template_switch.take()
+                else {
+                    return error("template switch exit without entrance".to_string());
+                };
+                if columns == 0 {
+                    return error(format!(
+                        "template switch {template_switch_count} has no secondary columns"
+                    ));
+                }
+
+                let (anti_primary_index, anti_primary_length) = match primary {
+                    TemplateSwitchPrimary::Reference => (query_index, query_length),
+                    TemplateSwitchPrimary::Query => (reference_index, reference_length),
+                };
+                let anti_primary_end = anti_primary_index + primary_length + length_difference;
+                if !(0..=anti_primary_length).contains(&anti_primary_end) {
+                    return error(format!(
+                        "template switch {template_switch_count} exits the anti-primary at index {anti_primary_end}, \
+                         but the anti-primary has length {anti_primary_length}"
+                    ));
+                }
+                match primary {
+                    TemplateSwitchPrimary::Reference => {
+                        reference_index += primary_length;
+                        query_index = anti_primary_end;
+                    }
+                    TemplateSwitchPrimary::Query => {
+                        query_index += primary_length;
+                        reference_index = anti_primary_end;
+                    }
+                }
+            }
+            AlignmentType::InversionEntrance { length } => {
+                let inversion_end = reference_index + length as isize;
+                if inversion_end > reference_length {
+                    return error(format!(
+                        "an inversion ends at reference index {inversion_end}, but the reference has length {reference_length}"
+                    ));
+                }
+                inversion = Some((length as isize, 0isize));
+            }
+            AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                let Some((length, columns)) = &mut inversion else {
+                    return error("inversion columns outside of an inversion".to_string());
+                };
+                *columns += count;
+                reference_index += count;
+                query_index += count;
+                if *columns > *length {
+                    return error(format!(
+                        "an inversion has {columns} columns, but length {length}"
+                    ));
+                }
+            }
+            AlignmentType::InversionExit => {
+                if inversion.take().is_some_and(|(_, columns)| columns == 0) {
+                    return error("an inversion has no columns".to_string());
+                }
+            }
+            AlignmentType::Root | AlignmentType::SecondaryRoot | AlignmentType::PrimaryReentry => {}
+        }
+
+        if !(0..=reference_length).contains(&reference_index)
+            || !(0..=query_length).contains(&query_index)
+        {
+            return error(format!(
+                "the alignment reaches reference index {reference_index} and query index {query_index}, \
+                 but the sequences have lengths {reference_length} and {query_length}"
+            ));
+        }
+    }
+
+    if (reference_index, query_index) != (reference_length, query_length) {
+        return error(format!(
+            "the CIGAR aligns {reference_index} reference and {query_index} query characters, \
+             but the sequences have lengths {reference_length} and {query_length}"
+        ));
+    }
+    Ok(())
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.cigar[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += character.len_utf8();
+        Some(character)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(character) if character == expected => Ok(()),
+            Some(character) => Err(self.error_before(
+                character,
+                format!("expected '{expected}', but found '{character}'"),
+            )),
+            None => Err(self.error(format!("expected '{expected}', but the CIGAR ended"))),
+        }
+    }
+
+    fn expect_str(&mut self, expected: &str) -> Result<()> {
+        expected
+            .chars()
+            .try_for_each(|character| self.expect(character))
+    }
+
+    /// Parses the digits of an optional count.
+    fn parse_count(&mut self) -> Result<Option<usize>> {
+        let digits = self.cigar[self.position..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count();
+        if digits == 0 {
+            return Ok(None);
+        }
+
+        let count = &self.cigar[self.position..self.position + digits];
+        let count = count
+            .parse()
+            .map_err(|error| self.error(format!("invalid count {count}: {error}")))?;
+        self.position += digits;
+        Ok(Some(count))
+    }
+
+    fn parse_unsigned(&mut self) -> Result<usize> {
+        self.parse_count()?
+            .ok_or_else(|| self.error("expected a number".to_string()))
+    }
+
+    fn parse_signed(&mut self) -> Result<isize> {
+        let negative = self.peek() == Some('-');
+        if negative {
+            self.position += 1;
+        }
+        let value = isize::try_from(self.parse_unsigned()?)
+            .map_err(|error| self.error(format!("invalid number: {error}")))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Parses a bracketed event after its opening bracket.
+    ///
+    /// Template switches and inversions are only parsed up to the colon before their columns.
+    fn parse_event(&mut self) -> Result<AlignmentType> {
+        let rest = &self.cigar[self.position..];
+        if rest.starts_with("TS") {
+            self.expect_str("TS")?;
+            let primary = match self.next() {
+                Some('R') => TemplateSwitchPrimary::Reference,
+                Some('Q') => TemplateSwitchPrimary::Query,
+                other => {
+                    return Err(self.error_before_option(
+                        other,
+                        "expected a template switch primary 'R' or 'Q'".to_string(),
+                    ))
+                }
+            };
+            let secondary = match self.next() {
+                Some('R') => TemplateSwitchSecondary::Reference,
+                Some('Q') => TemplateSwitchSecondary::Query,
+                other => {
+                    return Err(self.error_before_option(
+                        other,
+                        "expected a template switch secondary 'R' or 'Q'".to_string(),
+                    ))
+                }
+            };
+            let strand = if self.peek() == Some('F') {
+                self.position += 1;
+                TemplateSwitchStrand::Forward
+            } else {
+                TemplateSwitchStrand::ReverseComplement
+            };
+            let first_offset = self.parse_signed()?;
+            self.expect(':')?;
+            Ok(AlignmentType::TemplateSwitchEntrance {
+                primary,
+                secondary,
+                strand,
+                first_offset,
+            })
+        } else if rest.starts_with("PS") {
+            self.expect_str("PS:R")?;
+            let delta_reference = self.parse_signed()?;
+            self.expect('Q')?;
+            let delta_query = self.parse_signed()?;
+            self.expect(']')?;
+            Ok(AlignmentType::PrimaryShortcut {
+                delta_reference,
+                delta_query,
+            })
+        } else if rest.starts_with("DUP") {
+            self.expect_str("DUP")?;
+            let length = self.parse_unsigned()?;
+            self.expect(']')?;
+            Ok(AlignmentType::PrimaryDuplication { length })
+        } else if rest.starts_with("INV") {
+            self.expect_str("INV")?;
+            let length = self.parse_unsigned()?;
+            self.expect(':')?;
+            Ok(AlignmentType::InversionEntrance { length })
+        } else {
+            Err(self.error("expected 'TS', 'PS', 'DUP' or 'INV' after '['".to_string()))
+        }
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::CigarParse {
+            position: self.position,
+            message,
+        }
+    }
+
+    /// Returns an error at the position of the `character` that was consumed last.
+    fn error_before(&self, character: char, message: String) -> Error {
+        Error::CigarParse {
+            position: self.position - character.len_utf8(),
+            message,
+        }
+    }
+
+    /// Returns an error at the position of the `character` that was consumed last, or at the end if there was none.
+    fn error_before_option(&self, character: Option<char>, message: String) -> Error {
+        match character {
+            Some(character) => self.error_before(character, message),
+            None => self.error(message),
+        }
+    }
+}
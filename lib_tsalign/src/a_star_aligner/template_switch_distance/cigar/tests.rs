@@ -0,0 +1,184 @@
+use crate::{
+    a_star_aligner::{
+        alignment_result::alignment_cigar,
+        template_switch_distance::{
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+    },
+    error::Error,
+};
+
+use super::{check_cigar_bounds, parse_cigar};
+
+#[test]
+fn round_trip() {
+    let alignment = vec![
+        (3, AlignmentType::PrimaryMatch),
+        (1, AlignmentType::PrimarySubstitution),
+        (2, AlignmentType::PrimaryInsertion),
+        (12, AlignmentType::PrimaryDeletion),
+        (
+            1,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset: -3,
+            },
+        ),
+        (4, AlignmentType::SecondaryMatch),
+        (1, AlignmentType::SecondarySubstitution),
+        (1, AlignmentType::SecondaryInsertion),
+        (2, AlignmentType::SecondaryDeletion),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 2,
+            },
+        ),
+        (1, AlignmentType::PrimaryMatch),
+        (
+            1,
+            AlignmentType::PrimaryShortcut {
+                delta_reference: -1,
+                delta_query: 5,
+            },
+        ),
+        (1, AlignmentType::PrimaryDuplication { length: 4 }),
+        (1, AlignmentType::InversionEntrance { length: 3 }),
+        (2, AlignmentType::InversionMatch),
+        (1, AlignmentType::InversionSubstitution),
+        (1, AlignmentType::InversionExit),
+        (5, AlignmentType::PrimaryMatch),
+    ];
+
+    let cigar = alignment_cigar(&alignment);
+    assert_eq!(
+        cigar,
+        "3M1S2I12D[TSQR-3:4M1S1I2D:2]1M[PS:R-1Q5][DUP4][INV3:2M1S]5M"
+    );
+    assert_eq!(parse_cigar(&cigar).unwrap(), alignment);
+}
+
+#[test]
+fn forward_template_switch_round_trip() {
+    let alignment = vec![
+        (2, AlignmentType::PrimaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Reference,
+                secondary: TemplateSwitchSecondary::Query,
+                strand: TemplateSwitchStrand::Forward,
+                first_offset: -3,
+            },
+        ),
+        (2, AlignmentType::SecondaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 0,
+            },
+        ),
+        (1, AlignmentType::PrimaryMatch),
+    ];
+
+    let cigar = alignment_cigar(&alignment);
+    assert_eq!(cigar, "2M[TSRQF-3:2M:0]1M");
+    assert_eq!(parse_cigar(&cigar).unwrap(), alignment);
+}
+
+#[test]
+fn hand_written() {
+    assert_eq!(
+        parse_cigar(" 2=X3M[TSRR4:M:-1]M\n").unwrap(),
+        vec![
+            (2, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::PrimarySubstitution),
+            (3, AlignmentType::PrimaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Reference,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::ReverseComplement,
+                    first_offset: 4,
+                },
+            ),
+            (1, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: -1,
+                },
+            ),
+            (1, AlignmentType::PrimaryMatch),
+        ]
+    );
+    assert_eq!(parse_cigar("").unwrap(), Vec::new());
+
+    // `=` and `X` are aliases of `M` and `S`.
+    assert_eq!(parse_cigar("2=3X").unwrap(), parse_cigar("2M3S").unwrap());
+}
+
+#[test]
+fn errors() {
+    for (cigar, expected_position) in [
+        ("3M2", 3),
+        ("3Q", 1),
+        ("3M[TSQX1:2M:0]", 6),
+        ("3M[TSQR1:2M", 11),
+        ("3M[TSQR1:2M:0", 13),
+        ("[INV2:2I]", 7),
+        ("3M[FOO]", 3),
+        ("2[DUP3]", 1),
+        ("[TSQR1:[DUP3]:0]", 7),
+    ] {
+        match parse_cigar(cigar) {
+            Err(Error::CigarParse { position, .. }) => {
+                assert_eq!(position, expected_position, "{cigar}")
+            }
+            result => panic!("{cigar}: {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn bounds() {
+    for cigar in [
+        "20M",
+        "5M[TSQR0:5D:0]15M",
+        "10M[TSQR-5:5M:0]5M",
+        "2M[TSRQF-2:2M:0]16M",
+        "5M[INV3:3M]12M",
+        "[DUP0]20M",
+    ] {
+        check_cigar_bounds(&parse_cigar(cigar).unwrap(), 20, 20)
+            .unwrap_or_else(|error| panic!("{cigar}: {error}"));
+    }
+
+    for cigar in [
+        // The secondary range of the template switch starts before the reference.
+        "5M[TSQR-5:5M:0]10M",
+        "5M[TSQR-6:M:0]14M",
+        // The forward secondary range ends after the query.
+        "15M[TSRQF3:3M:0]2M",
+        // The template switch exits after the end of the reference.
+        "15M[TSQR0:5M:3]",
+        // Empty template switches.
+        "10M[TSQR0:0M:0]10M",
+        "10M[TSQR0::0]10M",
+        // The inversion ends after the reference, or has more columns than its length.
+        "18M[INV3:2M]",
+        "5M[INV2:3M]12M",
+        "5M[INV2:]15M",
+        "[DUP1]20M",
+        "19M",
+        "21M",
+    ] {
+        match check_cigar_bounds(&parse_cigar(cigar).unwrap(), 20, 20) {
+            Err(Error::CigarOutOfRange { .. }) => {}
+            result => panic!("{cigar}: {result:?}"),
+        }
+    }
+}
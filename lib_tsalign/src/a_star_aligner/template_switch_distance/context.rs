@@ -2,23 +2,33 @@ use std::fmt::Display;
 
 use compact_genome::interface::alphabet::AlphabetCharacter;
 use compact_genome::interface::sequence::GenomeSequence;
-use extend_map::ExtendMap;
+use extend_map::{ExtendFilter, ExtendMap};
 use generic_a_star::cost::Cost;
 use generic_a_star::reset::Reset;
 use generic_a_star::{AStarBuffers, AStarContext};
 
+use crate::a_star_aligner::alignment_result::AlignmentStatistics;
 use crate::a_star_aligner::template_switch_distance::Node;
 use crate::a_star_aligner::AlignmentContext;
 use crate::config::TemplateSwitchConfig;
-
-use super::identifier::{GapType, TemplateSwitchPrimary, TemplateSwitchSecondary};
+use crate::costs::gap_affine::IndelType;
+use crate::guide::AlignmentGuide;
+use crate::soft_mask::SoftMasks;
+use crate::ts_normalisation::TemplateSwitchAnnotation;
+
+use super::identifier::{
+    GapType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+};
+use super::secondary_score::SecondaryScorePolicy;
 use super::strategies::chaining::ChainingStrategy;
+use super::strategies::node_ord::NodeOrdStrategy;
 use super::strategies::primary_match::PrimaryMatchStrategy;
 use super::strategies::secondary_deletion::SecondaryDeletionStrategy;
 use super::strategies::shortcut::ShortcutStrategy;
 use super::strategies::template_switch_count::TemplateSwitchCountStrategy;
 use super::strategies::template_switch_min_length::TemplateSwitchMinLengthStrategy;
 use super::strategies::{AlignmentStrategiesNodeMemory, AlignmentStrategySelector};
+use super::{primary_match_runs::PrimaryMatchRuns, secondary_match_runs::SecondaryMatchRuns};
 use super::{AlignmentType, Identifier, NodeData};
 
 pub struct Context<
@@ -36,9 +46,16 @@ pub struct Context<
     pub memory: Memory<Strategies>,
 
     max_cost: Option<Cost>,
+    primary_match_runs: Option<PrimaryMatchRuns>,
+    secondary_match_runs: Option<SecondaryMatchRuns>,
+    soft_masks: Option<SoftMasks>,
+    guide: Option<AlignmentGuide>,
+    secondary_score_policy: SecondaryScorePolicy,
+    self_alignment: bool,
 }
 
 pub struct Memory<Strategies: AlignmentStrategySelector> {
+    pub node_ord: <<Strategies as AlignmentStrategySelector>::NodeOrd as NodeOrdStrategy<<Strategies as AlignmentStrategySelector>::PrimaryMatch>>::Memory,
     pub template_switch_min_length: <<Strategies as AlignmentStrategySelector>::TemplateSwitchMinLength as TemplateSwitchMinLengthStrategy>::Memory,
     pub chaining: <<Strategies as AlignmentStrategySelector>::Chaining as ChainingStrategy>::Memory,
     pub template_switch_count:  <<Strategies as AlignmentStrategySelector>::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory,
@@ -67,8 +84,95 @@ impl<
             a_star_buffers: Default::default(),
             memory,
             max_cost,
+            primary_match_runs: None,
+            secondary_match_runs: None,
+            soft_masks: None,
+            guide: None,
+            secondary_score_policy: Default::default(),
+            self_alignment: false,
         }
     }
+
+    /// Extend along the diagonal until the first mismatch in a single step in the primary alignment,
+    /// if this does not change the cost of the alignment.
+    ///
+    /// See [`PrimaryMatchRuns`] for when this is the case.
+    pub fn set_skip_primary_match_runs(&mut self, skip_primary_match_runs: bool) {
+        self.primary_match_runs = if skip_primary_match_runs {
+            PrimaryMatchRuns::new(self.reference, self.query, &self.config)
+        } else {
+            None
+        };
+    }
+
+    /// Skip over runs of exact matches in the secondary alignment of template switches,
+    /// if this does not change the cost of the alignment.
+    ///
+    /// See [`SecondaryMatchRuns`] for when this is the case.
+    pub fn set_skip_secondary_match_runs(&mut self, skip_secondary_match_runs: bool) {
+        self.secondary_match_runs = if skip_secondary_match_runs {
+            SecondaryMatchRuns::new(self.reference, self.query, &self.config)
+        } else {
+            None
+        };
+    }
+
+    /// Make template switches entering within soft-masked regions more expensive or forbid them.
+    ///
+    /// See [`SoftMasks::template_switch_base_cost`] for when an entrance is within a masked region.
+    pub fn set_soft_masks(&mut self, soft_masks: Option<SoftMasks>) {
+        self.soft_masks = soft_masks;
+    }
+
+    /// Restrict the primary alignment to the corridor of an external guide, such as the chain of a read mapper.
+    ///
+    /// Primary match runs are not skipped while a guide is set, since a run may leave the corridor.
+    pub fn set_guide(&mut self, guide: Option<AlignmentGuide>) {
+        self.guide = guide;
+    }
+
+    /// Returns true if the node is not a primary node outside of the corridor of the guide.
+    fn is_within_guide(&self, node: &Node<Strategies>) -> bool {
+        let Some(guide) = &self.guide else {
+            return true;
+        };
+
+        match node.node_data.identifier {
+            Identifier::Primary {
+                reference_index,
+                query_index,
+                ..
+            }
+            | Identifier::PrimaryReentry {
+                reference_index,
+                query_index,
+                ..
+            } => guide.contains(reference_index, query_index),
+            _ => true,
+        }
+    }
+
+    /// Choose which co-optimal alignments are preferred with [`AStarTieBreaking::SecondaryScore`](generic_a_star::comparator::AStarTieBreaking::SecondaryScore).
+    pub fn set_secondary_score_policy(&mut self, secondary_score_policy: SecondaryScorePolicy) {
+        self.secondary_score_policy = secondary_score_policy;
+    }
+
+    pub fn secondary_score_policy(&self) -> SecondaryScorePolicy {
+        self.secondary_score_policy
+    }
+
+    /// Only accept alignments with at least one template switch as target.
+    ///
+    /// This is meant for aligning a sequence to itself, where it excludes the trivial alignment along the main diagonal,
+    /// such that the optimal alignment is the cheapest explanation of the sequence by a template switch,
+    /// e.g. a hairpin formed by an inverted repeat.
+    pub fn set_self_alignment(&mut self, self_alignment: bool) {
+        self.self_alignment = self_alignment;
+    }
+
+    pub fn self_alignment(&self) -> bool {
+        self.self_alignment
+    }
 }
 
 impl<
@@ -86,6 +190,7 @@ impl<
                 predecessor_edge_type: AlignmentType::Root,
                 cost: Cost::ZERO,
                 a_star_lower_bound: Cost::ZERO,
+                secondary_score: self.secondary_score_policy.root_score(),
             },
             strategies: AlignmentStrategiesNodeMemory::create_root(self),
         }
@@ -97,8 +202,12 @@ impl<
         opened_nodes_output: &mut impl Extend<Self::Node>,
     ) {
         let config = &self.config;
-        let mut opened_nodes_output =
-            ExtendMap::new(opened_nodes_output, generate_output_mapper_function(self));
+        let mut filtered_opened_nodes_output =
+            ExtendFilter::new(opened_nodes_output, generate_output_filter_function(self));
+        let mut opened_nodes_output = ExtendMap::new(
+            &mut filtered_opened_nodes_output,
+            generate_output_mapper_function(self),
+        );
 
         match node.node_data.identifier {
             Identifier::Primary {
@@ -106,12 +215,14 @@ impl<
                 query_index,
                 flank_index,
                 gap_type,
+                homopolymer_length,
                 ..
             }
             | Identifier::PrimaryReentry {
                 reference_index,
                 query_index,
                 gap_type,
+                homopolymer_length,
                 flank_index,
                 ..
             } => {
@@ -125,46 +236,85 @@ impl<
                     .template_switch_count
                     .can_start_another_template_switch(self);
 
-                if reference_index < self.reference.len() && query_index < self.query.len() {
-                    // Diagonal characters
-                    let r = self.reference[reference_index].clone();
-                    let q = self.query[query_index].clone();
-                    let is_match = r == q;
-
-                    if flank_index == 0 {
-                        let can_do_primary_non_flank_match = <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::can_do_primary_non_flank_match(node.node_data.identifier, self);
+                let run_length = match (&self.primary_match_runs, flank_index, gap_type) {
+                    (Some(primary_match_runs), 0, GapType::None)
+                        if reference_index < self.reference.len()
+                            && query_index < self.query.len()
+                            && self.guide.is_none()
+                            && !<<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::always_generate_substitution()
+                            && <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::can_do_primary_non_flank_match(node.node_data.identifier, self) =>
+                    {
+                        primary_match_runs.run_length(
+                            reference_index,
+                            query_index,
+                            can_start_another_template_switch,
+                        )
+                    }
+                    _ => 0,
+                };
 
-                        let (is_match, cost_increment) =
-                            if is_match && can_do_primary_non_flank_match {
-                                (
-                                    true,
-                                    config.primary_edit_costs.match_cost(r.clone(), q.clone()),
-                                )
-                            } else if is_match && !can_do_primary_non_flank_match {
-                                (
-                                    false,
-                                    node.strategies.primary_match.fake_substitution_cost(self),
-                                )
-                            } else {
-                                debug_assert!(!is_match);
-                                (
-                                    false,
-                                    config
-                                        .primary_edit_costs
-                                        .substitution_cost(r.clone(), q.clone()),
-                                )
-                            };
+                if run_length > 1 {
+                    // Following the run is optimal and cannot skip a template switch, so no other successors are needed.
+                    opened_nodes_output.extend(Some(
+                        node.generate_primary_match_run_successor(run_length, self),
+                    ));
+                } else {
+                    if reference_index < self.reference.len() && query_index < self.query.len() {
+                        // Diagonal characters
+                        let r = self.reference[reference_index].clone();
+                        let q = self.query[query_index].clone();
+                        let is_match = r == q;
+                        // The reference character before `r` selects the context substitution cost table.
+                        let previous = reference_index
+                            .checked_sub(1)
+                            .map(|previous_index| self.reference[previous_index].clone());
+
+                        if flank_index == 0 {
+                            let can_do_primary_non_flank_match = <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::can_do_primary_non_flank_match(node.node_data.identifier, self);
+
+                            let (is_match, cost_increment) =
+                                if is_match && can_do_primary_non_flank_match {
+                                    (
+                                        true,
+                                        config
+                                            .primary_edit_costs
+                                            .context_match_or_substitution_cost(
+                                                previous.clone(),
+                                                r.clone(),
+                                                q.clone(),
+                                            ),
+                                    )
+                                } else if is_match && !can_do_primary_non_flank_match {
+                                    (
+                                        false,
+                                        node.strategies.primary_match.fake_substitution_cost(self),
+                                    )
+                                } else {
+                                    debug_assert!(!is_match);
+                                    (
+                                        false,
+                                        config
+                                            .primary_edit_costs
+                                            .context_match_or_substitution_cost(
+                                                previous.clone(),
+                                                r.clone(),
+                                                q.clone(),
+                                            ),
+                                    )
+                                };
 
-                        if cost_increment != Cost::MAX {
-                            opened_nodes_output.extend(node.generate_primary_diagonal_successor(
-                                0,
-                                cost_increment,
-                                is_match,
-                                self,
-                            ));
-                        }
+                            if cost_increment != Cost::MAX {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_diagonal_successor(
+                                        0,
+                                        cost_increment,
+                                        is_match,
+                                        self,
+                                    ),
+                                );
+                            }
 
-                        if is_match && <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::always_generate_substitution() {
+                            if is_match && <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::always_generate_substitution() {
                             let cost_increment = node.strategies.primary_match.fake_substitution_cost(self);
 
                             if cost_increment != Cost::MAX {
@@ -176,137 +326,245 @@ impl<
                                 ));
                             }
                         }
-                    }
-
-                    if (flank_index < config.left_flank_length && can_start_another_template_switch)
-                        || flank_index < 0
-                    {
-                        let can_do_primary_flank_match = <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::can_do_primary_flank_match(node.node_data.identifier, self);
-
-                        let edit_costs = if flank_index < 0 {
-                            &config.right_flank_edit_costs
-                        } else {
-                            &config.left_flank_edit_costs
-                        };
-
-                        let (is_match, cost_increment) = if is_match && can_do_primary_flank_match {
-                            (true, edit_costs.match_cost(r.clone(), q.clone()))
-                        } else if is_match && !can_do_primary_flank_match {
-                            (
-                                false,
-                                node.strategies.primary_match.fake_substitution_cost(self),
-                            )
-                        } else {
-                            debug_assert!(!is_match);
-                            (false, edit_costs.substitution_cost(r.clone(), q.clone()))
-                        };
-
-                        if cost_increment != Cost::MAX {
-                            opened_nodes_output.extend(node.generate_primary_diagonal_successor(
-                                flank_index + 1,
-                                cost_increment,
-                                is_match,
-                                self,
-                            ));
                         }
-                    }
-                }
 
-                if reference_index < self.reference.len() {
-                    // Deleted character
-                    let r = self.reference[reference_index].clone();
+                        if (flank_index < config.left_flank_length
+                            && can_start_another_template_switch)
+                            || flank_index < 0
+                        {
+                            let can_do_primary_flank_match = <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::can_do_primary_flank_match(node.node_data.identifier, self);
 
-                    if flank_index == 0 {
-                        opened_nodes_output.extend(
-                            node.generate_primary_deletion_successor(
-                                0,
-                                config
-                                    .primary_edit_costs
-                                    .gap_costs(r.clone(), gap_type != GapType::Deletion),
-                                self,
-                            ),
-                        );
+                            let edit_costs = if flank_index < 0 {
+                                &config.right_flank_edit_costs
+                            } else {
+                                &config.left_flank_edit_costs
+                            };
+
+                            let (is_match, cost_increment) =
+                                if is_match && can_do_primary_flank_match {
+                                    (
+                                        true,
+                                        edit_costs.context_match_or_substitution_cost(
+                                            previous.clone(),
+                                            r.clone(),
+                                            q.clone(),
+                                        ),
+                                    )
+                                } else if is_match && !can_do_primary_flank_match {
+                                    (
+                                        false,
+                                        node.strategies.primary_match.fake_substitution_cost(self),
+                                    )
+                                } else {
+                                    debug_assert!(!is_match);
+                                    (
+                                        false,
+                                        edit_costs.context_match_or_substitution_cost(
+                                            previous.clone(),
+                                            r.clone(),
+                                            q.clone(),
+                                        ),
+                                    )
+                                };
+
+                            if cost_increment != Cost::MAX {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_diagonal_successor(
+                                        flank_index + 1,
+                                        cost_increment,
+                                        is_match,
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
 
-                    if flank_index >= 0
-                        && flank_index < config.left_flank_length
-                        && can_start_another_template_switch
-                    {
-                        opened_nodes_output.extend(
-                            node.generate_primary_deletion_successor(
-                                flank_index + 1,
-                                config
-                                    .left_flank_edit_costs
-                                    .gap_costs(r, gap_type != GapType::Deletion),
-                                self,
-                            ),
-                        );
-                    } else if flank_index < 0 {
-                        opened_nodes_output.extend(
-                            node.generate_primary_deletion_successor(
-                                flank_index + 1,
-                                config
-                                    .right_flank_edit_costs
-                                    .gap_costs(r, gap_type != GapType::Deletion),
-                                self,
-                            ),
-                        );
+                    if reference_index < self.reference.len() {
+                        // Deleted character
+                        let r = self.reference[reference_index].clone();
+                        for long_gap in [false, true] {
+                            let previous = (gap_type == GapType::deletion(long_gap))
+                                .then(|| self.reference[reference_index - 1].clone());
+
+                            if flank_index == 0 {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_deletion_successor(
+                                        0,
+                                        long_gap,
+                                        config.primary_edit_costs.homopolymer_gap_costs(
+                                            r.clone(),
+                                            IndelType::Deletion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            }
+
+                            if flank_index >= 0
+                                && flank_index < config.left_flank_length
+                                && can_start_another_template_switch
+                            {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_deletion_successor(
+                                        flank_index + 1,
+                                        long_gap,
+                                        config.left_flank_edit_costs.homopolymer_gap_costs(
+                                            r.clone(),
+                                            IndelType::Deletion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            } else if flank_index < 0 {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_deletion_successor(
+                                        flank_index + 1,
+                                        long_gap,
+                                        config.right_flank_edit_costs.homopolymer_gap_costs(
+                                            r.clone(),
+                                            IndelType::Deletion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
-                }
 
-                if query_index < self.query.len() {
-                    // Inserted character
-                    let q = self.query[query_index].clone();
+                    if query_index < self.query.len() {
+                        // Inserted character
+                        let q = self.query[query_index].clone();
+                        for long_gap in [false, true] {
+                            let previous = (gap_type == GapType::insertion(long_gap))
+                                .then(|| self.query[query_index - 1].clone());
+
+                            if flank_index == 0 {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_insertion_successor(
+                                        0,
+                                        long_gap,
+                                        config.primary_edit_costs.homopolymer_gap_costs(
+                                            q.clone(),
+                                            IndelType::Insertion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            }
 
-                    if flank_index == 0 {
-                        opened_nodes_output.extend(
-                            node.generate_primary_insertion_successor(
-                                0,
-                                config
-                                    .primary_edit_costs
-                                    .gap_costs(q.clone(), gap_type != GapType::Insertion),
-                                self,
-                            ),
-                        );
+                            if flank_index >= 0
+                                && flank_index < config.left_flank_length
+                                && can_start_another_template_switch
+                            {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_insertion_successor(
+                                        flank_index + 1,
+                                        long_gap,
+                                        config.left_flank_edit_costs.homopolymer_gap_costs(
+                                            q.clone(),
+                                            IndelType::Insertion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            } else if flank_index < 0 {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_insertion_successor(
+                                        flank_index + 1,
+                                        long_gap,
+                                        config.right_flank_edit_costs.homopolymer_gap_costs(
+                                            q.clone(),
+                                            IndelType::Insertion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
 
-                    if flank_index >= 0
-                        && flank_index < config.left_flank_length
-                        && can_start_another_template_switch
+                    // Template switches are always allowed, as long as we have a left flank.
+                    if flank_index == config.left_flank_length && can_start_another_template_switch
                     {
-                        opened_nodes_output.extend(
-                            node.generate_primary_insertion_successor(
-                                flank_index + 1,
-                                config
-                                    .left_flank_edit_costs
-                                    .gap_costs(q, gap_type != GapType::Insertion),
-                                self,
-                            ),
-                        );
-                    } else if flank_index < 0 {
-                        opened_nodes_output.extend(
-                            node.generate_primary_insertion_successor(
-                                flank_index + 1,
-                                config
-                                    .right_flank_edit_costs
-                                    .gap_costs(q, gap_type != GapType::Insertion),
-                                self,
-                            ),
-                        );
+                        let offset_costs = config.offset_costs.evaluate(&0);
+
+                        for template_switch_strand in TemplateSwitchStrand::ALL {
+                            let base_cost = config.base_cost_of(template_switch_strand);
+                            let base_cost = if let Some(soft_masks) = &self.soft_masks {
+                                soft_masks.template_switch_base_cost(
+                                    base_cost,
+                                    reference_index,
+                                    query_index,
+                                )
+                            } else {
+                                base_cost
+                            };
+
+                            if offset_costs != Cost::MAX && base_cost != Cost::MAX {
+                                opened_nodes_output.extend(
+                                    node.generate_initial_template_switch_entrance_successors(
+                                        offset_costs + base_cost,
+                                        template_switch_strand,
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
-                }
 
-                // Template switches are always allowed, as long as we have a left flank.
-                if flank_index == config.left_flank_length && can_start_another_template_switch {
-                    let offset_costs = config.offset_costs.evaluate(&0);
+                    // Jump events start outside of flanks, like template switches start after a complete left flank.
+                    if flank_index == 0 {
+                        if let Some(duplication_costs) = &config.duplication_costs {
+                            let max_length = duplication_costs
+                                .maximum_finite_input()
+                                .unwrap_or(0)
+                                .min(reference_index);
+                            for length in 1..=max_length {
+                                opened_nodes_output.extend(
+                                    node.generate_primary_duplication_successor(
+                                        length,
+                                        duplication_costs.evaluate(&length),
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
 
-                    if offset_costs != Cost::MAX && config.base_cost != Cost::MAX {
-                        opened_nodes_output.extend(
-                            node.generate_initial_template_switch_entrance_successors(
-                                config.offset_costs.evaluate(&0) + config.base_cost,
-                                self,
-                            ),
-                        );
+                        if let Some(inversion_costs) = &config.inversion_costs {
+                            let max_length = inversion_costs
+                                .maximum_finite_input()
+                                .unwrap_or(0)
+                                .min(self.reference.len() - reference_index)
+                                .min(self.query.len() - query_index);
+                            for length in 1..=max_length {
+                                opened_nodes_output.extend(
+                                    node.generate_inversion_entrance_successor(
+                                        length,
+                                        inversion_costs.evaluate(&length),
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
                 }
             }
@@ -377,13 +635,18 @@ impl<
 
                 // Temporarily unpack opened_nodes_output because it borrows self,
                 // but generating the secondary root node wants to borrow self as mutable.
-                let opened_nodes_direct_output = opened_nodes_output.into_inner();
+                opened_nodes_output.into_inner();
+                let opened_nodes_direct_output = filtered_opened_nodes_output.into_inner();
                 let secondary_root_node: Vec<_> = node
                     .generate_secondary_root_node(self)
                     .into_iter()
                     .collect();
-                opened_nodes_output = ExtendMap::new(
+                filtered_opened_nodes_output = ExtendFilter::new(
                     opened_nodes_direct_output,
+                    generate_output_filter_function(self),
+                );
+                opened_nodes_output = ExtendMap::new(
+                    &mut filtered_opened_nodes_output,
                     generate_output_mapper_function(self),
                 );
                 opened_nodes_output.extend(secondary_root_node);
@@ -392,10 +655,12 @@ impl<
             Identifier::Secondary {
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
                 secondary_index,
                 gap_type,
+                homopolymer_length,
                 ..
             } => {
                 // TODO Some of the nodes generated here are unable to reach the target:
@@ -412,66 +677,123 @@ impl<
                     TemplateSwitchSecondary::Query => self.query,
                 };
 
-                // Only generate secondary successors if they can ever exit the template switch based on their length.
-                let min_length_cost = config.length_costs.min(length..).unwrap();
-                if min_length_cost != Cost::MAX {
-                    if primary_index < primary_sequence.len() && secondary_index > 0 {
-                        // Diagonal characters
-                        let p = primary_sequence[primary_index].clone();
-                        let s = secondary_sequence[secondary_index - 1].complement();
+                let run_length = match (&self.secondary_match_runs, gap_type) {
+                    (Some(secondary_match_runs), GapType::None) => secondary_match_runs.run_length(
+                        template_switch_primary,
+                        template_switch_secondary,
+                        template_switch_strand,
+                        length,
+                        primary_index,
+                        secondary_index,
+                    ),
+                    _ => 0,
+                };
 
-                        opened_nodes_output.extend(
-                            node.generate_secondary_diagonal_successor(
-                                config
-                                    .secondary_edit_costs
-                                    .match_or_substitution_cost(p.clone(), s.clone()),
-                                p == s,
-                                self,
-                            ),
-                        );
-                    }
+                if run_length > 1 {
+                    // Following the run is optimal and cannot skip an exit, so no other successors are needed.
+                    opened_nodes_output.extend(Some(
+                        node.generate_secondary_match_run_successor(run_length, self),
+                    ));
+                } else {
+                    // Only generate secondary successors if they can ever exit the template switch based on their length.
+                    let min_length_cost = config.length_costs.min(length..).unwrap();
+                    if min_length_cost != Cost::MAX {
+                        let edit_costs = config.secondary_edit_costs_of(template_switch_strand);
+                        // The next and the previous character of the copied strand of the secondary.
+                        let (next_secondary_character, previous_secondary_character) =
+                            match template_switch_strand {
+                                TemplateSwitchStrand::ReverseComplement => (
+                                    secondary_index
+                                        .checked_sub(1)
+                                        .map(|index| secondary_sequence[index].complement()),
+                                    (secondary_index < secondary_sequence.len())
+                                        .then(|| secondary_sequence[secondary_index].complement()),
+                                ),
+                                TemplateSwitchStrand::Forward => (
+                                    (secondary_index < secondary_sequence.len())
+                                        .then(|| secondary_sequence[secondary_index].clone()),
+                                    secondary_index
+                                        .checked_sub(1)
+                                        .map(|index| secondary_sequence[index].clone()),
+                                ),
+                            };
 
-                    if secondary_index > 0
-                        && Strategies::SecondaryDeletion::allow_secondary_deletions()
-                    {
-                        // Deleted character
-                        let s = secondary_sequence[secondary_index - 1].complement();
+                        if let Some(s) = &next_secondary_character {
+                            if primary_index < primary_sequence.len() {
+                                // Diagonal characters
+                                let p = primary_sequence[primary_index].clone();
+
+                                opened_nodes_output.extend(
+                                    node.generate_secondary_diagonal_successor(
+                                        edit_costs.match_or_substitution_cost(p.clone(), s.clone()),
+                                        p == *s,
+                                        self,
+                                    ),
+                                );
+                            }
 
-                        opened_nodes_output.extend(
-                            node.generate_secondary_deletion_successor(
-                                config
-                                    .secondary_edit_costs
-                                    .gap_costs(s, gap_type != GapType::Deletion),
-                                self,
-                            ),
-                        );
+                            if Strategies::SecondaryDeletion::allow_secondary_deletions() {
+                                // Deleted character
+                                for long_gap in [false, true] {
+                                    let previous = (gap_type == GapType::deletion(long_gap))
+                                        .then(|| previous_secondary_character.clone())
+                                        .flatten();
+
+                                    opened_nodes_output.extend(
+                                        node.generate_secondary_deletion_successor(
+                                            long_gap,
+                                            edit_costs.homopolymer_gap_costs(
+                                                s.clone(),
+                                                IndelType::Deletion,
+                                                long_gap,
+                                                previous,
+                                                homopolymer_length,
+                                            ),
+                                            self,
+                                        ),
+                                    );
+                                }
+                            }
+                        }
+
+                        if primary_index < primary_sequence.len() {
+                            // Inserted character
+                            let p = primary_sequence[primary_index].clone();
+                            for long_gap in [false, true] {
+                                let previous = (gap_type == GapType::insertion(long_gap))
+                                    .then(|| primary_sequence[primary_index - 1].clone());
+
+                                opened_nodes_output.extend(
+                                    node.generate_secondary_insertion_successor(
+                                        long_gap,
+                                        edit_costs.homopolymer_gap_costs(
+                                            p.clone(),
+                                            IndelType::Insertion,
+                                            long_gap,
+                                            previous.clone(),
+                                            homopolymer_length,
+                                        ),
+                                        self,
+                                    ),
+                                );
+                            }
+                        }
                     }
 
-                    if primary_index < primary_sequence.len() {
-                        // Inserted character
-                        let p = primary_sequence[primary_index].clone();
+                    let length_cost = config.length_costs.evaluate(&length);
+                    if length_cost != Cost::MAX {
+                        let length_difference_cost = config.length_difference_costs.evaluate(&0);
+                        assert_ne!(length_difference_cost, Cost::MAX);
+                        let cost_increment = length_cost + length_difference_cost;
 
                         opened_nodes_output.extend(
-                            node.generate_secondary_insertion_successor(
-                                config
-                                    .secondary_edit_costs
-                                    .gap_costs(p, gap_type != GapType::Insertion),
+                            node.generate_initial_template_switch_exit_successor(
+                                cost_increment,
                                 self,
                             ),
-                        );
+                        )
                     }
                 }
-
-                let length_cost = config.length_costs.evaluate(&length);
-                if length_cost != Cost::MAX {
-                    let length_difference_cost = config.length_difference_costs.evaluate(&0);
-                    assert_ne!(length_difference_cost, Cost::MAX);
-                    let cost_increment = length_cost + length_difference_cost;
-
-                    opened_nodes_output.extend(
-                        node.generate_initial_template_switch_exit_successor(cost_increment, self),
-                    )
-                }
             }
 
             Identifier::TemplateSwitchExit {
@@ -530,6 +852,36 @@ impl<
                     },
                 ));
             }
+
+            Identifier::Inversion {
+                start_reference_index,
+                reference_index,
+                query_index,
+                ..
+            } => {
+                if reference_index == start_reference_index {
+                    opened_nodes_output.extend(Some(node.generate_inversion_exit_successor(self)));
+                } else {
+                    // The entrance ensures that the query is long enough for the whole inversion.
+                    debug_assert!(query_index < self.query.len(), "{node:?}");
+                    let r = self.reference[reference_index - 1].complement();
+                    let q = self.query[query_index].clone();
+                    let is_match = r == q;
+                    // The inversion reads the reverse complement of the reference,
+                    // so the character before `r`, which selects the context substitution cost table, is the complement of the next reference character.
+                    let previous = (reference_index < self.reference.len())
+                        .then(|| self.reference[reference_index].complement());
+                    let cost_increment = config
+                        .primary_edit_costs
+                        .context_match_or_substitution_cost(previous, r, q);
+
+                    opened_nodes_output.extend(node.generate_inversion_diagonal_successor(
+                        cost_increment,
+                        is_match,
+                        self,
+                    ));
+                }
+            }
         }
 
         // Add additional successors through strategies.
@@ -541,8 +893,13 @@ impl<
             Identifier::Primary {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
-            } => reference_index == self.reference.len() && query_index == self.query.len(),
+            } => {
+                reference_index == self.reference.len()
+                    && query_index == self.query.len()
+                    && (template_switched || !self.self_alignment)
+            }
             _ => false,
         }
     }
@@ -550,6 +907,10 @@ impl<
     fn max_cost(&self) -> Option<Cost> {
         self.max_cost
     }
+
+    fn is_label_setting(&self) -> bool {
+        <Strategies::Chaining as ChainingStrategy>::is_label_setting(&self.memory.chaining)
+    }
 }
 
 fn generate_output_mapper_function<
@@ -569,6 +930,20 @@ fn generate_output_mapper_function<
     }
 }
 
+fn generate_output_filter_function<
+    'context,
+    'reference,
+    'query,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    Strategies: AlignmentStrategySelector,
+>(
+    context: &'context Context<'reference, 'query, SubsequenceType, Strategies>,
+) -> impl use<'context, 'reference, 'query, SubsequenceType, Strategies>
+       + Fn(&<Context<'reference, 'query, SubsequenceType, Strategies> as AStarContext>::Node) -> bool
+{
+    move |node| context.is_within_guide(node)
+}
+
 impl<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
         Strategies: AlignmentStrategySelector,
@@ -603,6 +978,53 @@ impl<
     fn query(&self) -> &Self::SubsequenceType {
         self.query
     }
+
+    fn update_statistics(&self, statistics: &mut AlignmentStatistics) {
+        Strategies::TemplateSwitchMinLength::update_statistics(
+            &self.memory.template_switch_min_length,
+            statistics,
+        );
+        Strategies::Chaining::update_statistics(&self.memory.chaining, statistics);
+    }
+
+    fn update_cooptimal_statistics(
+        &self,
+        alignment: &[(usize, Self::AlignmentType)],
+        cooptimal_alignments: &[Vec<(usize, Self::AlignmentType)>],
+        statistics: &mut AlignmentStatistics,
+    ) {
+        let cooptimal_template_switches: Vec<_> = cooptimal_alignments
+            .iter()
+            .map(|cooptimal_alignment| {
+                TemplateSwitchAnnotation::canonical_from_alignment(
+                    cooptimal_alignment,
+                    self.reference,
+                    self.query,
+                )
+            })
+            .collect();
+
+        statistics.template_switch_support = TemplateSwitchAnnotation::canonical_from_alignment(
+            alignment,
+            self.reference,
+            self.query,
+        )
+        .iter()
+        .map(|template_switch| {
+            cooptimal_template_switches
+                .iter()
+                .filter(|template_switches| template_switches.contains(template_switch))
+                .count() as u64
+        })
+        .collect();
+        statistics.ambiguous_template_switches = statistics
+            .template_switch_support
+            .iter()
+            .enumerate()
+            .filter(|(_, &support)| support < cooptimal_template_switches.len() as u64)
+            .map(|(index, _)| index)
+            .collect();
+    }
 }
 
 impl<Strategies: AlignmentStrategySelector> Display for AlignmentStrategiesNodeMemory<Strategies> {
@@ -1,6 +1,9 @@
 use std::fmt::{Display, Formatter, Result};
 
-use super::{AlignmentType, GapType, Identifier, TemplateSwitchPrimary, TemplateSwitchSecondary};
+use super::{
+    AlignmentType, GapType, Identifier, TemplateSwitchPrimary, TemplateSwitchSecondary,
+    TemplateSwitchStrand,
+};
 
 impl Display for AlignmentType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
@@ -15,11 +18,15 @@ impl Display for AlignmentType {
             | Self::PrimaryFlankSubstitution
             | Self::SecondarySubstitution => write!(f, "S"),
             Self::PrimaryMatch | Self::PrimaryFlankMatch | Self::SecondaryMatch => write!(f, "M"),
+            Self::PrimaryMatchRun { length } | Self::SecondaryMatchRun { length } => {
+                write!(f, "{length}M")
+            }
             Self::TemplateSwitchEntrance {
                 primary,
                 secondary,
+                strand,
                 first_offset,
-            } => write!(f, "[TS{primary}{secondary}{first_offset}:"),
+            } => write!(f, "[TS{primary}{secondary}{strand}{first_offset}:"),
             Self::TemplateSwitchExit { length_difference } => write!(f, ":{length_difference}]"),
             Self::Root => Ok(()),
             Self::SecondaryRoot => Ok(()),
@@ -28,6 +35,11 @@ impl Display for AlignmentType {
                 delta_reference,
                 delta_query,
             } => write!(f, "[PS:R{delta_reference}Q{delta_query}]"),
+            Self::PrimaryDuplication { length } => write!(f, "[DUP{length}]"),
+            Self::InversionEntrance { length } => write!(f, "[INV{length}:"),
+            Self::InversionSubstitution => write!(f, "S"),
+            Self::InversionMatch => write!(f, "M"),
+            Self::InversionExit => write!(f, "]"),
         }
     }
 }
@@ -37,6 +49,8 @@ impl Display for GapType {
         match self {
             Self::Insertion => write!(f, "I"),
             Self::Deletion => write!(f, "D"),
+            Self::LongInsertion => write!(f, "LI"),
+            Self::LongDeletion => write!(f, "LD"),
             Self::None => write!(f, "M/S"),
         }
     }
@@ -60,6 +74,16 @@ impl Display for TemplateSwitchSecondary {
     }
 }
 
+/// Template switches that copy the reverse complement are written without a strand, since they are the default.
+impl Display for TemplateSwitchStrand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::ReverseComplement => Ok(()),
+            Self::Forward => write!(f, "F"),
+        }
+    }
+}
+
 impl<PrimaryExtraData> Display for Identifier<PrimaryExtraData> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
@@ -92,15 +116,17 @@ impl<PrimaryExtraData> Display for Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 template_switch_first_offset,
             } => {
                 write!(
                     f,
-                    "TemplateSwitchEntrance({}R, {}Q, {}P, {}S, {}O)",
+                    "TemplateSwitchEntrance({}R, {}Q, {}P, {}S{}, {}O)",
                     entrance_reference_index,
                     entrance_query_index,
                     template_switch_primary,
                     template_switch_secondary,
+                    template_switch_strand,
                     template_switch_first_offset
                 )
             }
@@ -110,13 +136,15 @@ impl<PrimaryExtraData> Display for Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
                 secondary_index,
                 gap_type,
+                ..
             } => write!(
                 f,
-                "Secondary({}R, {}Q, {}L, {}P, {}S, {}, {}, {})",
+                "Secondary({}R, {}Q, {}L, {}P, {}S, {}, {}{}, {})",
                 entrance_reference_index,
                 entrance_query_index,
                 length,
@@ -124,6 +152,7 @@ impl<PrimaryExtraData> Display for Identifier<PrimaryExtraData> {
                 secondary_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 gap_type
             ),
 
@@ -132,17 +161,30 @@ impl<PrimaryExtraData> Display for Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 primary_index,
                 length_difference,
             } => write!(
                 f,
-                "TemplateSwitchExit({}R, {}Q, {}P, {}D, {}, {})",
+                "TemplateSwitchExit({}R, {}Q, {}P, {}D, {}, {}{})",
                 entrance_reference_index,
                 entrance_query_index,
                 primary_index,
                 length_difference,
                 template_switch_primary,
-                template_switch_secondary
+                template_switch_secondary,
+                template_switch_strand
+            ),
+
+            Self::Inversion {
+                start_reference_index,
+                end_reference_index,
+                reference_index,
+                query_index,
+            } => write!(
+                f,
+                "Inversion({}R, {}Q, {}..{})",
+                reference_index, query_index, start_reference_index, end_reference_index
             ),
         }
     }
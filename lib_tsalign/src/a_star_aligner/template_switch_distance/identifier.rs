@@ -11,16 +11,32 @@ pub enum Identifier<PrimaryExtraData> {
         reference_index: usize,
         query_index: usize,
         gap_type: GapType,
+        /// The number of identical characters at the end of the current gap.
+        ///
+        /// Only tracked if homopolymer gap extend costs are used, and zero otherwise.
+        homopolymer_length: u8,
         /// Positive for left flank, negative for right flank.
         flank_index: isize,
+        /// True if a template switch or a jump event was taken before this node.
+        ///
+        /// Only tracked in self-alignment mode, and false otherwise.
+        template_switched: bool,
         data: PrimaryExtraData,
     },
     PrimaryReentry {
         reference_index: usize,
         query_index: usize,
         gap_type: GapType,
+        /// The number of identical characters at the end of the current gap.
+        ///
+        /// Only tracked if homopolymer gap extend costs are used, and zero otherwise.
+        homopolymer_length: u8,
         /// Positive for left flank, negative for right flank.
         flank_index: isize,
+        /// True if a template switch or a jump event was taken before this node.
+        ///
+        /// Only tracked in self-alignment mode, and false otherwise.
+        template_switched: bool,
         data: PrimaryExtraData,
     },
     TemplateSwitchEntrance {
@@ -28,6 +44,7 @@ pub enum Identifier<PrimaryExtraData> {
         entrance_query_index: usize,
         template_switch_primary: TemplateSwitchPrimary,
         template_switch_secondary: TemplateSwitchSecondary,
+        template_switch_strand: TemplateSwitchStrand,
         template_switch_first_offset: isize,
     },
     Secondary {
@@ -35,28 +52,52 @@ pub enum Identifier<PrimaryExtraData> {
         entrance_query_index: usize,
         template_switch_primary: TemplateSwitchPrimary,
         template_switch_secondary: TemplateSwitchSecondary,
+        template_switch_strand: TemplateSwitchStrand,
         length: usize,
         /// The index that does not jump.
         primary_index: usize,
         /// The index that jumps.
+        ///
+        /// The next secondary character is the one before this index on the reverse complement strand,
+        /// and the one at this index on the forward strand.
         secondary_index: usize,
         gap_type: GapType,
+        /// The number of identical characters at the end of the current gap.
+        ///
+        /// Only tracked if homopolymer gap extend costs are used, and zero otherwise.
+        homopolymer_length: u8,
     },
     TemplateSwitchExit {
         entrance_reference_index: usize,
         entrance_query_index: usize,
         template_switch_primary: TemplateSwitchPrimary,
         template_switch_secondary: TemplateSwitchSecondary,
+        template_switch_strand: TemplateSwitchStrand,
         /// The index that does not jump.
         primary_index: usize,
         length_difference: isize,
     },
+    /// Inside an inversion, where the query is aligned to the reverse complement of the reference without gaps.
+    Inversion {
+        /// The first reference index of the inverted region.
+        start_reference_index: usize,
+        /// The reference index after the inverted region, where the primary alignment continues after the inversion.
+        end_reference_index: usize,
+        /// Decreases from `end_reference_index` to `start_reference_index`,
+        /// where the next reference character is the one before this index.
+        reference_index: usize,
+        query_index: usize,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub enum GapType {
     Insertion,
     Deletion,
+    /// An insertion using the long gap costs of the two-piece affine gap model.
+    LongInsertion,
+    /// A deletion using the long gap costs of the two-piece affine gap model.
+    LongDeletion,
     None,
 }
 
@@ -76,6 +117,47 @@ pub enum TemplateSwitchSecondary {
     Query,
 }
 
+/// The strand of the secondary sequence that a template switch copies.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TemplateSwitchStrand {
+    /// The primary is aligned backwards to the complement of the secondary, as for template switches mediated by inverted repeats.
+    #[default]
+    ReverseComplement,
+    /// The primary is aligned forwards to the secondary itself, as for template switches mediated by direct repeats.
+    Forward,
+}
+
+impl GapType {
+    pub const fn insertion(long_gap: bool) -> Self {
+        if long_gap {
+            Self::LongInsertion
+        } else {
+            Self::Insertion
+        }
+    }
+
+    pub const fn deletion(long_gap: bool) -> Self {
+        if long_gap {
+            Self::LongDeletion
+        } else {
+            Self::Deletion
+        }
+    }
+}
+
+impl TemplateSwitchStrand {
+    pub const ALL: [Self; 2] = [Self::ReverseComplement, Self::Forward];
+
+    /// Returns the secondary index after consuming `count` secondary characters from `secondary_index`.
+    pub const fn advance(self, secondary_index: usize, count: usize) -> usize {
+        match self {
+            Self::ReverseComplement => secondary_index - count,
+            Self::Forward => secondary_index + count,
+        }
+    }
+}
+
 impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
     pub const fn new_primary(
         reference_index: usize,
@@ -94,6 +176,8 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
             query_index,
             flank_index,
             gap_type,
+            homopolymer_length: 0,
+            template_switched: false,
             data,
         }
     }
@@ -112,11 +196,13 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
             Self::Primary {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             }
             | Self::PrimaryReentry {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             } => {
                 debug_assert!(reference_index != usize::MAX);
@@ -129,6 +215,8 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                     query_index: query_index + 1,
                     flank_index,
                     gap_type: GapType::None,
+                    homopolymer_length: 0,
+                    template_switched,
                     data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(self, alignment_type, context),
                 }
             }
@@ -138,6 +226,43 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
         }
     }
 
+    pub fn generate_primary_match_run_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>,
+        PrimaryMatch: PrimaryMatchStrategy<IdentifierPrimaryExtraData = PrimaryExtraData>,
+    >(
+        self,
+        run_length: usize,
+        alignment_type: AlignmentType,
+        context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        match self {
+            Self::Primary {
+                reference_index,
+                query_index,
+                template_switched,
+                ..
+            }
+            | Self::PrimaryReentry {
+                reference_index,
+                query_index,
+                template_switched,
+                ..
+            } => Self::Primary {
+                reference_index: reference_index + run_length,
+                query_index: query_index + run_length,
+                flank_index: 0,
+                gap_type: GapType::None,
+                homopolymer_length: 0,
+                template_switched,
+                data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(self, alignment_type, context),
+            },
+            other => unreachable!(
+                "Function is only called on primary identifiers, but this is: {other}."
+            ),
+        }
+    }
+
     pub fn generate_primary_deletion_successor<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
         Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>,
@@ -145,6 +270,8 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
     >(
         self,
         flank_index: isize,
+        long_gap: bool,
+        homopolymer_length: u8,
         alignment_type: AlignmentType,
         context: &Context<'_, '_, SubsequenceType, Strategies>,
     ) -> Self {
@@ -152,11 +279,13 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
             Self::Primary {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             }
             | Self::PrimaryReentry {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             } => {
                 debug_assert!(reference_index != usize::MAX);
@@ -168,7 +297,9 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                     reference_index: reference_index + 1,
                     query_index,
                     flank_index,
-                    gap_type: GapType::Deletion,
+                    gap_type: GapType::deletion(long_gap),
+                    homopolymer_length,
+                    template_switched,
                     data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(self, alignment_type, context),
                 }
             }
@@ -185,6 +316,8 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
     >(
         self,
         flank_index: isize,
+        long_gap: bool,
+        homopolymer_length: u8,
         alignment_type: AlignmentType,
         context: &Context<'_, '_, SubsequenceType, Strategies>,
     ) -> Self {
@@ -192,11 +325,13 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
             Self::Primary {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             }
             | Self::PrimaryReentry {
                 reference_index,
                 query_index,
+                template_switched,
                 ..
             } => {
                 debug_assert!(reference_index != usize::MAX);
@@ -208,7 +343,9 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                     reference_index,
                     query_index: query_index + 1,
                     flank_index,
-                    gap_type: GapType::Insertion,
+                    gap_type: GapType::insertion(long_gap),
+                    homopolymer_length,
+                    template_switched,
                     data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(self, alignment_type, context),
                 }
             }
@@ -218,8 +355,82 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
         }
     }
 
+    /// A jump event from a primary node to the given indices, where the primary alignment continues outside of flanks.
+    pub fn generate_primary_jump_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>,
+        PrimaryMatch: PrimaryMatchStrategy<IdentifierPrimaryExtraData = PrimaryExtraData>,
+    >(
+        self,
+        reference_index: usize,
+        query_index: usize,
+        alignment_type: AlignmentType,
+        context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        debug_assert!(matches!(
+            self,
+            Self::Primary { .. } | Self::PrimaryReentry { .. } | Self::Inversion { .. }
+        ));
+        debug_assert!(reference_index < isize::MAX as usize);
+        debug_assert!(query_index < isize::MAX as usize);
+
+        Self::Primary {
+            reference_index,
+            query_index,
+            flank_index: 0,
+            gap_type: GapType::None,
+            homopolymer_length: 0,
+            template_switched: context.self_alignment(),
+            data: <<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::generate_successor_identifier_primary_extra_data(self, alignment_type, context),
+        }
+    }
+
+    /// Enter an inversion of the next `length` characters of the reference.
+    pub fn generate_inversion_entrance_successor(self, length: usize) -> Self {
+        match self {
+            Self::Primary {
+                reference_index,
+                query_index,
+                ..
+            }
+            | Self::PrimaryReentry {
+                reference_index,
+                query_index,
+                ..
+            } => Self::Inversion {
+                start_reference_index: reference_index,
+                end_reference_index: reference_index + length,
+                reference_index: reference_index + length,
+                query_index,
+            },
+            other => unreachable!(
+                "Function is only called on primary identifiers, but this is: {other}."
+            ),
+        }
+    }
+
+    pub fn generate_inversion_diagonal_successor(self) -> Self {
+        match self {
+            Self::Inversion {
+                start_reference_index,
+                end_reference_index,
+                reference_index,
+                query_index,
+            } => Self::Inversion {
+                start_reference_index,
+                end_reference_index,
+                reference_index: reference_index - 1,
+                query_index: query_index + 1,
+            },
+            other => unreachable!(
+                "Function is only called on inversion identifiers, but this is: {other}."
+            ),
+        }
+    }
+
     pub fn generate_initial_template_switch_entrance_successors(
         self,
+        template_switch_strand: TemplateSwitchStrand,
     ) -> impl Iterator<Item = Self> {
         match self {
             Identifier::Primary {
@@ -254,6 +465,7 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                             entrance_query_index,
                             template_switch_primary,
                             template_switch_secondary,
+                            template_switch_strand,
                             template_switch_first_offset,
                         }
                     },
@@ -273,6 +485,7 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
                 secondary_index,
@@ -282,10 +495,42 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length: length + 1,
                 primary_index: primary_index + 1,
-                secondary_index: secondary_index - 1,
+                secondary_index: template_switch_strand.advance(secondary_index, 1),
                 gap_type: GapType::None,
+                homopolymer_length: 0,
+            },
+            other => unreachable!(
+                "Function is only called on primary identifiers, but this is: {other}."
+            ),
+        }
+    }
+
+    pub fn generate_secondary_match_run_successor(self, run_length: usize) -> Self {
+        match self {
+            Self::Secondary {
+                entrance_reference_index,
+                entrance_query_index,
+                template_switch_primary,
+                template_switch_secondary,
+                template_switch_strand,
+                length,
+                primary_index,
+                secondary_index,
+                ..
+            } => Self::Secondary {
+                entrance_reference_index,
+                entrance_query_index,
+                template_switch_primary,
+                template_switch_secondary,
+                template_switch_strand,
+                length: length + run_length,
+                primary_index: primary_index + run_length,
+                secondary_index: template_switch_strand.advance(secondary_index, run_length),
+                gap_type: GapType::None,
+                homopolymer_length: 0,
             },
             other => unreachable!(
                 "Function is only called on primary identifiers, but this is: {other}."
@@ -294,13 +539,18 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
     }
 
     /// The secondary contains a base missing in the primary.
-    pub fn generate_secondary_deletion_successor(self) -> Self {
+    pub fn generate_secondary_deletion_successor(
+        self,
+        long_gap: bool,
+        homopolymer_length: u8,
+    ) -> Self {
         match self {
             Self::Secondary {
                 entrance_reference_index,
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
                 secondary_index,
@@ -310,10 +560,12 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
-                secondary_index: secondary_index - 1,
-                gap_type: GapType::Deletion,
+                secondary_index: template_switch_strand.advance(secondary_index, 1),
+                gap_type: GapType::deletion(long_gap),
+                homopolymer_length,
             },
             other => unreachable!(
                 "Function is only called on primary identifiers, but this is: {other}."
@@ -322,13 +574,18 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
     }
 
     /// The secondary misses a base present in the primary.
-    pub fn generate_secondary_insertion_successor(self) -> Self {
+    pub fn generate_secondary_insertion_successor(
+        self,
+        long_gap: bool,
+        homopolymer_length: u8,
+    ) -> Self {
         match self {
             Self::Secondary {
                 entrance_reference_index,
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length,
                 primary_index,
                 secondary_index,
@@ -338,10 +595,12 @@ impl<PrimaryExtraData> Identifier<PrimaryExtraData> {
                 entrance_query_index,
                 template_switch_primary,
                 template_switch_secondary,
+                template_switch_strand,
                 length: length + 1,
                 primary_index: primary_index + 1,
                 secondary_index,
-                gap_type: GapType::Insertion,
+                gap_type: GapType::insertion(long_gap),
+                homopolymer_length,
             },
             other => unreachable!(
                 "Function is only called on primary identifiers, but this is: {other}."
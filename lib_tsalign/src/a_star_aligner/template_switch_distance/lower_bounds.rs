@@ -1,2 +1,110 @@
+//! Lower bounds of the template switch distance that are precomputed before aligning.
+//!
+//! The precomputation may run for minutes with large configs or sequences.
+//! It can be observed, limited and cancelled with a [`LowerBoundPrecomputation`].
+
+use std::time::{Duration, Instant};
+
+use generic_a_star::cancellation::CancellationToken;
+use log::warn;
+
+use crate::{a_star_aligner::alignment_result::NoTargetReason, error::Error};
+
 pub mod template_switch;
 pub mod template_switch_alignment;
+#[cfg(test)]
+mod tests;
+
+/// The progress of precomputing a lower bound matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowerBoundProgress {
+    /// The name of the matrix, `"TS"` or `"TSA"`.
+    pub matrix: &'static str,
+    /// The number of entries computed so far.
+    pub computed_entries: usize,
+    /// The number of entries of the matrix, if known in advance.
+    pub total_entries: Option<usize>,
+    /// The time since the precomputation of the matrix started.
+    pub elapsed: Duration,
+}
+
+/// What happens if the precomputation of a lower bound matrix exceeds its time limit or is cancelled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LowerBoundEarlyStop {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Return the entries computed so far, and use zero for the others.
+    ///
+    /// The resulting lower bounds stay admissible, but guide the search less well.
+    /// A TS lower bound matrix cannot be padded, since its entries are the only places where template switches may end,
+    /// hence it is marked as incomplete, and TSA lower bound matrices computed from it are all zero.
+    Degrade,
+}
+
+/// Callbacks and limits of precomputing lower bound matrices.
+#[derive(Default)]
+pub struct LowerBoundPrecomputation<'callback> {
+    /// Called after each computed entry.
+    pub progress: Option<Box<dyn FnMut(LowerBoundProgress) + 'callback>>,
+    /// The maximum duration of precomputing each matrix.
+    pub time_limit: Option<Duration>,
+    pub cancellation_token: Option<CancellationToken>,
+    pub early_stop: LowerBoundEarlyStop,
+}
+
+impl LowerBoundPrecomputation<'_> {
+    /// Returns the reason to stop the precomputation of the matrix started at `start_time`, if any.
+    fn stop_reason(&self, start_time: Instant) -> Option<NoTargetReason> {
+        if self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            Some(NoTargetReason::Cancelled)
+        } else if self
+            .time_limit
+            .is_some_and(|time_limit| start_time.elapsed() >= time_limit)
+        {
+            Some(NoTargetReason::ExceededTimeLimit)
+        } else {
+            None
+        }
+    }
+
+    /// The time left of the time limit of the matrix started at `start_time`.
+    fn remaining_time(&self, start_time: Instant) -> Option<Duration> {
+        self.time_limit
+            .map(|time_limit| time_limit.saturating_sub(start_time.elapsed()))
+    }
+
+    fn report_progress(
+        &mut self,
+        matrix: &'static str,
+        computed_entries: usize,
+        total_entries: Option<usize>,
+        start_time: Instant,
+    ) {
+        if let Some(progress) = &mut self.progress {
+            progress(LowerBoundProgress {
+                matrix,
+                computed_entries,
+                total_entries,
+                elapsed: start_time.elapsed(),
+            });
+        }
+    }
+
+    /// Returns an error if the early stop mode is [`LowerBoundEarlyStop::Error`].
+    fn early_stop(&self, matrix: &'static str, reason: NoTargetReason) -> Result<(), Error> {
+        match self.early_stop {
+            LowerBoundEarlyStop::Error => {
+                Err(Error::LowerBoundPrecomputationStopped { matrix, reason })
+            }
+            LowerBoundEarlyStop::Degrade => {
+                warn!("Stopped computing the {matrix} lower bound matrix early ({reason:?}), the lower bounds are degraded");
+                Ok(())
+            }
+        }
+    }
+}
@@ -2,18 +2,21 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::{Display, Write},
     iter,
+    time::Instant,
 };
 
 use compact_genome::{
     implementation::vec_sequence::VectorGenome,
     interface::{alphabet::Alphabet, sequence::GenomeSequence},
 };
+use deterministic_default_hasher::DeterministicDefaultHasher;
 use generic_a_star::{cost::Cost, AStar, AStarNode, AStarResult};
 use log::{debug, info, trace};
 
+use super::LowerBoundPrecomputation;
 use crate::{
     a_star_aligner::{
-        alignment_result::IAlignmentType,
+        alignment_result::{IAlignmentType, NoTargetReason},
         template_switch_distance::{
             context::Memory,
             identifier::GapType,
@@ -25,11 +28,12 @@ use crate::{
                 template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
                 AlignmentStrategySelection,
             },
-            Context, Identifier, Node,
+            Context, Identifier, Node, TemplateSwitchStrand,
         },
     },
-    config::TemplateSwitchConfig,
+    config::{TemplateSwitchConfig, TemplateSwitchDirections},
     costs::gap_affine::GapAffineAlignmentCostTable,
+    error::{Error, Result},
 };
 
 #[derive(Debug, Clone)]
@@ -37,6 +41,9 @@ use crate::{
 pub struct TemplateSwitchLowerBoundMatrix {
     entries: Vec<TSLBMatrixEntry>,
     min_distance_between_two_template_switches: usize,
+    /// True if the precomputation was stopped early, such that some template switches are missing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    incomplete: bool,
 }
 
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
@@ -59,22 +66,47 @@ type TSLBAlignmentStrategies<AlphabetType> = AlignmentStrategySelection<
 >;
 
 impl TemplateSwitchLowerBoundMatrix {
-    pub fn new<AlphabetType: Alphabet>(config: &TemplateSwitchConfig<AlphabetType>) -> Self {
+    /// Returns an error if some secondary gap extend cost is zero, since then the search would not terminate.
+    ///
+    /// Also returns an error if the config contains jump events,
+    /// since these can explain the gaps between anchors more cheaply than assumed by the lower bounds.
+    pub fn new<AlphabetType: Alphabet>(
+        config: &TemplateSwitchConfig<AlphabetType>,
+    ) -> Result<Self> {
+        Self::new_with_precomputation(config, &mut LowerBoundPrecomputation::default())
+    }
+
+    /// Like [`new`](Self::new), but reports progress and stops early as configured in `precomputation`.
+    ///
+    /// If stopped early with [`LowerBoundEarlyStop::Degrade`](super::LowerBoundEarlyStop::Degrade),
+    /// then the matrix is [incomplete](Self::is_complete).
+    pub fn new_with_precomputation<AlphabetType: Alphabet>(
+        config: &TemplateSwitchConfig<AlphabetType>,
+        precomputation: &mut LowerBoundPrecomputation,
+    ) -> Result<Self> {
+        if config.has_jump_events() {
+            return Err(Error::JumpEventsWithLowerBounds);
+        }
+
         info!("Computing TS lower bound matrix...");
         let lower_bound_config = generate_template_switch_lower_bound_config(config);
-        assert!(
-            lower_bound_config
-                .secondary_edit_costs
-                .min_gap_extend_cost()
-                > Cost::ZERO,
-            "Secondary gap extend costs must be greater than zero for all alphabet characters."
-        );
+        let min_gap_extend_cost = lower_bound_config
+            .secondary_edit_costs
+            .min_gap_extend_cost();
+        if min_gap_extend_cost == Cost::ZERO {
+            return Err(Error::ZeroSecondaryGapExtendCost {
+                min_gap_extend_cost,
+            });
+        }
 
-        let mut open_lower_bounds = HashSet::new();
+        // Use deterministic hashers, since the iteration order of the open set determines the search order.
+        let mut open_lower_bounds = HashSet::with_hasher(DeterministicDefaultHasher);
         open_lower_bounds.insert((0isize, 0isize));
-        let mut closed_lower_bounds = HashMap::new();
-        let mut previous_closed_lower_bounds = HashMap::new();
+        let mut closed_lower_bounds = HashMap::with_hasher(DeterministicDefaultHasher);
+        let mut previous_closed_lower_bounds = HashMap::with_hasher(DeterministicDefaultHasher);
         let mut genome_length = 1_000;
+        let start_time = Instant::now();
+        let mut stop_reason = None;
 
         'outer: loop {
             debug!("Using genome length {genome_length}");
@@ -87,6 +119,7 @@ impl TemplateSwitchLowerBoundMatrix {
                 genome.as_genome_subsequence(),
                 lower_bound_config.clone(),
                 Memory {
+                    node_ord: (),
                     template_switch_min_length: (),
                     chaining: (),
                     template_switch_count: 1,
@@ -95,8 +128,9 @@ impl TemplateSwitchLowerBoundMatrix {
                 },
                 None,
             ));
+            a_star.set_cancellation_token(precomputation.cancellation_token.clone());
             let root_xy = genome_length / 2;
-            a_star.initialise_with(|context| Node::new_root_at(root_xy, root_xy, context));
+            a_star.initialise_with(|context| Node::new_root_at(root_xy, root_xy, context))?;
             previous_closed_lower_bounds.extend(closed_lower_bounds.drain());
 
             let root_xy_isize = isize::try_from(root_xy).unwrap();
@@ -107,8 +141,13 @@ impl TemplateSwitchLowerBoundMatrix {
                 if closed_lower_bounds.contains_key(&(x, y)) {
                     continue 'inner;
                 }
+                if let Some(reason) = precomputation.stop_reason(start_time) {
+                    stop_reason = Some(reason);
+                    break 'outer;
+                }
 
                 trace!("Searching for target ({x}, {y})");
+                a_star.set_time_limit(precomputation.remaining_time(start_time));
 
                 let has_target = match a_star.search_until(|context, node| {
                     match *node.identifier() {
@@ -192,8 +231,11 @@ impl TemplateSwitchLowerBoundMatrix {
                         Identifier::TemplateSwitchExit { .. } => {
                             node.generate_primary_reentry_successor(context).is_none()
                         }
+                        Identifier::Inversion { .. } => {
+                            unreachable!("The lower bound config contains no jump events.")
+                        }
                     }
-                }) {
+                })? {
                     AStarResult::FoundTarget { identifier, cost } => {
                         trace!("Search termianted with target {identifier} at cost {cost}");
 
@@ -205,7 +247,7 @@ impl TemplateSwitchLowerBoundMatrix {
                                 let mut alignment = Vec::new();
 
                                 // Backtrack.
-                                for alignment_type in a_star.backtrack() {
+                                for alignment_type in a_star.backtrack()? {
                                     if let Some((count, previous_alignment_type)) =
                                         alignment.last_mut()
                                     {
@@ -227,6 +269,17 @@ impl TemplateSwitchLowerBoundMatrix {
                             continue 'outer;
                         }
                     }
+                    AStarResult::ExceededMemoryLimit { .. } => {
+                        unreachable!("No memory limit was set")
+                    }
+                    AStarResult::ExceededTimeLimit { .. } => {
+                        stop_reason = Some(NoTargetReason::ExceededTimeLimit);
+                        break 'outer;
+                    }
+                    AStarResult::Cancelled { .. } => {
+                        stop_reason = Some(NoTargetReason::Cancelled);
+                        break 'outer;
+                    }
                     AStarResult::NoTarget { .. } => {
                         trace!("Search terminated without target");
                         let previous = closed_lower_bounds.insert((x, y), Cost::MAX);
@@ -239,11 +292,16 @@ impl TemplateSwitchLowerBoundMatrix {
                     trace!("Inserting neighbours after search");
                     enqueue_neighbours(x, y, &mut closed_lower_bounds, &mut open_lower_bounds);
                 }
+                precomputation.report_progress("TS", closed_lower_bounds.len(), None, start_time);
             }
 
             break;
         }
 
+        if let Some(reason) = stop_reason {
+            precomputation.early_stop("TS", reason)?;
+        }
+
         let entries = closed_lower_bounds
             .into_iter()
             .filter_map(|((x, y), cost)| {
@@ -257,10 +315,16 @@ impl TemplateSwitchLowerBoundMatrix {
         let min_distance_between_two_template_switches =
             usize::try_from(config.left_flank_length + config.right_flank_length).unwrap();
 
-        Self {
+        Ok(Self {
             entries,
             min_distance_between_two_template_switches,
-        }
+            incomplete: stop_reason.is_some(),
+        })
+    }
+
+    /// Returns false if the precomputation was stopped early, such that template switches ending elsewhere than in the entries are possible.
+    pub fn is_complete(&self) -> bool {
+        !self.incomplete
     }
 
     pub fn min_distance_between_two_template_switches(&self) -> usize {
@@ -289,20 +353,35 @@ impl TSLBMatrixEntry {
 fn generate_template_switch_lower_bound_config<AlphabetType: Alphabet>(
     config: &TemplateSwitchConfig<AlphabetType>,
 ) -> TemplateSwitchConfig<AlphabetType> {
+    let secondary_edit_costs = if config.forward_base_cost.is_some() {
+        config
+            .secondary_edit_costs
+            .clone()
+            .pointwise_min(config.secondary_edit_costs_of(TemplateSwitchStrand::Forward))
+    } else {
+        config.secondary_edit_costs.clone()
+    };
+
     TemplateSwitchConfig {
         left_flank_length: 0,
         right_flank_length: 0,
         min_length: config.min_length,
+        ts_end_exclusion: 0,
+        // Restricting the directions only increases costs, so a bound over all directions stays admissible.
+        directions: TemplateSwitchDirections::ALL,
+        anchor_repeat_mask: None,
+        anchor_scheme: Default::default(),
 
-        base_cost: config.base_cost,
+        // The bound ignores which characters of the secondary are copied,
+        // so template switches that copy the forward strand only differ by their costs.
+        base_cost: config.min_base_cost(),
+        forward_base_cost: None,
 
         primary_edit_costs: GapAffineAlignmentCostTable::new_max(),
-        secondary_edit_costs: config
-            .secondary_edit_costs
-            .clone()
-            .into_match_agnostic_lower_bound(),
+        secondary_edit_costs: secondary_edit_costs.into_match_agnostic_lower_bound(),
         left_flank_edit_costs: GapAffineAlignmentCostTable::new_max(),
         right_flank_edit_costs: GapAffineAlignmentCostTable::new_max(),
+        forward_secondary_edit_costs: None,
 
         // The offset only affects which part of the secondary string is being compared against, but otherwise does not change anything.
         // Hence we can ignore it for the lower bound, and simply choose its minimum.
@@ -315,14 +394,18 @@ fn generate_template_switch_lower_bound_config<AlphabetType: Alphabet>(
         .unwrap(),
         length_costs: config.length_costs.clone(),
         length_difference_costs: config.length_difference_costs.clone(),
+
+        // The lower bounds are only computed for configs without jump events.
+        duplication_costs: None,
+        inversion_costs: None,
     }
 }
 
 fn enqueue_neighbours(
     x: isize,
     y: isize,
-    closed_lower_bounds: &mut HashMap<(isize, isize), Cost>,
-    open_lower_bounds: &mut HashSet<(isize, isize)>,
+    closed_lower_bounds: &mut HashMap<(isize, isize), Cost, DeterministicDefaultHasher>,
+    open_lower_bounds: &mut HashSet<(isize, isize), DeterministicDefaultHasher>,
 ) {
     for (x, y) in [
         (x + 1, y + 1),
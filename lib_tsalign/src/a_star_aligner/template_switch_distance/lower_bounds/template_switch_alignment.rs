@@ -2,6 +2,7 @@ use std::{
     collections::HashMap,
     fmt::{Display, Write},
     iter,
+    time::Instant,
 };
 
 use compact_genome::{
@@ -9,12 +10,12 @@ use compact_genome::{
     interface::{alphabet::Alphabet, sequence::GenomeSequence},
 };
 use generic_a_star::{cost::Cost, AStar, AStarNode, AStarResult};
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use ndarray::Array2;
 
 use crate::{
     a_star_aligner::{
-        alignment_result::IAlignmentType,
+        alignment_result::{IAlignmentType, NoTargetReason},
         template_switch_distance::{
             context::Memory,
             strategies::{
@@ -32,11 +33,12 @@ use crate::{
             Context, Identifier,
         },
     },
-    config::TemplateSwitchConfig,
+    config::{TemplateSwitchConfig, TemplateSwitchDirections},
     costs::{cost_function::CostFunction, gap_affine::GapAffineAlignmentCostTable},
+    error::Result,
 };
 
-use super::template_switch::TemplateSwitchLowerBoundMatrix;
+use super::{template_switch::TemplateSwitchLowerBoundMatrix, LowerBoundPrecomputation};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -64,6 +66,40 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
         max_consecutive_primary_matches: usize,
         max_consecutive_primary_matches_at_start_and_end: usize,
     ) -> Self {
+        Self::new_with_precomputation(
+            config,
+            tslb_matrix,
+            reference_length,
+            query_length,
+            max_consecutive_primary_matches,
+            max_consecutive_primary_matches_at_start_and_end,
+            &mut LowerBoundPrecomputation::default(),
+        )
+        .unwrap_or_else(|error| unreachable!("The precomputation has no limits: {error}"))
+    }
+
+    /// Like [`new`](Self::new), but reports progress and stops early as configured in `precomputation`.
+    ///
+    /// If stopped early with [`LowerBoundEarlyStop::Degrade`](super::LowerBoundEarlyStop::Degrade),
+    /// then the missing entries are zero.
+    /// If the `tslb_matrix` is incomplete, then all entries are zero.
+    pub fn new_with_precomputation<AlphabetType: Alphabet>(
+        config: &TemplateSwitchConfig<AlphabetType>,
+        tslb_matrix: &TemplateSwitchLowerBoundMatrix,
+        reference_length: usize,
+        query_length: usize,
+        max_consecutive_primary_matches: usize,
+        max_consecutive_primary_matches_at_start_and_end: usize,
+        precomputation: &mut LowerBoundPrecomputation,
+    ) -> Result<Self> {
+        let shape = (reference_length + 1, query_length + 1);
+        if !tslb_matrix.is_complete() {
+            warn!("The TS lower bound matrix is incomplete, hence the TS alignment lower bounds are zero");
+            return Ok(Self {
+                matrix: Array2::from_elem(shape, Cost::ZERO),
+            });
+        }
+
         info!("Computing TS alignment lower bound matrix...");
         let lower_bound_config = generate_template_switch_alignment_lower_bound_config(config);
 
@@ -86,6 +122,7 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
             genome.as_genome_subsequence(),
             lower_bound_config.clone(),
             Memory {
+                node_ord: (),
                 template_switch_min_length: (),
                 chaining: (),
                 template_switch_count: (),
@@ -96,12 +133,15 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
                         max_consecutive_primary_matches_at_start_and_end,
                     fake_substitution_cost: lower_bound_config
                         .primary_edit_costs
-                        .min_substitution_cost(),
+                        .min_substitution_or_ambiguous_match_cost(),
                 },
             },
             None,
         ));
-        a_star.initialise();
+        a_star.set_cancellation_token(precomputation.cancellation_token.clone());
+        a_star.initialise()?;
+        let start_time = Instant::now();
+        let mut stop_reason = None;
 
         for (target_reference_index, target_query_index) in
             (0..=reference_length).flat_map(|reference_index| {
@@ -112,7 +152,13 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
                 continue;
             }
 
+            if let Some(reason) = precomputation.stop_reason(start_time) {
+                stop_reason = Some(reason);
+                break;
+            }
+
             trace!("Searching for target ({target_reference_index}, {target_query_index})");
+            a_star.set_time_limit(precomputation.remaining_time(start_time));
 
             match a_star.search_until(|_, node| match *node.identifier() {
                 Identifier::Primary {
@@ -150,10 +196,11 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
                 }
                 Identifier::TemplateSwitchEntrance { .. }
                 | Identifier::Secondary { .. }
-                | Identifier::TemplateSwitchExit { .. } => {
+                | Identifier::TemplateSwitchExit { .. }
+                | Identifier::Inversion { .. } => {
                     unreachable!()
                 }
-            }) {
+            })? {
                 AStarResult::FoundTarget { identifier, cost } => {
                     trace!("Search terminated with target {identifier} at cost {cost}");
 
@@ -170,7 +217,7 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
 
                             // Backtrack.
                             for alignment_type in
-                                a_star.backtrack()
+                                a_star.backtrack()?
                             {
                                 if let Some((count, previous_alignment_type)) = alignment.last_mut()
                                 {
@@ -189,27 +236,41 @@ impl TemplateSwitchAlignmentLowerBoundMatrix {
                         });
                     }
                 }
-                AStarResult::NoTarget { .. } => {
+                AStarResult::ExceededTimeLimit { .. } => {
+                    stop_reason = Some(NoTargetReason::ExceededTimeLimit);
+                    break;
+                }
+                AStarResult::Cancelled { .. } => {
+                    stop_reason = Some(NoTargetReason::Cancelled);
+                    break;
+                }
+                AStarResult::NoTarget { .. } | AStarResult::ExceededMemoryLimit { .. } => {
                     unreachable!("Search terminated without target for target reference index {target_reference_index} and target query index {target_query_index}");
                 }
             }
+            precomputation.report_progress(
+                "TSA",
+                closed_lower_bounds.len(),
+                Some(shape.0 * shape.1),
+                start_time,
+            );
         }
 
-        let (max_x, max_y) = closed_lower_bounds
-            .iter()
-            .fold((usize::MIN, usize::MIN), |(max_x, max_y), (&(x, y), _)| {
-                (max_x.max(x), max_y.max(y))
-            });
+        if let Some(reason) = stop_reason {
+            precomputation.early_stop("TSA", reason)?;
+        }
 
-        let mut matrix = Array2::from_elem((max_x + 1, max_y + 1), Cost::MAX);
+        let mut matrix = Array2::from_elem(shape, Cost::MAX);
         for ((x, y), cost) in matrix.indexed_iter_mut() {
-            *cost = *closed_lower_bounds
-                .get(&(x, y))
-                .unwrap_or_else(|| unreachable!("Missing matrix entry for ({x}, {y})"));
+            *cost = match closed_lower_bounds.get(&(x, y)) {
+                Some(cost) => *cost,
+                None if stop_reason.is_some() => Cost::ZERO,
+                None => unreachable!("Missing matrix entry for ({x}, {y})"),
+            };
             assert_ne!(*cost, Cost::MAX);
         }
 
-        Self { matrix }
+        Ok(Self { matrix })
     }
 
     pub fn cost(&self, delta_reference: usize, delta_query: usize) -> Cost {
@@ -225,17 +286,36 @@ fn generate_template_switch_alignment_lower_bound_config<AlphabetType: Alphabet>
         left_flank_length: config.left_flank_length,
         right_flank_length: config.right_flank_length,
         min_length: usize::MAX,
+        ts_end_exclusion: 0,
+        directions: TemplateSwitchDirections::NONE,
+        anchor_repeat_mask: None,
+        anchor_scheme: Default::default(),
 
         base_cost: Cost::MAX,
+        forward_base_cost: None,
 
-        primary_edit_costs: config.primary_edit_costs.clone(),
+        // The search runs on an artificial genome, so the contexts of its substitutions are meaningless.
+        primary_edit_costs: config
+            .primary_edit_costs
+            .clone()
+            .into_context_free_lower_bound(),
         secondary_edit_costs: GapAffineAlignmentCostTable::new_max(),
-        left_flank_edit_costs: config.left_flank_edit_costs.clone(),
-        right_flank_edit_costs: config.right_flank_edit_costs.clone(),
+        left_flank_edit_costs: config
+            .left_flank_edit_costs
+            .clone()
+            .into_context_free_lower_bound(),
+        right_flank_edit_costs: config
+            .right_flank_edit_costs
+            .clone()
+            .into_context_free_lower_bound(),
+        forward_secondary_edit_costs: None,
 
         offset_costs: CostFunction::new_max(),
         length_costs: CostFunction::new_max(),
         length_difference_costs: CostFunction::new_max(),
+
+        duplication_costs: None,
+        inversion_costs: None,
     }
 }
 
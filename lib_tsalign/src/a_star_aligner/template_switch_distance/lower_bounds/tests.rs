@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use generic_a_star::{cancellation::CancellationToken, cost::Cost};
+
+use crate::{
+    a_star_aligner::alignment_result::NoTargetReason, error::Error, test_fixtures::sample_config,
+};
+
+use super::{
+    template_switch::TemplateSwitchLowerBoundMatrix,
+    template_switch_alignment::TemplateSwitchAlignmentLowerBoundMatrix, LowerBoundEarlyStop,
+    LowerBoundPrecomputation,
+};
+
+fn cancelled(early_stop: LowerBoundEarlyStop) -> LowerBoundPrecomputation<'static> {
+    let cancellation_token = CancellationToken::new();
+    cancellation_token.cancel();
+    LowerBoundPrecomputation {
+        cancellation_token: Some(cancellation_token),
+        early_stop,
+        ..Default::default()
+    }
+}
+
+fn tsa_lower_bounds(
+    tslb_matrix: &TemplateSwitchLowerBoundMatrix,
+    precomputation: &mut LowerBoundPrecomputation,
+) -> crate::error::Result<TemplateSwitchAlignmentLowerBoundMatrix> {
+    TemplateSwitchAlignmentLowerBoundMatrix::new_with_precomputation(
+        &sample_config(),
+        tslb_matrix,
+        4,
+        3,
+        3,
+        1,
+        precomputation,
+    )
+}
+
+#[test]
+fn progress_is_reported() {
+    let mut reports = Vec::new();
+    let mut precomputation = LowerBoundPrecomputation {
+        progress: Some(Box::new(|progress| reports.push(progress))),
+        ..Default::default()
+    };
+    let tslb_matrix = TemplateSwitchLowerBoundMatrix::new_with_precomputation(
+        &sample_config(),
+        &mut precomputation,
+    )
+    .unwrap();
+    let tsalb_matrix = tsa_lower_bounds(&tslb_matrix, &mut precomputation).unwrap();
+    drop(precomputation);
+
+    assert!(tslb_matrix.is_complete());
+    assert_eq!(
+        tslb_matrix.iter().collect::<Vec<_>>(),
+        TemplateSwitchLowerBoundMatrix::new(&sample_config())
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>()
+    );
+    assert!(reports.iter().any(|progress| progress.matrix == "TS"));
+    let tsa_reports: Vec<_> = reports
+        .iter()
+        .filter(|progress| progress.matrix == "TSA")
+        .collect();
+    assert!(!tsa_reports.is_empty());
+    assert!(tsa_reports
+        .windows(2)
+        .all(|window| window[0].computed_entries <= window[1].computed_entries));
+    assert_eq!(tsa_reports.last().unwrap().computed_entries, 5 * 4);
+    assert_eq!(tsa_reports.last().unwrap().total_entries, Some(5 * 4));
+    assert!(tsalb_matrix.cost(4, 3) > Cost::ZERO);
+}
+
+#[test]
+fn cancelled_precomputation_returns_error() {
+    let result = TemplateSwitchLowerBoundMatrix::new_with_precomputation(
+        &sample_config(),
+        &mut cancelled(LowerBoundEarlyStop::Error),
+    );
+    assert!(matches!(
+        result,
+        Err(Error::LowerBoundPrecomputationStopped {
+            matrix: "TS",
+            reason: NoTargetReason::Cancelled
+        })
+    ));
+
+    let tslb_matrix = TemplateSwitchLowerBoundMatrix::new(&sample_config()).unwrap();
+    let result = tsa_lower_bounds(
+        &tslb_matrix,
+        &mut LowerBoundPrecomputation {
+            time_limit: Some(Duration::ZERO),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(Error::LowerBoundPrecomputationStopped {
+            matrix: "TSA",
+            reason: NoTargetReason::ExceededTimeLimit
+        })
+    ));
+}
+
+#[test]
+fn degraded_precomputation_is_padded_with_zeros() {
+    let tslb_matrix = TemplateSwitchLowerBoundMatrix::new_with_precomputation(
+        &sample_config(),
+        &mut cancelled(LowerBoundEarlyStop::Degrade),
+    )
+    .unwrap();
+    assert!(!tslb_matrix.is_complete());
+
+    // An incomplete TS lower bound matrix makes all TS alignment lower bounds zero.
+    let tsalb_matrix =
+        tsa_lower_bounds(&tslb_matrix, &mut LowerBoundPrecomputation::default()).unwrap();
+    assert_eq!(tsalb_matrix.cost(4, 3), Cost::ZERO);
+
+    let tslb_matrix = TemplateSwitchLowerBoundMatrix::new(&sample_config()).unwrap();
+    let tsalb_matrix = tsa_lower_bounds(
+        &tslb_matrix,
+        &mut LowerBoundPrecomputation {
+            time_limit: Some(Duration::ZERO),
+            early_stop: LowerBoundEarlyStop::Degrade,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(tsalb_matrix.cost(0, 0), Cost::ZERO);
+    assert_eq!(tsalb_matrix.cost(4, 3), Cost::ZERO);
+}
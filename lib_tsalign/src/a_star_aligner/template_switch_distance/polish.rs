@@ -0,0 +1,613 @@
+use compact_genome::interface::{
+    alphabet::{Alphabet, AlphabetCharacter},
+    sequence::GenomeSequence,
+};
+use log::debug;
+use noisy_float::types::r64;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::{AlignmentResult, IAlignmentType},
+        compress_alignment,
+    },
+    config::TemplateSwitchConfig,
+    costs::cost::Cost,
+    soft_mask::SoftMasks,
+};
+
+use super::{
+    identifier::{TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand},
+    AlignmentType,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Shifts the entrances and exits of template switches to canonical positions.
+///
+/// Like indels in repetitive regions, a template switch can often be described with different entrance and exit points at the same cost.
+/// Each entrance and exit is moved by at most `window` characters to the position with the lowest cost,
+/// and among positions with equal cost, to the leftmost one.
+///
+/// Only template switches without gaps in their secondary, flanks and the `window` characters around them are polished,
+/// since moving them past gaps would require realigning.
+/// The cost of the alignment never increases, and is updated if polishing finds a cheaper alignment.
+pub fn polish_template_switches<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    mut result: AlignmentResult<AlignmentType>,
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    config: &TemplateSwitchConfig<AlphabetType>,
+    soft_masks: Option<&SoftMasks>,
+    window: usize,
+) -> AlignmentResult<AlignmentType> {
+    let AlignmentResult::WithTarget { alignment, .. } = &mut result else {
+        return result;
+    };
+
+    let mut polisher = Polisher {
+        steps: alignment
+            .iter()
+            .flat_map(|(count, alignment_type)| {
+                // Entrances and exits are counted once per offset and length difference step of the search.
+                let count = if alignment_type.is_repeatable() {
+                    *count
+                } else {
+                    1
+                };
+                std::iter::repeat(*alignment_type).take(count)
+            })
+            .collect(),
+        reference,
+        query,
+        config,
+        soft_masks,
+        window,
+    };
+
+    let original_steps = polisher.steps.clone();
+    let mut cost_decrease = 0;
+    let mut template_switch_index = 0;
+    while let Some(decrease) = polisher.polish(template_switch_index) {
+        cost_decrease += decrease;
+        template_switch_index += 1;
+    }
+    // The costs of the segments around moved template switches are unknown without realigning.
+    let is_modified = polisher.steps != original_steps;
+
+    *alignment = compress_alignment(polisher.steps.into_iter().rev());
+    for (count, alignment_type) in alignment.iter_mut() {
+        match alignment_type {
+            AlignmentType::TemplateSwitchEntrance { first_offset, .. } => {
+                *count = first_offset.unsigned_abs() + 1
+            }
+            AlignmentType::TemplateSwitchExit { length_difference } => {
+                *count = length_difference.unsigned_abs() + 1
+            }
+            _ => {}
+        }
+    }
+    if cost_decrease > 0 {
+        debug!("Polishing template switches decreased the cost by {cost_decrease}");
+        let statistics = result.statistics_mut();
+        statistics.cost -= r64(cost_decrease as f64);
+        statistics.cost_per_base =
+            statistics.cost * 2.0 / (reference.len() + query.len()).max(1) as f64;
+    }
+    if is_modified {
+        result.statistics_mut().cost_segments.clear();
+        result.statistics_mut().column_costs.clear();
+    }
+    result.update_identity_statistics();
+
+    result
+}
+
+struct Polisher<'a, AlphabetType, SubsequenceType: ?Sized> {
+    /// The alignment with one entry per alignment step.
+    steps: Vec<AlignmentType>,
+    reference: &'a SubsequenceType,
+    query: &'a SubsequenceType,
+    config: &'a TemplateSwitchConfig<AlphabetType>,
+    soft_masks: Option<&'a SoftMasks>,
+    window: usize,
+}
+
+/// A template switch within the alignment steps.
+struct TemplateSwitch {
+    entrance_step: usize,
+    exit_step: usize,
+    entrance_reference_index: usize,
+    entrance_query_index: usize,
+    primary: TemplateSwitchPrimary,
+    secondary: TemplateSwitchSecondary,
+    strand: TemplateSwitchStrand,
+    first_offset: isize,
+    length_difference: isize,
+}
+
+/// The positions of a polishable template switch, relative to its primary sequence.
+///
+/// The sequence that is not the primary is called the anti-primary.
+struct Boundaries {
+    primary: TemplateSwitchPrimary,
+    secondary: TemplateSwitchSecondary,
+    strand: TemplateSwitchStrand,
+    /// The primary index at which the alignment of the region starts.
+    start: usize,
+    /// The primary index at which the alignment of the region ends.
+    end: usize,
+    /// The primary index of the entrance.
+    entrance: usize,
+    /// The primary index of the exit.
+    exit: usize,
+    /// The anti-primary index minus the primary index before the template switch.
+    entrance_diagonal: isize,
+    /// The anti-primary index minus the primary index after the template switch.
+    exit_diagonal: isize,
+    /// The secondary index at primary index zero, if the template switch was extended that far.
+    ///
+    /// See [`Boundaries::secondary_index`].
+    secondary_anchor: isize,
+    length_difference: isize,
+}
+
+impl Boundaries {
+    /// The secondary index of the template switch before aligning the given primary index.
+    ///
+    /// The reverse complement of the secondary is traversed backwards, and the forward strand forwards.
+    fn secondary_index(&self, primary_index: usize) -> isize {
+        match self.strand {
+            TemplateSwitchStrand::ReverseComplement => {
+                self.secondary_anchor - primary_index as isize
+            }
+            TemplateSwitchStrand::Forward => self.secondary_anchor + primary_index as isize,
+        }
+    }
+}
+
+impl<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    > Polisher<'_, AlphabetType, SubsequenceType>
+{
+    /// Polishes the template switch with the given index.
+    ///
+    /// Returns the decrease in cost, or `None` if there is no template switch with this index.
+    fn polish(&mut self, template_switch_index: usize) -> Option<u64> {
+        let template_switches = self.template_switches();
+        let template_switch = template_switches.get(template_switch_index)?;
+        let lower_limit = template_switch_index
+            .checked_sub(1)
+            .map(|previous| {
+                template_switches[previous].exit_step
+                    + 1
+                    + self.config.right_flank_length.unsigned_abs()
+            })
+            .unwrap_or(0);
+        let upper_limit = template_switches
+            .get(template_switch_index + 1)
+            .map(|next| {
+                next.entrance_step
+                    .saturating_sub(self.config.left_flank_length.unsigned_abs())
+            })
+            .unwrap_or(self.steps.len());
+
+        let Some((boundaries, start_step, end_step)) =
+            self.boundaries(template_switch, lower_limit, upper_limit)
+        else {
+            return Some(0);
+        };
+
+        let Some((_, original_cost)) =
+            self.region(&boundaries, boundaries.entrance, boundaries.exit)
+        else {
+            return Some(0);
+        };
+
+        let mut best: Option<(Vec<AlignmentType>, Cost, usize, usize)> = None;
+        let left_flank_length = self.config.left_flank_length.unsigned_abs();
+        let right_flank_length = self.config.right_flank_length.unsigned_abs();
+        for entrance in boundaries.start + left_flank_length..=boundaries.entrance + self.window {
+            for exit in entrance.max(boundaries.exit.saturating_sub(self.window))
+                ..=boundaries.end.saturating_sub(right_flank_length)
+            {
+                let Some((steps, cost)) = self.region(&boundaries, entrance, exit) else {
+                    continue;
+                };
+                // Candidates are ordered from left to right, so equal costs keep the leftmost.
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, best_cost, ..)| cost < *best_cost)
+                {
+                    best = Some((steps, cost, entrance, exit));
+                }
+            }
+        }
+
+        let Some((steps, cost, entrance, exit)) = best else {
+            return Some(0);
+        };
+        if (entrance, exit) != (boundaries.entrance, boundaries.exit) {
+            self.steps.splice(start_step..end_step, steps);
+        }
+        Some(original_cost.as_u64() - cost.as_u64())
+    }
+
+    fn template_switches(&self) -> Vec<TemplateSwitch> {
+        let mut template_switches = Vec::new();
+        let mut reference_index = 0;
+        let mut query_index = 0;
+
+        for (step, alignment_type) in self.steps.iter().enumerate() {
+            match alignment_type {
+                AlignmentType::PrimaryMatch
+                | AlignmentType::PrimarySubstitution
+                | AlignmentType::PrimaryFlankMatch
+                | AlignmentType::PrimaryFlankSubstitution => {
+                    reference_index += 1;
+                    query_index += 1;
+                }
+                AlignmentType::PrimaryInsertion | AlignmentType::PrimaryFlankInsertion => {
+                    query_index += 1;
+                }
+                AlignmentType::PrimaryDeletion | AlignmentType::PrimaryFlankDeletion => {
+                    reference_index += 1;
+                }
+                &AlignmentType::TemplateSwitchEntrance {
+                    primary,
+                    secondary,
+                    strand,
+                    first_offset,
+                } => template_switches.push(TemplateSwitch {
+                    entrance_step: step,
+                    exit_step: usize::MAX,
+                    entrance_reference_index: reference_index,
+                    entrance_query_index: query_index,
+                    primary,
+                    secondary,
+                    strand,
+                    first_offset,
+                    length_difference: 0,
+                }),
+                &AlignmentType::TemplateSwitchExit { length_difference } => {
+                    let template_switch = template_switches.last_mut().unwrap();
+                    template_switch.exit_step = step;
+                    template_switch.length_difference = length_difference;
+
+                    let primary_length = self.steps[template_switch.entrance_step + 1..step]
+                        .iter()
+                        .filter(|alignment_type| {
+                            matches!(
+                                alignment_type,
+                                AlignmentType::SecondaryMatch
+                                    | AlignmentType::SecondarySubstitution
+                                    | AlignmentType::SecondaryInsertion
+                            )
+                        })
+                        .count();
+                    let anti_primary_length =
+                        (primary_length as isize + length_difference) as usize;
+                    match template_switch.primary {
+                        TemplateSwitchPrimary::Reference => {
+                            reference_index =
+                                template_switch.entrance_reference_index + primary_length;
+                            query_index =
+                                template_switch.entrance_query_index + anti_primary_length;
+                        }
+                        TemplateSwitchPrimary::Query => {
+                            reference_index =
+                                template_switch.entrance_reference_index + anti_primary_length;
+                            query_index = template_switch.entrance_query_index + primary_length;
+                        }
+                    }
+                }
+                AlignmentType::PrimaryDuplication { length } => {
+                    reference_index -= length;
+                }
+                AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                    reference_index += 1;
+                    query_index += 1;
+                }
+                _ => {}
+            }
+        }
+
+        template_switches
+    }
+
+    /// Computes the boundaries of the region of a template switch that can be polished,
+    /// together with the range of steps it spans.
+    ///
+    /// Returns `None` if the template switch or its flanks contain gaps.
+    fn boundaries(
+        &self,
+        template_switch: &TemplateSwitch,
+        lower_limit: usize,
+        upper_limit: usize,
+    ) -> Option<(Boundaries, usize, usize)> {
+        let is_primary_diagonal = |alignment_type: &&AlignmentType| {
+            matches!(
+                alignment_type,
+                AlignmentType::PrimaryMatch
+                    | AlignmentType::PrimarySubstitution
+                    | AlignmentType::PrimaryFlankMatch
+                    | AlignmentType::PrimaryFlankSubstitution
+            )
+        };
+        let left_flank_length = self.config.left_flank_length.unsigned_abs();
+        let right_flank_length = self.config.right_flank_length.unsigned_abs();
+        let TemplateSwitch {
+            entrance_step,
+            exit_step,
+            ..
+        } = *template_switch;
+
+        if !self.steps[entrance_step + 1..exit_step]
+            .iter()
+            .all(|alignment_type| {
+                matches!(
+                    alignment_type,
+                    AlignmentType::SecondaryMatch | AlignmentType::SecondarySubstitution
+                )
+            })
+        {
+            return None;
+        }
+
+        let before = self.steps[lower_limit.min(entrance_step)..entrance_step]
+            .iter()
+            .rev()
+            .take_while(is_primary_diagonal)
+            .count();
+        let after = self.steps[exit_step + 1..upper_limit.max(exit_step + 1)]
+            .iter()
+            .take_while(is_primary_diagonal)
+            .count();
+        if before < left_flank_length || after < right_flank_length {
+            return None;
+        }
+        let before = before.min(left_flank_length + self.window);
+        let after = after.min(right_flank_length + self.window);
+
+        let (entrance, anti_primary_entrance) = match template_switch.primary {
+            TemplateSwitchPrimary::Reference => (
+                template_switch.entrance_reference_index,
+                template_switch.entrance_query_index,
+            ),
+            TemplateSwitchPrimary::Query => (
+                template_switch.entrance_query_index,
+                template_switch.entrance_reference_index,
+            ),
+        };
+        let secondary_entrance = match template_switch.secondary {
+            TemplateSwitchSecondary::Reference => template_switch.entrance_reference_index,
+            TemplateSwitchSecondary::Query => template_switch.entrance_query_index,
+        };
+        let length = exit_step - entrance_step - 1;
+        let exit = entrance + length;
+        let entrance_diagonal = anti_primary_entrance as isize - entrance as isize;
+        let exit_diagonal = entrance_diagonal + template_switch.length_difference;
+        let secondary_start = secondary_entrance as isize + template_switch.first_offset;
+        let secondary_anchor = match template_switch.strand {
+            TemplateSwitchStrand::ReverseComplement => secondary_start + entrance as isize,
+            TemplateSwitchStrand::Forward => secondary_start - entrance as isize,
+        };
+
+        Some((
+            Boundaries {
+                primary: template_switch.primary,
+                secondary: template_switch.secondary,
+                strand: template_switch.strand,
+                start: entrance - before,
+                end: exit + after,
+                entrance,
+                exit,
+                entrance_diagonal,
+                exit_diagonal,
+                secondary_anchor,
+                length_difference: template_switch.length_difference,
+            },
+            entrance_step - before,
+            exit_step + 1 + after,
+        ))
+    }
+
+    /// Returns the alignment steps and the cost of the region with the template switch moved to the given primary entrance and exit indices.
+    ///
+    /// The secondary indices are moved such that the pairs of primary and secondary characters stay the same,
+    /// and the anti-primary index after the template switch stays the same.
+    /// Returns `None` if the template switch cannot be moved there.
+    fn region(
+        &self,
+        boundaries: &Boundaries,
+        entrance: usize,
+        exit: usize,
+    ) -> Option<(Vec<AlignmentType>, Cost)> {
+        let config = self.config;
+        let left_flank_start = entrance - config.left_flank_length.unsigned_abs();
+        let right_flank_end = exit + config.right_flank_length.unsigned_abs();
+        let secondary_start = boundaries.secondary_index(entrance);
+        let secondary_end = boundaries.secondary_index(exit);
+        if secondary_start.min(secondary_end) < 0
+            || secondary_start.max(secondary_end) > self.secondary(boundaries).len() as isize
+        {
+            return None;
+        }
+
+        let (entrance_reference_index, entrance_query_index) = self.reference_and_query_index(
+            boundaries,
+            entrance,
+            entrance as isize + boundaries.entrance_diagonal,
+        )?;
+        let (exit_reference_index, exit_query_index) = self.reference_and_query_index(
+            boundaries,
+            exit,
+            exit as isize + boundaries.exit_diagonal,
+        )?;
+        if !config.allows_template_switch_at(
+            entrance_reference_index,
+            self.reference.len(),
+            entrance_query_index,
+            self.query.len(),
+        ) || !config.allows_template_switch_at(
+            exit_reference_index,
+            self.reference.len(),
+            exit_query_index,
+            self.query.len(),
+        ) {
+            return None;
+        }
+        let secondary_entrance = match boundaries.secondary {
+            TemplateSwitchSecondary::Reference => entrance_reference_index,
+            TemplateSwitchSecondary::Query => entrance_query_index,
+        };
+        let first_offset = secondary_start - secondary_entrance as isize;
+        let base_cost = config.base_cost_of(boundaries.strand);
+        let base_cost = if let Some(soft_masks) = self.soft_masks {
+            soft_masks.template_switch_base_cost(
+                base_cost,
+                entrance_reference_index,
+                entrance_query_index,
+            )
+        } else {
+            base_cost
+        };
+        let secondary_edit_costs = config.secondary_edit_costs_of(boundaries.strand);
+
+        let mut steps = Vec::with_capacity(boundaries.end - boundaries.start + 2);
+        let mut costs = vec![
+            base_cost,
+            config.offset_costs.evaluate(&first_offset),
+            config.length_costs.evaluate(&(exit - entrance)),
+            config
+                .length_difference_costs
+                .evaluate(&boundaries.length_difference),
+        ];
+        for primary_index in boundaries.start..boundaries.end {
+            if primary_index == entrance {
+                steps.push(AlignmentType::TemplateSwitchEntrance {
+                    primary: boundaries.primary,
+                    secondary: boundaries.secondary,
+                    strand: boundaries.strand,
+                    first_offset,
+                });
+            }
+            if primary_index == exit {
+                steps.push(AlignmentType::TemplateSwitchExit {
+                    length_difference: boundaries.length_difference,
+                });
+            }
+
+            if (entrance..exit).contains(&primary_index) {
+                let secondary_index = boundaries.secondary_index(primary_index) as usize;
+                let p = self.primary(boundaries)[primary_index].clone();
+                let s = match boundaries.strand {
+                    TemplateSwitchStrand::ReverseComplement => {
+                        self.secondary(boundaries)[secondary_index - 1].complement()
+                    }
+                    TemplateSwitchStrand::Forward => {
+                        self.secondary(boundaries)[secondary_index].clone()
+                    }
+                };
+                steps.push(if p == s {
+                    AlignmentType::SecondaryMatch
+                } else {
+                    AlignmentType::SecondarySubstitution
+                });
+                costs.push(secondary_edit_costs.match_or_substitution_cost(p, s));
+                continue;
+            }
+
+            let (diagonal, is_flank, edit_costs) = if primary_index < left_flank_start {
+                (
+                    boundaries.entrance_diagonal,
+                    false,
+                    &config.primary_edit_costs,
+                )
+            } else if primary_index < entrance {
+                (
+                    boundaries.entrance_diagonal,
+                    true,
+                    &config.left_flank_edit_costs,
+                )
+            } else if primary_index < right_flank_end {
+                (
+                    boundaries.exit_diagonal,
+                    true,
+                    &config.right_flank_edit_costs,
+                )
+            } else {
+                (boundaries.exit_diagonal, false, &config.primary_edit_costs)
+            };
+            let (reference_index, query_index) = self.reference_and_query_index(
+                boundaries,
+                primary_index,
+                primary_index as isize + diagonal,
+            )?;
+            if reference_index >= self.reference.len() || query_index >= self.query.len() {
+                return None;
+            }
+
+            let r = self.reference[reference_index].clone();
+            let q = self.query[query_index].clone();
+            steps.push(match (is_flank, r == q) {
+                (false, true) => AlignmentType::PrimaryMatch,
+                (false, false) => AlignmentType::PrimarySubstitution,
+                (true, true) => AlignmentType::PrimaryFlankMatch,
+                (true, false) => AlignmentType::PrimaryFlankSubstitution,
+            });
+            let previous = reference_index
+                .checked_sub(1)
+                .map(|previous_index| self.reference[previous_index].clone());
+            costs.push(edit_costs.context_match_or_substitution_cost(previous, r, q));
+        }
+        if entrance == boundaries.end {
+            steps.push(AlignmentType::TemplateSwitchEntrance {
+                primary: boundaries.primary,
+                secondary: boundaries.secondary,
+                strand: boundaries.strand,
+                first_offset,
+            });
+        }
+        if exit == boundaries.end {
+            steps.push(AlignmentType::TemplateSwitchExit {
+                length_difference: boundaries.length_difference,
+            });
+        }
+
+        let cost = costs.into_iter().try_fold(0u64, |sum, cost| {
+            (cost != Cost::MAX).then(|| sum.saturating_add(cost.as_u64()))
+        })?;
+        Some((steps, cost.into()))
+    }
+
+    fn reference_and_query_index(
+        &self,
+        boundaries: &Boundaries,
+        primary_index: usize,
+        anti_primary_index: isize,
+    ) -> Option<(usize, usize)> {
+        let anti_primary_index = usize::try_from(anti_primary_index).ok()?;
+        Some(match boundaries.primary {
+            TemplateSwitchPrimary::Reference => (primary_index, anti_primary_index),
+            TemplateSwitchPrimary::Query => (anti_primary_index, primary_index),
+        })
+    }
+
+    fn primary(&self, boundaries: &Boundaries) -> &SubsequenceType {
+        match boundaries.primary {
+            TemplateSwitchPrimary::Reference => self.reference,
+            TemplateSwitchPrimary::Query => self.query,
+        }
+    }
+
+    fn secondary(&self, boundaries: &Boundaries) -> &SubsequenceType {
+        match boundaries.secondary {
+            TemplateSwitchSecondary::Reference => self.reference,
+            TemplateSwitchSecondary::Query => self.query,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::a_star_aligner::{
+    alignment_result::AlignmentResult,
+    template_switch_distance::{
+        identifier::{TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand},
+        AlignmentType,
+    },
+};
+
+use super::polish_template_switches;
+use crate::test_fixtures::{align, sample_config, QUERY, REFERENCE};
+
+fn polish(
+    alignment: AlignmentResult<AlignmentType>,
+    window: usize,
+) -> AlignmentResult<AlignmentType> {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    polish_template_switches(
+        alignment,
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        &sample_config(),
+        None,
+        window,
+    )
+}
+
+fn inversion(
+    prefix_type: AlignmentType,
+    first_offset: isize,
+    length: usize,
+    suffix: usize,
+) -> Vec<(usize, AlignmentType)> {
+    vec![
+        (40 - length - suffix, prefix_type),
+        (
+            first_offset.unsigned_abs() + 1,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset,
+            },
+        ),
+        (length, AlignmentType::SecondaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 0,
+            },
+        ),
+        (suffix, AlignmentType::PrimaryMatch),
+    ]
+}
+
+fn alignment_result(alignment: Vec<(usize, AlignmentType)>) -> AlignmentResult<AlignmentType> {
+    AlignmentResult::new_with_target(alignment, 7.into(), 0.0, 0, 0, 0, 40, 40)
+}
+
+#[test]
+fn entrance_is_left_aligned() {
+    let polished = polish(
+        alignment_result(inversion(AlignmentType::PrimaryMatch, 20, 20, 10)),
+        3,
+    );
+
+    let AlignmentResult::WithTarget { alignment, .. } = &polished else {
+        panic!("No alignment found");
+    };
+    assert_eq!(
+        alignment,
+        &inversion(AlignmentType::PrimaryFlankMatch, 22, 21, 10)
+    );
+    assert_eq!(polished.statistics().cost, 7.0);
+
+    // The canonical position does not depend on where the template switch was reported.
+    assert_eq!(
+        polish(
+            alignment_result(inversion(AlignmentType::PrimaryMatch, 20, 21, 9)),
+            3
+        ),
+        polished
+    );
+    assert_eq!(polish(polished.clone(), 3), polished);
+}
+
+#[test]
+fn zero_window_does_not_move() {
+    let alignment = alignment_result(inversion(AlignmentType::PrimaryMatch, 20, 20, 10));
+    assert_eq!(polish(alignment.clone(), 0), alignment);
+}
+
+#[test]
+fn alignment_without_template_switch_is_unchanged() {
+    let alignment = alignment_result(vec![
+        (10, AlignmentType::PrimaryMatch),
+        (1, AlignmentType::PrimarySubstitution),
+        (29, AlignmentType::PrimaryMatch),
+    ]);
+    assert_eq!(polish(alignment.clone(), 3), alignment);
+}
+
+#[test]
+fn polishing_does_not_increase_cost() {
+    let alignment = align(REFERENCE.as_bytes(), QUERY.as_bytes(), sample_config());
+    assert!(alignment.cigar().contains('['), "{}", alignment.cigar());
+
+    let polished = polish(alignment.clone(), 3);
+    assert!(polished.statistics().cost <= alignment.statistics().cost);
+    assert_eq!(polish(polished.clone(), 3), polished);
+}
+
+#[test]
+fn forward_entrance_is_moved_onto_matches() {
+    // The query copies the forward strand of the twelve reference characters before the replaced region.
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"ACGTTGCAACTGCAGGATCCGTATGTTACCAAGTGCAACCATGCATTA",
+    )
+    .unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"ACGTTGCAACTGCAGGATCCGTATCAGGATCCGTATAACCATGCATTA",
+    )
+    .unwrap();
+    let template_switch = |prefix: usize, first_offset: isize| {
+        let mut alignment = vec![(24, AlignmentType::PrimaryMatch)];
+        if prefix > 24 {
+            alignment.push((prefix - 24, AlignmentType::PrimarySubstitution));
+        }
+        alignment.extend([
+            (
+                first_offset.unsigned_abs() + 1,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Query,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::Forward,
+                    first_offset,
+                },
+            ),
+            (36 - prefix, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: 0,
+                },
+            ),
+            (12, AlignmentType::PrimaryMatch),
+        ]);
+        alignment
+    };
+    let mut config = sample_config();
+    config.forward_base_cost = Some(2u64.into());
+
+    let polished = polish_template_switches(
+        AlignmentResult::new_with_target(template_switch(25, -12), 10.into(), 0.0, 0, 0, 0, 48, 48),
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        &config,
+        None,
+        3,
+    );
+
+    let AlignmentResult::WithTarget { alignment, .. } = &polished else {
+        panic!("No alignment found");
+    };
+    assert!(
+        alignment.iter().all(|(_, alignment_type)| !matches!(
+            alignment_type,
+            AlignmentType::PrimarySubstitution | AlignmentType::PrimaryFlankSubstitution
+        )),
+        "{}",
+        polished.cigar()
+    );
+    assert!(
+        alignment.contains(&(
+            13,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::Forward,
+                first_offset: -12,
+            }
+        )),
+        "{}",
+        polished.cigar()
+    );
+    assert_eq!(polished.statistics().cost, 7.0);
+}
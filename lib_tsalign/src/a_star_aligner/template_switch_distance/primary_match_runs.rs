@@ -0,0 +1,79 @@
+use compact_genome::interface::{
+    alphabet::{Alphabet, AlphabetCharacter},
+    sequence::GenomeSequence,
+};
+use generic_a_star::cost::Cost;
+
+use crate::{config::TemplateSwitchConfig, longest_common_extension::LongestCommonExtension};
+
+/// The number of separators between the sequences in the text of the longest common extension structure.
+const SEPARATOR_COUNT: usize = 2;
+
+/// Finds runs of exact matches between reference and query,
+/// such that the primary alignment can extend along the diagonal until the first mismatch in a single step.
+///
+/// Extending greedily is only exact if the primary edit costs [allow it](crate::costs::gap_affine::GapAffineAlignmentCostTable::allows_greedy_match_extension),
+/// and if no template switch can start within the run.
+/// The latter holds if template switches are disabled by the config, or if the node cannot start another template switch,
+/// since then no flank or template switch entrance is reachable from the run.
+pub struct PrimaryMatchRuns {
+    /// Built over `reference $ query $` with distinct separators.
+    longest_common_extension: LongestCommonExtension,
+    reference_length: usize,
+    template_switches_possible: bool,
+}
+
+impl PrimaryMatchRuns {
+    /// Returns `None` if extending along match runs would not be exact with the given config.
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        config: &TemplateSwitchConfig<AlphabetType>,
+    ) -> Option<Self> {
+        // Jump events may start within a run.
+        if !config.primary_edit_costs.allows_greedy_match_extension() || config.has_jump_events() {
+            return None;
+        }
+
+        let template_switches_possible =
+            config.min_base_cost() != Cost::MAX && config.offset_costs.evaluate(&0) != Cost::MAX;
+
+        let character = |character: AlphabetType::CharacterType| {
+            usize::from(character.index()) + SEPARATOR_COUNT
+        };
+        let text: Vec<_> = reference
+            .iter()
+            .cloned()
+            .map(character)
+            .chain([0])
+            .chain(query.iter().cloned().map(character))
+            .chain([1])
+            .collect();
+
+        Some(Self {
+            longest_common_extension: LongestCommonExtension::new(&text),
+            reference_length: reference.len(),
+            template_switches_possible,
+        })
+    }
+
+    /// Returns the number of consecutive matches that can be skipped from the given primary node outside of flanks.
+    ///
+    /// This is zero if the node cannot skip.
+    pub fn run_length(
+        &self,
+        reference_index: usize,
+        query_index: usize,
+        can_start_another_template_switch: bool,
+    ) -> usize {
+        if self.template_switches_possible && can_start_another_template_switch {
+            return 0;
+        }
+
+        self.longest_common_extension
+            .extension(reference_index, self.reference_length + 1 + query_index)
+    }
+}
@@ -0,0 +1,133 @@
+use compact_genome::interface::{
+    alphabet::{Alphabet, AlphabetCharacter},
+    sequence::GenomeSequence,
+};
+use generic_a_star::cost::Cost;
+
+use crate::{config::TemplateSwitchConfig, longest_common_extension::LongestCommonExtension};
+
+use super::identifier::{TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand};
+
+/// The number of separators between the sequences in the text of the longest common extension structure.
+const SEPARATOR_COUNT: usize = 4;
+
+/// Finds runs of exact matches between the primary and the copied strand of the secondary of a template switch,
+/// such that the secondary alignment can skip over them in a single step.
+///
+/// Skipping a run is only exact if the secondary edit costs [allow it](crate::costs::gap_affine::GapAffineAlignmentCostTable::allows_greedy_match_extension),
+/// and if no template switch exit is skipped.
+/// The latter holds if runs end before the first length at which a template switch can exit.
+pub struct SecondaryMatchRuns {
+    /// Built over `reference $ query $ revcomp(reference) $ revcomp(query) $` with distinct separators.
+    ///
+    /// Forward secondaries are compared against the first two sequences.
+    longest_common_extension: LongestCommonExtension,
+    reference_length: usize,
+    query_length: usize,
+    /// Runs end at this template switch length at the latest.
+    max_length: usize,
+}
+
+impl SecondaryMatchRuns {
+    /// Returns `None` if skipping match runs would not be exact with the given config.
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        config: &TemplateSwitchConfig<AlphabetType>,
+    ) -> Option<Self> {
+        if !TemplateSwitchStrand::ALL
+            .into_iter()
+            .filter(|&strand| config.base_cost_of(strand) != Cost::MAX)
+            .all(|strand| {
+                config
+                    .secondary_edit_costs_of(strand)
+                    .allows_greedy_match_extension()
+            })
+        {
+            return None;
+        }
+
+        let max_length = config
+            .length_costs
+            .minimum_finite_input()
+            .unwrap_or(usize::MAX)
+            .min(config.min_length);
+        if max_length < 2 {
+            return None;
+        }
+
+        let character = |character: AlphabetType::CharacterType| {
+            usize::from(character.index()) + SEPARATOR_COUNT
+        };
+        let text: Vec<_> = reference
+            .iter()
+            .cloned()
+            .map(character)
+            .chain([0])
+            .chain(query.iter().cloned().map(character))
+            .chain([1])
+            .chain(reference.iter().rev().map(|c| character(c.complement())))
+            .chain([2])
+            .chain(query.iter().rev().map(|c| character(c.complement())))
+            .chain([3])
+            .collect();
+
+        Some(Self {
+            longest_common_extension: LongestCommonExtension::new(&text),
+            reference_length: reference.len(),
+            query_length: query.len(),
+            max_length,
+        })
+    }
+
+    /// Returns the number of consecutive matches that can be skipped from the given secondary node.
+    ///
+    /// This is zero if the node cannot skip, and at most `max_length - length`.
+    pub fn run_length(
+        &self,
+        template_switch_primary: TemplateSwitchPrimary,
+        template_switch_secondary: TemplateSwitchSecondary,
+        template_switch_strand: TemplateSwitchStrand,
+        length: usize,
+        primary_index: usize,
+        secondary_index: usize,
+    ) -> usize {
+        if length >= self.max_length {
+            return 0;
+        }
+
+        let (primary_offset, primary_length) = match template_switch_primary {
+            TemplateSwitchPrimary::Reference => (0, self.reference_length),
+            TemplateSwitchPrimary::Query => (self.reference_length + 1, self.query_length),
+        };
+        let secondary_position = match (template_switch_secondary, template_switch_strand) {
+            (TemplateSwitchSecondary::Reference, TemplateSwitchStrand::Forward) => secondary_index,
+            (TemplateSwitchSecondary::Query, TemplateSwitchStrand::Forward) => {
+                self.reference_length + 1 + secondary_index
+            }
+            // The secondary is aligned backwards starting before `secondary_index`,
+            // which is aligned forwards in its reverse complement starting at `secondary_length - secondary_index`.
+            (TemplateSwitchSecondary::Reference, TemplateSwitchStrand::ReverseComplement) => {
+                self.reference_length + self.query_length + 2 + self.reference_length
+                    - secondary_index
+            }
+            (TemplateSwitchSecondary::Query, TemplateSwitchStrand::ReverseComplement) => {
+                2 * self.reference_length + self.query_length + 3 + self.query_length
+                    - secondary_index
+            }
+        };
+
+        let primary_position = primary_offset + primary_index;
+        let extension = if primary_position == secondary_position {
+            // A forward secondary that starts at the primary itself matches until the end of the sequence, but not beyond.
+            primary_length - primary_index
+        } else {
+            self.longest_common_extension
+                .extension(primary_position, secondary_position)
+        };
+        extension.min(self.max_length - length)
+    }
+}
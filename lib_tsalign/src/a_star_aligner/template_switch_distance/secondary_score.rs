@@ -0,0 +1,45 @@
+use super::AlignmentType;
+
+/// Which co-optimal alignments are preferred with [`AStarTieBreaking::SecondaryScore`](crate::a_star_aligner::AStarTieBreaking::SecondaryScore).
+///
+/// The score of a node is accumulated along its path from the root, and among open nodes of equal cost,
+/// the node with the higher score is closed first.
+/// This is a preference only: the search still closes each node once,
+/// so a co-optimal alignment with a higher score may be missed if its nodes are opened after the competing ones were closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SecondaryScorePolicy {
+    /// Prefer alignments with more matching columns, inside and outside of template switches and inversions.
+    #[default]
+    MaximiseMatches,
+    /// Prefer alignments with fewer template switches.
+    MinimiseTemplateSwitches,
+}
+
+impl SecondaryScorePolicy {
+    pub fn root_score(&self) -> usize {
+        match self {
+            Self::MaximiseMatches => 0,
+            Self::MinimiseTemplateSwitches => usize::MAX,
+        }
+    }
+
+    pub fn successor_score(&self, score: usize, alignment_type: AlignmentType) -> usize {
+        match self {
+            Self::MaximiseMatches => match alignment_type {
+                AlignmentType::PrimaryMatch
+                | AlignmentType::PrimaryFlankMatch
+                | AlignmentType::SecondaryMatch
+                | AlignmentType::InversionMatch => score + 1,
+                AlignmentType::PrimaryMatchRun { length }
+                | AlignmentType::SecondaryMatchRun { length } => score + length,
+                _ => score,
+            },
+            // Entrances may span several edges that adjust the offset, but each template switch has a single secondary root.
+            Self::MinimiseTemplateSwitches => match alignment_type {
+                AlignmentType::SecondaryRoot => score - 1,
+                _ => score,
+            },
+        }
+    }
+}
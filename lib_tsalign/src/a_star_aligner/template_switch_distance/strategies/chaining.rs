@@ -1,29 +1,59 @@
+use std::fmt::Debug;
+
 use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
 use generic_a_star::cost::Cost;
 use log::debug;
-use num_traits::SaturatingSub;
+use noisy_float::types::R64;
+use num_traits::{SaturatingAdd, SaturatingSub};
 use seed_chain::{
     chain::{Chain, ChainingCostsProvider},
-    seed::{ChainingAnchor, ChainingAnchors},
+    seed::{ChainingAnchor, ChainingAnchors, HomopolymerCompressed, ReverseComplementAnchorClass},
 };
 
 use crate::{
-    a_star_aligner::template_switch_distance::{
-        identifier::GapType,
-        lower_bounds::{
-            template_switch::TemplateSwitchLowerBoundMatrix,
-            template_switch_alignment::TemplateSwitchAlignmentLowerBoundMatrix,
+    a_star_aligner::{
+        alignment_result::{
+            AlignmentStatistics, ChainAnchorStatistics, ChainStatistics, ChainTransitionStatistics,
+        },
+        template_switch_distance::{
+            identifier::GapType,
+            lower_bounds::{
+                template_switch::TemplateSwitchLowerBoundMatrix,
+                template_switch_alignment::TemplateSwitchAlignmentLowerBoundMatrix,
+            },
+            AlignmentType, Context, Identifier, Node, TemplateSwitchPrimary,
+            TemplateSwitchSecondary,
         },
-        AlignmentType, Context, Identifier, Node,
     },
-    config::TemplateSwitchConfig,
+    config::{TemplateSwitchConfig, TemplateSwitchDirections},
+    error::Result,
 };
 
 use super::{primary_match::PrimaryMatchStrategy, AlignmentStrategy, AlignmentStrategySelector};
 
+pub use seed_chain::{
+    chain::ChainingGraphFormat,
+    seed::{AnchorScheme, RepeatMaskThreshold},
+};
+
+/// The size of the reference blocks that are seeded as chaining anchors.
+///
+/// Chaining is only possible if the reference is at least this long.
+pub const CHAINING_BLOCK_SIZE: usize = 20;
+
 pub trait ChainingStrategy: AlignmentStrategy {
     type Memory;
 
+    /// Chooses the behaviour of strategies that are selected at runtime.
+    ///
+    /// Strategies that are selected at compile time use `()`.
+    type Selection: Debug + Clone + Copy;
+
+    /// True if the lower bound applied by this strategy is consistent.
+    ///
+    /// If not, the A* search reopens nodes that are reached again with a lower cost after being closed.
+    fn is_label_setting(memory: &Self::Memory) -> bool;
+
     fn initialise_memory<
         AlphabetType: Alphabet,
         SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
@@ -32,7 +62,15 @@ pub trait ChainingStrategy: AlignmentStrategy {
         query: &SubsequenceType,
         config: &TemplateSwitchConfig<AlphabetType>,
         block_size: usize,
-    ) -> Self::Memory;
+        selection: Self::Selection,
+    ) -> Result<Self::Memory>;
+
+    /// Estimate the memory required by [`Self::Memory`] in bytes, without initialising it.
+    fn estimate_memory(
+        reference_length: usize,
+        query_length: usize,
+        selection: Self::Selection,
+    ) -> usize;
 
     fn apply_lower_bound<
         Strategies: AlignmentStrategySelector<Chaining = Self>,
@@ -41,6 +79,9 @@ pub trait ChainingStrategy: AlignmentStrategy {
         node: Node<Strategies>,
         context: &Context<SubsequenceType, Strategies>,
     ) -> Node<Strategies>;
+
+    /// Add statistics about the chaining anchors to the statistics of an alignment.
+    fn update_statistics(_memory: &Self::Memory, _statistics: &mut AlignmentStatistics) {}
 }
 
 #[expect(dead_code)]
@@ -49,6 +90,13 @@ pub struct ChainingMemory {
     tsa_lower_bounds: TemplateSwitchAlignmentLowerBoundMatrix,
     chain: Chain,
     max_gap_open_cost: Cost,
+    /// The minimum cost of any template switch.
+    min_template_switch_cost: Cost,
+    /// The minimum cost of a single gap character in primary or flank alignments.
+    min_primary_gap_cost: Cost,
+    /// The number of anchors that were seeded for chaining.
+    anchor_count: usize,
+    chain_statistics: ChainStatistics,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -60,6 +108,30 @@ pub struct PrecomputeOnlyChainingStrategy;
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct LowerBoundChainingStrategy;
 
+/// Selects one of the other chaining strategies at runtime.
+///
+/// Chaining is applied only once per opened node, so dispatching at runtime is cheap,
+/// but avoids compiling the aligner once for each chaining strategy.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RuntimeChainingStrategy;
+
+/// The chaining strategy used by [`RuntimeChainingStrategy`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RuntimeChainingSelection {
+    /// Behave like [`NoChainingStrategy`].
+    None,
+    /// Behave like [`PrecomputeOnlyChainingStrategy`].
+    PrecomputeOnly,
+    /// Behave like [`LowerBoundChainingStrategy`].
+    LowerBound,
+}
+
+pub enum RuntimeChainingMemory {
+    None,
+    PrecomputeOnly(ChainingMemory),
+    LowerBound(ChainingMemory),
+}
+
 struct TemplateSwitchAlignmentLowerBoundChainingCosts<'a> {
     matrix: &'a TemplateSwitchAlignmentLowerBoundMatrix,
     reference_length: usize,
@@ -68,6 +140,11 @@ struct TemplateSwitchAlignmentLowerBoundChainingCosts<'a> {
 
 impl ChainingStrategy for NoChainingStrategy {
     type Memory = ();
+    type Selection = ();
+
+    fn is_label_setting(_memory: &Self::Memory) -> bool {
+        true
+    }
 
     fn initialise_memory<
         AlphabetType: Alphabet,
@@ -77,8 +154,18 @@ impl ChainingStrategy for NoChainingStrategy {
         _query: &SubsequenceType,
         _config: &TemplateSwitchConfig<AlphabetType>,
         _block_size: usize,
-    ) -> Self::Memory {
+        _selection: Self::Selection,
+    ) -> Result<Self::Memory> {
         // Do nothing.
+        Ok(())
+    }
+
+    fn estimate_memory(
+        _reference_length: usize,
+        _query_length: usize,
+        _selection: Self::Selection,
+    ) -> usize {
+        0
     }
 
     fn apply_lower_bound<
@@ -94,6 +181,11 @@ impl ChainingStrategy for NoChainingStrategy {
 
 impl ChainingStrategy for PrecomputeOnlyChainingStrategy {
     type Memory = ChainingMemory;
+    type Selection = ();
+
+    fn is_label_setting(_memory: &Self::Memory) -> bool {
+        true
+    }
 
     fn initialise_memory<
         AlphabetType: Alphabet,
@@ -103,35 +195,57 @@ impl ChainingStrategy for PrecomputeOnlyChainingStrategy {
         query: &SubsequenceType,
         config: &TemplateSwitchConfig<AlphabetType>,
         block_size: usize,
-    ) -> Self::Memory {
-        let ts_lower_bounds = TemplateSwitchLowerBoundMatrix::new(config);
-        debug!("{ts_lower_bounds}");
-        let tsa_lower_bounds = TemplateSwitchAlignmentLowerBoundMatrix::new(
-            config,
-            &ts_lower_bounds,
-            reference.len(),
-            query.len(),
-            block_size * 2 - 1,
-            block_size - 1,
-        );
-        debug!("{tsa_lower_bounds}");
-        let chaining_anchors = ChainingAnchors::seed_nonoverlapping(reference, query, block_size);
-        let chain = Chain::compute_chain(
-            TemplateSwitchAlignmentLowerBoundChainingCosts {
-                matrix: &tsa_lower_bounds,
-                reference_length: reference.len(),
-                query_length: query.len(),
-            },
+        _selection: Self::Selection,
+    ) -> Result<Self::Memory> {
+        let ComputedChain {
+            ts_lower_bounds,
+            tsa_lower_bounds,
             chaining_anchors,
-        );
-        debug!("{chain}");
+            chain,
+        } = compute_chain(reference, query, config, block_size)?;
+        let anchor_count = chaining_anchors.anchors().len();
+        let chain_statistics =
+            chain_statistics(&chain, reference.len(), query.len(), &config.directions);
 
-        ChainingMemory {
+        let min_template_switch_cost = ts_lower_bounds
+            .iter()
+            .map(|entry| entry.cost())
+            .min()
+            .unwrap_or(Cost::MAX);
+        let min_primary_gap_cost = [
+            &config.primary_edit_costs,
+            &config.left_flank_edit_costs,
+            &config.right_flank_edit_costs,
+        ]
+        .into_iter()
+        .map(|costs| costs.min_gap_open_cost().min(costs.min_gap_extend_cost()))
+        .min()
+        .unwrap();
+
+        Ok(ChainingMemory {
             ts_lower_bounds,
             tsa_lower_bounds,
             chain,
             max_gap_open_cost: config.primary_edit_costs.max_gap_open_cost(),
-        }
+            min_template_switch_cost,
+            min_primary_gap_cost,
+            anchor_count,
+            chain_statistics,
+        })
+    }
+
+    fn estimate_memory(
+        reference_length: usize,
+        query_length: usize,
+        _selection: Self::Selection,
+    ) -> usize {
+        // The TS lower bound matrix depends only on the config and is small,
+        // but the TS alignment lower bound matrix has an entry for each pair of reference and query indices.
+        // The chain has at most one link per reference block.
+        (reference_length + 1)
+            .saturating_mul(query_length + 1)
+            .saturating_mul(std::mem::size_of::<Cost>())
+            .saturating_add(reference_length.saturating_mul(std::mem::size_of::<ChainingAnchor>()))
     }
 
     fn apply_lower_bound<
@@ -143,10 +257,22 @@ impl ChainingStrategy for PrecomputeOnlyChainingStrategy {
     ) -> Node<Strategies> {
         node
     }
+
+    fn update_statistics(memory: &Self::Memory, statistics: &mut AlignmentStatistics) {
+        statistics.chaining_anchors = memory.anchor_count as u64;
+        statistics.chain = Some(memory.chain_statistics.clone());
+    }
 }
 
 impl ChainingStrategy for LowerBoundChainingStrategy {
     type Memory = ChainingMemory;
+    type Selection = ();
+
+    fn is_label_setting(_memory: &Self::Memory) -> bool {
+        // Neither the chain lower bound nor the template switch lower bound apply to non-primary nodes,
+        // so the lower bound drops when entering a template switch.
+        false
+    }
 
     fn initialise_memory<
         AlphabetType: Alphabet,
@@ -156,50 +282,182 @@ impl ChainingStrategy for LowerBoundChainingStrategy {
         query: &SubsequenceType,
         config: &TemplateSwitchConfig<AlphabetType>,
         block_size: usize,
-    ) -> Self::Memory {
-        PrecomputeOnlyChainingStrategy::initialise_memory(reference, query, config, block_size)
+        selection: Self::Selection,
+    ) -> Result<Self::Memory> {
+        PrecomputeOnlyChainingStrategy::initialise_memory(
+            reference, query, config, block_size, selection,
+        )
+    }
+
+    fn estimate_memory(
+        reference_length: usize,
+        query_length: usize,
+        selection: Self::Selection,
+    ) -> usize {
+        PrecomputeOnlyChainingStrategy::estimate_memory(reference_length, query_length, selection)
     }
 
     fn apply_lower_bound<
         Strategies: AlignmentStrategySelector<Chaining = Self>,
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
     >(
-        mut node: Node<Strategies>,
+        node: Node<Strategies>,
         context: &Context<SubsequenceType, Strategies>,
     ) -> Node<Strategies> {
-        if let Identifier::Primary {
-            reference_index,
-            query_index,
-            gap_type,
-            flank_index,
-            ..
+        apply_chain_lower_bound(node, &context.memory.chaining, context)
+    }
+    fn update_statistics(memory: &Self::Memory, statistics: &mut AlignmentStatistics) {
+        PrecomputeOnlyChainingStrategy::update_statistics(memory, statistics);
+    }
+}
+
+impl ChainingStrategy for RuntimeChainingStrategy {
+    type Memory = RuntimeChainingMemory;
+    type Selection = RuntimeChainingSelection;
+
+    fn is_label_setting(memory: &Self::Memory) -> bool {
+        match memory {
+            RuntimeChainingMemory::None => NoChainingStrategy::is_label_setting(&()),
+            RuntimeChainingMemory::PrecomputeOnly(memory) => {
+                PrecomputeOnlyChainingStrategy::is_label_setting(memory)
+            }
+            RuntimeChainingMemory::LowerBound(memory) => {
+                LowerBoundChainingStrategy::is_label_setting(memory)
+            }
         }
-        | Identifier::PrimaryReentry {
-            reference_index,
-            query_index,
-            gap_type,
-            flank_index,
-            ..
-        } = node.node_data.identifier
-        {
-            if flank_index <= 0 {
-                let mut chain_lower_bound = context
-                    .memory
-                    .chaining
-                    .chain
-                    .chain_lower_bound(reference_index, query_index);
-                if gap_type != GapType::None {
-                    chain_lower_bound = chain_lower_bound
-                        .saturating_sub(&context.memory.chaining.max_gap_open_cost);
-                }
+    }
+
+    fn initialise_memory<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        config: &TemplateSwitchConfig<AlphabetType>,
+        block_size: usize,
+        selection: Self::Selection,
+    ) -> Result<Self::Memory> {
+        Ok(match selection {
+            RuntimeChainingSelection::None => RuntimeChainingMemory::None,
+            RuntimeChainingSelection::PrecomputeOnly => RuntimeChainingMemory::PrecomputeOnly(
+                PrecomputeOnlyChainingStrategy::initialise_memory(
+                    reference,
+                    query,
+                    config,
+                    block_size,
+                    (),
+                )?,
+            ),
+            RuntimeChainingSelection::LowerBound => {
+                RuntimeChainingMemory::LowerBound(LowerBoundChainingStrategy::initialise_memory(
+                    reference,
+                    query,
+                    config,
+                    block_size,
+                    (),
+                )?)
+            }
+        })
+    }
 
-                node.node_data.a_star_lower_bound =
-                    node.node_data.a_star_lower_bound.max(chain_lower_bound);
+    fn estimate_memory(
+        reference_length: usize,
+        query_length: usize,
+        selection: Self::Selection,
+    ) -> usize {
+        match selection {
+            RuntimeChainingSelection::None => {
+                NoChainingStrategy::estimate_memory(reference_length, query_length, ())
+            }
+            RuntimeChainingSelection::PrecomputeOnly => {
+                PrecomputeOnlyChainingStrategy::estimate_memory(reference_length, query_length, ())
+            }
+            RuntimeChainingSelection::LowerBound => {
+                LowerBoundChainingStrategy::estimate_memory(reference_length, query_length, ())
             }
         }
+    }
 
-        node
+    fn apply_lower_bound<
+        Strategies: AlignmentStrategySelector<Chaining = Self>,
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        node: Node<Strategies>,
+        context: &Context<SubsequenceType, Strategies>,
+    ) -> Node<Strategies> {
+        match &context.memory.chaining {
+            RuntimeChainingMemory::None | RuntimeChainingMemory::PrecomputeOnly(_) => node,
+            RuntimeChainingMemory::LowerBound(memory) => {
+                apply_chain_lower_bound(node, memory, context)
+            }
+        }
+    }
+
+    fn update_statistics(memory: &Self::Memory, statistics: &mut AlignmentStatistics) {
+        match memory {
+            RuntimeChainingMemory::None => NoChainingStrategy::update_statistics(&(), statistics),
+            RuntimeChainingMemory::PrecomputeOnly(memory) => {
+                PrecomputeOnlyChainingStrategy::update_statistics(memory, statistics)
+            }
+            RuntimeChainingMemory::LowerBound(memory) => {
+                LowerBoundChainingStrategy::update_statistics(memory, statistics)
+            }
+        }
+    }
+}
+
+/// Raise the lower bound of primary nodes to the chain lower bound and the cost of bridging the remaining length difference.
+fn apply_chain_lower_bound<
+    Strategies: AlignmentStrategySelector,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    mut node: Node<Strategies>,
+    memory: &ChainingMemory,
+    context: &Context<SubsequenceType, Strategies>,
+) -> Node<Strategies> {
+    if let Identifier::Primary {
+        reference_index,
+        query_index,
+        gap_type,
+        flank_index,
+        ..
+    }
+    | Identifier::PrimaryReentry {
+        reference_index,
+        query_index,
+        gap_type,
+        flank_index,
+        ..
+    } = node.node_data.identifier
+    {
+        if flank_index <= 0 {
+            let mut chain_lower_bound =
+                memory.chain.chain_lower_bound(reference_index, query_index);
+            if gap_type != GapType::None {
+                chain_lower_bound = chain_lower_bound.saturating_sub(&memory.max_gap_open_cost);
+            }
+
+            node.node_data.a_star_lower_bound =
+                node.node_data.a_star_lower_bound.max(chain_lower_bound);
+        }
+
+        // The remaining length difference needs to be bridged either by gaps or by at least one template switch.
+        let remaining_length_difference =
+            (context.reference.len() - reference_index).abs_diff(context.query.len() - query_index);
+        let gap_lower_bound = Cost::from(
+            u64::try_from(remaining_length_difference)
+                .unwrap()
+                .saturating_mul(memory.min_primary_gap_cost.as_u64()),
+        );
+        let template_switch_lower_bound = gap_lower_bound.min(memory.min_template_switch_cost);
+
+        node.node_data.a_star_lower_bound = node
+            .node_data
+            .a_star_lower_bound
+            .max(template_switch_lower_bound);
     }
+
+    node
 }
 
 impl ChainingCostsProvider for TemplateSwitchAlignmentLowerBoundChainingCosts<'_> {
@@ -223,7 +481,15 @@ impl ChainingCostsProvider for TemplateSwitchAlignmentLowerBoundChainingCosts<'_
 
         let delta_reference = to.reference_block().start - from.reference_block().end;
         let delta_query = to.query_block().start - from.query_block().end;
-        self.matrix.cost(delta_reference, delta_query)
+        // The blocks of homopolymer-compressed anchors may differ in length, which needs to be bridged by gaps.
+        let reference_length = to.reference_block().len();
+        let query_length = to.query_block().len();
+        self.matrix
+            .cost(delta_reference, delta_query)
+            .saturating_add(&self.matrix.cost(
+                reference_length.saturating_sub(query_length),
+                query_length.saturating_sub(reference_length),
+            ))
     }
 }
 
@@ -310,3 +576,217 @@ impl AlignmentStrategy for LowerBoundChainingStrategy {
         *self
     }
 }
+
+impl AlignmentStrategy for RuntimeChainingStrategy {
+    fn create_root<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector,
+    >(
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        Self
+    }
+
+    fn generate_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector,
+    >(
+        &self,
+        _identifier: Identifier<<<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::IdentifierPrimaryExtraData>,
+        _alignment_type: AlignmentType,
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        *self
+    }
+}
+
+/// Compute the chain like [`PrecomputeOnlyChainingStrategy`] and write the searched chaining graph in the given format.
+///
+/// See [`Chain::write_graph`].
+pub fn write_chaining_graph<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    config: &TemplateSwitchConfig<AlphabetType>,
+    format: ChainingGraphFormat,
+    writer: impl std::io::Write,
+) -> Result<()> {
+    let ComputedChain {
+        tsa_lower_bounds,
+        chaining_anchors,
+        chain,
+        ..
+    } = compute_chain(reference, query, config, CHAINING_BLOCK_SIZE)?;
+    let chaining_costs = TemplateSwitchAlignmentLowerBoundChainingCosts {
+        matrix: &tsa_lower_bounds,
+        reference_length: reference.len(),
+        query_length: query.len(),
+    };
+    chain.write_graph(&chaining_costs, &chaining_anchors, format, writer)?;
+    Ok(())
+}
+
+/// The lower bounds, anchors and chain computed by [`compute_chain`].
+struct ComputedChain {
+    ts_lower_bounds: TemplateSwitchLowerBoundMatrix,
+    tsa_lower_bounds: TemplateSwitchAlignmentLowerBoundMatrix,
+    /// The anchors the chain was computed from.
+    chaining_anchors: ChainingAnchors,
+    chain: Chain,
+}
+
+/// Compute the template switch lower bounds, seed the anchors as configured and chain them.
+fn compute_chain<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    config: &TemplateSwitchConfig<AlphabetType>,
+    block_size: usize,
+) -> Result<ComputedChain> {
+    let ts_lower_bounds = TemplateSwitchLowerBoundMatrix::new(config)?;
+    debug!("{ts_lower_bounds}");
+    let homopolymer_length = max_homopolymer_length(reference, query, config.anchor_scheme);
+    let tsa_lower_bounds = TemplateSwitchAlignmentLowerBoundMatrix::new(
+        config,
+        &ts_lower_bounds,
+        reference.len(),
+        query.len(),
+        (block_size * 2 - 1) * homopolymer_length,
+        (block_size - 1) * homopolymer_length,
+    );
+    debug!("{tsa_lower_bounds}");
+    let mut chaining_anchors = ChainingAnchors::seed_nonoverlapping_with_scheme(
+        reference,
+        query,
+        block_size,
+        config.anchor_scheme,
+    )?;
+    if !config.directions.is_none() {
+        chaining_anchors =
+            chaining_anchors.seed_reverse_complements(reference, query, block_size)?;
+    }
+    if let Some(threshold) = config.anchor_repeat_mask {
+        chaining_anchors = chaining_anchors.mask_repeats(threshold);
+    }
+    let chain = Chain::compute_chain(
+        TemplateSwitchAlignmentLowerBoundChainingCosts {
+            matrix: &tsa_lower_bounds,
+            reference_length: reference.len(),
+            query_length: query.len(),
+        },
+        chaining_anchors.clone(),
+    )?;
+    debug!("{chain}");
+
+    Ok(ComputedChain {
+        ts_lower_bounds,
+        tsa_lower_bounds,
+        chaining_anchors,
+        chain,
+    })
+}
+
+/// The factor by which a stretch of exact matches without an anchor can be longer than without homopolymer compression.
+///
+/// With [`AnchorScheme::HpcKmer`], each character of a block may stand for a whole homopolymer,
+/// so this is the length of the longest homopolymer of the sequences.
+fn max_homopolymer_length<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    anchor_scheme: AnchorScheme,
+) -> usize {
+    match anchor_scheme {
+        AnchorScheme::Kmer => 1,
+        AnchorScheme::HpcKmer => [reference, query]
+            .into_iter()
+            .map(|sequence| HomopolymerCompressed::new(&sequence.as_string()).max_run_length())
+            .max()
+            .unwrap_or(1)
+            .max(1),
+    }
+}
+
+fn chain_statistics(
+    chain: &Chain,
+    reference_length: usize,
+    query_length: usize,
+    directions: &TemplateSwitchDirections,
+) -> ChainStatistics {
+    let (anchors, reference_covered, query_covered) = chain.anchors().fold(
+        (0, 0, 0),
+        |(anchors, reference_covered, query_covered), anchor| {
+            (
+                anchors + 1,
+                reference_covered + anchor.reference_block().len(),
+                query_covered + anchor.query_block().len(),
+            )
+        },
+    );
+    // Avoid dividing by zero for empty sequences.
+    let coverage = |covered: usize, length: usize| R64::new(covered as f64 / length.max(1) as f64);
+
+    ChainStatistics {
+        anchors,
+        reference_coverage: coverage(reference_covered, reference_length),
+        query_coverage: coverage(query_covered, query_length),
+        cost: R64::new(chain.cost().as_u64() as f64),
+        gaps: chain.gap_count(reference_length, query_length) as u64,
+        template_switch_anchors: chain
+            .template_switch_anchors()
+            .iter()
+            .filter(|anchor| match anchor.class() {
+                ReverseComplementAnchorClass::ReferenceQuery => {
+                    directions.allows(
+                        TemplateSwitchPrimary::Reference,
+                        TemplateSwitchSecondary::Query,
+                    ) || directions.allows(
+                        TemplateSwitchPrimary::Query,
+                        TemplateSwitchSecondary::Reference,
+                    )
+                }
+                ReverseComplementAnchorClass::ReferenceReference => directions.allows(
+                    TemplateSwitchPrimary::Reference,
+                    TemplateSwitchSecondary::Reference,
+                ),
+                ReverseComplementAnchorClass::QueryQuery => {
+                    directions.allows(TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Query)
+                }
+            })
+            .count() as u64,
+        transitions: chain
+            .transitions(reference_length, query_length)
+            .into_iter()
+            .map(|transition| ChainTransitionStatistics {
+                reference_start: transition.reference_gap.start as u64,
+                query_start: transition.query_gap.start as u64,
+                reference_gap: transition.reference_gap.len() as u64,
+                query_gap: transition.query_gap.len() as u64,
+                cost: R64::new(transition.cost.as_u64() as f64),
+                direct: transition.is_direct(),
+                demands_non_match: transition.demands_non_match(),
+            })
+            .collect(),
+        chain_anchors: chain
+            .anchors_with_costs()
+            .scan(chain.cost(), |previous_cost, (anchor, cost)| {
+                let cost_from_previous = *previous_cost - cost;
+                *previous_cost = cost;
+                Some(ChainAnchorStatistics {
+                    reference_start: anchor.reference_block().start as u64,
+                    reference_end: anchor.reference_block().end as u64,
+                    query_start: anchor.query_block().start as u64,
+                    query_end: anchor.query_block().end as u64,
+                    cost: R64::new(cost_from_previous.as_u64() as f64),
+                    cost_to_target: R64::new(cost.as_u64() as f64),
+                })
+            })
+            .collect(),
+    }
+}
@@ -1,11 +1,41 @@
+use std::fmt::Debug;
+
 use compact_genome::interface::sequence::GenomeSequence;
+use noisy_float::types::R64;
+
+use crate::a_star_aligner::template_switch_distance::{
+    identifier::GapType, AlignmentType, Context, Identifier, Node,
+};
+
+use super::{primary_match::PrimaryMatchStrategy, AlignmentStrategySelector};
 
-use crate::a_star_aligner::template_switch_distance::{AlignmentType, Context, Identifier, Node};
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "serde")]
+mod toml_io;
+
+pub trait NodeOrdStrategy<PrimaryMatch: PrimaryMatchStrategy>: Eq + Clone + Debug {
+    /// Data shared by all nodes of an alignment, e.g. a learned model.
+    type Memory: Default + Clone;
+
+    fn create_root<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self;
 
-use super::{primary_match::PrimaryMatchStrategy, AlignmentStrategy, AlignmentStrategySelector};
+    fn generate_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        &self,
+        identifier: Identifier<PrimaryMatch::IdentifierPrimaryExtraData>,
+        alignment_type: AlignmentType,
+        context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self;
 
-pub trait NodeOrdStrategy<PrimaryMatch: PrimaryMatchStrategy>: AlignmentStrategy {
-    fn cmp<Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>>(
+    fn cmp<Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>>(
         &self,
         n1: &Node<Strategies>,
         n2: &Node<Strategies>,
@@ -18,8 +48,59 @@ pub struct CostOnlyNodeOrdStrategy;
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct AntiDiagonalNodeOrdStrategy;
 
+/// Breaks cost ties by the score of a [`NodeOrdModel`], preferring nodes with a higher score.
+///
+/// The score is computed once per node from its identifier.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LearnedNodeOrdStrategy {
+    score: R64,
+}
+
+/// Weights of the node features that are summed up into the score of a [`LearnedNodeOrdStrategy`].
+///
+/// The weights are fitted to the alignments of a user's typical data, e.g. by a benchmark harness,
+/// and stored with [`write_toml`](Self::write_toml).
+/// All weights are zero by default, in which case nodes with equal cost are not ordered further.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeOrdModel {
+    /// The weight of the progress along the sequences, which is the anti-diagonal of primary nodes,
+    /// and the anti-diagonal of the entrance of template switch nodes.
+    pub anti_diagonal: f64,
+    /// The weight of the absolute flank index of primary nodes.
+    pub flank_index: f64,
+    /// The weight of being inside a template switch.
+    pub template_switch: f64,
+    /// The weight of the length of the secondary alignment of secondary nodes.
+    pub secondary_length: f64,
+    /// The weight of being inside a gap.
+    pub gap: f64,
+}
+
 impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch> for CostOnlyNodeOrdStrategy {
-    fn cmp<Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>>(
+    type Memory = ();
+
+    fn create_root<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        Self
+    }
+
+    fn generate_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        &self,
+        _identifier: Identifier<PrimaryMatch::IdentifierPrimaryExtraData>,
+        _alignment_type: AlignmentType,
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        *self
+    }
+
+    fn cmp<Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>>(
         &self,
         n1: &Node<Strategies>,
         n2: &Node<Strategies>,
@@ -33,7 +114,30 @@ impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch> for CostO
 impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch>
     for AntiDiagonalNodeOrdStrategy
 {
-    fn cmp<Strategies: AlignmentStrategySelector<PrimaryMatch = PrimaryMatch>>(
+    type Memory = ();
+
+    fn create_root<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        Self
+    }
+
+    fn generate_successor<
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
+    >(
+        &self,
+        _identifier: Identifier<PrimaryMatch::IdentifierPrimaryExtraData>,
+        _alignment_type: AlignmentType,
+        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+    ) -> Self {
+        *self
+    }
+
+    fn cmp<Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>>(
         &self,
         n1: &Node<Strategies>,
         n2: &Node<Strategies>,
@@ -43,10 +147,7 @@ impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch>
             .lower_bound_cost()
             .cmp(&n2.node_data.lower_bound_cost())
         {
-            // This secondary ordering may make things actually slower.
-            // While it does reduce the number of visited nodes a little bit,
-            // it also makes heap operations more expensive.
-            // Preliminary testing showed that this would be a slowdown.
+            // Break ties by anti-diagonal, see `AStarTieBreaking::NodeOrd` for why this may be slower.
             std::cmp::Ordering::Equal => n2
                 .node_data
                 .identifier
@@ -57,48 +158,135 @@ impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch>
     }
 }
 
-impl AlignmentStrategy for CostOnlyNodeOrdStrategy {
+impl<PrimaryMatch: PrimaryMatchStrategy> NodeOrdStrategy<PrimaryMatch> for LearnedNodeOrdStrategy {
+    type Memory = NodeOrdModel;
+
     fn create_root<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
-        Strategies: AlignmentStrategySelector,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
     >(
         _context: &Context<'_, '_, SubsequenceType, Strategies>,
     ) -> Self {
-        Self
+        // The root is never compared to another node.
+        Self {
+            score: R64::new(0.0),
+        }
     }
 
     fn generate_successor<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
-        Strategies: AlignmentStrategySelector,
+        Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>,
     >(
         &self,
-        _identifier: Identifier<<<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::IdentifierPrimaryExtraData>,
+        identifier: Identifier<PrimaryMatch::IdentifierPrimaryExtraData>,
         _alignment_type: AlignmentType,
-        _context: &Context<'_, '_, SubsequenceType, Strategies>,
+        context: &Context<'_, '_, SubsequenceType, Strategies>,
     ) -> Self {
-        *self
+        Self {
+            score: context.memory.node_ord.score(identifier),
+        }
+    }
+
+    fn cmp<Strategies: AlignmentStrategySelector<NodeOrd = Self, PrimaryMatch = PrimaryMatch>>(
+        &self,
+        n1: &Node<Strategies>,
+        n2: &Node<Strategies>,
+    ) -> std::cmp::Ordering {
+        n1.node_data
+            .lower_bound_cost()
+            .cmp(&n2.node_data.lower_bound_cost())
+            .then_with(|| {
+                n2.strategies
+                    .node_ord_strategy
+                    .score()
+                    .cmp(&n1.strategies.node_ord_strategy.score())
+            })
     }
 }
 
-impl AlignmentStrategy for AntiDiagonalNodeOrdStrategy {
-    fn create_root<
-        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
-        Strategies: AlignmentStrategySelector,
-    >(
-        _context: &Context<'_, '_, SubsequenceType, Strategies>,
-    ) -> Self {
-        Self
+impl LearnedNodeOrdStrategy {
+    pub fn score(&self) -> R64 {
+        self.score
     }
+}
 
-    fn generate_successor<
-        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
-        Strategies: AlignmentStrategySelector,
-    >(
-        &self,
-        _identifier: Identifier<<<Strategies as AlignmentStrategySelector>::PrimaryMatch as PrimaryMatchStrategy>::IdentifierPrimaryExtraData>,
-        _alignment_type: AlignmentType,
-        _context: &Context<'_, '_, SubsequenceType, Strategies>,
-    ) -> Self {
-        *self
+impl NodeOrdModel {
+    /// The weighted sum of the features of the node with the given identifier.
+    pub fn score<PrimaryExtraData>(&self, identifier: Identifier<PrimaryExtraData>) -> R64 {
+        let mut score = 0.0;
+
+        match identifier {
+            Identifier::Primary {
+                reference_index,
+                query_index,
+                gap_type,
+                flank_index,
+                ..
+            }
+            | Identifier::PrimaryReentry {
+                reference_index,
+                query_index,
+                gap_type,
+                flank_index,
+                ..
+            } => {
+                score += self.anti_diagonal * (reference_index + query_index) as f64;
+                score += self.flank_index * flank_index.unsigned_abs() as f64;
+                if gap_type != GapType::None {
+                    score += self.gap;
+                }
+            }
+            Identifier::TemplateSwitchEntrance {
+                entrance_reference_index,
+                entrance_query_index,
+                ..
+            }
+            | Identifier::TemplateSwitchExit {
+                entrance_reference_index,
+                entrance_query_index,
+                ..
+            } => {
+                score +=
+                    self.anti_diagonal * (entrance_reference_index + entrance_query_index) as f64;
+                score += self.template_switch;
+            }
+            Identifier::Secondary {
+                entrance_reference_index,
+                entrance_query_index,
+                length,
+                gap_type,
+                ..
+            } => {
+                score +=
+                    self.anti_diagonal * (entrance_reference_index + entrance_query_index) as f64;
+                score += self.template_switch;
+                score += self.secondary_length * length as f64;
+                if gap_type != GapType::None {
+                    score += self.gap;
+                }
+            }
+            Identifier::Inversion {
+                start_reference_index,
+                query_index,
+                ..
+            } => {
+                score += self.anti_diagonal * (start_reference_index + query_index) as f64;
+            }
+        }
+
+        R64::new(score)
+    }
+
+    /// True if all weights are finite, such that all scores are.
+    pub fn is_finite(&self) -> bool {
+        [
+            self.anti_diagonal,
+            self.flank_index,
+            self.template_switch,
+            self.secondary_length,
+            self.gap,
+        ]
+        .into_iter()
+        .all(f64::is_finite)
     }
 }
@@ -0,0 +1,74 @@
+use noisy_float::types::R64;
+
+use crate::a_star_aligner::template_switch_distance::{
+    identifier::{GapType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand},
+    Identifier,
+};
+
+use super::NodeOrdModel;
+
+const MODEL: NodeOrdModel = NodeOrdModel {
+    anti_diagonal: 1.0,
+    flank_index: -0.5,
+    template_switch: 4.0,
+    secondary_length: 0.25,
+    gap: -2.0,
+};
+
+#[test]
+fn score_weights_features() {
+    let primary = Identifier::Primary {
+        reference_index: 3,
+        query_index: 5,
+        gap_type: GapType::Insertion,
+        homopolymer_length: 0,
+        flank_index: -2,
+        template_switched: false,
+        data: (),
+    };
+    assert_eq!(MODEL.score(primary), R64::new(8.0 - 1.0 - 2.0));
+    assert_eq!(NodeOrdModel::default().score(primary), R64::new(0.0));
+
+    let secondary = Identifier::<()>::Secondary {
+        entrance_reference_index: 3,
+        entrance_query_index: 5,
+        template_switch_primary: TemplateSwitchPrimary::Query,
+        template_switch_secondary: TemplateSwitchSecondary::Reference,
+        template_switch_strand: TemplateSwitchStrand::ReverseComplement,
+        length: 4,
+        primary_index: 9,
+        secondary_index: 12,
+        gap_type: GapType::None,
+        homopolymer_length: 0,
+    };
+    assert_eq!(MODEL.score(secondary), R64::new(8.0 + 4.0 + 1.0));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn toml_round_trip() {
+    use crate::error::Error;
+
+    let mut written = Vec::new();
+    MODEL.write_toml(&mut written).unwrap();
+    assert_eq!(NodeOrdModel::read_toml(written.as_slice()).unwrap(), MODEL);
+
+    // Missing weights are zero.
+    assert_eq!(
+        NodeOrdModel::read_toml("gap = -2.0".as_bytes()).unwrap(),
+        NodeOrdModel {
+            gap: -2.0,
+            ..Default::default()
+        }
+    );
+
+    for invalid in ["gap = nan", "gap = inf", "curvature = 1.0"] {
+        assert!(
+            matches!(
+                NodeOrdModel::read_toml(invalid.as_bytes()),
+                Err(Error::InvalidNodeOrdModel { .. })
+            ),
+            "{invalid}"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Reading and writing node ordering models as TOML.
+//!
+//! Weights that are missing from the TOML are zero.
+
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::NodeOrdModel;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TomlNodeOrdModel {
+    anti_diagonal: f64,
+    flank_index: f64,
+    template_switch: f64,
+    secondary_length: f64,
+    gap: f64,
+}
+
+impl Default for TomlNodeOrdModel {
+    fn default() -> Self {
+        NodeOrdModel::default().into()
+    }
+}
+
+impl From<NodeOrdModel> for TomlNodeOrdModel {
+    fn from(model: NodeOrdModel) -> Self {
+        Self {
+            anti_diagonal: model.anti_diagonal,
+            flank_index: model.flank_index,
+            template_switch: model.template_switch,
+            secondary_length: model.secondary_length,
+            gap: model.gap,
+        }
+    }
+}
+
+impl NodeOrdModel {
+    pub fn write_toml(&self, mut writer: impl Write) -> Result<()> {
+        let output = toml::to_string(&TomlNodeOrdModel::from(*self)).map_err(|error| {
+            Error::InvalidNodeOrdModel {
+                message: error.to_string(),
+            }
+        })?;
+        writer.write_all(output.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a model written by [`write_toml`](Self::write_toml).
+    pub fn read_toml(mut reader: impl Read) -> Result<Self> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        let model: TomlNodeOrdModel =
+            toml::from_str(&input).map_err(|error| Error::InvalidNodeOrdModel {
+                message: error.to_string(),
+            })?;
+
+        let model = Self {
+            anti_diagonal: model.anti_diagonal,
+            flank_index: model.flank_index,
+            template_switch: model.template_switch,
+            secondary_length: model.secondary_length,
+            gap: model.gap,
+        };
+        if !model.is_finite() {
+            return Err(Error::InvalidNodeOrdModel {
+                message: "all weights must be finite".to_string(),
+            });
+        }
+        Ok(model)
+    }
+}
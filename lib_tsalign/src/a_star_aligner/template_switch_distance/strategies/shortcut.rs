@@ -7,6 +7,7 @@ use crate::{
         Identifier,
     },
     config::TemplateSwitchConfig,
+    error::Result,
 };
 
 use super::{primary_match::PrimaryMatchStrategy, AlignmentStrategy, AlignmentStrategySelector};
@@ -16,7 +17,7 @@ pub trait ShortcutStrategy: AlignmentStrategy {
 
     fn initialise_memory<AlphabetType: Alphabet>(
         config: &TemplateSwitchConfig<AlphabetType>,
-    ) -> Self::Memory;
+    ) -> Result<Self::Memory>;
 
     fn generate_successors<
         SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
@@ -41,8 +42,9 @@ impl ShortcutStrategy for NoShortcutStrategy {
 
     fn initialise_memory<AlphabetType: Alphabet>(
         _config: &TemplateSwitchConfig<AlphabetType>,
-    ) -> Self::Memory {
+    ) -> Result<Self::Memory> {
         // Do nothing.
+        Ok(())
     }
 
     fn generate_successors<
@@ -64,7 +66,7 @@ impl ShortcutStrategy for TemplateSwitchLowerBoundShortcutStrategy {
 
     fn initialise_memory<AlphabetType: Alphabet>(
         config: &TemplateSwitchConfig<AlphabetType>,
-    ) -> Self::Memory {
+    ) -> Result<Self::Memory> {
         TemplateSwitchLowerBoundMatrix::new(config)
     }
 
@@ -5,10 +5,13 @@ use deterministic_default_hasher::DeterministicDefaultHasher;
 use generic_a_star::reset::Reset;
 use generic_a_star::{AStar, AStarContext, AStarNode, AStarResult};
 
+use crate::a_star_aligner::alignment_result::AlignmentStatistics;
 use crate::a_star_aligner::template_switch_distance::AlignmentType;
 use crate::{
     a_star_aligner::template_switch_distance::{
-        identifier::{GapType, TemplateSwitchPrimary, TemplateSwitchSecondary},
+        identifier::{
+            GapType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
         Context, Identifier, Node,
     },
     costs::cost::Cost,
@@ -34,6 +37,9 @@ pub trait TemplateSwitchMinLengthStrategy: AlignmentStrategy {
         secondary_root_node: Node<Strategies>,
         context: &mut Context<SubsequenceType, Strategies>,
     ) -> impl IntoIterator<Item = Node<Strategies>>;
+
+    /// Add statistics about the memorised lookahead results to the statistics of an alignment.
+    fn update_statistics(_memory: &Self::Memory, _statistics: &mut AlignmentStatistics) {}
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -76,12 +82,30 @@ impl TemplateSwitchMinLengthStrategy for NoTemplateSwitchMinLengthStrategy {
 pub struct LookaheadMemoryKey {
     template_switch_primary: TemplateSwitchPrimary,
     template_switch_secondary: TemplateSwitchSecondary,
+    template_switch_strand: TemplateSwitchStrand,
     primary_index: usize,
     secondary_index: usize,
 }
 
+/// The lookahead results, memorised across all template switch entrances of an alignment.
+#[derive(Debug, Default)]
+pub struct LookaheadMemory {
+    /// The lower bound of each template switch entrance, or `None` if no target was found within the cost limit.
+    lower_bounds: HashMap<LookaheadMemoryKey, Option<Cost>, DeterministicDefaultHasher>,
+    hits: usize,
+    misses: usize,
+}
+
+impl Reset for LookaheadMemory {
+    fn reset(&mut self) {
+        self.lower_bounds.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
+
 impl TemplateSwitchMinLengthStrategy for LookaheadTemplateSwitchMinLengthStrategy {
-    type Memory = HashMap<LookaheadMemoryKey, Cost, DeterministicDefaultHasher>;
+    type Memory = LookaheadMemory;
 
     fn template_switch_min_length_lookahead<
         Strategies: AlignmentStrategySelector<TemplateSwitchMinLength = Self>,
@@ -94,10 +118,12 @@ impl TemplateSwitchMinLengthStrategy for LookaheadTemplateSwitchMinLengthStrateg
         let Identifier::Secondary {
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             length: 0,
             primary_index,
             secondary_index,
             gap_type: GapType::None,
+            homopolymer_length: 0,
             ..
         } = secondary_root_node.node_data.identifier
         else {
@@ -107,46 +133,57 @@ impl TemplateSwitchMinLengthStrategy for LookaheadTemplateSwitchMinLengthStrateg
         let memory_key = LookaheadMemoryKey {
             template_switch_primary,
             template_switch_secondary,
+            template_switch_strand,
             primary_index,
             secondary_index,
         };
 
-        let secondary_root_node = if let Some(a_star_lower_bound) =
-            context.memory.template_switch_min_length.get(&memory_key)
-        {
-            secondary_root_node.node_data.a_star_lower_bound += *a_star_lower_bound;
-            secondary_root_node
+        let memory = &mut context.memory.template_switch_min_length;
+        let lower_bound = if let Some(lower_bound) = memory.lower_bounds.get(&memory_key) {
+            memory.hits += 1;
+            *lower_bound
         } else {
+            memory.misses += 1;
             let buffers = mem::take(&mut context.a_star_buffers);
             let initial_cost = secondary_root_node.cost();
             let mut a_star = AStar::new_with_buffers(
                 TemplateSwitchMinLengthContext::new(secondary_root_node.clone(), context),
                 buffers,
             );
-            a_star.initialise();
-
-            let alignment_result = a_star.search();
-
-            if let AStarResult::FoundTarget { identifier, .. } = alignment_result {
-                let target_cost = a_star.closed_node(&identifier).unwrap().cost();
-                context.a_star_buffers = a_star.into_buffers();
-                let lower_bound = target_cost - initial_cost;
-
-                context
-                    .memory
-                    .template_switch_min_length
-                    .insert(memory_key, lower_bound);
-                secondary_root_node.node_data.a_star_lower_bound += lower_bound;
-
-                secondary_root_node
-            } else {
-                context.a_star_buffers = a_star.into_buffers();
-                secondary_root_node
-            }
+            let search_result = a_star
+                .initialise()
+                .and_then(AStar::search)
+                .expect("A new search is always initialisable");
+
+            let lower_bound = match search_result {
+                AStarResult::FoundTarget { cost, .. } => Some(cost - initial_cost),
+                AStarResult::NoTarget { .. } => None,
+                AStarResult::ExceededMemoryLimit { .. }
+                | AStarResult::ExceededTimeLimit { .. }
+                | AStarResult::Cancelled { .. } => {
+                    unreachable!("No memory or time limit or cancellation token was set")
+                }
+            };
+            context.a_star_buffers = a_star.into_buffers();
+
+            context
+                .memory
+                .template_switch_min_length
+                .lower_bounds
+                .insert(memory_key, lower_bound);
+            lower_bound
         };
 
+        if let Some(lower_bound) = lower_bound {
+            secondary_root_node.node_data.a_star_lower_bound += lower_bound;
+        }
         Some(secondary_root_node)
     }
+
+    fn update_statistics(memory: &Self::Memory, statistics: &mut AlignmentStatistics) {
+        statistics.lookahead_cache_hits = memory.hits as u64;
+        statistics.lookahead_cache_misses = memory.misses as u64;
+    }
 }
 
 impl AlignmentStrategy for NoTemplateSwitchMinLengthStrategy {
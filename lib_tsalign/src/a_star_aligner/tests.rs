@@ -1,10 +1,51 @@
+use std::time::Duration;
+
 use compact_genome::{
-    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
-    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+    implementation::{
+        alphabets::{dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN},
+        bit_vec_sequence::BitVectorGenome,
+        vec_sequence::VectorGenome,
+    },
+    interface::{
+        alphabet::{Alphabet, AlphabetCharacter},
+        sequence::{GenomeSequence, OwnedGenomeSequence},
+    },
 };
+use generic_a_star::cancellation::CancellationToken;
+use noisy_float::types::{r64, R64};
 use num_traits::real::Real;
 
-use super::{gap_affine_edit_distance::ScoringTable, gap_affine_edit_distance_a_star_align};
+use crate::{
+    config::{TemplateSwitchConfig, TemplateSwitchDirections},
+    costs::{cost::Cost, gap_affine::GapAffineAlignmentCostTable},
+    error::Error,
+    guide::{AlignmentGuide, GuideAnchor},
+    test_fixtures::{align, align_with_settings, sample_config, QUERY, REFERENCE},
+};
+
+#[cfg(feature = "serde")]
+use super::gap_affine_edit_distance;
+use super::{
+    alignment_result::{
+        AlignmentResult, AlignmentStatistics, CostSegment, CostSegmentKind, IAlignmentType,
+        NoTargetReason, StatisticsFormat, StatisticsMode, STATISTICS_FORMAT_VERSION,
+    },
+    fast_path::without_jumps,
+    gap_affine_edit_distance::ScoringTable,
+    gap_affine_edit_distance_a_star_align,
+    template_switch_distance::{
+        secondary_score::SecondaryScorePolicy,
+        strategies::{
+            chaining::{
+                PrecomputeOnlyChainingStrategy, RuntimeChainingSelection, RuntimeChainingStrategy,
+            },
+            node_ord::NodeOrdModel,
+            template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
+        },
+        AlignmentType, TemplateSwitchStrand,
+    },
+    AStarTieBreaking, AlignerBuilder, RandomRestarts, SearchSettings,
+};
 
 #[test]
 fn match_overtakes_gap() {
@@ -21,8 +62,1120 @@ fn match_overtakes_gap() {
         reference.as_genome_subsequence(),
         query.as_genome_subsequence(),
         scoring_table,
-    );
+        &Default::default(),
+    )
+    .unwrap();
 
     assert_eq!(alignment_result.cigar(), "1D2M2I");
-    assert!((alignment_result.statistics().cost - 9.0).abs() < 1e-6);
+    let statistics = alignment_result.statistics();
+    assert!((statistics.cost - 9.0).abs() < 1e-6);
+    assert_eq!(statistics.format_version, STATISTICS_FORMAT_VERSION);
+    assert!(statistics.peak_open_nodes > 0);
+    assert!(statistics.peak_closed_nodes <= statistics.closed_nodes);
+    assert!(statistics.peak_memory_bytes > 0);
+    assert_eq!(statistics.a_star_restarts, 0);
+    assert_eq!(statistics.primary_matches, 2);
+    assert_eq!(statistics.primary_mismatches, 0);
+    assert_eq!(statistics.primary_gap_columns, 3);
+    assert_eq!(statistics.primary_gap_opens, 2);
+    assert!((statistics.primary_identity - 0.4).abs() < 1e-6);
+    assert!((statistics.primary_gap_compressed_identity - 0.5).abs() < 1e-6);
+    assert_eq!(statistics.template_switch_identity, 0.0);
+    assert_eq!(
+        statistics.cost_segments,
+        [CostSegment {
+            kind: CostSegmentKind::Primary,
+            template_switch: None,
+            steps: 5,
+            cost: r64(9.0),
+        }]
+    );
+}
+
+#[test]
+fn tie_breaking_does_not_change_cost() {
+    let reference = VectorGenome::<DnaAlphabet>::from_iter_u8("ACGTTGCAAC".bytes()).unwrap();
+    let query = VectorGenome::from_iter_u8("ACGTGCATAC".bytes()).unwrap();
+    let scoring_table = ScoringTable {
+        match_cost: 0.into(),
+        substitution_cost: 2.into(),
+        gap_open_cost: 3.into(),
+        gap_extend_cost: 1.into(),
+    };
+
+    for tie_breaking in [
+        AStarTieBreaking::NodeOrd,
+        AStarTieBreaking::Lifo,
+        AStarTieBreaking::SecondaryScore,
+        AStarTieBreaking::Identifier,
+    ] {
+        let alignment_result = gap_affine_edit_distance_a_star_align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            scoring_table,
+            &SearchSettings {
+                tie_breaking,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(
+            (alignment_result.statistics().cost - 6.0).abs() < 1e-6,
+            "{tie_breaking:?}: {alignment_result}"
+        );
+    }
+}
+
+#[test]
+fn cooptimal_alignments_flag_ambiguous_template_switches() {
+    let align = |reference: &[u8], query: &[u8], cooptimal_limit| {
+        align_with_settings(
+            reference,
+            query,
+            sample_config(),
+            &SearchSettings {
+                cooptimal_limit,
+                ..Default::default()
+            },
+        )
+    };
+
+    // The deletion can be placed at any of the four characters of the homopolymer.
+    let reference = b"ACCGTTAGCATGCAGTTTTCAAGCTAGGATCCATGACTGCA";
+    let query = b"ACCGTTAGCATGCAGTTTCAAGCTAGGATCCATGACTGCA";
+    let uncounted = align(reference, query, None);
+    assert_eq!(uncounted.statistics().cooptimal_alignments, 0);
+    let counted = align(reference, query, Some(100));
+    assert_eq!(counted.cigar(), uncounted.cigar());
+    assert_eq!(counted.statistics().cost, uncounted.statistics().cost);
+    assert_eq!(counted.statistics().cooptimal_alignments, 4);
+    assert!(counted.statistics().ambiguous_template_switches.is_empty());
+
+    // The inverted middle part can be aligned with either the reference or the query as primary.
+    let reference = REFERENCE.as_bytes();
+    let query = QUERY.as_bytes();
+    let counted = align(reference, query, Some(100));
+    assert!(counted.cigar().contains("[TS"), "{}", counted.cigar());
+    assert!(counted.statistics().cooptimal_alignments > 1);
+    assert_eq!(counted.statistics().ambiguous_template_switches, [0]);
+
+    let statistics = counted.statistics();
+    assert!(statistics.template_switch_matches > 0);
+    assert!(statistics.template_switch_identity > 0.5);
+    assert!(statistics.primary_identity > 0.5);
+}
+
+#[test]
+fn learned_node_ord_keeps_optimal_cost() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let builder = || {
+        AlignerBuilder::new(sample_config())
+            .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+    };
+
+    let expected = builder()
+        .build()
+        .unwrap()
+        .align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    for model in [
+        NodeOrdModel::default(),
+        NodeOrdModel {
+            anti_diagonal: 1.0,
+            template_switch: 5.0,
+            gap: -1.0,
+            ..Default::default()
+        },
+        NodeOrdModel {
+            anti_diagonal: -1.0,
+            flank_index: 2.0,
+            secondary_length: -3.0,
+            ..Default::default()
+        },
+    ] {
+        let learned = builder()
+            .learned_node_ord(model)
+            .build()
+            .unwrap()
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            learned.statistics().cost,
+            expected.statistics().cost,
+            "{model:?}"
+        );
+    }
+}
+
+#[test]
+fn random_restarts_flag_ambiguous_template_switches() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let align = |random_restarts| {
+        AlignerBuilder::new(sample_config())
+            .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+            .random_restarts(random_restarts)
+            .build()
+            .unwrap()
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap()
+    };
+
+    let random_restarts = RandomRestarts { count: 8, seed: 0 };
+    let restarted = align(Some(random_restarts));
+    let statistics = restarted.statistics();
+    assert_eq!(statistics.a_star_restarts, 8);
+    assert!(statistics.cooptimal_alignments > 1, "{restarted}");
+    assert_eq!(statistics.ambiguous_template_switches, [0]);
+    assert_eq!(statistics.template_switch_support.len(), 1);
+    assert!(statistics.template_switch_support[0] < statistics.cooptimal_alignments);
+
+    // Restarts do not change the reported alignment, and are reproducible.
+    assert_eq!(restarted.cigar(), align(None).cigar());
+    assert_eq!(
+        align(Some(random_restarts))
+            .statistics()
+            .template_switch_support,
+        statistics.template_switch_support
+    );
+}
+
+#[test]
+fn secondary_score_policy_chooses_cooptimal_alignment() {
+    let align = |reference: &[u8],
+                 query: &[u8],
+                 config: TemplateSwitchConfig<DnaAlphabetOrN>,
+                 secondary_score_policy| {
+        let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+        let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query).unwrap();
+        AlignerBuilder::new(config)
+            .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+            .tie_breaking(AStarTieBreaking::SecondaryScore)
+            .secondary_score_policy(secondary_score_policy)
+            .build()
+            .unwrap()
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap()
+    };
+    // Two substitutions cost as much as a deletion and an insertion around an additional match.
+    let mut config = sample_config();
+    config.base_cost = Cost::MAX;
+    config.primary_edit_costs =
+        GapAffineAlignmentCostTable::new_uniform("primary", 6.into(), 6.into(), 1.into());
+    config.left_flank_edit_costs = config.primary_edit_costs.clone();
+    config.right_flank_edit_costs = config.primary_edit_costs.clone();
+    let alignment = align(
+        b"GGTCACGG",
+        b"GGTACCGG",
+        config,
+        SecondaryScorePolicy::MaximiseMatches,
+    );
+    assert_eq!(alignment.statistics().cost, 12.0, "{alignment}");
+    assert_eq!(alignment.statistics().primary_matches, 7, "{alignment}");
+
+    // Make the template switch in the inverted middle part cost as much as aligning it without template switches.
+    let reference = REFERENCE.as_bytes();
+    let query = QUERY.as_bytes();
+    let mut config = sample_config();
+    let with_template_switch = align(
+        reference,
+        query,
+        config.clone(),
+        SecondaryScorePolicy::MinimiseTemplateSwitches,
+    );
+    config.directions = TemplateSwitchDirections::NONE;
+    let without_template_switch = align(
+        reference,
+        query,
+        config.clone(),
+        SecondaryScorePolicy::MinimiseTemplateSwitches,
+    );
+    assert!(with_template_switch.cigar().contains("[TS"));
+    let cost_difference =
+        without_template_switch.statistics().cost - with_template_switch.statistics().cost;
+    config.directions = TemplateSwitchDirections::ALL;
+    config.base_cost += Cost::from(cost_difference.raw() as u64);
+
+    let alignment = align(
+        reference,
+        query,
+        config,
+        SecondaryScorePolicy::MinimiseTemplateSwitches,
+    );
+    assert_eq!(
+        alignment.statistics().cost,
+        without_template_switch.statistics().cost
+    );
+    assert!(!alignment.cigar().contains("[TS"), "{alignment}");
+}
+
+#[test]
+fn cost_segments_sum_to_cost() {
+    let result = align(REFERENCE.as_bytes(), QUERY.as_bytes(), sample_config());
+
+    let statistics = result.statistics();
+    let segments = &statistics.cost_segments;
+    assert_eq!(
+        segments.iter().map(|segment| segment.cost).sum::<R64>(),
+        statistics.cost,
+        "{result}"
+    );
+    let kinds: Vec<_> = segments
+        .iter()
+        .filter(|segment| segment.template_switch == Some(0))
+        .map(|segment| segment.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            CostSegmentKind::TemplateSwitchEntrance,
+            CostSegmentKind::TemplateSwitch,
+            CostSegmentKind::TemplateSwitchExit,
+        ],
+        "{result}"
+    );
+    assert!(result.to_string().contains("Cost breakdown:"));
+
+    let column_costs = &statistics.column_costs;
+    assert_eq!(
+        column_costs
+            .iter()
+            .map(|(count, cost)| *cost * *count as f64)
+            .sum::<R64>(),
+        statistics.cost,
+        "{result}"
+    );
+    let AlignmentResult::WithTarget { alignment, .. } = &result else {
+        panic!("{result}");
+    };
+    assert_eq!(
+        column_costs.iter().map(|(count, _)| count).sum::<usize>(),
+        alignment.iter().map(|(count, _)| count).sum::<usize>(),
+        "{result}"
+    );
+}
+
+#[test]
+fn asymmetric_gap_costs_prefer_cheaper_direction() {
+    let long = b"ACGTTGCAACTCAGGATCCGTAACGTTC";
+    let short = b"ACGTTGCAACTCAGATCCGTAACGTTC";
+
+    let symmetric = TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset_name("default").unwrap();
+    let mut asymmetric = symmetric.clone();
+    asymmetric.primary_edit_costs = asymmetric
+        .primary_edit_costs
+        .with_deletion_gap_cost_vectors(
+            vec![1.into(); DnaAlphabetOrN::SIZE.into()],
+            vec![1.into(); DnaAlphabetOrN::SIZE.into()],
+        );
+
+    // Deleting a character from the reference got cheaper, but inserting one into the query did not.
+    let deletion = align(long, short, asymmetric.clone());
+    let insertion = align(short, long, asymmetric);
+    let symmetric_insertion = align(short, long, symmetric);
+    assert_eq!(deletion.statistics().cost, r64(1.0), "{deletion}");
+    assert_eq!(
+        insertion.statistics().cost,
+        symmetric_insertion.statistics().cost,
+        "{insertion}"
+    );
+    assert!(insertion.statistics().cost > deletion.statistics().cost);
+}
+
+#[test]
+fn context_substitution_costs_depend_on_previous_reference_character() {
+    let align =
+        |reference: &[u8], query: &[u8], config| align(reference, query, config).statistics().cost;
+
+    // Transitions of `G` after `C` are cheap, like in methylated `CpG` sites.
+    let config = TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset_name("default").unwrap();
+    let character = |ascii| DnaAlphabetOrN::ascii_to_character(ascii).unwrap();
+    let primary_edit_costs = &config.primary_edit_costs;
+    let mut cpg_table: Vec<_> = DnaAlphabetOrN::iter()
+        .flat_map(|c1| {
+            DnaAlphabetOrN::iter()
+                .map(move |c2| primary_edit_costs.match_or_substitution_cost(c1, c2))
+        })
+        .collect();
+    cpg_table[usize::from(character(b'G').index()) * usize::from(DnaAlphabetOrN::SIZE)
+        + usize::from(character(b'A').index())] = 1.into();
+    let mut cpg_config = config.clone();
+    cpg_config.primary_edit_costs = cpg_config
+        .primary_edit_costs
+        .with_context_substitution_cost_table(character(b'C'), cpg_table);
+
+    let cpg_reference = b"ACGTTGCAACTCAGGATCCGTAACGTTC";
+    let cpg_query = b"ACGTTGCAACTCAGGATCCATAACGTTC";
+    assert_eq!(
+        align(cpg_reference, cpg_query, cpg_config.clone()),
+        r64(1.0)
+    );
+    assert!(align(cpg_reference, cpg_query, config.clone()) > r64(1.0));
+
+    let other_reference = b"ACGTTGCAACTCAGGATTTGTAACGTTC";
+    let other_query = b"ACGTTGCAACTCAGGATTTATAACGTTC";
+    assert_eq!(
+        align(other_reference, other_query, cpg_config),
+        align(other_reference, other_query, config)
+    );
+}
+
+#[test]
+fn ts_end_exclusion_forbids_template_switches_near_ends() {
+    let align = |ts_end_exclusion| {
+        let mut config = sample_config();
+        config.ts_end_exclusion = ts_end_exclusion;
+        align(REFERENCE.as_bytes(), QUERY.as_bytes(), config)
+    };
+    let template_switch_count = |result: &AlignmentResult<AlignmentType>| {
+        let AlignmentResult::WithTarget { alignment, .. } = result else {
+            panic!("{result}");
+        };
+        alignment
+            .iter()
+            .filter(|(_, alignment_type)| {
+                matches!(alignment_type, AlignmentType::TemplateSwitchEntrance { .. })
+            })
+            .count()
+    };
+
+    let unrestricted = align(0);
+    assert_eq!(template_switch_count(&unrestricted), 1, "{unrestricted}");
+    let small_exclusion = align(2);
+    assert_eq!(
+        small_exclusion.statistics().cost,
+        unrestricted.statistics().cost,
+        "{small_exclusion}"
+    );
+
+    // No template switch fits between the exclusion zones.
+    let restricted = align(20);
+    assert_eq!(template_switch_count(&restricted), 0, "{restricted}");
+    assert!(restricted.statistics().cost > unrestricted.statistics().cost);
+}
+
+#[test]
+fn forward_template_switches_align_direct_repeats() {
+    // The query copies the forward strand of the twelve reference characters before the replaced region.
+    let reference = b"ACGTTGCAACTGCAGGATCCGTATGTTACCAAGTGCAACCATGCATTA";
+    let query = b"ACGTTGCAACTGCAGGATCCGTATCAGGATCCGTATAACCATGCATTA";
+    let align = |forward_base_cost: Option<Cost>| {
+        let mut config = sample_config();
+        config.forward_base_cost = forward_base_cost;
+        align(reference, query, config)
+    };
+    let strands = |result: &AlignmentResult<AlignmentType>| {
+        let AlignmentResult::WithTarget { alignment, .. } = result else {
+            panic!("{result}");
+        };
+        alignment
+            .iter()
+            .filter_map(|(_, alignment_type)| match alignment_type {
+                AlignmentType::TemplateSwitchEntrance { strand, .. } => Some(*strand),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let reverse_complement_only = align(None);
+    assert!(
+        !strands(&reverse_complement_only).contains(&TemplateSwitchStrand::Forward),
+        "{reverse_complement_only}"
+    );
+
+    let with_forward = align(Some(2u64.into()));
+    assert_eq!(
+        strands(&with_forward),
+        [TemplateSwitchStrand::Forward],
+        "{with_forward}"
+    );
+    assert!(with_forward.statistics().cost < reverse_complement_only.statistics().cost);
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query).unwrap();
+    let donors = with_forward.template_switch_donors(
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+    );
+    assert_eq!(donors.len(), 1);
+    assert_eq!(donors[0].aligned_sequence, donors[0].sequence);
+}
+
+#[test]
+fn aligner_builder_limits_template_switch_count() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let config = sample_config();
+
+    let unlimited = AlignerBuilder::new(config.clone())
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap()
+        .align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    assert!(unlimited.cigar().contains("[TS"), "{unlimited}");
+
+    let limited = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .max_template_switch_count(0)
+        .build()
+        .unwrap()
+        .align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    assert!(!limited.cigar().contains("[TS"), "{limited}");
+    assert!(limited.statistics().cost > unlimited.statistics().cost);
+}
+
+#[test]
+fn guided_alignment_keeps_template_switches() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let config = sample_config();
+    let aligner = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap();
+    let align = |guide| {
+        aligner
+            .align_guided(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+                guide,
+            )
+            .unwrap()
+    };
+    let anchor = |reference_start, query_start, length| GuideAnchor {
+        reference_start,
+        query_start,
+        length,
+    };
+
+    let unguided = align(None);
+    // The mapper found the flanks, and the template switch between them lies on the main diagonal of the guide.
+    let guided = align(Some(AlignmentGuide::new(
+        [anchor(0, 0, 10), anchor(30, 30, 10)],
+        40,
+        40,
+        0,
+    )));
+    assert!(guided.cigar().contains("[TS"), "{guided}");
+    assert_eq!(guided.statistics().cost, unguided.statistics().cost);
+
+    // A guide away from the main diagonal forbids the optimal alignment.
+    let misguided = align(Some(AlignmentGuide::new([anchor(0, 10, 20)], 40, 40, 0)));
+    assert!(
+        misguided.statistics().cost > unguided.statistics().cost,
+        "{misguided}"
+    );
+}
+
+#[test]
+fn self_alignment_finds_inverted_repeat() {
+    // An inverted repeat `GGATCCGTAAC ... GTTACGGATCC` with a short loop between the arms.
+    let sequence = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"ACGTTGCAACTCAGGATCCGTAACTTTGTTACGGATCCTGAGAACCATGCA",
+    )
+    .unwrap();
+    let config = sample_config();
+
+    let trivial = AlignerBuilder::new(config.clone())
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap()
+        .align(
+            sequence.as_genome_subsequence(),
+            sequence.as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    assert!(!trivial.cigar().contains("[TS"), "{trivial}");
+    assert_eq!(trivial.statistics().cost, 0.0);
+
+    let self_alignment = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .self_alignment(true)
+        .build()
+        .unwrap()
+        .align(
+            sequence.as_genome_subsequence(),
+            sequence.as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    assert!(self_alignment.cigar().contains("[TS"), "{self_alignment}");
+    assert!(self_alignment.statistics().cost > 0.0);
+}
+
+#[test]
+fn chaining_lower_bound_passes_admissibility_check() {
+    let config = sample_config();
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+
+    let result = AlignerBuilder::new(config)
+        .chaining::<RuntimeChainingStrategy>(RuntimeChainingSelection::LowerBound)
+        .check_admissibility(true)
+        .build()
+        .unwrap()
+        .align(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            None,
+        );
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+}
+
+#[test]
+fn chaining_reports_chain_statistics() {
+    let config = sample_config();
+    let align = |reference: &[u8], query: &[u8]| {
+        let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+        let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query).unwrap();
+        AlignerBuilder::new(config.clone())
+            .chaining::<PrecomputeOnlyChainingStrategy>(())
+            .build()
+            .unwrap()
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap()
+    };
+
+    let identical = align(REFERENCE.as_bytes(), REFERENCE.as_bytes());
+    let chain = identical.statistics().chain.clone().unwrap();
+    assert!(chain.anchors > 0);
+    assert_eq!(chain.cost, 0.0);
+    assert_eq!(chain.transitions.len() as u64, chain.anchors + 1);
+    assert_eq!(chain.non_match_transitions(), 0);
+    assert_eq!(chain.chain_anchors.len() as u64, chain.anchors);
+    for window in chain.chain_anchors.windows(2) {
+        assert!(window[0].reference_end <= window[1].reference_start);
+        assert!(window[0].query_end <= window[1].query_start);
+    }
+
+    let different = align(REFERENCE.as_bytes(), QUERY.as_bytes());
+    let statistics = different.statistics();
+    let chain = statistics.chain.clone().unwrap();
+    assert!(chain.cost <= statistics.cost, "{different}");
+    assert!(chain.is_consistent_with(statistics.cost), "{different}");
+    assert_eq!(
+        chain
+            .transitions
+            .iter()
+            .map(|transition| transition.cost)
+            .sum::<R64>(),
+        chain.cost,
+        "{different}"
+    );
+    assert_eq!(
+        chain.non_match_transitions() > 0,
+        chain.cost > 0.0,
+        "{different}"
+    );
+    for coverage in [chain.reference_coverage, chain.query_coverage] {
+        assert!((0.0..=1.0).contains(&coverage.raw()), "{different}");
+    }
+}
+
+#[test]
+fn machine_statistics_are_raw() {
+    let mut statistics = AlignmentStatistics::zero();
+    statistics.opened_nodes = 12;
+    statistics.cost_per_base = r64(0.123456);
+    let machine = StatisticsFormat {
+        precision: 3,
+        mode: StatisticsMode::Machine,
+    };
+
+    let output = statistics.display(machine).to_string();
+    assert!(output.lines().any(|line| line == "opened_nodes = 12"));
+    assert!(output.lines().any(|line| line == "cost_per_base = 0.123"));
+    assert!(!output.contains("MiB"));
+
+    let human = statistics.display(StatisticsFormat::default()).to_string();
+    assert!(human.contains("Opened nodes: 12\n"));
+    assert!(human.contains("Cost per base: 0.12\n"));
+}
+
+#[test]
+fn averaged_counts_are_rounded() {
+    let mut first = AlignmentStatistics::zero();
+    first.opened_nodes = 3;
+    let mut second = AlignmentStatistics::zero();
+    second.opened_nodes = 4;
+
+    let average = first.piecewise_add(&second).piecewise_div(r64(2.0));
+    assert_eq!(average.opened_nodes, 4);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn float_counts_deserialize() {
+    let mut statistics = AlignmentStatistics::zero();
+    statistics.closed_nodes = 5;
+    let serialized = toml::to_string(&statistics).unwrap();
+    assert!(serialized.contains("closed_nodes = 5\n"));
+
+    let legacy = serialized.replace("closed_nodes = 5\n", "closed_nodes = 5.0\n");
+    let deserialized: AlignmentStatistics = toml::from_str(&legacy).unwrap();
+    assert_eq!(deserialized.closed_nodes, 5);
+
+    let negative = serialized.replace("closed_nodes = 5\n", "closed_nodes = -1.0\n");
+    assert!(toml::from_str::<AlignmentStatistics>(&negative).is_err());
+}
+
+/// An alignment result that contains every alignment type and every optional statistic.
+#[cfg(feature = "serde")]
+fn exhaustive_alignment_result() -> AlignmentResult<AlignmentType> {
+    use generic_a_star::histogram::{AStarSearchSnapshot, Histogram};
+
+    use crate::{
+        a_star_aligner::{
+            alignment_result::{ChainAnchorStatistics, ChainStatistics, ChainTransitionStatistics},
+            template_switch_distance::{
+                TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+            },
+        },
+        provenance::{ResultProvenance, SequenceProvenance},
+    };
+
+    let alignment = vec![
+        (1, AlignmentType::Root),
+        (2, AlignmentType::PrimaryMatch),
+        (1, AlignmentType::PrimaryMatchRun { length: 7 }),
+        (1, AlignmentType::PrimarySubstitution),
+        (3, AlignmentType::PrimaryInsertion),
+        (2, AlignmentType::PrimaryDeletion),
+        (1, AlignmentType::PrimaryFlankMatch),
+        (1, AlignmentType::PrimaryFlankSubstitution),
+        (1, AlignmentType::PrimaryFlankInsertion),
+        (1, AlignmentType::PrimaryFlankDeletion),
+        (
+            1,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset: -12,
+            },
+        ),
+        (1, AlignmentType::SecondaryRoot),
+        (4, AlignmentType::SecondaryMatch),
+        (1, AlignmentType::SecondaryMatchRun { length: 3 }),
+        (1, AlignmentType::SecondarySubstitution),
+        (1, AlignmentType::SecondaryInsertion),
+        (1, AlignmentType::SecondaryDeletion),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: -2,
+            },
+        ),
+        (1, AlignmentType::PrimaryReentry),
+        (
+            1,
+            AlignmentType::PrimaryShortcut {
+                delta_reference: -3,
+                delta_query: 4,
+            },
+        ),
+        (1, AlignmentType::PrimaryDuplication { length: 5 }),
+        (1, AlignmentType::InversionEntrance { length: 6 }),
+        (5, AlignmentType::InversionMatch),
+        (1, AlignmentType::InversionSubstitution),
+        (1, AlignmentType::InversionExit),
+    ];
+
+    let mut statistics = AlignmentStatistics::zero();
+    statistics.cost = r64(12.5);
+    statistics.cost_per_base = r64(0.125);
+    statistics.opened_nodes = u64::from(u32::MAX) + 1;
+    statistics.template_switch_identity = r64(1.0 / 3.0);
+    statistics.search_snapshots = vec![AStarSearchSnapshot {
+        closed_nodes: 10,
+        open_f_values: Histogram::from_values([3, 3, 5]),
+        closed_depths: Histogram::from_values([0, 1, 2]),
+    }];
+    statistics.ambiguous_template_switches = vec![0];
+    statistics.template_switch_support = vec![1];
+    statistics.cost_segments = vec![CostSegment {
+        kind: CostSegmentKind::TemplateSwitchEntrance,
+        template_switch: Some(0),
+        steps: 1,
+        cost: r64(10.0),
+    }];
+    statistics.column_costs = vec![(3, r64(0.0)), (1, r64(2.5))];
+    statistics.chain = Some(ChainStatistics {
+        anchors: 1,
+        reference_coverage: r64(0.5),
+        query_coverage: r64(0.25),
+        cost: r64(2.0),
+        gaps: 1,
+        template_switch_anchors: 1,
+        transitions: vec![ChainTransitionStatistics {
+            reference_start: 0,
+            query_start: 0,
+            reference_gap: 0,
+            query_gap: 0,
+            cost: r64(0.0),
+            direct: true,
+            demands_non_match: false,
+        }],
+        chain_anchors: vec![ChainAnchorStatistics {
+            reference_start: 0,
+            reference_end: 20,
+            query_start: 0,
+            query_end: 20,
+            cost: r64(0.0),
+            cost_to_target: r64(2.0),
+        }],
+    });
+    let sequence = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACGT").unwrap();
+    let mut reference = SequenceProvenance::new(
+        Some("reference \"quoted\".fa".to_string()),
+        "reference".to_string(),
+        0,
+        sequence.as_genome_subsequence(),
+        true,
+    );
+    reference.range = Some((2, 6));
+    reference.reverse_complement = true;
+    statistics.provenance = Some(ResultProvenance {
+        reference,
+        query: SequenceProvenance::new(
+            None,
+            "query".to_string(),
+            1,
+            sequence.as_genome_subsequence(),
+            false,
+        ),
+        config_sha256: Some("0123abcd".to_string()),
+    });
+
+    AlignmentResult::WithTarget {
+        alignment,
+        statistics,
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn alignment_results_round_trip() {
+    let mut results = vec![exhaustive_alignment_result()];
+    for reason in [
+        NoTargetReason::CostLimit,
+        NoTargetReason::ExceededTimeLimit,
+        NoTargetReason::Cancelled,
+    ] {
+        let mut statistics = AlignmentStatistics::zero();
+        statistics.cost = r64(100.0);
+        results.push(AlignmentResult::WithoutTarget { reason, statistics });
+    }
+
+    for result in results {
+        let toml = toml::to_string(&result).unwrap();
+        assert_eq!(
+            toml::from_str::<AlignmentResult<AlignmentType>>(&toml).unwrap(),
+            result,
+            "{toml}"
+        );
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AlignmentResult<AlignmentType>>(&json).unwrap(),
+            result,
+            "{json}"
+        );
+    }
+
+    let gap_affine = AlignmentResult::WithTarget {
+        alignment: vec![
+            (1, gap_affine_edit_distance::AlignmentType::Root),
+            (2, gap_affine_edit_distance::AlignmentType::Match),
+            (1, gap_affine_edit_distance::AlignmentType::Substitution),
+            (1, gap_affine_edit_distance::AlignmentType::Insertion),
+            (1, gap_affine_edit_distance::AlignmentType::Deletion),
+        ],
+        statistics: AlignmentStatistics::zero(),
+    };
+    let toml = toml::to_string(&gap_affine).unwrap();
+    assert_eq!(
+        toml::from_str::<AlignmentResult<_>>(&toml).unwrap(),
+        gap_affine
+    );
+    let json = serde_json::to_string(&gap_affine).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AlignmentResult<_>>(&json).unwrap(),
+        gap_affine
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn alignment_results_record_format_version() {
+    let result = exhaustive_alignment_result();
+    let toml = toml::to_string(&result).unwrap();
+    assert!(toml.contains(&format!("format_version = {STATISTICS_FORMAT_VERSION}\n")));
+    assert!(result.statistics().is_format_supported());
+
+    // Results written before the format was versioned have format version zero.
+    let legacy = toml.replace(
+        &format!("format_version = {STATISTICS_FORMAT_VERSION}\n"),
+        "",
+    );
+    let legacy = toml::from_str::<AlignmentResult<AlignmentType>>(&legacy).unwrap();
+    assert_eq!(legacy.statistics().format_version, 0);
+    assert!(legacy.statistics().is_format_supported());
+
+    let newer = toml.replace(
+        &format!("format_version = {STATISTICS_FORMAT_VERSION}\n"),
+        &format!("format_version = {}\n", STATISTICS_FORMAT_VERSION + 1),
+    );
+    let newer = toml::from_str::<AlignmentResult<AlignmentType>>(&newer).unwrap();
+    assert!(!newer.statistics().is_format_supported());
+}
+
+#[test]
+fn packed_sequences_align_like_vector_sequences() {
+    let reference = REFERENCE.as_bytes();
+    let query = QUERY.as_bytes();
+    let config = sample_config();
+    let aligner = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap();
+
+    let vector = aligner
+        .align(
+            VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference)
+                .unwrap()
+                .as_genome_subsequence(),
+            VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query)
+                .unwrap()
+                .as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    let packed = aligner
+        .align(
+            BitVectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference)
+                .unwrap()
+                .as_genome_subsequence(),
+            BitVectorGenome::<DnaAlphabetOrN>::from_slice_u8(query)
+                .unwrap()
+                .as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(packed.cigar(), vector.cigar());
+    assert_eq!(packed.statistics().cost, vector.statistics().cost);
+}
+
+#[test]
+fn time_limit_returns_lower_bound_without_target() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let config = sample_config();
+    let align = |time_limit| {
+        AlignerBuilder::new(config.clone())
+            .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+            .time_limit(time_limit)
+            .build()
+            .unwrap()
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap()
+    };
+
+    let optimal = align(None);
+    assert!(matches!(optimal, AlignmentResult::WithTarget { .. }));
+    assert_eq!(optimal.no_target_reason(), None);
+    let aborted = align(Some(Duration::ZERO));
+    assert!(matches!(aborted, AlignmentResult::WithoutTarget { .. }));
+    assert_eq!(
+        aborted.no_target_reason(),
+        Some(NoTargetReason::ExceededTimeLimit)
+    );
+    assert!(aborted.statistics().cost <= optimal.statistics().cost);
+}
+
+#[test]
+fn cancelled_alignment_has_no_target() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACGTTGCAACTCAGGATCCG").unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACGTTGCAACCTGAGGATCCG").unwrap();
+    let config = sample_config();
+    let cancellation_token = CancellationToken::new();
+    let aligner = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .cancellation_token(Some(cancellation_token.clone()))
+        .build()
+        .unwrap();
+    let align = || {
+        aligner
+            .align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+            )
+            .unwrap()
+    };
+
+    assert_eq!(align().no_target_reason(), None);
+    cancellation_token.cancel();
+    assert_eq!(align().no_target_reason(), Some(NoTargetReason::Cancelled));
+}
+
+#[test]
+fn align_str_aligns_text() {
+    let config = sample_config();
+    let aligner = AlignerBuilder::new(config).build().unwrap();
+
+    let reference = "ACGTTGCAACTCAGGATCCG";
+    let query = "ACGTTGCAACCTGAGGATCCG";
+    let alignment = aligner.align_str(reference, query).unwrap();
+    let expected = aligner
+        .align(
+            VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference.as_bytes())
+                .unwrap()
+                .as_genome_subsequence(),
+            VectorGenome::from_slice_u8(query.as_bytes())
+                .unwrap()
+                .as_genome_subsequence(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(alignment.cigar(), expected.cigar());
+    assert_eq!(alignment.statistics().cost, expected.statistics().cost);
+
+    assert!(matches!(
+        aligner.align_str(reference, "ACGU"),
+        Err(Error::Alphabet(_))
+    ));
+}
+
+#[test]
+fn alignment_errors_have_context() {
+    let config = sample_config();
+    let config_sha256 = config.sha256();
+    let aligner = AlignerBuilder::new(config)
+        .chaining::<RuntimeChainingStrategy>(RuntimeChainingSelection::LowerBound)
+        .build()
+        .unwrap();
+
+    // Chaining needs a reference of at least one block.
+    let result = aligner.align_str("ACGTTGCA", "ACGTTGCA");
+    let Err(Error::Alignment {
+        reference_range,
+        query_range,
+        block_size,
+        config_sha256: error_config_sha256,
+        source,
+    }) = result
+    else {
+        panic!("{result:?}");
+    };
+    assert_eq!((reference_range, query_range), (0..8, 0..8));
+    assert_eq!(block_size, 20);
+    assert_eq!(error_config_sha256, config_sha256);
+    assert!(
+        matches!(
+            *source,
+            Error::Chaining(seed_chain::error::Error::ReferenceShorterThanBlockSize { .. })
+        ),
+        "{source:?}"
+    );
+
+    let Err(Error::Alignment {
+        reference_range,
+        query_range,
+        ..
+    }) = aligner
+        .align_str("ACGTTGCA", "ACGTTGCA")
+        .map_err(|error| error.offset_alignment_ranges(100, 3))
+    else {
+        unreachable!("The same alignment failed above");
+    };
+    assert_eq!((reference_range, query_range), (100..108, 3..11));
+}
+
+#[test]
+fn streamed_segments_concatenate_to_the_alignment() {
+    let config = sample_config();
+    // With a single substitution, no alternatives stay below the cost of the alignment,
+    // so the part before the substitution is fixed before the search terminates.
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"GATTACAGCCTAGGCATTCGAGTCCATGACTTGCAGCTAGGCTAACGTCAGTCGGATCGTA",
+    )
+    .unwrap();
+    let query = VectorGenome::from_slice_u8(
+        b"GATTACAGCCTAGGCATTCGAGTCCATGACTTGCAGCTAGGCTAACGACAGTCGGATCGTA",
+    )
+    .unwrap();
+
+    for config in [without_jumps(&config), config] {
+        let aligner = AlignerBuilder::new(config).build().unwrap();
+        let mut segments = Vec::new();
+        let alignment = aligner
+            .align_guided_streaming(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                None,
+                None,
+                |segment| segments.push(segment.to_vec()),
+            )
+            .unwrap();
+        assert!(segments.len() > 1);
+
+        let mut streamed_alignment: Vec<(usize, _)> = Vec::new();
+        for (count, alignment_type) in segments.into_iter().flatten() {
+            match streamed_alignment.last_mut() {
+                Some((previous_count, previous_alignment_type))
+                    if alignment_type.is_repeated(previous_alignment_type) =>
+                {
+                    *previous_count += count
+                }
+                _ => streamed_alignment.push((count, alignment_type)),
+            }
+        }
+        let AlignmentResult::WithTarget {
+            alignment: expected,
+            ..
+        } = &alignment
+        else {
+            panic!("No alignment found");
+        };
+        assert_eq!(&streamed_alignment, expected);
+    }
 }
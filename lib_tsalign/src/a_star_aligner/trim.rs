@@ -0,0 +1,262 @@
+use std::ops::Range;
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use noisy_float::types::r64;
+
+use crate::{
+    config::TemplateSwitchConfig,
+    costs::cost::Cost,
+    soft_mask::{SoftMask, SoftMasks},
+};
+
+use super::{
+    alignment_result::{AlignmentResult, CostSegment, CostSegmentKind},
+    push_column_cost,
+    template_switch_distance::AlignmentType,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The lengths of the identical prefix and suffix of a reference and a query that are removed before aligning.
+///
+/// Pairs that differ only in the middle can be aligned much faster by aligning only the middle,
+/// and re-attaching the trimmed ends as primary matches afterwards.
+/// This is a heuristic, since template switches in the middle cannot use the trimmed ends as secondary.
+/// To make this unlikely, a margin of identical characters is kept on both sides of the middle.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TrimmedEnds {
+    pub prefix: usize,
+    pub suffix: usize,
+}
+
+impl TrimmedEnds {
+    /// Finds the identical prefix and suffix of `reference` and `query`, keeping `margin` identical characters next to the middle.
+    ///
+    /// The prefix is chosen first, and the suffix does not overlap with it.
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        margin: usize,
+    ) -> Self {
+        let prefix = reference
+            .iter()
+            .zip(query.iter())
+            .take_while(|(r, q)| r == q)
+            .count();
+        let suffix = reference
+            .iter()
+            .rev()
+            .zip(query.iter().rev())
+            .take(reference.len().min(query.len()) - prefix)
+            .take_while(|(r, q)| r == q)
+            .count();
+
+        Self {
+            prefix: prefix.saturating_sub(margin),
+            suffix: suffix.saturating_sub(margin),
+        }
+    }
+
+    /// Returns a margin that is large enough for template switches that overlap the middle to reach all their secondaries,
+    /// assuming that all their primary characters lie within the margin.
+    /// The margin also covers the source of tandem duplications and inversions.
+    ///
+    /// Returns `None` if the offset, length or length difference of template switches or the length of jump events is unbounded.
+    pub fn margin<AlphabetType>(config: &TemplateSwitchConfig<AlphabetType>) -> Option<usize> {
+        let offset = config
+            .offset_costs
+            .minimum_finite_input()?
+            .unsigned_abs()
+            .max(config.offset_costs.maximum_finite_input()?.unsigned_abs());
+        let length = config.length_costs.maximum_finite_input()?;
+        let length_difference = config
+            .length_difference_costs
+            .minimum_finite_input()?
+            .unsigned_abs()
+            .max(
+                config
+                    .length_difference_costs
+                    .maximum_finite_input()?
+                    .unsigned_abs(),
+            );
+        let flanks =
+            config.left_flank_length.unsigned_abs() + config.right_flank_length.unsigned_abs();
+        let mut jump_event_length = 0;
+        for costs in [&config.duplication_costs, &config.inversion_costs]
+            .into_iter()
+            .flatten()
+        {
+            jump_event_length = jump_event_length.max(costs.maximum_finite_input()?);
+        }
+
+        Some(offset + length + length_difference + flanks + jump_event_length)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefix == 0 && self.suffix == 0
+    }
+
+    /// Returns the range of the middle of a sequence of the given length.
+    pub fn middle(&self, length: usize) -> Range<usize> {
+        self.prefix..length - self.suffix
+    }
+
+    /// Removes the trimmed ends from `sequence`.
+    pub fn trim<
+        'sequence,
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        sequence: &'sequence SubsequenceType,
+    ) -> &'sequence SubsequenceType {
+        &sequence[self.middle(sequence.len())]
+    }
+
+    /// Removes the trimmed ends from soft masks, such that they match the trimmed sequences.
+    pub fn trim_soft_masks(&self, soft_masks: &SoftMasks) -> SoftMasks {
+        SoftMasks {
+            reference: self.trim_soft_mask(&soft_masks.reference),
+            query: self.trim_soft_mask(&soft_masks.query),
+            handling: soft_masks.handling,
+        }
+    }
+
+    fn trim_soft_mask(&self, soft_mask: &SoftMask) -> SoftMask {
+        let middle = self.middle(soft_mask.len().max(self.prefix + self.suffix));
+        SoftMask::new(middle.map(|index| soft_mask.is_masked(index)).collect())
+    }
+
+    /// Re-attaches the trimmed ends to an alignment of the trimmed sequences.
+    ///
+    /// `reference` and `query` are the untrimmed sequences.
+    /// The trimmed ends are attached as primary matches, and their match costs are added to the cost of the alignment.
+    pub fn reattach<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        mut result: AlignmentResult<AlignmentType>,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        config: &TemplateSwitchConfig<AlphabetType>,
+    ) -> AlignmentResult<AlignmentType> {
+        if self.is_empty() {
+            return result;
+        }
+
+        if let AlignmentResult::WithTarget { alignment, .. } = &mut result {
+            if self.prefix > 0 {
+                if let Some((count, AlignmentType::PrimaryMatch)) = alignment.first_mut() {
+                    *count += self.prefix;
+                } else {
+                    alignment.insert(0, (self.prefix, AlignmentType::PrimaryMatch));
+                }
+            }
+            if self.suffix > 0 {
+                if let Some((count, AlignmentType::PrimaryMatch)) = alignment.last_mut() {
+                    *count += self.suffix;
+                } else {
+                    alignment.push((self.suffix, AlignmentType::PrimaryMatch));
+                }
+            }
+        }
+
+        let match_costs = |indices: Range<usize>| {
+            indices
+                .map(|index| {
+                    let character = reference[index].clone();
+                    let previous = index
+                        .checked_sub(1)
+                        .map(|previous_index| reference[previous_index].clone());
+                    config
+                        .primary_edit_costs
+                        .context_match_or_substitution_cost(previous, character.clone(), character)
+                })
+                .collect::<Vec<_>>()
+        };
+        let total_cost = |costs: &[Cost]| {
+            costs.iter().fold(Cost::ZERO, |sum, cost| {
+                sum.as_u64().saturating_add(cost.as_u64()).into()
+            })
+        };
+        let prefix_costs = match_costs(0..self.prefix);
+        let suffix_costs = match_costs(reference.len() - self.suffix..reference.len());
+        let prefix_cost = total_cost(&prefix_costs);
+        let suffix_cost = total_cost(&suffix_costs);
+        let trimmed_cost: Cost = prefix_cost
+            .as_u64()
+            .saturating_add(suffix_cost.as_u64())
+            .into();
+
+        let statistics = result.statistics_mut();
+        statistics.cost += r64(trimmed_cost.as_u64() as f64);
+        // Aligning empty sequences has no cost, so avoid dividing by zero.
+        statistics.cost_per_base =
+            statistics.cost * 2.0 / (reference.len() + query.len()).max(1) as f64;
+        if !statistics.cost_segments.is_empty() {
+            extend_cost_segments(
+                &mut statistics.cost_segments,
+                true,
+                self.prefix,
+                prefix_cost,
+            );
+            extend_cost_segments(
+                &mut statistics.cost_segments,
+                false,
+                self.suffix,
+                suffix_cost,
+            );
+        }
+        if !statistics.column_costs.is_empty() {
+            let mut column_costs = Vec::new();
+            for cost in prefix_costs {
+                push_column_cost(&mut column_costs, 1, r64(cost.as_u64() as f64));
+            }
+            for (count, cost) in statistics.column_costs.drain(..) {
+                push_column_cost(&mut column_costs, count, cost);
+            }
+            for cost in suffix_costs {
+                push_column_cost(&mut column_costs, 1, r64(cost.as_u64() as f64));
+            }
+            statistics.column_costs = column_costs;
+        }
+        result.update_identity_statistics();
+
+        result
+    }
+}
+
+/// Adds trimmed matches to the primary cost segment at the start or the end of the alignment.
+fn extend_cost_segments(segments: &mut Vec<CostSegment>, at_start: bool, steps: usize, cost: Cost) {
+    if steps == 0 {
+        return;
+    }
+
+    let cost = r64(cost.as_u64() as f64);
+    let segment = if at_start {
+        segments.first_mut()
+    } else {
+        segments.last_mut()
+    };
+    if let Some(segment) = segment.filter(|segment| segment.kind == CostSegmentKind::Primary) {
+        segment.steps += steps;
+        segment.cost += cost;
+    } else {
+        let segment = CostSegment {
+            kind: CostSegmentKind::Primary,
+            template_switch: None,
+            steps,
+            cost,
+        };
+        if at_start {
+            segments.insert(0, segment);
+        } else {
+            segments.push(segment);
+        }
+    }
+}
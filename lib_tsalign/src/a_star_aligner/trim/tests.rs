@@ -0,0 +1,145 @@
+use compact_genome::{
+    implementation::{
+        alphabets::dna_alphabet_or_n::DnaAlphabetOrN,
+        vec_sequence::{SliceSubGenome, VectorGenome},
+    },
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult, template_switch_distance::AlignmentType,
+        template_switch_distance_a_star_align, SearchSettings,
+    },
+    costs::cost_function::CostFunction,
+    soft_mask::{SoftMask, SoftMaskHandling, SoftMasks},
+    test_fixtures::{sample_config, TestStrategies},
+};
+
+use super::TrimmedEnds;
+
+fn genome(sequence: &[u8]) -> VectorGenome<DnaAlphabetOrN> {
+    VectorGenome::from_slice_u8(sequence).unwrap()
+}
+
+fn trimmed_ends(reference: &[u8], query: &[u8], margin: usize) -> TrimmedEnds {
+    TrimmedEnds::new(
+        genome(reference).as_genome_subsequence(),
+        genome(query).as_genome_subsequence(),
+        margin,
+    )
+}
+
+#[test]
+fn identical_ends() {
+    assert_eq!(
+        trimmed_ends(b"AAAACGTTTT", b"AAAAGGTTTT", 1),
+        TrimmedEnds {
+            prefix: 3,
+            suffix: 4
+        }
+    );
+    assert_eq!(
+        trimmed_ends(b"AAAACGTTTT", b"AAAAGGTTTT", 10),
+        TrimmedEnds::default()
+    );
+    assert_eq!(
+        trimmed_ends(b"ACGT", b"ACGT", 1),
+        TrimmedEnds {
+            prefix: 3,
+            suffix: 0
+        }
+    );
+    assert_eq!(
+        trimmed_ends(b"ACG", b"ACGACG", 0),
+        TrimmedEnds {
+            prefix: 3,
+            suffix: 0
+        }
+    );
+    assert_eq!(
+        trimmed_ends(b"CAG", b"TTCAG", 0),
+        TrimmedEnds {
+            prefix: 0,
+            suffix: 3
+        }
+    );
+}
+
+#[test]
+fn margin_from_config() {
+    // Offsets up to 100, lengths up to 99, length differences up to 10 and flanks of 5 on both sides.
+    assert_eq!(TrimmedEnds::margin(&sample_config()), Some(219));
+
+    let mut config = sample_config();
+    config.length_costs = CostFunction::new_max();
+    assert_eq!(TrimmedEnds::margin(&config), None);
+}
+
+#[test]
+fn trim_soft_masks() {
+    let trimmed_ends = TrimmedEnds {
+        prefix: 2,
+        suffix: 1,
+    };
+    let soft_masks = trimmed_ends.trim_soft_masks(&SoftMasks {
+        reference: SoftMask::from_ascii(b"acGTa"),
+        query: SoftMask::from_ascii(b"ACgta"),
+        handling: SoftMaskHandling::ForbidTemplateSwitches,
+    });
+
+    assert_eq!(soft_masks.reference, SoftMask::from_ascii(b"GT"));
+    assert_eq!(soft_masks.query, SoftMask::from_ascii(b"gt"));
+}
+
+#[test]
+fn reattach_trimmed_ends() {
+    let reference = b"ACCGTTAGCATGCAGTTCAAGCTAGGATCCATGACTGCA";
+    let query = b"ACCGTTAGCATGCAGTTCATGCTAGGATCCATGACTGCA";
+    let config = sample_config();
+    let align = |reference: &SliceSubGenome<DnaAlphabetOrN>,
+                 query: &SliceSubGenome<DnaAlphabetOrN>| {
+        template_switch_distance_a_star_align::<TestStrategies, _>(
+            reference,
+            query,
+            config.clone(),
+            (),
+            None,
+            &SearchSettings::default(),
+        )
+        .unwrap()
+    };
+
+    let trimmed_ends = trimmed_ends(reference, query, 5);
+    assert_eq!(
+        trimmed_ends,
+        TrimmedEnds {
+            prefix: 14,
+            suffix: 14
+        }
+    );
+
+    let reference = genome(reference);
+    let query = genome(query);
+    let reference = reference.as_genome_subsequence();
+    let query = query.as_genome_subsequence();
+    let untrimmed = align(reference, query);
+    let trimmed = trimmed_ends.reattach(
+        align(trimmed_ends.trim(reference), trimmed_ends.trim(query)),
+        reference,
+        query,
+        &config,
+    );
+
+    assert_eq!(trimmed.statistics().cost, untrimmed.statistics().cost);
+    assert_eq!(
+        trimmed.statistics().cost_per_base,
+        untrimmed.statistics().cost_per_base
+    );
+    let AlignmentResult::WithTarget { alignment, .. } = &trimmed else {
+        panic!("No alignment found");
+    };
+    assert_eq!(alignment.first(), Some(&(19, AlignmentType::PrimaryMatch)));
+    assert_eq!(alignment.last(), Some(&(19, AlignmentType::PrimaryMatch)));
+    assert_eq!(trimmed.cigar(), "19M1S19M");
+}
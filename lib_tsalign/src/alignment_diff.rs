@@ -0,0 +1,257 @@
+//! Comparing two alignments of the same pair of sequences.
+//!
+//! Each alignment is a path through the primary alignment matrix, which jumps over template switches and inversions.
+//! The points of the matrix visited by both paths split them into stretches,
+//! and the stretches in which the paths take different columns are the divergences of the alignments.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    ops::Range,
+};
+
+use deterministic_default_hasher::DeterministicDefaultHasher;
+
+use crate::a_star_aligner::{
+    alignment_result::IAlignmentType,
+    template_switch_distance::{AlignmentType, TemplateSwitchPrimary},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// How two alignments differ within a divergence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DivergenceKind {
+    /// Both alignments contain template switches, but they are placed differently.
+    TemplateSwitchPlacement,
+    /// Only the first alignment contains a template switch.
+    TemplateSwitchOnlyInFirst,
+    /// Only the second alignment contains a template switch.
+    TemplateSwitchOnlyInSecond,
+    /// Neither alignment contains a template switch, e.g. because gaps are shifted.
+    PrimaryPath,
+}
+
+/// A stretch of the aligned sequences in which two alignments take different paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlignmentDivergence {
+    pub kind: DivergenceKind,
+    /// The reference characters between the last point shared by both alignments before and the first point after the divergence.
+    pub reference_range: Range<usize>,
+    /// The query characters between the last point shared by both alignments before and the first point after the divergence.
+    pub query_range: Range<usize>,
+    /// The run-length encoded columns of the first alignment within the divergence.
+    pub first: Vec<(usize, AlignmentType)>,
+    /// The run-length encoded columns of the second alignment within the divergence.
+    pub second: Vec<(usize, AlignmentType)>,
+}
+
+/// The columns of an alignment with one character per column, and the points of the primary matrix between them.
+struct AlignmentPath {
+    columns: Vec<AlignmentType>,
+    /// The points of the primary matrix outside of template switches and inversions,
+    /// each with the number of columns before it.
+    points: Vec<((usize, usize), usize)>,
+}
+
+impl AlignmentPath {
+    fn new(alignment: &[(usize, AlignmentType)]) -> Self {
+        let mut columns = Vec::new();
+        let mut points = vec![((0, 0), 0)];
+        let mut reference_index = 0;
+        let mut query_index = 0;
+        let mut jump_depth = 0usize;
+        // The primary, the reference and query indices at the entrance of the current template switch,
+        // and the number of primary characters inside it.
+        let mut template_switch = None;
+
+        for (alignment_type, count) in alignment
+            .iter()
+            .flat_map(|(count, alignment_type)| expand_column(alignment_type, *count))
+        {
+            for _ in 0..count {
+                match alignment_type {
+                    AlignmentType::PrimaryMatch | AlignmentType::PrimarySubstitution => {
+                        reference_index += 1;
+                        query_index += 1;
+                    }
+                    AlignmentType::PrimaryInsertion => query_index += 1,
+                    AlignmentType::PrimaryDeletion => reference_index += 1,
+                    AlignmentType::TemplateSwitchEntrance { primary, .. } => {
+                        jump_depth += 1;
+                        template_switch = Some((primary, reference_index, query_index, 0));
+                    }
+                    AlignmentType::SecondaryMatch
+                    | AlignmentType::SecondarySubstitution
+                    | AlignmentType::SecondaryInsertion => {
+                        if let Some((_, _, _, primary_length)) = &mut template_switch {
+                            *primary_length += 1;
+                        }
+                    }
+                    AlignmentType::TemplateSwitchExit { length_difference } => {
+                        jump_depth = jump_depth.saturating_sub(1);
+                        if let Some((primary, reference_entrance, query_entrance, primary_length)) =
+                            template_switch.take()
+                        {
+                            let anti_primary_length = primary_length as isize + length_difference;
+                            (reference_index, query_index) = match primary {
+                                TemplateSwitchPrimary::Reference => (
+                                    reference_entrance + primary_length,
+                                    (query_entrance as isize + anti_primary_length) as usize,
+                                ),
+                                TemplateSwitchPrimary::Query => (
+                                    (reference_entrance as isize + anti_primary_length) as usize,
+                                    query_entrance + primary_length,
+                                ),
+                            };
+                        }
+                    }
+                    AlignmentType::PrimaryDuplication { length } => reference_index -= length,
+                    AlignmentType::InversionEntrance { .. } => jump_depth += 1,
+                    AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                        reference_index += 1;
+                        query_index += 1;
+                    }
+                    AlignmentType::InversionExit => jump_depth = jump_depth.saturating_sub(1),
+                    _ => {}
+                }
+
+                columns.push(alignment_type);
+                if jump_depth == 0 {
+                    points.push(((reference_index, query_index), columns.len()));
+                }
+            }
+        }
+
+        Self { columns, points }
+    }
+}
+
+/// Splits a run-length encoded column into columns of one character, ignoring the difference between flanks and other primary columns,
+/// and ignoring roots and shortcuts.
+fn expand_column(alignment_type: &AlignmentType, count: usize) -> Option<(AlignmentType, usize)> {
+    let (alignment_type, count) = match *alignment_type {
+        AlignmentType::PrimaryMatchRun { length } => (AlignmentType::PrimaryMatch, count * length),
+        AlignmentType::SecondaryMatchRun { length } => {
+            (AlignmentType::SecondaryMatch, count * length)
+        }
+        AlignmentType::PrimaryFlankMatch => (AlignmentType::PrimaryMatch, count),
+        AlignmentType::PrimaryFlankSubstitution => (AlignmentType::PrimarySubstitution, count),
+        AlignmentType::PrimaryFlankInsertion => (AlignmentType::PrimaryInsertion, count),
+        AlignmentType::PrimaryFlankDeletion => (AlignmentType::PrimaryDeletion, count),
+        AlignmentType::Root
+        | AlignmentType::SecondaryRoot
+        | AlignmentType::PrimaryReentry
+        | AlignmentType::PrimaryShortcut { .. } => return None,
+        // Entrances and exits are single columns, even if the aligner repeats them.
+        alignment_type if !alignment_type.is_repeatable() => (alignment_type, 1),
+        alignment_type => (alignment_type, count),
+    };
+    Some((alignment_type, count))
+}
+
+/// Returns the stretches in which the two alignments of the same pair of sequences take different paths.
+///
+/// Flank columns are compared like other primary columns, so alignments that differ only in the flanks they mark agree.
+/// The divergences are ordered by their position in the first alignment.
+pub fn diff_alignments(
+    first: &[(usize, AlignmentType)],
+    second: &[(usize, AlignmentType)],
+) -> Vec<AlignmentDivergence> {
+    let first = AlignmentPath::new(first);
+    let second = AlignmentPath::new(second);
+
+    let mut second_points: HashMap<_, Vec<_>, DeterministicDefaultHasher> =
+        HashMap::with_hasher(DeterministicDefaultHasher);
+    for (index, (point, _)) in second.points.iter().enumerate() {
+        second_points.entry(*point).or_default().push(index);
+    }
+
+    // The points shared by both paths, as indices into their points, such that both indices increase.
+    let mut shared_points = Vec::new();
+    let mut last_second_index = None;
+    for (first_index, (point, _)) in first.points.iter().enumerate() {
+        let second_index = second_points.get(point).and_then(|indices| {
+            indices
+                .iter()
+                .copied()
+                .find(|index| last_second_index.is_none_or(|last| *index > last))
+        });
+        if let Some(second_index) = second_index {
+            shared_points.push((first_index, second_index));
+            last_second_index = Some(second_index);
+        }
+    }
+
+    let mut divergences = Vec::new();
+    for window in shared_points.windows(2) {
+        let [(first_start, second_start), (first_end, second_end)] = [window[0], window[1]];
+        let first_columns = &first.columns[first.points[first_start].1..first.points[first_end].1];
+        let second_columns =
+            &second.columns[second.points[second_start].1..second.points[second_end].1];
+        if first_columns == second_columns {
+            continue;
+        }
+
+        let is_template_switch = |columns: &[AlignmentType]| {
+            columns
+                .iter()
+                .any(|column| matches!(column, AlignmentType::TemplateSwitchEntrance { .. }))
+        };
+        let kind = match (
+            is_template_switch(first_columns),
+            is_template_switch(second_columns),
+        ) {
+            (true, true) => DivergenceKind::TemplateSwitchPlacement,
+            (true, false) => DivergenceKind::TemplateSwitchOnlyInFirst,
+            (false, true) => DivergenceKind::TemplateSwitchOnlyInSecond,
+            (false, false) => DivergenceKind::PrimaryPath,
+        };
+        let ((reference_start, query_start), _) = first.points[first_start];
+        let ((reference_end, query_end), _) = first.points[first_end];
+
+        divergences.push(AlignmentDivergence {
+            kind,
+            reference_range: reference_start..reference_end,
+            query_range: query_start..query_end,
+            first: run_length_encode(first_columns),
+            second: run_length_encode(second_columns),
+        });
+    }
+
+    divergences
+}
+
+/// Returns the point of the primary alignment matrix at which the alignment ends,
+/// i.e. the numbers of reference and query characters that it aligns.
+pub fn alignment_end(alignment: &[(usize, AlignmentType)]) -> (usize, usize) {
+    let (end, _) = AlignmentPath::new(alignment)
+        .points
+        .last()
+        .copied()
+        .unwrap();
+    end
+}
+
+fn run_length_encode(columns: &[AlignmentType]) -> Vec<(usize, AlignmentType)> {
+    let mut alignment: Vec<(usize, AlignmentType)> = Vec::new();
+    for column in columns {
+        match alignment.last_mut() {
+            Some((count, previous)) if column.is_repeated(previous) => *count += 1,
+            _ => alignment.push((1, *column)),
+        }
+    }
+    alignment
+}
+
+impl Display for DivergenceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TemplateSwitchPlacement => write!(f, "template-switch-placement"),
+            Self::TemplateSwitchOnlyInFirst => write!(f, "template-switch-only-in-first"),
+            Self::TemplateSwitchOnlyInSecond => write!(f, "template-switch-only-in-second"),
+            Self::PrimaryPath => write!(f, "primary-path"),
+        }
+    }
+}
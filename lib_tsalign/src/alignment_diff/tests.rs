@@ -0,0 +1,138 @@
+use crate::a_star_aligner::template_switch_distance::{
+    AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+};
+
+use super::{alignment_end, diff_alignments, AlignmentDivergence, DivergenceKind};
+
+fn template_switch(length: usize) -> Vec<(usize, AlignmentType)> {
+    vec![
+        (
+            // Aligners may repeat entrances, but they are a single column.
+            3,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset: 10,
+            },
+        ),
+        (length, AlignmentType::SecondaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 0,
+            },
+        ),
+    ]
+}
+
+#[test]
+fn equal_alignments_do_not_diverge() {
+    let alignment = [
+        (3, AlignmentType::PrimaryMatch),
+        (1, AlignmentType::PrimaryInsertion),
+        (3, AlignmentType::PrimaryMatch),
+    ];
+    assert_eq!(diff_alignments(&alignment, &alignment), []);
+}
+
+#[test]
+fn flanks_and_runs_do_not_diverge() {
+    assert_eq!(
+        diff_alignments(
+            &[(1, AlignmentType::PrimaryMatchRun { length: 4 })],
+            &[
+                (2, AlignmentType::PrimaryFlankMatch),
+                (2, AlignmentType::PrimaryMatch)
+            ],
+        ),
+        []
+    );
+}
+
+#[test]
+fn shifted_gaps_diverge() {
+    assert_eq!(
+        diff_alignments(
+            &[
+                (3, AlignmentType::PrimaryMatch),
+                (1, AlignmentType::PrimaryInsertion),
+                (3, AlignmentType::PrimaryMatch),
+            ],
+            &[
+                (2, AlignmentType::PrimaryMatch),
+                (1, AlignmentType::PrimaryInsertion),
+                (4, AlignmentType::PrimaryMatch),
+            ],
+        ),
+        [AlignmentDivergence {
+            kind: DivergenceKind::PrimaryPath,
+            reference_range: 2..3,
+            query_range: 2..4,
+            first: vec![
+                (1, AlignmentType::PrimaryMatch),
+                (1, AlignmentType::PrimaryInsertion)
+            ],
+            second: vec![
+                (1, AlignmentType::PrimaryInsertion),
+                (1, AlignmentType::PrimaryMatch)
+            ],
+        }]
+    );
+}
+
+#[test]
+fn template_switches_diverge() {
+    let first: Vec<_> = [(2, AlignmentType::PrimaryMatch)]
+        .into_iter()
+        .chain(template_switch(4))
+        .chain([(6, AlignmentType::PrimaryMatch)])
+        .collect();
+    let second = [
+        (2, AlignmentType::PrimaryMatch),
+        (4, AlignmentType::PrimarySubstitution),
+        (6, AlignmentType::PrimaryMatch),
+    ];
+
+    let divergences = diff_alignments(&first, &second);
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(
+        divergences[0].kind,
+        DivergenceKind::TemplateSwitchOnlyInFirst
+    );
+    assert_eq!(divergences[0].reference_range, 2..6);
+    assert_eq!(divergences[0].query_range, 2..6);
+    let mut expected_template_switch = template_switch(4);
+    expected_template_switch[0].0 = 1;
+    assert_eq!(divergences[0].first, expected_template_switch);
+    assert_eq!(
+        divergences[0].second,
+        [(4, AlignmentType::PrimarySubstitution)]
+    );
+
+    // Moving the template switch by one character changes its placement.
+    let moved: Vec<_> = [(3, AlignmentType::PrimaryMatch)]
+        .into_iter()
+        .chain(template_switch(4))
+        .chain([(5, AlignmentType::PrimaryMatch)])
+        .collect();
+    let divergences = diff_alignments(&first, &moved);
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].kind, DivergenceKind::TemplateSwitchPlacement);
+    assert_eq!(divergences[0].reference_range, 2..7);
+    assert_eq!(divergences[0].query_range, 2..7);
+}
+
+#[test]
+fn ends() {
+    assert_eq!(alignment_end(&[]), (0, 0));
+
+    let mut alignment = vec![
+        (3, AlignmentType::PrimaryMatch),
+        (2, AlignmentType::PrimaryInsertion),
+        (1, AlignmentType::PrimaryDeletion),
+    ];
+    alignment.extend(template_switch(4));
+    alignment.push((1, AlignmentType::PrimaryMatchRun { length: 5 }));
+    assert_eq!(alignment_end(&alignment), (13, 14));
+}
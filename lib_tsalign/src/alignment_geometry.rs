@@ -0,0 +1,330 @@
+//! Positions and ranges in the alignment of a reference against a query.
+//!
+//! Ranges are stored zero-based and half-open, and can be converted from and to the other common conventions
+//! with [`RangeConvention`].
+
+use std::{fmt::Display, ops::Range};
+
+#[cfg(test)]
+mod tests;
+
+/// A pair of positions in the reference and the query, i.e. a position in the alignment matrix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlignmentCoordinates {
+    pub reference: usize,
+    pub query: usize,
+}
+
+/// A zero-based half-open range in both the reference and the query.
+///
+/// The [`offset`](Self::offset) is never greater than the [`limit`](Self::limit) in either sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedAlignmentRange"))]
+pub struct AlignmentRange {
+    offset: AlignmentCoordinates,
+    limit: AlignmentCoordinates,
+}
+
+/// An [`AlignmentRange`] as deserialised, before checking that its offset is not greater than its limit.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedAlignmentRange {
+    offset: AlignmentCoordinates,
+    limit: AlignmentCoordinates,
+}
+
+/// The ways in which a range can be written as a start and an end position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeConvention {
+    /// The first position is zero and the end is excluded, like in Rust and BED files.
+    ZeroBasedHalfOpen,
+    /// The first position is zero and the end is included.
+    ZeroBasedClosed,
+    /// The first position is one and the end is excluded.
+    OneBasedHalfOpen,
+    /// The first position is one and the end is included, like in samtools regions and GFF files.
+    OneBasedClosed,
+}
+
+impl AlignmentCoordinates {
+    pub const fn new(reference: usize, query: usize) -> Self {
+        Self { reference, query }
+    }
+
+    /// Moves the coordinates by the given signed offsets, returning `None` if they would leave the range of `usize`.
+    pub fn checked_shift(self, reference_offset: isize, query_offset: isize) -> Option<Self> {
+        Some(Self {
+            reference: self.reference.checked_add_signed(reference_offset)?,
+            query: self.query.checked_add_signed(query_offset)?,
+        })
+    }
+
+    /// Moves the coordinates by the given signed offsets.
+    ///
+    /// Panics if they would leave the range of `usize`.
+    pub fn shift(self, reference_offset: isize, query_offset: isize) -> Self {
+        self.checked_shift(reference_offset, query_offset)
+            .unwrap_or_else(|| {
+                panic!("Shifting {self} by ({reference_offset}, {query_offset}) overflows")
+            })
+    }
+
+    /// Returns the smaller of both coordinates in each sequence.
+    pub fn component_min(self, other: Self) -> Self {
+        Self {
+            reference: self.reference.min(other.reference),
+            query: self.query.min(other.query),
+        }
+    }
+
+    /// Returns the greater of both coordinates in each sequence.
+    pub fn component_max(self, other: Self) -> Self {
+        Self {
+            reference: self.reference.max(other.reference),
+            query: self.query.max(other.query),
+        }
+    }
+
+    /// Returns true if the coordinates are not greater than `other` in either sequence.
+    pub fn is_component_le(self, other: Self) -> bool {
+        self.reference <= other.reference && self.query <= other.query
+    }
+
+    /// Moves the coordinates into `range`, where the limit of the range counts as inside.
+    pub fn clamp(self, range: &AlignmentRange) -> Self {
+        self.component_max(range.offset).component_min(range.limit)
+    }
+}
+
+impl AlignmentRange {
+    /// Creates a range from its first coordinates and the coordinates after its end.
+    ///
+    /// Panics if the offset is greater than the limit in either sequence.
+    pub fn new(offset: AlignmentCoordinates, limit: AlignmentCoordinates) -> Self {
+        Self::checked_new(offset, limit)
+            .unwrap_or_else(|| panic!("The offset {offset} is greater than the limit {limit}"))
+    }
+
+    /// Like [`new`](Self::new), but returns `None` instead of panicking.
+    pub fn checked_new(offset: AlignmentCoordinates, limit: AlignmentCoordinates) -> Option<Self> {
+        offset
+            .is_component_le(limit)
+            .then_some(Self { offset, limit })
+    }
+
+    /// Creates a range from the zero-based half-open ranges in the reference and the query.
+    ///
+    /// Panics if either range ends before it starts.
+    pub fn from_ranges(reference: Range<usize>, query: Range<usize>) -> Self {
+        Self::new(
+            AlignmentCoordinates::new(reference.start, query.start),
+            AlignmentCoordinates::new(reference.end, query.end),
+        )
+    }
+
+    /// Creates a range covering the whole reference and query.
+    pub fn from_lengths(reference_length: usize, query_length: usize) -> Self {
+        Self::from_ranges(0..reference_length, 0..query_length)
+    }
+
+    /// Creates a range from start and end positions in the reference and the query, written in `convention`.
+    ///
+    /// Returns `None` if a start is before the first position of the convention, or if a range ends before it starts.
+    pub fn from_convention(
+        reference: (usize, usize),
+        query: (usize, usize),
+        convention: RangeConvention,
+    ) -> Option<Self> {
+        Self::checked_new(
+            AlignmentCoordinates::new(
+                convention.start_to_zero_based(reference.0)?,
+                convention.start_to_zero_based(query.0)?,
+            ),
+            AlignmentCoordinates::new(
+                convention.end_to_zero_based_half_open(reference.1)?,
+                convention.end_to_zero_based_half_open(query.1)?,
+            ),
+        )
+    }
+
+    /// Returns the start and end positions in the reference and the query, written in `convention`.
+    ///
+    /// Returns `None` if the range is empty at position zero in a sequence and `convention` is zero-based and closed,
+    /// since its end would be negative.
+    pub fn to_convention(&self, convention: RangeConvention) -> Option<[(usize, usize); 2]> {
+        Some([
+            (
+                convention.start_from_zero_based(self.offset.reference),
+                convention.end_from_zero_based_half_open(self.limit.reference)?,
+            ),
+            (
+                convention.start_from_zero_based(self.offset.query),
+                convention.end_from_zero_based_half_open(self.limit.query)?,
+            ),
+        ])
+    }
+
+    /// The first coordinates inside the range.
+    pub fn offset(&self) -> AlignmentCoordinates {
+        self.offset
+    }
+
+    /// The coordinates after the end of the range.
+    pub fn limit(&self) -> AlignmentCoordinates {
+        self.limit
+    }
+
+    pub fn reference_range(&self) -> Range<usize> {
+        self.offset.reference..self.limit.reference
+    }
+
+    pub fn query_range(&self) -> Range<usize> {
+        self.offset.query..self.limit.query
+    }
+
+    pub fn reference_length(&self) -> usize {
+        self.limit.reference - self.offset.reference
+    }
+
+    pub fn query_length(&self) -> usize {
+        self.limit.query - self.offset.query
+    }
+
+    /// Returns true if the range is empty in both the reference and the query.
+    pub fn is_empty(&self) -> bool {
+        self.offset == self.limit
+    }
+
+    /// Returns true if the coordinates are inside the range in both sequences, excluding the limit.
+    pub fn contains(&self, coordinates: AlignmentCoordinates) -> bool {
+        self.reference_range().contains(&coordinates.reference)
+            && self.query_range().contains(&coordinates.query)
+    }
+
+    /// Returns true if `other` is inside this range in both sequences.
+    pub fn contains_range(&self, other: &Self) -> bool {
+        self.offset.is_component_le(other.offset) && other.limit.is_component_le(self.limit)
+    }
+
+    /// Returns the part of the range that is inside `other` in both sequences.
+    ///
+    /// Returns `None` if the ranges are disjoint in either sequence.
+    /// Ranges that only touch intersect in an empty range.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::checked_new(
+            self.offset.component_max(other.offset),
+            self.limit.component_min(other.limit),
+        )
+    }
+
+    /// Returns the smallest range containing both ranges.
+    ///
+    /// If the ranges are disjoint, this also contains the gap between them.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            offset: self.offset.component_min(other.offset),
+            limit: self.limit.component_max(other.limit),
+        }
+    }
+
+    /// Moves the range by the given signed offsets, returning `None` if it would leave the range of `usize`.
+    pub fn checked_shift(&self, reference_offset: isize, query_offset: isize) -> Option<Self> {
+        Some(Self {
+            offset: self.offset.checked_shift(reference_offset, query_offset)?,
+            limit: self.limit.checked_shift(reference_offset, query_offset)?,
+        })
+    }
+
+    /// Moves the range by the given signed offsets.
+    ///
+    /// Panics if it would leave the range of `usize`.
+    pub fn shift(&self, reference_offset: isize, query_offset: isize) -> Self {
+        self.checked_shift(reference_offset, query_offset)
+            .unwrap_or_else(|| {
+                panic!("Shifting {self} by ({reference_offset}, {query_offset}) overflows")
+            })
+    }
+
+    /// Shrinks the range to the part inside `bounds`.
+    ///
+    /// Unlike [`intersection`](Self::intersection), this always returns a range,
+    /// which is empty at the nearest border of `bounds` in the sequences where the ranges are disjoint.
+    pub fn clamp(&self, bounds: &Self) -> Self {
+        Self {
+            offset: self.offset.clamp(bounds),
+            limit: self.limit.clamp(bounds),
+        }
+    }
+}
+
+impl RangeConvention {
+    fn first_position(self) -> usize {
+        match self {
+            Self::ZeroBasedHalfOpen | Self::ZeroBasedClosed => 0,
+            Self::OneBasedHalfOpen | Self::OneBasedClosed => 1,
+        }
+    }
+
+    /// The difference between the end of a range in this convention and its first position after the end.
+    fn end_inclusion(self) -> usize {
+        match self {
+            Self::ZeroBasedHalfOpen | Self::OneBasedHalfOpen => 0,
+            Self::ZeroBasedClosed | Self::OneBasedClosed => 1,
+        }
+    }
+
+    fn start_to_zero_based(self, start: usize) -> Option<usize> {
+        start.checked_sub(self.first_position())
+    }
+
+    fn start_from_zero_based(self, start: usize) -> usize {
+        start + self.first_position()
+    }
+
+    fn end_to_zero_based_half_open(self, end: usize) -> Option<usize> {
+        (end + self.end_inclusion()).checked_sub(self.first_position())
+    }
+
+    fn end_from_zero_based_half_open(self, end: usize) -> Option<usize> {
+        (end + self.first_position()).checked_sub(self.end_inclusion())
+    }
+}
+
+impl From<(Range<usize>, Range<usize>)> for AlignmentRange {
+    fn from((reference, query): (Range<usize>, Range<usize>)) -> Self {
+        Self::from_ranges(reference, query)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedAlignmentRange> for AlignmentRange {
+    type Error = String;
+
+    fn try_from(range: UncheckedAlignmentRange) -> Result<Self, Self::Error> {
+        Self::checked_new(range.offset, range.limit).ok_or_else(|| {
+            format!(
+                "The offset {} is greater than the limit {}",
+                range.offset, range.limit
+            )
+        })
+    }
+}
+
+impl Display for AlignmentCoordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.reference, self.query)
+    }
+}
+
+impl Display for AlignmentRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "reference {:?}, query {:?}",
+            self.reference_range(),
+            self.query_range()
+        )
+    }
+}
@@ -0,0 +1,97 @@
+use super::{AlignmentCoordinates, AlignmentRange, RangeConvention};
+
+#[test]
+fn intersection_and_union() {
+    let a = AlignmentRange::from_ranges(2..10, 0..5);
+    let b = AlignmentRange::from_ranges(5..20, 3..8);
+
+    assert_eq!(
+        a.intersection(&b),
+        Some(AlignmentRange::from_ranges(5..10, 3..5))
+    );
+    assert_eq!(a.union(&b), AlignmentRange::from_ranges(2..20, 0..8));
+
+    // Touching ranges intersect in an empty range, disjoint ones not at all.
+    let touching = AlignmentRange::from_ranges(10..12, 5..6);
+    assert!(a.intersection(&touching).unwrap().is_empty());
+    let disjoint = AlignmentRange::from_ranges(11..12, 0..5);
+    assert_eq!(a.intersection(&disjoint), None);
+    assert_eq!(a.union(&disjoint), AlignmentRange::from_ranges(2..12, 0..5));
+}
+
+#[test]
+fn shift_and_clamp() {
+    let range = AlignmentRange::from_ranges(2..10, 0..5);
+    assert_eq!(range.shift(-2, 3), AlignmentRange::from_ranges(0..8, 3..8));
+    assert_eq!(range.checked_shift(-3, 0), None);
+
+    let bounds = AlignmentRange::from_lengths(6, 20);
+    assert_eq!(
+        range.clamp(&bounds),
+        AlignmentRange::from_ranges(2..6, 0..5)
+    );
+    let outside = AlignmentRange::from_ranges(8..9, 0..5);
+    assert_eq!(
+        outside.clamp(&bounds),
+        AlignmentRange::from_ranges(6..6, 0..5)
+    );
+
+    assert_eq!(
+        AlignmentCoordinates::new(12, 3).clamp(&range),
+        AlignmentCoordinates::new(10, 3)
+    );
+    assert!(range.contains(AlignmentCoordinates::new(2, 4)));
+    assert!(!range.contains(AlignmentCoordinates::new(10, 4)));
+    assert!(bounds.contains_range(&range.clamp(&bounds)));
+}
+
+#[test]
+fn convention_round_trips() {
+    // The samtools region 5-9 of the reference and the first three query characters.
+    let range = AlignmentRange::from_ranges(4..9, 0..3);
+
+    for (convention, expected) in [
+        (RangeConvention::ZeroBasedHalfOpen, [(4, 9), (0, 3)]),
+        (RangeConvention::ZeroBasedClosed, [(4, 8), (0, 2)]),
+        (RangeConvention::OneBasedHalfOpen, [(5, 10), (1, 4)]),
+        (RangeConvention::OneBasedClosed, [(5, 9), (1, 3)]),
+    ] {
+        assert_eq!(range.to_convention(convention), Some(expected));
+        assert_eq!(
+            AlignmentRange::from_convention(expected[0], expected[1], convention),
+            Some(range),
+        );
+    }
+
+    assert_eq!(
+        AlignmentRange::from_convention((0, 5), (1, 3), RangeConvention::OneBasedClosed),
+        None
+    );
+    assert_eq!(
+        AlignmentRange::from_lengths(0, 3).to_convention(RangeConvention::ZeroBasedClosed),
+        None
+    );
+    assert_eq!(
+        AlignmentRange::from_lengths(0, 3).to_convention(RangeConvention::OneBasedClosed),
+        Some([(1, 0), (1, 3)])
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let range = AlignmentRange::from_ranges(4..9, 0..3);
+    let serialised = toml::to_string(&range).unwrap();
+    assert_eq!(
+        toml::from_str::<AlignmentRange>(&serialised).unwrap(),
+        range
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialising_checks_offset_and_limit() {
+    let serialised =
+        "offset = { reference = 5, query = 0 }\nlimit = { reference = 4, query = 3 }\n";
+    assert!(toml::from_str::<AlignmentRange>(serialised).is_err());
+}
@@ -8,8 +8,14 @@ use index::{
 };
 use ndarray::Array2;
 
-use crate::{alignment_configuration::AlignmentConfiguration, costs::cost::Cost};
+use crate::{
+    a_star_aligner::{compress_alignment, gap_affine_edit_distance::AlignmentType},
+    alignment_configuration::AlignmentConfiguration,
+    costs::cost::Cost,
+};
 
+pub mod affine;
+pub mod hirschberg;
 pub mod index;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -75,6 +81,26 @@ impl AlignmentMatrix {
         self.matrix[[self.matrix.dim().0 - 1, self.matrix.dim().1 - 1]].cost
     }
 
+    /// Traces back an optimal alignment through the filled matrix.
+    pub fn traceback(&self) -> Vec<(usize, AlignmentType)> {
+        let mut index = AlignmentMatrixIndex::new(self.matrix.dim().0 - 1, self.matrix.dim().1 - 1);
+        let mut traceback = Vec::new();
+
+        loop {
+            let alignment_type = self.matrix[index].alignment_type;
+            traceback.push(match alignment_type {
+                BaseAlignmentType::None => break,
+                BaseAlignmentType::Insertion => AlignmentType::Insertion,
+                BaseAlignmentType::Deletion => AlignmentType::Deletion,
+                BaseAlignmentType::Match => AlignmentType::Match,
+                BaseAlignmentType::Substitution => AlignmentType::Substitution,
+            });
+            index = index.predecessor(alignment_type);
+        }
+
+        compress_alignment(traceback.into_iter())
+    }
+
     fn initialise(&mut self) {
         // Initialise matrix origin.
         self.matrix[[0, 0]].cost = Cost::ZERO;
@@ -276,6 +302,7 @@ mod tests {
     use traitsequence::interface::Sequence;
 
     use crate::{
+        a_star_aligner::gap_affine_edit_distance::AlignmentType,
         alignment_configuration::AlignmentConfiguration,
         alignment_matrix::{AlignmentMatrixEntry, BaseAlignmentType},
     };
@@ -323,6 +350,14 @@ mod tests {
             matrix, manual_matrix,
             "matrix:\n{matrix}\nmanual_matrix:\n{manual_matrix}"
         );
+        assert_eq!(
+            matrix.traceback(),
+            [
+                (1, AlignmentType::Match),
+                (1, AlignmentType::Insertion),
+                (2, AlignmentType::Match),
+            ]
+        );
 
         let reference = sequence_store.add_from_slice_u8(b"ACCCGT").unwrap();
         let query = sequence_store.add_from_slice_u8(b"ACCGT").unwrap();
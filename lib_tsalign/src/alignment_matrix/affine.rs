@@ -0,0 +1,332 @@
+use std::time::Instant;
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use ndarray::Array2;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        compress_alignment,
+        gap_affine_edit_distance::{AlignmentType, ScoringTable},
+    },
+    costs::cost::Cost,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A gap-affine alignment matrix, computed with the three matrices of Gotoh's algorithm.
+///
+/// A gap of length `n` costs `gap_open_cost + (n - 1) * gap_extend_cost`, like in the
+/// [gap-affine A* aligner](crate::a_star_aligner::gap_affine_edit_distance_a_star_align).
+/// Computing the full matrix takes quadratic time and memory, so this is meant as a reference for testing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AffineAlignmentMatrix {
+    /// The cost of aligning the prefixes such that the last alignment step is a match or substitution.
+    matches: Array2<Cost>,
+    /// The cost of aligning the prefixes such that the last alignment step is an insertion.
+    insertions: Array2<Cost>,
+    /// The cost of aligning the prefixes such that the last alignment step is a deletion.
+    deletions: Array2<Cost>,
+    scoring_table: ScoringTable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AffineMatrixType {
+    Match,
+    Insertion,
+    Deletion,
+}
+
+impl AffineAlignmentMatrix {
+    pub fn new(scoring_table: ScoringTable, reference_length: usize, query_length: usize) -> Self {
+        let dim = (reference_length + 1, query_length + 1);
+        Self {
+            matches: Array2::from_elem(dim, Cost::MAX),
+            insertions: Array2::from_elem(dim, Cost::MAX),
+            deletions: Array2::from_elem(dim, Cost::MAX),
+            scoring_table,
+        }
+    }
+
+    /// Fills the matrices and returns the cost of an optimal alignment.
+    pub fn align<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Cost {
+        assert_eq!(self.matches.dim(), (reference.len() + 1, query.len() + 1));
+
+        for reference_index in 0..=reference.len() {
+            for query_index in 0..=query.len() {
+                self.set_costs(reference_index, query_index, reference, query);
+            }
+        }
+
+        self.cost()
+    }
+
+    /// Fills the matrices and traces back an optimal alignment.
+    ///
+    /// The node counts of the result are the number of matrix cells.
+    pub fn align_with_traceback<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> AlignmentResult<AlignmentType> {
+        let start_time = Instant::now();
+        let cost = self.align(reference, query);
+        let alignment = self.traceback(reference, query);
+        let cells = self.matches.len();
+
+        AlignmentResult::new_with_target(
+            alignment,
+            cost,
+            start_time.elapsed().as_secs_f64(),
+            cells,
+            cells,
+            0,
+            reference.len(),
+            query.len(),
+        )
+    }
+
+    /// The cost of an optimal alignment, if the matrices were filled.
+    pub fn cost(&self) -> Cost {
+        let (reference_length, query_length) = self.dimensions();
+        self.min_entry(reference_length, query_length).1
+    }
+
+    /// Traces back an optimal alignment through the filled matrices.
+    ///
+    /// Ties are broken by preferring matches and substitutions over insertions over deletions.
+    pub fn traceback<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Vec<(usize, AlignmentType)> {
+        let (mut reference_index, mut query_index) = self.dimensions();
+        let (mut matrix_type, _) = self.min_entry(reference_index, query_index);
+        let mut traceback = Vec::new();
+
+        while reference_index > 0 || query_index > 0 {
+            let cost = self.entry(matrix_type, reference_index, query_index);
+            debug_assert_ne!(cost, Cost::MAX);
+
+            match matrix_type {
+                AffineMatrixType::Match => {
+                    reference_index -= 1;
+                    query_index -= 1;
+                    let (alignment_type, step_cost) =
+                        self.match_or_substitution(reference_index, query_index, reference, query);
+                    traceback.push(alignment_type);
+                    matrix_type = self.predecessor_type(
+                        reference_index,
+                        query_index,
+                        cost - step_cost,
+                        [
+                            (AffineMatrixType::Match, Cost::ZERO),
+                            (AffineMatrixType::Insertion, Cost::ZERO),
+                            (AffineMatrixType::Deletion, Cost::ZERO),
+                        ],
+                    );
+                }
+                AffineMatrixType::Insertion => {
+                    query_index -= 1;
+                    traceback.push(AlignmentType::Insertion);
+                    matrix_type = self.gap_predecessor_type(
+                        AffineMatrixType::Insertion,
+                        reference_index,
+                        query_index,
+                        cost,
+                    );
+                }
+                AffineMatrixType::Deletion => {
+                    reference_index -= 1;
+                    traceback.push(AlignmentType::Deletion);
+                    matrix_type = self.gap_predecessor_type(
+                        AffineMatrixType::Deletion,
+                        reference_index,
+                        query_index,
+                        cost,
+                    );
+                }
+            }
+        }
+
+        debug_assert_eq!(matrix_type, AffineMatrixType::Match);
+        compress_alignment(traceback.into_iter())
+    }
+
+    fn set_costs<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        reference_index: usize,
+        query_index: usize,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) {
+        let index = [reference_index, query_index];
+        let ScoringTable {
+            gap_open_cost,
+            gap_extend_cost,
+            ..
+        } = self.scoring_table;
+
+        if reference_index == 0 && query_index == 0 {
+            self.matches[index] = Cost::ZERO;
+            return;
+        }
+
+        if reference_index > 0 && query_index > 0 {
+            let (_, step_cost) =
+                self.match_or_substitution(reference_index - 1, query_index - 1, reference, query);
+            let (_, predecessor_cost) = self.min_entry(reference_index - 1, query_index - 1);
+            self.matches[index] = add_finite(predecessor_cost, step_cost);
+        }
+
+        if query_index > 0 {
+            let predecessor = [reference_index, query_index - 1];
+            self.insertions[index] = add_finite(self.matches[predecessor], gap_open_cost)
+                .min(add_finite(self.deletions[predecessor], gap_open_cost))
+                .min(add_finite(self.insertions[predecessor], gap_extend_cost));
+        }
+
+        if reference_index > 0 {
+            let predecessor = [reference_index - 1, query_index];
+            self.deletions[index] = add_finite(self.matches[predecessor], gap_open_cost)
+                .min(add_finite(self.insertions[predecessor], gap_open_cost))
+                .min(add_finite(self.deletions[predecessor], gap_extend_cost));
+        }
+    }
+
+    /// Returns the alignment type and cost of aligning the given reference and query characters.
+    fn match_or_substitution<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference_index: usize,
+        query_index: usize,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> (AlignmentType, Cost) {
+        if reference[reference_index] == query[query_index] {
+            (AlignmentType::Match, self.scoring_table.match_cost)
+        } else {
+            (
+                AlignmentType::Substitution,
+                self.scoring_table.substitution_cost,
+            )
+        }
+    }
+
+    /// Returns the matrix of the step before a gap character.
+    fn gap_predecessor_type(
+        &self,
+        gap_type: AffineMatrixType,
+        reference_index: usize,
+        query_index: usize,
+        cost: Cost,
+    ) -> AffineMatrixType {
+        let ScoringTable {
+            gap_open_cost,
+            gap_extend_cost,
+            ..
+        } = self.scoring_table;
+        let other_gap_type = if gap_type == AffineMatrixType::Insertion {
+            AffineMatrixType::Deletion
+        } else {
+            AffineMatrixType::Insertion
+        };
+
+        self.predecessor_type(
+            reference_index,
+            query_index,
+            cost,
+            [
+                (AffineMatrixType::Match, gap_open_cost),
+                (gap_type, gap_extend_cost),
+                (other_gap_type, gap_open_cost),
+            ],
+        )
+    }
+
+    /// Returns the first of the given matrices from which `cost` is reached with the given step cost.
+    fn predecessor_type(
+        &self,
+        reference_index: usize,
+        query_index: usize,
+        cost: Cost,
+        candidates: [(AffineMatrixType, Cost); 3],
+    ) -> AffineMatrixType {
+        candidates
+            .into_iter()
+            .find(|&(matrix_type, step_cost)| {
+                add_finite(
+                    self.entry(matrix_type, reference_index, query_index),
+                    step_cost,
+                ) == cost
+            })
+            .map(|(matrix_type, _)| matrix_type)
+            .unwrap_or_else(|| {
+                panic!("No predecessor of cost {cost} at ({reference_index}, {query_index})")
+            })
+    }
+
+    fn min_entry(&self, reference_index: usize, query_index: usize) -> (AffineMatrixType, Cost) {
+        [
+            AffineMatrixType::Match,
+            AffineMatrixType::Insertion,
+            AffineMatrixType::Deletion,
+        ]
+        .into_iter()
+        .map(|matrix_type| {
+            (
+                matrix_type,
+                self.entry(matrix_type, reference_index, query_index),
+            )
+        })
+        .min_by_key(|(_, cost)| *cost)
+        .unwrap()
+    }
+
+    fn entry(
+        &self,
+        matrix_type: AffineMatrixType,
+        reference_index: usize,
+        query_index: usize,
+    ) -> Cost {
+        let index = [reference_index, query_index];
+        match matrix_type {
+            AffineMatrixType::Match => self.matches[index],
+            AffineMatrixType::Insertion => self.insertions[index],
+            AffineMatrixType::Deletion => self.deletions[index],
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        let (reference_dim, query_dim) = self.matches.dim();
+        (reference_dim - 1, query_dim - 1)
+    }
+}
+
+/// Adds `step_cost` to `cost`, unless either of them marks an unreachable entry.
+pub(super) fn add_finite(cost: Cost, step_cost: Cost) -> Cost {
+    if cost == Cost::MAX || step_cost == Cost::MAX {
+        Cost::MAX
+    } else {
+        cost + step_cost
+    }
+}
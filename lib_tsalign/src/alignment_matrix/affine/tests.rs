@@ -0,0 +1,123 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+use traitsequence::interface::Sequence;
+
+use crate::a_star_aligner::{
+    alignment_result::AlignmentResult,
+    gap_affine_edit_distance::{AlignmentType, ScoringTable},
+    gap_affine_edit_distance_a_star_align,
+};
+
+use super::AffineAlignmentMatrix;
+
+fn scoring_tables() -> [ScoringTable; 3] {
+    [(0, 2, 4, 1), (0, 2, 3, 3), (1, 5, 2, 1)].map(
+        |(match_cost, substitution_cost, gap_open_cost, gap_extend_cost): (u64, u64, u64, u64)| {
+            ScoringTable {
+                match_cost: match_cost.into(),
+                substitution_cost: substitution_cost.into(),
+                gap_open_cost: gap_open_cost.into(),
+                gap_extend_cost: gap_extend_cost.into(),
+            }
+        },
+    )
+}
+
+const SEQUENCE_PAIRS: [(&str, &str); 8] = [
+    ("", ""),
+    ("ACGT", ""),
+    ("", "ACGT"),
+    ("AGT", "GTCC"),
+    ("ACG", "ACCG"),
+    ("ACGTTGCAAC", "ACGTGCATAC"),
+    ("ACGCCCCCT", "ACCCCCGCT"),
+    ("AAAACCCCGGGGTTTT", "AAAAGGGGTTTTCCCC"),
+];
+
+/// Sums the costs of the steps of `alignment`, charging the gap open cost for the first character of each gap.
+fn alignment_cost(alignment: &[(usize, AlignmentType)], scoring_table: &ScoringTable) -> f64 {
+    alignment
+        .iter()
+        .map(|&(count, alignment_type)| {
+            let count = count as u64;
+            match alignment_type {
+                AlignmentType::Match => count * scoring_table.match_cost.as_u64(),
+                AlignmentType::Substitution => count * scoring_table.substitution_cost.as_u64(),
+                AlignmentType::Insertion | AlignmentType::Deletion => {
+                    scoring_table.gap_open_cost.as_u64()
+                        + (count - 1) * scoring_table.gap_extend_cost.as_u64()
+                }
+                AlignmentType::Root => 0,
+            }
+        })
+        .sum::<u64>() as f64
+}
+
+fn matrix_alignment(
+    reference: &VectorGenome<DnaAlphabet>,
+    query: &VectorGenome<DnaAlphabet>,
+    scoring_table: ScoringTable,
+) -> AlignmentResult<AlignmentType> {
+    AffineAlignmentMatrix::new(scoring_table, reference.len(), query.len()).align_with_traceback(
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+    )
+}
+
+#[test]
+fn traceback_matches_cost() {
+    for scoring_table in scoring_tables() {
+        for (reference, query) in SEQUENCE_PAIRS {
+            let reference = VectorGenome::<DnaAlphabet>::from_iter_u8(reference.bytes()).unwrap();
+            let query = VectorGenome::from_iter_u8(query.bytes()).unwrap();
+            let result = matrix_alignment(&reference, &query, scoring_table);
+            let AlignmentResult::WithTarget {
+                alignment,
+                statistics,
+            } = &result
+            else {
+                panic!("The alignment matrix always has a target");
+            };
+
+            assert_eq!(
+                alignment_cost(alignment, &scoring_table),
+                statistics.cost.raw(),
+                "{scoring_table:?}: {result}"
+            );
+        }
+    }
+}
+
+#[test]
+fn gap_affine_a_star_matches_alignment_matrix() {
+    for scoring_table in scoring_tables() {
+        for (reference, query) in SEQUENCE_PAIRS {
+            let reference = VectorGenome::<DnaAlphabet>::from_iter_u8(reference.bytes()).unwrap();
+            let query = VectorGenome::from_iter_u8(query.bytes()).unwrap();
+            let expected = matrix_alignment(&reference, &query, scoring_table);
+            let actual = gap_affine_edit_distance_a_star_align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                scoring_table,
+                &Default::default(),
+            )
+            .unwrap();
+
+            assert_eq!(
+                actual.statistics().cost,
+                expected.statistics().cost,
+                "{scoring_table:?}\nexpected: {expected}\nactual: {actual}"
+            );
+            let AlignmentResult::WithTarget { alignment, .. } = &actual else {
+                panic!("A* found no alignment: {actual}");
+            };
+            assert_eq!(
+                alignment_cost(alignment, &scoring_table),
+                actual.statistics().cost.raw(),
+                "{scoring_table:?}: {actual}"
+            );
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use std::{ops::Range, time::Instant};
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        compress_alignment,
+        gap_affine_edit_distance::{AlignmentType, ScoringTable},
+    },
+    costs::cost::Cost,
+};
+
+use super::affine::add_finite;
+
+#[cfg(test)]
+mod tests;
+
+/// A gap-affine aligner that uses Hirschberg's divide-and-conquer scheme to trace back an optimal alignment in linear space.
+///
+/// It computes alignments of the same cost as [`AffineAlignmentMatrix`](super::affine::AffineAlignmentMatrix),
+/// but stores only a constant number of rows of the length of the shorter sequence, at the price of computing each cell about twice.
+/// The search space is split at the middle row of the longer sequence, and the halves are aligned recursively.
+/// To split gaps correctly, each half is aligned with the type of the step before it and after it fixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HirschbergAligner {
+    scoring_table: ScoringTable,
+}
+
+/// The type of an alignment step, where matches and substitutions are not distinguished.
+///
+/// Rows are indexed by the longer sequence, so depending on which sequence is longer,
+/// a row step is a deletion or an insertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Diagonal,
+    Column,
+    Row,
+}
+
+impl HirschbergAligner {
+    pub fn new(scoring_table: ScoringTable) -> Self {
+        Self { scoring_table }
+    }
+
+    /// Computes an optimal alignment.
+    ///
+    /// The node counts of the result are zero, since this does not search a graph.
+    pub fn align<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> AlignmentResult<AlignmentType> {
+        let start_time = Instant::now();
+
+        let steps = if query.len() > reference.len() {
+            self.align_rows(query.len(), reference.len(), |row, column| {
+                query[row] == reference[column]
+            })
+            .into_iter()
+            .map(|alignment_type| match alignment_type {
+                AlignmentType::Insertion => AlignmentType::Deletion,
+                AlignmentType::Deletion => AlignmentType::Insertion,
+                alignment_type => alignment_type,
+            })
+            .collect()
+        } else {
+            self.align_rows(reference.len(), query.len(), |row, column| {
+                reference[row] == query[column]
+            })
+        };
+
+        let alignment = compress_alignment(steps.into_iter().rev());
+        let cost = self.path_cost(State::Diagonal, alignment.iter().copied(), None);
+
+        AlignmentResult::new_with_target(
+            alignment,
+            cost,
+            start_time.elapsed().as_secs_f64(),
+            0,
+            0,
+            0,
+            reference.len(),
+            query.len(),
+        )
+    }
+
+    /// Aligns the rows to the columns, where rows are consumed by deletions and columns by insertions.
+    ///
+    /// Returns the steps of the alignment in forward order.
+    fn align_rows(
+        &self,
+        rows: usize,
+        columns: usize,
+        is_match: impl Fn(usize, usize) -> bool,
+    ) -> Vec<AlignmentType> {
+        let mut steps = Vec::with_capacity(rows + columns);
+        self.align_block(
+            0..rows,
+            0..columns,
+            State::Diagonal,
+            None,
+            &is_match,
+            &mut steps,
+        );
+        steps
+    }
+
+    /// Appends an optimal alignment of the block to `steps`.
+    ///
+    /// The step before the block is of type `start`, and if `end` is given, then the last step of the block must be of that type.
+    /// If the block is empty, then this is the type of the step before it.
+    fn align_block(
+        &self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        start: State,
+        end: Option<State>,
+        is_match: &impl Fn(usize, usize) -> bool,
+        steps: &mut Vec<AlignmentType>,
+    ) {
+        if rows.len() <= 1 {
+            self.align_small_block(rows, columns, start, end, is_match, steps);
+            return;
+        }
+
+        let middle = rows.start + rows.len() / 2;
+        let (split_column, split_state) =
+            self.split(rows.clone(), middle, columns.clone(), start, end, is_match);
+
+        self.align_block(
+            rows.start..middle,
+            columns.start..split_column,
+            start,
+            Some(split_state),
+            is_match,
+            steps,
+        );
+        self.align_block(
+            middle..rows.end,
+            split_column..columns.end,
+            split_state,
+            end,
+            is_match,
+            steps,
+        );
+    }
+
+    /// Finds the column and the state in which an optimal alignment of the block enters the middle row.
+    ///
+    /// The rows of the forward and backward matrices are dropped before recursing, so only a constant number of rows is stored at a time.
+    fn split(
+        &self,
+        rows: Range<usize>,
+        middle: usize,
+        columns: Range<usize>,
+        start: State,
+        end: Option<State>,
+        is_match: &impl Fn(usize, usize) -> bool,
+    ) -> (usize, State) {
+        let forward = self.forward_row(rows.start..middle, columns.clone(), start, is_match);
+        let backward = self.backward_row(middle..rows.end, columns.clone(), end, is_match);
+
+        let (split_column, split_state, _) = forward
+            .iter()
+            .zip(&backward)
+            .enumerate()
+            .flat_map(|(offset, (forward, backward))| {
+                State::ALL.into_iter().map(move |state| {
+                    (
+                        columns.start + offset,
+                        state,
+                        add_finite(forward[state.index()], backward[state.index()]),
+                    )
+                })
+            })
+            .min_by_key(|(_, _, cost)| *cost)
+            .unwrap();
+
+        (split_column, split_state)
+    }
+
+    /// Aligns a block with at most one row by trying all alignments.
+    fn align_small_block(
+        &self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        start: State,
+        end: Option<State>,
+        is_match: &impl Fn(usize, usize) -> bool,
+        steps: &mut Vec<AlignmentType>,
+    ) {
+        let width = columns.len();
+        let Some(row) = rows.clone().next() else {
+            steps.extend(std::iter::repeat_n(AlignmentType::Insertion, width));
+            return;
+        };
+
+        // Each candidate consists of some insertions, then a deletion or a diagonal step, and then more insertions.
+        let diagonal_candidates = (0..width).map(|offset| {
+            let diagonal = if is_match(row, columns.start + offset) {
+                AlignmentType::Match
+            } else {
+                AlignmentType::Substitution
+            };
+            (offset, diagonal, width - offset - 1)
+        });
+        let deletion_candidates =
+            (0..=width).map(|offset| (offset, AlignmentType::Deletion, width - offset));
+
+        let (before, alignment_type, after) = diagonal_candidates
+            .chain(deletion_candidates)
+            .min_by_key(|&(before, alignment_type, after)| {
+                self.path_cost(
+                    start,
+                    [
+                        (before, AlignmentType::Insertion),
+                        (1, alignment_type),
+                        (after, AlignmentType::Insertion),
+                    ],
+                    end,
+                )
+            })
+            .unwrap();
+
+        steps.extend(std::iter::repeat_n(AlignmentType::Insertion, before));
+        steps.push(alignment_type);
+        steps.extend(std::iter::repeat_n(AlignmentType::Insertion, after));
+    }
+
+    /// Computes the last row of the forward matrices of the block.
+    ///
+    /// Each entry contains the costs of the alignments of the block up to that column ending in each state.
+    fn forward_row(
+        &self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        start: State,
+        is_match: &impl Fn(usize, usize) -> bool,
+    ) -> Vec<[Cost; 3]> {
+        let width = columns.len() + 1;
+        let mut row = vec![[Cost::MAX; 3]; width];
+        row[0][start.index()] = Cost::ZERO;
+        for offset in 1..width {
+            row[offset][State::Column.index()] =
+                self.min_successor_cost(&row[offset - 1], State::Column);
+        }
+
+        for row_index in rows {
+            let mut next_row = vec![[Cost::MAX; 3]; width];
+            for offset in 0..width {
+                if offset > 0 {
+                    next_row[offset][State::Diagonal.index()] = add_finite(
+                        row[offset - 1].into_iter().min().unwrap(),
+                        self.diagonal_cost(is_match(row_index, columns.start + offset - 1)),
+                    );
+                    next_row[offset][State::Column.index()] =
+                        self.min_successor_cost(&next_row[offset - 1], State::Column);
+                }
+                next_row[offset][State::Row.index()] =
+                    self.min_successor_cost(&row[offset], State::Row);
+            }
+            row = next_row;
+        }
+
+        row
+    }
+
+    /// Computes the first row of the backward matrices of the block.
+    ///
+    /// Each entry contains the costs of the alignments of the block from that column to the end, if the step before is in each state.
+    fn backward_row(
+        &self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        end: Option<State>,
+        is_match: &impl Fn(usize, usize) -> bool,
+    ) -> Vec<[Cost; 3]> {
+        let width = columns.len() + 1;
+        let mut row = vec![[Cost::MAX; 3]; width];
+        row[width - 1] = State::ALL.map(|state| {
+            if end.is_none_or(|end| end == state) {
+                Cost::ZERO
+            } else {
+                Cost::MAX
+            }
+        });
+        for offset in (0..width - 1).rev() {
+            row[offset] = self.predecessor_costs(None, None, Some(row[offset + 1]));
+        }
+
+        for row_index in rows.rev() {
+            let mut next_row = vec![[Cost::MAX; 3]; width];
+            for offset in (0..width).rev() {
+                let diagonal = (offset + 1 < width).then(|| {
+                    add_finite(
+                        row[offset + 1][State::Diagonal.index()],
+                        self.diagonal_cost(is_match(row_index, columns.start + offset)),
+                    )
+                });
+                let column = (offset + 1 < width).then(|| next_row[offset + 1]);
+                next_row[offset] = self.predecessor_costs(diagonal, Some(row[offset]), column);
+            }
+            row = next_row;
+        }
+
+        row
+    }
+
+    /// Computes the costs of a backward entry for each state of the step before it.
+    ///
+    /// The arguments are the cost after a diagonal step including its cost,
+    /// and the costs of the entries after a row and a column step.
+    fn predecessor_costs(
+        &self,
+        diagonal: Option<Cost>,
+        row: Option<[Cost; 3]>,
+        column: Option<[Cost; 3]>,
+    ) -> [Cost; 3] {
+        State::ALL.map(|predecessor| {
+            let row = row.map(|row| {
+                add_finite(
+                    row[State::Row.index()],
+                    self.gap_cost(predecessor, State::Row),
+                )
+            });
+            let column = column.map(|column| {
+                add_finite(
+                    column[State::Column.index()],
+                    self.gap_cost(predecessor, State::Column),
+                )
+            });
+            [diagonal, row, column]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap_or(Cost::MAX)
+        })
+    }
+
+    /// Returns the minimum cost of reaching a gap state from the given costs of its predecessor.
+    fn min_successor_cost(&self, predecessor: &[Cost; 3], successor: State) -> Cost {
+        State::ALL
+            .into_iter()
+            .map(|state| add_finite(predecessor[state.index()], self.gap_cost(state, successor)))
+            .min()
+            .unwrap()
+    }
+
+    /// Computes the cost of a path given as pairs of a count and an alignment type,
+    /// or [`Cost::MAX`] if it does not end in the required state.
+    fn path_cost(
+        &self,
+        start: State,
+        path: impl IntoIterator<Item = (usize, AlignmentType)>,
+        end: Option<State>,
+    ) -> Cost {
+        let mut cost = Cost::ZERO;
+        let mut state = start;
+
+        for (count, alignment_type) in path {
+            if count == 0 {
+                continue;
+            }
+
+            let next_state = State::from(alignment_type);
+            let count = count as u64;
+            cost += match next_state {
+                State::Diagonal => (count
+                    * self
+                        .diagonal_cost(alignment_type == AlignmentType::Match)
+                        .as_u64())
+                .into(),
+                gap => {
+                    self.gap_cost(state, gap)
+                        + ((count - 1) * self.scoring_table.gap_extend_cost.as_u64()).into()
+                }
+            };
+            state = next_state;
+        }
+
+        if end.is_none_or(|end| end == state) {
+            cost
+        } else {
+            Cost::MAX
+        }
+    }
+
+    fn diagonal_cost(&self, is_match: bool) -> Cost {
+        if is_match {
+            self.scoring_table.match_cost
+        } else {
+            self.scoring_table.substitution_cost
+        }
+    }
+
+    /// The cost of a gap step of type `successor` after a step of type `predecessor`.
+    fn gap_cost(&self, predecessor: State, successor: State) -> Cost {
+        debug_assert_ne!(successor, State::Diagonal);
+        if predecessor == successor {
+            self.scoring_table.gap_extend_cost
+        } else {
+            self.scoring_table.gap_open_cost
+        }
+    }
+}
+
+impl State {
+    const ALL: [Self; 3] = [Self::Diagonal, Self::Column, Self::Row];
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<AlignmentType> for State {
+    fn from(alignment_type: AlignmentType) -> Self {
+        match alignment_type {
+            AlignmentType::Match | AlignmentType::Substitution => Self::Diagonal,
+            AlignmentType::Insertion => Self::Column,
+            AlignmentType::Deletion => Self::Row,
+            AlignmentType::Root => unreachable!("the root is not an alignment step"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+use traitsequence::interface::Sequence;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        gap_affine_edit_distance::{AlignmentType, ScoringTable},
+    },
+    alignment_matrix::affine::AffineAlignmentMatrix,
+};
+
+use super::HirschbergAligner;
+
+const SEQUENCE_PAIRS: [(&str, &str); 9] = [
+    ("", ""),
+    ("ACGT", ""),
+    ("A", "CCGTA"),
+    ("AGT", "GTCC"),
+    ("ACG", "ACCG"),
+    ("ACGTTGCAAC", "ACGTGCATAC"),
+    ("ACGCCCCCT", "ACCCCCGCT"),
+    ("AAAACCCCGGGGTTTT", "AAAAGGGGTTTTCCCC"),
+    ("ACGTACGTACGTACGTACGT", "ACGTTTTTTTTACGT"),
+];
+
+#[test]
+fn hirschberg_matches_alignment_matrix() {
+    for (match_cost, substitution_cost, gap_open_cost, gap_extend_cost) in
+        [(0, 2, 4, 1), (0, 2, 3, 3), (1, 5, 2, 1), (0, 3, 1, 2)]
+    {
+        let scoring_table = ScoringTable {
+            match_cost: match_cost.into(),
+            substitution_cost: substitution_cost.into(),
+            gap_open_cost: gap_open_cost.into(),
+            gap_extend_cost: gap_extend_cost.into(),
+        };
+
+        for (reference, query) in SEQUENCE_PAIRS
+            .into_iter()
+            .flat_map(|(reference, query)| [(reference, query), (query, reference)])
+        {
+            let reference = VectorGenome::<DnaAlphabet>::from_iter_u8(reference.bytes()).unwrap();
+            let query = VectorGenome::from_iter_u8(query.bytes()).unwrap();
+            let expected = AffineAlignmentMatrix::new(scoring_table, reference.len(), query.len())
+                .align_with_traceback(
+                    reference.as_genome_subsequence(),
+                    query.as_genome_subsequence(),
+                );
+            let actual = HirschbergAligner::new(scoring_table).align(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+            );
+
+            assert_eq!(
+                actual.statistics().cost,
+                expected.statistics().cost,
+                "{scoring_table:?}\nexpected: {expected}\nactual: {actual}"
+            );
+
+            let AlignmentResult::WithTarget { alignment, .. } = &actual else {
+                panic!("Hirschberg found no alignment: {actual}");
+            };
+            let (mut reference_length, mut query_length) = (0, 0);
+            for &(count, alignment_type) in alignment {
+                match alignment_type {
+                    AlignmentType::Match | AlignmentType::Substitution => {
+                        reference_length += count;
+                        query_length += count;
+                    }
+                    AlignmentType::Insertion => query_length += count,
+                    AlignmentType::Deletion => reference_length += count,
+                    AlignmentType::Root => unreachable!(),
+                }
+            }
+            assert_eq!(
+                (reference_length, query_length),
+                (reference.len(), query.len()),
+                "{actual}"
+            );
+        }
+    }
+}
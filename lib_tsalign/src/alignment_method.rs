@@ -0,0 +1,246 @@
+//! Choosing between the aligners of this crate at runtime.
+//!
+//! An [`AlignmentMethod`] names an aligner, and [`AlignmentMethod::select`] replaces [`AlignmentMethod::Auto`]
+//! with a concrete aligner and template switch strategies for a pair of sequences.
+//! A [`MethodAligner`] holds the configuration of the chosen aligner, and [`dispatch`] runs it.
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use log::info;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        configurable_a_star_align::Aligner,
+        gap_affine_edit_distance, gap_affine_edit_distance_a_star_align,
+        template_switch_distance::{
+            self,
+            strategies::{
+                chaining::{RepeatMaskThreshold, RuntimeChainingSelection, CHAINING_BLOCK_SIZE},
+                primary_match::AllowPrimaryMatchStrategy,
+                shortcut::ShortcutStrategy,
+                template_switch_count::TemplateSwitchCountStrategy,
+                AlignmentStrategySelector,
+            },
+        },
+        AStarTieBreaking, SearchSettings,
+    },
+    alignment_configuration::AlignmentConfiguration,
+    alignment_matrix::AlignmentMatrix,
+    costs::cost::Cost,
+    error::{Error, Result},
+    kmer_spectrum::KmerSpectrum,
+    sketch::MinHashSketch,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The k-mer size used to estimate the divergence between reference and query.
+const DIVERGENCE_K: usize = 11;
+
+/// The sketch size used to estimate the divergence between reference and query.
+const DIVERGENCE_SKETCH_SIZE: usize = 1_000;
+
+/// Above this estimated divergence, the exact search is expected to explore too many nodes on long sequences.
+const MAX_EXACT_DIVERGENCE: f64 = 0.1;
+
+/// Sequences with a product of lengths above this are considered long.
+const LONG_SEQUENCE_AREA: usize = 1_000_000;
+
+/// Above this repetitiveness of the chaining blocks of either sequence, the anchors of repetitive blocks are masked.
+///
+/// See [`KmerSpectrum::repetitiveness`].
+const MAX_UNMASKED_REPETITIVENESS: f64 = 0.5;
+
+/// An aligner of this crate.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlignmentMethod {
+    /// The quadratic dynamic programming aligner with linear gap costs, see [`AlignmentMatrix`].
+    Matrix,
+    /// The A* aligner with gap-affine costs, see [`gap_affine_edit_distance_a_star_align`].
+    AStarGapAffine,
+    /// The template switch aligner, see [`Aligner`].
+    AStarTemplateSwitch,
+    /// Choose the aligner and its strategies based on the sequences, see [`AlignmentMethod::select`].
+    Auto,
+}
+
+/// The template switch strategies chosen by [`AlignmentMethod::select`] for [`AlignmentMethod::Auto`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AutoSelection {
+    pub chaining: RuntimeChainingSelection,
+    /// True if the anytime search should be used instead of the exact A* search.
+    pub anytime: bool,
+    /// How to mask the chaining anchors of repetitive blocks, if at all.
+    ///
+    /// Masking trades the optimality guarantee for fast chaining, see [`TemplateSwitchConfig::anchor_repeat_mask`](crate::config::TemplateSwitchConfig::anchor_repeat_mask).
+    pub anchor_repeat_mask: Option<RepeatMaskThreshold>,
+}
+
+/// An aligner chosen at runtime, together with its configuration.
+pub enum MethodAligner<'aligner, Strategies: AlignmentStrategySelector> {
+    Matrix(AlignmentConfiguration),
+    AStarGapAffine {
+        scoring_table: gap_affine_edit_distance::ScoringTable,
+        tie_breaking: AStarTieBreaking,
+    },
+    AStarTemplateSwitch(&'aligner Aligner<Strategies>),
+}
+
+/// The result of [`dispatch`], depending on the aligner.
+#[derive(Debug)]
+pub enum MethodAlignment {
+    /// The matrix aligner only computes the cost of the alignment.
+    Matrix {
+        cost: Cost,
+    },
+    AStarGapAffine(AlignmentResult<gap_affine_edit_distance::AlignmentType>),
+    AStarTemplateSwitch(AlignmentResult<template_switch_distance::AlignmentType>),
+}
+
+impl AlignmentMethod {
+    /// Replaces [`Auto`](Self::Auto) with a concrete alignment method and template switch strategies for the given sequences.
+    ///
+    /// The template switch aligner is always chosen, since it is the only aligner that supports all alphabets and self-alignments.
+    /// Chaining is enabled if the sequences are long enough, and the anytime search is used if the sequences
+    /// are long and diverged, such that the exact search would be too slow.
+    /// When chaining, the anchors of repetitive blocks are masked if either sequence is repetitive.
+    /// For self-alignments, the template switch aligner is chosen without choosing its strategies.
+    /// Other methods are returned unchanged without strategies.
+    ///
+    /// Returns an error if `self_alignment` is set and the method is not the template switch aligner.
+    pub fn select<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        self_alignment: bool,
+    ) -> Result<(Self, Option<AutoSelection>)> {
+        match self {
+            Self::Matrix | Self::AStarGapAffine if self_alignment => {
+                Err(Error::SelfAlignmentUnsupported { method: self })
+            }
+            Self::Matrix | Self::AStarGapAffine | Self::AStarTemplateSwitch => Ok((self, None)),
+            Self::Auto if self_alignment => Ok((Self::AStarTemplateSwitch, None)),
+            Self::Auto => Ok((
+                Self::AStarTemplateSwitch,
+                Some(auto_select(reference, query)),
+            )),
+        }
+    }
+}
+
+/// Chooses the template switch strategies for [`AlignmentMethod::Auto`], and logs the reasons for the choice.
+fn auto_select<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+) -> AutoSelection {
+    let area = reference.len().saturating_mul(query.len());
+    let reference_sketch = MinHashSketch::new(reference, DIVERGENCE_K, DIVERGENCE_SKETCH_SIZE);
+    let query_sketch = MinHashSketch::new(query, DIVERGENCE_K, DIVERGENCE_SKETCH_SIZE);
+    let divergence = (!reference_sketch.is_empty() && !query_sketch.is_empty())
+        .then(|| reference_sketch.mash_distance(&query_sketch));
+    info!(
+        "Auto-selecting alignment method for reference length {}, query length {} and estimated divergence {}",
+        reference.len(),
+        query.len(),
+        divergence.map_or_else(|| "unknown".to_string(), |divergence| format!("{divergence:.3}")),
+    );
+
+    let chaining = if reference.len() < CHAINING_BLOCK_SIZE {
+        info!("Not chaining, since the reference is shorter than the chaining block size {CHAINING_BLOCK_SIZE}");
+        RuntimeChainingSelection::None
+    } else {
+        info!("Chaining, since the reference is at least as long as the chaining block size {CHAINING_BLOCK_SIZE}");
+        RuntimeChainingSelection::LowerBound
+    };
+
+    let anchor_repeat_mask = if chaining == RuntimeChainingSelection::None {
+        None
+    } else {
+        let repetitiveness = [reference, query]
+            .map(|sequence| KmerSpectrum::new(sequence, CHAINING_BLOCK_SIZE).repetitiveness())
+            .into_iter()
+            .fold(0.0, f64::max);
+        if repetitiveness > MAX_UNMASKED_REPETITIVENESS {
+            info!("Masking repetitive chaining anchors, since the sequences have a repetitiveness of {repetitiveness:.3}");
+            Some(RepeatMaskThreshold::Auto)
+        } else {
+            None
+        }
+    };
+
+    let anytime = if area > LONG_SEQUENCE_AREA
+        && divergence.is_none_or(|divergence| divergence > MAX_EXACT_DIVERGENCE)
+    {
+        info!("Using anytime search, since the sequences are long and diverged by more than {MAX_EXACT_DIVERGENCE}");
+        true
+    } else {
+        info!("Using exact A* search, since the sequences are short or similar");
+        false
+    };
+
+    AutoSelection {
+        chaining,
+        anytime,
+        anchor_repeat_mask,
+    }
+}
+
+impl<Strategies: AlignmentStrategySelector> MethodAligner<'_, Strategies> {
+    pub fn method(&self) -> AlignmentMethod {
+        match self {
+            Self::Matrix(_) => AlignmentMethod::Matrix,
+            Self::AStarGapAffine { .. } => AlignmentMethod::AStarGapAffine,
+            Self::AStarTemplateSwitch(_) => AlignmentMethod::AStarTemplateSwitch,
+        }
+    }
+}
+
+/// Aligns `reference` and `query` with the given aligner.
+///
+/// The template switch aligner is run without soft masks and guide.
+/// To use those, call [`Aligner::align_guided`] directly.
+pub fn dispatch<
+    Strategies: AlignmentStrategySelector<PrimaryMatch = AllowPrimaryMatchStrategy>,
+    SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+>(
+    aligner: &MethodAligner<Strategies>,
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+) -> Result<MethodAlignment>
+where
+    <Strategies::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory: Clone,
+    <Strategies::Shortcut as ShortcutStrategy>::Memory: Clone,
+{
+    Ok(match aligner {
+        MethodAligner::Matrix(configuration) => {
+            let mut alignment_matrix =
+                AlignmentMatrix::new(configuration.clone(), reference.len(), query.len());
+            MethodAlignment::Matrix {
+                cost: alignment_matrix.align(reference, query),
+            }
+        }
+        MethodAligner::AStarGapAffine {
+            scoring_table,
+            tie_breaking,
+        } => MethodAlignment::AStarGapAffine(gap_affine_edit_distance_a_star_align(
+            reference,
+            query,
+            *scoring_table,
+            &SearchSettings {
+                tie_breaking: *tie_breaking,
+                ..Default::default()
+            },
+        )?),
+        MethodAligner::AStarTemplateSwitch(aligner) => {
+            MethodAlignment::AStarTemplateSwitch(aligner.align(reference, query, None)?)
+        }
+    })
+}
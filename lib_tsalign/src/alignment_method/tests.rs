@@ -0,0 +1,198 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        configurable_a_star_align::AlignerBuilder,
+        gap_affine_edit_distance::ScoringTable,
+        template_switch_distance::strategies::{
+            chaining::{RepeatMaskThreshold, RuntimeChainingSelection},
+            template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
+        },
+        AStarTieBreaking,
+    },
+    alignment_configuration::AlignmentConfiguration,
+    error::Error,
+    test_fixtures::sample_config,
+};
+
+use super::{dispatch, AlignmentMethod, AutoSelection, MethodAligner, MethodAlignment};
+
+fn genome(sequence: &[u8]) -> VectorGenome<DnaAlphabetOrN> {
+    VectorGenome::from_slice_u8(sequence).unwrap()
+}
+
+/// A deterministic pseudo-random DNA sequence.
+fn random_genome(seed: u64, length: usize) -> VectorGenome<DnaAlphabetOrN> {
+    let mut state = seed;
+    let sequence: Vec<_> = (0..length)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            b"ACGT"[(state >> 62) as usize]
+        })
+        .collect();
+    genome(&sequence)
+}
+
+#[test]
+fn explicit_methods_are_kept() {
+    let reference = genome(b"ACGT");
+    let query = genome(b"AGGT");
+    for method in [
+        AlignmentMethod::Matrix,
+        AlignmentMethod::AStarGapAffine,
+        AlignmentMethod::AStarTemplateSwitch,
+    ] {
+        assert_eq!(
+            method
+                .select(
+                    reference.as_genome_subsequence(),
+                    query.as_genome_subsequence(),
+                    false
+                )
+                .unwrap(),
+            (method, None)
+        );
+    }
+
+    assert!(matches!(
+        AlignmentMethod::Matrix.select(
+            reference.as_genome_subsequence(),
+            reference.as_genome_subsequence(),
+            true
+        ),
+        Err(Error::SelfAlignmentUnsupported {
+            method: AlignmentMethod::Matrix
+        })
+    ));
+}
+
+#[test]
+fn auto_selects_template_switch_strategies() {
+    let select = |reference: &VectorGenome<DnaAlphabetOrN>,
+                  query: &VectorGenome<DnaAlphabetOrN>| {
+        AlignmentMethod::Auto
+            .select(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                false,
+            )
+            .unwrap()
+    };
+
+    let short = genome(b"ACGTACGT");
+    assert_eq!(
+        select(&short, &short),
+        (
+            AlignmentMethod::AStarTemplateSwitch,
+            Some(AutoSelection {
+                chaining: RuntimeChainingSelection::None,
+                anytime: false,
+                anchor_repeat_mask: None,
+            })
+        )
+    );
+
+    assert_eq!(
+        AlignmentMethod::Auto
+            .select(
+                short.as_genome_subsequence(),
+                short.as_genome_subsequence(),
+                true
+            )
+            .unwrap(),
+        (AlignmentMethod::AStarTemplateSwitch, None)
+    );
+
+    let long = random_genome(0, 1_100);
+    assert_eq!(
+        select(&long, &long),
+        (
+            AlignmentMethod::AStarTemplateSwitch,
+            Some(AutoSelection {
+                chaining: RuntimeChainingSelection::LowerBound,
+                anytime: false,
+                anchor_repeat_mask: None,
+            })
+        )
+    );
+
+    let repetitive = genome(&random_genome(2, 100).clone_as_vec().repeat(11));
+    assert_eq!(
+        select(&repetitive, &repetitive),
+        (
+            AlignmentMethod::AStarTemplateSwitch,
+            Some(AutoSelection {
+                chaining: RuntimeChainingSelection::LowerBound,
+                anytime: false,
+                anchor_repeat_mask: Some(RepeatMaskThreshold::Auto),
+            })
+        )
+    );
+
+    let unrelated = random_genome(1, 1_100);
+    assert_eq!(
+        select(&long, &unrelated),
+        (
+            AlignmentMethod::AStarTemplateSwitch,
+            Some(AutoSelection {
+                chaining: RuntimeChainingSelection::LowerBound,
+                anytime: true,
+                anchor_repeat_mask: None,
+            })
+        )
+    );
+}
+
+#[test]
+fn dispatch_runs_each_method() {
+    let reference = genome(b"ACGTTGCAACTCAGG");
+    let query = genome(b"ACGTGCAACTGAGG");
+    let config = sample_config();
+    let template_switch_aligner = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap();
+
+    let aligners = [
+        MethodAligner::Matrix(AlignmentConfiguration::default()),
+        MethodAligner::AStarGapAffine {
+            scoring_table: ScoringTable {
+                match_cost: 0.into(),
+                substitution_cost: 2.into(),
+                gap_open_cost: 4.into(),
+                gap_extend_cost: 1.into(),
+            },
+            tie_breaking: AStarTieBreaking::default(),
+        },
+        MethodAligner::AStarTemplateSwitch(&template_switch_aligner),
+    ];
+    for aligner in &aligners {
+        let alignment = dispatch(
+            aligner,
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+        )
+        .unwrap();
+        match (aligner.method(), alignment) {
+            (AlignmentMethod::Matrix, MethodAlignment::Matrix { cost }) => {
+                // One deletion and one substitution.
+                assert_eq!(cost, 5u64.into());
+            }
+            (AlignmentMethod::AStarGapAffine, MethodAlignment::AStarGapAffine(alignment)) => {
+                assert_eq!(alignment.no_target_reason(), None);
+            }
+            (
+                AlignmentMethod::AStarTemplateSwitch,
+                MethodAlignment::AStarTemplateSwitch(alignment),
+            ) => {
+                assert_eq!(alignment.no_target_reason(), None);
+            }
+            (method, alignment) => panic!("Method {method:?} produced {alignment:?}"),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! How the characters of aligned sequences are written in text outputs.
+//!
+//! Outputs get the characters of the alphabet the sequences were aligned with.
+//! A [`CharacterMapping`] adjusts them independently of the alphabet, e.g. to mask `N` characters
+//! or to mark where preprocessing removed characters from the input.
+
+use crate::preprocessing::CoordinateMap;
+
+#[cfg(test)]
+mod tests;
+
+/// How `N` characters are written in text outputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NCharacterPolicy {
+    /// Write `N` characters like any other character.
+    #[default]
+    Keep,
+    /// Write this character instead of `N`, keeping the case.
+    Replace(char),
+}
+
+/// Maps the characters of an aligned sequence to the characters written in text outputs.
+///
+/// The default mapping writes all characters unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharacterMapping {
+    n_character_policy: NCharacterPolicy,
+    /// If set, then characters that directly follow characters removed by preprocessing are written in lower case.
+    skipped_characters: Option<CoordinateMap>,
+}
+
+impl CharacterMapping {
+    pub fn new(n_character_policy: NCharacterPolicy) -> Self {
+        Self {
+            n_character_policy,
+            skipped_characters: None,
+        }
+    }
+
+    /// Marks characters that directly follow characters removed by preprocessing by writing them in lower case.
+    ///
+    /// Characters removed from the start of the sequence are marked at its first character,
+    /// and characters removed from the end are not marked.
+    pub fn mark_skipped_characters(mut self, coordinate_map: CoordinateMap) -> Self {
+        self.skipped_characters = Some(coordinate_map);
+        self
+    }
+
+    /// Returns the character written for `character` at `index` of the aligned sequence.
+    ///
+    /// For characters on the reverse strand, `character` is the complement, but `index` still refers to the forward strand.
+    pub fn map(&self, index: usize, character: char) -> char {
+        let character = match self.n_character_policy {
+            NCharacterPolicy::Replace(replacement) if character == 'N' => {
+                replacement.to_ascii_uppercase()
+            }
+            NCharacterPolicy::Replace(replacement) if character == 'n' => {
+                replacement.to_ascii_lowercase()
+            }
+            _ => character,
+        };
+
+        if self.follows_skipped_characters(index) {
+            character.to_ascii_lowercase()
+        } else {
+            character
+        }
+    }
+
+    fn follows_skipped_characters(&self, index: usize) -> bool {
+        let Some(coordinate_map) = &self.skipped_characters else {
+            return false;
+        };
+
+        let original_index = coordinate_map.original_index(index);
+        if index == 0 {
+            original_index > 0
+        } else {
+            original_index > coordinate_map.original_index(index - 1) + 1
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::preprocessing::PreprocessingSettings;
+
+use super::{CharacterMapping, NCharacterPolicy};
+
+fn map(mapping: &CharacterMapping, sequence: &str) -> String {
+    sequence
+        .chars()
+        .enumerate()
+        .map(|(index, character)| mapping.map(index, character))
+        .collect()
+}
+
+#[test]
+fn default_mapping_keeps_characters() {
+    assert_eq!(map(&CharacterMapping::default(), "ACGTNnX"), "ACGTNnX");
+}
+
+#[test]
+fn n_characters_are_replaced() {
+    let mapping = CharacterMapping::new(NCharacterPolicy::Replace('x'));
+    assert_eq!(map(&mapping, "ACNNTn"), "ACXXTx");
+}
+
+#[test]
+fn skipped_characters_are_marked() {
+    let preprocessed = PreprocessingSettings {
+        max_n_run: Some(1),
+        ..Default::default()
+    }
+    .preprocess(b"NNACNNNGT", None);
+    assert_eq!(preprocessed.sequence, b"NACNGT");
+
+    let mapping = CharacterMapping::new(NCharacterPolicy::Replace('x'))
+        .mark_skipped_characters(preprocessed.coordinate_map);
+    // The second `N` at the start and the last two `N`s of the run in the middle were removed.
+    assert_eq!(map(&mapping, "NACNGT"), "XaCXgT");
+}
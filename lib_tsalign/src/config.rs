@@ -1,10 +1,22 @@
 use compact_genome::interface::alphabet::Alphabet;
+use num_traits::SaturatingAdd;
+use seed_chain::seed::{AnchorScheme, RepeatMaskThreshold};
 
-use crate::costs::{
-    cost::Cost, cost_function::CostFunction, gap_affine::GapAffineAlignmentCostTable,
+use crate::{
+    a_star_aligner::template_switch_distance::TemplateSwitchStrand,
+    costs::{cost::Cost, cost_function::CostFunction, gap_affine::GapAffineAlignmentCostTable},
 };
 
+pub use directions::TemplateSwitchDirections;
+
+pub mod directions;
 pub mod io;
+pub mod presets;
+#[cfg(feature = "serde")]
+mod toml_io;
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct TemplateSwitchConfig<AlphabetType> {
@@ -12,20 +24,67 @@ pub struct TemplateSwitchConfig<AlphabetType> {
     pub left_flank_length: isize,
     pub right_flank_length: isize,
     pub min_length: usize,
+    /// Template switches may not enter or exit within this many characters of either end of the reference or the query.
+    ///
+    /// Template switches close to the ends of the sequences are rarely reliable, since their flanks are clipped there.
+    /// The ends are the ones of the aligned sequences, so after trimming identical ends, the exclusion applies to the ends of the middle part.
+    /// This is optional in the config file, and defaults to zero, which allows template switches everywhere.
+    pub ts_end_exclusion: usize,
+    /// The kinds of template switches that are searched.
+    ///
+    /// This is not part of the config file, and defaults to all kinds.
+    pub directions: TemplateSwitchDirections,
+    /// Drop the chaining anchors of blocks that match more often than this, if set.
+    ///
+    /// The chaining lower bound assumes that long exact matches contain an anchor,
+    /// so with masking it may overestimate costs in repeats, and the alignment is no longer guaranteed to be optimal.
+    ///
+    /// This is not part of the config file, and defaults to no masking.
+    pub anchor_repeat_mask: Option<RepeatMaskThreshold>,
+    /// How the chaining anchors are found.
+    ///
+    /// Homopolymer-compressed anchors may span homopolymers that the alignment does not match exactly,
+    /// so with them the chaining lower bound is a heuristic, and the alignment is no longer guaranteed to be optimal.
+    ///
+    /// This is not part of the config file, and defaults to exact matches of blocks.
+    pub anchor_scheme: AnchorScheme,
 
     // Base cost
     pub base_cost: Cost,
+    /// The base cost of template switches that copy the forward strand of the secondary, if these are searched.
+    ///
+    /// Other template switches copy the reverse complement of the secondary, as caused by inverted repeats.
+    /// Template switches that copy the forward strand are caused by direct repeats,
+    /// and are a distinct kind of template switch that is searched in addition.
+    /// They share the limits and jump costs with the other template switches.
+    /// This is optional in the config file, and forward template switches are not searched if it is missing.
+    pub forward_base_cost: Option<Cost>,
 
     // Edit costs
     pub primary_edit_costs: GapAffineAlignmentCostTable<AlphabetType>,
     pub secondary_edit_costs: GapAffineAlignmentCostTable<AlphabetType>,
     pub left_flank_edit_costs: GapAffineAlignmentCostTable<AlphabetType>,
     pub right_flank_edit_costs: GapAffineAlignmentCostTable<AlphabetType>,
+    /// The edit costs of template switches that copy the forward strand of the secondary.
+    ///
+    /// This is optional in the config file, and if missing, the secondary edit costs are used.
+    pub forward_secondary_edit_costs: Option<GapAffineAlignmentCostTable<AlphabetType>>,
 
     // Jump costs
     pub offset_costs: CostFunction<isize>,
     pub length_costs: CostFunction<usize>,
     pub length_difference_costs: CostFunction<isize>,
+
+    // Jump event costs
+    /// The costs of a tandem duplication by its length, if tandem duplications are searched.
+    ///
+    /// A tandem duplication of length `l` aligns the next characters of the query to the `l` reference characters before the current position again.
+    pub duplication_costs: Option<CostFunction<usize>>,
+    /// The costs of an inversion by its length, if inversions are searched.
+    ///
+    /// An inversion of length `l` aligns the next `l` characters of the query to the reverse complement of the next `l` characters of the reference,
+    /// without gaps and with the substitution costs of the primary edit costs.
+    pub inversion_costs: Option<CostFunction<usize>>,
 }
 
 impl<AlphabetType: Alphabet> Clone for TemplateSwitchConfig<AlphabetType> {
@@ -34,14 +93,106 @@ impl<AlphabetType: Alphabet> Clone for TemplateSwitchConfig<AlphabetType> {
             left_flank_length: self.left_flank_length,
             right_flank_length: self.right_flank_length,
             min_length: self.min_length,
+            ts_end_exclusion: self.ts_end_exclusion,
+            directions: self.directions,
+            anchor_repeat_mask: self.anchor_repeat_mask,
+            anchor_scheme: self.anchor_scheme,
             base_cost: self.base_cost,
+            forward_base_cost: self.forward_base_cost,
             primary_edit_costs: self.primary_edit_costs.clone(),
             secondary_edit_costs: self.secondary_edit_costs.clone(),
             left_flank_edit_costs: self.left_flank_edit_costs.clone(),
             right_flank_edit_costs: self.right_flank_edit_costs.clone(),
+            forward_secondary_edit_costs: self.forward_secondary_edit_costs.clone(),
             offset_costs: self.offset_costs.clone(),
             length_costs: self.length_costs.clone(),
             length_difference_costs: self.length_difference_costs.clone(),
+            duplication_costs: self.duplication_costs.clone(),
+            inversion_costs: self.inversion_costs.clone(),
+        }
+    }
+}
+
+impl<AlphabetType> TemplateSwitchConfig<AlphabetType> {
+    /// Returns true if tandem duplications or inversions are searched in addition to template switches.
+    pub fn has_jump_events(&self) -> bool {
+        self.duplication_costs.is_some() || self.inversion_costs.is_some()
+    }
+
+    /// The base cost of template switches that copy the given strand of the secondary.
+    ///
+    /// This is [`Cost::MAX`] if these template switches are not searched.
+    pub fn base_cost_of(&self, strand: TemplateSwitchStrand) -> Cost {
+        match strand {
+            TemplateSwitchStrand::ReverseComplement => self.base_cost,
+            TemplateSwitchStrand::Forward => self.forward_base_cost.unwrap_or(Cost::MAX),
         }
     }
+
+    /// The minimum base cost of template switches over both strands of the secondary.
+    pub fn min_base_cost(&self) -> Cost {
+        TemplateSwitchStrand::ALL
+            .into_iter()
+            .map(|strand| self.base_cost_of(strand))
+            .min()
+            .unwrap()
+    }
+
+    /// The edit costs within template switches that copy the given strand of the secondary.
+    pub fn secondary_edit_costs_of(
+        &self,
+        strand: TemplateSwitchStrand,
+    ) -> &GapAffineAlignmentCostTable<AlphabetType> {
+        match strand {
+            TemplateSwitchStrand::ReverseComplement => &self.secondary_edit_costs,
+            TemplateSwitchStrand::Forward => self
+                .forward_secondary_edit_costs
+                .as_ref()
+                .unwrap_or(&self.secondary_edit_costs),
+        }
+    }
+
+    /// Returns true if template switches may enter or exit at the given indices of a reference and a query of the given lengths.
+    ///
+    /// This is false within [`ts_end_exclusion`](Self::ts_end_exclusion) characters of any of the sequence ends.
+    pub fn allows_template_switch_at(
+        &self,
+        reference_index: usize,
+        reference_length: usize,
+        query_index: usize,
+        query_length: usize,
+    ) -> bool {
+        let exclusion = self.ts_end_exclusion;
+        reference_index >= exclusion
+            && reference_length.saturating_sub(reference_index) >= exclusion
+            && query_index >= exclusion
+            && query_length.saturating_sub(query_index) >= exclusion
+    }
+
+    /// A lower bound of the cost of any template switch, tandem duplication or inversion.
+    ///
+    /// For template switches, this is the minimum base cost plus the minimum of each jump cost function.
+    /// This is [`Cost::MAX`] if none of them are searched.
+    /// Alignments cheaper than this cannot be improved by them.
+    pub fn min_jump_cost(&self) -> Cost {
+        let template_switch_cost = if self.directions.is_none() {
+            Cost::MAX
+        } else {
+            [
+                Some(self.min_base_cost()),
+                self.offset_costs.min(..),
+                self.length_costs.min(self.min_length..),
+                self.length_difference_costs.min(..),
+            ]
+            .into_iter()
+            .try_fold(Cost::ZERO, |sum, cost| Some(sum.saturating_add(&cost?)))
+            .unwrap_or(Cost::MAX)
+        };
+
+        [&self.duplication_costs, &self.inversion_costs]
+            .into_iter()
+            .flatten()
+            .filter_map(|costs| costs.min(..))
+            .fold(template_switch_cost, Cost::min)
+    }
 }
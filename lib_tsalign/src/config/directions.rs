@@ -0,0 +1,161 @@
+use std::fmt::{Display, Formatter};
+
+use crate::a_star_aligner::template_switch_distance::{
+    TemplateSwitchPrimary, TemplateSwitchSecondary,
+};
+
+/// The kinds of template switches searched by the aligner, by their primary and secondary sequence.
+///
+/// Restricting the kinds shrinks the search space, which is useful when studying a specific mechanism only,
+/// e.g. template switches within the same strand.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TemplateSwitchDirections {
+    reference_reference: bool,
+    reference_query: bool,
+    query_reference: bool,
+    query_query: bool,
+}
+
+impl TemplateSwitchDirections {
+    pub const ALL: Self = Self {
+        reference_reference: true,
+        reference_query: true,
+        query_reference: true,
+        query_query: true,
+    };
+
+    pub const NONE: Self = Self {
+        reference_reference: false,
+        reference_query: false,
+        query_reference: false,
+        query_query: false,
+    };
+
+    /// Template switches whose primary is the reference.
+    pub const REFERENCE_PRIMARY: Self = Self::NONE
+        .with(
+            TemplateSwitchPrimary::Reference,
+            TemplateSwitchSecondary::Reference,
+        )
+        .with(
+            TemplateSwitchPrimary::Reference,
+            TemplateSwitchSecondary::Query,
+        );
+
+    /// Template switches whose primary is the query.
+    pub const QUERY_PRIMARY: Self = Self::NONE
+        .with(
+            TemplateSwitchPrimary::Query,
+            TemplateSwitchSecondary::Reference,
+        )
+        .with(TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Query);
+
+    /// Template switches whose secondary is the same sequence as their primary.
+    pub const SAME_SEQUENCE: Self = Self::NONE
+        .with(
+            TemplateSwitchPrimary::Reference,
+            TemplateSwitchSecondary::Reference,
+        )
+        .with(TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Query);
+
+    /// Template switches whose secondary is the other sequence than their primary.
+    pub const CROSS_SEQUENCE: Self = Self::NONE
+        .with(
+            TemplateSwitchPrimary::Reference,
+            TemplateSwitchSecondary::Query,
+        )
+        .with(
+            TemplateSwitchPrimary::Query,
+            TemplateSwitchSecondary::Reference,
+        );
+
+    /// Returns these directions with the given direction allowed in addition.
+    pub const fn with(
+        mut self,
+        primary: TemplateSwitchPrimary,
+        secondary: TemplateSwitchSecondary,
+    ) -> Self {
+        *match (primary, secondary) {
+            (TemplateSwitchPrimary::Reference, TemplateSwitchSecondary::Reference) => {
+                &mut self.reference_reference
+            }
+            (TemplateSwitchPrimary::Reference, TemplateSwitchSecondary::Query) => {
+                &mut self.reference_query
+            }
+            (TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Reference) => {
+                &mut self.query_reference
+            }
+            (TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Query) => &mut self.query_query,
+        } = true;
+        self
+    }
+
+    /// Returns the directions allowed by either `self` or `other`.
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            reference_reference: self.reference_reference || other.reference_reference,
+            reference_query: self.reference_query || other.reference_query,
+            query_reference: self.query_reference || other.query_reference,
+            query_query: self.query_query || other.query_query,
+        }
+    }
+
+    pub const fn allows(
+        &self,
+        primary: TemplateSwitchPrimary,
+        secondary: TemplateSwitchSecondary,
+    ) -> bool {
+        match (primary, secondary) {
+            (TemplateSwitchPrimary::Reference, TemplateSwitchSecondary::Reference) => {
+                self.reference_reference
+            }
+            (TemplateSwitchPrimary::Reference, TemplateSwitchSecondary::Query) => {
+                self.reference_query
+            }
+            (TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Reference) => {
+                self.query_reference
+            }
+            (TemplateSwitchPrimary::Query, TemplateSwitchSecondary::Query) => self.query_query,
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == Self::ALL
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+impl Default for TemplateSwitchDirections {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl Display for TemplateSwitchDirections {
+    /// Lists the allowed directions as primary and secondary initials, e.g. `RR, QR`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut once = false;
+        for (allowed, name) in [
+            (self.reference_reference, "RR"),
+            (self.reference_query, "RQ"),
+            (self.query_reference, "QR"),
+            (self.query_query, "QQ"),
+        ] {
+            if allowed {
+                if once {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{name}")?;
+                once = true;
+            }
+        }
+
+        if !once {
+            write!(f, "none")?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
-use std::str::FromStr;
+use std::{io::Write, str::FromStr};
 
 use compact_genome::interface::alphabet::Alphabet;
-use log::trace;
+use log::{trace, warn};
 use nom::{
     bytes::complete::{tag, take_while1},
     character::complete::line_ending,
@@ -12,71 +12,448 @@ use num_traits::PrimInt;
 
 use crate::{
     costs::{cost_function::CostFunction, gap_affine::GapAffineAlignmentCostTable},
-    io::{parse_any_whitespace, parse_whitespace, skip_any_whitespace, translate_nom_error},
+    error::Result,
+    io::{
+        parse_any_whitespace, parse_identifier, parse_title, parse_whitespace, skip_any_whitespace,
+        DiagnosticParser,
+    },
+    provenance::sha256_hex,
 };
 
-use super::TemplateSwitchConfig;
+use super::{presets::TemplateSwitchConfigPreset, TemplateSwitchConfig};
+
+/// How strictly config and cost files are parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// All sections must be given in the order written by `write_plain`.
+    #[default]
+    Strict,
+    /// Sections, and the entries within sections, may be given in any order.
+    /// Sections missing from a config are taken from the default preset.
+    Lenient,
+}
 
 impl<AlphabetType: Alphabet> TemplateSwitchConfig<AlphabetType> {
-    pub fn read_plain(mut reader: impl std::io::Read) -> crate::error::Result<Self> {
+    pub fn read_plain(reader: impl std::io::Read) -> Result<Self> {
+        Self::read_plain_with_mode(reader, ParseMode::Strict)
+    }
+
+    /// Like [`read_plain`](Self::read_plain), but with the given parse mode.
+    pub fn read_plain_with_mode(mut reader: impl std::io::Read, mode: ParseMode) -> Result<Self> {
         let mut input = String::new();
         reader.read_to_string(&mut input)?;
-        let input = input.as_str();
-        Self::parse_plain(input)
-            .map(|(_, result)| result)
-            .map_err(translate_nom_error)
+        let mut parser = DiagnosticParser::new(&input);
+        let result = match mode {
+            ParseMode::Strict => Self::parse_plain(&mut parser)?,
+            ParseMode::Lenient => Self::parse_plain_lenient(&mut parser)?,
+        };
+
+        if parser.is_at_end() {
+            Ok(result)
+        } else {
+            Err(parser.error("the end of the config"))
+        }
+    }
+
+    /// Returns the SHA-256 hash of the config as written by [`write_plain`](Self::write_plain), as lowercase hex string.
+    ///
+    /// This identifies the config in the provenance of results and in alignment errors.
+    pub fn sha256(&self) -> String {
+        let mut config = Vec::new();
+        self.write_plain(&mut config)
+            .expect("Writing to a vector does not fail");
+        sha256_hex(&config)
     }
 
-    fn parse_plain(input: &str) -> IResult<&str, Self> {
+    /// Writes the config in the format read by [`read_plain`](Self::read_plain).
+    pub fn write_plain(&self, mut writer: impl Write) -> Result<()> {
+        writeln!(writer, "# Limits")?;
+        writeln!(writer)?;
+        writeln!(writer, "left_flank_length = {}", self.left_flank_length)?;
+        writeln!(writer, "right_flank_length = {}", self.right_flank_length)?;
+        if self.ts_end_exclusion > 0 {
+            writeln!(writer, "ts_end_exclusion = {}", self.ts_end_exclusion)?;
+        }
+        writeln!(writer)?;
+
+        writeln!(writer, "# Base Cost")?;
+        writeln!(writer)?;
+        writeln!(writer, "base_cost = {}", self.base_cost)?;
+        if let Some(forward_base_cost) = self.forward_base_cost {
+            writeln!(writer, "forward_base_cost = {forward_base_cost}")?;
+        }
+        writeln!(writer)?;
+
+        writeln!(writer, "# Jump Costs")?;
+        writeln!(writer)?;
+        writeln!(writer, "Offset")?;
+        self.offset_costs.write_plain(&mut writer)?;
+        writeln!(writer)?;
+        writeln!(writer, "Length")?;
+        self.length_costs.write_plain(&mut writer)?;
+        writeln!(writer)?;
+        writeln!(writer, "LengthDifference")?;
+        self.length_difference_costs.write_plain(&mut writer)?;
+        writeln!(writer)?;
+
+        for edit_costs in [
+            &self.primary_edit_costs,
+            &self.secondary_edit_costs,
+            &self.left_flank_edit_costs,
+            &self.right_flank_edit_costs,
+        ]
+        .into_iter()
+        .chain(&self.forward_secondary_edit_costs)
+        {
+            edit_costs.write_plain(&mut writer)?;
+            writeln!(writer)?;
+        }
+
+        if self.has_jump_events() {
+            writeln!(writer, "# Event Costs")?;
+            writeln!(writer)?;
+            if let Some(duplication_costs) = &self.duplication_costs {
+                writeln!(writer, "Duplication")?;
+                duplication_costs.write_plain(&mut writer)?;
+                writeln!(writer)?;
+            }
+            if let Some(inversion_costs) = &self.inversion_costs {
+                writeln!(writer, "Inversion")?;
+                inversion_costs.write_plain(&mut writer)?;
+                writeln!(writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_plain(parser: &mut DiagnosticParser<'_>) -> Result<Self> {
         trace!("Parsing limits");
-        let (input, ()) = parse_specific_name("Limits")(input)?;
-        let (input, left_flank_length) = parse_specific_equals_value("left_flank_length")(input)?;
-        let (input, right_flank_length) = parse_specific_equals_value("right_flank_length")(input)?;
+        parser.parse("the section '# Limits'", parse_specific_name("Limits"))?;
+        let left_flank_length = parser.parse(
+            "'left_flank_length = <integer>'",
+            parse_specific_equals_value("left_flank_length"),
+        )?;
+        let right_flank_length = parser.parse(
+            "'right_flank_length = <integer>'",
+            parse_specific_equals_value("right_flank_length"),
+        )?;
+        let ts_end_exclusion = if parser.peek(parse_identifier) == Some("ts_end_exclusion") {
+            parser.parse(
+                "'ts_end_exclusion = <integer>'",
+                parse_specific_equals_value("ts_end_exclusion"),
+            )?
+        } else {
+            0
+        };
 
         trace!("Parsing base costs");
-        let (input, ()) = parse_specific_name("Base Cost")(input)?;
-        let (input, base_cost) = parse_specific_equals_value("base_cost")(input)?;
+        parser.parse(
+            "the section '# Base Cost'",
+            parse_specific_name("Base Cost"),
+        )?;
+        let base_cost = parser.parse(
+            "'base_cost = <cost>'",
+            parse_specific_equals_value("base_cost"),
+        )?;
+        let forward_base_cost = if parser.peek(parse_identifier) == Some("forward_base_cost") {
+            Some(parser.parse(
+                "'forward_base_cost = <cost>'",
+                parse_specific_equals_value("forward_base_cost"),
+            )?)
+        } else {
+            None
+        };
 
         trace!("Parsing jump costs");
-        let (input, ()) = parse_specific_name("Jump Costs")(input)?;
-        let (input, offset_costs) = parse_named_cost_function("Offset")(input)?;
-        let (input, length_costs) = parse_named_cost_function("Length")(input)?;
-        let (input, length_difference_costs) =
-            parse_named_cost_function("LengthDifference")(input)?;
+        parser.parse(
+            "the section '# Jump Costs'",
+            parse_specific_name("Jump Costs"),
+        )?;
+        let offset_costs = parser.parse(
+            "the cost function 'Offset'",
+            parse_named_cost_function("Offset"),
+        )?;
+        let length_costs = parser.parse(
+            "the cost function 'Length'",
+            parse_named_cost_function("Length"),
+        )?;
+        let length_difference_costs = parser.parse(
+            "the cost function 'LengthDifference'",
+            parse_named_cost_function("LengthDifference"),
+        )?;
 
         trace!("Parsing primary edit costs");
-        let (input, primary_edit_costs) = parse_named_cost_table("Primary Edit Costs")(input)?;
+        let primary_edit_costs = parser.parse(
+            "the cost table '# Primary Edit Costs'",
+            parse_named_cost_table("Primary Edit Costs"),
+        )?;
         trace!("Parsing secondary edit costs");
-        let (input, secondary_edit_costs) = parse_named_cost_table("Secondary Edit Costs")(input)?;
+        let secondary_edit_costs = parser.parse(
+            "the cost table '# Secondary Edit Costs'",
+            parse_named_cost_table("Secondary Edit Costs"),
+        )?;
         trace!("Parsing left flank edit costs");
-        let (input, left_flank_edit_costs) =
-            parse_named_cost_table("Left Flank Edit Costs")(input)?;
+        let left_flank_edit_costs = parser.parse(
+            "the cost table '# Left Flank Edit Costs'",
+            parse_named_cost_table("Left Flank Edit Costs"),
+        )?;
         trace!("Parsing right flank edit costs");
-        let (input, right_flank_edit_costs) =
-            parse_named_cost_table("Right Flank Edit Costs")(input)?;
+        let right_flank_edit_costs = parser.parse(
+            "the cost table '# Right Flank Edit Costs'",
+            parse_named_cost_table("Right Flank Edit Costs"),
+        )?;
+        let forward_secondary_edit_costs =
+            if parser.peek(parse_title) == Some("Forward Secondary Edit Costs") {
+                trace!("Parsing forward secondary edit costs");
+                Some(parser.parse(
+                    "the cost table '# Forward Secondary Edit Costs'",
+                    parse_named_cost_table("Forward Secondary Edit Costs"),
+                )?)
+            } else {
+                None
+            };
 
-        Ok((
-            input,
-            Self {
+        trace!("Parsing event costs");
+        let (duplication_costs, inversion_costs) = if parser
+            .parse_opt(
+                "the section '# Event Costs'",
+                parse_specific_name("Event Costs"),
+            )?
+            .is_some()
+        {
+            (
+                parser.parse_opt(
+                    "the cost function 'Duplication'",
+                    parse_named_cost_function("Duplication"),
+                )?,
+                parser.parse_opt(
+                    "the cost function 'Inversion'",
+                    parse_named_cost_function("Inversion"),
+                )?,
+            )
+        } else {
+            (None, None)
+        };
+
+        Ok(Self {
+            left_flank_length,
+            right_flank_length,
+            min_length: length_costs.minimum_finite_input().unwrap_or(usize::MAX),
+            ts_end_exclusion,
+            directions: Default::default(),
+            anchor_repeat_mask: None,
+            anchor_scheme: Default::default(),
+
+            base_cost,
+            forward_base_cost,
+
+            primary_edit_costs,
+            secondary_edit_costs,
+            left_flank_edit_costs,
+            right_flank_edit_costs,
+            forward_secondary_edit_costs,
+
+            offset_costs,
+            length_costs,
+            length_difference_costs,
+
+            duplication_costs,
+            inversion_costs,
+        })
+    }
+    /// Parses a config whose sections, and the entries within sections, may come in any order.
+    ///
+    /// The key-value entries and cost functions may be given in any of the sections that do not contain cost tables.
+    /// Sections and entries that are missing are taken from the default preset.
+    fn parse_plain_lenient(parser: &mut DiagnosticParser<'_>) -> Result<Self> {
+        let mut left_flank_length = None;
+        let mut right_flank_length = None;
+        let mut ts_end_exclusion = None;
+        let mut base_cost = None;
+        let mut forward_base_cost = None;
+        let mut offset_costs = None;
+        let mut length_costs = None;
+        let mut length_difference_costs = None;
+        let mut primary_edit_costs = None;
+        let mut secondary_edit_costs = None;
+        let mut left_flank_edit_costs = None;
+        let mut right_flank_edit_costs = None;
+        let mut forward_secondary_edit_costs = None;
+        let mut duplication_costs = None;
+        let mut inversion_costs = None;
+
+        while !parser.is_at_end() {
+            let Some(title) = parser.peek(parse_title) else {
+                return Err(parser.error("a section heading starting with '#'"));
+            };
+            trace!("Parsing section {title}");
+
+            match title {
+                "Limits" | "Base Cost" | "Jump Costs" | "Event Costs" => {
+                    parser.parse("a section heading", parse_title)?;
+                    while let Some(identifier) = parser.peek(parse_identifier) {
+                        match identifier {
+                            "left_flank_length" => parser.parse_once(
+                                &mut left_flank_length,
+                                identifier,
+                                parse_specific_equals_value(identifier),
+                            )?,
+                            "right_flank_length" => parser.parse_once(
+                                &mut right_flank_length,
+                                identifier,
+                                parse_specific_equals_value(identifier),
+                            )?,
+                            "ts_end_exclusion" => parser.parse_once(
+                                &mut ts_end_exclusion,
+                                identifier,
+                                parse_specific_equals_value(identifier),
+                            )?,
+                            "base_cost" => parser.parse_once(
+                                &mut base_cost,
+                                identifier,
+                                parse_specific_equals_value(identifier),
+                            )?,
+                            "forward_base_cost" => parser.parse_once(
+                                &mut forward_base_cost,
+                                identifier,
+                                parse_specific_equals_value(identifier),
+                            )?,
+                            "Offset" => parser.parse_once(
+                                &mut offset_costs,
+                                identifier,
+                                parse_named_cost_function(identifier),
+                            )?,
+                            "Length" => parser.parse_once(
+                                &mut length_costs,
+                                identifier,
+                                parse_named_cost_function(identifier),
+                            )?,
+                            "LengthDifference" => parser.parse_once(
+                                &mut length_difference_costs,
+                                identifier,
+                                parse_named_cost_function(identifier),
+                            )?,
+                            "Duplication" => parser.parse_once(
+                                &mut duplication_costs,
+                                identifier,
+                                parse_named_cost_function(identifier),
+                            )?,
+                            "Inversion" => parser.parse_once(
+                                &mut inversion_costs,
+                                identifier,
+                                parse_named_cost_function(identifier),
+                            )?,
+                            _ => {
+                                return Err(parser
+                                    .error(&format!("a known entry of the section '# {title}'")))
+                            }
+                        }
+                    }
+                }
+                "Primary Edit Costs" => {
+                    parse_cost_table_once(parser, &mut primary_edit_costs, title)?
+                }
+                "Secondary Edit Costs" => {
+                    parse_cost_table_once(parser, &mut secondary_edit_costs, title)?
+                }
+                "Left Flank Edit Costs" => {
+                    parse_cost_table_once(parser, &mut left_flank_edit_costs, title)?
+                }
+                "Right Flank Edit Costs" => {
+                    parse_cost_table_once(parser, &mut right_flank_edit_costs, title)?
+                }
+                "Forward Secondary Edit Costs" => {
+                    parse_cost_table_once(parser, &mut forward_secondary_edit_costs, title)?
+                }
+                _ => return Err(parser.error("a known section heading")),
+            }
+        }
+
+        let default = Self::from_preset(TemplateSwitchConfigPreset::Default);
+        let length_costs = or_default(length_costs, default.length_costs, "Length");
+
+        Ok(Self {
+            left_flank_length: or_default(
                 left_flank_length,
+                default.left_flank_length,
+                "left_flank_length",
+            ),
+            right_flank_length: or_default(
                 right_flank_length,
-                min_length: length_costs.minimum_finite_input().unwrap_or(usize::MAX),
+                default.right_flank_length,
+                "right_flank_length",
+            ),
+            min_length: length_costs.minimum_finite_input().unwrap_or(usize::MAX),
+            // Like the event costs, this is optional, so it is not taken from the default preset.
+            ts_end_exclusion: ts_end_exclusion.unwrap_or(0),
+            directions: Default::default(),
+            anchor_repeat_mask: None,
+            anchor_scheme: Default::default(),
 
-                base_cost,
+            base_cost: or_default(base_cost, default.base_cost, "base_cost"),
+            // Forward template switches are disabled unless configured.
+            forward_base_cost,
 
+            primary_edit_costs: or_default(
                 primary_edit_costs,
+                default.primary_edit_costs,
+                "# Primary Edit Costs",
+            ),
+            secondary_edit_costs: or_default(
                 secondary_edit_costs,
+                default.secondary_edit_costs,
+                "# Secondary Edit Costs",
+            ),
+            left_flank_edit_costs: or_default(
                 left_flank_edit_costs,
+                default.left_flank_edit_costs,
+                "# Left Flank Edit Costs",
+            ),
+            right_flank_edit_costs: or_default(
                 right_flank_edit_costs,
+                default.right_flank_edit_costs,
+                "# Right Flank Edit Costs",
+            ),
+            forward_secondary_edit_costs,
 
-                offset_costs,
-                length_costs,
+            offset_costs: or_default(offset_costs, default.offset_costs, "Offset"),
+            length_costs,
+            length_difference_costs: or_default(
                 length_difference_costs,
-            },
-        ))
+                default.length_difference_costs,
+                "LengthDifference",
+            ),
+
+            duplication_costs,
+            inversion_costs,
+        })
     }
 }
 
+fn or_default<Value>(value: Option<Value>, default: Value, name: &str) -> Value {
+    value.unwrap_or_else(|| {
+        warn!("The config does not contain '{name}', using it from the default preset");
+        default
+    })
+}
+
+/// Parses a cost table in lenient mode, and fails if `target` was already set.
+fn parse_cost_table_once<AlphabetType: Alphabet>(
+    parser: &mut DiagnosticParser<'_>,
+    target: &mut Option<GapAffineAlignmentCostTable<AlphabetType>>,
+    name: &str,
+) -> Result<()> {
+    if target.is_some() {
+        return Err(parser.error(&format!("anything but a second '# {name}'")));
+    }
+    *target = Some(GapAffineAlignmentCostTable::parse_plain_with_mode(
+        parser,
+        ParseMode::Lenient,
+    )?);
+    Ok(())
+}
+
 fn parse_specific_name(name: &str) -> impl '_ + FnMut(&str) -> IResult<&str, ()> {
     move |input| {
         tuple((
@@ -96,10 +473,12 @@ fn parse_specific_equals_value<Value: FromStr>(
     identifier: &str,
 ) -> impl '_ + FnMut(&str) -> IResult<&str, Value> {
     move |input| {
-        let (input, (actual_identifier, value)) = parse_equals_value(input)?;
+        let input = skip_any_whitespace(input)?;
+        let (remaining, (actual_identifier, value)) = parse_equals_value(input)?;
         if actual_identifier == identifier {
-            Ok((input, value))
+            Ok((remaining, value))
         } else {
+            // Point the error at the wrong identifier.
             Err(nom::Err::Failure(nom::error::Error {
                 input,
                 code: nom::error::ErrorKind::Verify,
@@ -112,8 +491,9 @@ fn parse_equals_value<Value: FromStr>(input: &str) -> IResult<&str, (&str, Value
     let input = skip_any_whitespace(input)?;
     let (input, identifier) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
     let (input, _) = tuple((parse_whitespace, tag("="), parse_whitespace))(input)?;
-    let (input, value) = take_while1(|c: char| !c.is_whitespace())(input)?;
+    let (remaining, value) = take_while1(|c: char| !c.is_whitespace())(input)?;
 
+    // Point the error at the unparseable value.
     let value = Value::from_str(value).map_err(|_| {
         nom::Err::Failure(nom::error::Error {
             input,
@@ -121,7 +501,7 @@ fn parse_equals_value<Value: FromStr>(input: &str) -> IResult<&str, (&str, Value
         })
     })?;
 
-    Ok((input, (identifier, value)))
+    Ok((remaining, (identifier, value)))
 }
 
 fn parse_named_cost_function<SourceType: PrimInt>(
@@ -138,9 +518,10 @@ fn parse_named_cost_table<AlphabetType: Alphabet>(
     name: &str,
 ) -> impl '_ + FnMut(&str) -> IResult<&str, GapAffineAlignmentCostTable<AlphabetType>> {
     move |input| {
-        let (input, result) = GapAffineAlignmentCostTable::parse_plain(input)?;
+        let input = skip_any_whitespace(input)?;
+        let (remaining, result) = GapAffineAlignmentCostTable::parse_plain(input)?;
         if result.name() == name {
-            Ok((input, result))
+            Ok((remaining, result))
         } else {
             Err(nom::Err::Failure(nom::error::Error {
                 input,
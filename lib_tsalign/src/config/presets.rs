@@ -0,0 +1,237 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use compact_genome::interface::alphabet::Alphabet;
+
+use crate::{
+    costs::{cost::Cost, cost_function::CostFunction, gap_affine::GapAffineAlignmentCostTable},
+    error::{Error, Result},
+};
+
+use super::TemplateSwitchConfig;
+
+/// A set of costs built into the aligner, such that no config file is needed.
+///
+/// The costs of all characters are the same, so presets work with any alphabet.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TemplateSwitchConfigPreset {
+    /// Equivalent to the sample config, except that wildcard characters are not treated specially.
+    Default,
+    /// Cheaper and shorter template switches, for finding short or diverged template switches at the cost of runtime.
+    Sensitive,
+    /// More expensive template switches with narrower jumps, for aligning long sequences quickly.
+    Fast,
+    /// Like the default preset, but gaps that extend homopolymers are cheap,
+    /// matching the error profiles of long reads from ONT and PacBio.
+    LongRead,
+}
+
+/// The parameters from which a preset config is built.
+struct PresetParameters {
+    flank_length: isize,
+    base_cost: u64,
+    offset_costs: &'static [(isize, u64)],
+    length_costs: &'static [(usize, u64)],
+    length_difference_costs: &'static [(isize, u64)],
+    /// Substitution, gap open and gap extend costs.
+    primary_edit_costs: [u64; 3],
+    secondary_edit_costs: [u64; 3],
+    flank_edit_costs: [u64; 3],
+    /// The gap extend costs within homopolymer runs of all edit cost tables, if any.
+    homopolymer_gap_extend_costs: Option<&'static [(usize, u64)]>,
+}
+
+/// Marks infinite costs in the cost functions of [`PresetParameters`].
+const INF: u64 = u64::MAX;
+
+impl TemplateSwitchConfigPreset {
+    pub const ALL: [Self; 4] = [Self::Default, Self::Sensitive, Self::Fast, Self::LongRead];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Sensitive => "sensitive",
+            Self::Fast => "fast",
+            Self::LongRead => "long-read",
+        }
+    }
+
+    fn parameters(&self) -> PresetParameters {
+        match self {
+            Self::Default => PresetParameters {
+                flank_length: 5,
+                base_cost: 2,
+                offset_costs: &[(isize::MIN, INF), (-100, 5), (-10, 0), (11, 5), (100, INF)],
+                length_costs: &[(0, INF), (5, 5), (6, 3), (7, 1), (8, 0), (100, INF)],
+                length_difference_costs: &[(isize::MIN, INF), (-10, 5), (-5, 0), (6, 5), (10, INF)],
+                primary_edit_costs: [2, 3, 1],
+                secondary_edit_costs: [8, 9, 2],
+                flank_edit_costs: [3, 4, 1],
+                homopolymer_gap_extend_costs: None,
+            },
+            Self::Sensitive => PresetParameters {
+                flank_length: 5,
+                base_cost: 2,
+                offset_costs: &[(isize::MIN, INF), (-200, 5), (-20, 0), (21, 5), (200, INF)],
+                length_costs: &[(0, INF), (4, 5), (5, 3), (6, 1), (7, 0), (150, INF)],
+                length_difference_costs: &[(isize::MIN, INF), (-15, 5), (-8, 0), (9, 5), (15, INF)],
+                primary_edit_costs: [2, 3, 1],
+                secondary_edit_costs: [7, 8, 2],
+                flank_edit_costs: [3, 4, 1],
+                homopolymer_gap_extend_costs: None,
+            },
+            Self::Fast => PresetParameters {
+                flank_length: 3,
+                base_cost: 4,
+                offset_costs: &[(isize::MIN, INF), (-50, 5), (-5, 0), (6, 5), (50, INF)],
+                length_costs: &[(0, INF), (10, 3), (12, 0), (60, INF)],
+                length_difference_costs: &[(isize::MIN, INF), (-5, 5), (-2, 0), (3, 5), (5, INF)],
+                primary_edit_costs: [2, 3, 1],
+                secondary_edit_costs: [8, 9, 2],
+                flank_edit_costs: [3, 4, 1],
+                homopolymer_gap_extend_costs: None,
+            },
+            Self::LongRead => PresetParameters {
+                flank_length: 5,
+                base_cost: 4,
+                offset_costs: &[
+                    (isize::MIN, INF),
+                    (-100, 10),
+                    (-10, 0),
+                    (11, 10),
+                    (100, INF),
+                ],
+                length_costs: &[(0, INF), (5, 10), (6, 6), (7, 2), (8, 0), (100, INF)],
+                length_difference_costs: &[
+                    (isize::MIN, INF),
+                    (-10, 10),
+                    (-5, 0),
+                    (6, 10),
+                    (10, INF),
+                ],
+                primary_edit_costs: [4, 6, 2],
+                secondary_edit_costs: [16, 18, 4],
+                flank_edit_costs: [6, 8, 2],
+                homopolymer_gap_extend_costs: Some(&[(0, 1), (10, 2)]),
+            },
+        }
+    }
+}
+
+impl<AlphabetType: Alphabet> TemplateSwitchConfig<AlphabetType> {
+    pub fn from_preset(preset: TemplateSwitchConfigPreset) -> Self {
+        let parameters = preset.parameters();
+        let length_costs = cost_function(parameters.length_costs);
+
+        Self {
+            left_flank_length: parameters.flank_length,
+            right_flank_length: parameters.flank_length,
+            min_length: length_costs.minimum_finite_input().unwrap_or(usize::MAX),
+            ts_end_exclusion: 0,
+            directions: Default::default(),
+            anchor_repeat_mask: None,
+            anchor_scheme: Default::default(),
+
+            base_cost: parameters.base_cost.into(),
+            forward_base_cost: None,
+
+            primary_edit_costs: cost_table(
+                "Primary Edit Costs",
+                parameters.primary_edit_costs,
+                parameters.homopolymer_gap_extend_costs,
+            ),
+            secondary_edit_costs: cost_table(
+                "Secondary Edit Costs",
+                parameters.secondary_edit_costs,
+                parameters.homopolymer_gap_extend_costs,
+            ),
+            left_flank_edit_costs: cost_table(
+                "Left Flank Edit Costs",
+                parameters.flank_edit_costs,
+                parameters.homopolymer_gap_extend_costs,
+            ),
+            right_flank_edit_costs: cost_table(
+                "Right Flank Edit Costs",
+                parameters.flank_edit_costs,
+                parameters.homopolymer_gap_extend_costs,
+            ),
+            forward_secondary_edit_costs: None,
+
+            offset_costs: cost_function(parameters.offset_costs),
+            length_costs,
+            length_difference_costs: cost_function(parameters.length_difference_costs),
+
+            duplication_costs: None,
+            inversion_costs: None,
+        }
+    }
+
+    /// Like [`from_preset`](Self::from_preset), but selects the preset by its name.
+    pub fn from_preset_name(name: &str) -> Result<Self> {
+        Ok(Self::from_preset(name.parse()?))
+    }
+}
+
+fn cost_function<SourceType: Ord + Copy>(
+    function: &[(SourceType, u64)],
+) -> CostFunction<SourceType> {
+    function
+        .iter()
+        .map(|&(input, cost)| {
+            (
+                input,
+                if cost == INF {
+                    Cost::MAX
+                } else {
+                    Cost::from(cost)
+                },
+            )
+        })
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap()
+}
+
+fn cost_table<AlphabetType: Alphabet>(
+    name: &str,
+    [substitution_cost, gap_open_cost, gap_extend_cost]: [u64; 3],
+    homopolymer_gap_extend_costs: Option<&[(usize, u64)]>,
+) -> GapAffineAlignmentCostTable<AlphabetType> {
+    let table = GapAffineAlignmentCostTable::new_uniform(
+        name,
+        substitution_cost.into(),
+        gap_open_cost.into(),
+        gap_extend_cost.into(),
+    );
+
+    if let Some(homopolymer_gap_extend_costs) = homopolymer_gap_extend_costs {
+        table.with_homopolymer_gap_extend_costs(cost_function(homopolymer_gap_extend_costs))
+    } else {
+        table
+    }
+}
+
+impl FromStr for TemplateSwitchConfigPreset {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| Error::UnknownConfigPreset {
+                name: name.to_string(),
+                available: Self::ALL
+                    .iter()
+                    .map(|preset| preset.name().to_string())
+                    .collect(),
+            })
+    }
+}
+
+impl Display for TemplateSwitchConfigPreset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,453 @@
+use compact_genome::{
+    implementation::alphabets::dna_alphabet_or_n::DnaAlphabetOrN,
+    interface::alphabet::{Alphabet, AlphabetCharacter},
+};
+
+use crate::{
+    a_star_aligner::{alignment_result::AlignmentResult, template_switch_distance::AlignmentType},
+    costs::{
+        cost::Cost,
+        cost_function::CostFunction,
+        gap_affine::{GapAffineAlignmentCostTable, IndelType},
+    },
+    error::Error,
+    test_fixtures::{align, sample_config, QUERY, REFERENCE, SAMPLE_CONFIG},
+};
+
+use super::{
+    io::ParseMode, presets::TemplateSwitchConfigPreset, TemplateSwitchConfig,
+    TemplateSwitchDirections,
+};
+
+#[test]
+fn preset_names_round_trip() {
+    for preset in TemplateSwitchConfigPreset::ALL {
+        assert_eq!(
+            preset.name().parse::<TemplateSwitchConfigPreset>().unwrap(),
+            preset
+        );
+    }
+
+    assert!("unknown".parse::<TemplateSwitchConfigPreset>().is_err());
+    assert!(TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset_name("unknown").is_err());
+}
+
+#[test]
+fn default_preset_matches_sample_config() {
+    let sample = sample_config();
+    let preset = TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset_name("default").unwrap();
+
+    assert_eq!(preset.left_flank_length, sample.left_flank_length);
+    assert_eq!(preset.right_flank_length, sample.right_flank_length);
+    assert_eq!(preset.min_length, sample.min_length);
+    assert_eq!(preset.base_cost, sample.base_cost);
+    assert_eq!(preset.offset_costs, sample.offset_costs);
+    assert_eq!(preset.length_costs, sample.length_costs);
+    assert_eq!(
+        preset.length_difference_costs,
+        sample.length_difference_costs
+    );
+
+    // The sample config makes the wildcard character `N` free, which presets do not.
+    let assert_equal_without_wildcard =
+        |preset: &GapAffineAlignmentCostTable<DnaAlphabetOrN>,
+         sample: &GapAffineAlignmentCostTable<DnaAlphabetOrN>| {
+            let characters: Vec<_> = b"ACGT"
+                .iter()
+                .map(|&character| DnaAlphabetOrN::ascii_to_character(character).unwrap())
+                .collect();
+            for &c1 in &characters {
+                for indel_type in [IndelType::Insertion, IndelType::Deletion] {
+                    assert_eq!(
+                        preset.gap_open_cost(c1, indel_type),
+                        sample.gap_open_cost(c1, indel_type)
+                    );
+                    assert_eq!(
+                        preset.gap_extend_cost(c1, indel_type),
+                        sample.gap_extend_cost(c1, indel_type)
+                    );
+                }
+                for &c2 in &characters {
+                    assert_eq!(
+                        preset.match_or_substitution_cost(c1, c2),
+                        sample.match_or_substitution_cost(c1, c2)
+                    );
+                }
+            }
+        };
+    assert_equal_without_wildcard(&preset.primary_edit_costs, &sample.primary_edit_costs);
+    assert_equal_without_wildcard(&preset.secondary_edit_costs, &sample.secondary_edit_costs);
+    assert_equal_without_wildcard(&preset.left_flank_edit_costs, &sample.left_flank_edit_costs);
+    assert_equal_without_wildcard(
+        &preset.right_flank_edit_costs,
+        &sample.right_flank_edit_costs,
+    );
+}
+
+#[test]
+fn long_read_preset_makes_homopolymer_gaps_cheap() {
+    let align = |config| {
+        align(
+            b"GATTCGCTAGGCTAAAAAACCGTATCTGG",
+            b"GATTCGCTAGGCTAAACCGTATCTGG",
+            config,
+        )
+    };
+
+    let config = TemplateSwitchConfig::from_preset_name("long-read").unwrap();
+    let mut without_homopolymers = config.clone();
+    without_homopolymers.primary_edit_costs = without_homopolymers
+        .primary_edit_costs
+        .without_homopolymer_gap_extend_costs();
+
+    // Opening the gap costs 6, and extending it within the homopolymer costs 1 instead of 2.
+    let alignment = align(config);
+    assert_eq!(alignment.cigar(), "16M3D10M");
+    assert_eq!(alignment.statistics().cost, 8.0);
+    assert_eq!(align(without_homopolymers).statistics().cost, 10.0);
+}
+
+#[test]
+fn two_piece_gap_costs_make_long_gaps_cheap() {
+    let align = |config| {
+        align(
+            b"GATTCGCTAGCATGGTCAACTGTTAGCCATTCCGTATCTG",
+            b"GATTCGCTAGTCCGTATCTG",
+            config,
+        )
+    };
+
+    // Template switches are disabled, since they could also bridge the gap.
+    let mut config = TemplateSwitchConfig::from_preset_name("default").unwrap();
+    config.base_cost = Cost::MAX;
+    config.primary_edit_costs =
+        GapAffineAlignmentCostTable::new_uniform("primary", 4.into(), 6.into(), 2.into());
+    let mut two_piece = config.clone();
+    two_piece.primary_edit_costs = two_piece
+        .primary_edit_costs
+        .with_long_gap_costs(20.into(), 1.into());
+
+    // A short gap of length 20 costs 6 + 19 * 2, but a long gap costs only 20 + 19 * 1.
+    let alignment = align(two_piece);
+    assert_eq!(alignment.cigar(), "10M20D10M");
+    assert_eq!(alignment.statistics().cost, 39.0);
+    assert_eq!(align(config).statistics().cost, 44.0);
+}
+
+#[test]
+fn jump_events_explain_structural_variants() {
+    let reference = b"GATTCGCTAGCATGGTCAACTGTTAGCCATTCCGTATCTG";
+    // A tandem duplication of `CATGGTCA` and an inversion of `CATGGTCAACTG`.
+    let duplication: &[u8] = b"GATTCGCTAGCATGGTCACATGGTCAACTGTTAGCCATTCCGTATCTG";
+    let inversion: &[u8] = b"GATTCGCTAGCAGTTGACCATGTTAGCCATTCCGTATCTG";
+    let align = |query, config| align(reference, query, config);
+
+    // Template switches are disabled, since they could also explain the inversion.
+    let mut config = TemplateSwitchConfig::from_preset_name("default").unwrap();
+    config.base_cost = Cost::MAX;
+    let event_costs =
+        CostFunction::try_from(vec![(0, Cost::MAX), (4, 2.into()), (20, Cost::MAX)]).unwrap();
+    let mut with_events = config.clone();
+    with_events.duplication_costs = Some(event_costs.clone());
+    with_events.inversion_costs = Some(event_costs);
+
+    let alignment = align(duplication, with_events.clone());
+    assert!(alignment.cigar().contains("[DUP8]"), "{alignment}");
+    assert_eq!(alignment.statistics().cost, 2.0);
+    assert!(align(duplication, config.clone()).statistics().cost > 2.0);
+
+    let alignment = align(inversion, with_events);
+    assert!(alignment.cigar().contains("[INV12:"), "{alignment}");
+    assert_eq!(alignment.statistics().cost, 2.0);
+    assert!(align(inversion, config).statistics().cost > 2.0);
+}
+
+#[test]
+fn inversions_use_context_substitution_costs() {
+    let reference = b"GATTCGCTAGCATGGTCAACTGTTAGCCATTCCGTATCTG";
+    // An inversion of `CATGGTCAACTG` into `CAGTTGACCATG`, with the `G` after `A` substituted by `A`.
+    let query = b"GATTCGCTAGCAATTGACCATGTTAGCCATTCCGTATCTG";
+    let align = |config| align(reference, query, config);
+
+    let mut config = TemplateSwitchConfig::from_preset_name("default").unwrap();
+    config.base_cost = Cost::MAX;
+    config.inversion_costs =
+        Some(CostFunction::try_from(vec![(0, Cost::MAX), (4, 2.into()), (20, Cost::MAX)]).unwrap());
+    let character = |ascii| DnaAlphabetOrN::ascii_to_character(ascii).unwrap();
+    let primary_edit_costs = &config.primary_edit_costs;
+    let mut context_table: Vec<_> = DnaAlphabetOrN::iter()
+        .flat_map(|c1| {
+            DnaAlphabetOrN::iter()
+                .map(move |c2| primary_edit_costs.match_or_substitution_cost(c1, c2))
+        })
+        .collect();
+    context_table[usize::from(character(b'G').index()) * usize::from(DnaAlphabetOrN::SIZE)
+        + usize::from(character(b'A').index())] = 1.into();
+    let mut context_config = config.clone();
+    context_config.primary_edit_costs = context_config
+        .primary_edit_costs
+        .with_context_substitution_cost_table(character(b'A'), context_table);
+
+    // Within the inversion, the character before the substituted `G` is the complement of the next reference character.
+    let alignment = align(context_config);
+    assert!(alignment.cigar().contains("[INV12:"), "{alignment}");
+    assert_eq!(alignment.statistics().cost, 3.0);
+    assert!(align(config).statistics().cost > 3.0);
+}
+
+fn round_trip_configs() -> impl Iterator<Item = TemplateSwitchConfig<DnaAlphabetOrN>> {
+    TemplateSwitchConfigPreset::ALL
+        .into_iter()
+        .map(TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset)
+        .chain([sample_config()])
+        .chain([{
+            let mut config = TemplateSwitchConfig::from_preset_name("default").unwrap();
+            config.inversion_costs = Some(
+                CostFunction::try_from(vec![(0, Cost::MAX), (4, 2.into()), (20, Cost::MAX)])
+                    .unwrap(),
+            );
+            config.secondary_edit_costs = config
+                .secondary_edit_costs
+                .with_long_gap_costs(20.into(), 1.into());
+            config.ts_end_exclusion = 5;
+            config
+        }])
+        .chain([{
+            let mut config = TemplateSwitchConfig::from_preset_name("default").unwrap();
+            config.primary_edit_costs = config.primary_edit_costs.with_deletion_gap_cost_vectors(
+                vec![5.into(); DnaAlphabetOrN::SIZE.into()],
+                vec![Cost::MAX; DnaAlphabetOrN::SIZE.into()],
+            );
+            config.left_flank_edit_costs = config
+                .left_flank_edit_costs
+                .with_context_substitution_cost_table(
+                    DnaAlphabetOrN::ascii_to_character(b'C').unwrap(),
+                    vec![3.into(); usize::from(DnaAlphabetOrN::SIZE).pow(2)],
+                );
+            config.forward_base_cost = Some(3.into());
+            config.forward_secondary_edit_costs = Some(
+                config
+                    .secondary_edit_costs
+                    .clone()
+                    .with_name("Forward Secondary Edit Costs"),
+            );
+            config
+        }])
+}
+
+#[test]
+fn single_gap_cost_direction_is_symmetric() {
+    let sample = SAMPLE_CONFIG;
+    let expected = TemplateSwitchConfig::<DnaAlphabetOrN>::read_plain(sample.as_bytes()).unwrap();
+
+    for direction in ["Insertion", "Deletion"] {
+        let input = sample
+            .replace(
+                "\nGapOpenCostVector",
+                &format!("\n{direction}GapOpenCostVector"),
+            )
+            .replace(
+                "\nGapExtendCostVector",
+                &format!("\n{direction}GapExtendCostVector"),
+            );
+        let config = TemplateSwitchConfig::<DnaAlphabetOrN>::read_plain(input.as_bytes()).unwrap();
+
+        for costs in [
+            &config.primary_edit_costs,
+            &config.secondary_edit_costs,
+            &config.left_flank_edit_costs,
+            &config.right_flank_edit_costs,
+        ] {
+            assert!(costs.has_symmetric_gaps(), "{direction}: {}", costs.name());
+        }
+        assert_eq!(config, expected, "{direction}");
+    }
+}
+
+#[test]
+fn write_plain_round_trip() {
+    for config in round_trip_configs() {
+        let mut written = Vec::new();
+        config.write_plain(&mut written).unwrap();
+        let read = TemplateSwitchConfig::read_plain(written.as_slice()).unwrap();
+        assert_eq!(read, config, "{}", String::from_utf8(written).unwrap());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn write_toml_round_trip() {
+    for config in round_trip_configs() {
+        let mut written = Vec::new();
+        config.write_toml(&mut written).unwrap();
+        let read = TemplateSwitchConfig::read_toml(written.as_slice()).unwrap();
+        assert_eq!(read, config, "{}", String::from_utf8(written).unwrap());
+    }
+
+    // Tables of another alphabet are rejected.
+    let mut written = Vec::new();
+    TemplateSwitchConfig::<DnaAlphabetOrN>::from_preset(TemplateSwitchConfigPreset::Default)
+        .write_toml(&mut written)
+        .unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.contains("alphabet = \"ACGNT\""), "{written}");
+    assert!(matches!(
+        TemplateSwitchConfig::<DnaAlphabetOrN>::read_toml(
+            written
+                .replacen("alphabet = \"ACGNT\"", "alphabet = \"ACGT\"", 1)
+                .as_bytes()
+        ),
+        Err(Error::InvalidTomlConfig { .. })
+    ));
+}
+
+#[test]
+fn directions_restrict_template_switches() {
+    let align = |directions| {
+        let mut config = sample_config();
+        config.directions = directions;
+        align(REFERENCE.as_bytes(), QUERY.as_bytes(), config)
+    };
+
+    let unrestricted = align(TemplateSwitchDirections::ALL);
+    assert!(unrestricted.cigar().contains("[TS"), "{unrestricted}");
+    let none = align(TemplateSwitchDirections::NONE);
+    assert!(!none.cigar().contains("[TS"), "{none}");
+    assert!(none.statistics().cost > unrestricted.statistics().cost);
+
+    for directions in [
+        TemplateSwitchDirections::REFERENCE_PRIMARY,
+        TemplateSwitchDirections::QUERY_PRIMARY,
+        TemplateSwitchDirections::SAME_SEQUENCE,
+        TemplateSwitchDirections::CROSS_SEQUENCE,
+    ] {
+        let restricted = align(directions);
+        assert!(restricted.statistics().cost >= unrestricted.statistics().cost);
+        let AlignmentResult::WithTarget { alignment, .. } = &restricted else {
+            panic!("No alignment found for {directions}");
+        };
+        for (_, alignment_type) in alignment {
+            if let AlignmentType::TemplateSwitchEntrance {
+                primary, secondary, ..
+            } = alignment_type
+            {
+                assert!(directions.allows(*primary, *secondary), "{restricted}");
+            }
+        }
+    }
+}
+
+fn read_with_mode(
+    input: &str,
+    mode: ParseMode,
+) -> crate::error::Result<TemplateSwitchConfig<DnaAlphabetOrN>> {
+    TemplateSwitchConfig::read_plain_with_mode(input.as_bytes(), mode)
+}
+
+#[test]
+fn parse_errors_report_line_and_column() {
+    let assert_diagnostic =
+        |input: &str, expected_line, expected_column, expected_hint: &str| match read_with_mode(
+            input,
+            ParseMode::Strict,
+        ) {
+            Err(Error::ParserDiagnostic {
+                line,
+                column,
+                expected,
+                ..
+            }) => {
+                assert_eq!(
+                    (line, column),
+                    (expected_line, expected_column),
+                    "{expected}"
+                );
+                assert!(expected.contains(expected_hint), "{expected}");
+            }
+            result => panic!("{result:?}"),
+        };
+
+    assert_diagnostic(
+        &SAMPLE_CONFIG.replacen("base_cost =", "basecost =", 1),
+        8,
+        1,
+        "base_cost",
+    );
+    assert_diagnostic(
+        &SAMPLE_CONFIG.replacen("right_flank_length = 5", "right_flank_length = five", 1),
+        4,
+        22,
+        "right_flank_length",
+    );
+    assert_diagnostic(
+        &SAMPLE_CONFIG.replacen("# Left Flank Edit Costs", "# Right Flank Edit Costs", 1),
+        62,
+        1,
+        "Left Flank Edit Costs",
+    );
+    assert_diagnostic(
+        &format!("{SAMPLE_CONFIG}\n# Unknown\n"),
+        100,
+        1,
+        "end of the config",
+    );
+    assert_diagnostic("", 1, 1, "Limits");
+}
+
+#[test]
+fn lenient_parsing_accepts_reordered_sections() {
+    let strict = read_with_mode(SAMPLE_CONFIG, ParseMode::Strict).unwrap();
+
+    let mut sections: Vec<_> = SAMPLE_CONFIG
+        .split("\n# ")
+        .map(|section| section.trim_start_matches("# ").trim())
+        .collect();
+    sections.reverse();
+    let reordered = sections
+        .iter()
+        .map(|section| format!("# {section}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        // Also swap two entries within a section.
+        .replacen(
+            "left_flank_length = 5\nright_flank_length = 5",
+            "right_flank_length = 5\nleft_flank_length = 5",
+            1,
+        );
+
+    assert!(read_with_mode(&reordered, ParseMode::Strict).is_err());
+    assert_eq!(
+        read_with_mode(&reordered, ParseMode::Lenient).unwrap(),
+        strict
+    );
+    assert_eq!(
+        read_with_mode(SAMPLE_CONFIG, ParseMode::Lenient).unwrap(),
+        strict
+    );
+}
+
+#[test]
+fn lenient_parsing_takes_missing_sections_from_default_preset() {
+    let config = read_with_mode("# Base Cost\n\nbase_cost = 7\n", ParseMode::Lenient).unwrap();
+    let mut expected = TemplateSwitchConfig::from_preset(TemplateSwitchConfigPreset::Default);
+    expected.base_cost = 7u64.into();
+    assert_eq!(config, expected);
+
+    assert_eq!(
+        read_with_mode("", ParseMode::Lenient).unwrap(),
+        TemplateSwitchConfig::from_preset(TemplateSwitchConfigPreset::Default)
+    );
+
+    // Entries may still be given only once.
+    assert!(matches!(
+        read_with_mode(
+            "# Base Cost\n\nbase_cost = 7\n\n# Limits\n\nbase_cost = 8\n",
+            ParseMode::Lenient
+        ),
+        Err(Error::ParserDiagnostic { line: 7, .. })
+    ));
+    assert!(matches!(
+        read_with_mode("# Unknown\n", ParseMode::Lenient),
+        Err(Error::ParserDiagnostic { line: 1, .. })
+    ));
+}
@@ -0,0 +1,441 @@
+//! Reading and writing template switch configs as TOML.
+//!
+//! The TOML has the same content as the plain format written by [`write_plain`](TemplateSwitchConfig::write_plain).
+//! The gap open and extend costs of a cost table apply to insertions, and to deletions unless these have their own costs.
+//! Since infinite costs and cost function inputs do not fit into TOML integers, they are written as the strings `"inf"` and `"-inf"`.
+
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+};
+
+use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter};
+use num_traits::PrimInt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    costs::{
+        cost::Cost,
+        cost_function::CostFunction,
+        gap_affine::{GapAffineAlignmentCostTable, IndelType},
+    },
+    error::{Error, Result},
+};
+
+use super::TemplateSwitchConfig;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlConfig {
+    left_flank_length: isize,
+    right_flank_length: isize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    ts_end_exclusion: usize,
+    base_cost: TomlCost,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    forward_base_cost: Option<TomlCost>,
+
+    offset_costs: TomlCostFunction<isize>,
+    length_costs: TomlCostFunction<usize>,
+    length_difference_costs: TomlCostFunction<isize>,
+
+    primary_edit_costs: TomlCostTable,
+    secondary_edit_costs: TomlCostTable,
+    left_flank_edit_costs: TomlCostTable,
+    right_flank_edit_costs: TomlCostTable,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    forward_secondary_edit_costs: Option<TomlCostTable>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duplication_costs: Option<TomlCostFunction<usize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inversion_costs: Option<TomlCostFunction<usize>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlCostTable {
+    /// The characters of the alphabet, in the order of the rows and columns of the costs.
+    alphabet: String,
+    substitution_costs: Vec<Vec<TomlCost>>,
+    gap_open_costs: Vec<TomlCost>,
+    gap_extend_costs: Vec<TomlCost>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deletion_gap_open_costs: Option<Vec<TomlCost>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deletion_gap_extend_costs: Option<Vec<TomlCost>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    long_gap_open_costs: Option<Vec<TomlCost>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    long_gap_extend_costs: Option<Vec<TomlCost>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    homopolymer_gap_extend_costs: Option<TomlCostFunction<usize>>,
+    /// The substitution costs after the reference character of the key, in the same layout as the substitution costs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    context_substitution_costs: BTreeMap<String, Vec<Vec<TomlCost>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlCostFunction<SourceType> {
+    inputs: Vec<TomlInput<SourceType>>,
+    costs: Vec<TomlCost>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TomlCost {
+    Finite(u64),
+    Infinite(Infinity),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TomlInput<SourceType> {
+    Finite(SourceType),
+    Infinite(SignedInfinity),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Infinity {
+    #[serde(rename = "inf")]
+    Positive,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SignedInfinity {
+    #[serde(rename = "inf")]
+    Positive,
+    #[serde(rename = "-inf")]
+    Negative,
+}
+
+impl<AlphabetType: Alphabet> TemplateSwitchConfig<AlphabetType> {
+    /// Writes the config as TOML, in the format read by [`read_toml`](Self::read_toml).
+    pub fn write_toml(&self, mut writer: impl Write) -> Result<()> {
+        let config = TomlConfig {
+            left_flank_length: self.left_flank_length,
+            right_flank_length: self.right_flank_length,
+            ts_end_exclusion: self.ts_end_exclusion,
+            base_cost: TomlCost::from(self.base_cost),
+            forward_base_cost: self.forward_base_cost.map(TomlCost::from),
+
+            offset_costs: TomlCostFunction::from(&self.offset_costs),
+            length_costs: TomlCostFunction::from(&self.length_costs),
+            length_difference_costs: TomlCostFunction::from(&self.length_difference_costs),
+
+            primary_edit_costs: TomlCostTable::from(&self.primary_edit_costs),
+            secondary_edit_costs: TomlCostTable::from(&self.secondary_edit_costs),
+            left_flank_edit_costs: TomlCostTable::from(&self.left_flank_edit_costs),
+            right_flank_edit_costs: TomlCostTable::from(&self.right_flank_edit_costs),
+            forward_secondary_edit_costs: self
+                .forward_secondary_edit_costs
+                .as_ref()
+                .map(TomlCostTable::from),
+
+            duplication_costs: self.duplication_costs.as_ref().map(TomlCostFunction::from),
+            inversion_costs: self.inversion_costs.as_ref().map(TomlCostFunction::from),
+        };
+
+        let output = toml::to_string(&config).map_err(|error| Error::InvalidTomlConfig {
+            message: error.to_string(),
+        })?;
+        writer.write_all(output.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a config written by [`write_toml`](Self::write_toml).
+    pub fn read_toml(mut reader: impl Read) -> Result<Self> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        let config: TomlConfig =
+            toml::from_str(&input).map_err(|error| Error::InvalidTomlConfig {
+                message: error.to_string(),
+            })?;
+
+        let length_costs = config.length_costs.try_into_cost_function("length_costs")?;
+
+        Ok(Self {
+            left_flank_length: config.left_flank_length,
+            right_flank_length: config.right_flank_length,
+            min_length: length_costs.minimum_finite_input().unwrap_or(usize::MAX),
+            ts_end_exclusion: config.ts_end_exclusion,
+            directions: Default::default(),
+            anchor_repeat_mask: None,
+            anchor_scheme: Default::default(),
+
+            base_cost: config.base_cost.into(),
+            forward_base_cost: config.forward_base_cost.map(Into::into),
+
+            primary_edit_costs: config
+                .primary_edit_costs
+                .try_into_cost_table("Primary Edit Costs")?,
+            secondary_edit_costs: config
+                .secondary_edit_costs
+                .try_into_cost_table("Secondary Edit Costs")?,
+            left_flank_edit_costs: config
+                .left_flank_edit_costs
+                .try_into_cost_table("Left Flank Edit Costs")?,
+            right_flank_edit_costs: config
+                .right_flank_edit_costs
+                .try_into_cost_table("Right Flank Edit Costs")?,
+            forward_secondary_edit_costs: config
+                .forward_secondary_edit_costs
+                .map(|costs| costs.try_into_cost_table("Forward Secondary Edit Costs"))
+                .transpose()?,
+
+            offset_costs: config.offset_costs.try_into_cost_function("offset_costs")?,
+            length_costs,
+            length_difference_costs: config
+                .length_difference_costs
+                .try_into_cost_function("length_difference_costs")?,
+
+            duplication_costs: config
+                .duplication_costs
+                .map(|costs| costs.try_into_cost_function("duplication_costs"))
+                .transpose()?,
+            inversion_costs: config
+                .inversion_costs
+                .map(|costs| costs.try_into_cost_function("inversion_costs"))
+                .transpose()?,
+        })
+    }
+}
+
+impl<AlphabetType: Alphabet> From<&GapAffineAlignmentCostTable<AlphabetType>> for TomlCostTable {
+    fn from(table: &GapAffineAlignmentCostTable<AlphabetType>) -> Self {
+        let characters = alphabet_characters::<AlphabetType>();
+        let vector = |cost: &dyn Fn(AlphabetType::CharacterType) -> Cost| {
+            characters
+                .iter()
+                .map(|character| TomlCost::from(cost(character.clone())))
+                .collect()
+        };
+
+        let substitution_costs = |previous: Option<&AlphabetType::CharacterType>| {
+            characters
+                .iter()
+                .map(|c1| {
+                    vector(&|c2| {
+                        table.context_match_or_substitution_cost(previous.cloned(), c1.clone(), c2)
+                    })
+                })
+                .collect()
+        };
+
+        Self {
+            alphabet: characters.iter().map(ToString::to_string).collect(),
+            substitution_costs: substitution_costs(None),
+            gap_open_costs: vector(&|c| table.gap_open_cost(c, IndelType::Insertion)),
+            gap_extend_costs: vector(&|c| table.gap_extend_cost(c, IndelType::Insertion)),
+            deletion_gap_open_costs: (!table.has_symmetric_gaps())
+                .then(|| vector(&|c| table.gap_open_cost(c, IndelType::Deletion))),
+            deletion_gap_extend_costs: (!table.has_symmetric_gaps())
+                .then(|| vector(&|c| table.gap_extend_cost(c, IndelType::Deletion))),
+            long_gap_open_costs: table
+                .has_long_gaps()
+                .then(|| vector(&|c| table.long_gap_open_cost(c))),
+            long_gap_extend_costs: table
+                .has_long_gaps()
+                .then(|| vector(&|c| table.long_gap_extend_cost(c))),
+            homopolymer_gap_extend_costs: table
+                .homopolymer_gap_extend_costs()
+                .map(TomlCostFunction::from),
+            context_substitution_costs: characters
+                .iter()
+                .filter(|&previous| {
+                    table
+                        .context_substitution_cost_table(previous.clone())
+                        .is_some()
+                })
+                .map(|previous| (previous.to_string(), substitution_costs(Some(previous))))
+                .collect(),
+        }
+    }
+}
+
+impl TomlCostTable {
+    fn try_into_cost_table<AlphabetType: Alphabet>(
+        self,
+        name: &str,
+    ) -> Result<GapAffineAlignmentCostTable<AlphabetType>> {
+        let invalid = |message: String| Error::InvalidTomlConfig {
+            message: format!("the cost table '{name}' {message}"),
+        };
+        let expected_alphabet: String = alphabet_characters::<AlphabetType>()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if self.alphabet != expected_alphabet {
+            return Err(invalid(format!(
+                "has the alphabet '{}', but expected '{expected_alphabet}'",
+                self.alphabet
+            )));
+        }
+
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        let vector = |costs: Vec<TomlCost>, vector_name: &str| {
+            if costs.len() == alphabet_size {
+                Ok(costs.into_iter().map(Cost::from).collect::<Vec<_>>())
+            } else {
+                Err(invalid(format!(
+                    "has {} {vector_name}, but expected {alphabet_size}",
+                    costs.len()
+                )))
+            }
+        };
+
+        let substitution_cost_table = |rows: Vec<Vec<TomlCost>>| {
+            if rows.len() != alphabet_size {
+                return Err(invalid(format!(
+                    "has {} rows of substitution costs, but expected {alphabet_size}",
+                    rows.len()
+                )));
+            }
+            let mut substitution_cost_table = Vec::with_capacity(alphabet_size * alphabet_size);
+            for row in rows {
+                substitution_cost_table.extend(vector(row, "substitution costs in a row")?);
+            }
+            Ok(substitution_cost_table)
+        };
+
+        let mut table = GapAffineAlignmentCostTable::new(
+            name,
+            substitution_cost_table(self.substitution_costs)?,
+            vector(self.gap_open_costs, "gap open costs")?,
+            vector(self.gap_extend_costs, "gap extend costs")?,
+        );
+
+        match (self.deletion_gap_open_costs, self.deletion_gap_extend_costs) {
+            (Some(deletion_gap_open_costs), Some(deletion_gap_extend_costs)) => {
+                table = table.with_deletion_gap_cost_vectors(
+                    vector(deletion_gap_open_costs, "deletion gap open costs")?,
+                    vector(deletion_gap_extend_costs, "deletion gap extend costs")?,
+                );
+            }
+            (None, None) => {}
+            _ => {
+                return Err(invalid(
+                    "has only one of the deletion gap open and extend costs".to_string(),
+                ))
+            }
+        }
+
+        match (self.long_gap_open_costs, self.long_gap_extend_costs) {
+            (Some(long_gap_open_costs), Some(long_gap_extend_costs)) => {
+                table = table.with_long_gap_cost_vectors(
+                    vector(long_gap_open_costs, "long gap open costs")?,
+                    vector(long_gap_extend_costs, "long gap extend costs")?,
+                );
+            }
+            (None, None) => {}
+            _ => {
+                return Err(invalid(
+                    "has only one of the long gap open and extend costs".to_string(),
+                ))
+            }
+        }
+
+        for (previous, rows) in self.context_substitution_costs {
+            let Some(previous) = alphabet_characters::<AlphabetType>()
+                .into_iter()
+                .find(|character| character.to_string() == previous)
+            else {
+                return Err(invalid(format!(
+                    "has context substitution costs after '{previous}', which is not a character of the alphabet"
+                )));
+            };
+            table = table
+                .with_context_substitution_cost_table(previous, substitution_cost_table(rows)?);
+        }
+
+        if let Some(homopolymer_gap_extend_costs) = self.homopolymer_gap_extend_costs {
+            let homopolymer_gap_extend_costs = homopolymer_gap_extend_costs
+                .try_into_cost_function("homopolymer_gap_extend_costs")?;
+            if homopolymer_gap_extend_costs.min(..=1).is_none() {
+                return Err(invalid(
+                    "has homopolymer gap extend costs that are not defined at one".to_string(),
+                ));
+            }
+            table = table.with_homopolymer_gap_extend_costs(homopolymer_gap_extend_costs);
+        }
+
+        Ok(table)
+    }
+}
+
+impl<SourceType: PrimInt> From<&CostFunction<SourceType>> for TomlCostFunction<SourceType> {
+    fn from(function: &CostFunction<SourceType>) -> Self {
+        let (inputs, costs) = Vec::from(function.clone())
+            .into_iter()
+            .map(|(input, cost)| {
+                let input = if input == SourceType::max_value() {
+                    TomlInput::Infinite(SignedInfinity::Positive)
+                } else if input == SourceType::min_value() && input < SourceType::zero() {
+                    TomlInput::Infinite(SignedInfinity::Negative)
+                } else {
+                    TomlInput::Finite(input)
+                };
+                (input, TomlCost::from(cost))
+            })
+            .unzip();
+        Self { inputs, costs }
+    }
+}
+
+impl<SourceType: PrimInt> TomlCostFunction<SourceType> {
+    fn try_into_cost_function(self, name: &str) -> Result<CostFunction<SourceType>> {
+        if self.inputs.len() != self.costs.len() {
+            return Err(Error::InvalidTomlConfig {
+                message: format!(
+                    "the cost function '{name}' has {} inputs, but {} costs",
+                    self.inputs.len(),
+                    self.costs.len()
+                ),
+            });
+        }
+
+        self.inputs
+            .into_iter()
+            .zip(self.costs)
+            .map(|(input, cost)| {
+                let input = match input {
+                    TomlInput::Finite(input) => input,
+                    TomlInput::Infinite(SignedInfinity::Positive) => SourceType::max_value(),
+                    TomlInput::Infinite(SignedInfinity::Negative) => SourceType::min_value(),
+                };
+                (input, Cost::from(cost))
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+    }
+}
+
+impl From<Cost> for TomlCost {
+    fn from(cost: Cost) -> Self {
+        if cost == Cost::MAX {
+            Self::Infinite(Infinity::Positive)
+        } else {
+            Self::Finite(cost.as_u64())
+        }
+    }
+}
+
+impl From<TomlCost> for Cost {
+    fn from(cost: TomlCost) -> Self {
+        match cost {
+            TomlCost::Finite(cost) => cost.into(),
+            TomlCost::Infinite(Infinity::Positive) => Cost::MAX,
+        }
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+fn alphabet_characters<AlphabetType: Alphabet>() -> Vec<AlphabetType::CharacterType> {
+    (0..AlphabetType::SIZE)
+        .map(|index| AlphabetType::CharacterType::from_index(index).unwrap())
+        .collect()
+}
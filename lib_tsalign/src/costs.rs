@@ -1,5 +1,7 @@
+pub mod ambiguity;
 pub mod cost_function;
 pub mod gap_affine;
+pub mod score;
 
 pub mod cost {
     pub use generic_a_star::cost::Cost;
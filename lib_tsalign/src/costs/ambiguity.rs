@@ -0,0 +1,253 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter};
+
+use crate::{
+    config::TemplateSwitchConfig,
+    error::{Error, Result},
+};
+
+use super::{
+    cost::Cost,
+    gap_affine::{GapAffineAlignmentCostTable, IndelType},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// How ambiguous characters such as `N` or other IUPAC codes are aligned.
+///
+/// The costs of an ambiguous character are derived from the costs of the unambiguous bases it represents.
+/// For example, `R` represents `A` and `G`, and `N` represents all four bases.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AmbiguitySemantics {
+    /// Ambiguous characters get the cheapest cost of any represented base, so they match any of them.
+    MatchAny,
+    /// Ambiguous characters get the most expensive cost of any represented base, so they never match.
+    MismatchAlways,
+    /// Ambiguous characters get the average cost of all represented bases, rounded to the nearest integer.
+    Probabilistic,
+}
+
+impl AmbiguitySemantics {
+    pub const ALL: [Self; 3] = [Self::MatchAny, Self::MismatchAlways, Self::Probabilistic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MatchAny => "match-any",
+            Self::MismatchAlways => "mismatch-always",
+            Self::Probabilistic => "probabilistic",
+        }
+    }
+
+    fn combine(&self, costs: impl IntoIterator<Item = Cost>) -> Cost {
+        let mut costs = costs.into_iter();
+        match self {
+            Self::MatchAny => costs.min().unwrap(),
+            Self::MismatchAlways => costs.max().unwrap(),
+            Self::Probabilistic => costs
+                .try_fold((0u128, 0u128), |(sum, count), cost| {
+                    (cost != Cost::MAX).then(|| (sum + u128::from(cost.as_u64()), count + 1))
+                })
+                .map_or(Cost::MAX, |(sum, count)| {
+                    u64::try_from((sum + count / 2) / count).unwrap().into()
+                }),
+        }
+    }
+}
+
+/// Returns the unambiguous bases represented by an IUPAC nucleotide code.
+///
+/// Both `T` and `U` are returned for thymine or uracil, since alphabets contain at most one of them.
+/// Returns `None` for characters that are not IUPAC nucleotide codes.
+fn represented_bases(ascii: u8) -> Option<&'static [u8]> {
+    Some(match ascii.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"TU",
+        b'R' => b"AG",
+        b'Y' => b"CTU",
+        b'S' => b"CG",
+        b'W' => b"ATU",
+        b'K' => b"GTU",
+        b'M' => b"AC",
+        b'B' => b"CGTU",
+        b'D' => b"AGTU",
+        b'H' => b"ACTU",
+        b'V' => b"ACG",
+        b'N' => b"ACGTU",
+        _ => return None,
+    })
+}
+
+/// Returns true if the character is not one of `A`, `C`, `G`, `T` and `U`.
+///
+/// This matches the characters that get no chaining anchors.
+pub fn is_ambiguous<AlphabetType: Alphabet>(character: AlphabetType::CharacterType) -> bool {
+    !b"ACGTU".contains(&AlphabetType::character_to_ascii(character).to_ascii_uppercase())
+}
+
+/// Returns the unambiguous characters of the alphabet that are represented by `character`.
+///
+/// Returns an empty vector if `character` is unambiguous or represents no character of the alphabet.
+fn represented_characters<AlphabetType: Alphabet>(
+    character: AlphabetType::CharacterType,
+) -> Vec<AlphabetType::CharacterType> {
+    if !is_ambiguous::<AlphabetType>(character.clone()) {
+        return Vec::new();
+    }
+
+    represented_bases(AlphabetType::character_to_ascii(character))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|&base| AlphabetType::ascii_to_character(base).ok())
+        .collect()
+}
+
+impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
+    /// Derives the costs of ambiguous characters from the costs of the bases they represent.
+    ///
+    /// The costs of unambiguous characters are not changed, and neither are the costs of ambiguous characters
+    /// that represent no base of the alphabet.
+    pub fn with_ambiguity_semantics(self, semantics: AmbiguitySemantics) -> Self {
+        // Unambiguous characters represent only themselves, such that their costs stay the same.
+        let represented: Vec<_> = AlphabetType::iter()
+            .map(|character| {
+                let represented = represented_characters::<AlphabetType>(character.clone());
+                if represented.is_empty() {
+                    vec![character]
+                } else {
+                    represented
+                }
+            })
+            .collect();
+
+        // A context of `None` stands for the substitution cost table.
+        let substitution_cost_table = |previous: Option<&[AlphabetType::CharacterType]>| {
+            AlphabetType::iter()
+                .flat_map(|c1| AlphabetType::iter().map(move |c2| (c1.clone(), c2)))
+                .map(|(c1, c2)| {
+                    let represented1 = &represented[usize::from(c1.index())];
+                    let represented2 = &represented[usize::from(c2.index())];
+                    let previous = previous.map_or(vec![None], |previous| {
+                        previous.iter().cloned().map(Some).collect()
+                    });
+                    let mut costs = Vec::new();
+                    for previous in previous {
+                        for c1 in represented1 {
+                            for c2 in represented2 {
+                                costs.push(self.context_match_or_substitution_cost(
+                                    previous.clone(),
+                                    c1.clone(),
+                                    c2.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    semantics.combine(costs)
+                })
+                .collect::<Vec<_>>()
+        };
+        let gap_cost_vector = |cost: &dyn Fn(AlphabetType::CharacterType) -> Cost| {
+            represented
+                .iter()
+                .map(|represented| semantics.combine(represented.iter().cloned().map(cost)))
+                .collect::<Vec<_>>()
+        };
+        let gap_open_cost_vector =
+            |indel_type| gap_cost_vector(&|c| self.gap_open_cost(c, indel_type));
+        let gap_extend_cost_vector =
+            |indel_type| gap_cost_vector(&|c| self.gap_extend_cost(c, indel_type));
+        let long_gap_open_cost_vector = gap_cost_vector(&|c| self.long_gap_open_cost(c));
+        let long_gap_extend_cost_vector = gap_cost_vector(&|c| self.long_gap_extend_cost(c));
+
+        let mut table = Self::new(
+            self.name(),
+            substitution_cost_table(None),
+            gap_open_cost_vector(IndelType::Insertion),
+            gap_extend_cost_vector(IndelType::Insertion),
+        )
+        .with_deletion_gap_cost_vectors(
+            gap_open_cost_vector(IndelType::Deletion),
+            gap_extend_cost_vector(IndelType::Deletion),
+        )
+        .with_long_gap_cost_vectors(long_gap_open_cost_vector, long_gap_extend_cost_vector);
+        // Ambiguous contexts get a context substitution cost table if any of their bases has one.
+        for (previous, represented_previous) in AlphabetType::iter().zip(&represented) {
+            if represented_previous.iter().any(|previous| {
+                self.context_substitution_cost_table(previous.clone())
+                    .is_some()
+            }) {
+                table = table.with_context_substitution_cost_table(
+                    previous,
+                    substitution_cost_table(Some(represented_previous)),
+                );
+            }
+        }
+        match self.homopolymer_gap_extend_costs() {
+            Some(homopolymer_gap_extend_costs) => {
+                table.with_homopolymer_gap_extend_costs(homopolymer_gap_extend_costs.clone())
+            }
+            None => table,
+        }
+    }
+
+    /// Returns the minimum cost of aligning two characters that are not known to be equal.
+    ///
+    /// Next to substitutions, this includes aligning an ambiguous character to itself.
+    pub fn min_substitution_or_ambiguous_match_cost(&self) -> Cost {
+        AlphabetType::iter()
+            .filter(|character| is_ambiguous::<AlphabetType>(character.clone()))
+            .map(|character| self.match_cost(character.clone(), character))
+            .fold(self.min_substitution_cost(), Cost::min)
+    }
+}
+
+impl<AlphabetType: Alphabet> TemplateSwitchConfig<AlphabetType> {
+    /// Applies [`GapAffineAlignmentCostTable::with_ambiguity_semantics`] to all edit cost tables.
+    pub fn with_ambiguity_semantics(self, semantics: AmbiguitySemantics) -> Self {
+        Self {
+            primary_edit_costs: self.primary_edit_costs.with_ambiguity_semantics(semantics),
+            secondary_edit_costs: self
+                .secondary_edit_costs
+                .with_ambiguity_semantics(semantics),
+            left_flank_edit_costs: self
+                .left_flank_edit_costs
+                .with_ambiguity_semantics(semantics),
+            right_flank_edit_costs: self
+                .right_flank_edit_costs
+                .with_ambiguity_semantics(semantics),
+            forward_secondary_edit_costs: self
+                .forward_secondary_edit_costs
+                .map(|costs| costs.with_ambiguity_semantics(semantics)),
+            ..self
+        }
+    }
+}
+
+impl FromStr for AmbiguitySemantics {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|semantics| semantics.name() == name)
+            .ok_or_else(|| Error::UnknownAmbiguitySemantics {
+                name: name.to_string(),
+                available: Self::ALL
+                    .iter()
+                    .map(|semantics| semantics.name().to_string())
+                    .collect(),
+            })
+    }
+}
+
+impl Display for AmbiguitySemantics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,120 @@
+use compact_genome::{
+    implementation::alphabets::{
+        dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+        dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+    },
+    interface::alphabet::Alphabet,
+};
+
+use crate::costs::{
+    cost::Cost,
+    gap_affine::{GapAffineAlignmentCostTable, IndelType},
+};
+
+use super::AmbiguitySemantics;
+
+fn uniform<AlphabetType: Alphabet>() -> GapAffineAlignmentCostTable<AlphabetType> {
+    GapAffineAlignmentCostTable::new_uniform("uniform", 4.into(), 3.into(), 1.into())
+}
+
+fn cost<AlphabetType: Alphabet>(
+    table: &GapAffineAlignmentCostTable<AlphabetType>,
+    c1: u8,
+    c2: u8,
+) -> u64 {
+    table
+        .match_or_substitution_cost(
+            AlphabetType::ascii_to_character(c1).unwrap(),
+            AlphabetType::ascii_to_character(c2).unwrap(),
+        )
+        .as_u64()
+}
+
+#[test]
+fn semantics_names_round_trip() {
+    for semantics in AmbiguitySemantics::ALL {
+        assert_eq!(
+            semantics.name().parse::<AmbiguitySemantics>().unwrap(),
+            semantics
+        );
+    }
+
+    assert!("unknown".parse::<AmbiguitySemantics>().is_err());
+}
+
+#[test]
+fn n_semantics() {
+    for (semantics, n_to_a, n_to_n) in [
+        (AmbiguitySemantics::MatchAny, 0, 0),
+        (AmbiguitySemantics::MismatchAlways, 4, 4),
+        (AmbiguitySemantics::Probabilistic, 3, 3),
+    ] {
+        let table = uniform::<DnaAlphabetOrN>().with_ambiguity_semantics(semantics);
+
+        assert_eq!(cost(&table, b'N', b'A'), n_to_a, "{semantics}");
+        assert_eq!(cost(&table, b'T', b'N'), n_to_a, "{semantics}");
+        assert_eq!(cost(&table, b'N', b'N'), n_to_n, "{semantics}");
+        assert_eq!(cost(&table, b'A', b'A'), 0, "{semantics}");
+        assert_eq!(cost(&table, b'A', b'C'), 4, "{semantics}");
+        assert_eq!(
+            table.gap_open_cost(
+                DnaAlphabetOrN::ascii_to_character(b'N').unwrap(),
+                IndelType::Insertion
+            ),
+            3.into()
+        );
+    }
+}
+
+#[test]
+fn iupac_semantics() {
+    let match_any = uniform::<DnaIupacNucleicAcidAlphabet>()
+        .with_ambiguity_semantics(AmbiguitySemantics::MatchAny);
+    assert_eq!(cost(&match_any, b'R', b'G'), 0);
+    assert_eq!(cost(&match_any, b'R', b'Y'), 4);
+    assert_eq!(cost(&match_any, b'B', b'Y'), 0);
+
+    let probabilistic = uniform::<DnaIupacNucleicAcidAlphabet>()
+        .with_ambiguity_semantics(AmbiguitySemantics::Probabilistic);
+    assert_eq!(cost(&probabilistic, b'R', b'A'), 2);
+    assert_eq!(cost(&probabilistic, b'R', b'R'), 2);
+    assert_eq!(cost(&probabilistic, b'R', b'Y'), 4);
+    assert!(probabilistic.is_symmetric());
+}
+
+#[test]
+fn probabilistic_infinite_costs() {
+    let table = GapAffineAlignmentCostTable::<DnaAlphabetOrN>::new_uniform(
+        "infinite",
+        Cost::MAX,
+        3.into(),
+        1.into(),
+    )
+    .with_ambiguity_semantics(AmbiguitySemantics::Probabilistic);
+
+    assert_eq!(cost(&table, b'N', b'A'), u64::MAX);
+    assert_eq!(cost(&table, b'A', b'A'), 0);
+}
+
+#[test]
+fn unambiguous_alphabet_is_unchanged() {
+    for semantics in AmbiguitySemantics::ALL {
+        assert_eq!(
+            uniform::<DnaAlphabet>().with_ambiguity_semantics(semantics),
+            uniform::<DnaAlphabet>()
+        );
+    }
+}
+
+#[test]
+fn min_substitution_or_ambiguous_match_cost() {
+    // Uniform tables make `N` free to match with itself, but not with other characters.
+    assert_eq!(
+        uniform::<DnaAlphabetOrN>().min_substitution_or_ambiguous_match_cost(),
+        Cost::ZERO
+    );
+    assert_eq!(
+        uniform::<DnaAlphabet>().min_substitution_or_ambiguous_match_cost(),
+        4.into()
+    );
+}
@@ -6,6 +6,7 @@ use crate::error::Error;
 
 use super::cost::Cost;
 
+mod builder;
 pub mod io;
 
 /// A step-wise cost funtion.
@@ -277,4 +278,133 @@ mod tests {
         .map(|(range, cost)| (range, cost.map(Cost::from)))
         .all(|(range, min)| cost_function.min(range) == min));
     }
+
+    #[test]
+    fn new_affine() {
+        let cost_function = CostFunction::<isize>::new_affine(-2..3, 4.into(), 2.into());
+        assert_eq!(
+            Vec::from(cost_function.clone()),
+            vec![
+                (isize::MIN, Cost::MAX),
+                (-2, 4.into()),
+                (-1, 6.into()),
+                (0, 8.into()),
+                (1, 10.into()),
+                (2, 12.into()),
+                (3, Cost::MAX),
+            ]
+        );
+
+        let constant = CostFunction::<usize>::new_affine(0..usize::MAX, 1.into(), Cost::ZERO);
+        assert_eq!(Vec::from(constant), vec![(0, 1.into())]);
+    }
+
+    #[test]
+    fn from_samples() {
+        let cost_function = CostFunction::<isize>::from_samples([
+            (5, 2.into()),
+            (-5, 1.into()),
+            (0, 1.into()),
+            (10, Cost::MAX),
+        ])
+        .unwrap();
+        assert_eq!(
+            Vec::from(cost_function),
+            vec![
+                (isize::MIN, Cost::MAX),
+                (-5, 1.into()),
+                (5, 2.into()),
+                (10, Cost::MAX),
+            ]
+        );
+
+        assert!(CostFunction::<isize>::from_samples([(0, 1.into()), (0, 2.into())]).is_err());
+    }
+
+    #[test]
+    fn pointwise_add_and_min() {
+        let a =
+            CostFunction::<isize>::from_samples([(-4, 3.into()), (0, 1.into()), (4, Cost::MAX)])
+                .unwrap();
+        let b = CostFunction::<isize>::new_affine(-2..6, 0.into(), 1.into());
+
+        for input in -10..10 {
+            let (a_cost, b_cost) = (a.evaluate(&input), b.evaluate(&input));
+            let sum = if a_cost == Cost::MAX || b_cost == Cost::MAX {
+                Cost::MAX
+            } else {
+                a_cost + b_cost
+            };
+            assert_eq!(a.pointwise_add(&b).evaluate(&input), sum);
+            assert_eq!(a.pointwise_min(&b).evaluate(&input), a_cost.min(b_cost));
+        }
+    }
+
+    #[test]
+    #[expect(clippy::reversed_empty_ranges)]
+    fn evaluate_range() {
+        let cost_function = CostFunction::try_from(vec![
+            (2, Cost::from(100)),
+            (3, Cost::from(1)),
+            (4, Cost::from(2)),
+            (6, Cost::from(1)),
+            (8, Cost::from(3)),
+        ])
+        .unwrap();
+
+        for start in 2..12 {
+            for end in start..12 {
+                assert_eq!(
+                    cost_function.evaluate_range(start..end),
+                    (start..end)
+                        .map(|input| cost_function.evaluate(&input))
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+        assert!(cost_function.evaluate_range(5..0).is_empty());
+    }
+
+    #[test]
+    fn write_plain_round_trip() {
+        fn round_trip<SourceType: num_traits::PrimInt + std::fmt::Display + std::fmt::Debug>(
+            cost_function: CostFunction<SourceType>,
+        ) {
+            let mut writer = Vec::new();
+            cost_function.write_plain(&mut writer).unwrap();
+            let output = String::from_utf8(writer).unwrap();
+            let (remaining_input, parsed) = CostFunction::parse_plain(&output).unwrap();
+
+            assert!(remaining_input.is_empty());
+            assert_eq!(parsed, cost_function, "{output}");
+        }
+
+        let affine = CostFunction::<isize>::new_affine(-3..4, 2.into(), 3.into());
+        let samples = CostFunction::<isize>::from_samples([
+            (-100, 5.into()),
+            (-10, 0.into()),
+            (11, 5.into()),
+            (100, Cost::MAX),
+        ])
+        .unwrap();
+        round_trip(affine.pointwise_add(&samples));
+        round_trip(affine.pointwise_min(&samples));
+        round_trip(affine);
+        round_trip(samples);
+
+        let length_costs = CostFunction::<usize>::from_samples([
+            (5, 5.into()),
+            (6, 3.into()),
+            (8, 0.into()),
+            (100, Cost::MAX),
+        ])
+        .unwrap();
+        round_trip(CostFunction::<usize>::new_affine(0..10, 0.into(), 1.into()));
+        round_trip(length_costs.pointwise_add(&CostFunction::new_affine(
+            3..50,
+            1.into(),
+            Cost::ZERO,
+        )));
+        round_trip(length_costs);
+    }
 }
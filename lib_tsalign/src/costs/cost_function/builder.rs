@@ -0,0 +1,172 @@
+use std::ops::{Add, Range};
+
+use num_traits::{Bounded, One};
+
+use crate::{costs::cost::Cost, error::Result};
+
+use super::CostFunction;
+
+impl<SourceType: Clone + Ord + Bounded + One + Add<Output = SourceType>> CostFunction<SourceType> {
+    /// Constructs an affine cost function that is infinite outside of `range`.
+    ///
+    /// Inside of `range`, the cost of `input` is `base_cost + (input - range.start) * slope`, saturating at `Cost::MAX`.
+    /// The function stores one point per input in `range` until the cost stops changing, so for a non-zero slope the range should be small.
+    pub fn new_affine(range: Range<SourceType>, base_cost: Cost, slope: Cost) -> Self {
+        let mut function = vec![(SourceType::min_value(), Cost::MAX)];
+        let mut input = range.start;
+        let mut cost = base_cost;
+        while input < range.end {
+            function.push((input.clone(), cost));
+            if slope == Cost::ZERO || cost == Cost::MAX {
+                // The cost does not change anymore.
+                break;
+            }
+            input = input + SourceType::one();
+            cost = cost.as_u64().saturating_add(slope.as_u64()).into();
+        }
+        if range.end < SourceType::max_value() {
+            function.push((range.end, Cost::MAX));
+        }
+
+        Self::new_normalised(function)
+    }
+
+    /// Constructs a cost function from samples of its step points.
+    ///
+    /// The samples do not need to be sorted, but their inputs must be distinct.
+    /// The function is infinite before the first sample.
+    pub fn from_samples(samples: impl IntoIterator<Item = (SourceType, Cost)>) -> Result<Self> {
+        let mut samples: Vec<_> = samples.into_iter().collect();
+        samples.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let function = Self::try_from(samples)?.function;
+
+        Ok(Self::new_normalised(
+            [(SourceType::min_value(), Cost::MAX)]
+                .into_iter()
+                .chain(function)
+                .collect(),
+        ))
+    }
+
+    /// Returns the pointwise sum of this function and `other`.
+    ///
+    /// Inputs outside of the domain of either function get infinite cost.
+    pub fn pointwise_add(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| {
+            if a == Cost::MAX || b == Cost::MAX {
+                Cost::MAX
+            } else {
+                a.as_u64().saturating_add(b.as_u64()).into()
+            }
+        })
+    }
+
+    /// Returns the pointwise minimum of this function and `other`.
+    ///
+    /// Inputs outside of the domain of either function get infinite cost.
+    pub fn pointwise_min(&self, other: &Self) -> Self {
+        self.combine(other, Ord::min)
+    }
+
+    /// Evaluates the function at all inputs in `range`.
+    ///
+    /// This is equivalent to calling [`evaluate`](Self::evaluate) for each input,
+    /// but walks through the steps of the function instead of searching them for each input.
+    ///
+    /// **Panics** if `range` is not empty and starts before the first entry in the cost function.
+    pub fn evaluate_range(&self, range: Range<SourceType>) -> Vec<Cost> {
+        let mut result = Vec::new();
+        if range.start >= range.end {
+            return result;
+        }
+
+        let mut index = match self
+            .function
+            .binary_search_by(|(input, _)| input.cmp(&range.start))
+        {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let mut input = range.start;
+        while input < range.end {
+            while self
+                .function
+                .get(index + 1)
+                .is_some_and(|(next_input, _)| next_input <= &input)
+            {
+                index += 1;
+            }
+            result.push(self.function[index].1);
+            input = input + SourceType::one();
+        }
+
+        result
+    }
+
+    pub(crate) fn combine(&self, other: &Self, operation: impl Fn(Cost, Cost) -> Cost) -> Self {
+        let mut inputs: Vec<_> = self
+            .function
+            .iter()
+            .chain(&other.function)
+            .map(|(input, _)| input.clone())
+            .chain([SourceType::min_value()])
+            .collect();
+        inputs.sort();
+        inputs.dedup();
+
+        Self::new_normalised(
+            inputs
+                .into_iter()
+                .map(|input| {
+                    let cost =
+                        operation(self.evaluate_or_max(&input), other.evaluate_or_max(&input));
+                    (input, cost)
+                })
+                .collect(),
+        )
+    }
+
+    fn evaluate_or_max(&self, input: &SourceType) -> Cost {
+        if self
+            .function
+            .first()
+            .is_some_and(|(first_input, _)| input >= first_input)
+        {
+            self.evaluate(input)
+        } else {
+            Cost::MAX
+        }
+    }
+
+    /// Removes points that do not change the cost.
+    ///
+    /// The first point of `function` must be at `SourceType::min_value()`, such that the result can be written and parsed again.
+    fn new_normalised(function: Vec<(SourceType, Cost)>) -> Self {
+        debug_assert!(function
+            .first()
+            .is_some_and(|(input, _)| input == &SourceType::min_value()));
+        debug_assert!(function.windows(2).all(|window| window[0].0 <= window[1].0));
+
+        let mut normalised: Vec<(SourceType, Cost)> = Vec::with_capacity(function.len());
+        for (input, cost) in function {
+            if normalised
+                .last()
+                .is_some_and(|(_, last_cost)| *last_cost == cost)
+            {
+                continue;
+            }
+            // A point at `SourceType::min_value()` replaces the infinite prefix.
+            if normalised
+                .last()
+                .is_some_and(|(last_input, _)| *last_input == input)
+            {
+                normalised.pop();
+            }
+            normalised.push((input, cost));
+        }
+
+        Self {
+            function: normalised,
+        }
+    }
+}
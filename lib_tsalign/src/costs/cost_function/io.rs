@@ -19,7 +19,7 @@ impl<SourceType: PrimInt + Display> CostFunction<SourceType> {
             .map(|(index, cost)| {
                 if index == &SourceType::max_value() {
                     3
-                } else if index == &SourceType::min_value() {
+                } else if is_negative_infinity(index) {
                     4
                 } else {
                     format!("{index}").len()
@@ -45,7 +45,7 @@ impl<SourceType: PrimInt + Display> CostFunction<SourceType> {
                     write!(writer, " ")?;
                 }
                 write!(writer, "inf")?;
-            } else if index == &SourceType::min_value() {
+            } else if is_negative_infinity(index) {
                 for _ in 4..column_width {
                     write!(writer, " ")?;
                 }
@@ -78,6 +78,11 @@ impl<SourceType: PrimInt + Display> CostFunction<SourceType> {
     }
 }
 
+/// Unsigned types have no negative infinity, so their minimum is written as a number.
+fn is_negative_infinity<SourceType: PrimInt>(index: &SourceType) -> bool {
+    index == &SourceType::min_value() && SourceType::min_value() < SourceType::zero()
+}
+
 impl<SourceType: PrimInt> CostFunction<SourceType> {
     pub(crate) fn parse_plain(input: &str) -> IResult<&str, Self> {
         let mut input = skip_any_whitespace(input)?;
@@ -2,31 +2,80 @@ use std::marker::PhantomData;
 
 use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter};
 
-use crate::costs::cost::Cost;
+use crate::costs::{cost::Cost, cost_function::CostFunction};
 
+mod arithmetic;
 pub mod io;
+pub mod score;
+
+#[cfg(test)]
+mod tests;
+
+/// Whether a gap is an insertion or a deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndelType {
+    /// Query characters that are missing from the reference.
+    Insertion,
+    /// Reference characters that are missing from the query.
+    Deletion,
+}
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct GapAffineAlignmentCostTable<AlphabetType> {
     name: String,
     substitution_cost_table: Vec<Cost>,
-    gap_open_cost_vector: Vec<Cost>,
-    gap_extend_cost_vector: Vec<Cost>,
+    insertion_gap_open_cost_vector: Vec<Cost>,
+    insertion_gap_extend_cost_vector: Vec<Cost>,
+    /// The gap costs of deletions, which are the same as the ones of insertions unless given separately.
+    ///
+    /// This models indel biases of sequencing platforms, for example.
+    deletion_gap_open_cost_vector: Vec<Cost>,
+    deletion_gap_extend_cost_vector: Vec<Cost>,
+    /// The gap open costs of the second piece of a two-piece affine gap model.
+    ///
+    /// Each gap is either a short gap with the normal gap costs or a long gap with the long gap costs,
+    /// whichever is cheaper.
+    /// Usually, long gaps are more expensive to open but cheaper to extend, like in minimap2.
+    /// Infinite costs disable long gaps.
+    long_gap_open_cost_vector: Vec<Cost>,
+    /// The gap extend costs of the second piece of a two-piece affine gap model.
+    long_gap_extend_cost_vector: Vec<Cost>,
+    /// Replaces the gap extend costs within homopolymer runs, evaluated at the length of the run.
+    ///
+    /// This models the error profiles of long reads, which often have indels that extend or shorten homopolymers.
+    homopolymer_gap_extend_costs: Option<CostFunction<usize>>,
+    /// Replaces the substitution cost table after the reference character with the same index, where given.
+    ///
+    /// This models context-dependent mutation rates, like the hypermutability of `C` in `CpG` dinucleotides.
+    /// The context is only known where the alignment steps forward in the reference,
+    /// so it is used only by primary and flank alignments.
+    context_substitution_cost_tables: Vec<Option<Vec<Cost>>>,
     phantom_data: PhantomData<AlphabetType>,
 }
 
 impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
+    /// Creates a new table in which insertions and deletions have the same gap costs.
     pub fn new(
         name: impl Into<String>,
         substitution_cost_table: impl Into<Vec<Cost>>,
         gap_open_cost_vector: impl Into<Vec<Cost>>,
         gap_extend_cost_vector: impl Into<Vec<Cost>>,
     ) -> Self {
+        let alphabet_size: usize = AlphabetType::SIZE.into();
+        let gap_open_cost_vector = gap_open_cost_vector.into();
+        let gap_extend_cost_vector = gap_extend_cost_vector.into();
+
         Self {
             name: name.into(),
             substitution_cost_table: substitution_cost_table.into(),
-            gap_open_cost_vector: gap_open_cost_vector.into(),
-            gap_extend_cost_vector: gap_extend_cost_vector.into(),
+            insertion_gap_open_cost_vector: gap_open_cost_vector.clone(),
+            insertion_gap_extend_cost_vector: gap_extend_cost_vector.clone(),
+            deletion_gap_open_cost_vector: gap_open_cost_vector,
+            deletion_gap_extend_cost_vector: gap_extend_cost_vector,
+            long_gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
+            long_gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; alphabet_size],
             phantom_data: Default::default(),
         }
     }
@@ -37,12 +86,32 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         Self {
             name: "new_zero".to_string(),
             substitution_cost_table: vec![Cost::ZERO; alphabet_size * alphabet_size],
-            gap_open_cost_vector: vec![Cost::ZERO; alphabet_size],
-            gap_extend_cost_vector: vec![Cost::ZERO; alphabet_size],
+            insertion_gap_open_cost_vector: vec![Cost::ZERO; alphabet_size],
+            insertion_gap_extend_cost_vector: vec![Cost::ZERO; alphabet_size],
+            deletion_gap_open_cost_vector: vec![Cost::ZERO; alphabet_size],
+            deletion_gap_extend_cost_vector: vec![Cost::ZERO; alphabet_size],
+            long_gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
+            long_gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; alphabet_size],
             phantom_data: Default::default(),
         }
     }
 
+    /// Creates a new table with free matches and the same substitution and gap costs for all characters.
+    pub fn new_uniform(
+        name: impl Into<String>,
+        substitution_cost: Cost,
+        gap_open_cost: Cost,
+        gap_extend_cost: Cost,
+    ) -> Self {
+        Self::new_zero()
+            .with_name(name)
+            .with_substitution_cost(substitution_cost)
+            .with_gap_open_cost(gap_open_cost)
+            .with_gap_extend_cost(gap_extend_cost)
+    }
+
     /// Creates a new table with all costs set to `Cost::MAX`.
     pub fn new_max() -> Self {
         let alphabet_size: usize = AlphabetType::SIZE.into();
@@ -50,8 +119,14 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         Self {
             name: "new_max".to_string(),
             substitution_cost_table: vec![Cost::MAX; alphabet_size * alphabet_size],
-            gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
-            gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            insertion_gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
+            insertion_gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            deletion_gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
+            deletion_gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            long_gap_open_cost_vector: vec![Cost::MAX; alphabet_size],
+            long_gap_extend_cost_vector: vec![Cost::MAX; alphabet_size],
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; alphabet_size],
             phantom_data: Default::default(),
         }
     }
@@ -95,63 +170,284 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         self.substitution_cost_table[c1 * usize::from(AlphabetType::SIZE) + c2]
     }
 
+    /// Like [`match_or_substitution_cost`](Self::match_or_substitution_cost),
+    /// but uses the context substitution cost table of the `previous` reference character, if it has one.
+    ///
+    /// `previous` is `None` at the start of the reference.
+    pub fn context_match_or_substitution_cost(
+        &self,
+        previous: Option<AlphabetType::CharacterType>,
+        c1: impl Into<AlphabetType::CharacterType>,
+        c2: impl Into<AlphabetType::CharacterType>,
+    ) -> Cost {
+        let Some(table) = previous.and_then(|previous| {
+            self.context_substitution_cost_tables[usize::from(previous.index())].as_ref()
+        }) else {
+            return self.match_or_substitution_cost(c1, c2);
+        };
+        let c1: usize = c1.into().index().into();
+        let c2: usize = c2.into().index().into();
+
+        table[c1 * usize::from(AlphabetType::SIZE) + c2]
+    }
+
+    /// Returns the context substitution cost table after the reference character `previous`, if it has one.
+    pub fn context_substitution_cost_table(
+        &self,
+        previous: impl Into<AlphabetType::CharacterType>,
+    ) -> Option<&[Cost]> {
+        self.context_substitution_cost_tables[usize::from(previous.into().index())].as_deref()
+    }
+
+    /// Returns true if some reference character has a context substitution cost table.
+    pub fn has_context_substitution_costs(&self) -> bool {
+        self.context_substitution_cost_tables
+            .iter()
+            .any(Option::is_some)
+    }
+
+    /// The minimum match cost, including the context substitution cost tables.
     pub fn min_match_cost(&self) -> Cost {
         AlphabetType::iter()
-            .map(|character| self.match_cost(character.clone(), character))
+            .flat_map(|character| {
+                self.substitution_cost_tables().map(move |table| {
+                    let index = usize::from(character.index());
+                    table[index * usize::from(AlphabetType::SIZE) + index]
+                })
+            })
             .min()
             .unwrap()
     }
 
+    /// The minimum substitution cost, including the context substitution cost tables.
     pub fn min_substitution_cost(&self) -> Cost {
-        AlphabetType::iter()
-            .flat_map(|c1| {
-                AlphabetType::iter().filter_map(move |c2| {
-                    if c1 != c2 {
-                        Some(self.substitution_cost(c1.clone(), c2))
-                    } else {
-                        None
-                    }
-                })
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        self.substitution_cost_tables()
+            .flat_map(|table| {
+                table
+                    .iter()
+                    .enumerate()
+                    .filter(move |(index, _)| index / alphabet_size != index % alphabet_size)
+                    .map(|(_, cost)| *cost)
             })
             .min()
             .unwrap()
     }
 
-    pub fn gap_open_cost(&self, c: impl Into<AlphabetType::CharacterType>) -> Cost {
-        self.gap_open_cost_vector[usize::from(c.into().index())]
+    /// Iterates over the substitution cost table and all context substitution cost tables.
+    fn substitution_cost_tables(&self) -> impl '_ + Iterator<Item = &[Cost]> {
+        [self.substitution_cost_table.as_slice()].into_iter().chain(
+            self.context_substitution_cost_tables
+                .iter()
+                .flatten()
+                .map(Vec::as_slice),
+        )
+    }
+
+    pub fn gap_open_cost(
+        &self,
+        c: impl Into<AlphabetType::CharacterType>,
+        indel_type: IndelType,
+    ) -> Cost {
+        let vector = match indel_type {
+            IndelType::Insertion => &self.insertion_gap_open_cost_vector,
+            IndelType::Deletion => &self.deletion_gap_open_cost_vector,
+        };
+        vector[usize::from(c.into().index())]
+    }
+
+    pub fn gap_extend_cost(
+        &self,
+        c: impl Into<AlphabetType::CharacterType>,
+        indel_type: IndelType,
+    ) -> Cost {
+        let vector = match indel_type {
+            IndelType::Insertion => &self.insertion_gap_extend_cost_vector,
+            IndelType::Deletion => &self.deletion_gap_extend_cost_vector,
+        };
+        vector[usize::from(c.into().index())]
+    }
+
+    pub fn gap_costs(
+        &self,
+        c: impl Into<AlphabetType::CharacterType>,
+        indel_type: IndelType,
+        is_first: bool,
+    ) -> Cost {
+        if is_first {
+            self.gap_open_cost(c, indel_type)
+        } else {
+            self.gap_extend_cost(c, indel_type)
+        }
+    }
+
+    /// Returns true if insertions and deletions have the same gap costs.
+    ///
+    /// Long gaps and homopolymer gaps always have the same costs for insertions and deletions.
+    pub fn has_symmetric_gaps(&self) -> bool {
+        self.insertion_gap_open_cost_vector == self.deletion_gap_open_cost_vector
+            && self.insertion_gap_extend_cost_vector == self.deletion_gap_extend_cost_vector
     }
 
-    pub fn gap_extend_cost(&self, c: impl Into<AlphabetType::CharacterType>) -> Cost {
-        self.gap_extend_cost_vector[usize::from(c.into().index())]
+    pub fn long_gap_open_cost(&self, c: impl Into<AlphabetType::CharacterType>) -> Cost {
+        self.long_gap_open_cost_vector[usize::from(c.into().index())]
     }
 
-    pub fn gap_costs(&self, c: impl Into<AlphabetType::CharacterType>, is_first: bool) -> Cost {
+    pub fn long_gap_extend_cost(&self, c: impl Into<AlphabetType::CharacterType>) -> Cost {
+        self.long_gap_extend_cost_vector[usize::from(c.into().index())]
+    }
+
+    pub fn long_gap_costs(
+        &self,
+        c: impl Into<AlphabetType::CharacterType>,
+        is_first: bool,
+    ) -> Cost {
         if is_first {
-            self.gap_open_cost(c)
+            self.long_gap_open_cost(c)
         } else {
-            self.gap_extend_cost(c)
+            self.long_gap_extend_cost(c)
         }
     }
 
+    /// Returns true if long gaps can be opened for some character.
+    pub fn has_long_gaps(&self) -> bool {
+        self.long_gap_open_cost_vector
+            .iter()
+            .any(|cost| *cost != Cost::MAX)
+    }
+
+    pub fn homopolymer_gap_extend_costs(&self) -> Option<&CostFunction<usize>> {
+        self.homopolymer_gap_extend_costs.as_ref()
+    }
+
+    pub fn has_homopolymer_gap_extend_costs(&self) -> bool {
+        self.homopolymer_gap_extend_costs.is_some()
+    }
+
+    /// Returns true if extending an alignment greedily along a run of matches never misses an optimal alignment,
+    /// i.e. if a match is never worse than leaving the diagonal.
+    ///
+    /// This holds if matches are free and gap costs do not depend on the character,
+    /// by the same argument as for the greedy extension of diagonals in the wavefront algorithm.
+    /// Runs are not known to be optimal if gaps in homopolymers or long gaps are cheaper than elsewhere,
+    /// if the gap costs of insertions and deletions differ, or if matches depend on their context.
+    pub fn allows_greedy_match_extension(&self) -> bool {
+        let Some(first_character) = AlphabetType::iter().next() else {
+            return false;
+        };
+        let gap_open_cost = self.gap_open_cost(first_character.clone(), IndelType::Insertion);
+        let gap_extend_cost = self.gap_extend_cost(first_character, IndelType::Insertion);
+
+        gap_open_cost >= gap_extend_cost
+            && !self.has_homopolymer_gap_extend_costs()
+            && !self.has_long_gaps()
+            && self.has_symmetric_gaps()
+            && !self.has_context_substitution_costs()
+            && AlphabetType::iter().all(|character| {
+                self.match_cost(character.clone(), character.clone()) == Cost::ZERO
+                    && self.gap_open_cost(character.clone(), IndelType::Insertion) == gap_open_cost
+                    && self.gap_extend_cost(character, IndelType::Insertion) == gap_extend_cost
+            })
+    }
+
+    /// Returns the cost of gapping `c` in a gap of the given `indel_type`, and the homopolymer length of the gap after gapping `c`.
+    ///
+    /// If `long_gap` is true, then the gap is a long gap of the two-piece affine gap model.
+    /// `previous` is the character gapped directly before `c`, or `None` if `c` opens a new gap.
+    /// `homopolymer_length` is the number of identical characters at the end of the gap before `c`.
+    /// If `c` extends such a run, then its cost is taken from the homopolymer gap extend costs, for short and long gaps alike.
+    ///
+    /// The homopolymer length is only tracked if this table has homopolymer gap extend costs, and is zero otherwise.
+    /// This way, alignments without homopolymer gap extend costs do not distinguish more nodes than before.
+    /// It saturates at `u8::MAX`.
+    pub fn homopolymer_gap_costs(
+        &self,
+        c: impl Into<AlphabetType::CharacterType>,
+        indel_type: IndelType,
+        long_gap: bool,
+        previous: Option<AlphabetType::CharacterType>,
+        homopolymer_length: u8,
+    ) -> (Cost, u8) {
+        let c = c.into();
+        let gap_costs = |c, is_first| {
+            if long_gap {
+                self.long_gap_costs(c, is_first)
+            } else {
+                self.gap_costs(c, indel_type, is_first)
+            }
+        };
+        let Some(homopolymer_gap_extend_costs) = &self.homopolymer_gap_extend_costs else {
+            return (gap_costs(c, previous.is_none()), 0);
+        };
+
+        match previous {
+            None => (gap_costs(c, true), 1),
+            Some(previous) if previous == c && homopolymer_length > 0 => (
+                homopolymer_gap_extend_costs.evaluate(&usize::from(homopolymer_length)),
+                homopolymer_length.saturating_add(1),
+            ),
+            Some(_) => (gap_costs(c, false), 1),
+        }
+    }
+
+    /// The minimum gap open cost of short and long insertions and deletions.
     pub fn min_gap_open_cost(&self) -> Cost {
-        self.gap_open_cost_vector.iter().min().copied().unwrap()
+        self.insertion_gap_open_cost_vector
+            .iter()
+            .chain(&self.deletion_gap_open_cost_vector)
+            .chain(&self.long_gap_open_cost_vector)
+            .min()
+            .copied()
+            .unwrap()
     }
 
+    /// The maximum gap open cost of short insertions and deletions and of long gaps that can be opened.
     pub fn max_gap_open_cost(&self) -> Cost {
-        self.gap_open_cost_vector.iter().max().copied().unwrap()
+        self.insertion_gap_open_cost_vector
+            .iter()
+            .chain(&self.deletion_gap_open_cost_vector)
+            .chain(
+                self.long_gap_open_cost_vector
+                    .iter()
+                    .filter(|cost| **cost != Cost::MAX),
+            )
+            .max()
+            .copied()
+            .unwrap()
     }
 
+    /// The minimum gap extend cost of short and long insertions and deletions, including the homopolymer gap extend costs.
     pub fn min_gap_extend_cost(&self) -> Cost {
-        self.gap_extend_cost_vector.iter().min().copied().unwrap()
+        self.insertion_gap_extend_cost_vector
+            .iter()
+            .chain(&self.deletion_gap_extend_cost_vector)
+            .chain(&self.long_gap_extend_cost_vector)
+            .copied()
+            .chain([self.min_homopolymer_gap_extend_cost()])
+            .min()
+            .unwrap()
+    }
+
+    /// Returns `Cost::MAX` if there are no homopolymer gap extend costs.
+    fn min_homopolymer_gap_extend_cost(&self) -> Cost {
+        self.homopolymer_gap_extend_costs
+            .as_ref()
+            .and_then(|homopolymer_gap_extend_costs| homopolymer_gap_extend_costs.min(1..))
+            .unwrap_or(Cost::MAX)
     }
 
     /// Fill all costs with their minimum over all characters.
     ///
-    /// Gap open costs and gap extend costs are set to the minimum value over all characters.
+    /// Gap open costs and gap extend costs are set to the minimum value over all characters and over insertions and deletions, separately for short and long gaps.
     /// Match costs are set to the minimum value over all matches and substitution costs are set to the minimum value over all substitutions.
+    /// Homopolymer gap extend costs are removed, and the gap extend costs are lowered to at most their minimum instead.
+    /// Context substitution cost tables are removed, after including them in the minima.
     pub fn into_lower_bound(self) -> Self {
         let min_match_cost = self.min_match_cost();
         let min_substitution_cost = self.min_substitution_cost();
+        let min_homopolymer_gap_extend_cost = self.min_homopolymer_gap_extend_cost();
+        let (gap_open_cost_vector, gap_extend_cost_vector) =
+            self.symmetric_gap_lower_bound(min_homopolymer_gap_extend_cost);
         let substitution_cost_table = AlphabetType::iter()
             .flat_map(|c1| {
                 AlphabetType::iter().map(move |c2| {
@@ -167,25 +463,97 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         Self {
             name: self.name,
             substitution_cost_table,
-            gap_open_cost_vector: vec_into_min(self.gap_open_cost_vector),
-            gap_extend_cost_vector: vec_into_min(self.gap_extend_cost_vector),
+            insertion_gap_open_cost_vector: gap_open_cost_vector.clone(),
+            insertion_gap_extend_cost_vector: gap_extend_cost_vector.clone(),
+            deletion_gap_open_cost_vector: gap_open_cost_vector,
+            deletion_gap_extend_cost_vector: gap_extend_cost_vector,
+            long_gap_open_cost_vector: vec_into_min(self.long_gap_open_cost_vector),
+            long_gap_extend_cost_vector: vec_into_min_with(
+                self.long_gap_extend_cost_vector,
+                min_homopolymer_gap_extend_cost,
+            ),
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; AlphabetType::SIZE.into()],
             phantom_data: self.phantom_data,
         }
     }
 
     /// Fill all costs with their minimum over all characters.
     ///
-    /// Gap open costs and gap extend costs are set to the minimum value over all characters.
+    /// Gap open costs and gap extend costs are set to the minimum value over all characters and over insertions and deletions, separately for short and long gaps.
     /// Match and substitution costs are set to the minimum value over all matches and substitutions.
+    /// Homopolymer gap extend costs are removed, and the gap extend costs are lowered to at most their minimum instead.
+    /// Context substitution cost tables are removed, after including them in the minimum.
     pub fn into_match_agnostic_lower_bound(self) -> Self {
+        let min_match_or_substitution_cost =
+            self.min_match_cost().min(self.min_substitution_cost());
+        let min_homopolymer_gap_extend_cost = self.min_homopolymer_gap_extend_cost();
+        let (gap_open_cost_vector, gap_extend_cost_vector) =
+            self.symmetric_gap_lower_bound(min_homopolymer_gap_extend_cost);
+
         Self {
             name: self.name,
-            substitution_cost_table: vec_into_min(self.substitution_cost_table),
-            gap_open_cost_vector: vec_into_min(self.gap_open_cost_vector),
-            gap_extend_cost_vector: vec_into_min(self.gap_extend_cost_vector),
+            substitution_cost_table: vec![
+                min_match_or_substitution_cost;
+                self.substitution_cost_table.len()
+            ],
+            insertion_gap_open_cost_vector: gap_open_cost_vector.clone(),
+            insertion_gap_extend_cost_vector: gap_extend_cost_vector.clone(),
+            deletion_gap_open_cost_vector: gap_open_cost_vector,
+            deletion_gap_extend_cost_vector: gap_extend_cost_vector,
+            long_gap_open_cost_vector: vec_into_min(self.long_gap_open_cost_vector),
+            long_gap_extend_cost_vector: vec_into_min_with(
+                self.long_gap_extend_cost_vector,
+                min_homopolymer_gap_extend_cost,
+            ),
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; AlphabetType::SIZE.into()],
             phantom_data: self.phantom_data,
         }
     }
+
+    /// Removes the context substitution cost tables, lowering each match and substitution cost to its minimum over all contexts.
+    ///
+    /// All other costs stay the same.
+    pub fn into_context_free_lower_bound(mut self) -> Self {
+        for table in self.context_substitution_cost_tables.iter_mut() {
+            if let Some(table) = table.take() {
+                for (cost, context_cost) in self.substitution_cost_table.iter_mut().zip(table) {
+                    *cost = (*cost).min(context_cost);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl<AlphabetType> GapAffineAlignmentCostTable<AlphabetType> {
+    /// Returns gap open and extend cost vectors that are filled with the minimum costs of insertions and deletions.
+    ///
+    /// The gap extend costs are at most `max_gap_extend_cost`.
+    fn symmetric_gap_lower_bound(&self, max_gap_extend_cost: Cost) -> (Vec<Cost>, Vec<Cost>) {
+        let min_gap_open_cost = self
+            .insertion_gap_open_cost_vector
+            .iter()
+            .chain(&self.deletion_gap_open_cost_vector)
+            .min()
+            .copied()
+            .unwrap();
+        let min_gap_extend_cost = self
+            .insertion_gap_extend_cost_vector
+            .iter()
+            .chain(&self.deletion_gap_extend_cost_vector)
+            .copied()
+            .chain([max_gap_extend_cost])
+            .min()
+            .unwrap();
+        let alphabet_size = self.insertion_gap_open_cost_vector.len();
+
+        (
+            vec![min_gap_open_cost; alphabet_size],
+            vec![min_gap_extend_cost; alphabet_size],
+        )
+    }
 }
 
 fn vec_into_min<ValueType: Clone + Ord>(mut vec: Vec<ValueType>) -> Vec<ValueType> {
@@ -194,13 +562,29 @@ fn vec_into_min<ValueType: Clone + Ord>(mut vec: Vec<ValueType>) -> Vec<ValueTyp
     vec
 }
 
+/// Like [`vec_into_min`], but the minimum is at most `other`.
+fn vec_into_min_with<ValueType: Clone + Ord>(
+    mut vec: Vec<ValueType>,
+    other: ValueType,
+) -> Vec<ValueType> {
+    let min = vec.iter().min().unwrap().clone().min(other);
+    vec.iter_mut().for_each(|value| *value = min.clone());
+    vec
+}
+
 impl<AlphabetType> Clone for GapAffineAlignmentCostTable<AlphabetType> {
     fn clone(&self) -> Self {
         Self {
             name: self.name.clone(),
             substitution_cost_table: self.substitution_cost_table.clone(),
-            gap_open_cost_vector: self.gap_open_cost_vector.clone(),
-            gap_extend_cost_vector: self.gap_extend_cost_vector.clone(),
+            insertion_gap_open_cost_vector: self.insertion_gap_open_cost_vector.clone(),
+            insertion_gap_extend_cost_vector: self.insertion_gap_extend_cost_vector.clone(),
+            deletion_gap_open_cost_vector: self.deletion_gap_open_cost_vector.clone(),
+            deletion_gap_extend_cost_vector: self.deletion_gap_extend_cost_vector.clone(),
+            long_gap_open_cost_vector: self.long_gap_open_cost_vector.clone(),
+            long_gap_extend_cost_vector: self.long_gap_extend_cost_vector.clone(),
+            homopolymer_gap_extend_costs: self.homopolymer_gap_extend_costs.clone(),
+            context_substitution_cost_tables: self.context_substitution_cost_tables.clone(),
             phantom_data: self.phantom_data,
         }
     }
@@ -0,0 +1,346 @@
+use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter};
+
+use crate::costs::{cost::Cost, cost_function::CostFunction};
+
+use super::GapAffineAlignmentCostTable;
+
+impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
+    /// Sets the name of the table.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the cost of all matches.
+    pub fn with_match_cost(mut self, match_cost: Cost) -> Self {
+        self.set_substitution_costs(|c1, c2| (c1 == c2).then_some(match_cost));
+        self
+    }
+
+    /// Sets the cost of all substitutions.
+    pub fn with_substitution_cost(mut self, substitution_cost: Cost) -> Self {
+        self.set_substitution_costs(|c1, c2| (c1 != c2).then_some(substitution_cost));
+        self
+    }
+
+    /// Sets the gap open cost of all characters, for insertions and deletions.
+    pub fn with_gap_open_cost(mut self, gap_open_cost: Cost) -> Self {
+        self.insertion_gap_open_cost_vector.fill(gap_open_cost);
+        self.deletion_gap_open_cost_vector.fill(gap_open_cost);
+        self
+    }
+
+    /// Sets the gap extend cost of all characters, for insertions and deletions.
+    pub fn with_gap_extend_cost(mut self, gap_extend_cost: Cost) -> Self {
+        self.insertion_gap_extend_cost_vector.fill(gap_extend_cost);
+        self.deletion_gap_extend_cost_vector.fill(gap_extend_cost);
+        self
+    }
+
+    /// Sets the gap open and extend costs of each character for deletions, leaving the ones of insertions unchanged.
+    ///
+    /// **Panics** if the vectors do not have one entry per character.
+    pub fn with_deletion_gap_cost_vectors(
+        mut self,
+        deletion_gap_open_cost_vector: impl Into<Vec<Cost>>,
+        deletion_gap_extend_cost_vector: impl Into<Vec<Cost>>,
+    ) -> Self {
+        self.deletion_gap_open_cost_vector = deletion_gap_open_cost_vector.into();
+        self.deletion_gap_extend_cost_vector = deletion_gap_extend_cost_vector.into();
+        assert_eq!(
+            self.deletion_gap_open_cost_vector.len(),
+            usize::from(AlphabetType::SIZE)
+        );
+        assert_eq!(
+            self.deletion_gap_extend_cost_vector.len(),
+            usize::from(AlphabetType::SIZE)
+        );
+        self
+    }
+
+    /// Sets the long gap open and extend costs of all characters, enabling the two-piece affine gap model.
+    ///
+    /// Setting the long gap open cost to `Cost::MAX` disables long gaps.
+    pub fn with_long_gap_costs(
+        mut self,
+        long_gap_open_cost: Cost,
+        long_gap_extend_cost: Cost,
+    ) -> Self {
+        self.long_gap_open_cost_vector.fill(long_gap_open_cost);
+        self.long_gap_extend_cost_vector.fill(long_gap_extend_cost);
+        self
+    }
+
+    /// Sets the long gap open and extend costs of each character, enabling the two-piece affine gap model.
+    ///
+    /// **Panics** if the vectors do not have one entry per character.
+    pub fn with_long_gap_cost_vectors(
+        mut self,
+        long_gap_open_cost_vector: impl Into<Vec<Cost>>,
+        long_gap_extend_cost_vector: impl Into<Vec<Cost>>,
+    ) -> Self {
+        self.long_gap_open_cost_vector = long_gap_open_cost_vector.into();
+        self.long_gap_extend_cost_vector = long_gap_extend_cost_vector.into();
+        assert_eq!(
+            self.long_gap_open_cost_vector.len(),
+            usize::from(AlphabetType::SIZE)
+        );
+        assert_eq!(
+            self.long_gap_extend_cost_vector.len(),
+            usize::from(AlphabetType::SIZE)
+        );
+        self
+    }
+
+    /// Sets the gap extend costs within homopolymer runs, as a function of the length of the run.
+    ///
+    /// A gap character extends a homopolymer run if it is equal to the gap characters before it.
+    /// The function is evaluated at the number of these equal gap characters, so it needs to be defined from one on.
+    /// Runs longer than `u8::MAX` are evaluated at `u8::MAX`.
+    ///
+    /// **Panics** if `homopolymer_gap_extend_costs` is not defined at one.
+    pub fn with_homopolymer_gap_extend_costs(
+        mut self,
+        homopolymer_gap_extend_costs: CostFunction<usize>,
+    ) -> Self {
+        assert!(
+            homopolymer_gap_extend_costs.min(..=1).is_some(),
+            "homopolymer gap extend costs must be defined from one on"
+        );
+        self.homopolymer_gap_extend_costs = Some(homopolymer_gap_extend_costs);
+        self
+    }
+
+    /// Sets the match and substitution costs after the reference character `previous`.
+    ///
+    /// **Panics** if the table does not have one entry per pair of characters.
+    pub fn with_context_substitution_cost_table(
+        mut self,
+        previous: impl Into<AlphabetType::CharacterType>,
+        context_substitution_cost_table: impl Into<Vec<Cost>>,
+    ) -> Self {
+        let context_substitution_cost_table = context_substitution_cost_table.into();
+        assert_eq!(
+            context_substitution_cost_table.len(),
+            usize::from(AlphabetType::SIZE) * usize::from(AlphabetType::SIZE)
+        );
+        self.context_substitution_cost_tables[usize::from(previous.into().index())] =
+            Some(context_substitution_cost_table);
+        self
+    }
+
+    /// Removes the context substitution cost tables, such that the substitution cost table applies everywhere.
+    pub fn without_context_substitution_costs(mut self) -> Self {
+        self.context_substitution_cost_tables.fill(None);
+        self
+    }
+
+    /// Removes the homopolymer gap extend costs, such that the gap extend costs apply everywhere.
+    pub fn without_homopolymer_gap_extend_costs(mut self) -> Self {
+        self.homopolymer_gap_extend_costs = None;
+        self
+    }
+
+    /// Multiplies all costs by `factor`.
+    ///
+    /// Infinite costs stay infinite, and finite costs saturate at `Cost::MAX`.
+    pub fn scale(self, factor: u64) -> Self {
+        self.map(|cost| {
+            if cost == Cost::MAX {
+                cost
+            } else {
+                cost.as_u64().saturating_mul(factor).into()
+            }
+        })
+    }
+
+    /// Takes the minimum of each cost and the corresponding cost in `other`.
+    ///
+    /// The name of `self` is kept.
+    /// If only one of the tables has homopolymer gap extend costs, then they are combined with the minimum gap extend cost of the other.
+    pub fn pointwise_min(self, other: &Self) -> Self {
+        self.zip_with(other, Ord::min)
+    }
+
+    /// Takes the maximum of each cost and the corresponding cost in `other`.
+    ///
+    /// The name of `self` is kept.
+    /// If only one of the tables has homopolymer gap extend costs, then they are combined with the maximum gap extend cost of the other.
+    pub fn pointwise_max(self, other: &Self) -> Self {
+        self.zip_with(other, Ord::max)
+    }
+
+    /// Makes the substitution cost tables and the gap costs symmetric.
+    ///
+    /// Substituting `c1` with `c2` and `c2` with `c1` both get the cheaper of the two costs,
+    /// and so do inserting and deleting a character,
+    /// such that the symmetric table is a lower bound of the original one.
+    pub fn into_symmetric(mut self) -> Self {
+        for (insertion, deletion) in self
+            .insertion_gap_open_cost_vector
+            .iter_mut()
+            .zip(&mut self.deletion_gap_open_cost_vector)
+            .chain(
+                self.insertion_gap_extend_cost_vector
+                    .iter_mut()
+                    .zip(&mut self.deletion_gap_extend_cost_vector),
+            )
+        {
+            let cost = (*insertion).min(*deletion);
+            *insertion = cost;
+            *deletion = cost;
+        }
+
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        for table in [&mut self.substitution_cost_table]
+            .into_iter()
+            .chain(self.context_substitution_cost_tables.iter_mut().flatten())
+        {
+            for c1 in 0..alphabet_size {
+                for c2 in c1 + 1..alphabet_size {
+                    let cost = table[c1 * alphabet_size + c2].min(table[c2 * alphabet_size + c1]);
+                    table[c1 * alphabet_size + c2] = cost;
+                    table[c2 * alphabet_size + c1] = cost;
+                }
+            }
+        }
+        self
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        self.has_symmetric_gaps()
+            && self.substitution_cost_tables().all(|table| {
+                (0..alphabet_size).all(|c1| {
+                    (0..alphabet_size)
+                        .all(|c2| table[c1 * alphabet_size + c2] == table[c2 * alphabet_size + c1])
+                })
+            })
+    }
+
+    /// Sets the substitution cost of each character pair for which `cost` returns `Some`.
+    ///
+    /// The arguments of `cost` are character indices.
+    fn set_substitution_costs(&mut self, cost: impl Fn(usize, usize) -> Option<Cost>) {
+        for c1 in AlphabetType::iter() {
+            for c2 in AlphabetType::iter() {
+                let c1 = usize::from(c1.index());
+                let c2 = usize::from(c2.index());
+                if let Some(cost) = cost(c1, c2) {
+                    for table in [&mut self.substitution_cost_table]
+                        .into_iter()
+                        .chain(self.context_substitution_cost_tables.iter_mut().flatten())
+                    {
+                        table[c1 * usize::from(AlphabetType::SIZE) + c2] = cost;
+                    }
+                }
+            }
+        }
+    }
+
+    fn map(mut self, function: impl Fn(Cost) -> Cost) -> Self {
+        self.homopolymer_gap_extend_costs =
+            self.homopolymer_gap_extend_costs
+                .map(|homopolymer_gap_extend_costs| {
+                    homopolymer_gap_extend_costs
+                        .combine(&homopolymer_gap_extend_costs, |cost, _| function(cost))
+                });
+        self.substitution_cost_table
+            .iter_mut()
+            .chain(&mut self.insertion_gap_open_cost_vector)
+            .chain(&mut self.insertion_gap_extend_cost_vector)
+            .chain(&mut self.deletion_gap_open_cost_vector)
+            .chain(&mut self.deletion_gap_extend_cost_vector)
+            .chain(&mut self.long_gap_open_cost_vector)
+            .chain(&mut self.long_gap_extend_cost_vector)
+            .chain(
+                self.context_substitution_cost_tables
+                    .iter_mut()
+                    .flatten()
+                    .flatten(),
+            )
+            .for_each(|cost| *cost = function(*cost));
+        self
+    }
+
+    fn zip_with(mut self, other: &Self, function: impl Fn(Cost, Cost) -> Cost) -> Self {
+        // A table without homopolymer gap extend costs charges its gap extend costs in homopolymer runs as well.
+        let homopolymer_gap_extend_costs = |table: &Self| {
+            table
+                .homopolymer_gap_extend_costs
+                .clone()
+                .unwrap_or_else(|| {
+                    let gap_extend_cost = table
+                        .insertion_gap_extend_cost_vector
+                        .iter()
+                        .chain(&table.deletion_gap_extend_cost_vector)
+                        .copied()
+                        .reduce(&function)
+                        .unwrap();
+                    CostFunction::try_from(vec![(0, gap_extend_cost)]).unwrap()
+                })
+        };
+        if self.homopolymer_gap_extend_costs.is_some()
+            || other.homopolymer_gap_extend_costs.is_some()
+        {
+            self.homopolymer_gap_extend_costs = Some(
+                homopolymer_gap_extend_costs(&self)
+                    .combine(&homopolymer_gap_extend_costs(other), &function),
+            );
+        }
+
+        // A missing context substitution cost table means that the substitution cost table applies in that context.
+        for (table, other_table) in self
+            .context_substitution_cost_tables
+            .iter_mut()
+            .zip(&other.context_substitution_cost_tables)
+        {
+            if table.is_none() && other_table.is_none() {
+                continue;
+            }
+            let table = table.get_or_insert_with(|| self.substitution_cost_table.clone());
+            let other_table = other_table
+                .as_ref()
+                .unwrap_or(&other.substitution_cost_table);
+            for (cost, other_cost) in table.iter_mut().zip(other_table) {
+                *cost = function(*cost, *other_cost);
+            }
+        }
+
+        self.substitution_cost_table
+            .iter_mut()
+            .zip(&other.substitution_cost_table)
+            .chain(
+                self.insertion_gap_open_cost_vector
+                    .iter_mut()
+                    .zip(&other.insertion_gap_open_cost_vector),
+            )
+            .chain(
+                self.insertion_gap_extend_cost_vector
+                    .iter_mut()
+                    .zip(&other.insertion_gap_extend_cost_vector),
+            )
+            .chain(
+                self.deletion_gap_open_cost_vector
+                    .iter_mut()
+                    .zip(&other.deletion_gap_open_cost_vector),
+            )
+            .chain(
+                self.deletion_gap_extend_cost_vector
+                    .iter_mut()
+                    .zip(&other.deletion_gap_extend_cost_vector),
+            )
+            .chain(
+                self.long_gap_open_cost_vector
+                    .iter_mut()
+                    .zip(&other.long_gap_open_cost_vector),
+            )
+            .chain(
+                self.long_gap_extend_cost_vector
+                    .iter_mut()
+                    .zip(&other.long_gap_extend_cost_vector),
+            )
+            .for_each(|(cost, other_cost)| *cost = function(*cost, *other_cost));
+        self
+    }
+}
@@ -1,10 +1,11 @@
 use super::GapAffineAlignmentCostTable;
 use crate::{
-    costs::cost::Cost,
+    config::io::ParseMode,
+    costs::{cost::Cost, cost_function::CostFunction},
     error::{Error, Result},
     io::{
-        parse_any_whitespace, parse_title, parse_whitespace, skip_any_whitespace, skip_whitespace,
-        translate_nom_error,
+        parse_any_whitespace, parse_identifier, parse_title, parse_whitespace, skip_any_whitespace,
+        skip_whitespace, DiagnosticParser,
     },
 };
 
@@ -27,21 +28,27 @@ use std::{
 mod tests;
 
 impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
-    pub fn read_plain_multi(mut reader: impl Read) -> Result<HashMap<String, Self>> {
+    pub fn read_plain_multi(reader: impl Read) -> Result<HashMap<String, Self>> {
+        Self::read_plain_multi_with_mode(reader, ParseMode::Strict)
+    }
+
+    /// Like [`read_plain_multi`](Self::read_plain_multi), but with the given parse mode.
+    pub fn read_plain_multi_with_mode(
+        mut reader: impl Read,
+        mode: ParseMode,
+    ) -> Result<HashMap<String, Self>> {
         let mut input = String::new();
         reader.read_to_string(&mut input)?;
-        let mut input = input.as_str();
+        let mut parser = DiagnosticParser::new(&input);
         let mut result = HashMap::new();
 
         loop {
-            let (next_input, table) = Self::parse_plain(input).map_err(translate_nom_error)?;
-            input = next_input;
+            let table = Self::parse_plain_with_mode(&mut parser, mode)?;
             if let Some(previous_table) = result.insert(table.name().to_string(), table) {
                 return Err(Error::DuplicateCostTableName(previous_table.name));
             }
 
-            input = skip_any_whitespace(input).map_err(translate_nom_error)?;
-            if input.is_empty() {
+            if parser.is_at_end() {
                 break;
             }
         }
@@ -49,106 +56,99 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         Ok(result)
     }
 
-    pub fn read_plain(mut reader: impl Read) -> Result<Self> {
+    pub fn read_plain(reader: impl Read) -> Result<Self> {
+        Self::read_plain_with_mode(reader, ParseMode::Strict)
+    }
+
+    /// Like [`read_plain`](Self::read_plain), but with the given parse mode.
+    pub fn read_plain_with_mode(mut reader: impl Read, mode: ParseMode) -> Result<Self> {
         let mut input = String::new();
         reader.read_to_string(&mut input)?;
 
-        Self::parse_plain(&input)
-            .map_err(translate_nom_error)
-            .map(|(_, output)| output)
+        Self::parse_plain_with_mode(&mut DiagnosticParser::new(&input), mode)
     }
 
     pub fn write_plain(&self, mut writer: impl Write) -> Result<()> {
         writeln!(writer, "# {}", self.name)?;
         writeln!(writer)?;
 
-        writeln!(writer, "SubstitutionCostTable")?;
-
-        let column_width = self
-            .substitution_cost_table
-            .iter()
-            .map(|substitution_cost| format!("{}", substitution_cost.as_u64()).len())
-            .max()
-            .unwrap();
-
-        write!(writer, "  |")?;
-        for column_index in 0..AlphabetType::SIZE {
-            let character = AlphabetType::CharacterType::from_index(column_index).unwrap();
-            for _ in 0..column_width {
-                write!(writer, " ")?;
-            }
-            write!(writer, "{character}")?;
-        }
-        writeln!(writer)?;
-
-        write!(writer, "--+")?;
-        for _ in 0..(usize::from(AlphabetType::SIZE) * (column_width + 1)) {
-            write!(writer, "-")?;
-        }
+        write_substitution_cost_table::<AlphabetType>(
+            &mut writer,
+            "SubstitutionCostTable",
+            &self.substitution_cost_table,
+        )?;
         writeln!(writer)?;
 
-        for row_index in 0..AlphabetType::SIZE {
-            let character = AlphabetType::CharacterType::from_index(row_index).unwrap();
-            write!(writer, "{character} |")?;
-            for column_index in 0..AlphabetType::SIZE {
-                let cost = self.substitution_cost_table[usize::from(row_index)
-                    * usize::from(AlphabetType::SIZE)
-                    + usize::from(column_index)]
-                .as_u64();
-                write!(writer, " {cost: >column_width$}")?;
-            }
+        if self.has_symmetric_gaps() {
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "GapOpenCostVector",
+                &self.insertion_gap_open_cost_vector,
+            )?;
+            writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "GapExtendCostVector",
+                &self.insertion_gap_extend_cost_vector,
+            )?;
+        } else {
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "InsertionGapOpenCostVector",
+                &self.insertion_gap_open_cost_vector,
+            )?;
+            writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "InsertionGapExtendCostVector",
+                &self.insertion_gap_extend_cost_vector,
+            )?;
             writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "DeletionGapOpenCostVector",
+                &self.deletion_gap_open_cost_vector,
+            )?;
+            writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "DeletionGapExtendCostVector",
+                &self.deletion_gap_extend_cost_vector,
+            )?;
         }
-        writeln!(writer)?;
-
-        writeln!(writer, "GapOpenCostVector")?;
 
-        let column_width = self
-            .gap_open_cost_vector
-            .iter()
-            .map(|gap_open_cost| format!("{}", gap_open_cost.as_u64()).len())
-            .max()
-            .unwrap();
-
-        for column_index in 0..AlphabetType::SIZE {
-            let character = AlphabetType::CharacterType::from_index(column_index).unwrap();
-            for _ in 0..column_width {
-                write!(writer, " ")?;
-            }
-            write!(writer, "{character}")?;
+        if self.has_long_gaps() {
+            writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "LongGapOpenCostVector",
+                &self.long_gap_open_cost_vector,
+            )?;
+            writeln!(writer)?;
+            write_cost_vector::<AlphabetType>(
+                &mut writer,
+                "LongGapExtendCostVector",
+                &self.long_gap_extend_cost_vector,
+            )?;
         }
-        writeln!(writer)?;
 
-        for column_index in 0..AlphabetType::SIZE {
-            let cost = self.gap_open_cost_vector[usize::from(column_index)].as_u64();
-            write!(writer, " {cost: >column_width$}")?;
+        if let Some(homopolymer_gap_extend_costs) = &self.homopolymer_gap_extend_costs {
+            writeln!(writer)?;
+            writeln!(writer, "HomopolymerGapExtendCostFunction")?;
+            homopolymer_gap_extend_costs.write_plain(&mut writer)?;
+            writeln!(writer)?;
         }
-        writeln!(writer)?;
-        writeln!(writer)?;
 
-        writeln!(writer, "GapExtendCostVector")?;
-
-        let column_width = self
-            .gap_extend_cost_vector
-            .iter()
-            .map(|gap_extend_cost| format!("{}", gap_extend_cost.as_u64()).len())
-            .max()
-            .unwrap();
-
-        for column_index in 0..AlphabetType::SIZE {
-            let character = AlphabetType::CharacterType::from_index(column_index).unwrap();
-            for _ in 0..column_width {
-                write!(writer, " ")?;
+        for (previous, table) in AlphabetType::iter().zip(&self.context_substitution_cost_tables) {
+            if let Some(table) = table {
+                writeln!(writer)?;
+                write_substitution_cost_table::<AlphabetType>(
+                    &mut writer,
+                    &format!("ContextSubstitutionCostTable {previous}"),
+                    table,
+                )?;
             }
-            write!(writer, "{character}")?;
-        }
-        writeln!(writer)?;
-
-        for column_index in 0..AlphabetType::SIZE {
-            let cost = self.gap_extend_cost_vector[usize::from(column_index)].as_u64();
-            write!(writer, " {cost: >column_width$}")?;
         }
-        writeln!(writer)?;
 
         Ok(())
     }
@@ -157,21 +157,253 @@ impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
         let (input, name) = opt(parse_title)(input)?;
         let (input, substitution_cost_table) =
             parse_substitution_cost_table::<AlphabetType>(input)?;
-        let (input, gap_open_cost_vector) = parse_gap_open_cost_vector::<AlphabetType>(input)?;
-        let (input, gap_extend_cost_vector) = parse_gap_extend_cost_vector::<AlphabetType>(input)?;
+        let (input, gap_costs) = opt(tuple((
+            parse_named_cost_vector::<AlphabetType>("GapOpenCostVector"),
+            parse_named_cost_vector::<AlphabetType>("GapExtendCostVector"),
+        )))(input)?;
+        let (input, insertion_gap_costs) = opt(tuple((
+            parse_named_cost_vector::<AlphabetType>("InsertionGapOpenCostVector"),
+            parse_named_cost_vector::<AlphabetType>("InsertionGapExtendCostVector"),
+        )))(input)?;
+        let (input, deletion_gap_costs) = opt(tuple((
+            parse_named_cost_vector::<AlphabetType>("DeletionGapOpenCostVector"),
+            parse_named_cost_vector::<AlphabetType>("DeletionGapExtendCostVector"),
+        )))(input)?;
+        let Some((
+            (insertion_gap_open_cost_vector, insertion_gap_extend_cost_vector),
+            (deletion_gap_open_cost_vector, deletion_gap_extend_cost_vector),
+        )) = resolve_gap_costs(gap_costs, insertion_gap_costs, deletion_gap_costs)
+        else {
+            return Err(nom::Err::Failure(nom::error::Error {
+                input,
+                code: nom::error::ErrorKind::Verify,
+            }));
+        };
+        let (input, long_gap_cost_vectors) = opt(tuple((
+            parse_named_cost_vector::<AlphabetType>("LongGapOpenCostVector"),
+            parse_named_cost_vector::<AlphabetType>("LongGapExtendCostVector"),
+        )))(input)?;
+        let (input, homopolymer_gap_extend_costs) =
+            opt(parse_homopolymer_gap_extend_cost_function)(input)?;
+        let mut context_substitution_cost_tables = vec![None; AlphabetType::SIZE.into()];
+        let mut input = input;
+        while let (next_input, Some((previous, table))) =
+            opt(parse_context_substitution_cost_table::<AlphabetType>)(input)?
+        {
+            let context_substitution_cost_table =
+                &mut context_substitution_cost_tables[usize::from(previous.index())];
+            if context_substitution_cost_table.is_some() {
+                return Err(nom::Err::Failure(nom::error::Error {
+                    input,
+                    code: nom::error::ErrorKind::Verify,
+                }));
+            }
+            *context_substitution_cost_table = Some(table);
+            input = next_input;
+        }
+
+        let (long_gap_open_cost_vector, long_gap_extend_cost_vector) = long_gap_cost_vectors
+            .unwrap_or_else(|| {
+                (
+                    vec![Cost::MAX; AlphabetType::SIZE.into()],
+                    vec![Cost::MAX; AlphabetType::SIZE.into()],
+                )
+            });
 
         let name = name.unwrap_or("").to_string();
 
         let cost_table = Self {
             name,
             substitution_cost_table,
-            gap_open_cost_vector,
-            gap_extend_cost_vector,
+            insertion_gap_open_cost_vector,
+            insertion_gap_extend_cost_vector,
+            deletion_gap_open_cost_vector,
+            deletion_gap_extend_cost_vector,
+            long_gap_open_cost_vector,
+            long_gap_extend_cost_vector,
+            homopolymer_gap_extend_costs,
+            context_substitution_cost_tables,
             phantom_data: Default::default(),
         };
 
         Ok((input, cost_table))
     }
+
+    pub(crate) fn parse_plain_with_mode(
+        parser: &mut DiagnosticParser<'_>,
+        mode: ParseMode,
+    ) -> Result<Self> {
+        match mode {
+            ParseMode::Strict => parser.parse("a cost table", Self::parse_plain),
+            ParseMode::Lenient => Self::parse_plain_lenient(parser),
+        }
+    }
+
+    /// Parses a cost table whose parts may come in any order.
+    fn parse_plain_lenient(parser: &mut DiagnosticParser<'_>) -> Result<Self> {
+        let name = parser.parse_opt("a cost table title", parse_title)?;
+        let mut substitution_cost_table = None;
+        let mut gap_open_cost_vector = None;
+        let mut gap_extend_cost_vector = None;
+        let mut insertion_gap_open_cost_vector = None;
+        let mut insertion_gap_extend_cost_vector = None;
+        let mut deletion_gap_open_cost_vector = None;
+        let mut deletion_gap_extend_cost_vector = None;
+        let mut long_gap_open_cost_vector = None;
+        let mut long_gap_extend_cost_vector = None;
+        let mut homopolymer_gap_extend_costs = None;
+        let mut context_substitution_cost_tables = vec![None; AlphabetType::SIZE.into()];
+
+        while let Some(identifier) = parser.peek(parse_identifier) {
+            match identifier {
+                "SubstitutionCostTable" => parser.parse_once(
+                    &mut substitution_cost_table,
+                    identifier,
+                    parse_substitution_cost_table::<AlphabetType>,
+                )?,
+                "GapOpenCostVector" => parser.parse_once(
+                    &mut gap_open_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "GapExtendCostVector" => parser.parse_once(
+                    &mut gap_extend_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "InsertionGapOpenCostVector" => parser.parse_once(
+                    &mut insertion_gap_open_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "InsertionGapExtendCostVector" => parser.parse_once(
+                    &mut insertion_gap_extend_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "DeletionGapOpenCostVector" => parser.parse_once(
+                    &mut deletion_gap_open_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "DeletionGapExtendCostVector" => parser.parse_once(
+                    &mut deletion_gap_extend_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "LongGapOpenCostVector" => parser.parse_once(
+                    &mut long_gap_open_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "LongGapExtendCostVector" => parser.parse_once(
+                    &mut long_gap_extend_cost_vector,
+                    identifier,
+                    parse_named_cost_vector::<AlphabetType>(identifier),
+                )?,
+                "HomopolymerGapExtendCostFunction" => parser.parse_once(
+                    &mut homopolymer_gap_extend_costs,
+                    identifier,
+                    parse_homopolymer_gap_extend_cost_function,
+                )?,
+                "ContextSubstitutionCostTable" => {
+                    let Some((_, previous)) = parser.peek(|input| {
+                        tuple((
+                            tag(identifier),
+                            preceded(
+                                parse_whitespace,
+                                parse_alphabet_character::<AlphabetType::CharacterType>,
+                            ),
+                        ))(input)
+                    }) else {
+                        return Err(parser.error(&format!("'{identifier} <character>'")));
+                    };
+                    parser.parse_once(
+                        &mut context_substitution_cost_tables[usize::from(previous.index())],
+                        &format!("{identifier} {previous}"),
+                        |input| {
+                            parse_context_substitution_cost_table::<AlphabetType>(input)
+                                .map(|(input, (_, table))| (input, table))
+                        },
+                    )?
+                }
+                _ => break,
+            }
+        }
+
+        let gap_costs = pair_or_error(
+            parser,
+            gap_open_cost_vector,
+            gap_extend_cost_vector,
+            "GapOpenCostVector",
+            "GapExtendCostVector",
+        )?;
+        let insertion_gap_costs = pair_or_error(
+            parser,
+            insertion_gap_open_cost_vector,
+            insertion_gap_extend_cost_vector,
+            "InsertionGapOpenCostVector",
+            "InsertionGapExtendCostVector",
+        )?;
+        let deletion_gap_costs = pair_or_error(
+            parser,
+            deletion_gap_open_cost_vector,
+            deletion_gap_extend_cost_vector,
+            "DeletionGapOpenCostVector",
+            "DeletionGapExtendCostVector",
+        )?;
+        let (
+            (insertion_gap_open_cost_vector, insertion_gap_extend_cost_vector),
+            (deletion_gap_open_cost_vector, deletion_gap_extend_cost_vector),
+        ) = resolve_gap_costs(gap_costs, insertion_gap_costs, deletion_gap_costs)
+            .ok_or_else(|| parser.error("'GapOpenCostVector'"))?;
+        let (long_gap_open_cost_vector, long_gap_extend_cost_vector) = pair_or_error(
+            parser,
+            long_gap_open_cost_vector,
+            long_gap_extend_cost_vector,
+            "LongGapOpenCostVector",
+            "LongGapExtendCostVector",
+        )?
+        .unwrap_or_else(|| {
+            (
+                vec![Cost::MAX; AlphabetType::SIZE.into()],
+                vec![Cost::MAX; AlphabetType::SIZE.into()],
+            )
+        });
+
+        Ok(Self {
+            name: name.unwrap_or("").to_string(),
+            substitution_cost_table: substitution_cost_table
+                .ok_or_else(|| parser.error("'SubstitutionCostTable'"))?,
+            insertion_gap_open_cost_vector,
+            insertion_gap_extend_cost_vector,
+            deletion_gap_open_cost_vector,
+            deletion_gap_extend_cost_vector,
+            long_gap_open_cost_vector,
+            long_gap_extend_cost_vector,
+            homopolymer_gap_extend_costs,
+            context_substitution_cost_tables,
+            phantom_data: Default::default(),
+        })
+    }
+}
+
+/// Returns both parts of a pair of cost vectors, or `None` if neither is given.
+///
+/// Fails if only one of them is given.
+fn pair_or_error(
+    parser: &DiagnosticParser<'_>,
+    first: Option<Vec<Cost>>,
+    second: Option<Vec<Cost>>,
+    first_name: &str,
+    second_name: &str,
+) -> Result<Option<(Vec<Cost>, Vec<Cost>)>> {
+    match (first, second) {
+        (Some(first), Some(second)) => Ok(Some((first, second))),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(parser.error(&format!("'{second_name}'"))),
+        (None, Some(_)) => Err(parser.error(&format!("'{first_name}'"))),
+    }
 }
 
 fn parse_substitution_cost_table<AlphabetType: Alphabet>(input: &str) -> IResult<&str, Vec<Cost>> {
@@ -179,6 +411,26 @@ fn parse_substitution_cost_table<AlphabetType: Alphabet>(input: &str) -> IResult
     let input = skip_any_whitespace(input)?;
     let input = tag("SubstitutionCostTable")(input)?.0;
 
+    parse_substitution_cost_table_body::<AlphabetType>(input)
+}
+
+/// Parses a context substitution cost table, which is identified by the reference character before the substitution.
+fn parse_context_substitution_cost_table<AlphabetType: Alphabet>(
+    input: &str,
+) -> IResult<&str, (AlphabetType::CharacterType, Vec<Cost>)> {
+    // Identifier
+    let input = skip_any_whitespace(input)?;
+    let input = tag("ContextSubstitutionCostTable")(input)?.0;
+    let input = parse_whitespace(input)?.0;
+    let (input, previous) = parse_alphabet_character(input)?;
+
+    let (input, table) = parse_substitution_cost_table_body::<AlphabetType>(input)?;
+    Ok((input, (previous, table)))
+}
+
+fn parse_substitution_cost_table_body<AlphabetType: Alphabet>(
+    input: &str,
+) -> IResult<&str, Vec<Cost>> {
     // First row gives the order of the characters in the columns
     let (input, column_character_order) =
         parse_substitution_cost_table_first_row::<AlphabetType>(input)?;
@@ -237,8 +489,8 @@ fn parse_substitution_cost_table_first_row<AlphabetType: Alphabet>(
     sorted_characters.sort();
     sorted_characters.dedup();
 
-    if characters.len() != AlphabetType::SIZE.into()
-        || sorted_characters.len() != AlphabetType::SIZE.into()
+    if characters.len() != usize::from(AlphabetType::SIZE)
+        || sorted_characters.len() != usize::from(AlphabetType::SIZE)
     {
         Err(nom::Err::Failure(nom::error::Error {
             input,
@@ -264,20 +516,121 @@ fn parse_substitution_cost_table_row<AlphabetType: Alphabet>(
     Ok((input, (character, cost_vector)))
 }
 
-fn parse_gap_open_cost_vector<AlphabetType: Alphabet>(input: &str) -> IResult<&str, Vec<Cost>> {
-    // Identifier
-    let input = skip_any_whitespace(input)?;
-    let input = tag("GapOpenCostVector")(input)?.0;
+fn parse_named_cost_vector<AlphabetType: Alphabet>(
+    name: &str,
+) -> impl '_ + FnMut(&str) -> IResult<&str, Vec<Cost>> {
+    move |input| {
+        // Identifier
+        let input = skip_any_whitespace(input)?;
+        let input = tag(name)(input)?.0;
+
+        parse_cost_vector::<AlphabetType>(input)
+    }
+}
 
-    parse_cost_vector::<AlphabetType>(input)
+/// Resolves the gap cost vectors of insertions and deletions, which are returned in this order.
+///
+/// The general gap costs apply to insertions and deletions unless these are given separately.
+/// If only one of insertions and deletions has gap costs, then the other gets the same ones.
+/// Returns `None` if no gap costs are given.
+#[expect(clippy::type_complexity)]
+fn resolve_gap_costs(
+    gap_costs: Option<(Vec<Cost>, Vec<Cost>)>,
+    insertion_gap_costs: Option<(Vec<Cost>, Vec<Cost>)>,
+    deletion_gap_costs: Option<(Vec<Cost>, Vec<Cost>)>,
+) -> Option<((Vec<Cost>, Vec<Cost>), (Vec<Cost>, Vec<Cost>))> {
+    let insertion_gap_costs = insertion_gap_costs
+        .or_else(|| gap_costs.clone())
+        .or_else(|| deletion_gap_costs.clone())?;
+    let deletion_gap_costs = deletion_gap_costs
+        .or(gap_costs)
+        .unwrap_or_else(|| insertion_gap_costs.clone());
+    Some((insertion_gap_costs, deletion_gap_costs))
 }
 
-fn parse_gap_extend_cost_vector<AlphabetType: Alphabet>(input: &str) -> IResult<&str, Vec<Cost>> {
+/// The homopolymer gap extend costs are optional, and are written like a cost function over the length of the run.
+fn parse_homopolymer_gap_extend_cost_function(input: &str) -> IResult<&str, CostFunction<usize>> {
     // Identifier
     let input = skip_any_whitespace(input)?;
-    let input = tag("GapExtendCostVector")(input)?.0;
+    let input = tag("HomopolymerGapExtendCostFunction")(input)?.0;
+
+    CostFunction::parse_plain(input)
+}
+
+fn write_substitution_cost_table<AlphabetType: Alphabet>(
+    mut writer: impl Write,
+    title: &str,
+    substitution_cost_table: &[Cost],
+) -> Result<()> {
+    writeln!(writer, "{title}")?;
+
+    let column_width = substitution_cost_table
+        .iter()
+        .map(|substitution_cost| format!("{}", substitution_cost.as_u64()).len())
+        .max()
+        .unwrap();
+
+    write!(writer, "  |")?;
+    for column_index in 0..AlphabetType::SIZE {
+        let character = AlphabetType::CharacterType::from_index(column_index).unwrap();
+        for _ in 0..column_width {
+            write!(writer, " ")?;
+        }
+        write!(writer, "{character}")?;
+    }
+    writeln!(writer)?;
+
+    write!(writer, "--+")?;
+    for _ in 0..(usize::from(AlphabetType::SIZE) * (column_width + 1)) {
+        write!(writer, "-")?;
+    }
+    writeln!(writer)?;
+
+    for row_index in 0..AlphabetType::SIZE {
+        let character = AlphabetType::CharacterType::from_index(row_index).unwrap();
+        write!(writer, "{character} |")?;
+        for column_index in 0..AlphabetType::SIZE {
+            let cost = substitution_cost_table[usize::from(row_index)
+                * usize::from(AlphabetType::SIZE)
+                + usize::from(column_index)]
+            .as_u64();
+            write!(writer, " {cost: >column_width$}")?;
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+fn write_cost_vector<AlphabetType: Alphabet>(
+    mut writer: impl Write,
+    title: &str,
+    cost_vector: &[Cost],
+) -> Result<()> {
+    writeln!(writer, "{title}")?;
+
+    let column_width = cost_vector
+        .iter()
+        .map(|cost| format!("{}", cost.as_u64()).len())
+        .max()
+        .unwrap();
+
+    for column_index in 0..AlphabetType::SIZE {
+        let character = AlphabetType::CharacterType::from_index(column_index).unwrap();
+        for _ in 0..column_width {
+            write!(writer, " ")?;
+        }
+        write!(writer, "{character}")?;
+    }
+    writeln!(writer)?;
+
+    for column_index in 0..AlphabetType::SIZE {
+        let cost = cost_vector[usize::from(column_index)].as_u64();
+        write!(writer, " {cost: >column_width$}")?;
+    }
+    writeln!(writer)?;
 
-    parse_cost_vector::<AlphabetType>(input)
+    Ok(())
 }
 
 fn parse_cost_vector<AlphabetType: Alphabet>(input: &str) -> IResult<&str, Vec<Cost>> {
@@ -306,8 +659,8 @@ fn parse_cost_vector_index_row<AlphabetType: Alphabet>(
     sorted_characters.sort();
     sorted_characters.dedup();
 
-    if characters.len() != AlphabetType::SIZE.into()
-        || sorted_characters.len() != AlphabetType::SIZE.into()
+    if characters.len() != usize::from(AlphabetType::SIZE)
+        || sorted_characters.len() != usize::from(AlphabetType::SIZE)
     {
         Err(nom::Err::Failure(nom::error::Error {
             input,
@@ -1,6 +1,12 @@
-use compact_genome::implementation::alphabets::dna_alphabet::DnaAlphabet;
+use compact_genome::{
+    implementation::alphabets::dna_alphabet::DnaAlphabet, interface::alphabet::Alphabet,
+};
 
-use crate::costs::gap_affine::GapAffineAlignmentCostTable;
+use crate::{
+    config::io::ParseMode,
+    costs::{cost::Cost, gap_affine::GapAffineAlignmentCostTable},
+    error::Error,
+};
 
 #[test]
 fn simple_example() {
@@ -11,8 +17,14 @@ fn simple_example() {
             .into_iter()
             .map(Into::into)
             .collect(),
-        gap_open_cost_vector: [3, 4, 5, 1].into_iter().map(Into::into).collect(),
-        gap_extend_cost_vector: [10, 15, 0, 1].into_iter().map(Into::into).collect(),
+        insertion_gap_open_cost_vector: [3, 4, 5, 1].into_iter().map(Into::into).collect(),
+        insertion_gap_extend_cost_vector: [10, 15, 0, 1].into_iter().map(Into::into).collect(),
+        deletion_gap_open_cost_vector: [3, 4, 5, 1].into_iter().map(Into::into).collect(),
+        deletion_gap_extend_cost_vector: [10, 15, 0, 1].into_iter().map(Into::into).collect(),
+        long_gap_open_cost_vector: vec![Cost::MAX; 4],
+        long_gap_extend_cost_vector: vec![Cost::MAX; 4],
+        homopolymer_gap_extend_costs: None,
+        context_substitution_cost_tables: vec![None; 4],
         phantom_data: Default::default(),
     };
 
@@ -25,3 +37,188 @@ fn simple_example() {
     assert_eq!(expected_parsing_result, actual_parsing_result);
     assert_eq!(input, output);
 }
+
+#[test]
+fn homopolymer_gap_extend_costs() {
+    let input = "# Long Reads\n\nSubstitutionCostTable\n  | A C G T\n--+--------\nA | 0 4 4 4\nC | 4 0 4 4\nG | 4 4 0 4\nT | 4 4 4 0\n\nGapOpenCostVector\n A C G T\n 6 6 6 6\n\nGapExtendCostVector\n A C G T\n 2 2 2 2\n\nHomopolymerGapExtendCostFunction\n0 4   8\n0 1 inf\n";
+    let expected_parsing_result = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "Long Reads",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_homopolymer_gap_extend_costs(
+        vec![(0, 0.into()), (4, 1.into()), (8, Cost::MAX)]
+            .try_into()
+            .unwrap(),
+    );
+
+    let actual_parsing_result =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(input.as_bytes()).unwrap();
+    let mut writer = Vec::new();
+    actual_parsing_result.write_plain(&mut writer).unwrap();
+    let output = String::from_utf8(writer).unwrap();
+
+    assert_eq!(expected_parsing_result, actual_parsing_result);
+    assert_eq!(input, output);
+}
+
+#[test]
+fn long_gap_costs() {
+    let input = "# Two Piece\n\nSubstitutionCostTable\n  | A C G T\n--+--------\nA | 0 4 4 4\nC | 4 0 4 4\nG | 4 4 0 4\nT | 4 4 4 0\n\nGapOpenCostVector\n A C G T\n 6 6 6 6\n\nGapExtendCostVector\n A C G T\n 2 2 2 2\n\nLongGapOpenCostVector\n  A  C  G  T\n 24 24 24 24\n\nLongGapExtendCostVector\n A C G T\n 1 1 1 1\n";
+    let expected_parsing_result = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "Two Piece",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_long_gap_costs(24.into(), 1.into());
+
+    let actual_parsing_result =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(input.as_bytes()).unwrap();
+    let mut writer = Vec::new();
+    actual_parsing_result.write_plain(&mut writer).unwrap();
+    let output = String::from_utf8(writer).unwrap();
+
+    assert_eq!(expected_parsing_result, actual_parsing_result);
+    assert_eq!(input, output);
+}
+
+#[test]
+fn lenient_parts_in_any_order() {
+    let strict = "# Simple Example\n\nSubstitutionCostTable\n  |  A  C  G  T\n--+------------\nA | 10  9 11  7\nC |  2  1  3  4\nG |  5  6 17  8\nT | 99  0 50 51\n\nGapOpenCostVector\n A C G T\n 3 4 5 1\n\nGapExtendCostVector\n  A  C  G  T\n 10 15  0  1\n";
+    let reordered = "# Simple Example\n\nGapExtendCostVector\n  A  C  G  T\n 10 15  0  1\n\nGapOpenCostVector\n A C G T\n 3 4 5 1\n\nSubstitutionCostTable\n  |  A  C  G  T\n--+------------\nA | 10  9 11  7\nC |  2  1  3  4\nG |  5  6 17  8\nT | 99  0 50 51\n";
+
+    let expected =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(strict.as_bytes()).unwrap();
+    assert!(GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(reordered.as_bytes()).is_err());
+    assert_eq!(
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+            reordered.as_bytes(),
+            ParseMode::Lenient
+        )
+        .unwrap(),
+        expected
+    );
+
+    // Required parts must still be given, and only once.
+    let missing = &reordered[..reordered.find("SubstitutionCostTable").unwrap()];
+    match GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+        missing.as_bytes(),
+        ParseMode::Lenient,
+    ) {
+        Err(Error::ParserDiagnostic { line, expected, .. }) => {
+            assert_eq!(line, 11);
+            assert!(expected.contains("SubstitutionCostTable"), "{expected}");
+        }
+        result => panic!("{result:?}"),
+    }
+    let duplicate = format!("{reordered}\nGapOpenCostVector\n A C G T\n 3 4 5 1\n");
+    let result = GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+        duplicate.as_bytes(),
+        ParseMode::Lenient,
+    );
+    assert!(
+        matches!(result, Err(Error::ParserDiagnostic { line: 19, .. })),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn asymmetric_gap_costs() {
+    let input = "# Asymmetric\n\nSubstitutionCostTable\n  | A C G T\n--+--------\nA | 0 4 4 4\nC | 4 0 4 4\nG | 4 4 0 4\nT | 4 4 4 0\n\nInsertionGapOpenCostVector\n A C G T\n 6 6 6 6\n\nInsertionGapExtendCostVector\n A C G T\n 2 2 2 2\n\nDeletionGapOpenCostVector\n A C G T\n 8 8 8 8\n\nDeletionGapExtendCostVector\n A C G T\n 3 3 3 3\n";
+    let expected_parsing_result = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "Asymmetric",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_deletion_gap_cost_vectors(vec![8.into(); 4], vec![3.into(); 4]);
+
+    let actual_parsing_result =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(input.as_bytes()).unwrap();
+    let mut writer = Vec::new();
+    actual_parsing_result.write_plain(&mut writer).unwrap();
+    let output = String::from_utf8(writer).unwrap();
+
+    assert!(!actual_parsing_result.has_symmetric_gaps());
+    assert_eq!(expected_parsing_result, actual_parsing_result);
+    assert_eq!(input, output);
+
+    // If only one direction is given, then the other gets the same costs.
+    let deletions_only = input.replace("Insertion", "Deletion");
+    let deletions_only = &deletions_only[..deletions_only.rfind("\nDeletionGapOpen").unwrap()];
+    let parsing_result =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(deletions_only.as_bytes()).unwrap();
+    assert!(parsing_result.has_symmetric_gaps());
+    assert_eq!(
+        parsing_result,
+        GapAffineAlignmentCostTable::new_uniform("Asymmetric", 4.into(), 6.into(), 2.into())
+    );
+    assert_eq!(
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+            deletions_only.as_bytes(),
+            ParseMode::Lenient
+        )
+        .unwrap(),
+        parsing_result
+    );
+
+    // The general gap costs apply to the direction that is not given separately.
+    let general_and_deletions = input
+        .replace("InsertionGapOpen", "GapOpen")
+        .replace("InsertionGapExtend", "GapExtend");
+    assert_eq!(
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(general_and_deletions.as_bytes())
+            .unwrap(),
+        expected_parsing_result
+    );
+}
+
+#[test]
+fn context_substitution_cost_tables() {
+    let input = "# CpG\n\nSubstitutionCostTable\n  | A C G T\n--+--------\nA | 0 4 4 4\nC | 4 0 4 4\nG | 4 4 0 4\nT | 4 4 4 0\n\nGapOpenCostVector\n A C G T\n 6 6 6 6\n\nGapExtendCostVector\n A C G T\n 2 2 2 2\n\nContextSubstitutionCostTable C\n  | A C G T\n--+--------\nA | 0 4 4 4\nC | 4 0 4 4\nG | 1 4 0 4\nT | 4 4 4 0\n";
+    let c = DnaAlphabet::ascii_to_character(b'C').unwrap();
+    let expected_parsing_result = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "CpG",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_context_substitution_cost_table(
+        c,
+        [0, 4, 4, 4, 4, 0, 4, 4, 1, 4, 0, 4, 4, 4, 4, 0].map(Cost::from),
+    );
+
+    let actual_parsing_result =
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(input.as_bytes()).unwrap();
+    let mut writer = Vec::new();
+    actual_parsing_result.write_plain(&mut writer).unwrap();
+    let output = String::from_utf8(writer).unwrap();
+
+    assert_eq!(expected_parsing_result, actual_parsing_result);
+    assert_eq!(input, output);
+    assert_eq!(
+        GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+            input.as_bytes(),
+            ParseMode::Lenient
+        )
+        .unwrap(),
+        expected_parsing_result
+    );
+
+    // Each context has at most one table.
+    let duplicate = format!(
+        "{input}\n{}",
+        &input[input.find("ContextSubstitutionCostTable").unwrap()..]
+    );
+    assert!(GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain(duplicate.as_bytes()).is_err());
+    let result = GapAffineAlignmentCostTable::<DnaAlphabet>::read_plain_with_mode(
+        duplicate.as_bytes(),
+        ParseMode::Lenient,
+    );
+    assert!(
+        matches!(result, Err(Error::ParserDiagnostic { line: 27, .. })),
+        "{result:?}"
+    );
+}
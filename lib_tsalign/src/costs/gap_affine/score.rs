@@ -0,0 +1,220 @@
+use std::marker::PhantomData;
+
+use compact_genome::interface::alphabet::{Alphabet, AlphabetCharacter};
+
+use crate::{
+    costs::{cost::Cost, score::Score},
+    error::{Error, Result},
+};
+
+use super::GapAffineAlignmentCostTable;
+
+/// Like [`GapAffineAlignmentCostTable`], but with scores that are maximised instead of costs that are minimised.
+///
+/// A score of `i64::MIN` is treated as negative infinity.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GapAffineAlignmentScoreTable<AlphabetType> {
+    name: String,
+    substitution_score_table: Vec<i64>,
+    gap_open_score_vector: Vec<i64>,
+    gap_extend_score_vector: Vec<i64>,
+    phantom_data: PhantomData<AlphabetType>,
+}
+
+impl<AlphabetType: Alphabet> GapAffineAlignmentScoreTable<AlphabetType> {
+    pub fn new(
+        name: impl Into<String>,
+        substitution_score_table: impl Into<Vec<i64>>,
+        gap_open_score_vector: impl Into<Vec<i64>>,
+        gap_extend_score_vector: impl Into<Vec<i64>>,
+    ) -> Self {
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        let substitution_score_table = substitution_score_table.into();
+        let gap_open_score_vector = gap_open_score_vector.into();
+        let gap_extend_score_vector = gap_extend_score_vector.into();
+        assert_eq!(
+            substitution_score_table.len(),
+            alphabet_size * alphabet_size
+        );
+        assert_eq!(gap_open_score_vector.len(), alphabet_size);
+        assert_eq!(gap_extend_score_vector.len(), alphabet_size);
+
+        Self {
+            name: name.into(),
+            substitution_score_table,
+            gap_open_score_vector,
+            gap_extend_score_vector,
+            phantom_data: Default::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn match_or_substitution_score(
+        &self,
+        c1: impl Into<AlphabetType::CharacterType>,
+        c2: impl Into<AlphabetType::CharacterType>,
+    ) -> i64 {
+        let c1: usize = c1.into().index().into();
+        let c2: usize = c2.into().index().into();
+
+        self.substitution_score_table[c1 * usize::from(AlphabetType::SIZE) + c2]
+    }
+
+    pub fn gap_open_score(&self, c: impl Into<AlphabetType::CharacterType>) -> i64 {
+        self.gap_open_score_vector[usize::from(c.into().index())]
+    }
+
+    pub fn gap_extend_score(&self, c: impl Into<AlphabetType::CharacterType>) -> i64 {
+        self.gap_extend_score_vector[usize::from(c.into().index())]
+    }
+
+    /// The maximum of all finite substitution scores and twice all finite gap scores, called `M` in [`GapAffineAlignmentCostTable::from_scores`].
+    ///
+    /// This is twice the highest score that a single character of the aligned sequences can contribute.
+    pub fn max_score(&self) -> i128 {
+        let finite = |score: &&i64| **score != i64::MIN;
+        self.substitution_score_table
+            .iter()
+            .filter(finite)
+            .map(|&score| i128::from(score))
+            .chain(
+                self.gap_open_score_vector
+                    .iter()
+                    .chain(&self.gap_extend_score_vector)
+                    .filter(finite)
+                    .map(|&score| 2 * i128::from(score)),
+            )
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the score of a global alignment of sequences with the given lengths that has the given cost under [`GapAffineAlignmentCostTable::from_scores`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cost is not the cost of such an alignment, i.e. if the score would not be integral.
+    pub fn cost_to_score(&self, cost: Cost, reference_length: usize, query_length: usize) -> Score {
+        let doubled_score =
+            self.total_max_score(reference_length, query_length) - i128::from(cost.as_u64());
+        assert_eq!(
+            doubled_score % 2,
+            0,
+            "Cost {cost} is not the cost of an alignment under these scores"
+        );
+        i64::try_from(doubled_score / 2).unwrap().into()
+    }
+
+    /// Returns the cost under [`GapAffineAlignmentCostTable::from_scores`] of a global alignment of sequences with the given lengths that has the given score.
+    ///
+    /// Returns an error if the score is higher than the score of any such alignment,
+    /// or if the cost does not fit into a [`Cost`].
+    pub fn score_to_cost(
+        &self,
+        score: Score,
+        reference_length: usize,
+        query_length: usize,
+    ) -> Result<Cost> {
+        u64::try_from(
+            self.total_max_score(reference_length, query_length) - 2 * i128::from(score.as_i64()),
+        )
+        .map(Cost::from)
+        .map_err(|_| Error::ScoreOutOfRange {
+            score,
+            reference_length,
+            query_length,
+        })
+    }
+
+    fn total_max_score(&self, reference_length: usize, query_length: usize) -> i128 {
+        let length =
+            i128::try_from(reference_length).unwrap() + i128::try_from(query_length).unwrap();
+        length * self.max_score()
+    }
+}
+
+impl<AlphabetType: Alphabet> GapAffineAlignmentCostTable<AlphabetType> {
+    /// Converts scores into costs such that global alignments keep their order.
+    ///
+    /// Let `M` be [`GapAffineAlignmentScoreTable::max_score`].
+    /// Then substitutions cost `2 * (M - score)` and gap characters cost `M - 2 * score`.
+    /// This way, a global alignment of sequences with a total length of `n` gets cost `n * M - 2 * score`,
+    /// so the optimal alignments under the costs are exactly the optimal alignments under the scores.
+    /// This is the reduced cost of the negated doubled scores under the potential `-M * (i + j)` at position `(i, j)`,
+    /// see [`generic_a_star::potential`].
+    /// Use [`GapAffineAlignmentScoreTable::cost_to_score`] to convert the cost of an alignment back into its score.
+    ///
+    /// Scores of `i64::MIN` become `Cost::MAX`, and finite costs saturate just below `Cost::MAX`.
+    pub fn from_scores(scores: &GapAffineAlignmentScoreTable<AlphabetType>) -> Self {
+        let max_score = scores.max_score();
+        let cost = |score: i64, cost: &dyn Fn(i128) -> i128| {
+            if score == i64::MIN {
+                Cost::MAX
+            } else {
+                u64::try_from(cost(i128::from(score)))
+                    .unwrap_or(u64::MAX - 1)
+                    .min(u64::MAX - 1)
+                    .into()
+            }
+        };
+        let gap_open_cost_vector: Vec<_> = scores
+            .gap_open_score_vector
+            .iter()
+            .map(|&score| cost(score, &|score| max_score - 2 * score))
+            .collect();
+        let gap_extend_cost_vector: Vec<_> = scores
+            .gap_extend_score_vector
+            .iter()
+            .map(|&score| cost(score, &|score| max_score - 2 * score))
+            .collect();
+
+        Self {
+            name: scores.name.clone(),
+            substitution_cost_table: scores
+                .substitution_score_table
+                .iter()
+                .map(|&score| cost(score, &|score| 2 * (max_score - score)))
+                .collect(),
+            insertion_gap_open_cost_vector: gap_open_cost_vector.clone(),
+            insertion_gap_extend_cost_vector: gap_extend_cost_vector.clone(),
+            deletion_gap_open_cost_vector: gap_open_cost_vector,
+            deletion_gap_extend_cost_vector: gap_extend_cost_vector,
+            long_gap_open_cost_vector: vec![Cost::MAX; scores.gap_open_score_vector.len()],
+            long_gap_extend_cost_vector: vec![Cost::MAX; scores.gap_extend_score_vector.len()],
+            homopolymer_gap_extend_costs: None,
+            context_substitution_cost_tables: vec![None; AlphabetType::SIZE.into()],
+            phantom_data: Default::default(),
+        }
+    }
+
+    /// Converts costs into scores by negating them.
+    ///
+    /// `Cost::MAX` and costs that do not fit into an `i64` become `i64::MIN`.
+    /// Score tables have neither long gap scores, homopolymer gap extend scores nor context substitution scores, so these costs are dropped.
+    /// They also have the same gap scores for insertions and deletions, so the gap scores are taken from the insertion costs.
+    pub fn to_scores(&self) -> GapAffineAlignmentScoreTable<AlphabetType> {
+        let score = |cost: &Cost| {
+            i64::try_from(cost.as_u64())
+                .map(|cost| -cost)
+                .unwrap_or(i64::MIN)
+        };
+
+        GapAffineAlignmentScoreTable {
+            name: self.name.clone(),
+            substitution_score_table: self.substitution_cost_table.iter().map(score).collect(),
+            gap_open_score_vector: self
+                .insertion_gap_open_cost_vector
+                .iter()
+                .map(score)
+                .collect(),
+            gap_extend_score_vector: self
+                .insertion_gap_extend_cost_vector
+                .iter()
+                .map(score)
+                .collect(),
+            phantom_data: Default::default(),
+        }
+    }
+}
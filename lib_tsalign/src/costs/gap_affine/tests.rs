@@ -0,0 +1,454 @@
+use compact_genome::{
+    implementation::alphabets::dna_alphabet::DnaAlphabet, interface::alphabet::Alphabet,
+};
+
+use crate::costs::cost::Cost;
+
+use super::{score::GapAffineAlignmentScoreTable, GapAffineAlignmentCostTable, IndelType};
+
+fn table(
+    substitution_costs: [u64; 16],
+    gap_costs: [u64; 8],
+) -> GapAffineAlignmentCostTable<DnaAlphabet> {
+    GapAffineAlignmentCostTable::new(
+        "table",
+        substitution_costs.map(Cost::from),
+        gap_costs[..4]
+            .iter()
+            .copied()
+            .map(Cost::from)
+            .collect::<Vec<_>>(),
+        gap_costs[4..]
+            .iter()
+            .copied()
+            .map(Cost::from)
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[test]
+fn builder_equals_uniform() {
+    let built = GapAffineAlignmentCostTable::<DnaAlphabet>::new_zero()
+        .with_name("uniform")
+        .with_match_cost(1.into())
+        .with_substitution_cost(4.into())
+        .with_gap_open_cost(6.into())
+        .with_gap_extend_cost(2.into());
+    let expected = table(
+        [1, 4, 4, 4, 4, 1, 4, 4, 4, 4, 1, 4, 4, 4, 4, 1],
+        [6, 6, 6, 6, 2, 2, 2, 2],
+    )
+    .with_name("uniform");
+
+    assert_eq!(built, expected);
+    assert_eq!(
+        GapAffineAlignmentCostTable::new_uniform("uniform", 4.into(), 6.into(), 2.into()),
+        expected.with_match_cost(Cost::ZERO),
+    );
+}
+
+#[test]
+fn scale_keeps_infinite_costs() {
+    let scaled = table(
+        [0, 1, 2, 3, 4, 0, 5, 6, 7, 8, 0, 9, 10, 11, 12, u64::MAX],
+        [1, 2, 3, 4, 5, 6, 7, u64::MAX / 2],
+    )
+    .scale(3);
+
+    assert_eq!(
+        scaled,
+        table(
+            [
+                0,
+                3,
+                6,
+                9,
+                12,
+                0,
+                15,
+                18,
+                21,
+                24,
+                0,
+                27,
+                30,
+                33,
+                36,
+                u64::MAX
+            ],
+            [3, 6, 9, 12, 15, 18, 21, u64::MAX],
+        ),
+    );
+}
+
+#[test]
+fn pointwise_min_and_max() {
+    let a = table(
+        [0, 1, 2, 3, 4, 0, 5, 6, 7, 8, 0, 9, 10, 11, 12, 0],
+        [1, 9, 1, 9, 1, 9, 1, 9],
+    );
+    let b = table(
+        [1, 0, 3, 2, 0, 4, 0, 7, 8, 7, 1, 1, 11, 10, 12, 0],
+        [9, 1, 9, 1, 9, 1, 9, 1],
+    );
+
+    assert_eq!(
+        a.clone().pointwise_min(&b),
+        table(
+            [0, 0, 2, 2, 0, 0, 0, 6, 7, 7, 0, 1, 10, 10, 12, 0],
+            [1, 1, 1, 1, 1, 1, 1, 1],
+        ),
+    );
+    assert_eq!(
+        a.pointwise_max(&b),
+        table(
+            [1, 1, 3, 3, 4, 4, 5, 7, 8, 8, 1, 9, 11, 11, 12, 0],
+            [9, 9, 9, 9, 9, 9, 9, 9],
+        ),
+    );
+}
+
+#[test]
+fn into_symmetric_takes_cheaper_direction() {
+    let asymmetric = table(
+        [0, 1, 2, 3, 4, 0, 5, 6, 7, 8, 0, 9, 10, 11, 12, 0],
+        [1, 1, 1, 1, 1, 1, 1, 1],
+    );
+    assert!(!asymmetric.is_symmetric());
+
+    let symmetric = asymmetric.into_symmetric();
+    assert!(symmetric.is_symmetric());
+    assert_eq!(
+        symmetric,
+        table(
+            [0, 1, 2, 3, 1, 0, 5, 6, 2, 5, 0, 9, 3, 6, 9, 0],
+            [1, 1, 1, 1, 1, 1, 1, 1],
+        ),
+    );
+}
+
+#[test]
+fn scores_to_costs() {
+    let scores = GapAffineAlignmentScoreTable::<DnaAlphabet>::new(
+        "scores",
+        [
+            2,
+            -1,
+            -1,
+            -1,
+            -1,
+            2,
+            -1,
+            -1,
+            -1,
+            -1,
+            2,
+            -1,
+            -1,
+            -1,
+            -1,
+            i64::MIN,
+        ],
+        [-3; 4],
+        [-1; 4],
+    );
+
+    // `M` is 2, so substitutions cost `2 * (2 - score)` and gaps cost `2 - 2 * score`.
+    let costs = GapAffineAlignmentCostTable::from_scores(&scores);
+    assert_eq!(
+        costs,
+        table(
+            [0, 6, 6, 6, 6, 0, 6, 6, 6, 6, 0, 6, 6, 6, 6, u64::MAX],
+            [8, 8, 8, 8, 4, 4, 4, 4],
+        )
+        .with_name("scores"),
+    );
+
+    // Negated costs have the same optimal alignments, so converting them back yields the same costs up to scaling.
+    assert_eq!(
+        GapAffineAlignmentCostTable::from_scores(&costs.to_scores()),
+        costs.clone().scale(2),
+    );
+    let t = DnaAlphabet::ascii_to_character(b'T').unwrap();
+    assert_eq!(
+        costs.to_scores().match_or_substitution_score(t, t),
+        i64::MIN
+    );
+}
+
+fn homopolymer_table() -> GapAffineAlignmentCostTable<DnaAlphabet> {
+    table(
+        [0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0],
+        [6, 6, 6, 6, 3, 3, 2, 3],
+    )
+    .with_homopolymer_gap_extend_costs(vec![(0, 1.into()), (3, 2.into())].try_into().unwrap())
+}
+
+#[test]
+fn homopolymer_gap_costs() {
+    let a = DnaAlphabet::ascii_to_character(b'A').unwrap();
+    let c = DnaAlphabet::ascii_to_character(b'C').unwrap();
+    let costs = homopolymer_table();
+
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, None, 0),
+        (6.into(), 1)
+    );
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, Some(a), 1),
+        (1.into(), 2)
+    );
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, Some(a), 3),
+        (2.into(), 4)
+    );
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, Some(a), u8::MAX),
+        (2.into(), u8::MAX)
+    );
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, Some(c), 2),
+        (3.into(), 1)
+    );
+
+    // Without homopolymer gap extend costs, the homopolymer length is not tracked.
+    let costs = costs.without_homopolymer_gap_extend_costs();
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, None, 0),
+        (6.into(), 0)
+    );
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Insertion, false, Some(a), 0),
+        (3.into(), 0)
+    );
+}
+
+#[test]
+fn homopolymer_gap_costs_lower_bound() {
+    let costs = homopolymer_table();
+    assert_eq!(costs.min_gap_extend_cost(), 1.into());
+
+    let lower_bound = costs.clone().into_lower_bound();
+    assert!(!lower_bound.has_homopolymer_gap_extend_costs());
+    assert_eq!(lower_bound.min_gap_extend_cost(), 1.into());
+    assert!(!costs
+        .into_match_agnostic_lower_bound()
+        .has_homopolymer_gap_extend_costs());
+}
+
+#[test]
+fn homopolymer_gap_costs_arithmetic() {
+    let costs = homopolymer_table();
+    assert_eq!(
+        costs.clone().scale(2).homopolymer_gap_extend_costs(),
+        Some(&vec![(0, 2.into()), (3, 4.into())].try_into().unwrap())
+    );
+
+    // The other table charges its gap extend costs in homopolymers as well.
+    let other = table(
+        [0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0],
+        [6, 6, 6, 6, 5, 5, 5, 5],
+    );
+    assert_eq!(
+        costs
+            .clone()
+            .pointwise_min(&other)
+            .homopolymer_gap_extend_costs(),
+        Some(&vec![(0, 1.into()), (3, 2.into())].try_into().unwrap())
+    );
+    assert_eq!(
+        costs.pointwise_max(&other).homopolymer_gap_extend_costs(),
+        Some(&vec![(0, 5.into())].try_into().unwrap())
+    );
+}
+
+#[test]
+fn long_gap_costs() {
+    let a = DnaAlphabet::ascii_to_character(b'A').unwrap();
+    let costs = table(
+        [0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0],
+        [6, 6, 6, 6, 3, 3, 2, 3],
+    );
+    assert!(!costs.has_long_gaps());
+    assert_eq!(costs.long_gap_costs(a, true), Cost::MAX);
+    assert_eq!(costs.max_gap_open_cost(), 6.into());
+
+    let costs = costs.with_long_gap_costs(20.into(), 1.into());
+    assert!(costs.has_long_gaps());
+    assert_eq!(costs.long_gap_costs(a, true), 20.into());
+    assert_eq!(costs.long_gap_costs(a, false), 1.into());
+    assert_eq!(
+        costs.homopolymer_gap_costs(a, IndelType::Deletion, true, Some(a), 0),
+        (1.into(), 0)
+    );
+    assert_eq!(costs.min_gap_open_cost(), 6.into());
+    assert_eq!(costs.max_gap_open_cost(), 20.into());
+    assert_eq!(costs.min_gap_extend_cost(), 1.into());
+
+    // The lower bound lowers the costs of short and long gaps separately.
+    let lower_bound = costs.clone().into_lower_bound();
+    assert_eq!(
+        lower_bound.gap_costs(a, IndelType::Insertion, false),
+        2.into()
+    );
+    assert_eq!(lower_bound.long_gap_costs(a, true), 20.into());
+    assert_eq!(costs.scale(2).long_gap_costs(a, false), 2.into());
+}
+
+#[test]
+fn asymmetric_gap_costs() {
+    let a = DnaAlphabet::ascii_to_character(b'A').unwrap();
+    let costs = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "Asymmetric",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_deletion_gap_cost_vectors([8, 8, 1, 8].map(Cost::from), [3, 3, 3, 1].map(Cost::from));
+    assert!(!costs.has_symmetric_gaps());
+    assert!(!costs.is_symmetric());
+    assert_eq!(costs.gap_costs(a, IndelType::Insertion, true), 6.into());
+    assert_eq!(costs.gap_costs(a, IndelType::Deletion, true), 8.into());
+    assert_eq!(costs.gap_costs(a, IndelType::Deletion, false), 3.into());
+    assert_eq!(costs.min_gap_open_cost(), 1.into());
+    assert_eq!(costs.max_gap_open_cost(), 8.into());
+    assert_eq!(costs.min_gap_extend_cost(), 1.into());
+
+    // Lower bounds are symmetric and take the cheaper direction.
+    for lower_bound in [
+        costs.clone().into_lower_bound(),
+        costs.clone().into_match_agnostic_lower_bound(),
+    ] {
+        assert!(lower_bound.has_symmetric_gaps());
+        for indel_type in [IndelType::Insertion, IndelType::Deletion] {
+            assert_eq!(lower_bound.gap_costs(a, indel_type, true), 1.into());
+            assert_eq!(lower_bound.gap_costs(a, indel_type, false), 1.into());
+        }
+    }
+
+    let symmetric = costs.into_symmetric();
+    assert!(symmetric.is_symmetric());
+    assert_eq!(symmetric.gap_costs(a, IndelType::Deletion, true), 6.into());
+    assert_eq!(
+        symmetric.gap_costs(a, IndelType::Insertion, false),
+        2.into()
+    );
+}
+
+#[test]
+fn context_substitution_costs() {
+    let a = DnaAlphabet::ascii_to_character(b'A').unwrap();
+    let c = DnaAlphabet::ascii_to_character(b'C').unwrap();
+    let g = DnaAlphabet::ascii_to_character(b'G').unwrap();
+    let t = DnaAlphabet::ascii_to_character(b'T').unwrap();
+    let costs = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "CpG",
+        4.into(),
+        6.into(),
+        2.into(),
+    )
+    .with_context_substitution_cost_table(
+        c,
+        [0, 4, 4, 4, 4, 0, 4, 4, 1, 4, 0, 4, 4, 4, 4, 0].map(Cost::from),
+    );
+    assert!(costs.has_context_substitution_costs());
+    assert!(!costs.is_symmetric());
+
+    assert_eq!(
+        costs.context_match_or_substitution_cost(Some(c), g, a),
+        1.into()
+    );
+    assert_eq!(
+        costs.context_match_or_substitution_cost(Some(t), g, a),
+        4.into()
+    );
+    assert_eq!(
+        costs.context_match_or_substitution_cost(None, g, a),
+        4.into()
+    );
+    assert_eq!(costs.match_or_substitution_cost(g, a), 4.into());
+    assert_eq!(costs.min_substitution_cost(), 1.into());
+    assert_eq!(costs.min_match_cost(), 0.into());
+
+    // Lower bounds include the context substitution costs in their minima.
+    for (lower_bound, expected_cost) in [
+        (costs.clone().into_lower_bound(), 1),
+        (costs.clone().into_match_agnostic_lower_bound(), 0),
+        (costs.clone().into_context_free_lower_bound(), 1),
+    ] {
+        assert!(!lower_bound.has_context_substitution_costs());
+        assert_eq!(
+            lower_bound.context_match_or_substitution_cost(Some(c), g, a),
+            expected_cost.into()
+        );
+    }
+    let context_free = costs.clone().into_context_free_lower_bound();
+    assert_eq!(context_free.substitution_cost(a, g), 4.into());
+
+    // Tables without a context table use their substitution costs in that context.
+    let other = GapAffineAlignmentCostTable::new_uniform("", 3.into(), 0.into(), 0.into());
+    let max = costs.clone().pointwise_max(&other);
+    assert_eq!(
+        max.context_match_or_substitution_cost(Some(c), g, a),
+        3.into()
+    );
+    assert_eq!(
+        max.context_match_or_substitution_cost(Some(a), g, a),
+        4.into()
+    );
+    let min = costs.clone().pointwise_min(&other);
+    assert_eq!(
+        min.context_match_or_substitution_cost(Some(c), g, a),
+        1.into()
+    );
+    assert_eq!(
+        min.context_match_or_substitution_cost(Some(a), g, a),
+        3.into()
+    );
+    assert_eq!(
+        costs
+            .clone()
+            .scale(2)
+            .context_match_or_substitution_cost(Some(c), g, a),
+        2.into()
+    );
+
+    let symmetric = costs.into_symmetric();
+    assert!(symmetric.is_symmetric());
+    assert_eq!(
+        symmetric.context_match_or_substitution_cost(Some(c), a, g),
+        1.into()
+    );
+}
+
+#[test]
+fn greedy_match_extension() {
+    let c = DnaAlphabet::ascii_to_character(b'C').unwrap();
+    let costs = GapAffineAlignmentCostTable::<DnaAlphabet>::new_uniform(
+        "uniform",
+        4.into(),
+        6.into(),
+        2.into(),
+    );
+    assert!(costs.allows_greedy_match_extension());
+
+    for costs in [
+        costs.clone().with_match_cost(1.into()),
+        table(
+            [0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0, 4, 4, 4, 4, 0],
+            [6, 6, 6, 6, 3, 3, 2, 3],
+        ),
+        GapAffineAlignmentCostTable::new_uniform("cheap open", 4.into(), 1.into(), 2.into()),
+        costs.clone().with_long_gap_costs(20.into(), 1.into()),
+        costs
+            .clone()
+            .with_deletion_gap_cost_vectors([8; 4].map(Cost::from), [2; 4].map(Cost::from)),
+        costs.clone().with_context_substitution_cost_table(
+            c,
+            [0, 4, 4, 4, 4, 0, 4, 4, 1, 4, 0, 4, 4, 4, 4, 0].map(Cost::from),
+        ),
+        homopolymer_table(),
+    ] {
+        assert!(!costs.allows_greedy_match_extension(), "{costs:?}");
+    }
+}
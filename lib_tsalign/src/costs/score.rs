@@ -0,0 +1,177 @@
+//! Scores that are maximised, as an alternative to the costs that are minimised by the aligners.
+//!
+//! Scores reward matches with a bonus, like in BLAST or minimap2.
+//! For global alignments, they are converted into costs with [`GapAffineAlignmentCostTable::from_scores`].
+
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Neg, Sub},
+};
+
+use compact_genome::{
+    implementation::alphabets::dna_alphabet::DnaAlphabet, interface::alphabet::Alphabet,
+};
+
+use crate::{
+    a_star_aligner::gap_affine_edit_distance::ScoringTable,
+    costs::{
+        cost::Cost,
+        gap_affine::{score::GapAffineAlignmentScoreTable, GapAffineAlignmentCostTable, IndelType},
+    },
+    error::Result,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The score of an alignment, which is maximised.
+///
+/// Unlike [`Cost`], scores can be negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score(i64);
+
+/// Scores for aligning with gap-affine costs, the score-based counterpart of [`ScoringTable`].
+///
+/// Like for the costs, the gap open score is the score of the first character of a gap, and the gap extend score that of each further character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoringScheme {
+    /// The bonus for a match, usually positive.
+    pub match_score: Score,
+    pub substitution_score: Score,
+    pub gap_open_score: Score,
+    pub gap_extend_score: Score,
+}
+
+impl Score {
+    pub const ZERO: Self = Self(0);
+
+    pub fn as_i64(&self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for Score {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl ScoringScheme {
+    /// Returns a score table that assigns these scores uniformly to all characters of the alphabet.
+    pub fn to_score_table<AlphabetType: Alphabet>(
+        &self,
+        name: impl Into<String>,
+    ) -> GapAffineAlignmentScoreTable<AlphabetType> {
+        let alphabet_size = usize::from(AlphabetType::SIZE);
+        let substitution_score_table = (0..alphabet_size * alphabet_size)
+            .map(|index| {
+                if index / alphabet_size == index % alphabet_size {
+                    self.match_score.0
+                } else {
+                    self.substitution_score.0
+                }
+            })
+            .collect::<Vec<_>>();
+
+        GapAffineAlignmentScoreTable::new(
+            name,
+            substitution_score_table,
+            vec![self.gap_open_score.0; alphabet_size],
+            vec![self.gap_extend_score.0; alphabet_size],
+        )
+    }
+
+    /// See [`GapAffineAlignmentScoreTable::cost_to_score`].
+    pub fn cost_to_score(&self, cost: Cost, reference_length: usize, query_length: usize) -> Score {
+        self.uniform_score_table()
+            .cost_to_score(cost, reference_length, query_length)
+    }
+
+    /// See [`GapAffineAlignmentScoreTable::score_to_cost`].
+    pub fn score_to_cost(
+        &self,
+        score: Score,
+        reference_length: usize,
+        query_length: usize,
+    ) -> Result<Cost> {
+        self.uniform_score_table()
+            .score_to_cost(score, reference_length, query_length)
+    }
+
+    /// The scores are the same for all characters, so any alphabet with both matches and substitutions yields the same costs.
+    fn uniform_score_table(&self) -> GapAffineAlignmentScoreTable<DnaAlphabet> {
+        self.to_score_table("")
+    }
+}
+
+impl ScoringTable {
+    /// Converts scores into costs with [`GapAffineAlignmentCostTable::from_scores`].
+    pub fn from_scores(scores: &ScoringScheme) -> Self {
+        let costs = GapAffineAlignmentCostTable::from_scores(&scores.uniform_score_table());
+        let [a, c] =
+            [b'A', b'C'].map(|character| DnaAlphabet::ascii_to_character(character).unwrap());
+
+        Self {
+            match_cost: costs.match_or_substitution_cost(a, a),
+            substitution_cost: costs.match_or_substitution_cost(a, c),
+            gap_open_cost: costs.gap_open_cost(a, IndelType::Insertion),
+            gap_extend_cost: costs.gap_extend_cost(a, IndelType::Insertion),
+        }
+    }
+
+    /// Converts costs into scores by negating them.
+    ///
+    /// Converting the scores back with [`from_scores`](Self::from_scores) yields costs with the same optimal alignments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a cost does not fit into an `i64`.
+    pub fn to_scores(&self) -> ScoringScheme {
+        let score = |cost: Cost| Score(-i64::try_from(cost.as_u64()).unwrap());
+
+        ScoringScheme {
+            match_score: score(self.match_cost),
+            substitution_score: score(self.substitution_cost),
+            gap_open_score: score(self.gap_open_cost),
+            gap_extend_score: score(self.gap_extend_cost),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        gap_affine_edit_distance::ScoringTable, gap_affine_edit_distance_a_star_align,
+    },
+    costs::cost::Cost,
+    error::Error,
+};
+
+use super::{Score, ScoringScheme};
+
+/// Scores similar to the defaults of minimap2, with the gap open score including the first gap character.
+fn scoring_scheme() -> ScoringScheme {
+    ScoringScheme {
+        match_score: 2.into(),
+        substitution_score: (-4).into(),
+        gap_open_score: (-6).into(),
+        gap_extend_score: (-2).into(),
+    }
+}
+
+/// The optimal global alignment score, computed with the dynamic programming algorithm of Gotoh.
+fn optimal_score(scores: &ScoringScheme, reference: &[u8], query: &[u8]) -> i64 {
+    let negative_infinity = i64::MIN / 2;
+    let [match_score, substitution_score, gap_open_score, gap_extend_score] = [
+        scores.match_score,
+        scores.substitution_score,
+        scores.gap_open_score,
+        scores.gap_extend_score,
+    ]
+    .map(|score| score.as_i64());
+    let columns = query.len() + 1;
+    // The best score of a prefix alignment ending in a match or substitution, a deletion or an insertion.
+    let mut diagonal = vec![negative_infinity; (reference.len() + 1) * columns];
+    let mut deletion = diagonal.clone();
+    let mut insertion = diagonal.clone();
+    diagonal[0] = 0;
+
+    for reference_index in 0..=reference.len() {
+        for query_index in 0..=query.len() {
+            let index = reference_index * columns + query_index;
+            if reference_index > 0 && query_index > 0 {
+                let predecessor = index - columns - 1;
+                diagonal[index] = diagonal[predecessor]
+                    .max(deletion[predecessor])
+                    .max(insertion[predecessor])
+                    + if reference[reference_index - 1] == query[query_index - 1] {
+                        match_score
+                    } else {
+                        substitution_score
+                    };
+            }
+            if reference_index > 0 {
+                let predecessor = index - columns;
+                deletion[index] = (diagonal[predecessor].max(insertion[predecessor])
+                    + gap_open_score)
+                    .max(deletion[predecessor] + gap_extend_score);
+            }
+            if query_index > 0 {
+                let predecessor = index - 1;
+                insertion[index] = (diagonal[predecessor].max(deletion[predecessor])
+                    + gap_open_score)
+                    .max(insertion[predecessor] + gap_extend_score);
+            }
+        }
+    }
+
+    let index = diagonal.len() - 1;
+    diagonal[index].max(deletion[index]).max(insertion[index])
+}
+
+/// A deterministic pseudo-random DNA sequence.
+fn random_sequence(state: &mut u64, length: usize) -> Vec<u8> {
+    (0..length)
+        .map(|_| {
+            *state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            b"ACGT"[(*state >> 62) as usize]
+        })
+        .collect()
+}
+
+#[test]
+fn scores_to_costs() {
+    let scores = scoring_scheme();
+    assert_eq!(
+        scores.to_score_table::<DnaAlphabet>("minimap2").max_score(),
+        2
+    );
+    // `M` is 2, so matches and substitutions cost `2 * (2 - score)` and gap characters cost `2 - 2 * score`.
+    let scoring_table = ScoringTable::from_scores(&scores);
+    assert_eq!(
+        scoring_table,
+        ScoringTable {
+            match_cost: 0.into(),
+            substitution_cost: 12.into(),
+            gap_open_cost: 14.into(),
+            gap_extend_cost: 6.into(),
+        }
+    );
+
+    // Negated costs have the same optimal alignments, so converting them back yields the same costs up to scaling.
+    assert_eq!(
+        ScoringTable::from_scores(&scoring_table.to_scores()),
+        ScoringTable {
+            match_cost: 0.into(),
+            substitution_cost: 24.into(),
+            gap_open_cost: 28.into(),
+            gap_extend_cost: 12.into(),
+        }
+    );
+
+    // Two matches and a two character gap.
+    let score = Score::from(2 * 2 - 6 - 2);
+    let cost = scores.score_to_cost(score, 4, 2).unwrap();
+    assert_eq!(cost, Cost::from(14 + 6));
+    assert_eq!(scores.cost_to_score(cost, 4, 2), score);
+}
+
+#[test]
+fn extreme_scores_saturate() {
+    let scores = ScoringScheme {
+        match_score: 1.into(),
+        substitution_score: (i64::MIN + 1).into(),
+        gap_open_score: (i64::MIN + 1).into(),
+        gap_extend_score: (-1).into(),
+    };
+    // `M` is 1, and the costs of the huge negative scores do not fit into a `Cost`.
+    assert_eq!(
+        ScoringTable::from_scores(&scores),
+        ScoringTable {
+            match_cost: 0.into(),
+            substitution_cost: (u64::MAX - 1).into(),
+            gap_open_cost: (u64::MAX - 1).into(),
+            gap_extend_cost: 3.into(),
+        }
+    );
+
+    // A score above that of two matches.
+    assert!(matches!(
+        scores.score_to_cost(3.into(), 2, 2),
+        Err(Error::ScoreOutOfRange {
+            reference_length: 2,
+            query_length: 2,
+            ..
+        })
+    ));
+    // A cost that does not fit into a `Cost`.
+    assert!(matches!(
+        scores.score_to_cost(Score::from(i64::MIN + 1), 2, 2),
+        Err(Error::ScoreOutOfRange { .. })
+    ));
+}
+
+#[test]
+fn optimal_alignment_is_invariant() {
+    let scores = scoring_scheme();
+    let scoring_table = ScoringTable::from_scores(&scores);
+    let mut state = 0;
+
+    for _ in 0..50 {
+        let reference = random_sequence(&mut state, 12);
+        let mut query = random_sequence(&mut state, 10);
+        // Share a part of the sequences, such that matches matter.
+        query[2..8].copy_from_slice(&reference[3..9]);
+
+        let alignment = gap_affine_edit_distance_a_star_align(
+            VectorGenome::<DnaAlphabet>::from_slice_u8(&reference)
+                .unwrap()
+                .as_genome_subsequence(),
+            VectorGenome::from_slice_u8(&query)
+                .unwrap()
+                .as_genome_subsequence(),
+            scoring_table,
+            &Default::default(),
+        )
+        .unwrap();
+        let statistics = alignment.statistics();
+        let cost = Cost::from(statistics.cost.raw() as u64);
+
+        // The score of the alignment found with the converted costs is optimal.
+        let expected_score = optimal_score(&scores, &reference, &query);
+        let score = scores.cost_to_score(cost, reference.len(), query.len());
+        assert_eq!(
+            score.as_i64(),
+            expected_score,
+            "reference: {reference:?}, query: {query:?}"
+        );
+
+        // The score also follows from the columns of the alignment.
+        let column_score = statistics.primary_matches as i64 * scores.match_score.as_i64()
+            + statistics.primary_mismatches as i64 * scores.substitution_score.as_i64()
+            + statistics.primary_gap_opens as i64 * scores.gap_open_score.as_i64()
+            + (statistics.primary_gap_columns - statistics.primary_gap_opens) as i64
+                * scores.gap_extend_score.as_i64();
+        assert_eq!(column_score, expected_score);
+    }
+}
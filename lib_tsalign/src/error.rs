@@ -1,5 +1,13 @@
+use std::ops::Range;
+
+use generic_a_star::cost::Cost;
 use thiserror::Error;
 
+use crate::{
+    a_star_aligner::alignment_result::NoTargetReason, alignment_method::AlignmentMethod,
+    costs::score::Score,
+};
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
@@ -7,12 +15,20 @@ pub enum Error {
     #[error("An IO error occurred: {0}.")]
     Io(#[from] std::io::Error),
 
-    #[error("A parsing error of kind '{kind:?}' occurred when the remaining input was '{input}'.")]
-    Parser {
-        input: String,
-        kind: nom::error::ErrorKind,
+    #[error("Error parsing line {line}, column {column}: expected {expected}, but found {found}.")]
+    ParserDiagnostic {
+        line: usize,
+        column: usize,
+        expected: String,
+        found: String,
     },
 
+    #[error("Invalid TOML config: {message}.")]
+    InvalidTomlConfig { message: String },
+
+    #[error("Invalid node ordering model: {message}.")]
+    InvalidNodeOrdModel { message: String },
+
     #[error("Parsing was unsuccessful due to incomplete input: {0:?}.")]
     ParserIncomplete(nom::Needed),
 
@@ -25,6 +41,121 @@ pub enum Error {
         expected: Vec<String>,
     },
 
+    #[error("Invalid sequence: {0}.")]
+    Alphabet(#[from] compact_genome::interface::alphabet::AlphabetError),
+
+    #[error("Aligning the reference range {reference_range:?} to the query range {query_range:?} with chaining block size k = {block_size} and config SHA-256 {config_sha256} failed: {source}")]
+    Alignment {
+        /// The aligned range, zero-based and half-open, in the coordinates of the reference passed to the aligner
+        /// unless shifted with [`Error::offset_alignment_ranges`].
+        reference_range: Range<usize>,
+        /// Like `reference_range`, but for the query.
+        query_range: Range<usize>,
+        block_size: usize,
+        config_sha256: String,
+        source: Box<Error>,
+    },
+
+    #[error("Invalid use of the A* search: {0}.")]
+    AStarState(#[from] generic_a_star::AStarStateError),
+
+    #[error("Chaining failed: {0}")]
+    Chaining(#[from] seed_chain::error::Error),
+
+    #[error("The secondary gap extend costs must be greater than zero for all alphabet characters, but the minimum is {min_gap_extend_cost}.")]
+    ZeroSecondaryGapExtendCost { min_gap_extend_cost: Cost },
+
+    #[error("Duplication and inversion events are not supported by chaining and shortcut strategies, since their lower bounds only consider template switches.")]
+    JumpEventsWithLowerBounds,
+
+    #[error("The {event} costs must be infinite above some length, but they are finite for all lengths.")]
+    UnboundedJumpEventLength { event: &'static str },
+
+    #[error("Error parsing the alignment guide in line {line}: {message}.")]
+    GuideParse { line: usize, message: String },
+
+    #[error("Error parsing the CIGAR at character {position}: {message}.")]
+    CigarParse { position: usize, message: String },
+
+    #[error("The CIGAR does not fit the sequences: {message}.")]
+    CigarOutOfRange { message: String },
+
+    #[error("The lower bound is not admissible: {count} nodes were closed with a cost plus lower bound above the cost {target_cost} of the target. The path to the first one is:\n{}", path.join("\n"))]
+    InadmissibleLowerBound {
+        count: usize,
+        target_cost: Cost,
+        path: Vec<String>,
+    },
+
+    #[error("No alignment was found between the reference (length: {reference_length}) and the query (length: {query_length}), even though the cost was unlimited.")]
+    NoAlignmentFound {
+        reference_length: usize,
+        query_length: usize,
+    },
+
+    #[error("The aligner exceeded the memory limit of {memory_limit} bytes with a memory usage of {memory_usage} bytes.")]
+    MemoryLimitExceeded {
+        memory_limit: usize,
+        memory_usage: usize,
+    },
+
+    #[error("The random restart with tie-breaking seed {seed} found no alignment, even though the first search found one of cost {cost}.")]
+    RandomRestartWithoutTarget { seed: u64, cost: Cost },
+
+    #[error("Unknown ambiguity semantics '{name}'. Available semantics: {available:?}.")]
+    UnknownAmbiguitySemantics {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Unknown config preset '{name}'. Available presets: {available:?}.")]
+    UnknownConfigPreset {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Self-alignment is only supported by the template switch aligner, but the alignment method is {method:?}.")]
+    SelfAlignmentUnsupported { method: AlignmentMethod },
+
+    #[error("The sequence {sequence} could not be aligned to the centre of the multiple alignment: {reason:?}.")]
+    StarMsaWithoutAlignment {
+        sequence: String,
+        reason: NoTargetReason,
+    },
+
+    #[error("Precomputing the {matrix} lower bound matrix was stopped early: {reason:?}.")]
+    LowerBoundPrecomputationStopped {
+        matrix: &'static str,
+        reason: NoTargetReason,
+    },
+
+    #[error("The score {score} cannot be converted into a cost for a reference of length {reference_length} and a query of length {query_length}, since it is higher than the score of any alignment or its cost is too large.")]
+    ScoreOutOfRange {
+        score: Score,
+        reference_length: usize,
+        query_length: usize,
+    },
+
     #[error("A cost function was attempted to create from a sequence whose index does not strictly increase at {index}.")]
     CostFunctionIndexNotIncreasing { index: usize },
 }
+
+impl Error {
+    /// Shifts the ranges of an [`Error::Alignment`] by the given offsets,
+    /// for callers that passed subsequences of larger sequences to the aligner.
+    ///
+    /// Other errors are returned unchanged.
+    pub fn offset_alignment_ranges(mut self, reference_offset: usize, query_offset: usize) -> Self {
+        if let Self::Alignment {
+            reference_range,
+            query_range,
+            ..
+        } = &mut self
+        {
+            *reference_range =
+                reference_range.start + reference_offset..reference_range.end + reference_offset;
+            *query_range = query_range.start + query_offset..query_range.end + query_offset;
+        }
+        self
+    }
+}
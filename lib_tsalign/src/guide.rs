@@ -0,0 +1,245 @@
+use std::io::{BufRead, BufReader, Read};
+
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+mod tests;
+
+/// An ungapped block of matching positions proposed by an external mapper or chainer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct GuideAnchor {
+    pub reference_start: usize,
+    pub query_start: usize,
+    pub length: usize,
+}
+
+/// Restricts the primary alignment to a corridor around a path through external anchors.
+///
+/// The path starts at the origin, runs through the anchors and their connecting straight lines, and ends at the end of both sequences.
+/// Primary nodes may be at most `band` query characters away from the path, while template switches are not restricted.
+/// Since the corridor only removes alignments, the lower bounds of the aligner stay admissible.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AlignmentGuide {
+    /// The lowest query index of the corridor for each reference index.
+    lowest_query_indices: Vec<usize>,
+    /// The highest query index of the corridor for each reference index.
+    highest_query_indices: Vec<usize>,
+    anchor_count: usize,
+}
+
+impl GuideAnchor {
+    pub fn reference_end(&self) -> usize {
+        self.reference_start + self.length
+    }
+
+    pub fn query_end(&self) -> usize {
+        self.query_start + self.length
+    }
+
+    /// Reads anchors from a tab-separated file with the columns `reference_start`, `query_start` and `length`.
+    ///
+    /// Coordinates are zero-based. Empty lines and lines starting with `#` are ignored.
+    pub fn read_tsv(reader: impl Read) -> Result<Vec<Self>> {
+        let mut anchors = Vec::new();
+
+        for (line_index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<_> = line.split('\t').collect();
+            let [reference_start, query_start, length] = columns[..] else {
+                return Err(guide_error(
+                    line_index,
+                    format!("expected 3 columns, but found {}", columns.len()),
+                ));
+            };
+            anchors.push(Self {
+                reference_start: parse_column(reference_start, line_index)?,
+                query_start: parse_column(query_start, line_index)?,
+                length: parse_column(length, line_index)?,
+            });
+        }
+
+        Ok(anchors)
+    }
+
+    /// Reads anchors from the `cg:Z:` CIGAR tags of a PAF file, where the PAF target is the reference.
+    ///
+    /// Each run of `M`, `=` or `X` operations becomes an anchor.
+    /// Records on the reverse strand or without a CIGAR cannot guide a forward alignment and are skipped.
+    pub fn read_paf(reader: impl Read) -> Result<Vec<Self>> {
+        let mut anchors = Vec::new();
+
+        for (line_index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+
+            let columns: Vec<_> = line.split('\t').collect();
+            if columns.len() < 12 {
+                return Err(guide_error(
+                    line_index,
+                    format!("expected at least 12 columns, but found {}", columns.len()),
+                ));
+            }
+            if columns[4] != "+" {
+                continue;
+            }
+            let Some(cigar) = columns[12..]
+                .iter()
+                .find_map(|tag| tag.strip_prefix("cg:Z:"))
+            else {
+                continue;
+            };
+
+            let mut query_index: usize = parse_column(columns[2], line_index)?;
+            let mut reference_index: usize = parse_column(columns[7], line_index)?;
+            let mut count = 0;
+            for character in cigar.chars() {
+                if let Some(digit) = character.to_digit(10) {
+                    count = count * 10 + digit as usize;
+                    continue;
+                }
+
+                match character {
+                    'M' | '=' | 'X' => {
+                        anchors.push(Self {
+                            reference_start: reference_index,
+                            query_start: query_index,
+                            length: count,
+                        });
+                        reference_index += count;
+                        query_index += count;
+                    }
+                    'I' => query_index += count,
+                    'D' | 'N' => reference_index += count,
+                    other => {
+                        return Err(guide_error(
+                            line_index,
+                            format!("unsupported CIGAR operation '{other}'"),
+                        ))
+                    }
+                }
+                count = 0;
+            }
+        }
+
+        Ok(anchors)
+    }
+}
+
+impl AlignmentGuide {
+    /// Builds the corridor of width `band` around the path through the given anchors.
+    ///
+    /// Anchors are sorted, and anchors that overlap or are not colinear with the previously kept anchors are dropped,
+    /// as are anchors that reach past the end of the sequences.
+    pub fn new(
+        anchors: impl IntoIterator<Item = GuideAnchor>,
+        reference_length: usize,
+        query_length: usize,
+        band: usize,
+    ) -> Self {
+        let mut anchors: Vec<_> = anchors
+            .into_iter()
+            .filter(|anchor| {
+                anchor.reference_end() <= reference_length && anchor.query_end() <= query_length
+            })
+            .collect();
+        anchors.sort_unstable();
+
+        let mut points = vec![(0, 0)];
+        let mut anchor_count = 0;
+        for anchor in anchors {
+            let &(last_reference_index, last_query_index) = points.last().unwrap();
+            if anchor.reference_start < last_reference_index
+                || anchor.query_start < last_query_index
+            {
+                continue;
+            }
+
+            points.push((anchor.reference_start, anchor.query_start));
+            points.push((anchor.reference_end(), anchor.query_end()));
+            anchor_count += 1;
+        }
+        points.push((reference_length, query_length));
+
+        let mut lowest_query_indices = vec![usize::MAX; reference_length + 1];
+        let mut highest_query_indices = vec![0; reference_length + 1];
+        for window in points.windows(2) {
+            let [(reference_start, query_start), (reference_end, query_end)] = window else {
+                unreachable!()
+            };
+
+            // The path covers all query indices between its positions at reference index `i` and `i + 1`,
+            // such that consecutive corridor columns overlap and the corridor stays connected.
+            let query_at = |reference_index: usize, round_up: bool| {
+                if reference_end == reference_start {
+                    return if round_up { *query_end } else { *query_start };
+                }
+                let numerator = (reference_index - reference_start) * (query_end - query_start);
+                let denominator = reference_end - reference_start;
+                query_start
+                    + if round_up {
+                        numerator.div_ceil(denominator)
+                    } else {
+                        numerator / denominator
+                    }
+            };
+            for reference_index in *reference_start..=*reference_end {
+                let lowest = query_at(reference_index, false);
+                let highest = query_at((reference_index + 1).min(*reference_end), true);
+                lowest_query_indices[reference_index] =
+                    lowest_query_indices[reference_index].min(lowest.saturating_sub(band));
+                highest_query_indices[reference_index] =
+                    highest_query_indices[reference_index].max((highest + band).min(query_length));
+            }
+        }
+
+        Self {
+            lowest_query_indices,
+            highest_query_indices,
+            anchor_count,
+        }
+    }
+
+    /// Returns true if the primary alignment may pass through the given position.
+    pub fn contains(&self, reference_index: usize, query_index: usize) -> bool {
+        self.lowest_query_indices
+            .get(reference_index)
+            .zip(self.highest_query_indices.get(reference_index))
+            .is_some_and(|(&lowest, &highest)| (lowest..=highest).contains(&query_index))
+    }
+
+    /// The number of anchors the path runs through, after dropping non-colinear ones.
+    pub fn anchor_count(&self) -> usize {
+        self.anchor_count
+    }
+
+    /// The number of primary positions within the corridor.
+    pub fn area(&self) -> usize {
+        self.lowest_query_indices
+            .iter()
+            .zip(&self.highest_query_indices)
+            .map(|(lowest, highest)| highest + 1 - lowest)
+            .sum()
+    }
+}
+
+fn parse_column(column: &str, line_index: usize) -> Result<usize> {
+    column
+        .trim()
+        .parse()
+        .map_err(|error| guide_error(line_index, format!("cannot parse '{column}': {error}")))
+}
+
+fn guide_error(line_index: usize, message: String) -> Error {
+    Error::GuideParse {
+        line: line_index + 1,
+        message,
+    }
+}
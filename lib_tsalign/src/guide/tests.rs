@@ -0,0 +1,97 @@
+use super::{AlignmentGuide, GuideAnchor};
+
+#[test]
+fn read_tsv() {
+    let input = "# reference_start\tquery_start\tlength\n0\t0\t10\r\n\n12\t15\t5\n";
+    assert_eq!(
+        GuideAnchor::read_tsv(input.as_bytes()).unwrap(),
+        vec![
+            GuideAnchor {
+                reference_start: 0,
+                query_start: 0,
+                length: 10,
+            },
+            GuideAnchor {
+                reference_start: 12,
+                query_start: 15,
+                length: 5,
+            },
+        ]
+    );
+    assert!(GuideAnchor::read_tsv("1\t2\n".as_bytes()).is_err());
+    assert!(GuideAnchor::read_tsv("1\t2\tx\n".as_bytes()).is_err());
+}
+
+#[test]
+fn read_paf() {
+    let input =
+        "query\t30\t2\t28\t+\treference\t40\t5\t30\t20\t26\t60\ttp:A:P\tcg:Z:10M2I3=1X4D8M\n\
+        query\t30\t0\t30\t-\treference\t40\t0\t30\t30\t30\t60\tcg:Z:30M\n\
+        query\t30\t0\t30\t+\treference\t40\t0\t30\t30\t30\t60\n";
+    assert_eq!(
+        GuideAnchor::read_paf(input.as_bytes()).unwrap(),
+        vec![
+            GuideAnchor {
+                reference_start: 5,
+                query_start: 2,
+                length: 10,
+            },
+            GuideAnchor {
+                reference_start: 15,
+                query_start: 14,
+                length: 3,
+            },
+            GuideAnchor {
+                reference_start: 18,
+                query_start: 17,
+                length: 1,
+            },
+            GuideAnchor {
+                reference_start: 23,
+                query_start: 18,
+                length: 8,
+            },
+        ]
+    );
+    assert!(GuideAnchor::read_paf("query\t30\n".as_bytes()).is_err());
+}
+
+#[test]
+fn corridor() {
+    let anchor = |reference_start, query_start, length| GuideAnchor {
+        reference_start,
+        query_start,
+        length,
+    };
+    let guide = AlignmentGuide::new(
+        [
+            anchor(4, 8, 4),
+            // Not colinear with the first anchor.
+            anchor(6, 2, 2),
+            // Reaches past the end of the query.
+            anchor(12, 14, 10),
+        ],
+        16,
+        16,
+        0,
+    );
+    assert_eq!(guide.anchor_count(), 1);
+
+    // From the origin to the anchor, the path advances two query characters per reference character.
+    assert!(guide.contains(0, 0));
+    assert!(guide.contains(0, 2));
+    assert!(!guide.contains(0, 3));
+    assert!(guide.contains(2, 5));
+    // Along the anchor.
+    assert!(guide.contains(5, 9));
+    assert!(!guide.contains(5, 8));
+    // The end of both sequences is always reachable.
+    assert!(guide.contains(16, 16));
+    assert!(!guide.contains(17, 16));
+
+    let wide = AlignmentGuide::new([anchor(4, 8, 4)], 16, 16, 2);
+    assert!(wide.contains(5, 7));
+    assert!(wide.contains(5, 12));
+    assert!(!wide.contains(5, 13));
+    assert!(wide.area() > guide.area());
+}
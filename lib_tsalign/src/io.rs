@@ -1,11 +1,11 @@
 use nom::{
-    bytes::complete::take_till1,
+    bytes::complete::{take_till1, take_while1},
     character::complete::{char, satisfy},
     multi::many0,
     IResult,
 };
 
-use crate::error::Error;
+use crate::error::{Error, Result};
 
 pub fn parse_title(input: &str) -> IResult<&str, &str> {
     let input = skip_any_whitespace(input)?;
@@ -15,6 +15,12 @@ pub fn parse_title(input: &str) -> IResult<&str, &str> {
     Ok((input, result.trim()))
 }
 
+/// Parses an identifier made of alphanumeric characters and underscores, after skipping any whitespace.
+pub fn parse_identifier(input: &str) -> IResult<&str, &str> {
+    let input = skip_any_whitespace(input)?;
+    take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)
+}
+
 pub fn parse_whitespace(input: &str) -> IResult<&str, ()> {
     skip_whitespace(input).map(|input| (input, ()))
 }
@@ -47,12 +53,136 @@ pub fn is_any_line_break(c: char) -> bool {
     c == '\n' || c == '\r'
 }
 
-pub fn translate_nom_error(error: nom::Err<nom::error::Error<&str>>) -> Error {
-    match error {
-        nom::Err::Incomplete(needed) => Error::ParserIncomplete(needed),
-        nom::Err::Error(error) | nom::Err::Failure(error) => Error::Parser {
-            input: error.input.to_string(),
-            kind: error.code,
-        },
+/// Runs nom parsers one after another, and translates their errors into diagnostics
+/// that give the line and column of the error and what was expected there.
+pub struct DiagnosticParser<'input> {
+    full_input: &'input str,
+    input: &'input str,
+}
+
+impl<'input> DiagnosticParser<'input> {
+    pub fn new(input: &'input str) -> Self {
+        Self {
+            full_input: input,
+            input,
+        }
+    }
+
+    /// Runs `parser` on the remaining input.
+    ///
+    /// The `expected` hint describes what `parser` parses, and is used if it fails.
+    pub fn parse<Output>(
+        &mut self,
+        expected: &str,
+        mut parser: impl FnMut(&'input str) -> IResult<&'input str, Output>,
+    ) -> Result<Output> {
+        match parser(self.input) {
+            Ok((input, output)) => {
+                self.input = input;
+                Ok(output)
+            }
+            Err(error) => Err(self.diagnose(error, expected)),
+        }
+    }
+
+    /// Like [`parse`](Self::parse), but returns `None` without consuming input if `parser` fails recoverably.
+    pub fn parse_opt<Output>(
+        &mut self,
+        expected: &str,
+        mut parser: impl FnMut(&'input str) -> IResult<&'input str, Output>,
+    ) -> Result<Option<Output>> {
+        match parser(self.input) {
+            Ok((input, output)) => {
+                self.input = input;
+                Ok(Some(output))
+            }
+            Err(nom::Err::Error(_)) => Ok(None),
+            Err(error) => Err(self.diagnose(error, expected)),
+        }
+    }
+
+    /// Like [`parse`](Self::parse), but stores the output in `target`, and fails if `target` was already set.
+    ///
+    /// This is used for the parts of lenient inputs that may come in any order, but only once.
+    pub fn parse_once<Output>(
+        &mut self,
+        target: &mut Option<Output>,
+        name: &str,
+        parser: impl FnMut(&'input str) -> IResult<&'input str, Output>,
+    ) -> Result<()> {
+        self.skip_any_whitespace();
+        if target.is_some() {
+            return Err(self.error(&format!("anything but a second '{name}'")));
+        }
+        *target = Some(self.parse(&format!("a well-formed '{name}'"), parser)?);
+        Ok(())
+    }
+
+    /// Skips whitespace and runs `parser` without consuming its input.
+    pub fn peek<Output>(
+        &mut self,
+        mut parser: impl FnMut(&'input str) -> IResult<&'input str, Output>,
+    ) -> Option<Output> {
+        self.skip_any_whitespace();
+        parser(self.input).ok().map(|(_, output)| output)
+    }
+
+    /// Skips whitespace and returns true if no input remains.
+    pub fn is_at_end(&mut self) -> bool {
+        self.skip_any_whitespace();
+        self.input.is_empty()
+    }
+
+    /// Returns an error at the current position, stating that `expected` was expected.
+    pub fn error(&self, expected: &str) -> Error {
+        self.error_at(self.input, expected)
+    }
+
+    fn skip_any_whitespace(&mut self) {
+        self.input = self.input.trim_start_matches(is_any_whitespace);
+    }
+
+    fn diagnose(&self, error: nom::Err<nom::error::Error<&str>>, expected: &str) -> Error {
+        match error {
+            nom::Err::Incomplete(needed) => Error::ParserIncomplete(needed),
+            nom::Err::Error(error) | nom::Err::Failure(error) => {
+                self.error_at(error.input, expected)
+            }
+        }
+    }
+
+    /// Returns an error at the start of `remaining`, which must be a suffix of the full input.
+    fn error_at(&self, remaining: &str, expected: &str) -> Error {
+        const MAX_FOUND_LENGTH: usize = 40;
+
+        let offset = self.full_input.len() - remaining.len();
+        let preceding = &self.full_input[..offset];
+        let line = preceding.matches('\n').count() + 1;
+        let column = preceding
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+
+        let found_line = remaining.lines().next().unwrap_or_default().trim_end();
+        let found = if remaining.is_empty() {
+            "the end of the input".to_string()
+        } else if found_line.is_empty() {
+            "the end of the line".to_string()
+        } else if found_line.chars().count() > MAX_FOUND_LENGTH {
+            let found_line: String = found_line.chars().take(MAX_FOUND_LENGTH).collect();
+            format!("'{found_line}...'")
+        } else {
+            format!("'{found_line}'")
+        };
+
+        Error::ParserDiagnostic {
+            line,
+            column,
+            expected: expected.to_string(),
+            found,
+        }
     }
 }
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use deterministic_default_hasher::DeterministicDefaultHasher;
+use seed_chain::seed::contains_ambiguous_character;
+
+#[cfg(test)]
+mod tests;
+
+/// The k-mer frequency spectrum of a sequence.
+///
+/// The spectrum maps each number of occurrences to the number of distinct k-mers that occur that often.
+/// Like chaining anchors, k-mers containing characters other than `A`, `C`, `G`, `T` and `U` are skipped.
+/// Only the forward strand is counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KmerSpectrum {
+    k: usize,
+    /// Pairs of a number of occurrences and the number of distinct k-mers occurring that often.
+    ///
+    /// Sorted ascending by the number of occurrences, and without pairs of zero k-mers.
+    spectrum: Vec<(usize, usize)>,
+}
+
+impl KmerSpectrum {
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequence: &SubsequenceType,
+        k: usize,
+    ) -> Self {
+        assert!(k > 0, "The k-mer size must be positive, but is zero");
+
+        let sequence = sequence.as_string();
+        let mut occurrences: HashMap<_, usize, _> =
+            HashMap::with_hasher(DeterministicDefaultHasher);
+        for kmer in sequence.as_bytes().windows(k) {
+            // The windows are ASCII, since genome strings are.
+            let kmer = std::str::from_utf8(kmer).unwrap();
+            if !contains_ambiguous_character(kmer) {
+                *occurrences.entry(kmer).or_default() += 1;
+            }
+        }
+
+        let mut spectrum: HashMap<_, usize, _> = HashMap::with_hasher(DeterministicDefaultHasher);
+        for count in occurrences.into_values() {
+            *spectrum.entry(count).or_default() += 1;
+        }
+        let mut spectrum: Vec<_> = spectrum.into_iter().collect();
+        spectrum.sort_unstable();
+
+        Self { k, spectrum }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Pairs of a number of occurrences and the number of distinct k-mers occurring that often, ascending by occurrences.
+    pub fn spectrum(&self) -> &[(usize, usize)] {
+        &self.spectrum
+    }
+
+    /// The number of distinct k-mers of the sequence.
+    pub fn distinct_kmers(&self) -> usize {
+        self.spectrum.iter().map(|(_, kmers)| kmers).sum()
+    }
+
+    /// The number of k-mer occurrences in the sequence.
+    pub fn total_kmers(&self) -> usize {
+        self.spectrum
+            .iter()
+            .map(|(occurrences, kmers)| occurrences * kmers)
+            .sum()
+    }
+
+    /// The number of occurrences of the most frequent k-mer, or zero if the sequence has no k-mers.
+    pub fn max_occurrences(&self) -> usize {
+        self.spectrum
+            .last()
+            .map_or(0, |(occurrences, _)| *occurrences)
+    }
+
+    /// The fraction of k-mer occurrences whose k-mer occurs more than once.
+    ///
+    /// This is zero for sequences without repeats of length `k`, and approaches one for sequences consisting of few repeated k-mers.
+    /// If the sequence has no k-mers, then the repetitiveness is zero.
+    pub fn repetitiveness(&self) -> f64 {
+        let total_kmers = self.total_kmers();
+        if total_kmers == 0 {
+            0.0
+        } else {
+            let unique_kmers = self
+                .spectrum
+                .first()
+                .filter(|(occurrences, _)| *occurrences == 1)
+                .map_or(0, |(_, kmers)| *kmers);
+            (total_kmers - unique_kmers) as f64 / total_kmers as f64
+        }
+    }
+}
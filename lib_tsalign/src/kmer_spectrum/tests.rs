@@ -0,0 +1,44 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use super::KmerSpectrum;
+
+fn spectrum(sequence: &str, k: usize) -> KmerSpectrum {
+    let sequence = VectorGenome::<DnaAlphabetOrN>::from_iter_u8(sequence.bytes()).unwrap();
+    KmerSpectrum::new(sequence.as_genome_subsequence(), k)
+}
+
+#[test]
+fn unique_kmers() {
+    let spectrum = spectrum("ACGTTGCAAC", 4);
+
+    assert_eq!(spectrum.spectrum(), &[(1, 7)]);
+    assert_eq!(spectrum.distinct_kmers(), 7);
+    assert_eq!(spectrum.total_kmers(), 7);
+    assert_eq!(spectrum.max_occurrences(), 1);
+    assert_eq!(spectrum.repetitiveness(), 0.0);
+}
+
+#[test]
+fn repeated_kmers() {
+    // `ACG` occurs three times, `CGA` and `GAC` twice each.
+    let spectrum = spectrum("ACGACGACGT", 3);
+
+    assert_eq!(spectrum.spectrum(), &[(1, 1), (2, 2), (3, 1)]);
+    assert_eq!(spectrum.distinct_kmers(), 4);
+    assert_eq!(spectrum.total_kmers(), 8);
+    assert_eq!(spectrum.max_occurrences(), 3);
+    assert_eq!(spectrum.repetitiveness(), 7.0 / 8.0);
+}
+
+#[test]
+fn ambiguous_and_short_sequences() {
+    assert_eq!(spectrum("ACNGTA", 2).spectrum(), &[(1, 3)]);
+
+    let short = spectrum("AC", 3);
+    assert_eq!(short.spectrum(), &[]);
+    assert_eq!(short.max_occurrences(), 0);
+    assert_eq!(short.repetitiveness(), 0.0);
+}
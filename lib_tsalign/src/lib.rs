@@ -2,8 +2,25 @@
 
 pub mod a_star_aligner;
 pub mod alignment_configuration;
+pub mod alignment_diff;
+pub mod alignment_geometry;
 pub mod alignment_matrix;
+pub mod alignment_method;
+pub mod character_mapping;
 pub mod config;
 pub mod costs;
 pub mod error;
+pub mod guide;
 mod io;
+pub mod kmer_spectrum;
+pub mod longest_common_extension;
+pub mod maf;
+pub mod preprocessing;
+pub mod provenance;
+pub mod sketch;
+pub mod soft_mask;
+pub mod star_msa;
+pub mod summary;
+#[cfg(test)]
+mod test_fixtures;
+pub mod ts_normalisation;
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests;
+
+/// Answers longest common extension queries on a text in constant time.
+///
+/// The longest common extension of two positions is the length of the longest common prefix of the suffixes starting at them.
+/// It is computed as a range minimum over the longest common prefix array of the suffix array of the text.
+/// Construction takes `O(n log² n)` time and `O(n log n)` space.
+///
+/// To compute extensions between multiple sequences, concatenate them with separators that occur nowhere else in the text.
+#[derive(Debug, Clone)]
+pub struct LongestCommonExtension {
+    /// The rank of each suffix in the suffix array.
+    rank: Vec<usize>,
+    /// `range_minima[level][i]` is the minimum of the longest common prefix array in `i..i + 2^level`.
+    range_minima: Vec<Vec<u32>>,
+}
+
+impl LongestCommonExtension {
+    pub fn new(text: &[usize]) -> Self {
+        assert!(
+            text.len() < u32::MAX as usize,
+            "The text is too long for longest common extension queries"
+        );
+
+        let suffix_array = suffix_array(text);
+        let mut rank = vec![0; text.len()];
+        for (suffix_rank, &suffix) in suffix_array.iter().enumerate() {
+            rank[suffix] = suffix_rank;
+        }
+
+        // Kasai's algorithm, where `longest_common_prefixes[r]` is the longest common prefix of the suffixes of rank `r - 1` and `r`.
+        let mut longest_common_prefixes = vec![0; text.len()];
+        let mut length = 0;
+        for suffix in 0..text.len() {
+            if rank[suffix] == 0 {
+                length = 0;
+                continue;
+            }
+
+            let previous_suffix = suffix_array[rank[suffix] - 1];
+            while suffix + length < text.len()
+                && previous_suffix + length < text.len()
+                && text[suffix + length] == text[previous_suffix + length]
+            {
+                length += 1;
+            }
+            longest_common_prefixes[rank[suffix]] = length as u32;
+            length = length.saturating_sub(1);
+        }
+
+        let mut range_minima = vec![longest_common_prefixes];
+        let mut width = 1;
+        while 2 * width <= text.len() {
+            let previous = range_minima.last().unwrap();
+            let level = (0..previous.len() - width)
+                .map(|index| previous[index].min(previous[index + width]))
+                .collect();
+            range_minima.push(level);
+            width *= 2;
+        }
+
+        Self { rank, range_minima }
+    }
+
+    /// Returns the length of the text.
+    pub fn len(&self) -> usize {
+        self.rank.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rank.is_empty()
+    }
+
+    /// Returns the length of the longest common prefix of the suffixes starting at `first` and `second`.
+    pub fn extension(&self, first: usize, second: usize) -> usize {
+        if first == second {
+            return self.len() - first;
+        }
+
+        let first_rank = self.rank[first];
+        let second_rank = self.rank[second];
+        let (from, to) = if first_rank < second_rank {
+            (first_rank + 1, second_rank + 1)
+        } else {
+            (second_rank + 1, first_rank + 1)
+        };
+
+        let level = (to - from).ilog2() as usize;
+        let range_minima = &self.range_minima[level];
+        range_minima[from].min(range_minima[to - (1 << level)]) as usize
+    }
+}
+
+/// Sort the suffixes of the text by prefix doubling.
+fn suffix_array(text: &[usize]) -> Vec<usize> {
+    let mut suffix_array: Vec<_> = (0..text.len()).collect();
+    let mut rank = text.to_vec();
+    let mut next_rank = vec![0; text.len()];
+    let mut width = 1;
+
+    while text.len() > 1 {
+        let key = |suffix: usize| (rank[suffix], rank.get(suffix + width).map(|rank| rank + 1));
+        suffix_array.sort_unstable_by_key(|&suffix| key(suffix));
+
+        next_rank[suffix_array[0]] = 0;
+        for index in 1..suffix_array.len() {
+            next_rank[suffix_array[index]] = next_rank[suffix_array[index - 1]]
+                + usize::from(key(suffix_array[index - 1]) != key(suffix_array[index]));
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+
+        if rank[suffix_array[suffix_array.len() - 1]] == text.len() - 1 || width >= text.len() {
+            break;
+        }
+        width *= 2;
+    }
+
+    suffix_array
+}
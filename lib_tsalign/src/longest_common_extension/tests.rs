@@ -0,0 +1,38 @@
+use super::LongestCommonExtension;
+
+fn naive_extension(text: &[usize], first: usize, second: usize) -> usize {
+    text[first..]
+        .iter()
+        .zip(&text[second..])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[test]
+fn matches_naive_extension() {
+    for text in [
+        &b"mississippi"[..],
+        b"aaaaaaaa",
+        b"abababab$bababa",
+        b"ACGTTGCAACGGATCCATGA#TCATGGATCCGTTGCAACGT",
+        b"a",
+    ] {
+        let text: Vec<_> = text.iter().copied().map(usize::from).collect();
+        let longest_common_extension = LongestCommonExtension::new(&text);
+
+        for first in 0..text.len() {
+            for second in 0..text.len() {
+                assert_eq!(
+                    longest_common_extension.extension(first, second),
+                    naive_extension(&text, first, second),
+                    "text: {text:?}, first: {first}, second: {second}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_text() {
+    assert!(LongestCommonExtension::new(&[]).is_empty());
+}
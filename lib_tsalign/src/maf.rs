@@ -0,0 +1,506 @@
+use std::{
+    fmt::{Display, Formatter},
+    io::Write,
+};
+
+use compact_genome::interface::{
+    alphabet::{Alphabet, AlphabetCharacter},
+    sequence::GenomeSequence,
+};
+
+use crate::{
+    a_star_aligner::template_switch_distance::{
+        AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+    },
+    character_mapping::CharacterMapping,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The strand of a sequence in a [`MafRow`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MafStrand {
+    Forward,
+    Reverse,
+}
+
+/// What a [`MafBlock`] was split from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MafBlockKind {
+    /// Columns of the primary alignment between the reference and the query.
+    Primary,
+    /// The alignment of the primary of a template switch to the reverse complement of its secondary,
+    /// or to the secondary itself if the template switch copies the forward strand.
+    TemplateSwitch,
+    /// An inversion, where the query is aligned to the reverse complement of the reference.
+    Inversion,
+}
+
+/// A sequence line of a MAF block.
+///
+/// Like in MAF, the start of a row on the reverse strand is counted from the end of the source sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MafRow {
+    pub source: String,
+    pub start: usize,
+    pub size: usize,
+    pub strand: MafStrand,
+    pub source_size: usize,
+    pub text: String,
+}
+
+/// A colinear segment of an alignment, written as an alignment block of the multiple alignment format (MAF).
+///
+/// Template switches, inversions and duplications cannot be expressed within a MAF block,
+/// so alignments are split into a block for each of them and for each primary segment between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MafBlock {
+    pub kind: MafBlockKind,
+    /// For primary blocks, the reference row and the query row.
+    /// For template switch blocks, the primary row and the secondary row.
+    /// For inversion blocks, the reference row on the reverse strand and the query row.
+    pub rows: [MafRow; 2],
+}
+
+struct MafBlockBuilder {
+    kind: MafBlockKind,
+    /// The source, strand and forward start and end of each row.
+    rows: [(String, MafStrand, usize, usize, usize); 2],
+    texts: [String; 2],
+}
+
+impl MafBlock {
+    /// Splits an alignment of `reference` and `query` into MAF blocks.
+    ///
+    /// Empty blocks are omitted.
+    pub fn from_alignment<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        alignment: &[(usize, AlignmentType)],
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        reference_name: &str,
+        query_name: &str,
+    ) -> Vec<Self> {
+        Self::from_alignment_with_character_mappings(
+            alignment,
+            reference,
+            query,
+            reference_name,
+            query_name,
+            [&CharacterMapping::default(); 2],
+        )
+    }
+
+    /// Like [`from_alignment`](Self::from_alignment), but the characters of the reference and the query are written
+    /// as given by the respective character mapping.
+    pub fn from_alignment_with_character_mappings<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        alignment: &[(usize, AlignmentType)],
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        reference_name: &str,
+        query_name: &str,
+        [reference_mapping, query_mapping]: [&CharacterMapping; 2],
+    ) -> Vec<Self> {
+        let character = |is_reference: bool, index: usize, complement: bool| {
+            let (sequence, mapping) = if is_reference {
+                (reference, reference_mapping)
+            } else {
+                (query, query_mapping)
+            };
+            let character = &sequence[index];
+            mapping.map(
+                index,
+                if complement {
+                    character.complement().into()
+                } else {
+                    character.clone().into()
+                },
+            )
+        };
+        let sequence = |primary_is_reference: bool| {
+            if primary_is_reference {
+                (reference, reference_name)
+            } else {
+                (query, query_name)
+            }
+        };
+
+        let mut blocks = Vec::new();
+        let mut reference_index = 0;
+        let mut query_index = 0;
+        let mut block = MafBlockBuilder::primary(
+            reference_name,
+            reference,
+            query_name,
+            query,
+            reference_index,
+            query_index,
+        );
+        // The sequences, the strand, the primary and secondary index and the anti-primary start of the current template switch.
+        let mut template_switch = None;
+        // The reference end of the current inversion.
+        let mut inversion_end = 0;
+
+        for &(count, alignment_type) in alignment {
+            match alignment_type {
+                AlignmentType::PrimaryMatch
+                | AlignmentType::PrimarySubstitution
+                | AlignmentType::PrimaryFlankMatch
+                | AlignmentType::PrimaryFlankSubstitution
+                | AlignmentType::PrimaryMatchRun { .. } => {
+                    let count = match alignment_type {
+                        AlignmentType::PrimaryMatchRun { length } => count * length,
+                        _ => count,
+                    };
+                    for _ in 0..count {
+                        block.push(
+                            Some(character(true, reference_index, false)),
+                            Some(character(false, query_index, false)),
+                        );
+                        reference_index += 1;
+                        query_index += 1;
+                    }
+                }
+                AlignmentType::PrimaryInsertion | AlignmentType::PrimaryFlankInsertion => {
+                    for _ in 0..count {
+                        block.push(None, Some(character(false, query_index, false)));
+                        query_index += 1;
+                    }
+                }
+                AlignmentType::PrimaryDeletion | AlignmentType::PrimaryFlankDeletion => {
+                    for _ in 0..count {
+                        block.push(Some(character(true, reference_index, false)), None);
+                        reference_index += 1;
+                    }
+                }
+                AlignmentType::TemplateSwitchEntrance {
+                    primary,
+                    secondary,
+                    strand,
+                    first_offset,
+                } => {
+                    blocks.extend(block.build());
+
+                    let primary_is_reference = primary == TemplateSwitchPrimary::Reference;
+                    let (primary_index, anti_primary_index) = if primary_is_reference {
+                        (reference_index, query_index)
+                    } else {
+                        (query_index, reference_index)
+                    };
+                    let secondary_is_reference = secondary == TemplateSwitchSecondary::Reference;
+                    let secondary_index = (if secondary_is_reference {
+                        reference_index
+                    } else {
+                        query_index
+                    } as isize
+                        + first_offset) as usize;
+
+                    let (primary_sequence, primary_name) = sequence(primary_is_reference);
+                    let (secondary_sequence, secondary_name) = sequence(secondary_is_reference);
+                    block = MafBlockBuilder {
+                        kind: MafBlockKind::TemplateSwitch,
+                        rows: [
+                            (
+                                primary_name.to_string(),
+                                MafStrand::Forward,
+                                primary_index,
+                                primary_index,
+                                primary_sequence.len(),
+                            ),
+                            (
+                                secondary_name.to_string(),
+                                match strand {
+                                    TemplateSwitchStrand::ReverseComplement => MafStrand::Reverse,
+                                    TemplateSwitchStrand::Forward => MafStrand::Forward,
+                                },
+                                secondary_index,
+                                secondary_index,
+                                secondary_sequence.len(),
+                            ),
+                        ],
+                        texts: Default::default(),
+                    };
+                    template_switch = Some((
+                        primary_is_reference,
+                        secondary_is_reference,
+                        strand,
+                        primary_index,
+                        secondary_index,
+                        anti_primary_index,
+                    ));
+                }
+                AlignmentType::SecondaryMatch
+                | AlignmentType::SecondarySubstitution
+                | AlignmentType::SecondaryMatchRun { .. }
+                | AlignmentType::SecondaryInsertion
+                | AlignmentType::SecondaryDeletion => {
+                    let (
+                        primary_is_reference,
+                        secondary_is_reference,
+                        strand,
+                        primary_index,
+                        secondary_index,
+                        _,
+                    ) = template_switch
+                        .as_mut()
+                        .expect("secondary alignment outside of a template switch");
+                    let (count, advance_primary, advance_secondary) = match alignment_type {
+                        AlignmentType::SecondaryMatchRun { length } => (count * length, true, true),
+                        AlignmentType::SecondaryInsertion => (count, true, false),
+                        AlignmentType::SecondaryDeletion => (count, false, true),
+                        _ => (count, true, true),
+                    };
+                    for _ in 0..count {
+                        let primary_character = advance_primary.then(|| {
+                            *primary_index += 1;
+                            character(*primary_is_reference, *primary_index - 1, false)
+                        });
+                        let secondary_character = advance_secondary.then(|| match strand {
+                            TemplateSwitchStrand::ReverseComplement => {
+                                *secondary_index -= 1;
+                                character(*secondary_is_reference, *secondary_index, true)
+                            }
+                            TemplateSwitchStrand::Forward => {
+                                *secondary_index += 1;
+                                character(*secondary_is_reference, *secondary_index - 1, false)
+                            }
+                        });
+                        block.push(primary_character, secondary_character);
+                    }
+                }
+                AlignmentType::TemplateSwitchExit { length_difference } => {
+                    let (primary_is_reference, _, _, primary_end, _, anti_primary_start) =
+                        template_switch
+                            .take()
+                            .expect("template switch exit without entrance");
+                    let primary_length = block.rows[0].3 - block.rows[0].2;
+                    blocks.extend(block.build());
+
+                    let anti_primary_end = (anti_primary_start as isize
+                        + primary_length as isize
+                        + length_difference) as usize;
+                    (reference_index, query_index) = if primary_is_reference {
+                        (primary_end, anti_primary_end)
+                    } else {
+                        (anti_primary_end, primary_end)
+                    };
+                    block = MafBlockBuilder::primary(
+                        reference_name,
+                        reference,
+                        query_name,
+                        query,
+                        reference_index,
+                        query_index,
+                    );
+                }
+                AlignmentType::PrimaryDuplication { length } => {
+                    blocks.extend(block.build());
+                    reference_index -= count * length;
+                    block = MafBlockBuilder::primary(
+                        reference_name,
+                        reference,
+                        query_name,
+                        query,
+                        reference_index,
+                        query_index,
+                    );
+                }
+                AlignmentType::InversionEntrance { length } => {
+                    blocks.extend(block.build());
+                    inversion_end = reference_index + length;
+                    block = MafBlockBuilder {
+                        kind: MafBlockKind::Inversion,
+                        rows: [
+                            (
+                                reference_name.to_string(),
+                                MafStrand::Reverse,
+                                inversion_end,
+                                inversion_end,
+                                reference.len(),
+                            ),
+                            (
+                                query_name.to_string(),
+                                MafStrand::Forward,
+                                query_index,
+                                query_index,
+                                query.len(),
+                            ),
+                        ],
+                        texts: Default::default(),
+                    };
+                }
+                AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                    for _ in 0..count {
+                        let inverted_index =
+                            inversion_end - 1 - (block.rows[0].3 - block.rows[0].2);
+                        block.push(
+                            Some(character(true, inverted_index, true)),
+                            Some(character(false, query_index, false)),
+                        );
+                        reference_index += 1;
+                        query_index += 1;
+                    }
+                }
+                AlignmentType::InversionExit => {
+                    blocks.extend(block.build());
+                    block = MafBlockBuilder::primary(
+                        reference_name,
+                        reference,
+                        query_name,
+                        query,
+                        reference_index,
+                        query_index,
+                    );
+                }
+                AlignmentType::Root
+                | AlignmentType::SecondaryRoot
+                | AlignmentType::PrimaryReentry
+                | AlignmentType::PrimaryShortcut { .. } => {}
+            }
+        }
+        blocks.extend(block.build());
+
+        blocks
+    }
+}
+
+impl MafBlockBuilder {
+    fn primary<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference_name: &str,
+        reference: &SubsequenceType,
+        query_name: &str,
+        query: &SubsequenceType,
+        reference_index: usize,
+        query_index: usize,
+    ) -> Self {
+        Self {
+            kind: MafBlockKind::Primary,
+            rows: [
+                (
+                    reference_name.to_string(),
+                    MafStrand::Forward,
+                    reference_index,
+                    reference_index,
+                    reference.len(),
+                ),
+                (
+                    query_name.to_string(),
+                    MafStrand::Forward,
+                    query_index,
+                    query_index,
+                    query.len(),
+                ),
+            ],
+            texts: Default::default(),
+        }
+    }
+
+    /// Appends a column, where `None` is a gap.
+    ///
+    /// Rows on the forward strand extend their end, and rows on the reverse strand extend their start.
+    fn push(&mut self, first: Option<char>, second: Option<char>) {
+        for ((row, text), character) in self
+            .rows
+            .iter_mut()
+            .zip(&mut self.texts)
+            .zip([first, second])
+        {
+            if let Some(character) = character {
+                match row.1 {
+                    MafStrand::Forward => row.3 += 1,
+                    MafStrand::Reverse => row.2 -= 1,
+                }
+                text.push(character);
+            } else {
+                text.push('-');
+            }
+        }
+    }
+
+    fn build(self) -> Option<MafBlock> {
+        if self.texts[0].is_empty() {
+            return None;
+        }
+
+        let [first, second] = self.rows;
+        let [first_text, second_text] = self.texts;
+        let row = |(source, strand, start, end, source_size): (
+            String,
+            MafStrand,
+            usize,
+            usize,
+            usize,
+        ),
+                   text| MafRow {
+            source,
+            start: match strand {
+                MafStrand::Forward => start,
+                MafStrand::Reverse => source_size - end,
+            },
+            size: end - start,
+            strand,
+            source_size,
+            text,
+        };
+        Some(MafBlock {
+            kind: self.kind,
+            rows: [row(first, first_text), row(second, second_text)],
+        })
+    }
+}
+
+/// Writes a MAF file with the given blocks.
+pub fn write_maf<'block>(
+    mut writer: impl Write,
+    blocks: impl IntoIterator<Item = &'block MafBlock>,
+) -> std::io::Result<()> {
+    writeln!(writer, "##maf version=1")?;
+    for block in blocks {
+        writeln!(writer)?;
+        write!(writer, "{block}")?;
+    }
+    Ok(())
+}
+
+impl Display for MafBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            MafBlockKind::Primary => "primary",
+            MafBlockKind::TemplateSwitch => "template_switch",
+            MafBlockKind::Inversion => "inversion",
+        };
+        writeln!(f, "a kind={kind}")?;
+        for row in &self.rows {
+            writeln!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for MafRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "s {} {} {} {} {} {}",
+            self.source, self.start, self.size, self.strand, self.source_size, self.text
+        )
+    }
+}
+
+impl Display for MafStrand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Forward => write!(f, "+"),
+            Self::Reverse => write!(f, "-"),
+        }
+    }
+}
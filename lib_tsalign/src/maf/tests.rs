@@ -0,0 +1,211 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::template_switch_distance::{
+        AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+    },
+    character_mapping::{CharacterMapping, NCharacterPolicy},
+    preprocessing::PreprocessingSettings,
+};
+
+use super::{write_maf, MafBlock, MafBlockKind, MafRow, MafStrand};
+
+fn blocks(reference: &[u8], query: &[u8], alignment: &[(usize, AlignmentType)]) -> Vec<MafBlock> {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query).unwrap();
+    MafBlock::from_alignment(
+        alignment,
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        "reference",
+        "query",
+    )
+}
+
+fn row(source: &str, start: usize, strand: MafStrand, source_size: usize, text: &str) -> MafRow {
+    MafRow {
+        source: source.to_string(),
+        start,
+        size: text.chars().filter(|&character| character != '-').count(),
+        strand,
+        source_size,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn primary_block() {
+    let blocks = blocks(
+        b"ACGTAC",
+        b"AGTTAC",
+        &[
+            (1, AlignmentType::Root),
+            (1, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::PrimaryDeletion),
+            (2, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::PrimaryInsertion),
+            (2, AlignmentType::PrimaryMatch),
+        ],
+    );
+    assert_eq!(
+        blocks,
+        [MafBlock {
+            kind: MafBlockKind::Primary,
+            rows: [
+                row("reference", 0, MafStrand::Forward, 6, "ACGT-AC"),
+                row("query", 0, MafStrand::Forward, 6, "A-GTTAC"),
+            ],
+        }]
+    );
+
+    let mut output = Vec::new();
+    write_maf(&mut output, &blocks).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "##maf version=1\n\na kind=primary\ns reference 0 6 + 6 ACGT-AC\ns query 0 6 + 6 A-GTTAC\n"
+    );
+}
+
+#[test]
+fn template_switch_is_split_into_reverse_strand_block() {
+    let blocks = blocks(
+        b"AACCGGTT",
+        b"AACCGGTT",
+        &[
+            (2, AlignmentType::PrimaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Query,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::ReverseComplement,
+                    first_offset: 4,
+                },
+            ),
+            (2, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: 0,
+                },
+            ),
+            (4, AlignmentType::PrimaryMatch),
+        ],
+    );
+    assert_eq!(
+        blocks,
+        [
+            MafBlock {
+                kind: MafBlockKind::Primary,
+                rows: [
+                    row("reference", 0, MafStrand::Forward, 8, "AA"),
+                    row("query", 0, MafStrand::Forward, 8, "AA"),
+                ],
+            },
+            MafBlock {
+                kind: MafBlockKind::TemplateSwitch,
+                rows: [
+                    row("query", 2, MafStrand::Forward, 8, "CC"),
+                    // The secondary characters 4..6 counted from the end of the reference.
+                    row("reference", 2, MafStrand::Reverse, 8, "CC"),
+                ],
+            },
+            MafBlock {
+                kind: MafBlockKind::Primary,
+                rows: [
+                    row("reference", 4, MafStrand::Forward, 8, "GGTT"),
+                    row("query", 4, MafStrand::Forward, 8, "GGTT"),
+                ],
+            },
+        ]
+    );
+}
+
+#[test]
+fn jump_events_are_split() {
+    let inversion_blocks = blocks(
+        b"AACGTGGG",
+        b"AAACGGGG",
+        &[
+            (2, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::InversionEntrance { length: 3 }),
+            (3, AlignmentType::InversionMatch),
+            (1, AlignmentType::InversionExit),
+            (3, AlignmentType::PrimaryMatch),
+        ],
+    );
+    assert_eq!(inversion_blocks.len(), 3);
+    assert_eq!(
+        inversion_blocks[1],
+        MafBlock {
+            kind: MafBlockKind::Inversion,
+            rows: [
+                row("reference", 3, MafStrand::Reverse, 8, "ACG"),
+                row("query", 2, MafStrand::Forward, 8, "ACG"),
+            ],
+        }
+    );
+    assert_eq!(
+        inversion_blocks[2].rows[0],
+        row("reference", 5, MafStrand::Forward, 8, "GGG")
+    );
+
+    let duplication_blocks = blocks(
+        b"ACGT",
+        b"ACGCGT",
+        &[
+            (3, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::PrimaryDuplication { length: 2 }),
+            (3, AlignmentType::PrimaryMatch),
+        ],
+    );
+    assert_eq!(
+        duplication_blocks
+            .iter()
+            .map(|block| block.rows.clone())
+            .collect::<Vec<_>>(),
+        [
+            [
+                row("reference", 0, MafStrand::Forward, 4, "ACG"),
+                row("query", 0, MafStrand::Forward, 6, "ACG"),
+            ],
+            [
+                row("reference", 1, MafStrand::Forward, 4, "CGT"),
+                row("query", 3, MafStrand::Forward, 6, "CGT"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn characters_are_mapped() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACNGT").unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACNGT").unwrap();
+    let reference_mapping = CharacterMapping::new(NCharacterPolicy::Replace('x'));
+    let query_mapping = CharacterMapping::default().mark_skipped_characters(
+        PreprocessingSettings {
+            max_n_run: Some(1),
+            ..Default::default()
+        }
+        .preprocess(b"ACNNGT", None)
+        .coordinate_map,
+    );
+    let blocks = MafBlock::from_alignment_with_character_mappings(
+        &[(5, AlignmentType::PrimaryMatch)],
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        "reference",
+        "query",
+        [&reference_mapping, &query_mapping],
+    );
+    assert_eq!(
+        blocks[0].rows,
+        [
+            row("reference", 0, MafStrand::Forward, 5, "ACXGT"),
+            row("query", 0, MafStrand::Forward, 5, "ACNgT"),
+        ]
+    );
+}
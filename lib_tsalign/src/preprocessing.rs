@@ -0,0 +1,251 @@
+//! Cleaning up input sequences before aligning them, with maps from the cleaned back to the original coordinates.
+
+use std::ops::Range;
+
+#[cfg(test)]
+mod tests;
+
+/// Which preprocessing steps are applied to a sequence, in the order of the fields.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PreprocessingSettings {
+    /// Characters at both ends with a Phred quality below this are trimmed, if qualities are given.
+    pub min_quality: Option<u8>,
+    /// The ASCII value that encodes a Phred quality of zero.
+    pub quality_offset: u8,
+    /// Adapters clipped from the end of the sequence, together with everything after them.
+    ///
+    /// Adapters are compared ignoring case.
+    pub adapters: Vec<Vec<u8>>,
+    /// The minimum number of characters of an adapter that need to match at the very end of the sequence for it to be clipped.
+    ///
+    /// Adapters that occur completely within the sequence are always clipped.
+    pub min_adapter_overlap: usize,
+    /// Runs of more `N` characters than this are shortened to this length.
+    pub max_n_run: Option<usize>,
+}
+
+/// A contiguous part of the original sequence that was kept by preprocessing.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoordinateSegment {
+    pub original_start: usize,
+    pub length: usize,
+}
+
+/// Maps the indices of a preprocessed sequence back to the indices of the original sequence.
+///
+/// Preprocessing only removes characters, so the preprocessed sequence is the concatenation of the kept segments of the original.
+#[derive(Debug, Clone, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoordinateMap {
+    segments: Vec<CoordinateSegment>,
+    original_length: usize,
+}
+
+/// A preprocessed sequence.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Preprocessed {
+    pub sequence: Vec<u8>,
+    pub coordinate_map: CoordinateMap,
+}
+
+impl Default for PreprocessingSettings {
+    fn default() -> Self {
+        Self {
+            min_quality: None,
+            quality_offset: 33,
+            adapters: Vec::new(),
+            min_adapter_overlap: 8,
+            max_n_run: None,
+        }
+    }
+}
+
+impl PreprocessingSettings {
+    /// Returns true if no preprocessing step is enabled.
+    ///
+    /// Quality trimming counts as enabled even though it is skipped for sequences without qualities.
+    pub fn is_empty(&self) -> bool {
+        self.min_quality.is_none() && self.adapters.is_empty() && self.max_n_run.is_none()
+    }
+
+    /// Applies the enabled preprocessing steps to `sequence`.
+    ///
+    /// Qualities are only used for quality trimming and are ignored if `min_quality` is not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `qualities` is given with a different length than `sequence`.
+    pub fn preprocess(&self, sequence: &[u8], qualities: Option<&[u8]>) -> Preprocessed {
+        let mut range = 0..sequence.len();
+
+        if let (Some(min_quality), Some(qualities)) = (self.min_quality, qualities) {
+            assert_eq!(
+                qualities.len(),
+                sequence.len(),
+                "The qualities have a different length than the sequence"
+            );
+            let is_low =
+                |index: usize| qualities[index].saturating_sub(self.quality_offset) < min_quality;
+            while range.start < range.end && is_low(range.start) {
+                range.start += 1;
+            }
+            while range.start < range.end && is_low(range.end - 1) {
+                range.end -= 1;
+            }
+        }
+
+        if let Some(adapter_start) = self
+            .adapters
+            .iter()
+            .filter_map(|adapter| self.find_adapter(&sequence[range.clone()], adapter))
+            .min()
+        {
+            range.end = range.start + adapter_start;
+        }
+
+        let mut segments: Vec<CoordinateSegment> = Vec::new();
+        let mut push = |original_start: usize, length: usize| {
+            if length == 0 {
+                return;
+            }
+            if let Some(last) = segments.last_mut() {
+                if last.original_start + last.length == original_start {
+                    last.length += length;
+                    return;
+                }
+            }
+            segments.push(CoordinateSegment {
+                original_start,
+                length,
+            });
+        };
+
+        if let Some(max_n_run) = self.max_n_run {
+            let mut index = range.start;
+            while index < range.end {
+                let run_length = sequence[index..range.end]
+                    .iter()
+                    .take_while(|character| character.eq_ignore_ascii_case(&b'N'))
+                    .count();
+                if run_length > 0 {
+                    push(index, run_length.min(max_n_run));
+                    index += run_length;
+                } else {
+                    push(index, 1);
+                    index += 1;
+                }
+            }
+        } else {
+            push(range.start, range.len());
+        }
+
+        let coordinate_map = CoordinateMap {
+            segments,
+            original_length: sequence.len(),
+        };
+        Preprocessed {
+            sequence: coordinate_map.apply(sequence),
+            coordinate_map,
+        }
+    }
+
+    /// Returns the leftmost index at which `adapter` starts within `sequence`, possibly reaching past its end.
+    fn find_adapter(&self, sequence: &[u8], adapter: &[u8]) -> Option<usize> {
+        if adapter.is_empty() {
+            return None;
+        }
+        let min_overlap = self.min_adapter_overlap.clamp(1, adapter.len());
+        if sequence.len() < min_overlap {
+            return None;
+        }
+
+        (0..=sequence.len() - min_overlap).find(|&start| {
+            let overlap = &sequence[start..sequence.len().min(start + adapter.len())];
+            overlap.eq_ignore_ascii_case(&adapter[..overlap.len()])
+        })
+    }
+}
+
+impl CoordinateMap {
+    /// Returns the map of a sequence that was not changed.
+    pub fn identity(length: usize) -> Self {
+        Self {
+            segments: if length == 0 {
+                Vec::new()
+            } else {
+                vec![CoordinateSegment {
+                    original_start: 0,
+                    length,
+                }]
+            },
+            original_length: length,
+        }
+    }
+
+    pub fn segments(&self) -> &[CoordinateSegment] {
+        &self.segments
+    }
+
+    pub fn original_length(&self) -> usize {
+        self.original_length
+    }
+
+    pub fn processed_length(&self) -> usize {
+        self.segments.iter().map(|segment| segment.length).sum()
+    }
+
+    /// Returns true if no character was removed.
+    pub fn is_identity(&self) -> bool {
+        self.processed_length() == self.original_length
+    }
+
+    /// Returns the index in the original sequence of the character at `processed_index` in the preprocessed sequence.
+    ///
+    /// The end of the preprocessed sequence maps to the index after its last character in the original sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `processed_index` is greater than the length of the preprocessed sequence.
+    pub fn original_index(&self, processed_index: usize) -> usize {
+        let mut remaining = processed_index;
+        for segment in &self.segments {
+            if remaining < segment.length {
+                return segment.original_start + remaining;
+            }
+            remaining -= segment.length;
+        }
+
+        assert_eq!(
+            remaining, 0,
+            "Index {processed_index} is past the end of the preprocessed sequence"
+        );
+        self.segments
+            .last()
+            .map(|segment| segment.original_start + segment.length)
+            .unwrap_or(0)
+    }
+
+    /// Returns the range of the original sequence spanned by the given range of the preprocessed sequence,
+    /// including any characters that were removed between its first and last character.
+    pub fn original_range(&self, processed_range: Range<usize>) -> Range<usize> {
+        let start = self.original_index(processed_range.start);
+        if processed_range.is_empty() {
+            start..start
+        } else {
+            start..self.original_index(processed_range.end - 1) + 1
+        }
+    }
+
+    /// Returns the kept characters of `original`.
+    pub fn apply<T: Clone>(&self, original: &[T]) -> Vec<T> {
+        self.segments
+            .iter()
+            .flat_map(|segment| {
+                original[segment.original_start..segment.original_start + segment.length]
+                    .iter()
+                    .cloned()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+use super::{CoordinateMap, CoordinateSegment, PreprocessingSettings};
+
+fn segment(original_start: usize, length: usize) -> CoordinateSegment {
+    CoordinateSegment {
+        original_start,
+        length,
+    }
+}
+
+#[test]
+fn no_preprocessing_is_identity() {
+    let preprocessed = PreprocessingSettings::default().preprocess(b"ACGTN", None);
+    assert_eq!(preprocessed.sequence, b"ACGTN");
+    assert_eq!(preprocessed.coordinate_map, CoordinateMap::identity(5));
+    assert!(preprocessed.coordinate_map.is_identity());
+}
+
+#[test]
+fn quality_trimming() {
+    let settings = PreprocessingSettings {
+        min_quality: Some(20),
+        ..Default::default()
+    };
+    // '#' encodes quality 2 and 'I' encodes quality 40.
+    let preprocessed = settings.preprocess(b"ACGTACGT", Some(b"##II#I##"));
+    assert_eq!(preprocessed.sequence, b"GTAC");
+    assert_eq!(preprocessed.coordinate_map.segments(), [segment(2, 4)]);
+
+    // Without qualities, nothing is trimmed.
+    assert_eq!(settings.preprocess(b"ACGT", None).sequence, b"ACGT");
+    // If all characters are of low quality, nothing is left.
+    assert!(settings.preprocess(b"AC", Some(b"##")).sequence.is_empty());
+}
+
+#[test]
+fn adapter_clipping() {
+    let settings = PreprocessingSettings {
+        adapters: vec![b"AGATCGGAAG".to_vec()],
+        min_adapter_overlap: 4,
+        ..Default::default()
+    };
+
+    // The whole adapter and everything after it are clipped.
+    assert_eq!(
+        settings.preprocess(b"CCCCagatcggaagTTTT", None).sequence,
+        b"CCCC"
+    );
+    // A prefix of the adapter at the end is clipped if it is long enough.
+    assert_eq!(settings.preprocess(b"CCCCCAGATC", None).sequence, b"CCCCC");
+    assert_eq!(settings.preprocess(b"CCCCCAGA", None).sequence, b"CCCCCAGA");
+}
+
+#[test]
+fn n_runs_are_collapsed() {
+    let settings = PreprocessingSettings {
+        max_n_run: Some(2),
+        ..Default::default()
+    };
+    let preprocessed = settings.preprocess(b"ACNNNNNGTnnA", None);
+    assert_eq!(preprocessed.sequence, b"ACNNGTnnA");
+    assert_eq!(
+        preprocessed.coordinate_map.segments(),
+        [segment(0, 4), segment(7, 5)]
+    );
+
+    let map = &preprocessed.coordinate_map;
+    assert_eq!(map.original_length(), 12);
+    assert_eq!(map.processed_length(), 9);
+    assert!(!map.is_identity());
+    assert_eq!(map.original_index(3), 3);
+    assert_eq!(map.original_index(4), 7);
+    assert_eq!(map.original_index(9), 12);
+    assert_eq!(map.original_range(2..6), 2..9);
+    assert_eq!(map.original_range(4..4), 7..7);
+    assert_eq!(map.apply(b"ACNNNNNGTnnA"), preprocessed.sequence);
+}
+
+#[test]
+fn steps_are_combined() {
+    let settings = PreprocessingSettings {
+        min_quality: Some(20),
+        adapters: vec![b"GGGG".to_vec()],
+        max_n_run: Some(1),
+        ..Default::default()
+    };
+    let preprocessed = settings.preprocess(b"TACNNNAGGGGT", Some(b"#IIIIIIIIIII"));
+    assert_eq!(preprocessed.sequence, b"ACNA");
+    assert_eq!(
+        preprocessed.coordinate_map.segments(),
+        [segment(1, 3), segment(6, 1)]
+    );
+}
@@ -0,0 +1,114 @@
+//! Records of the inputs that an alignment result was computed from.
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+
+use crate::preprocessing::CoordinateMap;
+
+mod sha256;
+
+#[cfg(test)]
+mod tests;
+
+pub use sha256::sha256_hex;
+
+/// The inputs of an alignment, stored with its result so that it can be validated and re-rendered later.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResultProvenance {
+    pub reference: SequenceProvenance,
+    pub query: SequenceProvenance,
+    /// The hex-encoded SHA-256 hash of the cost configuration, written in its plain text format.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub config_sha256: Option<String>,
+}
+
+/// Where an aligned sequence came from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SequenceProvenance {
+    /// The fasta file containing the sequence.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub file: Option<String>,
+    /// The id of the fasta record.
+    pub record_id: String,
+    /// The index of the fasta record in the file, counting from zero.
+    pub record_index: usize,
+    /// The start and end of the aligned part of the record, if not the whole record was aligned.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub range: Option<(usize, usize)>,
+    /// True if the reverse complement of the range was aligned.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub reverse_complement: bool,
+    /// Maps the aligned sequence back to the range or strand of the record, if preprocessing removed characters from it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub coordinate_map: Option<CoordinateMap>,
+    /// The length of the aligned sequence.
+    pub length: usize,
+    /// The hex-encoded SHA-256 hash of the sequence characters, without line breaks or the record header.
+    pub sha256: String,
+    /// The sequence itself, if it was embedded.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sequence: Option<String>,
+}
+
+impl SequenceProvenance {
+    /// Records the hash of `sequence`, and embeds it if `embed_sequence` is set.
+    ///
+    /// If only a part of the record or its reverse complement was aligned,
+    /// its [`range`](Self::range) and [`reverse_complement`](Self::reverse_complement) need to be set afterwards,
+    /// and likewise the [`coordinate_map`](Self::coordinate_map) if it was preprocessed.
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        file: Option<String>,
+        record_id: String,
+        record_index: usize,
+        sequence: &SubsequenceType,
+        embed_sequence: bool,
+    ) -> Self {
+        let characters = sequence.clone_as_vec();
+
+        Self {
+            file,
+            record_id,
+            record_index,
+            range: None,
+            reverse_complement: false,
+            coordinate_map: None,
+            length: characters.len(),
+            sha256: sha256_hex(&characters),
+            sequence: embed_sequence
+                .then(|| String::from_utf8(characters).expect("alphabet characters are ASCII")),
+        }
+    }
+
+    /// Returns true if `sequence` has the recorded length and hash.
+    pub fn matches<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        sequence: &SubsequenceType,
+    ) -> bool {
+        self.length == sequence.len() && self.sha256 == sha256_hex(&sequence.clone_as_vec())
+    }
+}
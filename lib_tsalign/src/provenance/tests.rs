@@ -0,0 +1,55 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use super::{sha256_hex, SequenceProvenance};
+
+#[test]
+fn sha256_test_vectors() {
+    assert_eq!(
+        sha256_hex(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // 56 bytes, so the padding needs an extra block.
+    assert_eq!(
+        sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(
+        sha256_hex(&[b'a'; 1_000_000]),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn sequence_provenance_matches_sequence() {
+    let sequence = VectorGenome::<DnaAlphabet>::from_iter_u8("ACGTTG".bytes()).unwrap();
+    let other = VectorGenome::<DnaAlphabet>::from_iter_u8("ACGTTC".bytes()).unwrap();
+
+    let hashed = SequenceProvenance::new(
+        None,
+        "reference".to_string(),
+        0,
+        sequence.as_genome_subsequence(),
+        false,
+    );
+    assert_eq!(hashed.length, 6);
+    assert_eq!(hashed.sha256, sha256_hex(b"ACGTTG"));
+    assert_eq!(hashed.sequence, None);
+    assert!(hashed.matches(sequence.as_genome_subsequence()));
+    assert!(!hashed.matches(other.as_genome_subsequence()));
+
+    let embedded = SequenceProvenance::new(
+        None,
+        "reference".to_string(),
+        0,
+        sequence.as_genome_subsequence(),
+        true,
+    );
+    assert_eq!(embedded.sequence.as_deref(), Some("ACGTTG"));
+}
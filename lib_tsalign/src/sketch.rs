@@ -0,0 +1,136 @@
+use std::{cmp::Ordering, hash::BuildHasher};
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+use deterministic_default_hasher::DeterministicDefaultHasher;
+
+#[cfg(test)]
+mod tests;
+
+/// A bottom-k MinHash sketch of the k-mers of a sequence.
+///
+/// The sketch consists of the `sketch_size` smallest distinct k-mer hashes of the sequence.
+/// Two sketches with the same `k` and `sketch_size` can be compared to estimate
+/// the Jaccard index of the k-mer sets of their sequences, and from that their average nucleotide identity (ANI).
+///
+/// Only the forward strand is sketched, since template switch alignment does not consider reverse complements of whole sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinHashSketch {
+    k: usize,
+    sketch_size: usize,
+    /// Sorted ascending and without duplicates.
+    hashes: Vec<u64>,
+}
+
+impl MinHashSketch {
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequence: &SubsequenceType,
+        k: usize,
+        sketch_size: usize,
+    ) -> Self {
+        assert!(k > 0, "The k-mer size must be positive, but is zero");
+        assert!(
+            sketch_size > 0,
+            "The sketch size must be positive, but is zero"
+        );
+
+        let sequence = sequence.as_string();
+        let mut hashes: Vec<_> = sequence
+            .as_bytes()
+            .windows(k)
+            .map(|kmer| DeterministicDefaultHasher.hash_one(kmer))
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.truncate(sketch_size);
+
+        Self {
+            k,
+            sketch_size,
+            hashes,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn sketch_size(&self) -> usize {
+        self.sketch_size
+    }
+
+    /// Returns true if the sequence contained no k-mers, i.e. was shorter than `k`.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Estimate the Jaccard index of the k-mer sets of the two sketched sequences.
+    ///
+    /// This is the fraction of the smallest `sketch_size` hashes of the union of both sketches that are in both sketches.
+    /// If both sequences contain no k-mers, then the Jaccard index is zero.
+    pub fn jaccard(&self, other: &Self) -> f64 {
+        assert_eq!(self.k, other.k, "Sketches have different k-mer sizes");
+        assert_eq!(
+            self.sketch_size, other.sketch_size,
+            "Sketches have different sizes"
+        );
+
+        let mut union_size = 0;
+        let mut intersection_size = 0;
+        let mut self_hashes = self.hashes.iter().peekable();
+        let mut other_hashes = other.hashes.iter().peekable();
+
+        while union_size < self.sketch_size {
+            match (self_hashes.peek(), other_hashes.peek()) {
+                (Some(self_hash), Some(other_hash)) => match self_hash.cmp(other_hash) {
+                    Ordering::Less => {
+                        self_hashes.next();
+                    }
+                    Ordering::Greater => {
+                        other_hashes.next();
+                    }
+                    Ordering::Equal => {
+                        self_hashes.next();
+                        other_hashes.next();
+                        intersection_size += 1;
+                    }
+                },
+                (Some(_), None) => {
+                    self_hashes.next();
+                }
+                (None, Some(_)) => {
+                    other_hashes.next();
+                }
+                (None, None) => break,
+            }
+
+            union_size += 1;
+        }
+
+        if union_size == 0 {
+            0.0
+        } else {
+            intersection_size as f64 / union_size as f64
+        }
+    }
+
+    /// Estimate the per-base divergence of the two sketched sequences with the Mash distance `-ln(2J / (1 + J)) / k`.
+    ///
+    /// The result is clamped to at most one, which is also returned if the sketches share no hashes.
+    pub fn mash_distance(&self, other: &Self) -> f64 {
+        let jaccard = self.jaccard(other);
+        if jaccard == 0.0 {
+            1.0
+        } else {
+            (-(2.0 * jaccard / (1.0 + jaccard)).ln() / self.k as f64).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Estimate the average nucleotide identity of the two sketched sequences as one minus the Mash distance.
+    pub fn ani(&self, other: &Self) -> f64 {
+        1.0 - self.mash_distance(other)
+    }
+}
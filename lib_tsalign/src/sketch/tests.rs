@@ -0,0 +1,51 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use super::MinHashSketch;
+
+fn sketch(sequence: &str, k: usize, sketch_size: usize) -> MinHashSketch {
+    let sequence = VectorGenome::<DnaAlphabet>::from_iter_u8(sequence.bytes()).unwrap();
+    MinHashSketch::new(sequence.as_genome_subsequence(), k, sketch_size)
+}
+
+#[test]
+fn identical_sequences() {
+    let a = sketch("ACGTTGCAACGGATCCATGA", 5, 100);
+    let b = sketch("ACGTTGCAACGGATCCATGA", 5, 100);
+
+    assert_eq!(a.jaccard(&b), 1.0);
+    assert_eq!(a.mash_distance(&b), 0.0);
+    assert_eq!(a.ani(&b), 1.0);
+}
+
+#[test]
+fn disjoint_sequences() {
+    let a = sketch("AAAAAAAAAA", 3, 100);
+    let b = sketch("CCCCCCCCCC", 3, 100);
+
+    assert_eq!(a.jaccard(&b), 0.0);
+    assert_eq!(a.ani(&b), 0.0);
+}
+
+#[test]
+fn similar_sequences() {
+    // One substitution in the middle destroys five of the 16 shared 5-mers.
+    let a = sketch("ACGTTGCAACGGATCCATGA", 5, 100);
+    let b = sketch("ACGTTGCAAGGGATCCATGA", 5, 100);
+
+    let jaccard = a.jaccard(&b);
+    assert!(jaccard > 0.3 && jaccard < 0.8, "{jaccard}");
+    let ani = a.ani(&b);
+    assert!(ani > 0.8 && ani < 1.0, "{ani}");
+}
+
+#[test]
+fn short_sequences() {
+    let a = sketch("ACG", 5, 100);
+    let b = sketch("ACG", 5, 100);
+
+    assert!(a.is_empty());
+    assert_eq!(a.jaccard(&b), 0.0);
+}
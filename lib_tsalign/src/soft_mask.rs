@@ -0,0 +1,131 @@
+use std::io::{BufRead, BufReader, Read};
+
+use generic_a_star::cost::Cost;
+
+use crate::error::Result;
+
+#[cfg(test)]
+mod tests;
+
+/// Marks which characters of a sequence are soft-masked, i.e. written in lower case in the input.
+///
+/// Soft-masking usually marks repeats, in which template switches are hard to distinguish from alignment artifacts.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SoftMask {
+    masked: Vec<bool>,
+}
+
+/// How the aligner treats soft-masked regions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SoftMaskHandling {
+    /// No template switch may enter within a masked region.
+    ForbidTemplateSwitches,
+    /// The base cost of template switches entering within a masked region is multiplied by `factor`.
+    ///
+    /// Factors below one are treated as one, such that masked regions never make template switches cheaper.
+    ScaleTemplateSwitchBaseCost { factor: u64 },
+}
+
+/// The soft masks of a reference and a query, together with how the aligner should treat them.
+#[derive(Debug, Clone)]
+pub struct SoftMasks {
+    pub reference: SoftMask,
+    pub query: SoftMask,
+    pub handling: SoftMaskHandling,
+}
+
+impl SoftMask {
+    pub fn new(masked: Vec<bool>) -> Self {
+        Self { masked }
+    }
+
+    /// Masks all lower case characters.
+    pub fn from_ascii(sequence: &[u8]) -> Self {
+        Self::new(
+            sequence
+                .iter()
+                .map(|character| character.is_ascii_lowercase())
+                .collect(),
+        )
+    }
+
+    /// Reads the soft masks of all records in a fasta file.
+    ///
+    /// Like when loading the sequences, characters whose upper case version is marked in `skip_characters` are skipped,
+    /// such that the masks have the same lengths as the loaded sequences.
+    pub fn read_fasta(reader: impl Read, skip_characters: &[bool]) -> Result<Vec<Self>> {
+        let mut masks = Vec::new();
+
+        for line in BufReader::new(reader).split(b'\n') {
+            let line = line?;
+            if line.starts_with(b">") {
+                masks.push(Self::default());
+                continue;
+            }
+
+            let Some(mask) = masks.last_mut() else {
+                continue;
+            };
+            mask.masked.extend(
+                line.iter()
+                    .filter(|&&character| {
+                        character != b'\r'
+                            && !skip_characters
+                                .get(usize::from(character.to_ascii_uppercase()))
+                                .copied()
+                                .unwrap_or(false)
+                    })
+                    .map(|character| character.is_ascii_lowercase()),
+            );
+        }
+
+        Ok(masks)
+    }
+
+    /// Returns true if the character at `index` is masked.
+    ///
+    /// Indices past the end of the sequence are not masked.
+    pub fn is_masked(&self, index: usize) -> bool {
+        self.masked.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn masked_count(&self) -> usize {
+        self.masked.iter().filter(|masked| **masked).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.masked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masked.is_empty()
+    }
+}
+
+impl SoftMasks {
+    /// Returns the base cost of a template switch entering at the given primary position.
+    ///
+    /// The entrance is within a masked region if the next character of either the reference or the query is masked.
+    /// Since the cost never decreases, lower bounds computed from the unmasked costs stay admissible.
+    pub fn template_switch_base_cost(
+        &self,
+        base_cost: Cost,
+        reference_index: usize,
+        query_index: usize,
+    ) -> Cost {
+        if !self.reference.is_masked(reference_index) && !self.query.is_masked(query_index) {
+            return base_cost;
+        }
+
+        match self.handling {
+            SoftMaskHandling::ForbidTemplateSwitches => Cost::MAX,
+            SoftMaskHandling::ScaleTemplateSwitchBaseCost { factor } => {
+                if base_cost == Cost::MAX {
+                    base_cost
+                } else {
+                    base_cost.as_u64().saturating_mul(factor.max(1)).into()
+                }
+            }
+        }
+    }
+}
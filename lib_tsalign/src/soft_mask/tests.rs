@@ -0,0 +1,98 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+use generic_a_star::cost::Cost;
+
+use crate::a_star_aligner::{template_switch_distance_a_star_align, SearchSettings};
+
+use super::{SoftMask, SoftMaskHandling, SoftMasks};
+use crate::test_fixtures::{sample_config, TestStrategies};
+
+#[test]
+fn read_fasta() {
+    let input = ">reference\r\nACgt\r\n-nA\r\n>query description\nAC\n>empty\n";
+    let mut skip_characters = vec![false; 128];
+    skip_characters[usize::from(b'-')] = true;
+    let masks = SoftMask::read_fasta(input.as_bytes(), &skip_characters).unwrap();
+
+    assert_eq!(
+        masks,
+        vec![
+            SoftMask::new(vec![false, false, true, true, true, false]),
+            SoftMask::from_ascii(b"AC"),
+            SoftMask::default(),
+        ]
+    );
+    assert_eq!(masks[0].masked_count(), 3);
+    assert!(masks[0].is_masked(4));
+    assert!(!masks[0].is_masked(6));
+}
+
+#[test]
+fn template_switch_base_cost() {
+    let soft_masks = |handling| SoftMasks {
+        reference: SoftMask::from_ascii(b"ACgt"),
+        query: SoftMask::from_ascii(b"aCGT"),
+        handling,
+    };
+
+    let forbid = soft_masks(SoftMaskHandling::ForbidTemplateSwitches);
+    assert_eq!(forbid.template_switch_base_cost(3.into(), 1, 1), 3.into());
+    assert_eq!(forbid.template_switch_base_cost(3.into(), 0, 1), 3.into());
+    assert_eq!(forbid.template_switch_base_cost(3.into(), 2, 1), Cost::MAX);
+    assert_eq!(forbid.template_switch_base_cost(3.into(), 1, 0), Cost::MAX);
+    assert_eq!(forbid.template_switch_base_cost(3.into(), 4, 4), 3.into());
+
+    let scale = soft_masks(SoftMaskHandling::ScaleTemplateSwitchBaseCost { factor: 5 });
+    assert_eq!(scale.template_switch_base_cost(3.into(), 1, 1), 3.into());
+    assert_eq!(scale.template_switch_base_cost(3.into(), 3, 2), 15.into());
+    assert_eq!(scale.template_switch_base_cost(Cost::MAX, 3, 2), Cost::MAX);
+
+    let shrink = soft_masks(SoftMaskHandling::ScaleTemplateSwitchBaseCost { factor: 0 });
+    assert_eq!(shrink.template_switch_base_cost(3.into(), 3, 2), 3.into());
+}
+
+#[test]
+fn masked_template_switches() {
+    let reference = b"AAAAAACCTTTTTCCCCCCCC";
+    let query = b"AAAAAAggaaaaaggCCCCCC";
+    let config = sample_config();
+    let cost = |handling: Option<SoftMaskHandling>| {
+        let reference_sequence = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+        let query_sequence =
+            VectorGenome::<DnaAlphabetOrN>::from_slice_u8(&query.to_ascii_uppercase()).unwrap();
+        template_switch_distance_a_star_align::<TestStrategies, _>(
+            reference_sequence.as_genome_subsequence(),
+            query_sequence.as_genome_subsequence(),
+            config.clone(),
+            (),
+            handling.map(|handling| SoftMasks {
+                reference: SoftMask::from_ascii(reference),
+                query: SoftMask::from_ascii(query),
+                handling,
+            }),
+            &SearchSettings::default(),
+        )
+        .unwrap()
+        .statistics()
+        .cost
+        .raw()
+    };
+
+    let unmasked_cost = cost(None);
+    assert_eq!(unmasked_cost, 2.0);
+    assert_eq!(
+        cost(Some(SoftMaskHandling::ScaleTemplateSwitchBaseCost {
+            factor: 1
+        })),
+        unmasked_cost
+    );
+    assert!(
+        cost(Some(SoftMaskHandling::ScaleTemplateSwitchBaseCost {
+            factor: 2
+        })) > unmasked_cost
+    );
+
+    assert!(cost(Some(SoftMaskHandling::ForbidTemplateSwitches)) > unmasked_cost);
+}
@@ -0,0 +1,464 @@
+//! Multiple alignments of sequences to a centre sequence.
+//!
+//! Each sequence is aligned pairwise to the centre with the template switch aligner,
+//! and the pairwise alignments are merged into a star alignment in the coordinates of the centre.
+//! Template switches, inversions and duplications cannot be expressed as columns of the centre,
+//! so the characters they cover are written as unaligned characters, and template switches are annotated per sequence.
+
+use std::{io::Write, ops::Range};
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        template_switch_distance::{
+            strategies::{
+                primary_match::AllowPrimaryMatchStrategy, shortcut::ShortcutStrategy,
+                template_switch_count::TemplateSwitchCountStrategy, AlignmentStrategySelector,
+            },
+            AlignmentType, TemplateSwitchPrimary,
+        },
+        Aligner,
+    },
+    error::{Error, Result},
+    sketch::MinHashSketch,
+    ts_normalisation::TemplateSwitchAnnotation,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A multiple alignment of sequences that were each aligned to the same centre sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarMsa {
+    /// The index of the centre in [`rows`](Self::rows).
+    pub centre: usize,
+    /// A row for each aligned sequence, in the order they were given.
+    pub rows: Vec<StarMsaRow>,
+}
+
+/// A sequence of a [`StarMsa`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarMsaRow {
+    pub name: String,
+    /// The aligned characters, with `-` for gaps.
+    ///
+    /// Characters that are not aligned to a character of the centre, e.g. because they are covered by a template switch
+    /// or duplicate a part of the centre, are written in lower case.
+    /// Such characters are placed into the columns between the centre characters next to them.
+    pub text: String,
+    /// The pairwise alignment to the centre, where the centre is the reference, or `None` for the centre itself.
+    pub alignment: Option<AlignmentResult<AlignmentType>>,
+    /// The template switches of the pairwise alignment to the centre.
+    pub template_switches: Vec<StarMsaTemplateSwitch>,
+}
+
+/// A template switch of a sequence in a [`StarMsa`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarMsaTemplateSwitch {
+    /// The template switch, where the reference is the centre and the query is the sequence of the row.
+    pub annotation: TemplateSwitchAnnotation,
+    /// The columns of the multiple alignment spanned by the centre and row characters covered by the template switch.
+    pub columns: Range<usize>,
+}
+
+/// The pairwise alignment of a sequence to the centre, in coordinates of the centre.
+#[derive(Debug)]
+struct Projection {
+    /// The sequence index aligned to each centre index, if any.
+    aligned: Vec<Option<usize>>,
+    /// The sequence indices placed before each centre index and after the last, and whether they are unaligned.
+    inserted: Vec<Vec<(usize, bool)>>,
+    /// The first centre index that was not yet placed.
+    centre_end: usize,
+    /// The first sequence index that was not yet placed.
+    sequence_end: usize,
+}
+
+impl StarMsa {
+    /// Chooses the sequence whose k-mers are most similar to those of all other sequences as centre.
+    ///
+    /// The similarity is the sum of the Jaccard indices estimated with [`MinHashSketch`]es of the given size.
+    /// Ties are broken towards the first sequence.
+    pub fn choose_centre<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequences: &[&SubsequenceType],
+        k: usize,
+        sketch_size: usize,
+    ) -> usize {
+        let sketches: Vec<_> = sequences
+            .iter()
+            .map(|sequence| MinHashSketch::new(*sequence, k, sketch_size))
+            .collect();
+        let similarities: Vec<f64> = sketches
+            .iter()
+            .enumerate()
+            .map(|(index, sketch)| {
+                sketches
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_index, _)| *other_index != index)
+                    .map(|(_, other)| sketch.jaccard(other))
+                    .sum()
+            })
+            .collect();
+
+        similarities
+            .iter()
+            .enumerate()
+            .fold(
+                None,
+                |best: Option<(usize, f64)>, (index, &similarity)| match best {
+                    Some((_, best_similarity)) if best_similarity >= similarity => best,
+                    _ => Some((index, similarity)),
+                },
+            )
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Aligns all sequences to the sequence at index `centre` and merges the alignments into a star alignment.
+    ///
+    /// The same aligner is used for all pairs, so the parts of its strategies that do not depend on the sequences,
+    /// like the template switch lower bounds, are computed only once.
+    /// Returns [`Error::StarMsaWithoutAlignment`] if a sequence could not be aligned to the centre, e.g. because of the cost limit.
+    pub fn align<
+        Strategies: AlignmentStrategySelector<PrimaryMatch = AllowPrimaryMatchStrategy>,
+        SubsequenceType: GenomeSequence<Strategies::Alphabet, SubsequenceType> + ?Sized,
+    >(
+        aligner: &Aligner<Strategies>,
+        sequences: &[(&str, &SubsequenceType)],
+        centre: usize,
+    ) -> Result<Self>
+    where
+        <Strategies::TemplateSwitchCount as TemplateSwitchCountStrategy>::Memory: Clone,
+        <Strategies::Shortcut as ShortcutStrategy>::Memory: Clone,
+    {
+        let (_, centre_sequence) = sequences[centre];
+        let alignments = sequences
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != centre)
+            .map(|(_, (_, sequence))| aligner.align(centre_sequence, *sequence, None))
+            .collect::<Result<_>>()?;
+
+        Self::from_alignments(sequences, centre, alignments)
+    }
+
+    /// Merges the pairwise alignments of all sequences except the centre to the sequence at index `centre`.
+    ///
+    /// The `alignments` are given in the order of the sequences, skipping the centre, and the centre is their reference.
+    pub fn from_alignments<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        sequences: &[(&str, &SubsequenceType)],
+        centre: usize,
+        alignments: Vec<AlignmentResult<AlignmentType>>,
+    ) -> Result<Self> {
+        assert_eq!(
+            alignments.len() + 1,
+            sequences.len(),
+            "Expected an alignment for each sequence except the centre"
+        );
+        let (_, centre_sequence) = sequences[centre];
+        let centre_length = centre_sequence.len();
+
+        let mut alignments = alignments.into_iter();
+        let alignments: Vec<_> = (0..sequences.len())
+            .map(|index| (index != centre).then(|| alignments.next().unwrap()))
+            .collect();
+        let projections = sequences
+            .iter()
+            .zip(&alignments)
+            .map(|((name, sequence), alignment)| match alignment {
+                None => Ok(Projection::identity(centre_length)),
+                Some(AlignmentResult::WithTarget { alignment, .. }) => {
+                    Ok(Projection::new(alignment, centre_length, sequence.len()))
+                }
+                Some(alignment @ AlignmentResult::WithoutTarget { .. }) => {
+                    Err(Error::StarMsaWithoutAlignment {
+                        sequence: name.to_string(),
+                        reason: alignment.no_target_reason().unwrap(),
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // The number of columns before each centre character and after the last one.
+        let insertion_widths: Vec<_> = (0..=centre_length)
+            .map(|centre_index| {
+                projections
+                    .iter()
+                    .map(|projection| projection.inserted[centre_index].len())
+                    .max()
+                    .unwrap()
+            })
+            .collect();
+        let insertion_offsets: Vec<_> = insertion_widths
+            .iter()
+            .scan(0, |offset, width| {
+                let insertion_offset = *offset;
+                *offset += width + 1;
+                Some(insertion_offset)
+            })
+            .collect();
+        let column_count = insertion_offsets[centre_length] + insertion_widths[centre_length];
+
+        let rows = sequences
+            .iter()
+            .zip(alignments)
+            .zip(&projections)
+            .map(|(((name, sequence), alignment), projection)| {
+                let mut text = vec!['-'; column_count];
+                let mut sequence_columns = vec![0; sequence.len()];
+                let mut centre_columns = Vec::with_capacity(centre_length);
+                for (centre_index, inserted) in projection.inserted.iter().enumerate() {
+                    let offset = insertion_offsets[centre_index];
+                    for (column, &(sequence_index, unaligned)) in (offset..).zip(inserted) {
+                        let character: char = sequence[sequence_index].clone().into();
+                        text[column] = if unaligned {
+                            character.to_ascii_lowercase()
+                        } else {
+                            character
+                        };
+                        sequence_columns[sequence_index] = column;
+                    }
+
+                    if let Some(aligned) = projection.aligned.get(centre_index) {
+                        let column = offset + insertion_widths[centre_index];
+                        if let Some(sequence_index) = *aligned {
+                            text[column] = sequence[sequence_index].clone().into();
+                            sequence_columns[sequence_index] = column;
+                        }
+                        centre_columns.push(column);
+                    }
+                }
+
+                let template_switches = match &alignment {
+                    Some(AlignmentResult::WithTarget { alignment, .. }) => {
+                        TemplateSwitchAnnotation::from_alignment(alignment)
+                            .into_iter()
+                            .map(|annotation| {
+                                let columns = template_switch_columns(
+                                    &annotation,
+                                    &centre_columns,
+                                    &sequence_columns,
+                                    column_count,
+                                );
+                                StarMsaTemplateSwitch {
+                                    annotation,
+                                    columns,
+                                }
+                            })
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+
+                StarMsaRow {
+                    name: name.to_string(),
+                    text: text.into_iter().collect(),
+                    alignment,
+                    template_switches,
+                }
+            })
+            .collect();
+
+        Ok(Self { centre, rows })
+    }
+
+    /// The number of columns of the alignment.
+    pub fn column_count(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.text.chars().count())
+    }
+
+    /// Writes the alignment as aligned fasta, with one record per row.
+    pub fn write_fasta(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for row in &self.rows {
+            writeln!(writer, ">{}", row.name)?;
+            writeln!(writer, "{}", row.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl Projection {
+    fn identity(length: usize) -> Self {
+        Self {
+            aligned: (0..length).map(Some).collect(),
+            inserted: vec![Vec::new(); length + 1],
+            centre_end: length,
+            sequence_end: length,
+        }
+    }
+
+    fn new(
+        alignment: &[(usize, AlignmentType)],
+        centre_length: usize,
+        sequence_length: usize,
+    ) -> Self {
+        let mut projection = Self {
+            aligned: vec![None; centre_length],
+            inserted: vec![Vec::new(); centre_length + 1],
+            centre_end: 0,
+            sequence_end: 0,
+        };
+        let mut centre_index = 0;
+        let mut sequence_index = 0;
+        // The centre and sequence indices at the entrance of the current template switch or inversion,
+        // and the number of primary characters of the current template switch.
+        let mut event_start = (0, 0);
+        let mut primary_length = 0;
+        let mut primary = TemplateSwitchPrimary::Reference;
+
+        for &(count, alignment_type) in alignment {
+            match alignment_type {
+                AlignmentType::PrimaryMatch
+                | AlignmentType::PrimarySubstitution
+                | AlignmentType::PrimaryFlankMatch
+                | AlignmentType::PrimaryFlankSubstitution
+                | AlignmentType::PrimaryMatchRun { .. } => {
+                    let count = match alignment_type {
+                        AlignmentType::PrimaryMatchRun { length } => count * length,
+                        _ => count,
+                    };
+                    for _ in 0..count {
+                        projection.align(Some(centre_index), Some(sequence_index));
+                        centre_index += 1;
+                        sequence_index += 1;
+                    }
+                }
+                AlignmentType::PrimaryInsertion | AlignmentType::PrimaryFlankInsertion => {
+                    for _ in 0..count {
+                        projection.align(None, Some(sequence_index));
+                        sequence_index += 1;
+                    }
+                }
+                AlignmentType::PrimaryDeletion | AlignmentType::PrimaryFlankDeletion => {
+                    for _ in 0..count {
+                        projection.align(Some(centre_index), None);
+                        centre_index += 1;
+                    }
+                }
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: template_switch_primary,
+                    ..
+                } => {
+                    event_start = (centre_index, sequence_index);
+                    primary_length = 0;
+                    primary = template_switch_primary;
+                }
+                AlignmentType::SecondaryMatch
+                | AlignmentType::SecondarySubstitution
+                | AlignmentType::SecondaryInsertion => primary_length += count,
+                AlignmentType::SecondaryMatchRun { length } => primary_length += count * length,
+                AlignmentType::SecondaryDeletion => {}
+                AlignmentType::TemplateSwitchExit { length_difference } => {
+                    let (primary_start, anti_primary_start) = match primary {
+                        TemplateSwitchPrimary::Reference => event_start,
+                        TemplateSwitchPrimary::Query => (event_start.1, event_start.0),
+                    };
+                    let primary_end = primary_start + primary_length;
+                    let anti_primary_end = (anti_primary_start as isize
+                        + primary_length as isize
+                        + length_difference) as usize;
+                    (centre_index, sequence_index) = match primary {
+                        TemplateSwitchPrimary::Reference => (primary_end, anti_primary_end),
+                        TemplateSwitchPrimary::Query => (anti_primary_end, primary_end),
+                    };
+                    projection.skip(event_start.0..centre_index, event_start.1..sequence_index);
+                }
+                AlignmentType::PrimaryDuplication { length } => centre_index -= count * length,
+                AlignmentType::InversionEntrance { .. } => {
+                    event_start = (centre_index, sequence_index);
+                }
+                AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                    centre_index += count;
+                    sequence_index += count;
+                }
+                AlignmentType::InversionExit => {
+                    projection.skip(event_start.0..centre_index, event_start.1..sequence_index);
+                }
+                AlignmentType::Root
+                | AlignmentType::SecondaryRoot
+                | AlignmentType::PrimaryReentry
+                | AlignmentType::PrimaryShortcut { .. } => {}
+            }
+        }
+        debug_assert_eq!(projection.centre_end, centre_length);
+        debug_assert_eq!(projection.sequence_end, sequence_length);
+
+        projection
+    }
+
+    /// Places an alignment column.
+    ///
+    /// Centre characters that were placed already are not placed again, and sequence characters aligned to them are placed as unaligned.
+    fn align(&mut self, centre_index: Option<usize>, sequence_index: Option<usize>) {
+        let sequence_index = sequence_index.filter(|&index| index >= self.sequence_end);
+        if let Some(index) = sequence_index {
+            self.sequence_end = index + 1;
+        }
+
+        match centre_index {
+            Some(index) if index >= self.centre_end => {
+                self.aligned[index] = sequence_index;
+                self.centre_end = index + 1;
+            }
+            _ => {
+                if let Some(index) = sequence_index {
+                    self.inserted[self.centre_end].push((index, centre_index.is_some()));
+                }
+            }
+        }
+    }
+
+    /// Places the characters of both ranges unaligned to each other.
+    fn skip(&mut self, centre_range: Range<usize>, sequence_range: Range<usize>) {
+        self.centre_end = self.centre_end.max(centre_range.end);
+        for index in sequence_range.start.max(self.sequence_end)..sequence_range.end {
+            self.inserted[self.centre_end].push((index, true));
+            self.sequence_end = index + 1;
+        }
+    }
+}
+
+/// The columns spanned by the characters of the centre and of the row sequence that are covered by the template switch.
+fn template_switch_columns(
+    annotation: &TemplateSwitchAnnotation,
+    centre_columns: &[usize],
+    sequence_columns: &[usize],
+    column_count: usize,
+) -> Range<usize> {
+    let (centre_range, sequence_range) = match annotation.primary {
+        TemplateSwitchPrimary::Reference => (
+            annotation.primary_range.clone(),
+            annotation.anti_primary_range.clone(),
+        ),
+        TemplateSwitchPrimary::Query => (
+            annotation.anti_primary_range.clone(),
+            annotation.primary_range.clone(),
+        ),
+    };
+    // Ranges that end before they start jump back and cover no characters.
+    let forward = |range: Range<usize>| range.start..range.end.max(range.start);
+    let columns: Vec<_> = centre_columns[forward(centre_range.clone())]
+        .iter()
+        .chain(&sequence_columns[forward(sequence_range)])
+        .copied()
+        .collect();
+
+    match (columns.iter().min(), columns.iter().max()) {
+        (Some(&first), Some(&last)) => first..last + 1,
+        _ => {
+            let column = centre_columns
+                .get(centre_range.start)
+                .copied()
+                .unwrap_or(column_count);
+            column..column
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::{AlignmentResult, NoTargetReason},
+        template_switch_distance::{
+            strategies::template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+        AlignerBuilder,
+    },
+    error::Error,
+    test_fixtures::{sample_config, QUERY, REFERENCE},
+};
+
+use super::StarMsa;
+
+fn genome(sequence: &[u8]) -> VectorGenome<DnaAlphabetOrN> {
+    VectorGenome::from_slice_u8(sequence).unwrap()
+}
+
+fn with_target(alignment: &[(usize, AlignmentType)]) -> AlignmentResult<AlignmentType> {
+    AlignmentResult::WithTarget {
+        alignment: alignment.to_vec(),
+        statistics: Default::default(),
+    }
+}
+
+fn texts(msa: &StarMsa) -> Vec<&str> {
+    msa.rows.iter().map(|row| row.text.as_str()).collect()
+}
+
+#[test]
+fn insertions_are_merged() {
+    let centre = genome(b"ACGT");
+    let inserted = genome(b"ACGGT");
+    let deleted = genome(b"AGT");
+    let sequences = [
+        ("inserted", inserted.as_genome_subsequence()),
+        ("centre", centre.as_genome_subsequence()),
+        ("deleted", deleted.as_genome_subsequence()),
+    ];
+
+    let msa = StarMsa::from_alignments(
+        &sequences,
+        1,
+        vec![
+            with_target(&[
+                (2, AlignmentType::PrimaryMatch),
+                (1, AlignmentType::PrimaryInsertion),
+                (2, AlignmentType::PrimaryMatch),
+            ]),
+            with_target(&[
+                (1, AlignmentType::PrimaryMatch),
+                (1, AlignmentType::PrimaryDeletion),
+                (2, AlignmentType::PrimaryMatch),
+            ]),
+        ],
+    )
+    .unwrap();
+    assert_eq!(msa.centre, 1);
+    assert_eq!(texts(&msa), ["ACGGT", "AC-GT", "A--GT"]);
+    assert_eq!(msa.column_count(), 5);
+    assert!(msa.rows[1].alignment.is_none());
+
+    let mut output = Vec::new();
+    msa.write_fasta(&mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        ">inserted\nACGGT\n>centre\nAC-GT\n>deleted\nA--GT\n"
+    );
+}
+
+#[test]
+fn template_switches_are_unaligned() {
+    let centre = genome(b"AACCGGTT");
+    let sequence = genome(b"AACCGGTT");
+    let sequences = [
+        ("centre", centre.as_genome_subsequence()),
+        ("sequence", sequence.as_genome_subsequence()),
+    ];
+
+    let msa = StarMsa::from_alignments(
+        &sequences,
+        0,
+        vec![with_target(&[
+            (2, AlignmentType::PrimaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Query,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::ReverseComplement,
+                    first_offset: 4,
+                },
+            ),
+            (2, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: 0,
+                },
+            ),
+            (4, AlignmentType::PrimaryMatch),
+        ])],
+    )
+    .unwrap();
+    assert_eq!(texts(&msa), ["AACC--GGTT", "AA--ccGGTT"]);
+
+    let template_switches = &msa.rows[1].template_switches;
+    assert_eq!(template_switches.len(), 1);
+    assert_eq!(template_switches[0].annotation.primary_range, 2..4);
+    assert_eq!(template_switches[0].columns, 2..6);
+}
+
+#[test]
+fn duplications_are_unaligned() {
+    let centre = genome(b"ACGTA");
+    let sequence = genome(b"ACGTGTA");
+    let sequences = [
+        ("centre", centre.as_genome_subsequence()),
+        ("sequence", sequence.as_genome_subsequence()),
+    ];
+
+    let msa = StarMsa::from_alignments(
+        &sequences,
+        0,
+        vec![with_target(&[
+            (4, AlignmentType::PrimaryMatch),
+            (1, AlignmentType::PrimaryDuplication { length: 2 }),
+            (3, AlignmentType::PrimaryMatch),
+        ])],
+    )
+    .unwrap();
+    assert_eq!(texts(&msa), ["ACGT--A", "ACGTgtA"]);
+}
+
+#[test]
+fn missing_alignments_are_reported() {
+    let centre = genome(b"ACGT");
+    let sequences = [
+        ("centre", centre.as_genome_subsequence()),
+        ("sequence", centre.as_genome_subsequence()),
+    ];
+
+    let result = StarMsa::from_alignments(
+        &sequences,
+        0,
+        vec![AlignmentResult::WithoutTarget {
+            reason: NoTargetReason::ExceededTimeLimit,
+            statistics: Default::default(),
+        }],
+    );
+    assert!(matches!(
+        result,
+        Err(Error::StarMsaWithoutAlignment { sequence, reason: NoTargetReason::ExceededTimeLimit })
+            if sequence == "sequence"
+    ));
+}
+
+#[test]
+fn choose_centre_prefers_shared_kmers() {
+    let sequences = [
+        genome(b"TTTTTTTTTTTTTTTTTTTT"),
+        genome(b"ACGTTGCAACTCAGGATCCG"),
+        genome(b"ACGTTGCAACTCAGGATCCGAAAA"),
+        genome(b"ACGTTGCAACTCAGGATCCGCCCC"),
+    ];
+    let sequences: Vec<_> = sequences
+        .iter()
+        .map(|sequence| sequence.as_genome_subsequence())
+        .collect();
+    assert_eq!(StarMsa::choose_centre(&sequences, 5, 100), 1);
+}
+
+#[test]
+fn aligned_rows_contain_their_sequences() {
+    let config = sample_config();
+    let aligner = AlignerBuilder::new(config)
+        .template_switch_min_length::<NoTemplateSwitchMinLengthStrategy>()
+        .build()
+        .unwrap();
+
+    let genomes = [
+        genome(REFERENCE.as_bytes()),
+        genome(QUERY.as_bytes()),
+        genome(b"ACGTTGCAACTCAGGATCCGTAACGTTCAGAACCATGCA"),
+        genome(b"ACGTTGCATCAGGATCCGTAACGTTCAGGAACCATGCATT"),
+    ];
+    let sequences: Vec<_> = ["centre", "template_switch", "deletion", "shifted"]
+        .into_iter()
+        .zip(genomes.iter().map(|genome| genome.as_genome_subsequence()))
+        .collect();
+    let msa = StarMsa::align(&aligner, &sequences, 0).unwrap();
+
+    let column_count = msa.column_count();
+    for (row, (_, sequence)) in msa.rows.iter().zip(&sequences) {
+        assert_eq!(row.text.len(), column_count, "{}", row.text);
+        let characters: String = row
+            .text
+            .chars()
+            .filter(|&character| character != '-')
+            .map(|character| character.to_ascii_uppercase())
+            .collect();
+        assert_eq!(characters, sequence.as_string());
+    }
+
+    let template_switches = &msa.rows[1].template_switches;
+    assert!(!template_switches.is_empty());
+    for template_switch in template_switches {
+        assert!(template_switch.columns.end <= column_count);
+        assert!(msa.rows[1].text[template_switch.columns.clone()]
+            .chars()
+            .any(|character| character.is_ascii_lowercase()));
+    }
+}
@@ -0,0 +1,294 @@
+//! Summaries of the template switches found in many alignments against the same reference.
+//!
+//! Template switches of different alignments are compared by their reference coordinates,
+//! since the queries differ between the alignments.
+//! Template switches at the same locus are counted together, which shows the recurrent template switch loci of the reference.
+
+use std::{
+    fmt::{Display, Formatter},
+    ops::Range,
+};
+
+use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        template_switch_distance::{
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+    },
+    preprocessing::CoordinateMap,
+    ts_normalisation::TemplateSwitchAnnotation,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The part of a template switch that lies in the reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TemplateSwitchLocus {
+    pub primary: TemplateSwitchPrimary,
+    pub secondary: TemplateSwitchSecondary,
+    pub strand: TemplateSwitchStrand,
+    /// The primary range if the reference is the primary, and the anti-primary range otherwise.
+    ///
+    /// Like the anti-primary range, this ends before it starts if the template switch jumps back in the reference.
+    pub reference_range: Range<usize>,
+    /// The secondary range if the secondary is the reference.
+    pub secondary_range: Option<Range<usize>>,
+}
+
+/// Template switches of many alignments that were clustered into the same locus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrentTemplateSwitchLocus {
+    /// The most frequent locus of the clustered template switches.
+    pub locus: TemplateSwitchLocus,
+    /// The number of clustered template switches.
+    pub count: usize,
+    /// The number of alignments containing at least one of the clustered template switches.
+    pub alignments: usize,
+    /// The names of the first alignments containing one of the clustered template switches.
+    pub examples: Vec<String>,
+}
+
+/// Collects the template switches of many alignments against the same reference and clusters them by locus.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSwitchSummariser {
+    /// Loci whose coordinates differ by at most this many characters are clustered together.
+    tolerance: usize,
+    /// The maximum number of example alignments per locus.
+    max_examples: usize,
+    alignment_names: Vec<String>,
+    /// The locus of each template switch and the index of its alignment.
+    loci: Vec<(TemplateSwitchLocus, usize)>,
+}
+
+impl TemplateSwitchLocus {
+    pub fn new(annotation: &TemplateSwitchAnnotation) -> Self {
+        Self {
+            primary: annotation.primary,
+            secondary: annotation.secondary,
+            strand: annotation.strand,
+            reference_range: match annotation.primary {
+                TemplateSwitchPrimary::Reference => annotation.primary_range.clone(),
+                TemplateSwitchPrimary::Query => annotation.anti_primary_range.clone(),
+            },
+            secondary_range: (annotation.secondary == TemplateSwitchSecondary::Reference)
+                .then(|| annotation.secondary_range.clone()),
+        }
+    }
+
+    /// The coordinates compared when clustering.
+    fn coordinates(&self) -> [usize; 4] {
+        let secondary_range = self.secondary_range.clone().unwrap_or(0..0);
+        [
+            self.reference_range.start,
+            self.reference_range.end,
+            secondary_range.start,
+            secondary_range.end,
+        ]
+    }
+
+    fn sort_key(
+        &self,
+    ) -> (
+        TemplateSwitchPrimary,
+        TemplateSwitchSecondary,
+        TemplateSwitchStrand,
+        [usize; 4],
+    ) {
+        (
+            self.primary,
+            self.secondary,
+            self.strand,
+            self.coordinates(),
+        )
+    }
+
+    /// Returns true if both loci have the same primary, secondary and strand, and their coordinates differ by at most `tolerance`.
+    fn is_close(&self, other: &Self, tolerance: usize) -> bool {
+        self.primary == other.primary
+            && self.secondary == other.secondary
+            && self.strand == other.strand
+            && self
+                .coordinates()
+                .into_iter()
+                .zip(other.coordinates())
+                .all(|(a, b)| a.abs_diff(b) <= tolerance)
+    }
+}
+
+impl TemplateSwitchSummariser {
+    /// Creates a summariser that clusters loci whose coordinates differ by at most `tolerance`,
+    /// and keeps up to `max_examples` example alignments per locus.
+    pub fn new(tolerance: usize, max_examples: usize) -> Self {
+        Self {
+            tolerance,
+            max_examples,
+            ..Default::default()
+        }
+    }
+
+    /// Adds the template switches of an alignment with the given name.
+    pub fn add_template_switches(
+        &mut self,
+        name: &str,
+        template_switches: impl IntoIterator<Item = TemplateSwitchAnnotation>,
+    ) {
+        let alignment_index = self.alignment_names.len();
+        self.alignment_names.push(name.to_string());
+        self.loci.extend(
+            template_switches
+                .into_iter()
+                .map(|annotation| (TemplateSwitchLocus::new(&annotation), alignment_index)),
+        );
+    }
+
+    /// Adds the template switches of an alignment result with the given name.
+    ///
+    /// If the aligned sequences are given, then the template switches are canonicalised,
+    /// such that equivalent template switches of different alignments get the same locus.
+    /// If the sequences were preprocessed before aligning, as recorded in the provenance of the result,
+    /// then the template switches are mapped back to the coordinates before preprocessing.
+    /// Results without alignment are counted as alignments without template switches.
+    pub fn add_alignment<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        name: &str,
+        alignment: &AlignmentResult<AlignmentType>,
+        sequences: Option<(&SubsequenceType, &SubsequenceType)>,
+    ) {
+        let AlignmentResult::WithTarget {
+            alignment,
+            statistics,
+        } = alignment
+        else {
+            self.add_template_switches(name, []);
+            return;
+        };
+
+        let template_switches = match sequences {
+            Some((reference, query)) => {
+                TemplateSwitchAnnotation::canonical_from_alignment(alignment, reference, query)
+            }
+            None => TemplateSwitchAnnotation::from_alignment(alignment),
+        };
+        let template_switches: Vec<_> = match statistics.provenance.as_ref().map(|provenance| {
+            [&provenance.reference, &provenance.query].map(|sequence| {
+                sequence
+                    .coordinate_map
+                    .clone()
+                    .unwrap_or_else(|| CoordinateMap::identity(sequence.length))
+            })
+        }) {
+            Some([reference_map, query_map])
+                if !reference_map.is_identity() || !query_map.is_identity() =>
+            {
+                template_switches
+                    .iter()
+                    .map(|template_switch| {
+                        template_switch.to_original_coordinates(&reference_map, &query_map)
+                    })
+                    .collect()
+            }
+            _ => template_switches,
+        };
+
+        self.add_template_switches(name, template_switches);
+    }
+
+    /// The number of alignments added.
+    pub fn alignment_count(&self) -> usize {
+        self.alignment_names.len()
+    }
+
+    /// Clusters the template switches by locus, and returns the clusters ordered by decreasing number of alignments.
+    ///
+    /// Loci are sorted by their primary, secondary, strand and coordinates, and each locus joins the cluster before it
+    /// if its coordinates differ from the first locus of that cluster by at most the tolerance.
+    pub fn summarise(&self) -> Vec<RecurrentTemplateSwitchLocus> {
+        let mut loci: Vec<_> = self.loci.iter().collect();
+        loci.sort_by_key(|(locus, alignment_index)| (locus.sort_key(), *alignment_index));
+
+        let mut clusters: Vec<Vec<&(TemplateSwitchLocus, usize)>> = Vec::new();
+        for entry in loci {
+            match clusters.last_mut() {
+                Some(cluster) if cluster[0].0.is_close(&entry.0, self.tolerance) => {
+                    cluster.push(entry)
+                }
+                _ => clusters.push(vec![entry]),
+            }
+        }
+
+        let mut summary: Vec<_> = clusters
+            .into_iter()
+            .map(|cluster| {
+                let mut alignment_indices: Vec<_> = cluster
+                    .iter()
+                    .map(|(_, alignment_index)| *alignment_index)
+                    .collect();
+                alignment_indices.sort_unstable();
+                alignment_indices.dedup();
+
+                // The cluster is sorted by locus, so equal loci are adjacent.
+                let mut locus = &cluster[0].0;
+                let mut locus_count = 0;
+                let mut current_count = 0;
+                for (index, (current, _)) in cluster.iter().enumerate() {
+                    if index > 0 && *current != cluster[index - 1].0 {
+                        current_count = 0;
+                    }
+                    current_count += 1;
+                    if current_count > locus_count {
+                        locus = current;
+                        locus_count = current_count;
+                    }
+                }
+
+                RecurrentTemplateSwitchLocus {
+                    locus: locus.clone(),
+                    count: cluster.len(),
+                    alignments: alignment_indices.len(),
+                    examples: alignment_indices
+                        .iter()
+                        .take(self.max_examples)
+                        .map(|&alignment_index| self.alignment_names[alignment_index].clone())
+                        .collect(),
+                }
+            })
+            .collect();
+        summary.sort_by(|a, b| {
+            b.alignments
+                .cmp(&a.alignments)
+                .then(b.count.cmp(&a.count))
+                .then_with(|| a.locus.sort_key().cmp(&b.locus.sort_key()))
+        });
+
+        summary
+    }
+}
+
+impl Display for TemplateSwitchLocus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TS{}{}{} reference {}..{}",
+            self.primary,
+            self.secondary,
+            self.strand,
+            self.reference_range.start,
+            self.reference_range.end,
+        )?;
+        if let Some(secondary_range) = &self.secondary_range {
+            write!(
+                f,
+                ", secondary {}..{}",
+                secondary_range.start, secondary_range.end
+            )?;
+        }
+        Ok(())
+    }
+}
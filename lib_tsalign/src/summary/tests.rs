@@ -0,0 +1,164 @@
+use compact_genome::{
+    implementation::{
+        alphabets::dna_alphabet_or_n::DnaAlphabetOrN,
+        vec_sequence::{SliceSubGenome, VectorGenome},
+    },
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        template_switch_distance::{
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+    },
+    test_fixtures::{QUERY, REFERENCE},
+    ts_normalisation::TemplateSwitchAnnotation,
+};
+
+use super::{RecurrentTemplateSwitchLocus, TemplateSwitchLocus, TemplateSwitchSummariser};
+
+fn annotation(
+    primary: TemplateSwitchPrimary,
+    reference_start: usize,
+    secondary_start: usize,
+) -> TemplateSwitchAnnotation {
+    TemplateSwitchAnnotation {
+        primary,
+        secondary: TemplateSwitchSecondary::Reference,
+        strand: TemplateSwitchStrand::ReverseComplement,
+        primary_range: 10..14,
+        anti_primary_range: reference_start..reference_start + 4,
+        secondary_range: secondary_start..secondary_start + 4,
+    }
+}
+
+fn locus(reference_start: usize, secondary_start: usize) -> TemplateSwitchLocus {
+    TemplateSwitchLocus {
+        primary: TemplateSwitchPrimary::Query,
+        secondary: TemplateSwitchSecondary::Reference,
+        strand: TemplateSwitchStrand::ReverseComplement,
+        reference_range: reference_start..reference_start + 4,
+        secondary_range: Some(secondary_start..secondary_start + 4),
+    }
+}
+
+#[test]
+fn recurrent_loci_are_counted() {
+    let mut summariser = TemplateSwitchSummariser::new(0, 2);
+    summariser.add_template_switches(
+        "a",
+        [
+            annotation(TemplateSwitchPrimary::Query, 20, 40),
+            annotation(TemplateSwitchPrimary::Query, 60, 80),
+        ],
+    );
+    summariser.add_template_switches("b", [annotation(TemplateSwitchPrimary::Query, 20, 40)]);
+    summariser.add_template_switches("c", []);
+    summariser.add_template_switches("d", [annotation(TemplateSwitchPrimary::Query, 20, 40)]);
+    assert_eq!(summariser.alignment_count(), 4);
+
+    assert_eq!(
+        summariser.summarise(),
+        [
+            RecurrentTemplateSwitchLocus {
+                locus: locus(20, 40),
+                count: 3,
+                alignments: 3,
+                examples: vec!["a".to_string(), "b".to_string()],
+            },
+            RecurrentTemplateSwitchLocus {
+                locus: locus(60, 80),
+                count: 1,
+                alignments: 1,
+                examples: vec!["a".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn close_loci_are_clustered() {
+    let mut summariser = TemplateSwitchSummariser::new(2, 10);
+    summariser.add_template_switches("a", [annotation(TemplateSwitchPrimary::Query, 20, 40)]);
+    summariser.add_template_switches("b", [annotation(TemplateSwitchPrimary::Query, 21, 41)]);
+    summariser.add_template_switches("c", [annotation(TemplateSwitchPrimary::Query, 21, 41)]);
+    // Too far from the first locus of the cluster.
+    summariser.add_template_switches("d", [annotation(TemplateSwitchPrimary::Query, 23, 43)]);
+    // A different primary is never clustered together.
+    summariser.add_template_switches("e", [annotation(TemplateSwitchPrimary::Reference, 20, 40)]);
+
+    let summary = summariser.summarise();
+    assert_eq!(summary.len(), 3);
+    assert_eq!(summary[0].locus, locus(21, 41));
+    assert_eq!(summary[0].count, 3);
+    assert_eq!(summary[0].examples, ["a", "b", "c"]);
+    assert_eq!(summary[1].locus.primary, TemplateSwitchPrimary::Reference);
+    assert_eq!(summary[1].locus.reference_range, 10..14);
+    assert_eq!(summary[2].locus, locus(23, 43));
+}
+
+/// The query contains the reverse complement of the middle of the reference, see the tests of [`TemplateSwitchAnnotation::canonicalise`].
+fn inversion(first_offset: isize, length: usize, suffix: usize) -> AlignmentResult<AlignmentType> {
+    AlignmentResult::WithTarget {
+        alignment: vec![
+            (40 - length - suffix, AlignmentType::PrimaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Query,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::ReverseComplement,
+                    first_offset,
+                },
+            ),
+            (length, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: 0,
+                },
+            ),
+            (suffix, AlignmentType::PrimaryMatch),
+        ],
+        statistics: Default::default(),
+    }
+}
+
+#[test]
+fn alignments_are_canonicalised() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+
+    // Both alignments contain the same template switch, but the second one enters it one character earlier.
+    let mut summariser = TemplateSwitchSummariser::new(0, 10);
+    for (name, alignment) in [
+        ("late", inversion(20, 20, 10)),
+        ("early", inversion(22, 21, 10)),
+    ] {
+        summariser.add_alignment(
+            name,
+            &alignment,
+            Some((
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+            )),
+        );
+    }
+    // Without the sequences, the template switches are not canonicalised.
+    summariser.add_alignment::<DnaAlphabetOrN, _>(
+        "uncanonicalised",
+        &inversion(20, 20, 10),
+        None::<(&SliceSubGenome<DnaAlphabetOrN>, _)>,
+    );
+
+    let summary = summariser.summarise();
+    assert_eq!(summary.len(), 2, "{summary:?}");
+    assert_eq!(summary[0].examples, ["late", "early"]);
+    assert_eq!(
+        summary[0].locus.to_string(),
+        "TSQR reference 9..30, secondary 10..31"
+    );
+    assert_eq!(summary[1].examples, ["uncanonicalised"]);
+}
@@ -0,0 +1,83 @@
+//! Inputs that are shared by the tests of multiple modules.
+
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::AlignmentResult,
+        template_switch_distance::{
+            strategies::{
+                chaining::NoChainingStrategy, node_ord::AntiDiagonalNodeOrdStrategy,
+                primary_match::AllowPrimaryMatchStrategy,
+                secondary_deletion::AllowSecondaryDeletionStrategy, shortcut::NoShortcutStrategy,
+                template_switch_count::NoTemplateSwitchCountStrategy,
+                template_switch_min_length::NoTemplateSwitchMinLengthStrategy,
+                AlignmentStrategySelection,
+            },
+            AlignmentType,
+        },
+        template_switch_distance_a_star_align, SearchSettings,
+    },
+    config::TemplateSwitchConfig,
+};
+
+/// The sample config of the repository in plain text.
+pub const SAMPLE_CONFIG: &str = include_str!("../../sample_tsa_config/config.tsa");
+
+/// The query contains the reverse complement of the middle of the reference.
+///
+/// The last character before the inversion is the complement of the first character after it,
+/// so the inversion can also start one character earlier.
+pub const REFERENCE: &str = "ACGTTGCAACTCAGGATCCGTAACGTTCAGGAACCATGCA";
+pub const QUERY: &str = "ACGTTGCAACCTGAACGTTACGGATCCTGAGAACCATGCA";
+
+/// Parses the [`SAMPLE_CONFIG`].
+///
+/// The sample config contains costs for `N`, so it is parsed with an alphabet that contains it.
+pub fn sample_config() -> TemplateSwitchConfig<DnaAlphabetOrN> {
+    TemplateSwitchConfig::read_plain(SAMPLE_CONFIG.as_bytes()).unwrap()
+}
+
+/// The strategies of the template switch aligner used by the tests, without chaining or lookahead.
+pub type TestStrategies = AlignmentStrategySelection<
+    DnaAlphabetOrN,
+    AntiDiagonalNodeOrdStrategy,
+    NoTemplateSwitchMinLengthStrategy,
+    NoChainingStrategy,
+    NoTemplateSwitchCountStrategy,
+    AllowSecondaryDeletionStrategy,
+    NoShortcutStrategy,
+    AllowPrimaryMatchStrategy,
+>;
+
+/// Aligns `reference` and `query` with the [`TestStrategies`] and the default search settings.
+pub fn align(
+    reference: &[u8],
+    query: &[u8],
+    config: TemplateSwitchConfig<DnaAlphabetOrN>,
+) -> AlignmentResult<AlignmentType> {
+    align_with_settings(reference, query, config, &SearchSettings::default())
+}
+
+/// Like [`align`], but with the given search settings.
+pub fn align_with_settings(
+    reference: &[u8],
+    query: &[u8],
+    config: TemplateSwitchConfig<DnaAlphabetOrN>,
+    settings: &SearchSettings,
+) -> AlignmentResult<AlignmentType> {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(reference).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(query).unwrap();
+    template_switch_distance_a_star_align::<TestStrategies, _>(
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+        config,
+        (),
+        None,
+        settings,
+    )
+    .unwrap()
+}
@@ -0,0 +1,563 @@
+use std::{
+    fmt::{Display, Formatter},
+    ops::Range,
+};
+
+use compact_genome::interface::{
+    alphabet::{Alphabet, AlphabetCharacter},
+    sequence::GenomeSequence,
+};
+use noisy_float::types::R64;
+use num_traits::Zero;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::{AlignmentColumn, AlignmentRegion, AlignmentResult, IAlignmentType},
+        template_switch_distance::{
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+    },
+    preprocessing::CoordinateMap,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A template switch in absolute coordinates of the reference and the query.
+///
+/// The primary is the sequence whose characters are aligned to the secondary,
+/// and the anti-primary is the other one of reference and query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TemplateSwitchAnnotation {
+    pub primary: TemplateSwitchPrimary,
+    pub secondary: TemplateSwitchSecondary,
+    /// Annotations without a strand copy the reverse complement.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strand: TemplateSwitchStrand,
+    /// The characters of the primary that are aligned to the secondary.
+    pub primary_range: Range<usize>,
+    /// The characters of the anti-primary that are skipped by the template switch.
+    ///
+    /// This ends before it starts if the template switch jumps back in the anti-primary.
+    pub anti_primary_range: Range<usize>,
+    /// The characters of the secondary that are aligned to the primary.
+    ///
+    /// If the template switch copies the reverse complement, the secondary is traversed backwards,
+    /// so the first primary character is aligned to the last secondary character.
+    pub secondary_range: Range<usize>,
+}
+
+/// The part of the secondary that a template switch copies into the primary.
+///
+/// Template switches copy either the reverse complement of their donor or, on the forward strand, the donor itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSwitchDonor {
+    /// The coordinates of the template switch, with the donor at the secondary range of the secondary.
+    pub annotation: TemplateSwitchAnnotation,
+    /// The donor characters as they appear in the secondary.
+    pub sequence: String,
+    /// The donor in the order and on the strand in which it is aligned to the primary.
+    pub aligned_sequence: String,
+    /// The fraction of the columns of the alignment between the primary and the donor that are matches.
+    pub identity: R64,
+}
+
+/// The properties shared by all annotations that describe the same template switch event.
+///
+/// Moving the entrance or the exit of a template switch changes its offset, length and the ranges it covers,
+/// but neither the alignment around it nor which primary and secondary characters are aligned to each other.
+/// Annotations are equivalent if their equivalence classes are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TemplateSwitchEquivalenceClass {
+    pub primary: TemplateSwitchPrimary,
+    pub secondary: TemplateSwitchSecondary,
+    pub strand: TemplateSwitchStrand,
+    /// The anti-primary index minus the primary index at the entrance.
+    pub entrance_diagonal: isize,
+    /// The anti-primary index minus the primary index at the exit.
+    pub exit_diagonal: isize,
+    /// The primary index plus the secondary index at the entrance,
+    /// or the secondary index minus the primary index on the forward strand.
+    pub entrance_anchor: isize,
+    /// The primary index plus the secondary index at the exit,
+    /// or the secondary index minus the primary index on the forward strand.
+    pub exit_anchor: isize,
+}
+
+/// Counts of template switches found by an aligner compared to a ground truth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TemplateSwitchEvaluation {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl TemplateSwitchAnnotation {
+    /// Extracts the template switches from an alignment of `reference` and `query`.
+    pub fn from_alignment(alignment: &[(usize, AlignmentType)]) -> Vec<Self> {
+        let mut annotations = Vec::new();
+        let mut reference_index = 0;
+        let mut query_index = 0;
+        let mut current: Option<Self> = None;
+
+        for (count, alignment_type) in alignment {
+            let count = *count;
+            match alignment_type {
+                AlignmentType::PrimaryMatch
+                | AlignmentType::PrimarySubstitution
+                | AlignmentType::PrimaryFlankMatch
+                | AlignmentType::PrimaryFlankSubstitution => {
+                    reference_index += count;
+                    query_index += count;
+                }
+                AlignmentType::PrimaryMatchRun { length } => {
+                    reference_index += count * length;
+                    query_index += count * length;
+                }
+                AlignmentType::PrimaryInsertion | AlignmentType::PrimaryFlankInsertion => {
+                    query_index += count;
+                }
+                AlignmentType::PrimaryDeletion | AlignmentType::PrimaryFlankDeletion => {
+                    reference_index += count;
+                }
+                &AlignmentType::TemplateSwitchEntrance {
+                    primary,
+                    secondary,
+                    strand,
+                    first_offset,
+                } => {
+                    let (primary_index, anti_primary_index) = match primary {
+                        TemplateSwitchPrimary::Reference => (reference_index, query_index),
+                        TemplateSwitchPrimary::Query => (query_index, reference_index),
+                    };
+                    let secondary_index = (match secondary {
+                        TemplateSwitchSecondary::Reference => reference_index,
+                        TemplateSwitchSecondary::Query => query_index,
+                    } as isize
+                        + first_offset) as usize;
+
+                    current = Some(Self {
+                        primary,
+                        secondary,
+                        strand,
+                        primary_range: primary_index..primary_index,
+                        anti_primary_range: anti_primary_index..anti_primary_index,
+                        secondary_range: secondary_index..secondary_index,
+                    });
+                }
+                AlignmentType::SecondaryMatch
+                | AlignmentType::SecondarySubstitution
+                | AlignmentType::SecondaryMatchRun { .. }
+                | AlignmentType::SecondaryInsertion
+                | AlignmentType::SecondaryDeletion => {
+                    let annotation = current
+                        .as_mut()
+                        .expect("secondary alignment outside of a template switch");
+                    let (primary_count, secondary_count) = match alignment_type {
+                        AlignmentType::SecondaryMatchRun { length } => {
+                            (count * length, count * length)
+                        }
+                        AlignmentType::SecondaryInsertion => (count, 0),
+                        AlignmentType::SecondaryDeletion => (0, count),
+                        _ => (count, count),
+                    };
+                    annotation.primary_range.end += primary_count;
+                    match annotation.strand {
+                        TemplateSwitchStrand::ReverseComplement => {
+                            annotation.secondary_range.start -= secondary_count
+                        }
+                        TemplateSwitchStrand::Forward => {
+                            annotation.secondary_range.end += secondary_count
+                        }
+                    }
+                }
+                &AlignmentType::TemplateSwitchExit { length_difference } => {
+                    let mut annotation = current
+                        .take()
+                        .expect("template switch exit without entrance");
+                    annotation.anti_primary_range.end =
+                        (annotation.anti_primary_range.start as isize
+                            + annotation.primary_range.len() as isize
+                            + length_difference) as usize;
+                    (reference_index, query_index) = match annotation.primary {
+                        TemplateSwitchPrimary::Reference => (
+                            annotation.primary_range.end,
+                            annotation.anti_primary_range.end,
+                        ),
+                        TemplateSwitchPrimary::Query => (
+                            annotation.anti_primary_range.end,
+                            annotation.primary_range.end,
+                        ),
+                    };
+                    annotations.push(annotation);
+                }
+                AlignmentType::PrimaryDuplication { length } => {
+                    reference_index -= count * length;
+                }
+                AlignmentType::InversionMatch | AlignmentType::InversionSubstitution => {
+                    reference_index += count;
+                    query_index += count;
+                }
+                AlignmentType::Root
+                | AlignmentType::SecondaryRoot
+                | AlignmentType::PrimaryReentry
+                | AlignmentType::PrimaryShortcut { .. }
+                | AlignmentType::InversionEntrance { .. }
+                | AlignmentType::InversionExit => {}
+            }
+        }
+
+        annotations
+    }
+
+    pub fn equivalence_class(&self) -> TemplateSwitchEquivalenceClass {
+        let (entrance_anchor, exit_anchor) = match self.strand {
+            TemplateSwitchStrand::ReverseComplement => (
+                (self.primary_range.start + self.secondary_range.end) as isize,
+                (self.primary_range.end + self.secondary_range.start) as isize,
+            ),
+            TemplateSwitchStrand::Forward => (
+                self.secondary_range.start as isize - self.primary_range.start as isize,
+                self.secondary_range.end as isize - self.primary_range.end as isize,
+            ),
+        };
+
+        TemplateSwitchEquivalenceClass {
+            primary: self.primary,
+            secondary: self.secondary,
+            strand: self.strand,
+            entrance_diagonal: self.anti_primary_range.start as isize
+                - self.primary_range.start as isize,
+            exit_diagonal: self.anti_primary_range.end as isize - self.primary_range.end as isize,
+            entrance_anchor,
+            exit_anchor,
+        }
+    }
+
+    /// Returns true if both annotations describe the same template switch event.
+    pub fn is_equivalent(&self, other: &Self) -> bool {
+        self.equivalence_class() == other.equivalence_class()
+    }
+
+    /// Moves the entrance and the exit to the left while this does not change the number of mismatches.
+    ///
+    /// Like left-aligning indels, this makes equivalent annotations of the same event equal,
+    /// as long as they differ only by moving the entrance or the exit over matching characters.
+    /// The entrance moves left if the primary character before it matches both the anti-primary character before it
+    /// and the complement of the secondary character after the secondary range.
+    /// The exit moves left if the last primary character matches both the anti-primary character before the exit
+    /// and the complement of the first secondary character.
+    /// On the forward strand, the secondary characters before the secondary range and the last secondary character
+    /// are compared instead, without complementing them.
+    pub fn canonicalise<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Self {
+        let (primary, anti_primary) = match self.primary {
+            TemplateSwitchPrimary::Reference => (reference, query),
+            TemplateSwitchPrimary::Query => (query, reference),
+        };
+        let secondary = match self.secondary {
+            TemplateSwitchSecondary::Reference => reference,
+            TemplateSwitchSecondary::Query => query,
+        };
+        let mut result = self.clone();
+        if self.strand == TemplateSwitchStrand::Forward {
+            result.canonicalise_forward(primary, anti_primary, secondary);
+            return result;
+        }
+
+        while result.primary_range.start > 0
+            && result.anti_primary_range.start > 0
+            && result.secondary_range.end < secondary.len()
+        {
+            let p = primary[result.primary_range.start - 1].clone();
+            if p != anti_primary[result.anti_primary_range.start - 1]
+                || p != secondary[result.secondary_range.end].complement()
+            {
+                break;
+            }
+
+            result.primary_range.start -= 1;
+            result.anti_primary_range.start -= 1;
+            result.secondary_range.end += 1;
+        }
+
+        while result.primary_range.len() > 1
+            && result.anti_primary_range.end > 0
+            && result.secondary_range.start < result.secondary_range.end
+        {
+            let p = primary[result.primary_range.end - 1].clone();
+            if p != anti_primary[result.anti_primary_range.end - 1]
+                || p != secondary[result.secondary_range.start].complement()
+            {
+                break;
+            }
+
+            result.primary_range.end -= 1;
+            result.anti_primary_range.end -= 1;
+            result.secondary_range.start += 1;
+        }
+
+        result
+    }
+
+    /// Like [`canonicalise`](Self::canonicalise), but for template switches that copy the forward strand.
+    fn canonicalise_forward<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &mut self,
+        primary: &SubsequenceType,
+        anti_primary: &SubsequenceType,
+        secondary: &SubsequenceType,
+    ) {
+        while self.primary_range.start > 0
+            && self.anti_primary_range.start > 0
+            && self.secondary_range.start > 0
+        {
+            let p = &primary[self.primary_range.start - 1];
+            if p != &anti_primary[self.anti_primary_range.start - 1]
+                || p != &secondary[self.secondary_range.start - 1]
+            {
+                break;
+            }
+
+            self.primary_range.start -= 1;
+            self.anti_primary_range.start -= 1;
+            self.secondary_range.start -= 1;
+        }
+
+        while self.primary_range.len() > 1
+            && self.anti_primary_range.end > 0
+            && self.secondary_range.start < self.secondary_range.end
+        {
+            let p = &primary[self.primary_range.end - 1];
+            if p != &anti_primary[self.anti_primary_range.end - 1]
+                || p != &secondary[self.secondary_range.end - 1]
+            {
+                break;
+            }
+
+            self.primary_range.end -= 1;
+            self.anti_primary_range.end -= 1;
+            self.secondary_range.end -= 1;
+        }
+    }
+
+    /// Extracts the template switches from an alignment and canonicalises them.
+    pub fn canonical_from_alignment<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        alignment: &[(usize, AlignmentType)],
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Vec<Self> {
+        Self::from_alignment(alignment)
+            .into_iter()
+            .map(|annotation| annotation.canonicalise(reference, query))
+            .collect()
+    }
+
+    /// Maps the ranges of this template switch from preprocessed sequences back to the original sequences.
+    ///
+    /// Ranges that span characters removed by preprocessing are widened to include them.
+    pub fn to_original_coordinates(
+        &self,
+        reference_map: &CoordinateMap,
+        query_map: &CoordinateMap,
+    ) -> Self {
+        let (primary_map, anti_primary_map) = match self.primary {
+            TemplateSwitchPrimary::Reference => (reference_map, query_map),
+            TemplateSwitchPrimary::Query => (query_map, reference_map),
+        };
+        let secondary_map = match self.secondary {
+            TemplateSwitchSecondary::Reference => reference_map,
+            TemplateSwitchSecondary::Query => query_map,
+        };
+
+        // The anti-primary range is reversed if the template switch jumps back.
+        let anti_primary_range = if self.anti_primary_range.start <= self.anti_primary_range.end {
+            anti_primary_map.original_range(self.anti_primary_range.clone())
+        } else {
+            let range = anti_primary_map
+                .original_range(self.anti_primary_range.end..self.anti_primary_range.start);
+            range.end..range.start
+        };
+
+        Self {
+            primary: self.primary,
+            secondary: self.secondary,
+            strand: self.strand,
+            primary_range: primary_map.original_range(self.primary_range.clone()),
+            anti_primary_range,
+            secondary_range: secondary_map.original_range(self.secondary_range.clone()),
+        }
+    }
+}
+
+impl AlignmentResult<AlignmentType> {
+    /// Returns the donor of each template switch in the alignment of `reference` and `query`, in alignment order.
+    ///
+    /// Returns no donors if the result has no alignment.
+    pub fn template_switch_donors<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        &self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Vec<TemplateSwitchDonor> {
+        let Self::WithTarget { alignment, .. } = self else {
+            return Vec::new();
+        };
+
+        // The matches and the total number of columns inside each template switch.
+        let mut column_counts = Vec::new();
+        let mut is_template_switch = false;
+        for (count, alignment_type) in alignment {
+            match alignment_type {
+                AlignmentType::TemplateSwitchEntrance { .. } => {
+                    if !is_template_switch {
+                        column_counts.push((0, 0));
+                    }
+                    is_template_switch = true;
+                }
+                AlignmentType::TemplateSwitchExit { .. } => is_template_switch = false,
+                _ => {}
+            }
+
+            let (count, column) = match alignment_type.as_run() {
+                Some((length, alignment_type)) => {
+                    (count * length, alignment_type.alignment_column())
+                }
+                None => (*count, alignment_type.alignment_column()),
+            };
+            if let (Some((AlignmentRegion::TemplateSwitch, column)), Some((matches, columns))) =
+                (column, column_counts.last_mut())
+            {
+                if column == AlignmentColumn::Match {
+                    *matches += count;
+                }
+                *columns += count;
+            }
+        }
+
+        TemplateSwitchAnnotation::from_alignment(alignment)
+            .into_iter()
+            .zip(column_counts)
+            .map(|(annotation, (matches, columns))| {
+                let secondary = match annotation.secondary {
+                    TemplateSwitchSecondary::Reference => reference,
+                    TemplateSwitchSecondary::Query => query,
+                };
+                let donor = &secondary[annotation.secondary_range.clone()];
+
+                TemplateSwitchDonor {
+                    sequence: donor.as_string(),
+                    aligned_sequence: match annotation.strand {
+                        TemplateSwitchStrand::ReverseComplement => donor
+                            .reverse_complement_iter()
+                            .map(|character| {
+                                char::from(AlphabetType::character_to_ascii(character))
+                            })
+                            .collect(),
+                        TemplateSwitchStrand::Forward => donor.as_string(),
+                    },
+                    identity: if columns == 0 {
+                        R64::zero()
+                    } else {
+                        R64::new(matches as f64 / columns as f64)
+                    },
+                    annotation,
+                }
+            })
+            .collect()
+    }
+}
+
+impl TemplateSwitchEvaluation {
+    /// Compares predicted template switches to a ground truth.
+    ///
+    /// Both are canonicalised first, and a predicted template switch is a true positive if it is equal to a
+    /// ground truth template switch that was not yet matched.
+    pub fn new<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        predicted: &[TemplateSwitchAnnotation],
+        truth: &[TemplateSwitchAnnotation],
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+    ) -> Self {
+        let mut unmatched_truth: Vec<_> = truth
+            .iter()
+            .map(|annotation| annotation.canonicalise(reference, query))
+            .collect();
+        let mut result = Self::default();
+
+        for annotation in predicted {
+            let annotation = annotation.canonicalise(reference, query);
+            if let Some(index) = unmatched_truth
+                .iter()
+                .position(|truth| truth == &annotation)
+            {
+                unmatched_truth.swap_remove(index);
+                result.true_positives += 1;
+            } else {
+                result.false_positives += 1;
+            }
+        }
+        result.false_negatives = unmatched_truth.len();
+
+        result
+    }
+
+    /// The fraction of predicted template switches that are in the ground truth.
+    ///
+    /// Returns one if nothing was predicted.
+    pub fn precision(&self) -> f64 {
+        let predicted = self.true_positives + self.false_positives;
+        if predicted == 0 {
+            1.0
+        } else {
+            self.true_positives as f64 / predicted as f64
+        }
+    }
+
+    /// The fraction of ground truth template switches that were predicted.
+    ///
+    /// Returns one if the ground truth is empty.
+    pub fn recall(&self) -> f64 {
+        let truth = self.true_positives + self.false_negatives;
+        if truth == 0 {
+            1.0
+        } else {
+            self.true_positives as f64 / truth as f64
+        }
+    }
+}
+
+impl Display for TemplateSwitchAnnotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TS{}{}{} primary {}..{}, anti-primary {}..{}, secondary {}..{}",
+            self.primary,
+            self.secondary,
+            self.strand,
+            self.primary_range.start,
+            self.primary_range.end,
+            self.anti_primary_range.start,
+            self.anti_primary_range.end,
+            self.secondary_range.start,
+            self.secondary_range.end,
+        )
+    }
+}
@@ -0,0 +1,339 @@
+use compact_genome::{
+    implementation::{alphabets::dna_alphabet_or_n::DnaAlphabetOrN, vec_sequence::VectorGenome},
+    interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+};
+
+use noisy_float::types::R64;
+
+use crate::{
+    a_star_aligner::{
+        alignment_result::{AlignmentResult, AlignmentStatistics},
+        template_switch_distance::{
+            AlignmentType, TemplateSwitchPrimary, TemplateSwitchSecondary, TemplateSwitchStrand,
+        },
+    },
+    preprocessing::{CoordinateMap, PreprocessingSettings},
+    test_fixtures::{QUERY, REFERENCE},
+};
+
+use super::{TemplateSwitchAnnotation, TemplateSwitchDonor, TemplateSwitchEvaluation};
+
+fn inversion(first_offset: isize, length: usize, suffix: usize) -> Vec<(usize, AlignmentType)> {
+    vec![
+        (40 - length - suffix, AlignmentType::PrimaryMatch),
+        (
+            first_offset.unsigned_abs() + 1,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset,
+            },
+        ),
+        (length, AlignmentType::SecondaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 0,
+            },
+        ),
+        (suffix, AlignmentType::PrimaryMatch),
+    ]
+}
+
+fn annotation(
+    primary_range: std::ops::Range<usize>,
+    anti_primary_range: std::ops::Range<usize>,
+    secondary_range: std::ops::Range<usize>,
+) -> TemplateSwitchAnnotation {
+    TemplateSwitchAnnotation {
+        primary: TemplateSwitchPrimary::Query,
+        secondary: TemplateSwitchSecondary::Reference,
+        strand: TemplateSwitchStrand::ReverseComplement,
+        primary_range,
+        anti_primary_range,
+        secondary_range,
+    }
+}
+
+fn canonical(alignment: &[(usize, AlignmentType)]) -> Vec<TemplateSwitchAnnotation> {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    TemplateSwitchAnnotation::canonical_from_alignment(
+        alignment,
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+    )
+}
+
+#[test]
+fn annotations_from_alignment() {
+    assert_eq!(
+        TemplateSwitchAnnotation::from_alignment(&inversion(20, 20, 10)),
+        vec![annotation(10..30, 10..30, 10..30)]
+    );
+    assert_eq!(
+        TemplateSwitchAnnotation::from_alignment(&inversion(22, 21, 10)),
+        vec![annotation(9..30, 9..30, 10..31)]
+    );
+
+    let alignment = vec![
+        (5, AlignmentType::PrimaryMatch),
+        (1, AlignmentType::PrimaryInsertion),
+        (
+            3,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Reference,
+                secondary: TemplateSwitchSecondary::Query,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset: 2,
+            },
+        ),
+        (4, AlignmentType::SecondaryMatch),
+        (1, AlignmentType::SecondaryDeletion),
+        (2, AlignmentType::SecondaryInsertion),
+        (
+            4,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: -3,
+            },
+        ),
+        (2, AlignmentType::PrimaryDeletion),
+        (1, AlignmentType::PrimaryMatch),
+    ];
+    assert_eq!(
+        TemplateSwitchAnnotation::from_alignment(&alignment),
+        vec![TemplateSwitchAnnotation {
+            primary: TemplateSwitchPrimary::Reference,
+            secondary: TemplateSwitchSecondary::Query,
+            strand: TemplateSwitchStrand::ReverseComplement,
+            primary_range: 5..11,
+            anti_primary_range: 6..9,
+            secondary_range: 3..8,
+        }]
+    );
+    assert!(
+        TemplateSwitchAnnotation::from_alignment(&[(40, AlignmentType::PrimaryMatch)]).is_empty()
+    );
+}
+
+#[test]
+fn shifted_annotations_are_equivalent() {
+    let original = annotation(10..30, 10..30, 10..30);
+    assert!(original.is_equivalent(&annotation(9..30, 9..30, 10..31)));
+    assert!(original.is_equivalent(&annotation(10..31, 10..31, 9..30)));
+    assert!(!original.is_equivalent(&annotation(10..30, 10..30, 11..31)));
+    assert!(!original.is_equivalent(&annotation(10..30, 11..31, 10..30)));
+}
+
+#[test]
+fn donors() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let alignment = vec![
+        (10, AlignmentType::PrimaryMatch),
+        (
+            22,
+            AlignmentType::TemplateSwitchEntrance {
+                primary: TemplateSwitchPrimary::Query,
+                secondary: TemplateSwitchSecondary::Reference,
+                strand: TemplateSwitchStrand::ReverseComplement,
+                first_offset: 21,
+            },
+        ),
+        (1, AlignmentType::SecondaryInsertion),
+        (20, AlignmentType::SecondaryMatch),
+        (
+            1,
+            AlignmentType::TemplateSwitchExit {
+                length_difference: 0,
+            },
+        ),
+        (9, AlignmentType::PrimaryMatch),
+    ];
+    let result = AlignmentResult::WithTarget {
+        alignment,
+        statistics: AlignmentStatistics::zero(),
+    };
+
+    assert_eq!(
+        result.template_switch_donors(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence()
+        ),
+        vec![TemplateSwitchDonor {
+            annotation: annotation(10..31, 10..31, 11..31),
+            sequence: "CAGGATCCGTAACGTTCAGG".to_string(),
+            aligned_sequence: "CCTGAACGTTACGGATCCTG".to_string(),
+            identity: R64::new(20.0 / 21.0),
+        }]
+    );
+    assert_eq!(
+        AlignmentResult::<AlignmentType>::WithoutTarget {
+            reason: Default::default(),
+            statistics: AlignmentStatistics::zero(),
+        }
+        .template_switch_donors(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence()
+        ),
+        Vec::new()
+    );
+}
+
+#[test]
+fn equivalent_annotations_have_equal_canonical_forms() {
+    let expected = vec![annotation(9..30, 9..30, 10..31)];
+    assert_eq!(canonical(&inversion(20, 20, 10)), expected);
+    assert_eq!(canonical(&inversion(22, 21, 10)), expected);
+    assert_eq!(canonical(&inversion(20, 21, 9)), expected);
+    assert!(annotation(10..30, 10..30, 10..30).is_equivalent(&expected[0]));
+}
+
+#[test]
+fn evaluation() {
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(REFERENCE.as_bytes()).unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(QUERY.as_bytes()).unwrap();
+    let evaluate = |predicted: &[TemplateSwitchAnnotation], truth: &[TemplateSwitchAnnotation]| {
+        TemplateSwitchEvaluation::new(
+            predicted,
+            truth,
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+        )
+    };
+
+    let truth = [annotation(10..30, 10..30, 10..30)];
+    let evaluation = evaluate(&[annotation(10..31, 10..31, 9..30)], &truth);
+    assert_eq!(
+        evaluation,
+        TemplateSwitchEvaluation {
+            true_positives: 1,
+            false_positives: 0,
+            false_negatives: 0,
+        }
+    );
+    assert_eq!(evaluation.precision(), 1.0);
+    assert_eq!(evaluation.recall(), 1.0);
+
+    let evaluation = evaluate(
+        &[
+            annotation(10..30, 10..30, 10..30),
+            annotation(10..30, 10..30, 10..30),
+            annotation(12..20, 12..20, 20..28),
+        ],
+        &truth,
+    );
+    assert_eq!(
+        evaluation,
+        TemplateSwitchEvaluation {
+            true_positives: 1,
+            false_positives: 2,
+            false_negatives: 0,
+        }
+    );
+    assert_eq!(evaluation.precision(), 1.0 / 3.0);
+
+    let evaluation = evaluate(&[], &truth);
+    assert_eq!(evaluation.false_negatives, 1);
+    assert_eq!(evaluation.precision(), 1.0);
+    assert_eq!(evaluation.recall(), 0.0);
+}
+
+#[test]
+#[expect(clippy::reversed_empty_ranges)]
+fn to_original_coordinates() {
+    let reference_map = PreprocessingSettings {
+        max_n_run: Some(0),
+        ..Default::default()
+    }
+    .preprocess(b"NNNNACGTACGTNNACGT", None)
+    .coordinate_map;
+    let query_map = CoordinateMap::identity(12);
+
+    // The reference is the anti-primary and the secondary, and the backwards jump in the anti-primary stays reversed.
+    assert_eq!(
+        annotation(2..6, 6..4, 3..10).to_original_coordinates(&reference_map, &query_map),
+        annotation(2..6, 10..8, 7..16)
+    );
+    // The reference is the primary.
+    let mut reference_primary = annotation(2..6, 6..4, 3..10);
+    reference_primary.primary = TemplateSwitchPrimary::Reference;
+    reference_primary.secondary = TemplateSwitchSecondary::Query;
+    let mut expected = annotation(6..10, 6..4, 3..10);
+    expected.primary = TemplateSwitchPrimary::Reference;
+    expected.secondary = TemplateSwitchSecondary::Query;
+    assert_eq!(
+        reference_primary.to_original_coordinates(&reference_map, &query_map),
+        expected
+    );
+}
+
+#[test]
+fn forward_annotations_are_canonicalised() {
+    // The query copies the forward strand of the twelve reference characters before the replaced region,
+    // and the replaced region ends with the same character as the copy.
+    let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"ACGTTGCAACTGCAGGATCCGTATGTTACCAAGTGTAACCATGCATTA",
+    )
+    .unwrap();
+    let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(
+        b"ACGTTGCAACTGCAGGATCCGTATCAGGATCCGTATAACCATGCATTA",
+    )
+    .unwrap();
+    let alignment = |length: usize| {
+        vec![
+            (24, AlignmentType::PrimaryMatch),
+            (
+                13,
+                AlignmentType::TemplateSwitchEntrance {
+                    primary: TemplateSwitchPrimary::Query,
+                    secondary: TemplateSwitchSecondary::Reference,
+                    strand: TemplateSwitchStrand::Forward,
+                    first_offset: -12,
+                },
+            ),
+            (length, AlignmentType::SecondaryMatch),
+            (
+                1,
+                AlignmentType::TemplateSwitchExit {
+                    length_difference: 0,
+                },
+            ),
+            (24 - length, AlignmentType::PrimaryMatch),
+        ]
+    };
+    let forward = |primary_range, secondary_range| TemplateSwitchAnnotation {
+        strand: TemplateSwitchStrand::Forward,
+        ..annotation(primary_range, 24..36, secondary_range)
+    };
+
+    let annotations = TemplateSwitchAnnotation::from_alignment(&alignment(12));
+    assert_eq!(annotations, vec![forward(24..36, 12..24)]);
+    assert!(annotations[0].is_equivalent(&TemplateSwitchAnnotation {
+        anti_primary_range: 24..35,
+        ..forward(24..35, 12..23)
+    }));
+    assert!(!annotations[0].is_equivalent(&annotation(24..36, 24..36, 12..24)));
+
+    let canonical = TemplateSwitchAnnotation::canonical_from_alignment(
+        &alignment(12),
+        reference.as_genome_subsequence(),
+        query.as_genome_subsequence(),
+    );
+    assert_eq!(
+        canonical,
+        vec![TemplateSwitchAnnotation {
+            anti_primary_range: 24..35,
+            ..forward(24..35, 12..23)
+        }]
+    );
+    assert_eq!(
+        TemplateSwitchAnnotation::canonical_from_alignment(
+            &alignment(11),
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+        ),
+        canonical
+    );
+}
@@ -1,19 +1,27 @@
 use context::Context;
-use generic_a_star::{cost::Cost, AStar};
+use generic_a_star::{cost::Cost, phases::SearchOutcome, AStar};
 use log::info;
 use node::EdgeType;
 
-use crate::seed::ChainingAnchors;
+use std::ops::Range;
+
+use crate::{
+    error::{Error, Result},
+    seed::{ChainingAnchor, ChainingAnchors, ReverseComplementAnchor},
+};
 
 pub use context::ChainingCostsProvider;
+pub use export::ChainingGraphFormat;
 pub use node::Identifier;
 
 mod context;
 mod display;
+mod export;
 mod node;
 
 pub struct Chain {
     chain: Vec<ChainLink>,
+    template_switch_anchors: Vec<ReverseComplementAnchor>,
 }
 
 pub struct ChainLink {
@@ -21,15 +29,43 @@ pub struct ChainLink {
     cost: Cost,
 }
 
+/// A transition between consecutive links of a chain, from the root or an anchor to the next anchor or the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainTransition {
+    /// The reference characters between the links.
+    pub reference_gap: Range<usize>,
+    /// The query characters between the links.
+    pub query_gap: Range<usize>,
+    /// The chaining cost of the transition.
+    pub cost: Cost,
+}
+
+impl ChainTransition {
+    /// Returns true if the links touch in both sequences, such that the alignment can go directly from one to the other.
+    pub fn is_direct(&self) -> bool {
+        self.reference_gap.is_empty() && self.query_gap.is_empty()
+    }
+
+    /// Returns true if the chaining costs demand at least one non-match between the links.
+    pub fn demands_non_match(&self) -> bool {
+        self.cost > Cost::ZERO
+    }
+}
+
 impl Chain {
     pub fn compute_chain<ChainingCosts: ChainingCostsProvider>(
         chaining_costs: ChainingCosts,
         chaining_anchors: ChainingAnchors,
-    ) -> Self {
+    ) -> Result<Self> {
         info!("Computing chain...");
-        let mut a_star = AStar::new(Context::new(chaining_costs, chaining_anchors));
-        a_star.initialise();
-        a_star.search();
+        let reverse_complement_anchors = chaining_anchors.reverse_complement_anchors().to_vec();
+        let SearchOutcome::FoundTarget(a_star) =
+            AStar::new(Context::new(chaining_costs, chaining_anchors))
+                .into_initialised()
+                .search()
+        else {
+            return Err(Error::NoChain);
+        };
 
         let mut chain = Vec::new();
         chain.extend(
@@ -75,7 +111,7 @@ impl Chain {
                             anchor: second_anchor,
                         },
                     ) => {
-                        first.cost <= second.cost
+                        first.cost >= second.cost
                             && first_anchor.reference_block().end
                                 <= second_anchor.reference_block().start
                             && first_anchor.query_block().end <= second_anchor.query_block().start
@@ -84,7 +120,117 @@ impl Chain {
             })
         });
 
-        Self { chain }
+        let mut result = Self {
+            chain,
+            template_switch_anchors: Vec::new(),
+        };
+        result.template_switch_anchors = reverse_complement_anchors
+            .into_iter()
+            .filter(|anchor| {
+                anchor.reference_ranges().any(|range| {
+                    !is_covered(range, result.anchors().map(ChainingAnchor::reference_block))
+                }) || anchor.query_ranges().any(|range| {
+                    !is_covered(range, result.anchors().map(ChainingAnchor::query_block))
+                })
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    /// The anchors of the chain, ordered by their position in the sequences.
+    pub fn anchors(&self) -> impl Iterator<Item = &ChainingAnchor> {
+        self.chain.iter().filter_map(|link| match &link.identifier {
+            Identifier::Anchor { anchor } => Some(anchor),
+            Identifier::Root | Identifier::Target => None,
+        })
+    }
+
+    /// The anchors of the chain with the cost of the chain from the end of each anchor to the target,
+    /// ordered by their position in the sequences.
+    pub fn anchors_with_costs(&self) -> impl Iterator<Item = (&ChainingAnchor, Cost)> {
+        self.chain.iter().filter_map(|link| match &link.identifier {
+            Identifier::Anchor { anchor } => Some((anchor, link.cost)),
+            Identifier::Root | Identifier::Target => None,
+        })
+    }
+
+    /// The reverse-complement anchors that reach into a gap of the chain in at least one of their ranges.
+    ///
+    /// These are the candidates for template switches, since the chain explains the covered parts of the sequences
+    /// without template switches already.
+    pub fn template_switch_anchors(&self) -> &[ReverseComplementAnchor] {
+        &self.template_switch_anchors
+    }
+
+    /// The cost of the chain from the root to the target.
+    pub fn cost(&self) -> Cost {
+        self.chain
+            .first()
+            .map(|link| link.cost)
+            .unwrap_or(Cost::ZERO)
+    }
+
+    /// The number of gaps between consecutive anchors and between the anchors and the ends of the sequences,
+    /// in which the anchors do not touch in at least one of the sequences.
+    ///
+    /// These are the parts of the alignment that are not covered by the chain.
+    pub fn gap_count(&self, reference_length: usize, query_length: usize) -> usize {
+        let mut previous_ends = (0, 0);
+        let mut gap_count = 0;
+        for (reference_block, query_block) in self
+            .anchors()
+            .map(|anchor| {
+                (
+                    anchor.reference_block().clone(),
+                    anchor.query_block().clone(),
+                )
+            })
+            .chain([(
+                reference_length..reference_length,
+                query_length..query_length,
+            )])
+        {
+            if (reference_block.start, query_block.start) != previous_ends {
+                gap_count += 1;
+            }
+            previous_ends = (reference_block.end, query_block.end);
+        }
+        gap_count
+    }
+
+    /// The transitions between consecutive links of the chain, from the root to the target.
+    ///
+    /// The costs of the transitions sum up to the cost of the chain.
+    pub fn transitions(
+        &self,
+        reference_length: usize,
+        query_length: usize,
+    ) -> Vec<ChainTransition> {
+        let ranges = |identifier: &Identifier| match identifier {
+            Identifier::Root => (0..0, 0..0),
+            Identifier::Anchor { anchor } => (
+                anchor.reference_block().clone(),
+                anchor.query_block().clone(),
+            ),
+            Identifier::Target => (
+                reference_length..reference_length,
+                query_length..query_length,
+            ),
+        };
+
+        self.chain
+            .windows(2)
+            .map(|window| {
+                let (from_reference, from_query) = ranges(&window[0].identifier);
+                let (to_reference, to_query) = ranges(&window[1].identifier);
+                ChainTransition {
+                    reference_gap: from_reference.end..to_reference.start,
+                    query_gap: from_query.end..to_query.start,
+                    cost: window[0].cost - window[1].cost,
+                }
+            })
+            .collect()
     }
 
     pub fn chain_lower_bound(&self, reference_index: usize, query_index: usize) -> Cost {
@@ -108,3 +254,174 @@ impl Chain {
         }
     }
 }
+
+/// Returns true if `range` is covered by the union of `blocks`, which must be ordered and non-overlapping.
+fn is_covered<'block>(
+    range: &Range<usize>,
+    blocks: impl IntoIterator<Item = &'block Range<usize>>,
+) -> bool {
+    let mut covered_until = range.start;
+    for block in blocks {
+        if block.end <= covered_until {
+            continue;
+        }
+        if block.start > covered_until {
+            return false;
+        }
+        covered_until = covered_until.max(block.end);
+        if covered_until >= range.end {
+            return true;
+        }
+    }
+
+    covered_until >= range.end
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_genome::{
+        implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+        interface::sequence::{GenomeSequence, OwnedGenomeSequence},
+    };
+    use generic_a_star::cost::Cost;
+
+    use crate::seed::ChainingAnchors;
+
+    use super::{
+        is_covered, Chain, ChainTransition, ChainingCostsProvider, ChainingGraphFormat, Identifier,
+    };
+
+    /// Charges the characters skipped in the reference.
+    struct SkippedReferenceCosts {
+        reference_length: usize,
+    }
+
+    impl ChainingCostsProvider for SkippedReferenceCosts {
+        fn chaining_costs(&self, from: &Identifier, to: &Identifier) -> Cost {
+            let from = match from {
+                Identifier::Root => 0,
+                Identifier::Anchor { anchor } => anchor.reference_block().end,
+                Identifier::Target => return Cost::MAX,
+            };
+            let to = match to {
+                Identifier::Root => return Cost::MAX,
+                Identifier::Anchor { anchor } => anchor.reference_block().start,
+                Identifier::Target => self.reference_length,
+            };
+            Cost::from((to - from) as u64)
+        }
+    }
+
+    #[test]
+    fn test_write_graph() {
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACTTGGAAAA").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(b"TACTGGAAAAACT").unwrap();
+        let chaining_anchors = ChainingAnchors::seed_nonoverlapping(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap();
+        let chaining_costs = SkippedReferenceCosts {
+            reference_length: 10,
+        };
+        let chain = Chain::compute_chain(&chaining_costs, chaining_anchors.clone()).unwrap();
+
+        let mut dot = Vec::new();
+        chain
+            .write_graph(
+                &chaining_costs,
+                &chaining_anchors,
+                ChainingGraphFormat::Dot,
+                &mut dot,
+            )
+            .unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("digraph chaining {"));
+        // Root, target and five anchors.
+        assert_eq!(dot.matches("[label=\"[").count(), 7);
+        // The chain of root, two anchors and target is highlighted.
+        assert_eq!(chain.anchors().count(), 2);
+        assert_eq!(dot.matches("color=red").count(), 4 + 3);
+
+        let mut graphml = Vec::new();
+        chain
+            .write_graph(
+                &chaining_costs,
+                &chaining_anchors,
+                ChainingGraphFormat::GraphMl,
+                &mut graphml,
+            )
+            .unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert_eq!(graphml.matches("<node ").count(), 7);
+        assert_eq!(
+            graphml.matches("<edge ").count(),
+            dot.matches(" -> ").count()
+        );
+    }
+
+    #[test]
+    fn test_transitions() {
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACTTGGAAAA").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(b"TACTGGAAAAACT").unwrap();
+        let chaining_anchors = ChainingAnchors::seed_nonoverlapping(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap();
+        let chain = Chain::compute_chain(
+            &SkippedReferenceCosts {
+                reference_length: 10,
+            },
+            chaining_anchors,
+        )
+        .unwrap();
+
+        let transitions = chain.transitions(10, 13);
+        assert_eq!(transitions.len(), chain.anchors().count() + 1);
+        assert_eq!(
+            transitions
+                .iter()
+                .fold(Cost::ZERO, |cost, transition| cost + transition.cost),
+            chain.cost()
+        );
+        for window in transitions.windows(2) {
+            assert!(window[0].reference_gap.end <= window[1].reference_gap.start);
+            assert!(window[0].query_gap.end <= window[1].query_gap.start);
+        }
+        for transition in &transitions {
+            assert_eq!(
+                transition.demands_non_match(),
+                !transition.reference_gap.is_empty(),
+                "{transition:?}"
+            );
+        }
+        assert_eq!(transitions.first().unwrap().reference_gap.start, 0);
+        for ((anchor, cost), transition) in chain.anchors_with_costs().zip(&transitions[1..]) {
+            assert_eq!(anchor.reference_block().end, transition.reference_gap.start);
+            assert!(cost >= transition.cost);
+        }
+        assert_eq!(transitions.last().unwrap().query_gap.end, 13);
+
+        let transition = ChainTransition {
+            reference_gap: 3..3,
+            query_gap: 4..4,
+            cost: Cost::ZERO,
+        };
+        assert!(transition.is_direct());
+        assert!(!transition.demands_non_match());
+    }
+
+    #[test]
+    fn test_is_covered() {
+        let blocks = [0..3, 3..6, 8..10];
+        assert!(is_covered(&(1..5), &blocks));
+        assert!(is_covered(&(0..6), &blocks));
+        assert!(is_covered(&(8..9), &blocks));
+        assert!(!is_covered(&(4..8), &blocks));
+        assert!(!is_covered(&(6..8), &blocks));
+        assert!(!is_covered(&(9..11), &blocks));
+    }
+}
@@ -11,6 +11,12 @@ pub trait ChainingCostsProvider {
     fn chaining_costs(&self, from: &Identifier, to: &Identifier) -> Cost;
 }
 
+impl<ChainingCosts: ChainingCostsProvider> ChainingCostsProvider for &ChainingCosts {
+    fn chaining_costs(&self, from: &Identifier, to: &Identifier) -> Cost {
+        (*self).chaining_costs(from, to)
+    }
+}
+
 pub struct Context<ChainingCosts: ChainingCostsProvider> {
     chaining_costs: ChainingCosts,
     chaining_anchors: ChainingAnchors,
@@ -0,0 +1,146 @@
+use std::{collections::HashSet, io::Write};
+
+use generic_a_star::cost::Cost;
+
+use crate::seed::ChainingAnchors;
+
+use super::{Chain, ChainingCostsProvider, Identifier};
+
+/// The file format of an exported chaining graph.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ChainingGraphFormat {
+    /// The Graphviz DOT format.
+    Dot,
+    /// The GraphML format.
+    GraphMl,
+}
+
+impl Chain {
+    /// Write the graph that was searched for this chain.
+    ///
+    /// The nodes are the root, the target and the anchors, and the edges are all feasible transitions with their costs.
+    /// The nodes and edges of this chain are highlighted.
+    ///
+    /// The graph has an edge for each pair of chainable anchors, so it should only be exported for small inputs.
+    pub fn write_graph<ChainingCosts: ChainingCostsProvider>(
+        &self,
+        chaining_costs: &ChainingCosts,
+        chaining_anchors: &ChainingAnchors,
+        format: ChainingGraphFormat,
+        mut writer: impl Write,
+    ) -> std::io::Result<()> {
+        let nodes: Vec<_> = [Identifier::Root]
+            .into_iter()
+            .chain(
+                chaining_anchors
+                    .anchors()
+                    .iter()
+                    .map(|anchor| Identifier::Anchor {
+                        anchor: anchor.clone(),
+                    }),
+            )
+            .chain([Identifier::Target])
+            .collect();
+        let chain_nodes: HashSet<_> = self.chain.iter().map(|link| &link.identifier).collect();
+        let chain_edges: HashSet<_> = self
+            .chain
+            .windows(2)
+            .map(|window| (&window[0].identifier, &window[1].identifier))
+            .collect();
+
+        let mut edges = Vec::new();
+        for (from_index, from) in nodes.iter().enumerate() {
+            for (to_index, to) in nodes.iter().enumerate() {
+                if !is_chainable(from, to) {
+                    continue;
+                }
+                let cost = chaining_costs.chaining_costs(from, to);
+                if cost == Cost::MAX {
+                    continue;
+                }
+                edges.push((
+                    from_index,
+                    to_index,
+                    cost,
+                    chain_edges.contains(&(from, to)),
+                ));
+            }
+        }
+
+        match format {
+            ChainingGraphFormat::Dot => {
+                writeln!(writer, "digraph chaining {{")?;
+                for (index, node) in nodes.iter().enumerate() {
+                    write!(writer, "  n{index} [label=\"{node}\"")?;
+                    if chain_nodes.contains(node) {
+                        write!(writer, ", color=red, penwidth=2")?;
+                    }
+                    writeln!(writer, "];")?;
+                }
+                for (from_index, to_index, cost, in_chain) in edges {
+                    write!(writer, "  n{from_index} -> n{to_index} [label=\"{cost}\"")?;
+                    if in_chain {
+                        write!(writer, ", color=red, penwidth=2")?;
+                    }
+                    writeln!(writer, "];")?;
+                }
+                writeln!(writer, "}}")?;
+            }
+            ChainingGraphFormat::GraphMl => {
+                writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+                writeln!(
+                    writer,
+                    r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+                )?;
+                writeln!(
+                    writer,
+                    r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#
+                )?;
+                writeln!(
+                    writer,
+                    r#"  <key id="cost" for="edge" attr.name="cost" attr.type="long"/>"#
+                )?;
+                writeln!(
+                    writer,
+                    r#"  <key id="chain" for="all" attr.name="chain" attr.type="boolean"><default>false</default></key>"#
+                )?;
+                writeln!(writer, r#"  <graph id="chaining" edgedefault="directed">"#)?;
+                for (index, node) in nodes.iter().enumerate() {
+                    writeln!(writer, r#"    <node id="n{index}">"#)?;
+                    writeln!(writer, r#"      <data key="label">{node}</data>"#)?;
+                    if chain_nodes.contains(node) {
+                        writeln!(writer, r#"      <data key="chain">true</data>"#)?;
+                    }
+                    writeln!(writer, r#"    </node>"#)?;
+                }
+                for (from_index, to_index, cost, in_chain) in edges {
+                    writeln!(
+                        writer,
+                        r#"    <edge source="n{from_index}" target="n{to_index}">"#
+                    )?;
+                    writeln!(writer, r#"      <data key="cost">{}</data>"#, cost.as_u64())?;
+                    if in_chain {
+                        writeln!(writer, r#"      <data key="chain">true</data>"#)?;
+                    }
+                    writeln!(writer, r#"    </edge>"#)?;
+                }
+                writeln!(writer, "  </graph>")?;
+                writeln!(writer, "</graphml>")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true if `to` may follow `from` in a chain, using the same rules as the chaining search.
+fn is_chainable(from: &Identifier, to: &Identifier) -> bool {
+    match (from, to) {
+        (Identifier::Target, _) | (_, Identifier::Root) => false,
+        (Identifier::Root, _) | (Identifier::Anchor { .. }, Identifier::Target) => true,
+        (Identifier::Anchor { anchor: from }, Identifier::Anchor { anchor: to }) => {
+            to.reference_block().start >= from.reference_block().end
+                && to.query_block().start >= from.query_block().end
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::seed::ChainingAnchor;
 
 pub mod display;
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Identifier {
     Root,
     Anchor { anchor: ChainingAnchor },
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "The reference (length: {reference_length}) is shorter than the block size {block_size}."
+    )]
+    ReferenceShorterThanBlockSize {
+        reference_length: usize,
+        block_size: usize,
+    },
+
+    #[error("The block size must be positive, but is zero.")]
+    ZeroBlockSize,
+
+    #[error(
+        "Unknown repeat mask threshold {value:?}, expected `auto` or a number of occurrences."
+    )]
+    UnknownRepeatMaskThreshold { value: String },
+
+    #[error("The chaining graph does not connect the root to the target.")]
+    NoChain,
+}
@@ -1,4 +1,5 @@
 #![forbid(clippy::mod_module_files)]
 
 pub mod chain;
+pub mod error;
 pub mod seed;
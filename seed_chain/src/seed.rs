@@ -1,11 +1,20 @@
-use std::ops::Range;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    hash::Hash,
+    ops::Range,
+    str::FromStr,
+};
 
 use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
 use log::info;
 
+use crate::error::{Error, Result};
+
 #[derive(Debug, Clone)]
 pub struct ChainingAnchors {
     anchors: Vec<ChainingAnchor>,
+    reverse_complement_anchors: Vec<ReverseComplementAnchor>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -14,12 +23,80 @@ pub struct ChainingAnchor {
     query_block: Range<usize>,
 }
 
+/// The sequences connected by a [`ReverseComplementAnchor`].
+///
+/// Each class corresponds to the template switches whose primary and secondary are these sequences.
+/// Anchors between reference and query serve template switches in both directions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ReverseComplementAnchorClass {
+    /// A reference block matching the reverse complement of the query.
+    ReferenceQuery,
+    /// A reference block matching the reverse complement of the reference.
+    ReferenceReference,
+    /// A query block matching the reverse complement of the query.
+    QueryQuery,
+}
+
+/// An exact match between a block and the reverse complement of a sequence.
+///
+/// These do not take part in the chain itself, but mark candidate template switches.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ReverseComplementAnchor {
+    class: ReverseComplementAnchorClass,
+    /// The block in the query for [`ReverseComplementAnchorClass::QueryQuery`], and in the reference otherwise.
+    block: Range<usize>,
+    /// The range in forward coordinates whose reverse complement matches the block.
+    ///
+    /// It is in the reference for [`ReverseComplementAnchorClass::ReferenceReference`], and in the query otherwise.
+    reverse_complement_block: Range<usize>,
+}
+
+/// How [`ChainingAnchors`] are found.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum AnchorScheme {
+    /// Exact matches of blocks, see [`ChainingAnchors::seed_nonoverlapping`].
+    #[default]
+    Kmer,
+    /// Exact matches of blocks of the homopolymer-compressed sequences,
+    /// see [`ChainingAnchors::seed_nonoverlapping_homopolymer_compressed`].
+    HpcKmer,
+}
+
+/// A sequence in which each run of the same character is collapsed into a single character.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HomopolymerCompressed {
+    sequence: String,
+    /// The start of each run in the uncompressed sequence, followed by the length of the uncompressed sequence.
+    run_starts: Vec<usize>,
+}
+
+/// The minimum threshold chosen by [`RepeatMaskThreshold::Auto`].
+pub const MIN_AUTO_REPEAT_MASK_THRESHOLD: usize = 16;
+
+/// The factor by which the threshold chosen by [`RepeatMaskThreshold::Auto`] exceeds the median occurrence count.
+pub const AUTO_REPEAT_MASK_FACTOR: usize = 10;
+
+/// The number of matches above which [`ChainingAnchors::mask_repeats`] drops the anchors of a block.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RepeatMaskThreshold {
+    /// Choose the threshold from the spectrum of occurrence counts of the blocks.
+    ///
+    /// The threshold is [`AUTO_REPEAT_MASK_FACTOR`] times the median occurrence count of the blocks that match at all,
+    /// but at least [`MIN_AUTO_REPEAT_MASK_THRESHOLD`].
+    Auto,
+    /// Drop the anchors of blocks that match more often than this.
+    Fixed(usize),
+}
+
 impl ChainingAnchors {
     /// Compute a set of anchors for the given reference and query sequences.
     ///
     /// The anchors are computed by subdividing the reference sequence into non-overlapping blocks of size `block_size`,
     /// and collecting all their matches in the query sequence.
     /// The last block is merged with the second-to-last block if it is smaller than `block_size`.
+    /// Blocks containing characters other than `A`, `C`, `G`, `T` and `U` (such as `N` or other IUPAC codes) get no anchors.
+    ///
+    /// Returns an error if `block_size` is zero or larger than the reference.
     pub fn seed_nonoverlapping<
         AlphabetType: Alphabet,
         SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
@@ -27,52 +104,252 @@ impl ChainingAnchors {
         reference: &SubsequenceType,
         query: &SubsequenceType,
         block_size: usize,
-    ) -> Self {
+    ) -> Result<Self> {
         info!("Computing non-overlapping chaining anchors with block size {block_size}...");
-        assert!(
-            reference.len() >= block_size,
-            "Reference (length: {}) is shorter than the block size {}",
-            reference.len(),
-            block_size
-        );
-        assert!(block_size > 0, "Block size must be positive, but is zero");
+        if reference.len() < block_size {
+            return Err(Error::ReferenceShorterThanBlockSize {
+                reference_length: reference.len(),
+                block_size,
+            });
+        }
+        if block_size == 0 {
+            return Err(Error::ZeroBlockSize);
+        }
 
         // Read into strings so we can use stdlib string matching and don't need to implement our own.
+        let mut anchors =
+            nonoverlapping_anchors(&reference.as_string(), &query.as_string(), block_size);
+        anchors.sort_unstable();
+
+        Ok(ChainingAnchors {
+            anchors,
+            reverse_complement_anchors: Vec::new(),
+        })
+    }
+
+    /// Like [`Self::seed_nonoverlapping`], but with blocks of the homopolymer-compressed sequences.
+    ///
+    /// Runs of the same character in the reference and the query are collapsed into a single character,
+    /// such that blocks match even if the lengths of their homopolymers differ, as is common in noisy long reads.
+    /// The anchors are mapped back to the uncompressed sequences, where they span whole homopolymers,
+    /// so their reference and query blocks may have different lengths.
+    ///
+    /// Returns an error if `block_size` is zero or larger than the compressed reference.
+    pub fn seed_nonoverlapping_homopolymer_compressed<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        block_size: usize,
+    ) -> Result<Self> {
+        info!("Computing non-overlapping homopolymer-compressed chaining anchors with block size {block_size}...");
+        let reference = HomopolymerCompressed::new(&reference.as_string());
+        let query = HomopolymerCompressed::new(&query.as_string());
+        if reference.sequence.len() < block_size {
+            return Err(Error::ReferenceShorterThanBlockSize {
+                reference_length: reference.sequence.len(),
+                block_size,
+            });
+        }
+        if block_size == 0 {
+            return Err(Error::ZeroBlockSize);
+        }
+
+        let mut anchors: Vec<_> =
+            nonoverlapping_anchors(&reference.sequence, &query.sequence, block_size)
+                .into_iter()
+                .map(|anchor| ChainingAnchor {
+                    reference_block: reference.original_range(&anchor.reference_block),
+                    query_block: query.original_range(&anchor.query_block),
+                })
+                .collect();
+        anchors.sort_unstable();
+
+        Ok(ChainingAnchors {
+            anchors,
+            reverse_complement_anchors: Vec::new(),
+        })
+    }
+
+    /// Compute anchors with [`Self::seed_nonoverlapping`] or [`Self::seed_nonoverlapping_homopolymer_compressed`], depending on `scheme`.
+    pub fn seed_nonoverlapping_with_scheme<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        block_size: usize,
+        scheme: AnchorScheme,
+    ) -> Result<Self> {
+        match scheme {
+            AnchorScheme::Kmer => Self::seed_nonoverlapping(reference, query, block_size),
+            AnchorScheme::HpcKmer => {
+                Self::seed_nonoverlapping_homopolymer_compressed(reference, query, block_size)
+            }
+        }
+    }
+
+    /// Add anchors between non-overlapping blocks and reverse-complement matches,
+    /// both between reference and query and within each sequence.
+    ///
+    /// The blocks are chosen like in [`Self::seed_nonoverlapping`],
+    /// with query blocks used for matches within the query.
+    /// If the query is shorter than `block_size`, then it gets no blocks.
+    pub fn seed_reverse_complements<
+        AlphabetType: Alphabet,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    >(
+        mut self,
+        reference: &SubsequenceType,
+        query: &SubsequenceType,
+        block_size: usize,
+    ) -> Result<Self> {
+        info!("Computing reverse-complement anchors with block size {block_size}...");
+        if reference.len() < block_size {
+            return Err(Error::ReferenceShorterThanBlockSize {
+                reference_length: reference.len(),
+                block_size,
+            });
+        }
+        if block_size == 0 {
+            return Err(Error::ZeroBlockSize);
+        }
+
+        let reverse_complement = |sequence: &SubsequenceType| -> String {
+            sequence
+                .reverse_complement_iter()
+                .map(Into::<char>::into)
+                .collect()
+        };
+        let reference_reverse_complement = reverse_complement(reference);
+        let query_reverse_complement = reverse_complement(query);
         let reference = reference.as_string();
         let query = query.as_string();
 
-        let reference_block_ranges: Vec<_> =
-            nonoverlapping_block_ranges(reference.len(), block_size).collect();
-        let reference_blocks: Vec<_> = reference_block_ranges
-            .iter()
-            .cloned()
-            .map(|block_range| &reference[block_range])
-            .collect();
-        let mut anchors: Vec<_> = find_all_substrings(&query, &reference_blocks)
-            .map(
-                |SubstringMatch {
-                     haystack_offset,
-                     needle_index,
-                 }| {
-                    let reference_block = reference_block_ranges[needle_index].clone();
-                    let block_size = reference_block.len();
-                    let query_block = haystack_offset..haystack_offset + block_size;
-
-                    ChainingAnchor {
-                        reference_block,
-                        query_block,
-                    }
-                },
-            )
-            .collect();
-        anchors.sort_unstable();
+        let mut push_anchors = |class, sequence: &str, reverse_complement_haystack: &str| {
+            if sequence.len() < block_size {
+                return;
+            }
+
+            let (block_ranges, blocks): (Vec<_>, Vec<_>) =
+                nonoverlapping_block_ranges(sequence.len(), block_size)
+                    .map(|block_range| (block_range.clone(), &sequence[block_range]))
+                    .filter(|(_, block)| !contains_ambiguous_character(block))
+                    .unzip();
+            let haystack_length = reverse_complement_haystack.len();
+            self.reverse_complement_anchors.extend(
+                find_all_substrings(reverse_complement_haystack, &blocks).map(
+                    |SubstringMatch {
+                         haystack_offset,
+                         needle_index,
+                     }| {
+                        let block = block_ranges[needle_index].clone();
+                        // Translate from reverse-complement coordinates into forward coordinates.
+                        let end = haystack_length - haystack_offset;
+                        let reverse_complement_block = end - block.len()..end;
 
-        ChainingAnchors { anchors }
+                        ReverseComplementAnchor {
+                            class,
+                            block,
+                            reverse_complement_block,
+                        }
+                    },
+                ),
+            );
+        };
+
+        push_anchors(
+            ReverseComplementAnchorClass::ReferenceQuery,
+            &reference,
+            &query_reverse_complement,
+        );
+        push_anchors(
+            ReverseComplementAnchorClass::ReferenceReference,
+            &reference,
+            &reference_reverse_complement,
+        );
+        push_anchors(
+            ReverseComplementAnchorClass::QueryQuery,
+            &query,
+            &query_reverse_complement,
+        );
+        self.reverse_complement_anchors
+            .sort_unstable_by_key(|anchor| {
+                (
+                    anchor.class,
+                    anchor.block.start,
+                    anchor.reverse_complement_block.start,
+                )
+            });
+
+        Ok(self)
+    }
+
+    /// Drop the anchors of blocks that match more often than `threshold`.
+    ///
+    /// Each copy of a repeat matches each other copy, so repeats make the number of anchors grow quadratically.
+    /// Since such anchors barely constrain the chain, dropping them keeps chaining fast on repetitive sequences.
+    /// Anchors are counted per reference block, and reverse-complement anchors per class and block.
+    ///
+    /// Chaining costs that assume that each exact match of a block is an anchor are no longer lower bounds after masking.
+    pub fn mask_repeats(mut self, threshold: RepeatMaskThreshold) -> Self {
+        mask_repeats(&mut self.anchors, threshold, |anchor| {
+            anchor.reference_block.start
+        });
+        mask_repeats(&mut self.reverse_complement_anchors, threshold, |anchor| {
+            (anchor.class, anchor.block.start)
+        });
+        self
     }
 
     pub fn anchors(&self) -> &[ChainingAnchor] {
         &self.anchors
     }
+
+    pub fn reverse_complement_anchors(&self) -> &[ReverseComplementAnchor] {
+        &self.reverse_complement_anchors
+    }
+}
+
+impl HomopolymerCompressed {
+    pub fn new(sequence: &str) -> Self {
+        let mut compressed = String::new();
+        let mut run_starts = Vec::new();
+        let mut previous = None;
+        for (index, character) in sequence.char_indices() {
+            if previous != Some(character) {
+                compressed.push(character);
+                run_starts.push(index);
+                previous = Some(character);
+            }
+        }
+        run_starts.push(sequence.len());
+
+        Self {
+            sequence: compressed,
+            run_starts,
+        }
+    }
+
+    /// The compressed sequence.
+    pub fn sequence(&self) -> &str {
+        &self.sequence
+    }
+
+    /// The range of the uncompressed sequence spanned by the runs of the given range of the compressed sequence.
+    pub fn original_range(&self, compressed_range: &Range<usize>) -> Range<usize> {
+        self.run_starts[compressed_range.start]..self.run_starts[compressed_range.end]
+    }
+
+    /// The length of the longest run, or zero if the sequence is empty.
+    pub fn max_run_length(&self) -> usize {
+        self.run_starts
+            .windows(2)
+            .map(|window| window[1] - window[0])
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 impl ChainingAnchor {
@@ -92,6 +369,124 @@ impl ChainingAnchor {
     }
 }
 
+impl ReverseComplementAnchor {
+    pub fn new(
+        class: ReverseComplementAnchorClass,
+        block: Range<usize>,
+        reverse_complement_block: Range<usize>,
+    ) -> Self {
+        Self {
+            class,
+            block,
+            reverse_complement_block,
+        }
+    }
+
+    pub fn class(&self) -> ReverseComplementAnchorClass {
+        self.class
+    }
+
+    pub fn block(&self) -> &Range<usize> {
+        &self.block
+    }
+
+    pub fn reverse_complement_block(&self) -> &Range<usize> {
+        &self.reverse_complement_block
+    }
+
+    /// The range of this anchor in the reference and in the query, if it has one there.
+    ///
+    /// Anchors within a sequence have two ranges in that sequence.
+    pub fn reference_ranges(&self) -> impl Iterator<Item = &Range<usize>> {
+        match self.class {
+            ReverseComplementAnchorClass::ReferenceQuery => [Some(&self.block), None],
+            ReverseComplementAnchorClass::ReferenceReference => {
+                [Some(&self.block), Some(&self.reverse_complement_block)]
+            }
+            ReverseComplementAnchorClass::QueryQuery => [None, None],
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    /// The ranges of this anchor in the query, see [`Self::reference_ranges`].
+    pub fn query_ranges(&self) -> impl Iterator<Item = &Range<usize>> {
+        match self.class {
+            ReverseComplementAnchorClass::ReferenceQuery => {
+                [Some(&self.reverse_complement_block), None]
+            }
+            ReverseComplementAnchorClass::ReferenceReference => [None, None],
+            ReverseComplementAnchorClass::QueryQuery => {
+                [Some(&self.block), Some(&self.reverse_complement_block)]
+            }
+        }
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Drop the anchors whose block, given by `block`, occurs more often than `threshold`.
+fn mask_repeats<Anchor, Block: Eq + Hash>(
+    anchors: &mut Vec<Anchor>,
+    threshold: RepeatMaskThreshold,
+    block: impl Fn(&Anchor) -> Block,
+) {
+    let mut occurrences: HashMap<_, usize> = HashMap::new();
+    for anchor in anchors.iter() {
+        *occurrences.entry(block(anchor)).or_default() += 1;
+    }
+
+    let threshold = match threshold {
+        RepeatMaskThreshold::Auto => {
+            let mut spectrum: Vec<_> = occurrences.values().copied().collect();
+            spectrum.sort_unstable();
+            spectrum
+                .get(spectrum.len() / 2)
+                .map_or(0, |median| median * AUTO_REPEAT_MASK_FACTOR)
+                .max(MIN_AUTO_REPEAT_MASK_THRESHOLD)
+        }
+        RepeatMaskThreshold::Fixed(threshold) => threshold,
+    };
+
+    let anchor_count = anchors.len();
+    anchors.retain(|anchor| occurrences[&block(anchor)] <= threshold);
+    if anchors.len() < anchor_count {
+        info!(
+            "Masked {} of {anchor_count} anchors of blocks occurring more than {threshold} times",
+            anchor_count - anchors.len()
+        );
+    }
+}
+
+/// Returns all exact matches of the non-overlapping blocks of `reference` in `query`, in no particular order.
+fn nonoverlapping_anchors(reference: &str, query: &str, block_size: usize) -> Vec<ChainingAnchor> {
+    let reference_block_ranges: Vec<_> =
+        nonoverlapping_block_ranges(reference.len(), block_size).collect();
+    // Blocks with ambiguous characters are skipped, since their exact matches are no evidence of homology.
+    let (reference_block_ranges, reference_blocks): (Vec<_>, Vec<_>) = reference_block_ranges
+        .into_iter()
+        .map(|block_range| (block_range.clone(), &reference[block_range]))
+        .filter(|(_, block)| !contains_ambiguous_character(block))
+        .unzip();
+    find_all_substrings(query, &reference_blocks)
+        .map(
+            |SubstringMatch {
+                 haystack_offset,
+                 needle_index,
+             }| {
+                let reference_block = reference_block_ranges[needle_index].clone();
+                let block_size = reference_block.len();
+                let query_block = haystack_offset..haystack_offset + block_size;
+
+                ChainingAnchor {
+                    reference_block,
+                    query_block,
+                }
+            },
+        )
+        .collect()
+}
+
 fn nonoverlapping_block_ranges(
     length: usize,
     block_size: usize,
@@ -106,6 +501,13 @@ fn nonoverlapping_block_ranges(
         ))
 }
 
+/// Returns true if the block contains characters other than `A`, `C`, `G`, `T` and `U`, in upper or lower case.
+pub fn contains_ambiguous_character(block: &str) -> bool {
+    block
+        .bytes()
+        .any(|character| !b"ACGTUacgtu".contains(&character))
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct SubstringMatch {
     haystack_offset: usize,
@@ -175,16 +577,48 @@ impl PartialOrd for ChainingAnchor {
     }
 }
 
+impl FromStr for RepeatMaskThreshold {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if value == "auto" {
+            Ok(Self::Auto)
+        } else {
+            value
+                .parse()
+                .map(Self::Fixed)
+                .map_err(|_| Error::UnknownRepeatMaskThreshold {
+                    value: value.to_string(),
+                })
+        }
+    }
+}
+
+impl Display for RepeatMaskThreshold {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Fixed(threshold) => write!(f, "{threshold}"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use compact_genome::{
-        implementation::{alphabets::dna_alphabet::DnaAlphabet, vec_sequence::VectorGenome},
+        implementation::{
+            alphabets::{dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN},
+            vec_sequence::VectorGenome,
+        },
         interface::sequence::{GenomeSequence, OwnedGenomeSequence},
     };
 
+    use crate::error::Error;
+
     use super::{
-        find_all_substrings, nonoverlapping_block_ranges, ChainingAnchor, ChainingAnchors,
-        SubstringMatch,
+        find_all_substrings, nonoverlapping_block_ranges, AnchorScheme, ChainingAnchor,
+        ChainingAnchors, HomopolymerCompressed, RepeatMaskThreshold, ReverseComplementAnchor,
+        ReverseComplementAnchorClass, SubstringMatch,
     };
 
     #[test]
@@ -241,8 +675,189 @@ mod tests {
             query.as_genome_subsequence(),
             3,
         )
+        .unwrap()
+        .anchors;
+
+        assert_eq!(&expected, actual.as_slice());
+    }
+
+    #[test]
+    fn test_seed_nonoverlapping_skips_ambiguous_blocks() {
+        let reference = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACTNNNGGAA").unwrap();
+        let query = VectorGenome::<DnaAlphabetOrN>::from_slice_u8(b"ACTNNNGGAA").unwrap();
+        let expected =
+            [(0..3, 0..3), (6..10, 6..10)].map(|(reference_block, query_block)| ChainingAnchor {
+                reference_block,
+                query_block,
+            });
+        let actual = ChainingAnchors::seed_nonoverlapping(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap()
+        .anchors;
+
+        assert_eq!(&expected, actual.as_slice());
+    }
+
+    #[test]
+    fn test_seed_nonoverlapping_homopolymer_compressed() {
+        // Both compress to `ACGTA`.
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"AACCCGTTTA").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACCGGTA").unwrap();
+        let expected =
+            [(0..5, 0..3), (5..10, 3..7)].map(|(reference_block, query_block)| ChainingAnchor {
+                reference_block,
+                query_block,
+            });
+        let actual = ChainingAnchors::seed_nonoverlapping_with_scheme(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            2,
+            AnchorScheme::HpcKmer,
+        )
+        .unwrap()
         .anchors;
+        assert_eq!(&expected, actual.as_slice());
+
+        // Without compression, only the blocks `CC`, `CG` and `TA` match.
+        let actual = ChainingAnchors::seed_nonoverlapping_with_scheme(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            2,
+            AnchorScheme::Kmer,
+        )
+        .unwrap()
+        .anchors;
+        assert_eq!(
+            actual,
+            [(2..4, 1..3), (4..6, 2..4), (8..10, 5..7)].map(|(reference_block, query_block)| {
+                ChainingAnchor {
+                    reference_block,
+                    query_block,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_homopolymer_compressed() {
+        let compressed = HomopolymerCompressed::new("AACCCGTTTA");
+        assert_eq!(compressed.sequence(), "ACGTA");
+        assert_eq!(compressed.original_range(&(1..3)), 2..6);
+        assert_eq!(compressed.original_range(&(5..5)), 10..10);
+        assert_eq!(compressed.max_run_length(), 3);
+        assert_eq!(HomopolymerCompressed::new("").max_run_length(), 0);
+    }
+
+    #[test]
+    fn test_seed_reverse_complements() {
+        // The reference blocks `ACC` and `AAT` are reverse complements of `GGT` and `ATT` in the query,
+        // and `ACC` and `GGT` in the reference are reverse complements of each other.
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACCAATGGT").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(b"GGTATT").unwrap();
+        let expected = [
+            (ReverseComplementAnchorClass::ReferenceQuery, 0..3, 0..3),
+            (ReverseComplementAnchorClass::ReferenceQuery, 3..6, 3..6),
+            (ReverseComplementAnchorClass::ReferenceReference, 0..3, 6..9),
+            (ReverseComplementAnchorClass::ReferenceReference, 6..9, 0..3),
+        ]
+        .map(|(class, block, reverse_complement_block)| {
+            ReverseComplementAnchor::new(class, block, reverse_complement_block)
+        });
+        let actual = ChainingAnchors::seed_nonoverlapping(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap()
+        .seed_reverse_complements(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap()
+        .reverse_complement_anchors;
 
         assert_eq!(&expected, actual.as_slice());
     }
+
+    #[test]
+    fn test_mask_repeats() {
+        // The first block matches each of the 20 copies of `ACG` in the query.
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACGTTTGGC").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(
+            &[b"ACG".repeat(20), b"TTTGGC".to_vec()].concat(),
+        )
+        .unwrap();
+        let anchors = ChainingAnchors::seed_nonoverlapping(
+            reference.as_genome_subsequence(),
+            query.as_genome_subsequence(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(anchors.anchors.len(), 22);
+
+        let expected =
+            [(3..6, 60..63), (6..9, 63..66)].map(|(reference_block, query_block)| ChainingAnchor {
+                reference_block,
+                query_block,
+            });
+        assert_eq!(
+            anchors
+                .clone()
+                .mask_repeats(RepeatMaskThreshold::Fixed(1))
+                .anchors,
+            expected
+        );
+        assert_eq!(
+            anchors
+                .clone()
+                .mask_repeats(RepeatMaskThreshold::Auto)
+                .anchors,
+            expected
+        );
+        assert_eq!(
+            anchors
+                .clone()
+                .mask_repeats(RepeatMaskThreshold::Fixed(20))
+                .anchors,
+            anchors.anchors
+        );
+    }
+
+    #[test]
+    fn test_parse_repeat_mask_threshold() {
+        assert_eq!(
+            "auto".parse::<RepeatMaskThreshold>().unwrap(),
+            RepeatMaskThreshold::Auto
+        );
+        assert_eq!(
+            "12".parse::<RepeatMaskThreshold>().unwrap(),
+            RepeatMaskThreshold::Fixed(12)
+        );
+        assert!(matches!(
+            "often".parse::<RepeatMaskThreshold>(),
+            Err(Error::UnknownRepeatMaskThreshold { .. })
+        ));
+    }
+
+    #[test]
+    fn test_seed_nonoverlapping_short_reference() {
+        let reference = VectorGenome::<DnaAlphabet>::from_slice_u8(b"ACT").unwrap();
+        let query = VectorGenome::<DnaAlphabet>::from_slice_u8(b"TACTGG").unwrap();
+
+        assert!(matches!(
+            ChainingAnchors::seed_nonoverlapping(
+                reference.as_genome_subsequence(),
+                query.as_genome_subsequence(),
+                4,
+            ),
+            Err(Error::ReferenceShorterThanBlockSize {
+                reference_length: 3,
+                block_size: 4
+            })
+        ));
+    }
 }
@@ -0,0 +1,64 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Wraps the system allocator and counts the bytes allocated on the heap.
+///
+/// This measures the actual memory usage of the aligners, as opposed to the accounting of the A* search,
+/// which only counts the open and closed lists.
+pub struct CountingAllocator;
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+impl CountingAllocator {
+    fn add(size: usize) {
+        let allocated_bytes = ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_ALLOCATED_BYTES.fetch_max(allocated_bytes, Ordering::Relaxed);
+    }
+
+    fn sub(size: usize) {
+        ALLOCATED_BYTES.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            Self::add(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            Self::add(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        Self::sub(layout.size());
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            Self::sub(layout.size());
+            Self::add(new_size);
+        }
+        new_pointer
+    }
+}
+
+/// The maximum number of bytes that were allocated on the heap at the same time since the program started.
+pub fn peak_allocated_bytes() -> usize {
+    PEAK_ALLOCATED_BYTES.load(Ordering::Relaxed)
+}
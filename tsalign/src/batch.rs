@@ -0,0 +1,298 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        vec_sequence_store::VectorSequenceStore,
+        DefaultSequenceStore,
+    },
+    interface::{alphabet::Alphabet, sequence_store::SequenceStore},
+};
+use lib_tsalign::costs::cost::Cost;
+use log::info;
+use serde::Deserialize;
+use traitsequence::interface::Sequence;
+
+use crate::{
+    align_sequences, compression::read_input_to_string, exit_with_error,
+    preprocessing::preprocess_input, sequence_selection::SequenceSelection, skip_characters,
+    soft_mask::SoftMaskSelector, Cli, InputAlphabet, SequenceStoreSelector,
+};
+
+#[derive(Args, Clone)]
+pub struct BatchArgs {
+    /// The manifest listing the pairs to align.
+    ///
+    /// Files ending in `.tsv` are read as tab-separated values with a header line naming the columns,
+    /// all other files are read as toml with one `[[pairs]]` table per pair.
+    /// The columns or keys are `reference`, `reference_record`, `reference_range`, `reference_strand`,
+    /// `query`, `query_record`, `query_range`, `query_strand`, `max_cost` and `output`,
+    /// of which only `reference` and `query` are required.
+    /// Ranges are given one-based and inclusive as `start-end`, like in the sequence selections of the command line,
+    /// strands as `+` or `-`,
+    /// and relative paths are relative to the directory of the manifest.
+    manifest: PathBuf,
+}
+
+/// A manifest listing the pairs of a batch alignment.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    pairs: Vec<BatchPair>,
+}
+
+/// One pair of a batch alignment, with parameters that override the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchPair {
+    reference: PathBuf,
+    /// The id of the reference record, required if the reference file contains more than one record.
+    reference_record: Option<String>,
+    /// The range of the reference record to align, one-based and inclusive, formatted as `start-end`.
+    reference_range: Option<String>,
+    /// `-` to align the reverse complement of the reference range.
+    reference_strand: Option<String>,
+    query: PathBuf,
+    /// The id of the query record, required if the query file contains more than one record.
+    query_record: Option<String>,
+    /// The range of the query record to align, one-based and inclusive, formatted as `start-end`.
+    query_range: Option<String>,
+    /// `-` to align the reverse complement of the query range.
+    query_strand: Option<String>,
+    /// Overrides `--max-cost`.
+    max_cost: Option<u64>,
+    /// The file to store the alignment statistics in, like `--output`.
+    output: Option<PathBuf>,
+}
+
+/// Align the pairs listed in a manifest one after the other, with the options given on the command line.
+///
+/// Only the sequences of the current pair are kept in memory.
+pub fn batch(cli: Cli, args: BatchArgs) {
+    if cli.output.is_some() {
+        exit_with_error(
+            "--output is not supported in batch mode, use the output column of the manifest instead",
+        );
+    }
+    if cli.soft_mask != SoftMaskSelector::Ignore {
+        exit_with_error("Soft-masking is not supported in batch mode");
+    }
+
+    info!(file:% = args.manifest.display(); "Loading batch manifest {:?}", args.manifest);
+    let input = read_input_to_string(&args.manifest).unwrap_or_else(|error| {
+        exit_with_error(format!("Error reading {:?}: {error}", args.manifest))
+    });
+    let pairs = if args
+        .manifest
+        .extension()
+        .is_some_and(|extension| extension == "tsv")
+    {
+        parse_tsv_manifest(&input)
+    } else {
+        toml::from_str::<Manifest>(&input)
+            .map(|manifest| manifest.pairs)
+            .map_err(|error| error.to_string())
+    }
+    .unwrap_or_else(|error| {
+        exit_with_error(format!(
+            "Error parsing batch manifest {:?}: {error}",
+            args.manifest
+        ))
+    });
+
+    let base_directory = args.manifest.parent().unwrap_or(Path::new(""));
+    let pairs: Vec<_> = pairs
+        .into_iter()
+        .map(|pair| BatchPair {
+            reference: base_directory.join(&pair.reference),
+            query: base_directory.join(&pair.query),
+            output: pair.output.map(|output| base_directory.join(output)),
+            ..pair
+        })
+        .collect();
+    info!("Aligning {} pairs", pairs.len());
+
+    match cli.alphabet {
+        InputAlphabet::Dna => batch_with_alphabet::<DnaAlphabet>(cli, pairs),
+        InputAlphabet::DnaN => batch_with_alphabet::<DnaAlphabetOrN>(cli, pairs),
+        InputAlphabet::Rna => batch_with_alphabet::<RnaAlphabet>(cli, pairs),
+        InputAlphabet::RnaN => batch_with_alphabet::<RnaAlphabetOrN>(cli, pairs),
+        InputAlphabet::DnaIupac => batch_with_alphabet::<DnaIupacNucleicAcidAlphabet>(cli, pairs),
+        InputAlphabet::RnaIupac => batch_with_alphabet::<RnaIupacNucleicAcidAlphabet>(cli, pairs),
+    }
+}
+
+fn batch_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
+    cli: Cli,
+    pairs: Vec<BatchPair>,
+) {
+    match cli.sequence_store {
+        SequenceStoreSelector::Packed => {
+            batch_with_sequence_store(cli, pairs, DefaultSequenceStore::<AlphabetType>::new)
+        }
+        SequenceStoreSelector::Vector => {
+            batch_with_sequence_store(cli, pairs, VectorSequenceStore::<AlphabetType>::new)
+        }
+    }
+}
+
+fn batch_with_sequence_store<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
+    SequenceStoreType: SequenceStore<AlphabetType>,
+>(
+    cli: Cli,
+    pairs: Vec<BatchPair>,
+    new_sequence_store: fn() -> SequenceStoreType,
+) {
+    let skip_characters = skip_characters(&cli);
+
+    for (index, pair) in pairs.into_iter().enumerate() {
+        let mut sequence_store = new_sequence_store();
+        let [(reference_handle, reference_input), (query_handle, query_input)] = [
+            (
+                &pair.reference,
+                &pair.reference_record,
+                &pair.reference_range,
+                &pair.reference_strand,
+            ),
+            (
+                &pair.query,
+                &pair.query_record,
+                &pair.query_range,
+                &pair.query_strand,
+            ),
+        ]
+        .map(|(file, record, range, strand)| {
+            let (handle, input) = selection(file, record, range.as_deref(), strand.as_deref())
+                .unwrap_or_else(|error| {
+                    exit_with_error(format!("Error in pair {index} of the manifest: {error}"))
+                })
+                .load(&mut sequence_store, &skip_characters);
+            preprocess_input(&cli, &mut sequence_store, handle, input)
+        });
+
+        let reference_sequence = sequence_store.get(&reference_handle);
+        let query_sequence = sequence_store.get(&query_handle);
+        println!(
+            "# Pair {index}: {} against {}",
+            reference_input.record_id, query_input.record_id
+        );
+
+        let mut pair_cli = cli.clone();
+        pair_cli.max_cost = pair.max_cost.map(Cost::from).or(cli.max_cost);
+        pair_cli.output = pair.output.clone();
+        align_sequences(
+            pair_cli,
+            reference_sequence,
+            query_sequence,
+            [reference_input, query_input],
+        );
+    }
+}
+
+/// Builds the selection of a record from the cells of a manifest.
+///
+/// Ranges and strands are parsed like in a [`SequenceSelection`] given on the command line.
+fn selection(
+    file: &Path,
+    record: &Option<String>,
+    range: Option<&str>,
+    strand: Option<&str>,
+) -> Result<SequenceSelection, String> {
+    Ok(SequenceSelection {
+        file: file.to_path_buf(),
+        record: record.clone(),
+        range: range.map(SequenceSelection::parse_range).transpose()?,
+        reverse_complement: strand
+            .map(SequenceSelection::parse_strand)
+            .transpose()?
+            .unwrap_or(false),
+    })
+}
+
+const COLUMNS: [&str; 10] = [
+    "reference",
+    "reference_record",
+    "reference_range",
+    "reference_strand",
+    "query",
+    "query_record",
+    "query_range",
+    "query_strand",
+    "max_cost",
+    "output",
+];
+
+/// Parses a tab-separated manifest with a header line.
+///
+/// Missing trailing columns and empty cells are treated like missing keys in the toml format.
+fn parse_tsv_manifest(input: &str) -> Result<Vec<BatchPair>, String> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<_> = header.split('\t').map(str::trim).collect();
+    for column in &columns {
+        if !COLUMNS.contains(column) {
+            return Err(format!("Unknown column {column:?}"));
+        }
+    }
+    for required in ["reference", "query"] {
+        if !columns.contains(&required) {
+            return Err(format!("Missing column {required:?}"));
+        }
+    }
+
+    lines
+        .map(|(line_index, line)| {
+            let line_number = line_index + 1;
+            let cells: HashMap<_, _> = columns
+                .iter()
+                .zip(line.split('\t').map(str::trim))
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(column, cell)| (*column, cell))
+                .collect();
+            let mut pair = BatchPair::default();
+
+            for (&column, &cell) in &cells {
+                match column {
+                    "reference" => pair.reference = cell.into(),
+                    "reference_record" => pair.reference_record = Some(cell.to_string()),
+                    "reference_range" => pair.reference_range = Some(cell.to_string()),
+                    "reference_strand" => pair.reference_strand = Some(cell.to_string()),
+                    "query" => pair.query = cell.into(),
+                    "query_record" => pair.query_record = Some(cell.to_string()),
+                    "query_range" => pair.query_range = Some(cell.to_string()),
+                    "query_strand" => pair.query_strand = Some(cell.to_string()),
+                    "max_cost" => {
+                        pair.max_cost = Some(cell.parse().map_err(|error| {
+                            format!("Invalid max_cost {cell:?} in line {line_number}: {error}")
+                        })?)
+                    }
+                    "output" => pair.output = Some(cell.into()),
+                    _ => unreachable!("The columns were checked above"),
+                }
+            }
+
+            for required in ["reference", "query"] {
+                if !cells.contains_key(required) {
+                    return Err(format!("Missing {required} in line {line_number}"));
+                }
+            }
+            Ok(pair)
+        })
+        .collect()
+}
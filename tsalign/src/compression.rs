@@ -0,0 +1,80 @@
+//! Transparent decompression of input files and compression of output files.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use compact_genome::{
+    interface::{alphabet::Alphabet, sequence_store::SequenceStore},
+    io::{
+        error::IOError,
+        fasta::{read_fasta, FastaRecord},
+    },
+};
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder, Compression};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Opens a file for reading, decompressing it if it is gzip-compressed.
+///
+/// The compression is detected from the content, not the file name.
+/// Bgzip files consist of multiple gzip members, which are all decompressed.
+pub fn open_input(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
+
+    if magic.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if magic.starts_with(&ZSTD_MAGIC) {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "zstd-compressed files are not supported, decompress them with `zstd -d` first",
+        ))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Reads a file to a string, decompressing it like [`open_input`].
+pub fn read_input_to_string(path: impl AsRef<Path>) -> Result<String, Error> {
+    let mut result = String::new();
+    open_input(path)?.read_to_string(&mut result)?;
+    Ok(result)
+}
+
+/// Writes `contents` to a file, compressing it with gzip if the file name ends in `.gz`.
+pub fn write_output(path: impl AsRef<Path>, contents: &[u8]) -> Result<(), Error> {
+    let path = path.as_ref();
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") => {
+            let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+            encoder.write_all(contents)?;
+            encoder.finish()?.sync_all()
+        }
+        Some(extension @ ("bgz" | "zst")) => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Writing .{extension} files is not supported, use .gz instead"),
+        )),
+        _ => std::fs::write(path, contents),
+    }
+}
+
+/// Like [`compact_genome::io::fasta::read_fasta_file`], but decompresses the file like [`open_input`].
+pub fn read_fasta_file<AlphabetType: Alphabet, SequenceStoreType: SequenceStore<AlphabetType>>(
+    path: impl AsRef<Path>,
+    store: &mut SequenceStoreType,
+    skip_invalid_characters: bool,
+    capitalise_characters: bool,
+    skip_characters: &[bool],
+) -> Result<Vec<FastaRecord<SequenceStoreType::Handle>>, IOError> {
+    read_fasta(
+        open_input(path)?,
+        store,
+        skip_invalid_characters,
+        capitalise_characters,
+        skip_characters,
+    )
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use lib_tsalign::{
+    a_star_aligner::{
+        alignment_result::{alignment_cigar, AlignmentResult},
+        template_switch_distance::AlignmentType,
+    },
+    alignment_diff::diff_alignments,
+};
+use log::{info, warn};
+
+use crate::{compression::read_input_to_string, exit_with_error};
+
+#[derive(Args, Clone)]
+pub struct DiffArgs {
+    /// The alignment result file written with `--output` that is compared to the second one.
+    first: PathBuf,
+
+    /// The alignment result file written with `--output` that is compared to the first one.
+    second: PathBuf,
+}
+
+/// Print where two alignments of the same pair of sequences diverge as tab-separated values.
+///
+/// Coordinates refer to the aligned sequences, and each divergence contains the CIGAR of both alignments within it.
+pub fn diff(args: DiffArgs) {
+    let [first, second] = [&args.first, &args.second].map(|file| load_alignment(file));
+
+    let [first_provenance, second_provenance] =
+        [&first, &second].map(|alignment| alignment.statistics().provenance.as_ref());
+    if let (Some(first_provenance), Some(second_provenance)) = (first_provenance, second_provenance)
+    {
+        if first_provenance.reference.sha256 != second_provenance.reference.sha256
+            || first_provenance.query.sha256 != second_provenance.query.sha256
+        {
+            warn!("The results align different sequences");
+        }
+    }
+
+    let (
+        AlignmentResult::WithTarget {
+            alignment: first_alignment,
+            statistics: first_statistics,
+        },
+        AlignmentResult::WithTarget {
+            alignment: second_alignment,
+            statistics: second_statistics,
+        },
+    ) = (&first, &second)
+    else {
+        exit_with_error("Both results must contain an alignment");
+    };
+
+    let divergences = diff_alignments(first_alignment, second_alignment);
+    info!(
+        "Found {} divergences between the alignments with costs {} and {}",
+        divergences.len(),
+        first_statistics.cost,
+        second_statistics.cost
+    );
+    println!("kind\treference_start\treference_end\tquery_start\tquery_end\tfirst\tsecond");
+    for divergence in divergences {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            divergence.kind,
+            divergence.reference_range.start,
+            divergence.reference_range.end,
+            divergence.query_range.start,
+            divergence.query_range.end,
+            alignment_cigar(&divergence.first),
+            alignment_cigar(&divergence.second),
+        );
+    }
+}
+
+fn load_alignment(file: &Path) -> AlignmentResult<AlignmentType> {
+    info!(file:% = file.display(); "Loading alignment result {file:?}");
+    let input = read_input_to_string(file)
+        .unwrap_or_else(|error| exit_with_error(format!("Error reading {file:?}: {error}")));
+    toml::from_str(&input).unwrap_or_else(|error| {
+        exit_with_error(format!("Error parsing alignment result {file:?}: {error}"))
+    })
+}
@@ -0,0 +1,38 @@
+use clap::ValueEnum;
+use lib_tsalign::guide::{AlignmentGuide, GuideAnchor};
+use log::info;
+
+use crate::{compression::open_input, exit_with_error, Cli};
+
+#[derive(Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum GuideFormatSelector {
+    Paf,
+    Tsv,
+}
+
+/// Reads the anchors of the external guide, if given, and builds the corridor around them.
+pub fn read_guide(
+    cli: &Cli,
+    reference_length: usize,
+    query_length: usize,
+) -> Option<AlignmentGuide> {
+    let path = cli.guide.as_ref()?;
+    let reader = open_input(path)
+        .unwrap_or_else(|error| exit_with_error(format!("Error opening guide {path:?}: {error}")));
+    let anchors = match cli.guide_format {
+        GuideFormatSelector::Paf => GuideAnchor::read_paf(reader),
+        GuideFormatSelector::Tsv => GuideAnchor::read_tsv(reader),
+    }
+    .unwrap_or_else(|error| exit_with_error(format!("Error reading guide {path:?}: {error}")));
+
+    let anchor_count = anchors.len();
+    let guide = AlignmentGuide::new(anchors, reference_length, query_length, cli.guide_band);
+    info!(
+        "Guiding the alignment along {} of {anchor_count} anchors with a band of {}, leaving {} primary positions",
+        guide.anchor_count(),
+        cli.guide_band,
+        guide.area()
+    );
+
+    Some(guide)
+}
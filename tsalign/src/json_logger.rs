@@ -0,0 +1,145 @@
+use std::{
+    fmt::Write as _,
+    io::Write as _,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{
+    kv::{self, Key, Value, VisitSource, VisitValue},
+    LevelFilter, Log, Metadata, Record, SetLoggerError,
+};
+
+/// A logger that writes each log record as a single line JSON object to stderr.
+///
+/// The object contains the fields `timestamp` (seconds since the Unix epoch), `level`, `target` and `message`,
+/// followed by the key-value pairs of the record.
+pub struct JsonLogger {
+    level: LevelFilter,
+}
+
+impl JsonLogger {
+    pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_max_level(level);
+        log::set_boxed_logger(Box::new(Self { level }))
+    }
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_record(record);
+        let mut stderr = std::io::stderr().lock();
+        // Logging must not panic if stderr is closed.
+        let _ = writeln!(stderr, "{line}");
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+fn format_record(record: &Record) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64());
+
+    let mut line = String::new();
+    write!(line, "{{\"timestamp\":{timestamp:.6},\"level\":").unwrap();
+    write_json_string(&mut line, record.level().as_str());
+    line.push_str(",\"target\":");
+    write_json_string(&mut line, record.target());
+    line.push_str(",\"message\":");
+    write_json_string(&mut line, &record.args().to_string());
+    // Writing to a string never fails.
+    record
+        .key_values()
+        .visit(&mut KeyValueWriter { line: &mut line })
+        .unwrap();
+    line.push('}');
+    line
+}
+
+struct KeyValueWriter<'line> {
+    line: &'line mut String,
+}
+
+impl<'kvs> VisitSource<'kvs> for KeyValueWriter<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.line.push(',');
+        write_json_string(self.line, key.as_str());
+        self.line.push(':');
+        value.visit(ValueWriter { line: self.line })
+    }
+}
+
+struct ValueWriter<'line> {
+    line: &'line mut String,
+}
+
+impl VisitValue<'_> for ValueWriter<'_> {
+    fn visit_any(&mut self, value: Value) -> Result<(), kv::Error> {
+        write_json_string(self.line, &value.to_string());
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), kv::Error> {
+        self.line.push_str("null");
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        write!(self.line, "{value}").unwrap();
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        write!(self.line, "{value}").unwrap();
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+        // JSON has no representation for infinite or NaN numbers.
+        if value.is_finite() {
+            write!(self.line, "{value}").unwrap();
+        } else {
+            self.line.push_str("null");
+        }
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        write!(self.line, "{value}").unwrap();
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+        write_json_string(self.line, value);
+        Ok(())
+    }
+}
+
+/// Appends `string` to `line` as a quoted and escaped JSON string.
+pub fn write_json_string(line: &mut String, string: &str) {
+    line.push('"');
+    for character in string.chars() {
+        match character {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            character if character.is_control() => {
+                write!(line, "\\u{:04x}", u32::from(character)).unwrap()
+            }
+            character => line.push(character),
+        }
+    }
+    line.push('"');
+}
@@ -0,0 +1,95 @@
+use std::{fmt::Write, path::PathBuf};
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        DefaultSequenceStore,
+    },
+    interface::{alphabet::Alphabet, sequence_store::SequenceStore},
+};
+use lib_tsalign::kmer_spectrum::KmerSpectrum;
+use log::info;
+use traitsequence::interface::Sequence;
+
+use crate::{
+    compression::read_fasta_file, exit_with_error, json_logger::write_json_string, InputAlphabet,
+};
+
+#[derive(Args, Clone)]
+pub struct KmersArgs {
+    /// The fasta files containing the sequences to analyse.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+
+    /// The alphabet present in the input files.
+    #[clap(long, short = 'a', default_value = "dna-n")]
+    alphabet: InputAlphabet,
+
+    /// The k-mer size.
+    ///
+    /// The default is the chaining block size, for which the repetitiveness decides if `--alignment-method auto` masks anchors.
+    #[clap(long, short = 'k', default_value = "20")]
+    k: usize,
+}
+
+/// Print the k-mer spectrum and repetitiveness of each input sequence as one JSON object per line.
+///
+/// The spectrum is a list of pairs of a number of occurrences and the number of distinct k-mers occurring that often.
+pub fn kmers(args: KmersArgs) {
+    match args.alphabet {
+        InputAlphabet::Dna => kmers_with_alphabet::<DnaAlphabet>(args),
+        InputAlphabet::DnaN => kmers_with_alphabet::<DnaAlphabetOrN>(args),
+        InputAlphabet::Rna => kmers_with_alphabet::<RnaAlphabet>(args),
+        InputAlphabet::RnaN => kmers_with_alphabet::<RnaAlphabetOrN>(args),
+        InputAlphabet::DnaIupac => kmers_with_alphabet::<DnaIupacNucleicAcidAlphabet>(args),
+        InputAlphabet::RnaIupac => kmers_with_alphabet::<RnaIupacNucleicAcidAlphabet>(args),
+    }
+}
+
+fn kmers_with_alphabet<AlphabetType: Alphabet + 'static>(args: KmersArgs) {
+    let mut sequence_store = DefaultSequenceStore::<AlphabetType>::new();
+
+    for file in &args.files {
+        info!("Loading fasta file {file:?}");
+        let records =
+            read_fasta_file(file, &mut sequence_store, false, true, &[]).unwrap_or_else(|error| {
+                exit_with_error(format!("Error loading fasta file {file:?}: {error}"))
+            });
+
+        for record in records {
+            let sequence = sequence_store.get(&record.sequence_handle);
+            let spectrum = KmerSpectrum::new(sequence, args.k);
+
+            let mut line = String::from("{\"file\":");
+            write_json_string(&mut line, &file.to_string_lossy());
+            line.push_str(",\"record\":");
+            write_json_string(&mut line, &record.id);
+            // Writing to a string never fails.
+            write!(
+                line,
+                ",\"length\":{},\"k\":{},\"distinct_kmers\":{},\"total_kmers\":{},\"max_occurrences\":{},\"repetitiveness\":{:.6},\"spectrum\":[",
+                sequence.len(),
+                spectrum.k(),
+                spectrum.distinct_kmers(),
+                spectrum.total_kmers(),
+                spectrum.max_occurrences(),
+                spectrum.repetitiveness(),
+            )
+            .unwrap();
+            for (index, (occurrences, kmers)) in spectrum.spectrum().iter().enumerate() {
+                if index > 0 {
+                    line.push(',');
+                }
+                write!(line, "[{occurrences},{kmers}]").unwrap();
+            }
+            line.push_str("]}");
+            println!("{line}");
+        }
+    }
+}
@@ -1,13 +1,14 @@
 #![deny(clippy::mod_module_files)]
 
 use std::{
-    fmt::Debug,
-    fs::File,
-    io::{BufReader, Read},
-    path::PathBuf,
+    fmt::{Debug, Display},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
-use clap::{Args, Parser, ValueEnum};
+use batch::{batch, BatchArgs};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use compact_genome::{
     implementation::{
         alphabets::{
@@ -16,93 +17,575 @@ use compact_genome::{
             rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
             rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
         },
+        vec_sequence_store::VectorSequenceStore,
         DefaultSequenceStore,
     },
     interface::{alphabet::Alphabet, sequence::GenomeSequence, sequence_store::SequenceStore},
-    io::fasta::read_fasta_file,
 };
+use compression::{read_fasta_file, read_input_to_string, write_output};
+use diff::{diff, DiffArgs};
+use guide::GuideFormatSelector;
+use json_logger::JsonLogger;
+use kmers::{kmers, KmersArgs};
+use lib_tsalign::a_star_aligner::PHASE_LOG_TARGET;
 use lib_tsalign::{
-    a_star_aligner::{gap_affine_edit_distance, gap_affine_edit_distance_a_star_align},
+    a_star_aligner::{
+        alignment_result::{StatisticsFormat, StatisticsMode},
+        gap_affine_edit_distance, gap_affine_edit_distance_a_star_align,
+        template_switch_distance::{
+            secondary_score::SecondaryScorePolicy, strategies::chaining::RepeatMaskThreshold,
+        },
+        AStarTieBreaking, SearchSettings,
+    },
     alignment_configuration::AlignmentConfiguration,
     alignment_matrix::AlignmentMatrix,
-    costs::cost::Cost,
+    alignment_method::{AlignmentMethod, AutoSelection},
+    config::presets::TemplateSwitchConfigPreset,
+    costs::{ambiguity::AmbiguitySemantics, cost::Cost},
+    preprocessing::CoordinateMap,
+    provenance::{sha256_hex, ResultProvenance, SequenceProvenance},
 };
 use log::{debug, info, LevelFilter};
+use msa::{msa, MsaArgs};
+use preprocessing::preprocess_input;
+use sequence_selection::SequenceSelection;
+use show::{show, ShowArgs};
 use simplelog::{ColorChoice, TermLogger, TerminalMode};
+use sketch::{sketch, SketchArgs};
+use soft_mask::SoftMaskSelector;
+use summarize::{summarize, SummarizeArgs};
 use template_switch_distance_type_selectors::{
-    align_a_star_template_switch_distance, TemplateSwitchChainingStrategySelector,
+    align_a_star_template_switch_distance, AnchorSchemeSelector, ChainingGraphFormatSelector,
+    SharedBufferPool, TemplateSwitchChainingStrategySelector, TemplateSwitchDirectionSelector,
     TemplateSwitchMinLengthStrategySelector, TemplateSwitchNodeOrdStrategySelector,
+    TemplateSwitchSearchAlgorithmSelector,
 };
+use traitsequence::interface::Sequence;
 
+#[cfg(feature = "count-allocations")]
+mod allocation_counter;
+mod batch;
+mod compression;
+mod diff;
+mod guide;
+mod json_logger;
+mod kmers;
+mod msa;
+mod preprocessing;
+mod sequence_selection;
+mod show;
+mod sketch;
+mod soft_mask;
+mod summarize;
 mod template_switch_distance_type_selectors;
 
-#[derive(Parser)]
+#[derive(Parser, Clone)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
-    #[clap(long, short = 'l', default_value = "info")]
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    #[clap(long, short = 'l', default_value = "info", global = true)]
     log_level: LevelFilter,
 
+    /// The format of the log output on stderr.
+    ///
+    /// `json` writes one JSON object per line, including the key-value pairs of structured events,
+    /// such as the duration of each phase of the alignment.
+    #[clap(long, default_value = "text", global = true)]
+    log_format: LogFormat,
+
     #[command(flatten)]
     input: CliInput,
 
     /// The file to store the alignment statistics in toml format.
+    ///
+    /// If the file name ends in `.gz`, the file is compressed with gzip.
     #[clap(long, short = 'o')]
     output: Option<PathBuf>,
 
+    /// The file to store the alignment in the multiple alignment format (MAF).
+    ///
+    /// The alignment is split into a block for each template switch and each colinear segment between them,
+    /// where the secondary of a template switch is on the reverse strand.
+    /// Coordinates refer to the aligned sequences, i.e. to the selected range or strand if one was selected.
+    /// Only supported by the template switch aligner.
+    /// If the file name ends in `.gz`, the file is compressed with gzip.
+    #[clap(long)]
+    maf: Option<PathBuf>,
+
+    /// Write this character instead of `N` in the MAF output, keeping its case.
+    #[clap(long)]
+    maf_n_character: Option<char>,
+
+    /// Write characters that directly follow characters removed by `--clip-adapter` or `--max-n-run`
+    /// in lower case in the MAF output.
+    #[clap(long)]
+    maf_mark_skipped: bool,
+
     /// The alphabet present in the input files.
     ///
     /// This must also match the alphabet used in the config.
     #[clap(long, short = 'a', default_value = "dna-n")]
     alphabet: InputAlphabet,
 
+    /// How the input sequences are stored in memory while aligning.
+    ///
+    /// With `packed`, each character takes only as many bits as needed for the alphabet,
+    /// which keeps large references small and cache-friendly.
+    /// With `vector`, each character takes one byte, which makes accessing characters cheaper.
+    #[clap(long, default_value = "packed")]
+    sequence_store: SequenceStoreSelector,
+
     /// A string of (ASCII) characters that should be skipped in the input fasta.
     ///
     /// For example, `-` characters caused by alignment hints can be skipped this way.
     #[clap(long, default_value = "")]
     skip_characters: String,
 
+    /// An adapter sequence that is clipped from the end of the input sequences before aligning, together with everything after it.
+    ///
+    /// Can be given multiple times, and the leftmost adapter is clipped.
+    /// Prefixes of an adapter at the very end of a sequence are clipped if they have at least `--min-adapter-overlap` characters.
+    #[clap(long)]
+    clip_adapter: Vec<String>,
+
+    /// The minimum number of characters of a `--clip-adapter` prefix that need to match at the end of a sequence.
+    #[clap(long, default_value = "8")]
+    min_adapter_overlap: usize,
+
+    /// Shorten runs of more `N` characters than this in the input sequences before aligning.
+    #[clap(long)]
+    max_n_run: Option<usize>,
+
+    /// How ambiguous characters such as `N` or other IUPAC codes are aligned by the template switch aligner.
+    ///
+    /// One of `match-any`, `mismatch-always` or `probabilistic`.
+    /// The costs of ambiguous characters are then derived from the costs of the bases they represent,
+    /// replacing the costs given for them in the config.
+    /// If not given, the costs from the config are used as they are.
+    #[clap(long)]
+    ambiguity_semantics: Option<AmbiguitySemantics>,
+
+    /// How soft-masked (lower case) regions of the input are treated by the template switch aligner.
+    ///
+    /// Template switches entering where the reference or the query is masked can be forbidden,
+    /// or their base cost can be multiplied by `--soft-mask-cost-factor`.
+    #[clap(long, default_value = "ignore")]
+    soft_mask: SoftMaskSelector,
+
+    /// The factor by which the base cost of template switches entering within soft-masked regions is multiplied.
+    ///
+    /// Only used with `--soft-mask scale-template-switch-cost`.
+    #[clap(long, default_value = "2")]
+    soft_mask_cost_factor: u64,
+
+    /// Remove identical prefixes and suffixes of the reference and the query before running the template switch aligner.
+    ///
+    /// The removed ends are re-attached to the alignment as matches.
+    /// This makes aligning pairs that differ only in the middle much faster,
+    /// but template switches can then not use the removed ends as secondary.
+    #[clap(long)]
+    trim_identical_ends: bool,
+
+    /// The number of identical characters that `--trim-identical-ends` keeps next to the differing middle.
+    ///
+    /// If not given, it is derived from the maximum offset, length and length difference of template switches in the config.
+    #[clap(long)]
+    trim_margin: Option<usize>,
+
+    /// Move the entrances and exits of template switches to canonical positions after aligning.
+    ///
+    /// Among the positions within `--polish-window` characters that have the lowest cost, the leftmost one is chosen,
+    /// like when left-aligning indels.
+    /// This makes the reported template switches comparable between runs.
+    #[clap(long)]
+    polish_template_switches: bool,
+
+    /// The number of characters by which `--polish-template-switches` moves entrances and exits at most.
+    #[clap(long, default_value = "5")]
+    polish_window: usize,
+
+    /// Align the reference to itself and report only alignments with at least one template switch.
+    ///
+    /// This excludes the trivial alignment along the main diagonal,
+    /// such that the reported template switches are candidate hairpins or inverted repeats of the reference.
+    /// The query is ignored, and only the template switch aligner is supported.
+    #[clap(long, conflicts_with = "trim_identical_ends")]
+    self_alignment: bool,
+
+    /// A file of anchors proposed by an external mapper or chainer, such as minimap2.
+    ///
+    /// The primary alignment of the template switch aligner is then restricted to a corridor around the path through the anchors,
+    /// while template switches may still leave it.
+    /// Coordinates refer to the aligned sequences, so the guide cannot be combined with trimming identical ends.
+    #[clap(long, conflicts_with = "trim_identical_ends")]
+    guide: Option<PathBuf>,
+
+    /// The format of `--guide`.
+    ///
+    /// With `paf`, the `cg:Z:` CIGARs of forward records are split into anchors, where the PAF target is the reference.
+    /// With `tsv`, each line contains the zero-based `reference_start`, `query_start` and `length` of an anchor.
+    #[clap(long, default_value = "paf")]
+    guide_format: GuideFormatSelector,
+
+    /// The number of query characters by which the primary alignment may deviate from the path through the `--guide` anchors.
+    #[clap(long, default_value = "50")]
+    guide_band: usize,
+
     /// A directory containing the configuration files.
     ///
     /// See the README for its layout.
     #[clap(long, short = 'c', default_value = "sample_tsa_config")]
     configuration_directory: PathBuf,
 
+    /// Use costs built into the binary for the template switch aligner instead of the `config.tsa` in the configuration directory.
+    ///
+    /// One of `default`, `sensitive`, `fast` or `long-read`.
+    /// Presets use the same costs for all characters, so they work with any alphabet.
+    #[clap(long)]
+    preset: Option<TemplateSwitchConfigPreset>,
+
+    /// Parse the `config.tsa` leniently.
+    ///
+    /// Sections and their entries may then be given in any order,
+    /// and missing sections are taken from the `default` preset.
+    #[clap(long, conflicts_with = "preset")]
+    lenient_config: bool,
+
+    /// The alignment method.
+    ///
+    /// `auto` chooses the template switch aligner and its strategies based on the sequence lengths
+    /// and their divergence estimated from shared k-mers, and logs the reasons for its choice.
     #[clap(long, default_value = "a-star-template-switch")]
-    alignment_method: AlignmentMethod,
+    alignment_method: AlignmentMethodSelector,
 
     #[clap(long, default_value = "anti-diagonal")]
     ts_node_ord_strategy: TemplateSwitchNodeOrdStrategySelector,
 
+    /// The TOML file with the weights of the `learned` node ordering strategy.
+    ///
+    /// The weights are fitted by a benchmark harness to the user's typical data.
+    /// Weights missing from the file are zero.
+    #[clap(long, required_if_eq("ts_node_ord_strategy", "learned"))]
+    ts_node_ord_model: Option<PathBuf>,
+
     #[clap(long, default_value = "lookahead")]
     ts_min_length_strategy: TemplateSwitchMinLengthStrategySelector,
 
     #[clap(long, default_value = "none")]
     ts_chaining_strategy: TemplateSwitchChainingStrategySelector,
 
+    /// Write the chaining graph to this file before aligning, to debug which chains are found.
+    ///
+    /// The graph contains the anchors, all feasible transitions between them with their costs, and highlights the chosen chain.
+    /// It is written independently of `--ts-chaining-strategy`, and has an edge for each pair of chainable anchors.
+    #[clap(long)]
+    chaining_graph: Option<PathBuf>,
+
+    /// The file format of `--chaining-graph`.
+    #[clap(long, default_value = "dot")]
+    chaining_graph_format: ChainingGraphFormatSelector,
+
+    /// Ignore the chaining anchors of blocks that match more often than this, to keep chaining fast on repetitive sequences.
+    ///
+    /// Either a number of matches, or `auto` to choose it from the spectrum of how often the blocks match.
+    /// By default, no anchors are ignored.
+    /// With masking, the chaining lower bound may overestimate costs in repeats, so the alignment may be suboptimal.
+    #[clap(long)]
+    mask_anchor_repeats: Option<RepeatMaskThreshold>,
+
+    /// How chaining anchors are found.
+    ///
+    /// `hpc-kmer` matches blocks after collapsing homopolymers, which finds anchors in noisy long reads with homopolymer length errors.
+    /// Its anchors may span homopolymers that are not matched exactly, so the alignment may be suboptimal.
+    #[clap(long, default_value = "kmer")]
+    anchor_scheme: AnchorSchemeSelector,
+
+    /// The kinds of template switches searched by the template switch aligner.
+    ///
+    /// A comma-separated list of `all`, `reference-primary`, `query-primary`, `same-sequence` and `cross-sequence`,
+    /// where `same-sequence` selects template switches whose secondary is their primary sequence.
+    /// Template switches of any listed kind are searched, and restricting the kinds makes the search faster.
+    #[clap(long, default_value = "all", value_delimiter = ',')]
+    ts_directions: Vec<TemplateSwitchDirectionSelector>,
+
+    /// The search algorithm used by the template switch aligner.
+    ///
+    /// `anytime` runs weighted A* with decreasing weights to quickly find a first alignment and then refine it.
+    /// `ida-star` uses memory only linear in the length of the alignment, but may be exponentially slower than A*.
+    #[clap(long, default_value = "a-star")]
+    ts_search_algorithm: TemplateSwitchSearchAlgorithmSelector,
+
+    /// The initial weight of the lower bound for `--ts-search-algorithm anytime`.
+    ///
+    /// The first alignment has a cost of at most this factor times the optimal cost.
+    /// Must be a finite number of at least one.
+    #[clap(long, default_value = "2.0", value_parser = parse_lower_bound_weight)]
+    anytime_initial_weight: f64,
+
+    /// The time after which `--ts-search-algorithm anytime` stops refining the alignment, e.g. `10s` or `5m`.
+    ///
+    /// A number without unit is in seconds.
+    /// A refinement that is still running at this time is aborted, and the best alignment found before is reported.
+    #[clap(long, default_value = "10", value_parser = parse_duration)]
+    anytime_time_limit: Duration,
+
     #[clap(long)]
     max_cost: Option<Cost>,
+
+    /// Count the alignments with the same cost as the reported alignment, and flag its template switches that do not occur in all of them.
+    ///
+    /// At most this many co-optimal paths through the alignment graph are enumerated to compare their template switches.
+    /// Different paths may describe the same alignment, so this may yield fewer distinct alignments.
+    /// Only supported by `--ts-search-algorithm a-star`.
+    #[clap(long)]
+    cooptimal_limit: Option<usize>,
+
+    /// Repeat the search this many times with pseudo-random tie-breaking, and flag the template switches of the reported alignment
+    /// that do not occur in all of the co-optimal alignments found this way.
+    ///
+    /// For each template switch, the number of distinct co-optimal alignments containing it is reported.
+    /// Can be combined with `--cooptimal-limit`, and is only supported by `--ts-search-algorithm a-star`.
+    #[clap(long)]
+    random_restarts: Option<usize>,
+
+    /// The seed of the pseudo-random tie-breaking of `--random-restarts`.
+    ///
+    /// Runs with the same seed find the same alignments, so `--deterministic` output is preserved.
+    #[clap(long, default_value = "0")]
+    random_restarts_seed: u64,
+
+    /// After aligning, check that no node was closed with a cost plus lower bound above the cost of the alignment.
+    ///
+    /// This is a debugging aid for lower bounds: a violation means that the lower bound overestimated somewhere,
+    /// and the path to the first violating node is reported.
+    /// Only supported by `--ts-search-algorithm a-star`.
+    #[clap(long)]
+    check_admissibility: bool,
+
+    /// After aligning with a chaining strategy, cross-check the chain cost against an exact alignment computed without chaining.
+    ///
+    /// The chain cost must not exceed the exact alignment cost, and the transitions of the chain are reported.
+    /// This is a debugging aid for chaining costs, and the exact alignment is only computed for small inputs.
+    #[clap(long)]
+    validate_chain: bool,
+
+    /// Align pairs with at most this unit-cost edit distance without template switches,
+    /// if the resulting alignment is cheaper than the base cost plus the minimum jump costs of a template switch.
+    ///
+    /// The alignment stays optimal, and skipping the template switch search saves its precomputation and lookaheads.
+    /// Pairs that are too distant or that may benefit from template switches are aligned as usual.
+    #[clap(long)]
+    fast_path_max_edit_distance: Option<usize>,
+
+    /// Log the CIGAR of each segment of the alignment as soon as it is fixed, before the search terminates.
+    ///
+    /// The segments concatenate to the alignment, excluding the ends trimmed by `--trim-identical-ends`.
+    /// They are fixed early only by `--ts-search-algorithm a-star` with a label-setting chaining strategy,
+    /// and mostly for similar sequences, since the cost of the alignment without template switches is used as upper bound.
+    #[clap(long)]
+    stream_partial_alignments: bool,
+
+    /// The policy used by the A* aligners to order open nodes with equal costs.
+    #[clap(long, default_value = "node-ord")]
+    tie_breaking: TieBreakingSelector,
+
+    /// Which co-optimal alignments the template switch aligner prefers with `--tie-breaking secondary-score`.
+    ///
+    /// `maximise-matches` prefers alignments with more matching columns,
+    /// and `minimise-template-switches` prefers alignments with fewer template switches.
+    #[clap(long, default_value = "maximise-matches")]
+    secondary_score_policy: SecondaryScorePolicySelector,
+
+    /// Make runs with the same input and options produce byte-identical output.
+    ///
+    /// Open nodes that are tied under `--tie-breaking` are closed in insertion order,
+    /// so the search order does not depend on the layout of the open list.
+    /// All hash maps whose iteration order may affect the alignment use a fixed seed, and chaining anchors are processed in sorted order.
+    /// Wall-clock timings are removed from the output statistics.
+    /// Conflicts with `--timeout` and `--ts-search-algorithm anytime`, whose results depend on the elapsed time.
+    #[clap(long, conflicts_with = "timeout")]
+    deterministic: bool,
+
+    /// Print an estimate of the peak memory usage of the template switch aligner and exit without aligning.
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Refuse to run the template switch aligner if its estimated peak memory usage exceeds this limit,
+    /// and abort it if the memory used by its open and closed lists exceeds this limit while aligning.
+    ///
+    /// The limit is given in bytes, optionally with a binary unit suffix (K, M, G or T), e.g. `16G`.
+    /// Precomputed lower bounds are not counted while aligning.
+    #[clap(long, value_parser = parse_memory_size)]
+    memory_limit: Option<usize>,
+
+    /// Abort the template switch aligner if aligning a pair takes longer than this, e.g. `30s`, `5m` or `2h`.
+    ///
+    /// A number without unit is in seconds.
+    /// The aborted alignment is reported without target, with a lower bound of its cost,
+    /// and with `--ts-search-algorithm anytime` the best alignment found so far is reported instead, if any.
+    /// In batch mode, the remaining pairs are aligned afterwards.
+    /// Ignored by `--ts-search-algorithm ida-star`.
+    #[clap(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Run the template switch aligner even if its estimated peak memory usage exceeds `--memory-limit`.
+    ///
+    /// The aligner is still aborted if its actual memory usage exceeds the limit.
+    #[clap(long)]
+    force: bool,
+
+    /// The number of decimal places of the printed statistics that are not integers, like cost per base.
+    #[clap(long, default_value = "2", global = true)]
+    statistics_precision: usize,
+
+    /// How the statistics are printed on stdout.
+    #[clap(long, default_value = "human", global = true)]
+    statistics_mode: StatisticsModeSelector,
+
+    /// Which information about the inputs is stored in the `--output` file.
+    ///
+    /// `hashes` stores the fasta file, the record id and index, and the length and SHA-256 hash of both sequences,
+    /// as well as the SHA-256 hash of the cost config.
+    /// `sequences` additionally embeds the sequences, so that `tsalign show` does not need the fasta files.
+    #[clap(long, default_value = "none")]
+    provenance: ProvenanceSelector,
+
+    /// The provenance of the input sequences, recorded after loading them unless `--provenance` is `none`.
+    #[clap(skip)]
+    input_provenance: Option<ResultProvenance>,
+
+    /// The fasta record ids of the reference and the query, set after loading them.
+    #[clap(skip)]
+    input_record_ids: [String; 2],
+
+    /// The buffers of the template switch aligner, reused by all pairs of a batch.
+    #[clap(skip)]
+    buffer_pool: SharedBufferPool,
+}
+
+#[derive(Subcommand, Clone)]
+enum CliCommand {
+    /// Estimate the similarity of sequences from MinHash sketches of their k-mers.
+    ///
+    /// This is much faster than aligning, and can be used to find the pairs that are worth aligning.
+    Sketch(SketchArgs),
+
+    /// Count how often the k-mers of sequences occur, to find out how repetitive they are.
+    ///
+    /// For each record, the k-mer spectrum and the fraction of k-mer occurrences whose k-mer is repeated are printed as a JSON object.
+    Kmers(KmersArgs),
+
+    /// Print an alignment result file written with `--output` without aligning again.
+    ///
+    /// The statistics are printed according to `--statistics-mode` and `--statistics-precision`.
+    Show(ShowArgs),
+
+    /// Align the pairs listed in a manifest file, one pair after the other.
+    ///
+    /// All options given before `batch` apply to every pair, except that the manifest can override
+    /// the cost limit and the output file of each pair.
+    Batch(BatchArgs),
+
+    /// Align the records of fasta files to a centre record and merge the alignments into a star multiple alignment.
+    ///
+    /// All options of the template switch aligner given before `msa` apply to every pair.
+    /// The alignment is printed as aligned fasta, where characters that are not aligned to a character of the centre,
+    /// e.g. because they are covered by a template switch, are written in lower case.
+    Msa(MsaArgs),
+
+    /// Count the recurrent template switch loci in many alignment result files written with `--output`.
+    ///
+    /// Template switches are clustered by their reference coordinates,
+    /// and the loci are printed as tab-separated values, ordered by the number of results containing them.
+    Summarize(SummarizeArgs),
+
+    /// Compare two alignment result files of the same pair of sequences written with `--output`.
+    ///
+    /// The alignments are split at the points of the alignment matrix visited by both,
+    /// and the stretches in which they differ, e.g. by template switches or shifted gaps, are printed as tab-separated values.
+    Diff(DiffArgs),
 }
 
-#[derive(Args)]
+#[derive(Args, Clone)]
 #[group(required = true)]
 struct CliInput {
-    /// The path to the reference fasta file.
-    #[clap(long, short = 'r', requires = "query", group = "input")]
-    reference: Option<PathBuf>,
+    /// The reference fasta file, which may be compressed with gzip or bgzip.
+    ///
+    /// A record, range and strand can be selected as `file:record:start-end:strand`,
+    /// where trailing parts may be omitted and the record may be left empty if the file contains only one record.
+    /// The range is one-based and inclusive, like in samtools regions, and the strand is `+` or `-`,
+    /// where `-` aligns the reverse complement of the range.
+    /// If a range or strand is selected, at least `--provenance hashes` is recorded in the output,
+    /// so that the coordinates in the result can be related to the record.
+    #[clap(long, short = 'r')]
+    reference: Option<SequenceSelection>,
 
-    /// The path to the query fasta file.
-    #[clap(long, short = 'q', requires = "reference", group = "input")]
-    query: Option<PathBuf>,
+    /// The query fasta file, which may be compressed with gzip or bgzip.
+    ///
+    /// A record, range and strand can be selected like for `--reference`.
+    /// Not required with `--self-alignment`.
+    #[clap(
+        long,
+        short = 'q',
+        requires = "reference",
+        required_unless_present_any = ["pair_fasta", "self_alignment"]
+    )]
+    query: Option<SequenceSelection>,
 
-    /// The path to a fasta file containing both the reference and the query.
-    #[clap(long, short = 'p', conflicts_with_all = ["reference", "query"], group = "input")]
+    /// The path to a fasta file containing both the reference and the query, which may be compressed with gzip or bgzip.
+    #[clap(long, short = 'p', conflicts_with_all = ["reference", "query"])]
     pair_fasta: Option<PathBuf>,
 }
 
-#[derive(Clone, PartialEq, Eq, ValueEnum)]
-enum AlignmentMethod {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AlignmentMethodSelector {
     Matrix,
     AStarGapAffine,
     AStarTemplateSwitch,
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum SecondaryScorePolicySelector {
+    MaximiseMatches,
+    MinimiseTemplateSwitches,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum TieBreakingSelector {
+    /// Use the node ordering strategy, e.g. `--ts-node-ord-strategy`.
+    NodeOrd,
+    /// Prefer the node that was opened last.
+    Lifo,
+    /// Prefer the node with the higher secondary score, see `--secondary-score-policy`.
+    SecondaryScore,
+    /// Prefer the node with the smaller identifier.
+    Identifier,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum ProvenanceSelector {
+    None,
+    Hashes,
+    Sequences,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum StatisticsModeSelector {
+    /// Descriptive labels and derived statistics like cache hit rates.
+    Human,
+    /// One `name = value` line per statistic, named like in the `--output` file.
+    Machine,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line.
+    Json,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, ValueEnum)]
@@ -115,34 +598,103 @@ enum InputAlphabet {
     RnaIupac,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, ValueEnum)]
+enum SequenceStoreSelector {
+    Packed,
+    Vector,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
-    TermLogger::init(
-        cli.log_level,
-        Default::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )
+    match cli.log_format {
+        LogFormat::Text => TermLogger::init(
+            cli.log_level,
+            Default::default(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        ),
+        LogFormat::Json => JsonLogger::init(cli.log_level),
+    }
     .unwrap();
 
-    if cli.alignment_method != AlignmentMethod::AStarTemplateSwitch
-        && cli.alphabet != InputAlphabet::Dna
+    let batch_args = match cli.command.take() {
+        Some(CliCommand::Sketch(args)) => {
+            sketch(args);
+            return;
+        }
+        Some(CliCommand::Kmers(args)) => {
+            kmers(args);
+            return;
+        }
+        Some(CliCommand::Show(args)) => {
+            show(args, cli.statistics_format());
+            return;
+        }
+        Some(CliCommand::Summarize(args)) => {
+            summarize(args);
+            return;
+        }
+        Some(CliCommand::Diff(args)) => {
+            diff(args);
+            return;
+        }
+        Some(CliCommand::Msa(args)) => {
+            msa(cli, args);
+            return;
+        }
+        Some(CliCommand::Batch(args)) => Some(args),
+        None => None,
+    };
+
+    if !matches!(
+        cli.alignment_method,
+        AlignmentMethodSelector::AStarTemplateSwitch | AlignmentMethodSelector::Auto
+    ) && cli.alphabet != InputAlphabet::Dna
     {
-        panic!("Unsupported alphabet type: {:?}", cli.alphabet);
+        exit_with_error(format!("Unsupported alphabet type: {:?}", cli.alphabet));
     }
 
-    match cli.alphabet {
-        InputAlphabet::Dna => execute_with_alphabet::<DnaAlphabet>(cli),
-        InputAlphabet::DnaN => execute_with_alphabet::<DnaAlphabetOrN>(cli),
-        InputAlphabet::Rna => execute_with_alphabet::<RnaAlphabet>(cli),
-        InputAlphabet::RnaN => execute_with_alphabet::<RnaAlphabetOrN>(cli),
-        InputAlphabet::DnaIupac => execute_with_alphabet::<DnaIupacNucleicAcidAlphabet>(cli),
-        InputAlphabet::RnaIupac => execute_with_alphabet::<RnaIupacNucleicAcidAlphabet>(cli),
+    if let Some(batch_args) = batch_args {
+        batch(cli, batch_args);
+    } else {
+        match cli.alphabet {
+            InputAlphabet::Dna => execute_with_alphabet::<DnaAlphabet>(cli),
+            InputAlphabet::DnaN => execute_with_alphabet::<DnaAlphabetOrN>(cli),
+            InputAlphabet::Rna => execute_with_alphabet::<RnaAlphabet>(cli),
+            InputAlphabet::RnaN => execute_with_alphabet::<RnaAlphabetOrN>(cli),
+            InputAlphabet::DnaIupac => execute_with_alphabet::<DnaIupacNucleicAcidAlphabet>(cli),
+            InputAlphabet::RnaIupac => execute_with_alphabet::<RnaIupacNucleicAcidAlphabet>(cli),
+        }
     }
+
+    #[cfg(feature = "count-allocations")]
+    info!(
+        "Peak heap memory: {}",
+        lib_tsalign::a_star_aligner::memory_estimate::format_bytes(
+            allocation_counter::peak_allocated_bytes()
+        )
+    );
 }
 
-fn execute_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(cli: Cli) {
+/// Prints the error that ends the program and exits with a non-zero status.
+///
+/// This is used for errors caused by the input or the environment, which are not bugs and hence should not panic.
+fn exit_with_error(message: impl Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+/// Reads and parses a toml config file, exiting with an error if this fails.
+fn read_toml_config<Config: serde::de::DeserializeOwned>(path: &Path) -> Config {
+    let config = read_input_to_string(path)
+        .unwrap_or_else(|error| exit_with_error(format!("Error reading config {path:?}: {error}")));
+    toml::from_str(&config)
+        .unwrap_or_else(|error| exit_with_error(format!("Error parsing config {path:?}: {error}")))
+}
+
+/// Returns a lookup table of the ASCII characters that should be skipped when reading the input.
+fn skip_characters(cli: &Cli) -> Vec<bool> {
     let mut skip_characters = Vec::new();
     for character in cli.skip_characters.bytes().map(usize::from) {
         if skip_characters.len() <= character {
@@ -150,11 +702,35 @@ fn execute_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
         }
         skip_characters[character] = true;
     }
-    let skip_characters = skip_characters;
+    skip_characters
+}
+
+fn execute_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(cli: Cli) {
+    match cli.sequence_store {
+        SequenceStoreSelector::Packed => {
+            execute_with_sequence_store(cli, DefaultSequenceStore::<AlphabetType>::new)
+        }
+        SequenceStoreSelector::Vector => {
+            execute_with_sequence_store(cli, VectorSequenceStore::<AlphabetType>::new)
+        }
+    }
+}
 
-    let mut sequence_store = DefaultSequenceStore::<AlphabetType>::new();
-    let sequences = if let Some(pair_fasta) = &cli.input.pair_fasta {
-        info!("Loading pair file {pair_fasta:?}");
+fn execute_with_sequence_store<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
+    SequenceStoreType: SequenceStore<AlphabetType>,
+>(
+    cli: Cli,
+    new_sequence_store: fn() -> SequenceStoreType,
+) where
+    SequenceStoreType::Handle: Clone,
+{
+    let parsing_start_time = Instant::now();
+    let skip_characters = skip_characters(&cli);
+
+    let mut sequence_store = new_sequence_store();
+    let (handles, inputs) = if let Some(pair_fasta) = &cli.input.pair_fasta {
+        info!(file:% = pair_fasta.display(); "Loading pair file {pair_fasta:?}");
         let sequences = read_fasta_file(
             pair_fasta,
             &mut sequence_store,
@@ -162,51 +738,181 @@ fn execute_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
             true,
             &skip_characters,
         )
-        .unwrap_or_else(|error| panic!("Error loading pair file: {error}"));
-
-        assert_eq!(
-            sequences.len(),
-            2,
-            "Pair sequence file contains not exactly two records"
-        );
+        .unwrap_or_else(|error| exit_with_error(format!("Error loading pair file: {error}")));
 
-        sequences
+        let [reference, query]: [_; 2] =
+            sequences.try_into().unwrap_or_else(|sequences: Vec<_>| {
+                exit_with_error(format!(
+                    "Pair sequence file contains {} records instead of exactly two",
+                    sequences.len()
+                ))
+            });
+        let [(reference_handle, reference_input), (query_handle, query_input)] =
+            [(reference, 0), (query, 1)].map(|(record, record_index)| {
+                (
+                    record.sequence_handle,
+                    InputRecord {
+                        file: pair_fasta.clone(),
+                        record_id: record.id,
+                        record_index,
+                        range: None,
+                        reverse_complement: false,
+                        coordinate_map: None,
+                    },
+                )
+            });
+        (
+            [reference_handle, query_handle],
+            [reference_input, query_input],
+        )
+    } else if let (Some(reference), None) = (&cli.input.reference, &cli.input.query) {
+        // Only allowed with `--self-alignment`, which replaces the query with the reference below.
+        info!("Loading reference {reference}");
+        let (reference_handle, reference_input) =
+            reference.load(&mut sequence_store, &skip_characters);
+        (
+            [reference_handle.clone(), reference_handle],
+            [reference_input.clone(), reference_input],
+        )
     } else if let (Some(reference), Some(query)) = (&cli.input.reference, &cli.input.query) {
-        info!("Loading reference file {reference:?}");
-        let mut sequences = read_fasta_file(
-            reference,
-            &mut sequence_store,
-            false,
-            true,
-            &skip_characters,
+        info!("Loading reference {reference}");
+        let (reference_handle, reference_input) =
+            reference.load(&mut sequence_store, &skip_characters);
+        info!("Loading query {query}");
+        let (query_handle, query_input) = query.load(&mut sequence_store, &skip_characters);
+        (
+            [reference_handle, query_handle],
+            [reference_input, query_input],
         )
-        .unwrap();
-        assert_eq!(
-            sequences.len(),
-            1,
-            "Reference sequence file contains not exactly one record"
-        );
+    } else {
+        exit_with_error("No fasta input file given")
+    };
 
-        info!("Loading query file {query:?}");
-        sequences.extend(
-            read_fasta_file(query, &mut sequence_store, false, true, &skip_characters).unwrap(),
-        );
-        assert_eq!(
-            sequences.len(),
-            1,
-            "Query sequence file contains not exactly one record"
-        );
+    let [reference_handle, query_handle] = handles;
+    let [reference_input, query_input] = inputs;
+    let [(reference_handle, reference_input), (query_handle, query_input)] = [
+        (reference_handle, reference_input),
+        (query_handle, query_input),
+    ]
+    .map(|(handle, input)| preprocess_input(&cli, &mut sequence_store, handle, input));
+    let inputs = [reference_input, query_input];
+
+    let reference = sequence_store.get(&reference_handle);
+    let query = sequence_store.get(&query_handle);
+    let duration = parsing_start_time.elapsed().as_secs_f64();
+    info!(
+        target: PHASE_LOG_TARGET,
+        phase = "parsing",
+        seconds = duration,
+        reference_length = reference.len(),
+        query_length = query.len();
+        "Parsed the input in {duration:.3}s"
+    );
+
+    align_sequences(cli, reference, query, inputs);
+}
+
+/// The fasta record an aligned sequence was loaded from.
+#[derive(Clone)]
+struct InputRecord {
+    file: PathBuf,
+    record_id: String,
+    record_index: usize,
+    /// The aligned range of the record, if not the whole record was aligned.
+    range: Option<Range<usize>>,
+    /// True if the reverse complement of the range was aligned.
+    reverse_complement: bool,
+    /// Maps the aligned sequence back to the range or strand, if preprocessing removed characters from it.
+    coordinate_map: Option<CoordinateMap>,
+}
 
-        sequences
+/// Aligns a pair of loaded sequences with the alignment method selected in `cli`.
+fn align_sequences<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    mut cli: Cli,
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+    inputs: [InputRecord; 2],
+) {
+    let (query, inputs) = if cli.self_alignment {
+        info!("Aligning the reference to itself");
+        let [reference_input, _] = inputs;
+        (reference, [reference_input.clone(), reference_input])
     } else {
-        panic!("No fasta input file given")
+        (query, inputs)
     };
+    cli.input_record_ids = inputs.each_ref().map(|input| input.record_id.clone());
+
+    // Without provenance, the coordinates of a selected range or strand or of a preprocessed sequence could not be related to the record.
+    if cli.provenance == ProvenanceSelector::None
+        && inputs.iter().any(|input| {
+            input.range.is_some() || input.reverse_complement || input.coordinate_map.is_some()
+        })
+    {
+        info!("Recording provenance hashes because a range or strand was selected or a sequence was preprocessed");
+        cli.provenance = ProvenanceSelector::Hashes;
+    }
 
-    let reference = sequence_store.get(&sequences[0].sequence_handle);
-    let query = sequence_store.get(&sequences[1].sequence_handle);
+    if cli.provenance != ProvenanceSelector::None {
+        let embed_sequences = cli.provenance == ProvenanceSelector::Sequences;
+        let [reference_input, query_input] = inputs;
+        let [reference_provenance, query_provenance] =
+            [(reference, reference_input), (query, query_input)].map(|(sequence, input)| {
+                let mut provenance = SequenceProvenance::new(
+                    Some(input.file.display().to_string()),
+                    input.record_id,
+                    input.record_index,
+                    sequence,
+                    embed_sequences,
+                );
+                provenance.range = input.range.map(|range| (range.start, range.end));
+                provenance.reverse_complement = input.reverse_complement;
+                provenance.coordinate_map = input.coordinate_map;
+                provenance
+            });
+        cli.input_provenance = Some(ResultProvenance {
+            reference: reference_provenance,
+            query: query_provenance,
+            config_sha256: None,
+        });
+    }
 
     debug!("Choosing alignment method...");
-    match cli.alignment_method {
+    let (alignment_method, auto_selection) = AlignmentMethod::from(cli.alignment_method)
+        .select(reference, query, cli.self_alignment)
+        .unwrap_or_else(|error| {
+            exit_with_error(format!("Error choosing alignment method: {error}"))
+        });
+    if let Some(AutoSelection {
+        chaining,
+        anytime,
+        anchor_repeat_mask,
+    }) = auto_selection
+    {
+        cli.ts_chaining_strategy = chaining.into();
+        // An explicitly given threshold takes precedence.
+        cli.mask_anchor_repeats = cli.mask_anchor_repeats.or(anchor_repeat_mask);
+        cli.ts_search_algorithm = if anytime && !cli.deterministic {
+            TemplateSwitchSearchAlgorithmSelector::Anytime
+        } else {
+            TemplateSwitchSearchAlgorithmSelector::AStar
+        };
+    }
+
+    if cli.deterministic
+        && matches!(
+            cli.ts_search_algorithm,
+            TemplateSwitchSearchAlgorithmSelector::Anytime
+        )
+    {
+        exit_with_error(
+            "--deterministic is not supported by --ts-search-algorithm anytime, whose result depends on the elapsed time",
+        );
+    }
+
+    match alignment_method {
         AlignmentMethod::Matrix => align_matrix(cli, reference, query),
         AlignmentMethod::AStarGapAffine => {
             align_a_star_gap_affine_edit_distance(cli, reference, query)
@@ -214,6 +920,7 @@ fn execute_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
         AlignmentMethod::AStarTemplateSwitch => {
             align_a_star_template_switch_distance(cli, reference, query)
         }
+        AlignmentMethod::Auto => unreachable!("The alignment method was selected above."),
     }
 }
 
@@ -226,7 +933,7 @@ fn align_matrix<
     query: &SubsequenceType,
 ) {
     if cli.output.is_some() {
-        panic!("Outputting statistics not supported by matrix alignment");
+        exit_with_error("Outputting statistics not supported by matrix alignment");
     }
 
     #[derive(serde::Deserialize)]
@@ -238,10 +945,7 @@ fn align_matrix<
 
     let mut config_path = cli.configuration_directory.clone();
     config_path.push("matrix.toml");
-    let mut config_file = BufReader::new(File::open(config_path).unwrap());
-    let mut config = String::new();
-    config_file.read_to_string(&mut config).unwrap();
-    let matrix_config: MatrixConfig = toml::from_str(&config).unwrap();
+    let matrix_config: MatrixConfig = read_toml_config(&config_path);
 
     let configuration = AlignmentConfiguration {
         match_cost: matrix_config.match_cost.into(),
@@ -263,7 +967,7 @@ fn align_a_star_gap_affine_edit_distance<
     reference: &SubsequenceType,
     query: &SubsequenceType,
 ) {
-    #[derive(serde::Deserialize)]
+    #[derive(serde::Serialize, serde::Deserialize)]
     struct GapAffineConfig {
         match_cost: u64,
         substitution_cost: u64,
@@ -273,12 +977,9 @@ fn align_a_star_gap_affine_edit_distance<
 
     let mut config_path = cli.configuration_directory.clone();
     config_path.push("a_star_gap_affine.toml");
-    let mut config_file = BufReader::new(File::open(config_path).unwrap());
-    let mut config = String::new();
-    config_file.read_to_string(&mut config).unwrap();
-    let gap_affine_config: GapAffineConfig = toml::from_str(&config).unwrap();
+    let gap_affine_config: GapAffineConfig = read_toml_config(&config_path);
 
-    let alignment = gap_affine_edit_distance_a_star_align(
+    let mut alignment = gap_affine_edit_distance_a_star_align(
         reference,
         query,
         gap_affine_edit_distance::ScoringTable {
@@ -287,13 +988,139 @@ fn align_a_star_gap_affine_edit_distance<
             gap_open_cost: gap_affine_config.gap_open_cost.into(),
             gap_extend_cost: gap_affine_config.gap_extend_cost.into(),
         },
-    );
+        &SearchSettings {
+            tie_breaking: cli.tie_breaking.into(),
+            deterministic: cli.deterministic,
+            ..Default::default()
+        },
+    )
+    .unwrap_or_else(|error| exit_with_error(format!("Error aligning: {error}")));
 
+    if cli.deterministic {
+        alignment.statistics_mut().clear_timing();
+    }
+    if let Some(mut provenance) = cli.input_provenance.clone() {
+        let config = toml::to_string(&gap_affine_config).unwrap();
+        provenance.config_sha256 = Some(sha256_hex(config.as_bytes()));
+        alignment.statistics_mut().provenance = Some(provenance);
+    }
+
+    let output_start_time = Instant::now();
+    let statistics_format = cli.statistics_format();
     if let Some(output) = cli.output {
-        use std::io::Write;
-        let mut output = std::io::BufWriter::new(std::fs::File::create(output).unwrap());
-        write!(output, "{}", toml::to_string(&alignment).unwrap()).unwrap();
+        write_output(&output, toml::to_string(&alignment).unwrap().as_bytes())
+            .unwrap_or_else(|error| exit_with_error(format!("Error writing {output:?}: {error}")));
+    }
+
+    println!("{}", alignment.display(statistics_format));
+    log_output_phase(output_start_time);
+}
+
+/// Logs the end of the output phase that started at `start_time`.
+fn log_output_phase(start_time: Instant) {
+    let duration = start_time.elapsed().as_secs_f64();
+    info!(
+        target: PHASE_LOG_TARGET,
+        phase = "output",
+        seconds = duration;
+        "Wrote the output in {duration:.3}s"
+    );
+}
+
+fn parse_memory_size(size: &str) -> Result<usize, String> {
+    let size = size.trim();
+    let (number, factor) = match size.chars().last() {
+        Some('k' | 'K') => (&size[..size.len() - 1], 1 << 10),
+        Some('m' | 'M') => (&size[..size.len() - 1], 1 << 20),
+        Some('g' | 'G') => (&size[..size.len() - 1], 1 << 30),
+        Some('t' | 'T') => (&size[..size.len() - 1], 1 << 40),
+        _ => (size, 1),
+    };
+
+    number
+        .parse::<usize>()
+        .map_err(|error| format!("Invalid memory size {size:?}: {error}"))?
+        .checked_mul(factor)
+        .ok_or_else(|| format!("Memory size {size:?} is too large"))
+}
+
+fn parse_lower_bound_weight(weight: &str) -> Result<f64, String> {
+    let weight = weight
+        .trim()
+        .parse::<f64>()
+        .map_err(|error| format!("Invalid weight {weight:?}: {error}"))?;
+    if weight.is_finite() && weight >= 1.0 {
+        Ok(weight)
+    } else {
+        Err(format!(
+            "Invalid weight {weight}, expected a finite number of at least one"
+        ))
     }
+}
+
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let (number, factor) = if let Some(number) = duration.strip_suffix("ms") {
+        (number, 1e-3)
+    } else {
+        match duration.chars().last() {
+            Some('s') => (&duration[..duration.len() - 1], 1.0),
+            Some('m') => (&duration[..duration.len() - 1], 60.0),
+            Some('h') => (&duration[..duration.len() - 1], 3600.0),
+            _ => (duration, 1.0),
+        }
+    };
 
-    println!("{}", alignment);
+    let seconds = number
+        .trim()
+        .parse::<f64>()
+        .map_err(|error| format!("Invalid duration {duration:?}: {error}"))?
+        * factor;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|error| format!("Invalid duration {duration:?}: {error}"))
+}
+
+impl Cli {
+    fn statistics_format(&self) -> StatisticsFormat {
+        StatisticsFormat {
+            precision: self.statistics_precision,
+            mode: match self.statistics_mode {
+                StatisticsModeSelector::Human => StatisticsMode::HumanReadable,
+                StatisticsModeSelector::Machine => StatisticsMode::Machine,
+            },
+        }
+    }
+}
+
+impl From<AlignmentMethodSelector> for AlignmentMethod {
+    fn from(value: AlignmentMethodSelector) -> Self {
+        match value {
+            AlignmentMethodSelector::Matrix => Self::Matrix,
+            AlignmentMethodSelector::AStarGapAffine => Self::AStarGapAffine,
+            AlignmentMethodSelector::AStarTemplateSwitch => Self::AStarTemplateSwitch,
+            AlignmentMethodSelector::Auto => Self::Auto,
+        }
+    }
+}
+
+impl From<SecondaryScorePolicySelector> for SecondaryScorePolicy {
+    fn from(value: SecondaryScorePolicySelector) -> Self {
+        match value {
+            SecondaryScorePolicySelector::MaximiseMatches => Self::MaximiseMatches,
+            SecondaryScorePolicySelector::MinimiseTemplateSwitches => {
+                Self::MinimiseTemplateSwitches
+            }
+        }
+    }
+}
+
+impl From<TieBreakingSelector> for AStarTieBreaking {
+    fn from(value: TieBreakingSelector) -> Self {
+        match value {
+            TieBreakingSelector::NodeOrd => Self::NodeOrd,
+            TieBreakingSelector::Lifo => Self::Lifo,
+            TieBreakingSelector::SecondaryScore => Self::SecondaryScore,
+            TieBreakingSelector::Identifier => Self::Identifier,
+        }
+    }
 }
@@ -0,0 +1,203 @@
+use std::{fmt::Debug, fmt::Write, marker::PhantomData, path::PathBuf};
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        DefaultSequenceStore,
+    },
+    interface::{alphabet::Alphabet, sequence_store::SequenceStore},
+};
+use lib_tsalign::{
+    a_star_aligner::template_switch_distance::strategies::template_switch_min_length::TemplateSwitchMinLengthStrategy,
+    star_msa::StarMsa,
+};
+use log::info;
+
+use crate::{
+    compression::{read_fasta_file, write_output},
+    exit_with_error, skip_characters,
+    template_switch_distance_type_selectors::{
+        build_aligner, load_template_switch_config, select_cli_strategies, CliNodeOrdStrategy,
+        WithCliStrategies,
+    },
+    Cli, InputAlphabet,
+};
+
+/// The k-mer size used to choose the centre, like the default of `tsalign sketch`.
+const CENTRE_SKETCH_K: usize = 15;
+/// The sketch size used to choose the centre, like the default of `tsalign sketch`.
+const CENTRE_SKETCH_SIZE: usize = 1000;
+
+#[derive(Args, Clone)]
+pub struct MsaArgs {
+    /// The fasta files containing the sequences to align, which may be compressed with gzip or bgzip.
+    ///
+    /// All records of all files are aligned, and their ids must be distinct.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+
+    /// The id of the record that all other records are aligned to.
+    ///
+    /// If not given, the record sharing the most k-mers with all others is chosen.
+    #[clap(long)]
+    centre: Option<String>,
+
+    /// The file to store the template switches of each record in, as tab-separated values.
+    ///
+    /// Each line contains the record id, the zero-based columns of the multiple alignment spanned by the template switch,
+    /// and the template switch in the coordinates of the pairwise alignment of the centre as reference to the record as query.
+    /// If the file name ends in `.gz`, the file is compressed with gzip.
+    #[clap(long)]
+    template_switches: Option<PathBuf>,
+}
+
+/// Align all records to a centre record with the template switch aligner, and print the merged star alignment as aligned fasta.
+///
+/// The options of the template switch aligner given on the command line apply to all pairs.
+pub fn msa(cli: Cli, args: MsaArgs) {
+    if cli.output.is_some() {
+        exit_with_error(
+            "--output is not supported for multiple alignments, use --template-switches instead",
+        );
+    }
+
+    match cli.alphabet {
+        InputAlphabet::Dna => msa_with_alphabet::<DnaAlphabet>(cli, args),
+        InputAlphabet::DnaN => msa_with_alphabet::<DnaAlphabetOrN>(cli, args),
+        InputAlphabet::Rna => msa_with_alphabet::<RnaAlphabet>(cli, args),
+        InputAlphabet::RnaN => msa_with_alphabet::<RnaAlphabetOrN>(cli, args),
+        InputAlphabet::DnaIupac => msa_with_alphabet::<DnaIupacNucleicAcidAlphabet>(cli, args),
+        InputAlphabet::RnaIupac => msa_with_alphabet::<RnaIupacNucleicAcidAlphabet>(cli, args),
+    }
+}
+
+fn msa_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
+    cli: Cli,
+    args: MsaArgs,
+) {
+    select_cli_strategies(
+        cli,
+        MsaWithStrategies::<AlphabetType> {
+            args,
+            phantom_data: PhantomData,
+        },
+    );
+}
+
+struct MsaWithStrategies<AlphabetType> {
+    args: MsaArgs,
+    phantom_data: PhantomData<AlphabetType>,
+}
+
+impl<AlphabetType: Alphabet + Debug + Clone + Eq + 'static> WithCliStrategies
+    for MsaWithStrategies<AlphabetType>
+{
+    type Output = ();
+
+    fn call<
+        NodeOrd: CliNodeOrdStrategy,
+        TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
+    >(
+        self,
+        cli: Cli,
+    ) {
+        msa_with_strategies::<AlphabetType, NodeOrd, TemplateSwitchMinLength>(cli, self.args)
+    }
+}
+
+fn msa_with_strategies<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
+    NodeOrd: CliNodeOrdStrategy,
+    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
+>(
+    cli: Cli,
+    args: MsaArgs,
+) {
+    let skip_characters = skip_characters(&cli);
+    let mut sequence_store = DefaultSequenceStore::<AlphabetType>::new();
+    let mut records = Vec::new();
+    for file in &args.files {
+        info!(file:% = file.display(); "Loading fasta file {file:?}");
+        records.extend(
+            read_fasta_file(file, &mut sequence_store, false, true, &skip_characters)
+                .unwrap_or_else(|error| {
+                    exit_with_error(format!("Error loading fasta file {file:?}: {error}"))
+                }),
+        );
+    }
+    if records.len() < 2 {
+        exit_with_error("At least two records are required for a multiple alignment");
+    }
+    let sequences: Vec<_> = records
+        .iter()
+        .map(|record| {
+            (
+                record.id.as_str(),
+                sequence_store.get(&record.sequence_handle),
+            )
+        })
+        .collect();
+
+    let centre = if let Some(centre) = &args.centre {
+        records
+            .iter()
+            .position(|record| &record.id == centre)
+            .unwrap_or_else(|| exit_with_error(format!("Centre record {centre:?} not found")))
+    } else {
+        let sequences: Vec<_> = sequences.iter().map(|(_, sequence)| *sequence).collect();
+        StarMsa::choose_centre(&sequences, CENTRE_SKETCH_K, CENTRE_SKETCH_SIZE)
+    };
+    info!(
+        "Aligning {} records to the centre {}",
+        records.len() - 1,
+        records[centre].id
+    );
+
+    let costs = load_template_switch_config(&cli);
+    let aligner = build_aligner::<_, NodeOrd, TemplateSwitchMinLength>(&cli, costs);
+    let msa = StarMsa::align(&aligner, &sequences, centre)
+        .unwrap_or_else(|error| exit_with_error(format!("Error aligning: {error}")));
+
+    for row in &msa.rows {
+        if let Some(alignment) = &row.alignment {
+            info!(
+                "Aligned {} with cost {} and {} template switches",
+                row.name,
+                alignment.statistics().cost,
+                row.template_switches.len()
+            );
+        }
+    }
+
+    if let Some(template_switches) = &args.template_switches {
+        info!(file:% = template_switches.display(); "Outputting template switches to {template_switches:?}");
+        let mut output = String::new();
+        for row in &msa.rows {
+            for template_switch in &row.template_switches {
+                writeln!(
+                    output,
+                    "{}\t{}..{}\t{}",
+                    row.name,
+                    template_switch.columns.start,
+                    template_switch.columns.end,
+                    template_switch.annotation
+                )
+                .unwrap();
+            }
+        }
+        write_output(template_switches, output.as_bytes()).unwrap_or_else(|error| {
+            exit_with_error(format!("Error writing {template_switches:?}: {error}"))
+        });
+    }
+
+    msa.write_fasta(&mut std::io::stdout().lock())
+        .unwrap_or_else(|error| {
+            exit_with_error(format!("Error writing the multiple alignment: {error}"))
+        });
+}
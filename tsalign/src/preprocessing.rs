@@ -0,0 +1,57 @@
+use compact_genome::interface::{
+    alphabet::Alphabet, sequence::GenomeSequence, sequence_store::SequenceStore,
+};
+use lib_tsalign::preprocessing::PreprocessingSettings;
+use log::info;
+
+use crate::{exit_with_error, Cli, InputRecord};
+
+/// Returns the preprocessing steps selected in `cli`.
+pub fn preprocessing_settings(cli: &Cli) -> PreprocessingSettings {
+    PreprocessingSettings {
+        adapters: cli
+            .clip_adapter
+            .iter()
+            .map(|adapter| adapter.as_bytes().to_vec())
+            .collect(),
+        min_adapter_overlap: cli.min_adapter_overlap,
+        max_n_run: cli.max_n_run,
+        ..Default::default()
+    }
+}
+
+/// Preprocesses a loaded sequence as selected in `cli`.
+///
+/// If characters are removed, the preprocessed sequence is added to the sequence store,
+/// and its map back to the loaded sequence is recorded in the input record.
+pub fn preprocess_input<AlphabetType: Alphabet, SequenceStoreType: SequenceStore<AlphabetType>>(
+    cli: &Cli,
+    sequence_store: &mut SequenceStoreType,
+    handle: SequenceStoreType::Handle,
+    mut input: InputRecord,
+) -> (SequenceStoreType::Handle, InputRecord) {
+    let settings = preprocessing_settings(cli);
+    if settings.is_empty() {
+        return (handle, input);
+    }
+
+    let preprocessed = settings.preprocess(&sequence_store.get(&handle).clone_as_vec(), None);
+    let coordinate_map = preprocessed.coordinate_map;
+    if coordinate_map.is_identity() {
+        return (handle, input);
+    }
+
+    info!(
+        "Preprocessing shortened {:?} from {} to {} characters",
+        input.record_id,
+        coordinate_map.original_length(),
+        coordinate_map.processed_length()
+    );
+    let handle = sequence_store
+        .add_from_slice_u8(&preprocessed.sequence)
+        .unwrap_or_else(|error| {
+            exit_with_error(format!("Error storing preprocessed sequence: {error}"))
+        });
+    input.coordinate_map = Some(coordinate_map);
+    (handle, input)
+}
@@ -0,0 +1,197 @@
+use std::{
+    fmt::Display,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use compact_genome::interface::{
+    alphabet::Alphabet, sequence::GenomeSequence, sequence_store::SequenceStore,
+};
+use log::info;
+use traitsequence::interface::Sequence;
+
+use crate::{compression::read_fasta_file, exit_with_error, InputRecord};
+
+/// A part of a record of a fasta file, given on the command line as `file[:record[:start-end[:strand]]]`.
+///
+/// The range is one-based and inclusive on the command line, like in samtools regions,
+/// but stored zero-based and half-open.
+/// The strand is `+` or `-`, where `-` selects the reverse complement of the range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceSelection {
+    pub file: PathBuf,
+    /// The id of the record, required if the file contains more than one record.
+    pub record: Option<String>,
+    pub range: Option<Range<usize>>,
+    pub reverse_complement: bool,
+}
+
+impl SequenceSelection {
+    /// Returns a selection of the only record of `file`.
+    pub fn whole_file(file: impl Into<PathBuf>) -> Self {
+        Self {
+            file: file.into(),
+            record: None,
+            range: None,
+            reverse_complement: false,
+        }
+    }
+
+    /// Returns true if only a part of the record is selected, or its reverse complement.
+    pub fn changes_coordinates(&self) -> bool {
+        self.range.is_some() || self.reverse_complement
+    }
+
+    /// Parses a one-based inclusive range formatted as `start-end` into a zero-based half-open range.
+    ///
+    /// Commas in the positions are ignored, so `1,001-2,000` is the same as `1001-2000`.
+    pub fn parse_range(range: &str) -> Result<Range<usize>, String> {
+        let invalid_range = || format!("Invalid range {range:?}, expected `start-end`");
+        let (start, end) = range.split_once('-').ok_or_else(invalid_range)?;
+        let [start, end] = [start, end].map(|position| {
+            position
+                .trim()
+                .replace(',', "")
+                .parse::<usize>()
+                .map_err(|_| invalid_range())
+        });
+        let (start, end) = (start?, end?);
+        if start == 0 || start > end + 1 {
+            return Err(invalid_range());
+        }
+        Ok(start - 1..end)
+    }
+
+    /// Parses a strand formatted as `+` or `-`, and returns true if it selects the reverse complement.
+    pub fn parse_strand(strand: &str) -> Result<bool, String> {
+        match strand {
+            "+" => Ok(false),
+            "-" => Ok(true),
+            strand => Err(format!("Invalid strand {strand:?}, expected `+` or `-`")),
+        }
+    }
+
+    /// Loads the selected sequence into the sequence store.
+    ///
+    /// If only a part of the record or its reverse complement is selected, it is copied into a new sequence.
+    pub fn load<AlphabetType: Alphabet, SequenceStoreType: SequenceStore<AlphabetType>>(
+        &self,
+        sequence_store: &mut SequenceStoreType,
+        skip_characters: &[bool],
+    ) -> (SequenceStoreType::Handle, InputRecord) {
+        let file = &self.file;
+        info!(file:% = file.display(); "Loading fasta file {file:?}");
+        let mut records = read_fasta_file(file, sequence_store, false, true, skip_characters)
+            .unwrap_or_else(|error| {
+                exit_with_error(format!("Error loading fasta file {file:?}: {error}"))
+            });
+
+        let record_index = if let Some(record_id) = &self.record {
+            records
+                .iter()
+                .position(|record| &record.id == record_id)
+                .unwrap_or_else(|| {
+                    exit_with_error(format!(
+                        "Fasta file {file:?} contains no record {record_id:?}"
+                    ))
+                })
+        } else {
+            if records.len() != 1 {
+                exit_with_error(format!(
+                    "Fasta file {file:?} contains {} records instead of exactly one, select one by its id",
+                    records.len()
+                ));
+            }
+            0
+        };
+        let record = records.swap_remove(record_index);
+
+        let sequence = sequence_store.get(&record.sequence_handle);
+        let range = self.range.clone().unwrap_or(0..sequence.len());
+        if range.end > sequence.len() {
+            exit_with_error(format!(
+                "Range {range:?} exceeds the length {} of record {:?} in {file:?}",
+                sequence.len(),
+                record.id
+            ));
+        }
+
+        let handle = if self.changes_coordinates() {
+            let selected: Vec<_> = if self.reverse_complement {
+                sequence[range].reverse_complement_iter().collect()
+            } else {
+                sequence[range].iter().cloned().collect()
+            };
+            sequence_store.add_from_iter(selected)
+        } else {
+            record.sequence_handle
+        };
+
+        (
+            handle,
+            InputRecord {
+                file: file.clone(),
+                record_id: record.id,
+                record_index,
+                range: self.range.clone(),
+                reverse_complement: self.reverse_complement,
+                coordinate_map: None,
+            },
+        )
+    }
+}
+
+impl FromStr for SequenceSelection {
+    type Err = String;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        // Existing files are never split, in case their name contains a colon.
+        if Path::new(selection).exists() {
+            return Ok(Self::whole_file(selection));
+        }
+
+        let mut parts = selection.split(':');
+        let mut result = Self::whole_file(parts.next().unwrap());
+        result.record = parts
+            .next()
+            .filter(|record| !record.is_empty())
+            .map(str::to_string);
+
+        result.range = parts
+            .next()
+            .filter(|range| !range.is_empty())
+            .map(Self::parse_range)
+            .transpose()?;
+        result.reverse_complement = parts
+            .next()
+            .filter(|strand| !strand.is_empty())
+            .map(Self::parse_strand)
+            .transpose()?
+            .unwrap_or(false);
+
+        if parts.next().is_some() {
+            return Err(format!(
+                "Too many parts in {selection:?}, expected `file[:record[:start-end[:strand]]]`"
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+impl Display for SequenceSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(record) = &self.record {
+            write!(f, ":{record}")?;
+        }
+        if let Some(range) = &self.range {
+            write!(f, ":{}-{}", range.start + 1, range.end)?;
+        }
+        if self.reverse_complement {
+            write!(f, ":-")?;
+        }
+        Ok(())
+    }
+}
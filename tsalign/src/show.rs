@@ -0,0 +1,479 @@
+use std::{fmt::Debug, path::PathBuf};
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        DefaultSequenceStore,
+    },
+    interface::{alphabet::Alphabet, sequence::GenomeSequence, sequence_store::SequenceStore},
+};
+use lib_tsalign::{
+    a_star_aligner::{
+        alignment_result::{
+            AlignmentResult, AlignmentStatistics, StatisticsFormat, StatisticsMode,
+            STATISTICS_FORMAT_VERSION,
+        },
+        gap_affine_edit_distance,
+        template_switch_distance::{
+            self,
+            cigar::{check_cigar_bounds, parse_cigar},
+        },
+    },
+    maf::{MafBlock, MafBlockKind, MafRow, MafStrand},
+    provenance::{ResultProvenance, SequenceProvenance},
+};
+use log::{info, warn};
+use traitsequence::interface::Sequence;
+
+use crate::{
+    compression::read_input_to_string, exit_with_error, sequence_selection::SequenceSelection,
+    template_switch_distance_type_selectors::print_template_switches, InputAlphabet,
+};
+
+#[derive(Args, Clone)]
+pub struct ShowArgs {
+    /// The alignment result file written with `--output`.
+    #[clap(required_unless_present = "cigar", conflicts_with = "cigar")]
+    file: Option<PathBuf>,
+
+    /// Show this template switch CIGAR instead of an alignment result file.
+    ///
+    /// This is the CIGAR as printed by the aligner, e.g. `10M2I5M[TSQR-3:4M:0]8M`,
+    /// so it can be hand-edited or produced by other tools.
+    /// Like in SAM, `=` and `X` are accepted as aliases of `M` and `S`.
+    /// This requires the aligned sequences, which the CIGAR has to align completely.
+    #[clap(long, requires = "reference")]
+    cigar: Option<String>,
+
+    /// The path to the reference fasta file that was aligned.
+    ///
+    /// If given together with the query, the template switches of the alignment are printed.
+    /// This is not necessary if the sequences were embedded with `--provenance sequences`.
+    #[clap(long, short = 'r', requires = "query")]
+    reference: Option<PathBuf>,
+
+    /// The path to the query fasta file that was aligned.
+    #[clap(long, short = 'q', requires = "reference")]
+    query: Option<PathBuf>,
+
+    /// The alphabet present in the input files.
+    #[clap(long, short = 'a', default_value = "dna-n")]
+    alphabet: InputAlphabet,
+
+    /// Print the cost of each alignment column as a greyscale track, to spot expensive regions.
+    ///
+    /// Only results written by this version of tsalign contain the column costs.
+    #[clap(long)]
+    cost_track: bool,
+
+    /// Print the alignment block by block, with the donor of each template switch below its primary.
+    ///
+    /// The donor is the secondary, which is shown reverse complemented unless the template switch copies the forward strand,
+    /// with the forward coordinates of the donor region.
+    /// Like the template switches, this requires the aligned sequences.
+    /// Coordinates refer to the aligned sequences, i.e. after preprocessing.
+    #[clap(long)]
+    side_by_side: bool,
+
+    /// Print the anchors of the chain with their coordinates and the chaining costs between them.
+    ///
+    /// Only results of a chaining strategy written by this version of tsalign contain the anchors of the chain.
+    #[clap(long)]
+    chain: bool,
+}
+
+/// A result file of either of the aligners that can output an alignment.
+enum ShownAlignment {
+    TemplateSwitch(AlignmentResult<template_switch_distance::AlignmentType>),
+    GapAffine(AlignmentResult<gap_affine_edit_distance::AlignmentType>),
+}
+
+/// Print a previously saved alignment result like the aligner printed it, without aligning again.
+pub fn show(args: ShowArgs, format: StatisticsFormat) {
+    let alignment = if let Some(cigar) = &args.cigar {
+        let alignment = parse_cigar(cigar)
+            .unwrap_or_else(|error| exit_with_error(format!("Error parsing CIGAR: {error}")));
+        ShownAlignment::TemplateSwitch(AlignmentResult::WithTarget {
+            alignment,
+            statistics: AlignmentStatistics::zero(),
+        })
+    } else {
+        load_alignment_result(args.file.as_ref().unwrap())
+    };
+
+    let statistics = match &alignment {
+        ShownAlignment::TemplateSwitch(alignment) => alignment.statistics(),
+        ShownAlignment::GapAffine(alignment) => alignment.statistics(),
+    };
+    if !statistics.is_format_supported() {
+        warn!(
+            "The alignment result has format version {}, but this version of tsalign supports only up to {STATISTICS_FORMAT_VERSION}",
+            statistics.format_version
+        );
+    }
+
+    match &alignment {
+        // A CIGAR has no statistics besides its alignment.
+        ShownAlignment::TemplateSwitch(alignment) if args.cigar.is_some() => {
+            println!("CIGAR: {}", alignment.cigar());
+            show_template_switches_with_alphabet(&args, alignment);
+        }
+        ShownAlignment::TemplateSwitch(alignment) => {
+            println!("{}", alignment.display(format));
+
+            if format.mode == StatisticsMode::HumanReadable {
+                show_template_switches_with_alphabet(&args, alignment);
+            }
+        }
+        ShownAlignment::GapAffine(alignment) => println!("{}", alignment.display(format)),
+    }
+
+    if args.cost_track {
+        let column_costs: Vec<_> = statistics
+            .column_costs
+            .iter()
+            .map(|(count, cost)| (*count, cost.raw()))
+            .collect();
+        print_cost_track(&column_costs);
+    }
+
+    if args.chain {
+        match &alignment {
+            ShownAlignment::TemplateSwitch(alignment) => print_chain(alignment.statistics()),
+            ShownAlignment::GapAffine(_) => println!("The gap-affine aligner does not chain"),
+        }
+    }
+}
+
+/// Prints the anchors of the chain, each with the chaining cost from the previous anchor.
+fn print_chain(statistics: &AlignmentStatistics) {
+    let Some(chain) = &statistics.chain else {
+        println!("The alignment result contains no chain");
+        return;
+    };
+    if chain.chain_anchors.is_empty() {
+        println!("The chain contains no anchors");
+        return;
+    }
+
+    println!("Chain anchors (reference range, query range, cost from previous, cost to end):");
+    for anchor in &chain.chain_anchors {
+        println!(
+            "{}..{}\t{}..{}\t{}\t{}",
+            anchor.reference_start,
+            anchor.reference_end,
+            anchor.query_start,
+            anchor.query_end,
+            anchor.cost,
+            anchor.cost_to_target,
+        );
+    }
+}
+
+fn load_alignment_result(file: &PathBuf) -> ShownAlignment {
+    info!(file:% = file.display(); "Loading alignment result {file:?}");
+    let input = read_input_to_string(file)
+        .unwrap_or_else(|error| exit_with_error(format!("Error reading {file:?}: {error}")));
+
+    // The alignment types of the aligners serialise to disjoint names, so at most one of them parses.
+    match toml::from_str(&input) {
+        Ok(alignment) => ShownAlignment::TemplateSwitch(alignment),
+        Err(template_switch_error) => match toml::from_str(&input) {
+            Ok(alignment) => ShownAlignment::GapAffine(alignment),
+            Err(_) => exit_with_error(format!(
+                "Error parsing alignment result {file:?}: {template_switch_error}"
+            )),
+        },
+    }
+}
+
+fn show_template_switches_with_alphabet(
+    args: &ShowArgs,
+    alignment: &AlignmentResult<template_switch_distance::AlignmentType>,
+) {
+    match args.alphabet {
+        InputAlphabet::Dna => show_template_switches::<DnaAlphabet>(args, alignment),
+        InputAlphabet::DnaN => show_template_switches::<DnaAlphabetOrN>(args, alignment),
+        InputAlphabet::Rna => show_template_switches::<RnaAlphabet>(args, alignment),
+        InputAlphabet::RnaN => show_template_switches::<RnaAlphabetOrN>(args, alignment),
+        InputAlphabet::DnaIupac => {
+            show_template_switches::<DnaIupacNucleicAcidAlphabet>(args, alignment)
+        }
+        InputAlphabet::RnaIupac => {
+            show_template_switches::<RnaIupacNucleicAcidAlphabet>(args, alignment)
+        }
+    }
+}
+
+/// The characters of the cost track, from zero cost to the maximum column cost.
+const COST_TRACK_SHADES: &[u8] = b" .:-=+*#%@";
+
+/// The number of columns per line of the cost track.
+const COST_TRACK_WIDTH: usize = 100;
+
+/// Prints the run-length encoded column costs as lines of greyscale characters, each prefixed by the index of its first column.
+fn print_cost_track(column_costs: &[(usize, f64)]) {
+    if column_costs.is_empty() {
+        println!("The alignment result contains no column costs");
+        return;
+    }
+
+    let max_cost = column_costs
+        .iter()
+        .map(|(_, cost)| *cost)
+        .fold(0.0, f64::max);
+    let shade = |cost: f64| {
+        if cost == 0.0 {
+            COST_TRACK_SHADES[0]
+        } else {
+            // Nonzero costs are never shown as blank.
+            let levels = (COST_TRACK_SHADES.len() - 1) as f64;
+            let level = (cost / max_cost * levels).ceil() as usize;
+            COST_TRACK_SHADES[level.clamp(1, COST_TRACK_SHADES.len() - 1)]
+        }
+    };
+    let track: Vec<u8> = column_costs
+        .iter()
+        .flat_map(|(count, cost)| std::iter::repeat_n(shade(*cost), *count))
+        .collect();
+
+    println!(
+        "Cost track ({} columns, '{}' is zero and '{}' is the maximum column cost {max_cost}):",
+        track.len(),
+        char::from(COST_TRACK_SHADES[0]),
+        char::from(*COST_TRACK_SHADES.last().unwrap()),
+    );
+    let index_width = track.len().to_string().len();
+    for (line_index, line) in track.chunks(COST_TRACK_WIDTH).enumerate() {
+        println!(
+            "{:>index_width$} |{}|",
+            line_index * COST_TRACK_WIDTH,
+            String::from_utf8_lossy(line)
+        );
+    }
+}
+
+/// Prints the template switches of the alignment, using the given fasta files or the sequences embedded in the result.
+///
+/// If the result records the hashes of the aligned sequences, the sequences are checked against them.
+fn show_template_switches<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
+    args: &ShowArgs,
+    alignment: &AlignmentResult<template_switch_distance::AlignmentType>,
+) {
+    let provenance = alignment.statistics().provenance.as_ref();
+    let mut sequence_store = DefaultSequenceStore::<AlphabetType>::new();
+
+    let handles = if let (Some(reference), Some(query)) = (&args.reference, &args.query) {
+        [
+            (
+                reference,
+                "reference",
+                provenance.map(|provenance| &provenance.reference),
+            ),
+            (
+                query,
+                "query",
+                provenance.map(|provenance| &provenance.query),
+            ),
+        ]
+        .map(|(file, name, sequence_provenance)| {
+            // Select the aligned part of the aligned record, if it was recorded.
+            let selection = SequenceSelection {
+                file: file.clone(),
+                record: sequence_provenance.map(|provenance| provenance.record_id.clone()),
+                range: sequence_provenance
+                    .and_then(|provenance| provenance.range)
+                    .map(|(start, end)| start..end),
+                reverse_complement: sequence_provenance
+                    .is_some_and(|provenance| provenance.reverse_complement),
+            };
+            info!("Loading {name} {selection}");
+            let handle = selection.load(&mut sequence_store, &[]).0;
+
+            // Remove the characters that were removed by preprocessing before aligning.
+            if let Some(coordinate_map) =
+                sequence_provenance.and_then(|provenance| provenance.coordinate_map.as_ref())
+            {
+                let preprocessed =
+                    coordinate_map.apply(&sequence_store.get(&handle).clone_as_vec());
+                sequence_store
+                    .add_from_slice_u8(&preprocessed)
+                    .unwrap_or_else(|error| {
+                        exit_with_error(format!("Error storing preprocessed {name}: {error}"))
+                    })
+            } else {
+                handle
+            }
+        })
+    } else if let Some(ResultProvenance {
+        reference:
+            SequenceProvenance {
+                sequence: Some(reference),
+                ..
+            },
+        query: SequenceProvenance {
+            sequence: Some(query),
+            ..
+        },
+        ..
+    }) = provenance
+    {
+        info!("Using the sequences embedded in the alignment result");
+        [reference, query].map(|sequence| {
+            sequence_store
+                .add_from_slice_u8(sequence.as_bytes())
+                .unwrap_or_else(|error| {
+                    exit_with_error(format!("Error reading embedded sequence: {error}"))
+                })
+        })
+    } else {
+        return;
+    };
+
+    let [reference, query] = handles.each_ref().map(|handle| sequence_store.get(handle));
+    // Hand-written CIGARs may not fit the sequences, which would make printing the template switches fail.
+    if args.cigar.is_some() {
+        if let AlignmentResult::WithTarget { alignment, .. } = alignment {
+            check_cigar_bounds(alignment, reference.len(), query.len())
+                .unwrap_or_else(|error| exit_with_error(error));
+        }
+    }
+    if let Some(provenance) = provenance {
+        for (name, sequence_provenance, sequence) in [
+            ("reference", &provenance.reference, reference),
+            ("query", &provenance.query, query),
+        ] {
+            if !sequence_provenance.matches(sequence) {
+                exit_with_error(format!(
+                    "The {name} sequence differs from the aligned record {:?} of length {} with SHA-256 hash {}",
+                    sequence_provenance.record_id,
+                    sequence_provenance.length,
+                    sequence_provenance.sha256,
+                ));
+            }
+        }
+    }
+
+    print_template_switches(alignment, reference, query);
+    if args.side_by_side {
+        if let AlignmentResult::WithTarget { alignment, .. } = alignment {
+            print_side_by_side(&MafBlock::from_alignment(
+                alignment,
+                reference,
+                query,
+                "reference",
+                "query",
+            ));
+        }
+    }
+}
+
+/// The number of columns per line of the side-by-side alignment.
+const SIDE_BY_SIDE_WIDTH: usize = 100;
+
+/// Prints alignment blocks one after the other, wrapping their rows after [`SIDE_BY_SIDE_WIDTH`] columns.
+///
+/// Each line of a row is prefixed by the forward coordinate of its first character,
+/// which decreases along rows on the reverse strand.
+fn print_side_by_side(blocks: &[MafBlock]) {
+    println!("Alignment blocks:");
+    for block in blocks {
+        let [first, second] = &block.rows;
+        let forward_range = |row: &MafRow| match row.strand {
+            MafStrand::Forward => row.start..row.start + row.size,
+            MafStrand::Reverse => {
+                row.source_size - row.start - row.size..row.source_size - row.start
+            }
+        };
+        let (first_range, second_range) = (forward_range(first), forward_range(second));
+        match block.kind {
+            MafBlockKind::Primary => println!(
+                "Primary, {} {}..{}, {} {}..{}",
+                first.source,
+                first_range.start,
+                first_range.end,
+                second.source,
+                second_range.start,
+                second_range.end,
+            ),
+            MafBlockKind::TemplateSwitch => println!(
+                "Template switch, primary {} {}..{}, donor {} {}..{}{}",
+                first.source,
+                first_range.start,
+                first_range.end,
+                second.source,
+                second_range.start,
+                second_range.end,
+                match second.strand {
+                    MafStrand::Forward => "",
+                    MafStrand::Reverse => " (reverse complement)",
+                },
+            ),
+            MafBlockKind::Inversion => println!(
+                "Inversion, {} {}..{} (reverse complement), {} {}..{}",
+                first.source,
+                first_range.start,
+                first_range.end,
+                second.source,
+                second_range.start,
+                second_range.end,
+            ),
+        }
+
+        let labels = block.rows.each_ref().map(|row| match row.strand {
+            MafStrand::Forward => row.source.clone(),
+            MafStrand::Reverse => format!("{} (rc)", row.source),
+        });
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let index_width = block
+            .rows
+            .iter()
+            .map(|row| row.source_size.to_string().len())
+            .max()
+            .unwrap_or(0);
+        // The forward coordinate of the next character of each row.
+        let mut positions =
+            [(first, &first_range), (second, &second_range)].map(|(row, range)| match row.strand {
+                MafStrand::Forward => Some(range.start),
+                MafStrand::Reverse => range.end.checked_sub(1),
+            });
+
+        let lines = block.rows.each_ref().map(|row| {
+            row.text
+                .as_bytes()
+                .chunks(SIDE_BY_SIDE_WIDTH)
+                .collect::<Vec<_>>()
+        });
+        for line_index in 0..lines[0].len() {
+            for ((row, label), (lines, position)) in block
+                .rows
+                .iter()
+                .zip(&labels)
+                .zip(lines.iter().zip(&mut positions))
+            {
+                let line = lines[line_index];
+                let position_string =
+                    position.map_or(String::new(), |position| position.to_string());
+                println!(
+                    "{label:<label_width$} {position_string:>index_width$} {}",
+                    String::from_utf8_lossy(line)
+                );
+
+                let characters = line.iter().filter(|character| **character != b'-').count();
+                *position = match row.strand {
+                    MafStrand::Forward => position.map(|position| position + characters),
+                    MafStrand::Reverse => {
+                        position.and_then(|position| position.checked_sub(characters))
+                    }
+                };
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        DefaultSequenceStore,
+    },
+    interface::{alphabet::Alphabet, sequence_store::SequenceStore},
+};
+use lib_tsalign::sketch::MinHashSketch;
+use log::info;
+
+use crate::{compression::read_fasta_file, exit_with_error, InputAlphabet};
+
+#[derive(Args, Clone)]
+pub struct SketchArgs {
+    /// The fasta files containing the sequences to compare.
+    ///
+    /// All records of all files are compared pairwise.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+
+    /// The alphabet present in the input files.
+    #[clap(long, short = 'a', default_value = "dna-n")]
+    alphabet: InputAlphabet,
+
+    /// The k-mer size.
+    #[clap(long, short = 'k', default_value = "15")]
+    k: usize,
+
+    /// The number of hashes kept per sequence.
+    #[clap(long, default_value = "1000")]
+    sketch_size: usize,
+}
+
+/// Print the estimated Jaccard index and average nucleotide identity of all pairs of input sequences as tab-separated values.
+///
+/// This is meant for quickly triaging which pairs are similar enough to be worth aligning.
+pub fn sketch(args: SketchArgs) {
+    match args.alphabet {
+        InputAlphabet::Dna => sketch_with_alphabet::<DnaAlphabet>(args),
+        InputAlphabet::DnaN => sketch_with_alphabet::<DnaAlphabetOrN>(args),
+        InputAlphabet::Rna => sketch_with_alphabet::<RnaAlphabet>(args),
+        InputAlphabet::RnaN => sketch_with_alphabet::<RnaAlphabetOrN>(args),
+        InputAlphabet::DnaIupac => sketch_with_alphabet::<DnaIupacNucleicAcidAlphabet>(args),
+        InputAlphabet::RnaIupac => sketch_with_alphabet::<RnaIupacNucleicAcidAlphabet>(args),
+    }
+}
+
+fn sketch_with_alphabet<AlphabetType: Alphabet + 'static>(args: SketchArgs) {
+    let mut sequence_store = DefaultSequenceStore::<AlphabetType>::new();
+    let mut sketches = Vec::new();
+
+    for file in &args.files {
+        info!("Loading fasta file {file:?}");
+        let records =
+            read_fasta_file(file, &mut sequence_store, false, true, &[]).unwrap_or_else(|error| {
+                exit_with_error(format!("Error loading fasta file {file:?}: {error}"))
+            });
+
+        for record in records {
+            let sequence = sequence_store.get(&record.sequence_handle);
+            sketches.push((
+                record.id,
+                MinHashSketch::new(sequence, args.k, args.sketch_size),
+            ));
+        }
+    }
+
+    info!("Comparing {} sketches pairwise", sketches.len());
+    println!("reference\tquery\tjaccard\tani");
+    for (index, (reference_id, reference_sketch)) in sketches.iter().enumerate() {
+        for (query_id, query_sketch) in &sketches[index + 1..] {
+            println!(
+                "{reference_id}\t{query_id}\t{:.4}\t{:.4}",
+                reference_sketch.jaccard(query_sketch),
+                reference_sketch.ani(query_sketch),
+            );
+        }
+    }
+}
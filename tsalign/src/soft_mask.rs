@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use lib_tsalign::soft_mask::{SoftMask, SoftMaskHandling, SoftMasks};
+use log::info;
+
+use crate::{
+    compression::open_input, exit_with_error, preprocessing::preprocessing_settings,
+    skip_characters, Cli,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SoftMaskSelector {
+    Ignore,
+    ForbidTemplateSwitches,
+    ScaleTemplateSwitchCost,
+}
+
+/// Reads the soft masks of the input sequences, unless soft-masking is ignored.
+pub fn read_soft_masks(cli: &Cli) -> Option<SoftMasks> {
+    let handling = match cli.soft_mask {
+        SoftMaskSelector::Ignore => return None,
+        SoftMaskSelector::ForbidTemplateSwitches => SoftMaskHandling::ForbidTemplateSwitches,
+        SoftMaskSelector::ScaleTemplateSwitchCost => {
+            SoftMaskHandling::ScaleTemplateSwitchBaseCost {
+                factor: cli.soft_mask_cost_factor,
+            }
+        }
+    };
+
+    if !preprocessing_settings(cli).is_empty() {
+        exit_with_error("Soft-masking is not supported when preprocessing the input");
+    }
+
+    let skip_characters = skip_characters(cli);
+    let read = |path: &Path| {
+        SoftMask::read_fasta(
+            open_input(path).unwrap_or_else(|error| {
+                exit_with_error(format!("Error opening fasta file {path:?}: {error}"))
+            }),
+            &skip_characters,
+        )
+        .unwrap_or_else(|error| {
+            exit_with_error(format!("Error reading soft mask from {path:?}: {error}"))
+        })
+    };
+    let masks = if let Some(pair_fasta) = &cli.input.pair_fasta {
+        read(pair_fasta)
+    } else if let (Some(reference), Some(query)) = (&cli.input.reference, &cli.input.query) {
+        if ![reference, query]
+            .iter()
+            .all(|selection| selection.record.is_none() && !selection.changes_coordinates())
+        {
+            exit_with_error(
+                "Soft-masking is not supported when selecting records, ranges or strands",
+            );
+        }
+        let mut masks = read(&reference.file);
+        masks.extend(read(&query.file));
+        masks
+    } else {
+        exit_with_error("No fasta input file given")
+    };
+    let [reference, query]: [SoftMask; 2] = masks.try_into().unwrap_or_else(|masks: Vec<_>| {
+        exit_with_error(format!(
+            "Expected exactly one reference and one query record, but found {} records",
+            masks.len()
+        ))
+    });
+
+    info!(
+        "Soft-masked characters: {} of {} in the reference, {} of {} in the query",
+        reference.masked_count(),
+        reference.len(),
+        query.masked_count(),
+        query.len()
+    );
+
+    Some(SoftMasks {
+        reference,
+        query,
+        handling,
+    })
+}
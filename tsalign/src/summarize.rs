@@ -0,0 +1,157 @@
+use std::{fmt::Debug, path::PathBuf};
+
+use clap::Args;
+use compact_genome::{
+    implementation::{
+        alphabets::{
+            dna_alphabet::DnaAlphabet, dna_alphabet_or_n::DnaAlphabetOrN,
+            dna_iupac_nucleic_acid_alphabet::DnaIupacNucleicAcidAlphabet,
+            rna_alphabet::RnaAlphabet, rna_alphabet_or_n::RnaAlphabetOrN,
+            rna_iupac_nucleic_acid_alphabet::RnaIupacNucleicAcidAlphabet,
+        },
+        vec_sequence::VectorGenome,
+    },
+    interface::{
+        alphabet::Alphabet,
+        sequence::{GenomeSequence, OwnedGenomeSequence},
+    },
+};
+use lib_tsalign::{
+    a_star_aligner::{alignment_result::AlignmentResult, template_switch_distance::AlignmentType},
+    provenance::{ResultProvenance, SequenceProvenance},
+    summary::TemplateSwitchSummariser,
+};
+use log::{info, warn};
+
+use crate::{compression::read_input_to_string, exit_with_error, InputAlphabet};
+
+#[derive(Args, Clone)]
+pub struct SummarizeArgs {
+    /// The alignment result files written with `--output` by the template switch aligner.
+    ///
+    /// All results should be alignments against the same reference.
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+
+    /// The alphabet of the sequences embedded in the results.
+    #[clap(long, short = 'a', default_value = "dna-n")]
+    alphabet: InputAlphabet,
+
+    /// Cluster template switches whose reference coordinates differ by at most this many characters.
+    #[clap(long, default_value = "0")]
+    tolerance: usize,
+
+    /// The maximum number of example result files printed per locus.
+    #[clap(long, default_value = "3")]
+    examples: usize,
+}
+
+/// Print the recurrent template switch loci of many alignment results as tab-separated values.
+///
+/// Template switches are canonicalised if the aligned sequences were embedded with `--provenance sequences`.
+pub fn summarize(args: SummarizeArgs) {
+    match args.alphabet {
+        InputAlphabet::Dna => summarize_with_alphabet::<DnaAlphabet>(args),
+        InputAlphabet::DnaN => summarize_with_alphabet::<DnaAlphabetOrN>(args),
+        InputAlphabet::Rna => summarize_with_alphabet::<RnaAlphabet>(args),
+        InputAlphabet::RnaN => summarize_with_alphabet::<RnaAlphabetOrN>(args),
+        InputAlphabet::DnaIupac => summarize_with_alphabet::<DnaIupacNucleicAcidAlphabet>(args),
+        InputAlphabet::RnaIupac => summarize_with_alphabet::<RnaIupacNucleicAcidAlphabet>(args),
+    }
+}
+
+fn summarize_with_alphabet<AlphabetType: Alphabet + Debug + Clone + Eq + 'static>(
+    args: SummarizeArgs,
+) {
+    let mut summariser = TemplateSwitchSummariser::new(args.tolerance, args.examples);
+    let mut reference_sha256 = None;
+    let mut uncanonicalised_results = 0;
+
+    for file in &args.files {
+        info!(file:% = file.display(); "Loading alignment result {file:?}");
+        let input = read_input_to_string(file)
+            .unwrap_or_else(|error| exit_with_error(format!("Error reading {file:?}: {error}")));
+        let alignment: AlignmentResult<AlignmentType> =
+            toml::from_str(&input).unwrap_or_else(|error| {
+                exit_with_error(format!("Error parsing alignment result {file:?}: {error}"))
+            });
+
+        let provenance = alignment.statistics().provenance.as_ref();
+        if let Some(provenance) = provenance {
+            match &reference_sha256 {
+                None => reference_sha256 = Some(provenance.reference.sha256.clone()),
+                Some(sha256) if *sha256 != provenance.reference.sha256 => warn!(
+                    "The reference of {file:?} differs from the reference of the first result"
+                ),
+                Some(_) => {}
+            }
+        }
+
+        let sequences = if let Some(ResultProvenance {
+            reference:
+                SequenceProvenance {
+                    sequence: Some(reference),
+                    ..
+                },
+            query:
+                SequenceProvenance {
+                    sequence: Some(query),
+                    ..
+                },
+            ..
+        }) = provenance
+        {
+            Some([reference, query].map(|sequence| {
+                VectorGenome::<AlphabetType>::from_slice_u8(sequence.as_bytes()).unwrap_or_else(
+                    |error| exit_with_error(format!("Error reading embedded sequence: {error}")),
+                )
+            }))
+        } else {
+            uncanonicalised_results += 1;
+            None
+        };
+
+        summariser.add_alignment(
+            &file.display().to_string(),
+            &alignment,
+            sequences.as_ref().map(|[reference, query]| {
+                (
+                    reference.as_genome_subsequence(),
+                    query.as_genome_subsequence(),
+                )
+            }),
+        );
+    }
+
+    if uncanonicalised_results > 0 {
+        warn!("{uncanonicalised_results} results contain no embedded sequences, so their template switches are not canonicalised");
+    }
+
+    let loci = summariser.summarise();
+    info!(
+        "Found {} template switch loci in {} results",
+        loci.len(),
+        summariser.alignment_count()
+    );
+    println!("primary\tsecondary\treference_start\treference_end\tsecondary_start\tsecondary_end\ttemplate_switches\tresults\texamples");
+    for locus in loci {
+        let (secondary_start, secondary_end) = locus
+            .locus
+            .secondary_range
+            .as_ref()
+            .map_or((String::new(), String::new()), |range| {
+                (range.start.to_string(), range.end.to_string())
+            });
+        println!(
+            "{}\t{}{}\t{}\t{}\t{secondary_start}\t{secondary_end}\t{}\t{}\t{}",
+            locus.locus.primary,
+            locus.locus.secondary,
+            locus.locus.strand,
+            locus.locus.reference_range.start,
+            locus.locus.reference_range.end,
+            locus.count,
+            locus.alignments,
+            locus.examples.join(","),
+        );
+    }
+}
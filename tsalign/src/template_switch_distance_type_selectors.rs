@@ -1,37 +1,118 @@
-use std::fmt::Debug;
+use std::{
+    any::Any, cell::RefCell, fmt::Debug, marker::PhantomData, rc::Rc, sync::Arc, time::Instant,
+};
 
 use clap::ValueEnum;
 use compact_genome::interface::{alphabet::Alphabet, sequence::GenomeSequence};
 use lib_tsalign::{
     a_star_aligner::{
-        template_switch_distance::strategies::{
-            chaining::{
-                ChainingStrategy, LowerBoundChainingStrategy, NoChainingStrategy,
-                PrecomputeOnlyChainingStrategy,
-            },
-            node_ord::{AntiDiagonalNodeOrdStrategy, CostOnlyNodeOrdStrategy, NodeOrdStrategy},
-            primary_match::AllowPrimaryMatchStrategy,
-            secondary_deletion::AllowSecondaryDeletionStrategy,
-            shortcut::NoShortcutStrategy,
-            template_switch_count::NoTemplateSwitchCountStrategy,
-            template_switch_min_length::{
-                LookaheadTemplateSwitchMinLengthStrategy, NoTemplateSwitchMinLengthStrategy,
-                TemplateSwitchMinLengthStrategy,
+        alignment_result::{alignment_cigar, AlignmentResult, StatisticsMode},
+        memory_estimate::format_bytes,
+        template_switch_distance::{
+            polish::polish_template_switches,
+            strategies::{
+                chaining::{
+                    write_chaining_graph, AnchorScheme, ChainingGraphFormat,
+                    RuntimeChainingSelection, RuntimeChainingStrategy,
+                },
+                node_ord::{
+                    AntiDiagonalNodeOrdStrategy, CostOnlyNodeOrdStrategy, LearnedNodeOrdStrategy,
+                    NodeOrdModel, NodeOrdStrategy,
+                },
+                primary_match::AllowPrimaryMatchStrategy,
+                shortcut::NoShortcutStrategy,
+                template_switch_count::NoTemplateSwitchCountStrategy,
+                template_switch_min_length::{
+                    LookaheadTemplateSwitchMinLengthStrategy, NoTemplateSwitchMinLengthStrategy,
+                    TemplateSwitchMinLengthStrategy,
+                },
             },
-            AlignmentStrategySelection,
+            AlignmentType,
         },
-        template_switch_distance_a_star_align,
+        trim::TrimmedEnds,
+        Aligner, AlignerBuilder, BuiltStrategies, RandomRestarts, SearchAlgorithm,
     },
-    config::TemplateSwitchConfig,
+    character_mapping::{CharacterMapping, NCharacterPolicy},
+    config::{io::ParseMode, TemplateSwitchConfig, TemplateSwitchDirections},
+    maf::{write_maf, MafBlock},
+    preprocessing::CoordinateMap,
+    soft_mask::SoftMasks,
+    ts_normalisation::TemplateSwitchAnnotation,
+};
+use log::{info, warn};
+
+use crate::{
+    compression::{open_input, write_output},
+    exit_with_error,
+    guide::read_guide,
+    log_output_phase,
+    soft_mask::read_soft_masks,
+    Cli,
 };
-use log::info;
 
-use crate::Cli;
+/// The maximum product of the sequence lengths for which `--validate-chain` computes an exact alignment.
+const MAX_CHAIN_VALIDATION_CELLS: usize = 1_000_000;
+
+/// The buffer pool of the template switch aligner, shared by all clones of a [`Cli`], such as the pairs of a batch.
+///
+/// The type of the pool depends on the strategies selected on the command line, hence it is stored type-erased,
+/// and replaced if an aligner with different strategies asks for it.
+#[derive(Clone, Default)]
+pub struct SharedBufferPool(Rc<RefCell<Option<Box<dyn Any>>>>);
+
+impl SharedBufferPool {
+    fn get<Pool: Default + 'static>(&self) -> Arc<Pool> {
+        let mut shared = self.0.borrow_mut();
+        if let Some(pool) = shared
+            .as_ref()
+            .and_then(|pool| pool.downcast_ref::<Arc<Pool>>())
+        {
+            return pool.clone();
+        }
+
+        let pool = Arc::new(Pool::default());
+        *shared = Some(Box::new(pool.clone()));
+        pool
+    }
+}
 
 #[derive(Clone, ValueEnum)]
 pub enum TemplateSwitchNodeOrdStrategySelector {
     CostOnly,
     AntiDiagonal,
+    /// Order nodes with equal cost by the weights of the model given with `--ts-node-ord-model`.
+    Learned,
+}
+
+/// A node ordering strategy that can be selected on the command line, together with its memory.
+pub trait CliNodeOrdStrategy: NodeOrdStrategy<AllowPrimaryMatchStrategy> + 'static {
+    fn memory(cli: &Cli) -> Self::Memory;
+}
+
+impl CliNodeOrdStrategy for CostOnlyNodeOrdStrategy {
+    fn memory(_cli: &Cli) -> Self::Memory {}
+}
+
+impl CliNodeOrdStrategy for AntiDiagonalNodeOrdStrategy {
+    fn memory(_cli: &Cli) -> Self::Memory {}
+}
+
+impl CliNodeOrdStrategy for LearnedNodeOrdStrategy {
+    fn memory(cli: &Cli) -> Self::Memory {
+        let path = cli.ts_node_ord_model.as_ref().unwrap_or_else(|| {
+            exit_with_error("The learned node ordering strategy requires --ts-node-ord-model")
+        });
+        let reader = open_input(path).unwrap_or_else(|error| {
+            exit_with_error(format!(
+                "Error opening node ordering model {path:?}: {error}"
+            ))
+        });
+        NodeOrdModel::read_toml(reader).unwrap_or_else(|error| {
+            exit_with_error(format!(
+                "Error reading node ordering model {path:?}: {error}"
+            ))
+        })
+    }
 }
 
 #[derive(Clone, ValueEnum)]
@@ -47,156 +128,617 @@ pub enum TemplateSwitchChainingStrategySelector {
     LowerBound,
 }
 
+impl From<RuntimeChainingSelection> for TemplateSwitchChainingStrategySelector {
+    fn from(value: RuntimeChainingSelection) -> Self {
+        match value {
+            RuntimeChainingSelection::None => Self::None,
+            RuntimeChainingSelection::PrecomputeOnly => Self::PrecomputeOnly,
+            RuntimeChainingSelection::LowerBound => Self::LowerBound,
+        }
+    }
+}
+
+impl From<TemplateSwitchChainingStrategySelector> for RuntimeChainingSelection {
+    fn from(value: TemplateSwitchChainingStrategySelector) -> Self {
+        match value {
+            TemplateSwitchChainingStrategySelector::None => Self::None,
+            TemplateSwitchChainingStrategySelector::PrecomputeOnly => Self::PrecomputeOnly,
+            TemplateSwitchChainingStrategySelector::LowerBound => Self::LowerBound,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum ChainingGraphFormatSelector {
+    Dot,
+    #[value(name = "graphml")]
+    GraphMl,
+}
+
+impl From<ChainingGraphFormatSelector> for ChainingGraphFormat {
+    fn from(value: ChainingGraphFormatSelector) -> Self {
+        match value {
+            ChainingGraphFormatSelector::Dot => Self::Dot,
+            ChainingGraphFormatSelector::GraphMl => Self::GraphMl,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum AnchorSchemeSelector {
+    Kmer,
+    HpcKmer,
+}
+
+impl From<AnchorSchemeSelector> for AnchorScheme {
+    fn from(value: AnchorSchemeSelector) -> Self {
+        match value {
+            AnchorSchemeSelector::Kmer => Self::Kmer,
+            AnchorSchemeSelector::HpcKmer => Self::HpcKmer,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum TemplateSwitchDirectionSelector {
+    All,
+    ReferencePrimary,
+    QueryPrimary,
+    SameSequence,
+    CrossSequence,
+}
+
+impl From<TemplateSwitchDirectionSelector> for TemplateSwitchDirections {
+    fn from(value: TemplateSwitchDirectionSelector) -> Self {
+        match value {
+            TemplateSwitchDirectionSelector::All => Self::ALL,
+            TemplateSwitchDirectionSelector::ReferencePrimary => Self::REFERENCE_PRIMARY,
+            TemplateSwitchDirectionSelector::QueryPrimary => Self::QUERY_PRIMARY,
+            TemplateSwitchDirectionSelector::SameSequence => Self::SAME_SEQUENCE,
+            TemplateSwitchDirectionSelector::CrossSequence => Self::CROSS_SEQUENCE,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum TemplateSwitchSearchAlgorithmSelector {
+    AStar,
+    Anytime,
+    IdaStar,
+}
+
+/// Select the strategies of the template switch aligner from the command line arguments.
+///
+/// Strategies that are consulted in the inner loop of the search are selected at compile time,
+/// by instantiating the aligner for each combination of them.
+/// Other strategies are selected at runtime, such that adding one of them does not multiply the number of instantiations.
+/// Currently, only the chaining strategy is selected at runtime.
 pub fn align_a_star_template_switch_distance<
-    AlphabetType: Alphabet + Debug + Clone + Eq,
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
     SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
 >(
     cli: Cli,
     reference: &SubsequenceType,
     query: &SubsequenceType,
 ) {
-    align_a_star_template_switch_distance_select_node_ord_strategy(cli, reference, query);
+    select_cli_strategies(
+        cli,
+        AlignTemplateSwitchDistance {
+            reference,
+            query,
+            phantom_data: PhantomData,
+        },
+    );
 }
 
-fn align_a_star_template_switch_distance_select_node_ord_strategy<
-    AlphabetType: Alphabet + Debug + Clone + Eq,
-    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
->(
+/// A function that is generic over the strategies that are selected at compile time by [`select_cli_strategies`].
+pub trait WithCliStrategies {
+    type Output;
+
+    fn call<
+        NodeOrd: CliNodeOrdStrategy,
+        TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
+    >(
+        self,
+        cli: Cli,
+    ) -> Self::Output;
+}
+
+/// Select the node ordering and template switch min length strategies from the command line arguments,
+/// and call `function` with them.
+pub fn select_cli_strategies<Function: WithCliStrategies>(
     cli: Cli,
-    reference: &SubsequenceType,
-    query: &SubsequenceType,
-) {
+    function: Function,
+) -> Function::Output {
     match cli.ts_node_ord_strategy {
         TemplateSwitchNodeOrdStrategySelector::CostOnly => {
-            align_a_star_template_switch_distance_select_template_switch_min_length_strategy::<
-                _,
-                _,
-                CostOnlyNodeOrdStrategy,
-            >(cli, reference, query)
+            select_template_switch_min_length_strategy::<_, CostOnlyNodeOrdStrategy>(cli, function)
         }
         TemplateSwitchNodeOrdStrategySelector::AntiDiagonal => {
-            align_a_star_template_switch_distance_select_template_switch_min_length_strategy::<
-                _,
-                _,
-                AntiDiagonalNodeOrdStrategy,
-            >(cli, reference, query)
+            select_template_switch_min_length_strategy::<_, AntiDiagonalNodeOrdStrategy>(
+                cli, function,
+            )
+        }
+        TemplateSwitchNodeOrdStrategySelector::Learned => {
+            select_template_switch_min_length_strategy::<_, LearnedNodeOrdStrategy>(cli, function)
         }
     }
 }
 
-fn align_a_star_template_switch_distance_select_template_switch_min_length_strategy<
-    AlphabetType: Alphabet + Debug + Clone + Eq,
-    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
-    NodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>,
+fn select_template_switch_min_length_strategy<
+    Function: WithCliStrategies,
+    NodeOrd: CliNodeOrdStrategy,
 >(
     cli: Cli,
-    reference: &SubsequenceType,
-    query: &SubsequenceType,
-) {
+    function: Function,
+) -> Function::Output {
     match cli.ts_min_length_strategy {
         TemplateSwitchMinLengthStrategySelector::None => {
-            align_a_star_template_switch_select_chaining_strategy::<
-                _,
-                _,
-                NodeOrd,
-                NoTemplateSwitchMinLengthStrategy,
-            >(cli, reference, query)
+            function.call::<NodeOrd, NoTemplateSwitchMinLengthStrategy>(cli)
         }
         TemplateSwitchMinLengthStrategySelector::Lookahead => {
-            align_a_star_template_switch_select_chaining_strategy::<
-                _,
-                _,
-                NodeOrd,
-                LookaheadTemplateSwitchMinLengthStrategy,
-            >(cli, reference, query)
+            function.call::<NodeOrd, LookaheadTemplateSwitchMinLengthStrategy>(cli)
         }
     }
 }
 
-fn align_a_star_template_switch_select_chaining_strategy<
-    AlphabetType: Alphabet + Debug + Clone + Eq,
+struct AlignTemplateSwitchDistance<'sequences, AlphabetType, SubsequenceType: ?Sized> {
+    reference: &'sequences SubsequenceType,
+    query: &'sequences SubsequenceType,
+    phantom_data: PhantomData<AlphabetType>,
+}
+
+impl<
+        AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
+        SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+    > WithCliStrategies for AlignTemplateSwitchDistance<'_, AlphabetType, SubsequenceType>
+{
+    type Output = ();
+
+    fn call<
+        NodeOrd: CliNodeOrdStrategy,
+        TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
+    >(
+        self,
+        cli: Cli,
+    ) {
+        align_a_star_template_switch_distance_call::<_, _, NodeOrd, TemplateSwitchMinLength>(
+            cli,
+            self.reference,
+            self.query,
+        )
+    }
+}
+
+fn align_a_star_template_switch_distance_call<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
     SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
-    NodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>,
-    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy,
+    NodeOrd: CliNodeOrdStrategy,
+    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
 >(
     cli: Cli,
     reference: &SubsequenceType,
     query: &SubsequenceType,
 ) {
-    match cli.ts_chaining_strategy {
-        TemplateSwitchChainingStrategySelector::None => {
-            align_a_star_template_switch_distance_call::<
-                _,
-                _,
-                NodeOrd,
-                TemplateSwitchMinLength,
-                NoChainingStrategy,
-            >(cli, reference, query)
-        }
-        TemplateSwitchChainingStrategySelector::PrecomputeOnly => {
-            align_a_star_template_switch_distance_call::<
-                _,
-                _,
-                NodeOrd,
-                TemplateSwitchMinLength,
-                PrecomputeOnlyChainingStrategy,
-            >(cli, reference, query)
-        }
-        TemplateSwitchChainingStrategySelector::LowerBound => {
-            align_a_star_template_switch_distance_call::<
-                _,
-                _,
-                NodeOrd,
-                TemplateSwitchMinLength,
-                LowerBoundChainingStrategy,
-            >(cli, reference, query)
+    let costs = load_template_switch_config(&cli);
+    let aligner = build_aligner::<_, NodeOrd, TemplateSwitchMinLength>(&cli, costs.clone());
+    let untrimmed_soft_masks = read_soft_masks(&cli);
+
+    let trimmed_ends = if cli.trim_identical_ends {
+        let margin = cli
+            .trim_margin
+            .or_else(|| TrimmedEnds::margin(&costs))
+            .unwrap_or_else(|| {
+                exit_with_error("Template switches are unbounded in the config, use --trim-margin to trim identical ends")
+            });
+        let trimmed_ends = TrimmedEnds::new(reference, query, margin);
+        info!(
+            "Trimming identical prefix of length {} and suffix of length {}, keeping a margin of {margin}",
+            trimmed_ends.prefix, trimmed_ends.suffix
+        );
+        trimmed_ends
+    } else {
+        TrimmedEnds::default()
+    };
+    let untrimmed_reference = reference;
+    let untrimmed_query = query;
+    let reference = trimmed_ends.trim(reference);
+    let query = trimmed_ends.trim(query);
+    let soft_masks = untrimmed_soft_masks
+        .as_ref()
+        .map(|soft_masks| trimmed_ends.trim_soft_masks(soft_masks));
+
+    let memory_estimate = aligner.memory_estimate(reference.len(), query.len());
+    if cli.dry_run {
+        println!("{memory_estimate}");
+        return;
+    }
+    info!(
+        estimated_peak_memory_bytes = memory_estimate.total_bytes();
+        "{memory_estimate}"
+    );
+    if let Some(memory_limit) = cli.memory_limit {
+        if memory_estimate.total_bytes() > memory_limit {
+            if cli.force {
+                warn!(
+                    "Estimated peak memory {} exceeds the memory limit {}, continuing anyways",
+                    format_bytes(memory_estimate.total_bytes()),
+                    format_bytes(memory_limit)
+                );
+            } else {
+                exit_with_error(format!(
+                    "Estimated peak memory {} exceeds the memory limit {}, use --force to align anyways",
+                    format_bytes(memory_estimate.total_bytes()),
+                    format_bytes(memory_limit)
+                ));
+            }
         }
     }
+
+    if let Some(chaining_graph) = &cli.chaining_graph {
+        info!("Writing chaining graph to {chaining_graph:?}");
+        let file = std::io::BufWriter::new(std::fs::File::create(chaining_graph).unwrap_or_else(
+            |error| {
+                exit_with_error(format!(
+                    "Error creating chaining graph file {chaining_graph:?}: {error}"
+                ))
+            },
+        ));
+        write_chaining_graph(
+            reference,
+            query,
+            &costs,
+            cli.chaining_graph_format.clone().into(),
+            file,
+        )
+        .unwrap_or_else(|error| exit_with_error(format!("Error writing chaining graph: {error}")));
+    }
+
+    if cli.cooptimal_limit.is_some()
+        && !matches!(
+            cli.ts_search_algorithm,
+            TemplateSwitchSearchAlgorithmSelector::AStar
+        )
+    {
+        warn!("Co-optimal alignments are only counted by A*, ignoring --cooptimal-limit");
+    }
+
+    info!("Calling aligner...");
+    let guide = read_guide(&cli, reference.len(), query.len());
+    let alignment = if cli.stream_partial_alignments {
+        aligner.align_guided_streaming(reference, query, soft_masks, guide, |segment| {
+            info!("Fixed alignment segment: {}", alignment_cigar(segment))
+        })
+    } else {
+        aligner.align_guided(reference, query, soft_masks, guide)
+    }
+    .unwrap_or_else(|error| {
+        let error = error.offset_alignment_ranges(trimmed_ends.prefix, trimmed_ends.prefix);
+        exit_with_error(format!("Error aligning: {error}"))
+    });
+    if cli.validate_chain {
+        validate_chain::<_, _, NodeOrd, TemplateSwitchMinLength>(
+            &cli,
+            costs.clone(),
+            reference,
+            query,
+            untrimmed_soft_masks
+                .as_ref()
+                .map(|soft_masks| trimmed_ends.trim_soft_masks(soft_masks)),
+            &alignment,
+        );
+    }
+    let mut alignment =
+        trimmed_ends.reattach(alignment, untrimmed_reference, untrimmed_query, &costs);
+    if cli.polish_template_switches {
+        alignment = polish_template_switches(
+            alignment,
+            untrimmed_reference,
+            untrimmed_query,
+            &costs,
+            untrimmed_soft_masks.as_ref(),
+            cli.polish_window,
+        );
+    }
+
+    if cli.deterministic {
+        alignment.statistics_mut().clear_timing();
+    }
+    if let Some(mut provenance) = cli.input_provenance.clone() {
+        provenance.config_sha256 = Some(costs.sha256());
+        alignment.statistics_mut().provenance = Some(provenance);
+    }
+
+    let output_start_time = Instant::now();
+    let statistics_format = cli.statistics_format();
+    if let Some(output) = cli.output {
+        info!(file:% = output.display(); "Outputting alignment statistics to {output:?}");
+        write_output(&output, toml::to_string(&alignment).unwrap().as_bytes())
+            .unwrap_or_else(|error| exit_with_error(format!("Error writing {output:?}: {error}")));
+    }
+    if let Some(maf) = &cli.maf {
+        info!(file:% = maf.display(); "Outputting alignment in MAF to {maf:?}");
+        let n_character_policy = cli
+            .maf_n_character
+            .map_or(NCharacterPolicy::Keep, NCharacterPolicy::Replace);
+        let character_mappings = [
+            cli.input_provenance
+                .as_ref()
+                .map(|provenance| &provenance.reference),
+            cli.input_provenance
+                .as_ref()
+                .map(|provenance| &provenance.query),
+        ]
+        .map(|provenance| {
+            let mapping = CharacterMapping::new(n_character_policy);
+            match provenance.and_then(|provenance| provenance.coordinate_map.clone()) {
+                Some(coordinate_map) if cli.maf_mark_skipped => {
+                    mapping.mark_skipped_characters(coordinate_map)
+                }
+                _ => mapping,
+            }
+        });
+        let blocks = match &alignment {
+            AlignmentResult::WithTarget { alignment, .. } => {
+                MafBlock::from_alignment_with_character_mappings(
+                    alignment,
+                    untrimmed_reference,
+                    untrimmed_query,
+                    &cli.input_record_ids[0],
+                    &cli.input_record_ids[1],
+                    character_mappings.each_ref(),
+                )
+            }
+            AlignmentResult::WithoutTarget { .. } => Vec::new(),
+        };
+        let mut output = Vec::new();
+        write_maf(&mut output, &blocks).unwrap();
+        write_output(maf, &output)
+            .unwrap_or_else(|error| exit_with_error(format!("Error writing {maf:?}: {error}")));
+    }
+
+    println!("{}", alignment.display(statistics_format));
+
+    // The template switches are not part of the machine-readable statistics.
+    if statistics_format.mode == StatisticsMode::HumanReadable {
+        print_template_switches(&alignment, untrimmed_reference, untrimmed_query);
+    }
+    log_output_phase(output_start_time);
 }
 
-fn align_a_star_template_switch_distance_call<
-    AlphabetType: Alphabet + Debug + Clone + Eq,
+/// Loads the template switch config from the preset or the configuration directory given in `cli`,
+/// and applies the ambiguity semantics and template switch directions given in `cli`.
+pub fn load_template_switch_config<AlphabetType: Alphabet>(
+    cli: &Cli,
+) -> TemplateSwitchConfig<AlphabetType> {
+    let costs = if let Some(preset) = cli.preset {
+        info!(preset:% = preset; "Using config preset {preset}");
+        TemplateSwitchConfig::from_preset(preset)
+    } else {
+        let mut config_path = cli.configuration_directory.clone();
+        info!("Loading alignment config directory {config_path:?}");
+
+        config_path.push("config.tsa");
+        let config_file =
+            std::io::BufReader::new(std::fs::File::open(&config_path).unwrap_or_else(|error| {
+                exit_with_error(format!(
+                    "Error opening config file {config_path:?}: {error}"
+                ))
+            }));
+        let mode = if cli.lenient_config {
+            ParseMode::Lenient
+        } else {
+            ParseMode::Strict
+        };
+        TemplateSwitchConfig::read_plain_with_mode(config_file, mode).unwrap_or_else(|error| {
+            exit_with_error(format!("Error parsing template switch config:\n{error}"))
+        })
+    };
+    let mut costs = if let Some(ambiguity_semantics) = cli.ambiguity_semantics {
+        info!("Using ambiguity semantics {ambiguity_semantics}");
+        costs.with_ambiguity_semantics(ambiguity_semantics)
+    } else {
+        costs
+    };
+    costs.directions = cli
+        .ts_directions
+        .iter()
+        .cloned()
+        .map(TemplateSwitchDirections::from)
+        .fold(
+            TemplateSwitchDirections::NONE,
+            TemplateSwitchDirections::union,
+        );
+    if !costs.directions.is_all() {
+        info!(
+            "Searching only template switches with primary and secondary {}",
+            costs.directions
+        );
+    }
+    costs.anchor_scheme = cli.anchor_scheme.clone().into();
+    if costs.anchor_scheme == AnchorScheme::HpcKmer {
+        info!("Seeding chaining anchors in homopolymer-compressed space");
+    }
+    costs.anchor_repeat_mask = cli.mask_anchor_repeats;
+    if let Some(threshold) = costs.anchor_repeat_mask {
+        info!("Masking chaining anchors of repetitive blocks with threshold {threshold}");
+    }
+
+    costs
+}
+
+/// Builds the template switch aligner with the chaining strategy, limits and other settings given in `cli`.
+pub fn build_aligner<
+    AlphabetType: Alphabet + 'static,
+    NodeOrd: CliNodeOrdStrategy,
+    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
+>(
+    cli: &Cli,
+    costs: TemplateSwitchConfig<AlphabetType>,
+) -> Aligner<
+    BuiltStrategies<
+        AlphabetType,
+        NodeOrd,
+        TemplateSwitchMinLength,
+        RuntimeChainingStrategy,
+        NoTemplateSwitchCountStrategy,
+        NoShortcutStrategy,
+    >,
+> {
+    AlignerBuilder::new(costs)
+        .node_ord::<NodeOrd>()
+        .node_ord_memory(NodeOrd::memory(cli))
+        .template_switch_min_length::<TemplateSwitchMinLength>()
+        .chaining::<RuntimeChainingStrategy>(cli.ts_chaining_strategy.clone().into())
+        .max_cost(cli.max_cost)
+        .tie_breaking(cli.tie_breaking.into())
+        .secondary_score_policy(cli.secondary_score_policy.into())
+        .self_alignment(cli.self_alignment)
+        .memory_limit(cli.memory_limit)
+        .time_limit(cli.timeout)
+        .cooptimal_limit(cli.cooptimal_limit)
+        .check_admissibility(cli.check_admissibility)
+        .deterministic(cli.deterministic)
+        .fast_path_max_edit_distance(cli.fast_path_max_edit_distance)
+        .random_restarts(cli.random_restarts.map(|count| RandomRestarts {
+            count,
+            seed: cli.random_restarts_seed,
+        }))
+        .search_algorithm(match cli.ts_search_algorithm {
+            TemplateSwitchSearchAlgorithmSelector::AStar => SearchAlgorithm::AStar,
+            TemplateSwitchSearchAlgorithmSelector::Anytime => SearchAlgorithm::Anytime {
+                initial_lower_bound_weight: cli.anytime_initial_weight,
+                time_limit: cli.anytime_time_limit,
+            },
+            TemplateSwitchSearchAlgorithmSelector::IdaStar => SearchAlgorithm::IterativeDeepening,
+        })
+        .build()
+        .unwrap_or_else(|error| exit_with_error(format!("Error building aligner: {error}")))
+        .with_buffer_pool(cli.buffer_pool.get())
+}
+
+/// Cross-checks the chain of the alignment against an exact alignment computed without chaining.
+///
+/// Exits with an error if the chain cost exceeds the cost of the exact alignment, since then the chain is not a lower bound.
+fn validate_chain<
+    AlphabetType: Alphabet + Debug + Clone + Eq + 'static,
     SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
-    NodeOrd: NodeOrdStrategy<AllowPrimaryMatchStrategy>,
-    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy,
-    Chaining: ChainingStrategy,
+    NodeOrd: CliNodeOrdStrategy,
+    TemplateSwitchMinLength: TemplateSwitchMinLengthStrategy + 'static,
 >(
-    cli: Cli,
+    cli: &Cli,
+    costs: TemplateSwitchConfig<AlphabetType>,
     reference: &SubsequenceType,
     query: &SubsequenceType,
+    soft_masks: Option<SoftMasks>,
+    alignment: &AlignmentResult<AlignmentType>,
 ) {
-    let mut config_path = cli.configuration_directory.clone();
-    info!("Loading alignment config directory {config_path:?}");
+    let Some(chain) = &alignment.statistics().chain else {
+        warn!("No chain was computed, use a chaining strategy to validate it");
+        return;
+    };
+    if reference.len().saturating_mul(query.len()) > MAX_CHAIN_VALIDATION_CELLS {
+        warn!(
+            "Not validating the chain, since the sequences are longer than {MAX_CHAIN_VALIDATION_CELLS} cells of the alignment matrix"
+        );
+        return;
+    }
+
+    info!("Validating the chain against an exact alignment without chaining...");
+    let mut exact_cli = cli.clone();
+    exact_cli.ts_chaining_strategy = TemplateSwitchChainingStrategySelector::None;
+    exact_cli.ts_search_algorithm = TemplateSwitchSearchAlgorithmSelector::AStar;
+    let exact = build_aligner::<_, NodeOrd, TemplateSwitchMinLength>(&exact_cli, costs)
+        .align(reference, query, soft_masks)
+        .unwrap_or_else(|error| {
+            exit_with_error(format!(
+                "Error aligning exactly to validate the chain: {error}"
+            ))
+        });
+    let AlignmentResult::WithTarget { .. } = exact else {
+        warn!("The exact alignment found no target, not validating the chain");
+        return;
+    };
 
-    config_path.push("config.tsa");
-    let config_file = std::io::BufReader::new(
-        std::fs::File::open(&config_path)
-            .unwrap_or_else(|error| panic!("Error opening config file {config_path:?}: {error}")),
+    let exact_cost = exact.statistics().cost;
+    info!(
+        "Chain transitions: {} direct, {} demanding non-matches, {} total",
+        chain.direct_transitions(),
+        chain.non_match_transitions(),
+        chain.transitions.len()
+    );
+    if !chain.is_consistent_with(exact_cost) {
+        exit_with_error(format!(
+            "The chain cost {} exceeds the exact alignment cost {exact_cost}, so the chain is not a lower bound",
+            chain.cost
+        ));
+    }
+    if alignment.statistics().cost > exact_cost {
+        warn!(
+            "The alignment cost {} exceeds the exact alignment cost {exact_cost}",
+            alignment.statistics().cost
+        );
+    }
+    info!(
+        "The chain cost {} is consistent with the exact alignment cost {exact_cost}",
+        chain.cost
     );
-    let costs = TemplateSwitchConfig::read_plain(config_file)
-        .unwrap_or_else(|error| panic!("Error parsing template switch config:\n{error}"));
+}
 
-    info!("Calling aligner...");
-    let alignment = template_switch_distance_a_star_align::<
-        AlignmentStrategySelection<
-            AlphabetType,
-            NodeOrd,
-            TemplateSwitchMinLength,
-            Chaining,
-            NoTemplateSwitchCountStrategy,
-            AllowSecondaryDeletionStrategy,
-            NoShortcutStrategy,
-            AllowPrimaryMatchStrategy,
-        >,
-        _,
-    >(reference, query, costs, cli.max_cost);
+/// Prints the canonical template switches of the alignment, marking those that are not part of all co-optimal alignments.
+pub fn print_template_switches<
+    AlphabetType: Alphabet,
+    SubsequenceType: GenomeSequence<AlphabetType, SubsequenceType> + ?Sized,
+>(
+    alignment: &AlignmentResult<AlignmentType>,
+    reference: &SubsequenceType,
+    query: &SubsequenceType,
+) {
+    let AlignmentResult::WithTarget {
+        alignment,
+        statistics,
+    } = alignment
+    else {
+        return;
+    };
 
-    if let Some(output) = cli.output {
-        info!("Outputting alignment statistics to {output:?}");
-        use std::io::Write;
-        let mut output = std::io::BufWriter::new(std::fs::File::create(output).unwrap());
-        write!(output, "{}", toml::to_string(&alignment).unwrap()).unwrap();
+    let template_switches =
+        TemplateSwitchAnnotation::canonical_from_alignment(alignment, reference, query);
+    // Report template switches in the coordinates of the sequences before preprocessing, if they were preprocessed.
+    let template_switches: Vec<_> = match statistics.provenance.as_ref().map(|provenance| {
+        [&provenance.reference, &provenance.query].map(|sequence| {
+            sequence
+                .coordinate_map
+                .clone()
+                .unwrap_or_else(|| CoordinateMap::identity(sequence.length))
+        })
+    }) {
+        Some([reference_map, query_map])
+            if !reference_map.is_identity() || !query_map.is_identity() =>
+        {
+            template_switches
+                .iter()
+                .map(|template_switch| {
+                    template_switch.to_original_coordinates(&reference_map, &query_map)
+                })
+                .collect()
+        }
+        _ => template_switches,
+    };
+    if !template_switches.is_empty() {
+        println!("Template switches:");
+        for (index, template_switch) in template_switches.iter().enumerate() {
+            if !statistics.ambiguous_template_switches.contains(&index) {
+                println!("{template_switch}");
+            } else if let Some(support) = statistics.template_switch_support.get(index) {
+                println!(
+                    "{template_switch} (ambiguous, in {support} of {} co-optimal alignments)",
+                    statistics.cooptimal_alignments
+                );
+            } else {
+                // Results written by older versions have no support.
+                println!("{template_switch} (ambiguous)");
+            }
+        }
     }
-
-    println!("{}", alignment);
 }
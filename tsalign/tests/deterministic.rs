@@ -0,0 +1,90 @@
+//! Tests of `tsalign --deterministic`, which must produce byte-identical output between runs.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The query contains the reverse complement of the middle of the reference.
+const REFERENCE: &str = "ACGTTGCAACTCAGGATCCGTAACGTTCAGGAACCATGCA";
+const QUERY: &str = "ACGTTGCAACCTGAACGTTACGGATCCTGAGAACCATGCA";
+
+/// Creates a directory that is unique to the test `name`, with the reference and the query as fasta files.
+fn write_sequences(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "tsalign-deterministic-{name}-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&directory).unwrap();
+    for (record, sequence) in [("reference", REFERENCE), ("query", QUERY)] {
+        fs::write(
+            directory.join(format!("{record}.fa")),
+            format!(">{record}\n{sequence}\n"),
+        )
+        .unwrap();
+    }
+    directory
+}
+
+/// Runs `tsalign` on the sequences in `directory` with the given extra arguments, writing its output to `output`.
+fn align(directory: &Path, output: &str, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tsalign"))
+        .args(["--log-level", "warn", "-c"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/../sample_tsa_config"))
+        .arg("-r")
+        .arg(directory.join("reference.fa"))
+        .arg("-q")
+        .arg(directory.join("query.fa"))
+        .arg("-o")
+        .arg(directory.join(output))
+        .args(args)
+        .output()
+        .unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn runs_are_byte_identical() {
+    let directory = write_sequences("identical");
+    let args = [
+        "--deterministic",
+        "--tie-breaking",
+        "secondary-score",
+        "--secondary-score-policy",
+        "minimise-template-switches",
+    ];
+    for output in ["first.toml", "second.toml"] {
+        let (success, stderr) = align(&directory, output, &args);
+        assert!(success, "{stderr}");
+    }
+
+    let first = fs::read(directory.join("first.toml")).unwrap();
+    let second = fs::read(directory.join("second.toml")).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn time_dependent_searches_are_rejected() {
+    let directory = write_sequences("rejected");
+    let results = [["--timeout", "10s"], ["--ts-search-algorithm", "anytime"]].map(|args| {
+        align(
+            &directory,
+            "output.toml",
+            &[&["--deterministic"], &args[..]].concat(),
+        )
+    });
+    fs::remove_dir_all(&directory).unwrap();
+
+    for (success, stderr) in results {
+        assert!(!success);
+        assert!(stderr.contains("--deterministic"), "{stderr}");
+        assert!(!stderr.contains("panicked"), "{stderr}");
+    }
+}
@@ -0,0 +1,118 @@
+//! Tests of `tsalign show`, mostly with `--cigar`, which runs on hand-written CIGARs that may not fit the given sequences.
+
+use std::{fs, path::PathBuf, process::Command};
+
+const REFERENCE: &str = "ACGTTGCAACTCAGGATCCG";
+const QUERY: &str = "ACGTTGCAACCTGAGGATCC";
+
+/// Writes the reference and the query into fasta files that are unique to the test `name`.
+fn write_sequences(name: &str) -> [PathBuf; 2] {
+    let directory =
+        std::env::temp_dir().join(format!("tsalign-show-{name}-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    [("reference", REFERENCE), ("query", QUERY)].map(|(record, sequence)| {
+        let path = directory.join(format!("{record}.fa"));
+        fs::write(&path, format!(">{record}\n{sequence}\n")).unwrap();
+        path
+    })
+}
+
+/// Runs `tsalign show --cigar` and returns its exit status, stdout and stderr.
+fn show_cigar(name: &str, cigar: &str) -> (bool, String, String) {
+    let [reference, query] = write_sequences(name);
+    let output = Command::new(env!("CARGO_BIN_EXE_tsalign"))
+        .args(["--log-level", "warn", "show", "--cigar", cigar, "-r"])
+        .arg(&reference)
+        .arg("-q")
+        .arg(&query)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(reference.parent().unwrap()).unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn valid_cigar() {
+    let (success, stdout, stderr) = show_cigar("valid", "10M[TSQR-5:5M:0]5M");
+    assert!(success, "{stderr}");
+    assert!(stdout.contains("CIGAR: 10M[TSQR-5:5M:0]5M"), "{stdout}");
+    assert!(stdout.contains("Template switches:"), "{stdout}");
+}
+
+#[test]
+fn malformed_cigar() {
+    for (name, cigar) in [("unterminated", "5M[TSQR-5:5M"), ("unknown", "5M3Q")] {
+        let (success, _, stderr) = show_cigar(name, cigar);
+        assert!(!success, "{cigar}");
+        assert!(stderr.contains("Error parsing CIGAR"), "{cigar}: {stderr}");
+        assert!(!stderr.contains("panicked"), "{cigar}: {stderr}");
+    }
+}
+
+#[test]
+fn out_of_range_cigar() {
+    for (name, cigar) in [
+        ("before-start", "5M[TSQR-5:5M:0]10M"),
+        ("empty", "10M[TSQR0:0M:0]10M"),
+        ("too-short", "19M"),
+        ("too-long", "15M[TSQR0:5M:3]"),
+    ] {
+        let (success, _, stderr) = show_cigar(name, cigar);
+        assert!(!success, "{cigar}");
+        assert!(
+            stderr.contains("The CIGAR does not fit the sequences"),
+            "{cigar}: {stderr}"
+        );
+        assert!(!stderr.contains("panicked"), "{cigar}: {stderr}");
+    }
+}
+
+#[test]
+fn mismatching_sequence() {
+    let [reference, query] = write_sequences("mismatching");
+    let directory = reference.parent().unwrap().to_path_buf();
+    let result = directory.join("result.toml");
+    let output = Command::new(env!("CARGO_BIN_EXE_tsalign"))
+        .args(["--log-level", "warn", "-c"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/../sample_tsa_config"))
+        .args(["--provenance", "hashes", "-r"])
+        .arg(&reference)
+        .arg("-q")
+        .arg(&query)
+        .arg("-o")
+        .arg(&result)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // A record with the same id, but a different sequence.
+    let mismatching_reference = directory.join("mismatching_reference.fa");
+    fs::write(&mismatching_reference, format!(">reference\n{QUERY}\n")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tsalign"))
+        .args(["--log-level", "warn", "show"])
+        .arg(&result)
+        .arg("-r")
+        .arg(&mismatching_reference)
+        .arg("-q")
+        .arg(&query)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("The reference sequence differs from the aligned record"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}